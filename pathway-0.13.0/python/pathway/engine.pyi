@@ -52,12 +52,53 @@ class ConnectorMode(Enum):
 
 class ReadMethod(Enum):
     BY_LINE: ReadMethod
+    BY_LINE_WITHOUT_TERMINATOR: ReadMethod
     FULL: ReadMethod
+    JSON_OBJECTS: ReadMethod
+    LENGTH_PREFIXED: ReadMethod
 
 class DebeziumDBType(Enum):
     POSTGRES: DebeziumDBType
     MONGO_DB: DebeziumDBType
 
+class CompressionFormat(Enum):
+    NONE: CompressionFormat
+    GZIP: CompressionFormat
+    ZSTD: CompressionFormat
+
+class KafkaCompression(Enum):
+    NONE: KafkaCompression
+    GZIP: KafkaCompression
+    SNAPPY: KafkaCompression
+    LZ4: KafkaCompression
+    ZSTD: KafkaCompression
+
+class FileStartPosition(Enum):
+    BEGINNING: FileStartPosition
+    END: FileStartPosition
+
+class ElasticSearchBulkAction(Enum):
+    INDEX: ElasticSearchBulkAction
+    CREATE: ElasticSearchBulkAction
+
+class ObjectOrdering(Enum):
+    MODIFIED_TIME_THEN_KEY: ObjectOrdering
+    KEY_ONLY: ObjectOrdering
+    KEY_DESCENDING: ObjectOrdering
+
+class InvalidBytesPolicy(Enum):
+    REPLACE: InvalidBytesPolicy
+    ERROR: InvalidBytesPolicy
+
+class CacheHashFunction(Enum):
+    XXH3: CacheHashFunction
+    SHA256: CacheHashFunction
+    BLAKE3: CacheHashFunction
+
+class MultiWriterErrorPolicy(Enum):
+    FAIL_FAST: MultiWriterErrorPolicy
+    COLLECT_ALL: MultiWriterErrorPolicy
+
 class Universe:
     pass
 
@@ -731,6 +772,59 @@ class DataStorage:
     mock_events: dict[tuple[str, int], list[SnapshotEvent]] | None
     table_name: str | None
     column_names: list[str] | None
+    refresh_interval: int | None
+    compression: CompressionFormat | None
+    max_consecutive_errors: int
+    http_reader_settings: HttpReaderSettings | None
+    consumer_name: str | None
+    kafka_compression: KafkaCompression | None
+    kafka_batch_num_messages: int | None
+    kafka_linger_ms: int | None
+    primary_key: list[str] | None
+    routing_key: str | None
+    subscription_message: str | None
+    ping_interval_ms: int | None
+    start_position: FileStartPosition | None
+    tombstone_marker: str | None
+    gcs_settings: GcsSettings | None
+    sort_key_field_index: int | None
+    consumer_group: str | None
+    iceberg_catalog_settings: IcebergCatalogSettings | None
+    partition_offsets: list[tuple[int, int]] | None
+    mqtt_qos: int
+    emit_metadata_record: bool
+    timescale_time_column: str | None
+    idle_timeout_ms: int | None
+    timestamp_field_index: int | None
+    azure_blob_settings: AzureBlobSettings | None
+    sqlite_projection: list[str] | None
+    sqlite_filter: str | None
+    sqlite_tracking_column: str | None
+    object_ordering: ObjectOrdering | None
+    skip_empty_files: bool | None
+    json_pointer: str | None
+    fsync_on_flush: bool
+    write_deletion_flag: bool
+    encoding: str | None
+    invalid_bytes_policy: InvalidBytesPolicy | None
+    max_in_flight_objects: int | None
+    max_record_bytes: int | None
+    kafka_transactional_id: str | None
+    extra_paths: list[str] | None
+    kafka_seek_validation_timeout_ms: int | None
+    max_get_attempts: int | None
+    kafka_poll_timeout_ms: int | None
+    key_field_indices: list[int] | None
+    kafka_commit_timeout_ms: int | None
+    cache_hash_function: CacheHashFunction | None
+    cache_hash_contents: bool
+    dead_letter: tuple[DataStorage, DataFormat] | None
+    max_records_per_second: float | None
+    max_bytes_per_second: float | None
+    max_write_attempts: int | None
+    write_retry_backoff_ms: int | None
+    extra_outputs: list[DataStorage] | None
+    output_error_policy: MultiWriterErrorPolicy | None
     def __init__(self, *args, **kwargs): ...
 
 class CsvParserSettings:
@@ -739,6 +833,34 @@ class CsvParserSettings:
 class AwsS3Settings:
     def __init__(self, *args, **kwargs): ...
 
+class HttpReaderSettings:
+    def __init__(
+        self,
+        items_path: str,
+        headers: dict[str, str] = {},
+        auth_token: str | None = None,
+        cursor_path: str | None = None,
+    ): ...
+
+class GcsSettings:
+    def __init__(
+        self, access_key: str, secret_access_key: str, bucket_name: str | None = None
+    ): ...
+
+class IcebergCatalogSettings:
+    def __init__(
+        self, catalog_type: str, uri: str | None = None, warehouse: str | None = None
+    ): ...
+
+class AzureBlobSettings:
+    def __init__(
+        self,
+        account: str,
+        container: str,
+        account_key: str | None = None,
+        sas_token: str | None = None,
+    ): ...
+
 class ValueField:
     name: str
     def __init__(self, name: str, type_: PathwayType, *, is_optional: bool = False): ...
@@ -22,6 +22,11 @@ def run(
 ) -> None:
     """Runs the computation graph.
 
+    When called from the main thread, it replaces the SIGTERM handler for the time of
+    the computation. The first SIGTERM makes the input connectors finish reading, so
+    that the computation ends with the data read so far, and the second one stops it
+    right away. The previous handler is restored once the computation ends.
+
     Args:
         debug: enable output out of table.debug() operators
         monitoring_level: the verbosity of stats monitoring mechanism. One of
@@ -66,6 +71,11 @@ def run_all(
 ) -> None:
     """Runs the computation graph with disabled tree-shaking optimization.
 
+    When called from the main thread, it replaces the SIGTERM handler for the time of
+    the computation. The first SIGTERM makes the input connectors finish reading, so
+    that the computation ends with the data read so far, and the second one stops it
+    right away. The previous handler is restored once the computation ends.
+
     Args:
         debug: enable output out of table.debug() operators
         monitoring_level: the verbosity of stats monitoring mechanism. One of
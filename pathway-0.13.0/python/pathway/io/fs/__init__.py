@@ -66,7 +66,8 @@ as recursively read the contents of all matching folders.
 "plaintext" and "plaintext_by_file" is how the input is tokenized: if the "plaintext" \
 option is chosen, it's split by the newlines. Otherwise, the files are split in full \
 and one row will correspond to one file. In case the "binary" format is specified, \
-the data is read as raw bytes without UTF-8 parsing.
+the data is read as raw bytes without UTF-8 parsing. With the "plaintext_by_file" \
+and "binary" formats, the empty files are skipped, so they don't produce any rows.
         schema: Schema of the resulting table.
         mode: Denotes how the engine polls the new data from the source. Currently \
 "streaming" and "static" are supported. If set to "streaming" the engine will wait for \
@@ -521,7 +521,7 @@ def write(
     *,
     format: str = "json",
     delimiter: str = ",",
-    key: ColumnReference | None = None,
+    key: ColumnReference | Iterable[ColumnReference] | None = None,
     value: ColumnReference | None = None,
     headers: Iterable[ColumnReference] | None = None,
 ) -> None:
@@ -560,7 +560,8 @@ def write(
             format.
         key: reference to the column that should be used as a key in the
             produced message in 'plaintext' or 'raw' format. If left empty, an internal primary key will
-            be used.
+            be used. A list of references makes a composite key, in which the value of each
+            column is prefixed with its length as a big-endian 32-bit integer.
         value: reference to the column that should be used as a value in
             the produced message in 'plaintext' or 'raw' format. It can be deduced automatically if the
             table has exactly one column. Otherwise it must be specified directly. It also has to be
@@ -669,7 +670,7 @@ def write(
     ... )
     """
 
-    key_field_index = None
+    key_field_indices: list[int] = []
     header_fields: dict[str, int] = {}
     if format == "json":
         data_format = api.DataFormat(
@@ -690,12 +691,17 @@ def write(
         columns_to_extract: list[ColumnReference] = []
         allowed_column_types = (dt.BYTES if format == "raw" else dt.STR, dt.ANY)
 
+        key_columns: list[ColumnReference] = []
         if key is not None:
             if value is None:
                 raise ValueError("'value' must be specified if 'key' is not None")
-            key_field_index = _add_column_reference_to_extract(
-                key, columns_to_extract, extracted_field_indices
-            )
+            key_columns = [key] if isinstance(key, ColumnReference) else list(key)
+            for key_column in key_columns:
+                key_field_indices.append(
+                    _add_column_reference_to_extract(
+                        key_column, columns_to_extract, extracted_field_indices
+                    )
+                )
         if value is not None:
             value_field_index = _add_column_reference_to_extract(
                 value, columns_to_extract, extracted_field_indices
@@ -720,13 +726,11 @@ def write(
 
         table = table.select(*columns_to_extract)
 
-        if (
-            key is not None
-            and table[key._name]._column.dtype not in allowed_column_types
-        ):
-            raise ValueError(
-                f"The key column should be of the type '{allowed_column_types[0]}'"
-            )
+        for key_column in key_columns:
+            if table[key_column._name]._column.dtype not in allowed_column_types:
+                raise ValueError(
+                    f"The key column should be of the type '{allowed_column_types[0]}'"
+                )
         if table[value._name]._column.dtype not in allowed_column_types:
             raise ValueError(
                 f"The value column should be of the type '{allowed_column_types[0]}'"
@@ -745,7 +749,7 @@ def write(
         storage_type="kafka",
         rdkafka_settings=rdkafka_settings,
         topic=topic_name,
-        key_field_index=key_field_index,
+        key_field_indices=key_field_indices,
         header_fields=[item for item in header_fields.items()],
     )
 
@@ -265,12 +265,16 @@ class ConnectorSubject(ABC):
             )
         self._buffer.put((DataEventType.DELETE, key, values))
 
-    def _read(self) -> Any:
-        """Allows to retrieve data from a buffer.
+    def _read(self, timeout: float | None = None) -> Any:
+        """Allows to retrieve data from a buffer. Returns None if nothing arrives
+        within ``timeout`` seconds.
 
         Should not be called directly.
         """
-        return self._buffer.get()
+        try:
+            return self._buffer.get(timeout=timeout)
+        except queue.Empty:
+            return None
 
     def _is_internal(self) -> bool:
         """
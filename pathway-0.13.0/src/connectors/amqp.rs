@@ -0,0 +1,328 @@
+// Copyright © 2024 Pathway
+
+use std::time::{Duration, Instant};
+
+use futures::executor::block_on;
+use futures::StreamExt;
+use lapin::options::{
+    BasicAckOptions, BasicConsumeOptions, BasicPublishOptions, ConfirmSelectOptions,
+};
+use lapin::publisher_confirm::PublisherConfirm as AmqpPublisherConfirm;
+use lapin::types::{AMQPValue, FieldTable as AmqpFieldTable};
+use lapin::{
+    BasicProperties as AmqpProperties, Channel as AmqpChannel, Connection as AmqpConnection,
+    ConnectionProperties as AmqpConnectionProperties, Consumer as AmqpConsumer, Error as AmqpError,
+};
+use log::info;
+use tokio::runtime::Runtime as TokioRuntime;
+
+use crate::connectors::data_format::FormatterContext;
+use crate::connectors::data_storage::{
+    message_headers, ReadError, ReadResult, Reader, ReaderContext, ShutdownSignal, StorageType,
+    WriteError, Writer,
+};
+use crate::connectors::{Offset, OffsetKey, OffsetValue};
+use crate::engine::Value;
+use crate::persistence::frontier::OffsetAntichain;
+use crate::persistence::PersistentId;
+
+fn connect_to_amqp(uri: &str) -> Result<(AmqpConnection, AmqpChannel), AmqpError> {
+    block_on(async {
+        let connection = AmqpConnection::connect(uri, AmqpConnectionProperties::default()).await?;
+        let channel = connection.create_channel().await?;
+        Ok((connection, channel))
+    })
+}
+
+/// Consumes the deliveries from an AMQP queue with manual acknowledgements.
+///
+/// A delivery is acknowledged only after the engine commits the data it belongs
+/// to. Delivery tags are only meaningful within the channel they were received in,
+/// so the offsets also carry a random id of the reading session, and the ones from
+/// a previous run are ignored. The unacknowledged deliveries are redelivered by the
+/// broker when the connection is lost, which includes the data that was committed
+/// but not yet acknowledged.
+///
+/// With the persistence, a commit is only passed to the reader once it's finalized,
+/// that is, once the outputs have processed the data and the snapshot is saved, so
+/// the delivery is at-least-once. Without it, the deliveries are acknowledged as
+/// soon as the input time they belong to is committed, so the ones that hadn't
+/// reached the outputs before a crash are lost.
+///
+/// A read waits for a delivery at most `POLL_TIMEOUT`. If none arrives, the read
+/// ends with [`ReadResult::FinishedSource`], so that the commits are passed to the
+/// reader and acknowledged even if the queue stays empty.
+pub struct AmqpReader {
+    runtime: TokioRuntime,
+    connection: AmqpConnection,
+    channel: AmqpChannel,
+    consumer: AmqpConsumer,
+    session: u64,
+    last_acked_tag: u64,
+    persistent_id: Option<PersistentId>,
+    last_activity: Option<Instant>,
+    shutdown_signal: ShutdownSignal,
+}
+
+impl AmqpReader {
+    const POLL_TIMEOUT: Duration = Duration::from_secs(1);
+
+    pub fn new(
+        uri: &str,
+        queue: &str,
+        consumer_tag: &str,
+        persistent_id: Option<PersistentId>,
+    ) -> Result<AmqpReader, ReadError> {
+        let (connection, channel) = connect_to_amqp(uri)?;
+        let consumer = block_on(channel.basic_consume(
+            queue,
+            consumer_tag,
+            BasicConsumeOptions::default(),
+            AmqpFieldTable::default(),
+        ))?;
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_time()
+            .build()?;
+
+        Ok(AmqpReader {
+            runtime,
+            connection,
+            channel,
+            consumer,
+            session: rand::random(),
+            last_acked_tag: 0,
+            persistent_id,
+            last_activity: None,
+            shutdown_signal: ShutdownSignal::default(),
+        })
+    }
+
+    /// Converts the delivery with `delivery_tag` received in the reading `session`
+    /// into the entry and the offset pointing at this delivery. The routing key of
+    /// the delivery becomes the key of the entry.
+    fn message_entry(
+        session: u64,
+        delivery_tag: u64,
+        routing_key: &str,
+        data: Vec<u8>,
+    ) -> (ReaderContext, Offset) {
+        let offset = (
+            OffsetKey::Empty,
+            OffsetValue::AmqpDeliveryTag {
+                session,
+                delivery_tag,
+            },
+        );
+        let key = routing_key.as_bytes().to_vec();
+        (ReaderContext::from_key_value(Some(key), Some(data)), offset)
+    }
+
+    /// Returns the tag of the last delivery in `frontier` if it was received in the
+    /// reading `session`, since the tags of the other sessions can't be acknowledged.
+    fn committed_delivery_tag(session: u64, frontier: &OffsetAntichain) -> Option<u64> {
+        match frontier.get_offset(&OffsetKey::Empty) {
+            Some(OffsetValue::AmqpDeliveryTag {
+                session: offset_session,
+                delivery_tag,
+            }) if *offset_session == session => Some(*delivery_tag),
+            _ => None,
+        }
+    }
+}
+
+impl Reader for AmqpReader {
+    fn seek(&mut self, frontier: &OffsetAntichain) -> Result<(), ReadError> {
+        // The position can't be set in an AMQP queue: the messages that weren't
+        // acknowledged before the restart are delivered again instead
+        if let Some(offset_value) = frontier.get_offset(&OffsetKey::Empty) {
+            info!("AMQP reader doesn't seek, the unacknowledged messages will be redelivered. Last offset: {offset_value:?}");
+        }
+        Ok(())
+    }
+
+    fn read(&mut self) -> Result<ReadResult, ReadError> {
+        let next_delivery = self.runtime.block_on(tokio::time::timeout(
+            Self::POLL_TIMEOUT,
+            self.consumer.next(),
+        ));
+        let Ok(next_delivery) = next_delivery else {
+            // Nothing has arrived within the timeout, so the control is returned to
+            // the caller, which can pass the commits to acknowledge
+            if self.shutdown_signal.is_requested() {
+                return Ok(ReadResult::Finished);
+            }
+            return Ok(ReadResult::FinishedSource {
+                commit_allowed: true,
+            });
+        };
+        let Some(delivery) = next_delivery else {
+            return Ok(ReadResult::Finished);
+        };
+        let delivery = delivery?;
+
+        self.last_activity = Some(Instant::now());
+        let (message, offset) = Self::message_entry(
+            self.session,
+            delivery.delivery_tag,
+            delivery.routing_key.as_str(),
+            delivery.data,
+        );
+        Ok(ReadResult::Data(message, offset))
+    }
+
+    fn on_commit(&mut self, frontier: &OffsetAntichain) -> Result<(), ReadError> {
+        let Some(delivery_tag) = Self::committed_delivery_tag(self.session, frontier) else {
+            return Ok(());
+        };
+        if delivery_tag <= self.last_acked_tag {
+            return Ok(());
+        }
+
+        block_on(
+            self.channel
+                .basic_ack(delivery_tag, BasicAckOptions { multiple: true }),
+        )?;
+        self.last_acked_tag = delivery_tag;
+        Ok(())
+    }
+
+    fn set_shutdown_signal(&mut self, signal: ShutdownSignal) {
+        self.shutdown_signal = signal;
+    }
+
+    fn persistent_id(&self) -> Option<PersistentId> {
+        self.persistent_id
+    }
+
+    fn update_persistent_id(&mut self, persistent_id: Option<PersistentId>) {
+        self.persistent_id = persistent_id;
+    }
+
+    fn storage_type(&self) -> StorageType {
+        StorageType::Amqp
+    }
+
+    fn last_activity(&self) -> Option<Instant> {
+        self.last_activity
+    }
+
+    fn is_healthy(&self) -> bool {
+        self.connection.status().connected()
+    }
+}
+
+pub struct AmqpWriter {
+    // The connection is closed when dropped, so it's kept alongside the channel
+    _connection: AmqpConnection,
+    channel: AmqpChannel,
+    exchange: String,
+    routing_key: String,
+    routing_key_field_index: Option<usize>,
+    header_fields: Vec<(String, usize)>,
+    pending_confirms: Vec<AmqpPublisherConfirm>,
+}
+
+impl AmqpWriter {
+    /// Creates a writer publishing to `exchange`. The routing key is taken from the
+    /// field with `routing_key_field_index` if it's set, otherwise `routing_key`
+    /// is used for all messages.
+    pub fn new(
+        uri: &str,
+        exchange: String,
+        routing_key: String,
+        routing_key_field_index: Option<usize>,
+        header_fields: Vec<(String, usize)>,
+    ) -> Result<AmqpWriter, WriteError> {
+        let (connection, channel) = connect_to_amqp(uri)?;
+        block_on(channel.confirm_select(ConfirmSelectOptions::default()))?;
+        Ok(AmqpWriter {
+            _connection: connection,
+            channel,
+            exchange,
+            routing_key,
+            routing_key_field_index,
+            header_fields,
+            pending_confirms: Vec::new(),
+        })
+    }
+
+    /// Returns the headers of the messages produced for `data`, with the values that
+    /// aren't valid UTF-8 converted lossily, since they are sent as long strings.
+    fn message_headers(
+        header_fields: &[(String, usize)],
+        data: &FormatterContext,
+    ) -> AmqpFieldTable {
+        let mut headers = AmqpFieldTable::default();
+        for (name, value) in message_headers(header_fields, data) {
+            headers.insert(
+                name.into(),
+                AMQPValue::LongString(String::from_utf8_lossy(&value).to_string().into()),
+            );
+        }
+        headers
+    }
+}
+
+impl Writer for AmqpWriter {
+    fn write(&mut self, data: FormatterContext) -> Result<(), WriteError> {
+        let headers = Self::message_headers(&self.header_fields, &data);
+
+        let routing_key = match self.routing_key_field_index {
+            Some(index) => match &data.values[index] {
+                Value::String(key) => key.to_string(),
+                other => other.to_string(),
+            },
+            None => self.routing_key.clone(),
+        };
+        for payload in &data.payloads {
+            let confirm = block_on(self.channel.basic_publish(
+                &self.exchange,
+                &routing_key,
+                BasicPublishOptions::default(),
+                payload,
+                AmqpProperties::default().with_headers(headers.clone()),
+            ))?;
+            self.pending_confirms.push(confirm);
+        }
+        Ok(())
+    }
+
+    fn flush(&mut self, _forced: bool) -> Result<(), WriteError> {
+        // The channel is in the confirm mode, so the broker acknowledges each of
+        // the published messages. They're awaited once per batch
+        for confirm in self.pending_confirms.drain(..) {
+            if block_on(confirm)?.is_nack() {
+                return Err(WriteError::AmqpMessageRejected);
+            }
+        }
+        Ok(())
+    }
+
+    fn retriable(&self) -> bool {
+        true
+    }
+
+    fn single_threaded(&self) -> bool {
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::persistence::frontier::OffsetAntichain;
+
+    use super::AmqpReader;
+
+    #[test]
+    fn test_committed_delivery_tag() {
+        let mut frontier = OffsetAntichain::new();
+        assert_eq!(AmqpReader::committed_delivery_tag(1, &frontier), None);
+
+        let (_, (offset_key, offset_value)) = AmqpReader::message_entry(1, 7, "orders", Vec::new());
+        frontier.advance_offset(offset_key, offset_value);
+        assert_eq!(AmqpReader::committed_delivery_tag(1, &frontier), Some(7));
+
+        // The delivery tags of another session can't be acknowledged
+        assert_eq!(AmqpReader::committed_delivery_tag(2, &frontier), None);
+    }
+}
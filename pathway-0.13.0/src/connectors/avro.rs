@@ -0,0 +1,392 @@
+// Copyright © 2024 Pathway
+
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufReader, BufWriter};
+use std::mem::take;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use apache_avro::schema::Schema as AvroSchema;
+use apache_avro::types::Value as AvroValue;
+use apache_avro::{Reader as AvroReader, Writer as AvroWriter};
+use log::warn;
+use rand::Rng;
+
+use crate::connectors::data_format::FormatterContext;
+use crate::connectors::data_storage::{
+    DataEventType, ReadError, ReadResult, Reader, ReaderContext, StorageType, ValuesMap,
+    WriteError, Writer, SPECIAL_OUTPUT_FIELDS,
+};
+use crate::connectors::metadata::SourceMetadata;
+use crate::connectors::{OffsetKey, OffsetValue};
+use crate::engine::{
+    DateTimeNaive as EngineDateTimeNaive, DateTimeUtc as EngineDateTimeUtc, Type, Value,
+};
+use crate::persistence::frontier::OffsetAntichain;
+use crate::persistence::PersistentId;
+use crate::python_api::ValueField;
+use crate::timestamp::current_unix_timestamp_secs;
+
+/// Converts a decimal stored as a big-endian two's complement integer into a float.
+fn avro_decimal_to_float(bytes: &[u8], scale: usize) -> Option<f64> {
+    if bytes.len() > 16 {
+        return None;
+    }
+    let mut unscaled: i128 = if bytes.first().is_some_and(|byte| byte & 0x80 != 0) {
+        -1
+    } else {
+        0
+    };
+    for byte in bytes {
+        unscaled = (unscaled << 8) | i128::from(*byte);
+    }
+    #[allow(clippy::cast_precision_loss)]
+    let unscaled = unscaled as f64;
+    Some(unscaled / 10_f64.powi(i32::try_from(scale).ok()?))
+}
+
+/// Returns the decimal scale for every field of the record schema that has the
+/// decimal logical type, possibly wrapped into a union with null.
+fn avro_decimal_scales(schema: &AvroSchema) -> HashMap<String, usize> {
+    let mut scales = HashMap::new();
+    let AvroSchema::Record(record_schema) = schema else {
+        return scales;
+    };
+    for field in &record_schema.fields {
+        let decimal_schema = match &field.schema {
+            AvroSchema::Decimal(decimal_schema) => Some(decimal_schema),
+            AvroSchema::Union(union_schema) => {
+                union_schema
+                    .variants()
+                    .iter()
+                    .find_map(|variant| match variant {
+                        AvroSchema::Decimal(decimal_schema) => Some(decimal_schema),
+                        _ => None,
+                    })
+            }
+            _ => None,
+        };
+        if let Some(decimal_schema) = decimal_schema {
+            scales.insert(field.name.clone(), decimal_schema.scale);
+        }
+    }
+    scales
+}
+
+fn avro_value_to_engine(
+    value: AvroValue,
+    decimal_scale: Option<usize>,
+) -> Result<Value, ReadError> {
+    let value = match value {
+        AvroValue::Null => Value::None,
+        AvroValue::Boolean(b) => Value::Bool(b),
+        AvroValue::Int(i) => Value::Int(i.into()),
+        AvroValue::Long(i) => Value::Int(i),
+        AvroValue::Float(f) => Value::Float(f64::from(f).into()),
+        AvroValue::Double(f) => Value::Float(f.into()),
+        AvroValue::Bytes(b) | AvroValue::Fixed(_, b) => Value::Bytes(b.into()),
+        AvroValue::String(s) | AvroValue::Enum(_, s) => Value::String(s.into()),
+        AvroValue::Uuid(uuid) => Value::String(uuid.to_string().into()),
+        AvroValue::Union(_, inner) => return avro_value_to_engine(*inner, decimal_scale),
+        AvroValue::Date(days) => Value::DateTimeNaive(EngineDateTimeNaive::new(
+            i64::from(days) * 86_400_000_000_000,
+        )),
+        AvroValue::TimestampMillis(ms) => {
+            Value::DateTimeUtc(EngineDateTimeUtc::new(ms * 1_000_000))
+        }
+        AvroValue::TimestampMicros(us) => Value::DateTimeUtc(EngineDateTimeUtc::new(us * 1_000)),
+        AvroValue::LocalTimestampMillis(ms) => {
+            Value::DateTimeNaive(EngineDateTimeNaive::new(ms * 1_000_000))
+        }
+        AvroValue::LocalTimestampMicros(us) => {
+            Value::DateTimeNaive(EngineDateTimeNaive::new(us * 1_000))
+        }
+        AvroValue::Decimal(decimal) => {
+            let float = decimal_scale.and_then(|scale| {
+                let bytes = Vec::<u8>::try_from(&decimal).ok()?;
+                avro_decimal_to_float(&bytes, scale)
+            });
+            let Some(float) = float else {
+                let decimal = AvroValue::Decimal(decimal);
+                return Err(ReadError::UnsupportedAvroValue(decimal));
+            };
+            Value::Float(float.into())
+        }
+        other => return Err(ReadError::UnsupportedAvroValue(other)),
+    };
+    Ok(value)
+}
+
+pub struct AvroFileReader {
+    path: Arc<PathBuf>,
+    persistent_id: Option<PersistentId>,
+
+    reader: Option<AvroReader<'static, BufReader<File>>>,
+    decimal_scales: HashMap<String, usize>,
+    total_entries_read: u64,
+    entries_to_skip: u64,
+    is_finished: bool,
+}
+
+impl AvroFileReader {
+    pub fn new(path: impl Into<PathBuf>, persistent_id: Option<PersistentId>) -> Self {
+        Self {
+            path: Arc::new(path.into()),
+            persistent_id,
+
+            reader: None,
+            decimal_scales: HashMap::new(),
+            total_entries_read: 0,
+            entries_to_skip: 0,
+            is_finished: false,
+        }
+    }
+
+    fn open_file(&mut self) -> Result<ReadResult, ReadError> {
+        let file = File::open(self.path.as_path())?;
+        let metadata = SourceMetadata::from_fs_meta(
+            self.path.as_path(),
+            &file.metadata()?,
+            current_unix_timestamp_secs(),
+        );
+        let reader = AvroReader::new(BufReader::new(file))?;
+        self.decimal_scales = avro_decimal_scales(reader.writer_schema());
+        self.reader = Some(reader);
+        Ok(ReadResult::NewSource(Some(metadata)))
+    }
+
+    fn next_row(&mut self) -> Result<Option<ValuesMap>, ReadError> {
+        let reader = self
+            .reader
+            .as_mut()
+            .expect("the file must be opened before reading rows");
+        let Some(record) = reader.next() else {
+            return Ok(None);
+        };
+
+        let fields = match record? {
+            AvroValue::Record(fields) => fields,
+            other => return Err(ReadError::MalformedData(format!("{other:?}").into_bytes())),
+        };
+        let mut values = HashMap::with_capacity(fields.len());
+        for (name, value) in fields {
+            let value = avro_value_to_engine(value, self.decimal_scales.get(&name).copied())?;
+            values.insert(name, value);
+        }
+        Ok(Some(values.into()))
+    }
+}
+
+impl Reader for AvroFileReader {
+    fn seek(&mut self, frontier: &OffsetAntichain) -> Result<(), ReadError> {
+        let offset_value = frontier.get_offset(&OffsetKey::Empty);
+        let Some(OffsetValue::FilePosition {
+            total_entries_read,
+            path,
+            ..
+        }) = offset_value
+        else {
+            if offset_value.is_some() {
+                warn!(
+                    "Incorrect type of offset value in AvroFilesystem frontier: {offset_value:?}"
+                );
+            }
+            return Ok(());
+        };
+
+        if *path != self.path {
+            warn!(
+                "Persisted offset refers to {path:?}, while the reader is configured for {:?}. Reading from the beginning.",
+                self.path
+            );
+            return Ok(());
+        }
+
+        // Avro container files are split into compressed blocks, so the records
+        // that were already processed are skipped when the file is opened.
+        self.entries_to_skip = *total_entries_read;
+        Ok(())
+    }
+
+    fn read(&mut self) -> Result<ReadResult, ReadError> {
+        if self.is_finished {
+            return Ok(ReadResult::Finished);
+        }
+        if self.reader.is_none() {
+            return self.open_file();
+        }
+
+        while let Some(values) = self.next_row()? {
+            self.total_entries_read += 1;
+            if self.total_entries_read <= self.entries_to_skip {
+                continue;
+            }
+
+            let offset = (
+                OffsetKey::Empty,
+                OffsetValue::FilePosition {
+                    total_entries_read: self.total_entries_read,
+                    path: self.path.clone(),
+                    bytes_offset: 0,
+                },
+            );
+            return Ok(ReadResult::Data(
+                ReaderContext::from_diff(DataEventType::Insert, None, values),
+                offset,
+            ));
+        }
+
+        self.is_finished = true;
+        Ok(ReadResult::FinishedSource {
+            commit_allowed: true,
+        })
+    }
+
+    fn persistent_id(&self) -> Option<PersistentId> {
+        self.persistent_id
+    }
+
+    fn update_persistent_id(&mut self, persistent_id: Option<PersistentId>) {
+        self.persistent_id = persistent_id;
+    }
+
+    fn storage_type(&self) -> StorageType {
+        StorageType::AvroFilesystem
+    }
+}
+
+pub struct AvroFileWriter {
+    writer: BufWriter<File>,
+    schema: AvroSchema,
+    value_fields: Vec<ValueField>,
+    sync_marker: [u8; 16],
+    header_written: bool,
+    buffered_records: Vec<AvroValue>,
+}
+
+impl AvroFileWriter {
+    pub fn new(
+        writer: BufWriter<File>,
+        value_fields: &[ValueField],
+    ) -> Result<AvroFileWriter, WriteError> {
+        Ok(AvroFileWriter {
+            writer,
+            schema: Self::construct_schema(value_fields)?,
+            value_fields: value_fields.to_vec(),
+            sync_marker: rand::thread_rng().gen(),
+            header_written: false,
+            buffered_records: Vec::new(),
+        })
+    }
+
+    fn avro_type(type_: Type) -> Result<serde_json::Value, WriteError> {
+        Ok(match type_ {
+            Type::Bool => serde_json::json!("boolean"),
+            Type::Int | Type::Duration => serde_json::json!("long"),
+            Type::Float => serde_json::json!("double"),
+            Type::Pointer | Type::String | Type::Json => serde_json::json!("string"),
+            Type::Bytes => serde_json::json!("bytes"),
+            Type::DateTimeNaive => {
+                serde_json::json!({"type": "long", "logicalType": "local-timestamp-micros"})
+            }
+            Type::DateTimeUtc => {
+                serde_json::json!({"type": "long", "logicalType": "timestamp-micros"})
+            }
+            Type::Any | Type::Array | Type::Tuple | Type::PyObjectWrapper => {
+                return Err(WriteError::UnsupportedType(type_))
+            }
+        })
+    }
+
+    pub fn construct_schema(value_fields: &[ValueField]) -> Result<AvroSchema, WriteError> {
+        let mut schema_fields = Vec::new();
+        for field in value_fields {
+            let mut avro_type = Self::avro_type(field.type_)?;
+            if field.is_optional {
+                avro_type = serde_json::json!(["null", avro_type]);
+            }
+            schema_fields.push(serde_json::json!({"name": field.name, "type": avro_type}));
+        }
+        for (field, type_) in SPECIAL_OUTPUT_FIELDS {
+            schema_fields.push(serde_json::json!({"name": field, "type": Self::avro_type(type_)?}));
+        }
+        let schema = serde_json::json!({
+            "type": "record",
+            "name": "pathway_row",
+            "fields": schema_fields,
+        });
+        Ok(AvroSchema::parse(&schema)?)
+    }
+
+    fn avro_value(value: &Value, field: &ValueField) -> Result<AvroValue, WriteError> {
+        let avro_value = match (value, field.type_) {
+            (Value::None, _) if field.is_optional => {
+                return Ok(AvroValue::Union(0, Box::new(AvroValue::Null)))
+            }
+            (Value::Bool(b), Type::Bool) => AvroValue::Boolean(*b),
+            (Value::Int(i), Type::Int) => AvroValue::Long(*i),
+            (Value::Duration(d), Type::Duration) => AvroValue::Long(d.microseconds()),
+            (Value::Float(f), Type::Float) => AvroValue::Double((*f).into()),
+            (Value::String(s), Type::String) => AvroValue::String(s.to_string()),
+            (Value::Pointer(p), Type::Pointer) => AvroValue::String(p.to_string()),
+            (Value::Json(j), Type::Json) => AvroValue::String(j.to_string()),
+            (Value::Bytes(b), Type::Bytes) => AvroValue::Bytes(b.to_vec()),
+            (Value::DateTimeNaive(dt), Type::DateTimeNaive) => {
+                AvroValue::LocalTimestampMicros(dt.timestamp_microseconds())
+            }
+            (Value::DateTimeUtc(dt), Type::DateTimeUtc) => {
+                AvroValue::TimestampMicros(dt.timestamp_microseconds())
+            }
+            _ => return Err(WriteError::AvroTypeMismatch(value.clone(), field.type_)),
+        };
+        if field.is_optional {
+            Ok(AvroValue::Union(1, Box::new(avro_value)))
+        } else {
+            Ok(avro_value)
+        }
+    }
+}
+
+impl Writer for AvroFileWriter {
+    fn write(&mut self, data: FormatterContext) -> Result<(), WriteError> {
+        let mut record = Vec::with_capacity(self.value_fields.len() + SPECIAL_OUTPUT_FIELDS.len());
+        for (value, field) in data.values.iter().zip(&self.value_fields) {
+            record.push((field.name.clone(), Self::avro_value(value, field)?));
+        }
+        record.push((
+            "time".to_string(),
+            AvroValue::Long(data.time.0.try_into().unwrap()),
+        ));
+        record.push((
+            "diff".to_string(),
+            AvroValue::Long(data.diff.try_into().unwrap()),
+        ));
+        self.buffered_records.push(AvroValue::Record(record));
+        Ok(())
+    }
+
+    fn flush(&mut self, _forced: bool) -> Result<(), WriteError> {
+        if self.buffered_records.is_empty() {
+            return Ok(());
+        }
+
+        // The container header is written only once, later blocks are appended
+        // with the same sync marker so that the file stays readable as a whole.
+        let mut writer = if self.header_written {
+            AvroWriter::append_to(&self.schema, &mut self.writer, self.sync_marker)
+        } else {
+            AvroWriter::builder()
+                .schema(&self.schema)
+                .writer(&mut self.writer)
+                .marker(self.sync_marker)
+                .build()
+        };
+        writer.extend(take(&mut self.buffered_records))?;
+        writer.into_inner()?;
+        self.header_written = true;
+
+        self.writer.flush()?;
+        Ok(())
+    }
+}
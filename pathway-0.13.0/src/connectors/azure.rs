@@ -0,0 +1,686 @@
+// Copyright © 2024 Pathway
+
+use std::collections::HashMap;
+use std::io;
+use std::io::{BufReader, Read, Write};
+use std::mem::take;
+use std::time::{Duration, Instant};
+
+use azure_core::error::{Error as AzureError, ErrorKind as AzureErrorKind};
+use azure_storage::StorageCredentials;
+use azure_storage_blobs::prelude::{
+    BlobClient, ClientBuilder as AzureClientBuilder, ContainerClient,
+};
+use bytes::Bytes;
+use futures::StreamExt;
+use log::{error, warn};
+use pipe::PipeReader;
+use tokio::runtime::Runtime as TokioRuntime;
+
+use crate::connectors::data_format::{FormatterContext, InnerSchemaField};
+use crate::connectors::data_storage::{
+    extract_seek_position, is_idle_timeout_reached, CompressionFormat, DataEventType, ReadError,
+    ReadMethod, ReadResult, Reader, ReaderContext, SeekOffsetKind, SeekPosition, ShutdownSignal,
+    StorageType, WriteError, Writer, WrittenRowsCounter,
+};
+use crate::connectors::s3::{
+    is_retriable_s3_status_code, CsvObjectReader, ObjectOrdering, ObjectScanner, ObjectStore,
+    S3CsvReader, S3PollingInterval,
+};
+use crate::connectors::{Offset, OffsetKey, OffsetValue};
+use crate::deepcopy::DeepCopy;
+use crate::persistence::frontier::OffsetAntichain;
+use crate::persistence::PersistentId;
+
+/// The credentials the requests to Azure Blob Storage are authorized with.
+#[derive(Clone, Debug)]
+pub enum AzureBlobCredentials {
+    AccountKey(String),
+    SasToken(String),
+}
+
+/// Creates the client of the container `container` of the storage account `account`.
+pub fn azure_container_client(
+    account: &str,
+    container: &str,
+    credentials: &AzureBlobCredentials,
+) -> Result<ContainerClient, AzureError> {
+    let storage_credentials = match credentials {
+        AzureBlobCredentials::AccountKey(key) => {
+            StorageCredentials::access_key(account.to_string(), key.clone())
+        }
+        AzureBlobCredentials::SasToken(token) => StorageCredentials::sas_token(token)?,
+    };
+    Ok(
+        AzureClientBuilder::new(account.to_string(), storage_credentials)
+            .container_client(container),
+    )
+}
+
+fn create_azure_runtime() -> io::Result<TokioRuntime> {
+    tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+}
+
+pub(crate) fn is_retriable_azure_error(error: &AzureError) -> bool {
+    match error.kind() {
+        AzureErrorKind::HttpResponse { status, .. } => {
+            is_retriable_s3_status_code(u16::from(*status))
+        }
+        AzureErrorKind::Io => true,
+        _ => false,
+    }
+}
+
+/// An Azure Blob Storage container as an `ObjectStore`. Only the blobs under
+/// `blobs_prefix` are listed.
+///
+/// The service can't continue a listing after a given name, so each listing goes
+/// over all of the blobs under the prefix, one page at a time.
+#[derive(Clone)]
+pub struct AzureBlobScanner {
+    container_client: ContainerClient,
+    blobs_prefix: String,
+}
+
+impl AzureBlobScanner {
+    pub fn new(container_client: ContainerClient, blobs_prefix: impl Into<String>) -> Self {
+        Self {
+            container_client,
+            blobs_prefix: blobs_prefix.into(),
+        }
+    }
+
+    async fn get_blob_to_writer(
+        blob_client: &BlobClient,
+        writer: &mut (dyn Write + Send),
+    ) -> Result<(), ReadError> {
+        let mut chunks = blob_client.get().into_stream();
+        while let Some(chunk) = chunks.next().await {
+            let data = chunk?.data.collect().await?;
+            writer.write_all(&data)?;
+        }
+        Ok(())
+    }
+}
+
+impl DeepCopy for AzureBlobScanner {
+    fn deep_copy(&self) -> Self {
+        self.clone()
+    }
+}
+
+impl ObjectStore for AzureBlobScanner {
+    const LISTS_AFTER_KEY: bool = false;
+    const OBJECT_KIND: &'static str = "Azure blob";
+
+    fn list_objects(
+        &self,
+        start_after: Option<&str>,
+        on_object: &mut dyn FnMut(&str, i64),
+    ) -> Result<Option<String>, ReadError> {
+        let runtime = create_azure_runtime()?;
+        let mut last_listed_name = None;
+        let mut pages = self
+            .container_client
+            .list_blobs()
+            .prefix(self.blobs_prefix.clone())
+            .into_stream();
+        runtime.block_on(async {
+            while let Some(page) = pages.next().await {
+                for blob in page?.blobs.blobs() {
+                    if start_after.is_some_and(|start_after| blob.name.as_str() <= start_after) {
+                        continue;
+                    }
+                    let Ok(last_modified) =
+                        i64::try_from(blob.properties.last_modified.unix_timestamp_nanos())
+                    else {
+                        continue;
+                    };
+                    on_object(&blob.name, last_modified);
+                    last_listed_name = Some(blob.name.clone());
+                }
+            }
+            Ok::<(), AzureError>(())
+        })?;
+        Ok(last_listed_name)
+    }
+
+    fn get_object_to_writer(
+        &self,
+        key: &str,
+        writer: &mut (dyn Write + Send),
+    ) -> Result<(), ReadError> {
+        let runtime = create_azure_runtime()?;
+        let blob_client = self.container_client.blob_client(key);
+        runtime.block_on(Self::get_blob_to_writer(&blob_client, writer))
+    }
+
+    fn is_retriable_error(error: &ReadError) -> bool {
+        matches!(error, ReadError::AzureBlob(e) if is_retriable_azure_error(e))
+    }
+}
+
+pub struct AzureBlobGenericReader {
+    scanner: ObjectScanner<AzureBlobScanner>,
+    poll_new_blobs: bool,
+    poll_interval: S3PollingInterval,
+    read_method: ReadMethod,
+    compression: Option<CompressionFormat>,
+    max_record_bytes: Option<usize>,
+
+    reader: Option<BufReader<Box<dyn Read + Send>>>,
+    persistent_id: Option<PersistentId>,
+    total_entries_read: u64,
+    current_bytes_read: u64,
+    deferred_read_result: Option<ReadResult>,
+    max_consecutive_errors: usize,
+    last_activity: Option<Instant>,
+    shutdown_signal: ShutdownSignal,
+    idle_timeout: Option<Duration>,
+}
+
+impl AzureBlobGenericReader {
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        container_client: ContainerClient,
+        blobs_prefix: impl Into<String>,
+        blob_pattern: &str,
+        poll_new_blobs: bool,
+        persistent_id: Option<PersistentId>,
+        read_method: ReadMethod,
+        compression: Option<CompressionFormat>,
+        poll_interval: Option<Duration>,
+        max_consecutive_errors: usize,
+        max_record_bytes: Option<usize>,
+    ) -> Result<AzureBlobGenericReader, ReadError> {
+        Ok(AzureBlobGenericReader {
+            scanner: ObjectScanner::new(
+                AzureBlobScanner::new(container_client, blobs_prefix),
+                blob_pattern,
+                ObjectOrdering::default(),
+                AzureBlobScanner::DEFAULT_MAX_GET_ATTEMPTS,
+                None,
+            )?,
+            poll_new_blobs,
+            poll_interval: S3PollingInterval::new(poll_interval),
+            read_method,
+            compression,
+            max_record_bytes,
+
+            reader: None,
+            persistent_id,
+            total_entries_read: 0,
+            current_bytes_read: 0,
+            deferred_read_result: None,
+            max_consecutive_errors,
+            last_activity: None,
+            shutdown_signal: ShutdownSignal::default(),
+            idle_timeout: None,
+        })
+    }
+
+    fn stream_next_blob(&mut self) -> Result<bool, ReadError> {
+        if let Some(pipe_reader) = self.scanner.stream_next_object()? {
+            let path = self.scanner.require_current_object_path()?;
+            self.current_bytes_read = 0;
+            self.reader = Some(self.decoded_reader(pipe_reader, path.as_str())?);
+            self.poll_interval.reset();
+            Ok(true)
+        } else {
+            Ok(false)
+        }
+    }
+
+    fn decoded_reader(
+        &self,
+        pipe_reader: PipeReader,
+        path: &str,
+    ) -> Result<BufReader<Box<dyn Read + Send>>, ReadError> {
+        let decoder = CompressionFormat::resolve(self.compression, path).decoder(pipe_reader)?;
+        Ok(BufReader::new(decoder))
+    }
+}
+
+impl Reader for AzureBlobGenericReader {
+    fn seek(&mut self, frontier: &OffsetAntichain) -> Result<(), ReadError> {
+        let Some(SeekPosition {
+            total_entries_read,
+            path: path_arc,
+            bytes_offset,
+            last_modified,
+            ..
+        }) = extract_seek_position(frontier, SeekOffsetKind::AzureBlobPosition)
+        else {
+            return Ok(());
+        };
+
+        let path = path_arc.to_string_lossy().into_owned();
+
+        let Some(last_modified) = self.scanner.seek_to_object(&path, last_modified)? else {
+            warn!("Azure blob {path} is no longer present, proceeding to the next blob");
+            self.total_entries_read = total_entries_read;
+            return Ok(());
+        };
+        let pipe_reader = self
+            .scanner
+            .stream_object_from_path(&path, Some(last_modified));
+
+        // The offset is counted in decompressed bytes, so the records read before
+        // are replayed from the start of the blob
+        let mut reader = self.decoded_reader(pipe_reader, &path)?;
+        let mut bytes_read = 0;
+        while bytes_read < bytes_offset {
+            let mut current_line = Vec::new();
+            let len = self.read_method.read_next_bytes(
+                &mut reader,
+                &mut current_line,
+                self.max_record_bytes,
+            )?;
+            if len == 0 {
+                break;
+            }
+            bytes_read += len as u64;
+        }
+        if bytes_read != bytes_offset {
+            error!("Azure blob {path} has changed since it was read: the records read before took {bytes_offset} bytes, but now they take {bytes_read}");
+        }
+
+        self.total_entries_read = total_entries_read;
+        self.current_bytes_read = bytes_read;
+        self.reader = if self.read_method == ReadMethod::Full {
+            None
+        } else {
+            Some(reader)
+        };
+
+        Ok(())
+    }
+
+    fn read(&mut self) -> Result<ReadResult, ReadError> {
+        if let Some(deferred_read_result) = self.deferred_read_result.take() {
+            return Ok(deferred_read_result);
+        }
+
+        let read_started_at = Instant::now();
+        loop {
+            if let Some(reader) = &mut self.reader {
+                let mut line = Vec::new();
+                let len =
+                    self.read_method
+                        .read_next_bytes(reader, &mut line, self.max_record_bytes)?;
+                if len > 0 || self.read_method == ReadMethod::Full {
+                    self.total_entries_read += 1;
+                    self.current_bytes_read += len as u64;
+
+                    let offset = (
+                        OffsetKey::Empty,
+                        OffsetValue::AzureBlobPosition {
+                            total_entries_read: self.total_entries_read,
+                            path: self.scanner.require_current_object_path()?,
+                            last_modified: self.scanner.require_current_object_last_modified()?,
+                            bytes_offset: self.current_bytes_read,
+                        },
+                    );
+
+                    if self.read_method == ReadMethod::Full {
+                        self.deferred_read_result = Some(ReadResult::FinishedSource {
+                            commit_allowed: true,
+                        });
+                        self.reader = None;
+                    }
+
+                    self.last_activity = Some(Instant::now());
+                    return Ok(ReadResult::Data(
+                        ReaderContext::from_raw_bytes(DataEventType::Insert, line),
+                        offset,
+                    ));
+                }
+            }
+            if self.stream_next_blob()? {
+                return Ok(ReadResult::NewSource(None));
+            }
+
+            if self.poll_new_blobs && !self.shutdown_signal.is_requested() {
+                if is_idle_timeout_reached(self.idle_timeout, read_started_at) {
+                    return Ok(ReadResult::FinishedSource {
+                        commit_allowed: true,
+                    });
+                }
+                self.poll_interval.sleep(&self.shutdown_signal);
+            } else {
+                return Ok(ReadResult::Finished);
+            }
+        }
+    }
+
+    fn storage_type(&self) -> StorageType {
+        StorageType::AzureBlobLines
+    }
+
+    fn max_allowed_consecutive_errors(&self) -> usize {
+        self.max_consecutive_errors
+    }
+
+    fn last_activity(&self) -> Option<Instant> {
+        self.last_activity
+    }
+
+    fn persistent_id(&self) -> Option<PersistentId> {
+        self.persistent_id
+    }
+
+    fn update_persistent_id(&mut self, persistent_id: Option<PersistentId>) {
+        self.persistent_id = persistent_id;
+    }
+
+    fn set_shutdown_signal(&mut self, signal: ShutdownSignal) {
+        self.shutdown_signal = signal;
+    }
+
+    fn set_idle_timeout(&mut self, timeout: Option<Duration>) {
+        self.idle_timeout = timeout;
+    }
+}
+
+pub struct AzureBlobCsvReader {
+    scanner: ObjectScanner<AzureBlobScanner>,
+    poll_new_blobs: bool,
+    poll_interval: S3PollingInterval,
+
+    parser_builder: csv::ReaderBuilder,
+    blob_reader: Option<CsvObjectReader<PipeReader>>,
+
+    persistent_id: Option<PersistentId>,
+    deferred_read_result: Option<ReadResult>,
+    total_entries_read: u64,
+    max_consecutive_errors: usize,
+    last_activity: Option<Instant>,
+    shutdown_signal: ShutdownSignal,
+    idle_timeout: Option<Duration>,
+    schema: Option<HashMap<String, InnerSchemaField>>,
+}
+
+impl AzureBlobCsvReader {
+    /// Creates a reader of the CSV blobs under `blobs_prefix`. The header of each
+    /// blob and the schema are handled as by [`S3CsvReader::new`].
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        container_client: ContainerClient,
+        blobs_prefix: impl Into<String>,
+        blob_pattern: &str,
+        parser_builder: csv::ReaderBuilder,
+        poll_new_blobs: bool,
+        persistent_id: Option<PersistentId>,
+        poll_interval: Option<Duration>,
+        max_consecutive_errors: usize,
+        schema: Option<HashMap<String, InnerSchemaField>>,
+    ) -> Result<AzureBlobCsvReader, ReadError> {
+        Ok(AzureBlobCsvReader {
+            scanner: ObjectScanner::new(
+                AzureBlobScanner::new(container_client, blobs_prefix),
+                blob_pattern,
+                ObjectOrdering::default(),
+                AzureBlobScanner::DEFAULT_MAX_GET_ATTEMPTS,
+                None,
+            )?,
+            poll_new_blobs,
+            poll_interval: S3PollingInterval::new(poll_interval),
+
+            parser_builder,
+            blob_reader: None,
+
+            persistent_id,
+            deferred_read_result: None,
+            total_entries_read: 0,
+            max_consecutive_errors,
+            last_activity: None,
+            shutdown_signal: ShutdownSignal::default(),
+            idle_timeout: None,
+            schema,
+        })
+    }
+
+    fn stream_next_blob(&mut self) -> Result<bool, ReadError> {
+        if let Some(pipe_reader) = self.scanner.stream_next_object()? {
+            let blob_reader = CsvObjectReader::new(&self.parser_builder, pipe_reader)?;
+            let offset = (
+                OffsetKey::Empty,
+                OffsetValue::AzureBlobPosition {
+                    total_entries_read: self.total_entries_read,
+                    path: self.scanner.require_current_object_path()?,
+                    last_modified: self.scanner.require_current_object_last_modified()?,
+                    bytes_offset: blob_reader.position(),
+                },
+            );
+            self.defer_header(&blob_reader, offset);
+            self.blob_reader = Some(blob_reader);
+            self.poll_interval.reset();
+            Ok(true)
+        } else {
+            Ok(false)
+        }
+    }
+
+    fn defer_header(&mut self, blob_reader: &CsvObjectReader<PipeReader>, offset: Offset) {
+        self.deferred_read_result = blob_reader.header().map(|header| {
+            ReadResult::Data(
+                ReaderContext::from_tokenized_entries(DataEventType::Insert, header.to_vec()),
+                offset,
+            )
+        });
+    }
+
+    fn validate_tokens(&self, tokens: &[String]) -> Result<(), ReadError> {
+        let Some(schema) = &self.schema else {
+            return Ok(());
+        };
+        let Some(header) = self.blob_reader.as_ref().and_then(CsvObjectReader::header) else {
+            return Ok(());
+        };
+        match S3CsvReader::check_tokens(schema, header, tokens) {
+            Ok(()) => Ok(()),
+            Err(error) => Err(ReadError::SchemaNotSatisfied {
+                path: self.scanner.require_current_object_path()?.to_string(),
+                entry: self.total_entries_read,
+                raw_entry: serde_json::to_vec(tokens)?,
+                error,
+            }),
+        }
+    }
+}
+
+impl Reader for AzureBlobCsvReader {
+    fn seek(&mut self, frontier: &OffsetAntichain) -> Result<(), ReadError> {
+        let Some(SeekPosition {
+            offset_value,
+            total_entries_read,
+            path: path_arc,
+            bytes_offset,
+            last_modified,
+        }) = extract_seek_position(frontier, SeekOffsetKind::AzureBlobPosition)
+        else {
+            return Ok(());
+        };
+
+        let path = path_arc.to_string_lossy().into_owned();
+
+        let Some(last_modified) = self.scanner.seek_to_object(&path, last_modified)? else {
+            warn!("Azure blob {path} is no longer present, proceeding to the next blob");
+            self.total_entries_read = total_entries_read;
+            return Ok(());
+        };
+        let pipe_reader = self
+            .scanner
+            .stream_object_from_path(&path, Some(last_modified));
+        let mut blob_reader = CsvObjectReader::new(&self.parser_builder, pipe_reader)?;
+        if blob_reader.header().is_none() {
+            error!("Empty Azure blob, nothing to rewind");
+            return Ok(());
+        }
+        self.defer_header(&blob_reader, (OffsetKey::Empty, offset_value.clone()));
+
+        let current_offset = blob_reader.skip_to(bytes_offset)?;
+        if current_offset != bytes_offset {
+            error!("Inconsistent bytes position in rewinded CSV blob: expected {current_offset}, got {bytes_offset}");
+        }
+
+        self.total_entries_read = total_entries_read;
+        self.blob_reader = Some(blob_reader);
+
+        Ok(())
+    }
+
+    fn read(&mut self) -> Result<ReadResult, ReadError> {
+        if let Some(deferred_read_result) = self.deferred_read_result.take() {
+            return Ok(deferred_read_result);
+        }
+
+        let read_started_at = Instant::now();
+        loop {
+            if let Some(blob_reader) = &mut self.blob_reader {
+                if let Some(tokens) = blob_reader.next_record()? {
+                    self.total_entries_read += 1;
+
+                    let offset = (
+                        OffsetKey::Empty,
+                        OffsetValue::AzureBlobPosition {
+                            total_entries_read: self.total_entries_read,
+                            path: self.scanner.require_current_object_path()?,
+                            last_modified: self.scanner.require_current_object_last_modified()?,
+                            bytes_offset: blob_reader.position(),
+                        },
+                    );
+
+                    self.last_activity = Some(Instant::now());
+                    self.validate_tokens(&tokens)?;
+                    return Ok(ReadResult::Data(
+                        ReaderContext::from_tokenized_entries(DataEventType::Insert, tokens),
+                        offset,
+                    ));
+                }
+            }
+            if self.stream_next_blob()? {
+                return Ok(ReadResult::NewSource(None));
+            }
+
+            if self.poll_new_blobs && !self.shutdown_signal.is_requested() {
+                if is_idle_timeout_reached(self.idle_timeout, read_started_at) {
+                    return Ok(ReadResult::FinishedSource {
+                        commit_allowed: true,
+                    });
+                }
+                self.poll_interval.sleep(&self.shutdown_signal);
+            } else {
+                return Ok(ReadResult::Finished);
+            }
+        }
+    }
+
+    fn storage_type(&self) -> StorageType {
+        StorageType::AzureBlobCsv
+    }
+
+    fn max_allowed_consecutive_errors(&self) -> usize {
+        self.max_consecutive_errors
+    }
+
+    fn last_activity(&self) -> Option<Instant> {
+        self.last_activity
+    }
+
+    fn persistent_id(&self) -> Option<PersistentId> {
+        self.persistent_id
+    }
+
+    fn update_persistent_id(&mut self, persistent_id: Option<PersistentId>) {
+        self.persistent_id = persistent_id;
+    }
+
+    fn set_shutdown_signal(&mut self, signal: ShutdownSignal) {
+        self.shutdown_signal = signal;
+    }
+
+    fn set_idle_timeout(&mut self, timeout: Option<Duration>) {
+        self.idle_timeout = timeout;
+    }
+}
+
+/// Writes the payloads, each followed by a newline, to an append blob, which is
+/// created anew when the writer starts. The payloads are buffered and appended on
+/// flush, split into the blocks of at most `MAX_BLOCK_SIZE` bytes.
+pub struct AzureBlobWriter {
+    blob_client: BlobClient,
+    runtime: TokioRuntime,
+    buffer: Vec<u8>,
+    buffered_rows: usize,
+    written_rows: WrittenRowsCounter,
+}
+
+impl AzureBlobWriter {
+    // The limit of the service for a single appended block
+    pub const MAX_BLOCK_SIZE: usize = 4 * 1024 * 1024;
+
+    pub fn new(container_client: &ContainerClient, blob_name: &str) -> Result<Self, WriteError> {
+        let blob_client = container_client.blob_client(blob_name);
+        let runtime = create_azure_runtime()?;
+        runtime.block_on(async { blob_client.put_append_blob().await })?;
+        Ok(Self {
+            blob_client,
+            runtime,
+            buffer: Vec::new(),
+            buffered_rows: 0,
+            written_rows: WrittenRowsCounter::default(),
+        })
+    }
+
+    /// Returns the length of the first block to append from `buffer`. The block
+    /// ends at a row boundary, unless a single row doesn't fit into a block.
+    pub fn block_len(buffer: &[u8]) -> usize {
+        if buffer.len() <= Self::MAX_BLOCK_SIZE {
+            return buffer.len();
+        }
+        buffer[..Self::MAX_BLOCK_SIZE]
+            .iter()
+            .rposition(|byte| *byte == b'\n')
+            .map_or(Self::MAX_BLOCK_SIZE, |position| position + 1)
+    }
+}
+
+impl Writer for AzureBlobWriter {
+    fn write(&mut self, data: FormatterContext) -> Result<(), WriteError> {
+        for payload in data.payloads {
+            self.buffer.extend_from_slice(&payload);
+            self.buffer.push(b'\n');
+            self.buffered_rows += 1;
+        }
+        Ok(())
+    }
+
+    fn flush(&mut self, _forced: bool) -> Result<(), WriteError> {
+        // The appended blocks are dropped from the buffer even if a later one fails,
+        // so that they aren't appended twice when the flush is repeated
+        let mut appended = 0;
+        while appended < self.buffer.len() {
+            let block_len = Self::block_len(&self.buffer[appended..]);
+            let block = Bytes::copy_from_slice(&self.buffer[appended..appended + block_len]);
+            let result = self
+                .runtime
+                .block_on(async { self.blob_client.append_block(block).await });
+            if let Err(error) = result {
+                self.buffer.drain(..appended);
+                return Err(error.into());
+            }
+            appended += block_len;
+        }
+        self.buffer.clear();
+        self.written_rows.add(take(&mut self.buffered_rows));
+        self.written_rows.on_flush();
+        Ok(())
+    }
+
+    fn rows_written_since_last_flush(&self) -> usize {
+        self.written_rows.last_flush()
+    }
+}
@@ -0,0 +1,344 @@
+// Copyright © 2024 Pathway
+
+use std::fs::File;
+use std::io::BufReader;
+use std::mem::take;
+use std::time::{Duration, Instant};
+
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine as _;
+use jsonwebtoken::{
+    encode as jwt_encode, Algorithm as JwtAlgorithm, EncodingKey as JwtEncodingKey,
+    Header as JwtHeader,
+};
+use reqwest::blocking::{Client as HttpClient, Response as HttpResponse};
+use reqwest::StatusCode as HttpStatusCode;
+use serde::{Deserialize, Serialize};
+
+use crate::connectors::data_format::FormatterContext;
+use crate::connectors::data_storage::{WriteError, Writer, SPECIAL_OUTPUT_FIELDS};
+use crate::engine::{Type, Value};
+use crate::python_api::ValueField;
+use crate::timestamp::current_unix_timestamp_secs;
+
+const BIGQUERY_API_URL: &str = "https://bigquery.googleapis.com/bigquery/v2";
+const BIGQUERY_SCOPE: &str = "https://www.googleapis.com/auth/bigquery";
+const GCP_JWT_BEARER_GRANT_TYPE: &str = "urn:ietf:params:oauth:grant-type:jwt-bearer";
+const GCP_TOKEN_LIFETIME: Duration = Duration::from_secs(3600);
+
+// The token is renewed a bit before its expiration, so that it doesn't expire
+// while the request is in flight
+const GCP_TOKEN_REFRESH_MARGIN: Duration = Duration::from_secs(60);
+
+#[derive(Deserialize)]
+struct GcpServiceAccountKey {
+    client_email: String,
+    private_key: String,
+    token_uri: String,
+}
+
+#[derive(Serialize)]
+struct GcpTokenClaims<'a> {
+    iss: &'a str,
+    scope: &'a str,
+    aud: &'a str,
+    iat: u64,
+    exp: u64,
+}
+
+#[derive(Deserialize)]
+struct GcpTokenResponse {
+    access_token: String,
+    expires_in: u64,
+}
+
+/// Issues the OAuth access tokens for a GCP service account, requesting a new one
+/// when the current token is about to expire.
+struct GcpTokenProvider {
+    key: GcpServiceAccountKey,
+    encoding_key: JwtEncodingKey,
+    current_token: Option<(String, Instant)>,
+}
+
+impl GcpTokenProvider {
+    fn new(service_account_path: &str) -> Result<Self, WriteError> {
+        let key: GcpServiceAccountKey =
+            serde_json::from_reader(BufReader::new(File::open(service_account_path)?))?;
+        let encoding_key = JwtEncodingKey::from_rsa_pem(key.private_key.as_bytes())?;
+        Ok(Self {
+            key,
+            encoding_key,
+            current_token: None,
+        })
+    }
+
+    fn access_token(&mut self, client: &HttpClient) -> Result<String, WriteError> {
+        if let Some((token, expires_at)) = &self.current_token {
+            if Instant::now() + GCP_TOKEN_REFRESH_MARGIN < *expires_at {
+                return Ok(token.clone());
+            }
+        }
+
+        let issued_at = current_unix_timestamp_secs();
+        let claims = GcpTokenClaims {
+            iss: &self.key.client_email,
+            scope: BIGQUERY_SCOPE,
+            aud: &self.key.token_uri,
+            iat: issued_at,
+            exp: issued_at + GCP_TOKEN_LIFETIME.as_secs(),
+        };
+        let assertion = jwt_encode(
+            &JwtHeader::new(JwtAlgorithm::RS256),
+            &claims,
+            &self.encoding_key,
+        )?;
+
+        let requested_at = Instant::now();
+        let response: GcpTokenResponse = client
+            .post(&self.key.token_uri)
+            .form(&[
+                ("grant_type", GCP_JWT_BEARER_GRANT_TYPE),
+                ("assertion", assertion.as_str()),
+            ])
+            .send()?
+            .error_for_status()?
+            .json()?;
+        let expires_at = requested_at + Duration::from_secs(response.expires_in);
+        self.current_token = Some((response.access_token.clone(), expires_at));
+        Ok(response.access_token)
+    }
+}
+
+pub struct BigQueryWriter {
+    client: HttpClient,
+    token_provider: GcpTokenProvider,
+    table_url: String,
+    field_names: Vec<String>,
+    field_types: Vec<Type>,
+    max_batch_size: Option<usize>,
+    min_commit_frequency: Option<Duration>,
+    last_commit_at: Instant,
+    buffered_rows: Vec<serde_json::Value>,
+}
+
+impl BigQueryWriter {
+    /// Creates a writer appending the rows to the table `dataset_id.table_id` of the
+    /// project `project_id` with the streaming inserts. If the table doesn't exist, it's
+    /// created with the schema derived from `value_fields`.
+    ///
+    /// The requests are authorized on behalf of the service account, whose JSON key is
+    /// stored at `service_account_path`.
+    pub fn new(
+        service_account_path: &str,
+        project_id: &str,
+        dataset_id: &str,
+        table_id: &str,
+        value_fields: &[ValueField],
+        max_batch_size: Option<usize>,
+        min_commit_frequency: Option<Duration>,
+    ) -> Result<Self, WriteError> {
+        let schema = Self::construct_schema(value_fields)?;
+        let mut writer = Self {
+            client: HttpClient::builder().build()?,
+            token_provider: GcpTokenProvider::new(service_account_path)?,
+            table_url: format!(
+                "{BIGQUERY_API_URL}/projects/{project_id}/datasets/{dataset_id}/tables/{table_id}"
+            ),
+            field_names: value_fields
+                .iter()
+                .map(|field| field.name.clone())
+                .collect(),
+            field_types: value_fields.iter().map(|field| field.type_).collect(),
+            max_batch_size,
+            min_commit_frequency,
+
+            // before the first commit, the time should be
+            // measured from the moment of the start
+            last_commit_at: Instant::now(),
+            buffered_rows: Vec::new(),
+        };
+        writer.ensure_table_exists(project_id, dataset_id, table_id, &schema)?;
+        Ok(writer)
+    }
+
+    fn bigquery_type(type_: Type) -> Result<&'static str, WriteError> {
+        Ok(match type_ {
+            Type::Bool => "BOOL",
+            Type::Int | Type::Duration => "INT64",
+            Type::Float => "FLOAT64",
+            Type::Pointer | Type::String => "STRING",
+            Type::Json => "JSON",
+            Type::Bytes => "BYTES",
+            Type::DateTimeNaive => "DATETIME",
+            Type::DateTimeUtc => "TIMESTAMP",
+            Type::Any | Type::Array | Type::Tuple | Type::PyObjectWrapper => {
+                return Err(WriteError::UnsupportedType(type_))
+            }
+        })
+    }
+
+    pub fn construct_schema(value_fields: &[ValueField]) -> Result<serde_json::Value, WriteError> {
+        let mut schema_fields = Vec::new();
+        for field in value_fields {
+            schema_fields.push(serde_json::json!({
+                "name": field.name,
+                "type": Self::bigquery_type(field.type_)?,
+                "mode": if field.is_optional { "NULLABLE" } else { "REQUIRED" },
+            }));
+        }
+        for (field, type_) in SPECIAL_OUTPUT_FIELDS {
+            schema_fields.push(serde_json::json!({
+                "name": field,
+                "type": Self::bigquery_type(type_)?,
+                "mode": "REQUIRED",
+            }));
+        }
+        Ok(serde_json::json!({ "fields": schema_fields }))
+    }
+
+    /// Converts the value into the JSON representation accepted by the streaming
+    /// inserts for the column of the type `type_`. Durations are written as the
+    /// number of microseconds, which is the precision of BigQuery timestamps.
+    pub fn bigquery_value(value: &Value, type_: Type) -> Result<serde_json::Value, WriteError> {
+        Ok(match value {
+            Value::None => serde_json::Value::Null,
+            Value::Bool(b) => (*b).into(),
+            Value::Int(i) => (*i).into(),
+            Value::Float(f) => match serde_json::Number::from_f64(f.0) {
+                Some(number) => number.into(),
+                None if f.is_nan() => "NaN".into(),
+                None if f.is_sign_positive() => "Infinity".into(),
+                None => "-Infinity".into(),
+            },
+            Value::String(s) => s.as_str().into(),
+            Value::Pointer(p) => p.to_string().into(),
+            Value::Json(j) => j.to_string().into(),
+            Value::Bytes(b) => BASE64.encode(b).into(),
+            Value::DateTimeNaive(dt) => dt.strftime("%Y-%m-%dT%H:%M:%S%.6f").into(),
+            Value::DateTimeUtc(dt) => dt.strftime("%Y-%m-%dT%H:%M:%S%.6fZ").into(),
+            Value::Duration(d) => d.microseconds().into(),
+            _ => return Err(WriteError::UnsupportedType(type_)),
+        })
+    }
+
+    fn response_json(response: HttpResponse) -> Result<serde_json::Value, WriteError> {
+        let status = response.status();
+        if !status.is_success() {
+            return Err(WriteError::BigQueryRequestFailed {
+                status,
+                message: response.text()?,
+            });
+        }
+        Ok(response.json()?)
+    }
+
+    fn ensure_table_exists(
+        &mut self,
+        project_id: &str,
+        dataset_id: &str,
+        table_id: &str,
+        schema: &serde_json::Value,
+    ) -> Result<(), WriteError> {
+        let token = self.token_provider.access_token(&self.client)?;
+        let response = self
+            .client
+            .get(&self.table_url)
+            .bearer_auth(&token)
+            .send()?;
+        if response.status() != HttpStatusCode::NOT_FOUND {
+            Self::response_json(response)?;
+            return Ok(());
+        }
+
+        let response = self
+            .client
+            .post(format!(
+                "{BIGQUERY_API_URL}/projects/{project_id}/datasets/{dataset_id}/tables"
+            ))
+            .bearer_auth(&token)
+            .json(&serde_json::json!({
+                "tableReference": {
+                    "projectId": project_id,
+                    "datasetId": dataset_id,
+                    "tableId": table_id,
+                },
+                "schema": schema,
+            }))
+            .send()?;
+        Self::response_json(response)?;
+        Ok(())
+    }
+
+    fn insert_buffered_rows(&mut self) -> Result<(), WriteError> {
+        let rows = take(&mut self.buffered_rows);
+        let token = self.token_provider.access_token(&self.client)?;
+        let response = self
+            .client
+            .post(format!("{}/insertAll", self.table_url))
+            .bearer_auth(token)
+            .json(&serde_json::json!({ "rows": rows }))
+            .send()?;
+        let response = Self::response_json(response)?;
+
+        // The request succeeds even if some of the rows are invalid, but then none
+        // of the rows are inserted and the valid ones are reported as "stopped"
+        if let Some(insert_errors) = response["insertErrors"].as_array() {
+            if !insert_errors.is_empty() {
+                let first_error = insert_errors
+                    .iter()
+                    .flat_map(|row_errors| row_errors["errors"].as_array().into_iter().flatten())
+                    .find(|error| error["reason"] != "stopped")
+                    .map_or_else(|| "unknown error".to_string(), ToString::to_string);
+                return Err(WriteError::BigQueryRowsRejected {
+                    rejected_rows: insert_errors.len(),
+                    total_rows: rows.len(),
+                    first_error,
+                });
+            }
+        }
+
+        self.last_commit_at = Instant::now();
+        Ok(())
+    }
+}
+
+impl Writer for BigQueryWriter {
+    fn write(&mut self, data: FormatterContext) -> Result<(), WriteError> {
+        let mut row = serde_json::Map::new();
+        for ((name, type_), value) in self
+            .field_names
+            .iter()
+            .zip(&self.field_types)
+            .zip(&data.values)
+        {
+            row.insert(name.clone(), Self::bigquery_value(value, *type_)?);
+        }
+        row.insert("time".to_string(), data.time.0.into());
+        row.insert("diff".to_string(), data.diff.into());
+        self.buffered_rows.push(serde_json::json!({ "json": row }));
+
+        if let Some(max_batch_size) = self.max_batch_size {
+            if self.buffered_rows.len() >= max_batch_size {
+                self.insert_buffered_rows()?;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn flush(&mut self, forced: bool) -> Result<(), WriteError> {
+        let commit_needed = !self.buffered_rows.is_empty()
+            && (self
+                .min_commit_frequency
+                .map_or(true, |f| self.last_commit_at.elapsed() >= f)
+                || forced);
+        if commit_needed {
+            self.insert_buffered_rows()?;
+        }
+        Ok(())
+    }
+
+    fn single_threaded(&self) -> bool {
+        false
+    }
+}
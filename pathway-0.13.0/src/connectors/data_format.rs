@@ -10,6 +10,7 @@ use std::iter::zip;
 use std::mem::take;
 use std::str::{from_utf8, Utf8Error};
 
+use crate::connectors::data_storage::ValuesMap;
 use crate::connectors::metadata::SourceMetadata;
 use crate::connectors::ReaderContext::{Diff, KeyValue, RawBytes, TokenizedEntries};
 use crate::connectors::{DataEventType, Offset, ReaderContext, SessionType, SnapshotEvent};
@@ -249,6 +250,12 @@ impl InnerSchemaField {
         }
     }
 
+    /// Parses the raw token of the field `name` into the value of the declared type,
+    /// in the same way as it's done for the DSV entries.
+    pub fn parse_token(&self, name: &str, raw_value: &str) -> DynResult<Value> {
+        parse_with_type(raw_value, self, name)
+    }
+
     pub fn adjust_value(&self, name: &str, value: Option<Value>) -> DynResult<Value> {
         match value {
             Some(value) => {
@@ -288,13 +295,17 @@ pub trait Parser: Send {
     }
 }
 
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub struct FormatterContext {
     pub payloads: Vec<Vec<u8>>,
     pub key: Key,
     pub values: Vec<Value>,
     pub time: Timestamp,
     pub diff: isize,
+
+    // Insertions and deletions are derived from the sign of `diff`. The upserts
+    // can only be set explicitly by the producer of the context
+    pub event_type: DataEventType,
 }
 
 impl FormatterContext {
@@ -311,6 +322,7 @@ impl FormatterContext {
             values,
             time,
             diff,
+            event_type: Self::event_type_for_diff(diff),
         }
     }
 
@@ -327,6 +339,20 @@ impl FormatterContext {
             values,
             time,
             diff,
+            event_type: Self::event_type_for_diff(diff),
+        }
+    }
+
+    pub fn with_event_type(mut self, event_type: DataEventType) -> FormatterContext {
+        self.event_type = event_type;
+        self
+    }
+
+    fn event_type_for_diff(diff: isize) -> DataEventType {
+        if diff < 0 {
+            DataEventType::Delete
+        } else {
+            DataEventType::Insert
         }
     }
 }
@@ -489,6 +515,34 @@ fn parse_with_type(
 /// "magic field" containing the metadata
 const METADATA_FIELD_NAME: &str = "_metadata";
 
+/// Parses the record with the metadata of a file, which the filesystem readers emit
+/// before its contents if `emit_metadata_record` is set. It's the only diff these
+/// parsers get. The value fields are taken from it by name, the metadata column and
+/// the reserved fields are those of the file, and the other fields are `None`.
+fn parse_metadata_record(
+    (event, key, values): &(DataEventType, Option<Vec<Value>>, ValuesMap),
+    value_fields: &[String],
+    metadata_column_value: &Value,
+    reserved_fields: &ValuesMap,
+    session_type: SessionType,
+) -> ParseResult {
+    let values = value_fields
+        .iter()
+        .map(|field| {
+            let value = if field == METADATA_FIELD_NAME {
+                Some(metadata_column_value)
+            } else if SourceMetadata::is_reserved_field(field) {
+                reserved_fields.get(field)
+            } else {
+                values.get(field)
+            };
+            Ok(value.cloned().unwrap_or(Value::None))
+        })
+        .collect();
+    let event = ParsedEventWithErrors::new(session_type, *event, key.clone().map(Ok), values);
+    Ok(vec![event])
+}
+
 impl DsvParser {
     pub fn new(settings: DsvSettings, schema: HashMap<String, InnerSchemaField>) -> DsvParser {
         DsvParser {
@@ -675,16 +729,20 @@ impl Parser for DsvParser {
                 Some(bytes) => self.parse_bytes_simple(DataEventType::Insert, bytes), // In Kafka we only have additions now
                 None => Err(ParseError::EmptyKafkaPayload.into()),
             },
-            Diff(_) => Err(ParseError::UnsupportedReaderContext.into()),
+            Diff(record) => parse_metadata_record(
+                record,
+                &self.settings.value_column_names,
+                &self.metadata_column_value,
+                &ValuesMap::default(),
+                SessionType::Native,
+            ),
         }
     }
 
     fn on_new_source_started(&mut self, metadata: Option<&SourceMetadata>) {
         self.dsv_header_read = false;
         if let Some(metadata) = metadata {
-            let metadata_serialized: JsonValue =
-                serde_json::to_value(metadata).expect("internal serialization error");
-            self.metadata_column_value = metadata_serialized.into();
+            self.metadata_column_value = metadata.column_value().into();
         }
     }
 
@@ -697,6 +755,7 @@ pub struct IdentityParser {
     value_fields: Vec<String>,
     parse_utf8: bool,
     metadata_column_value: Value,
+    reserved_fields: ValuesMap,
     session_type: SessionType,
 }
 
@@ -710,6 +769,7 @@ impl IdentityParser {
             value_fields,
             parse_utf8,
             metadata_column_value: Value::None,
+            reserved_fields: ValuesMap::default(),
             session_type,
         }
     }
@@ -741,9 +801,16 @@ impl Parser for IdentityParser {
                     None => return Err(ParseError::EmptyKafkaPayload.into()),
                 }
             }
-            Diff(_) | TokenizedEntries(_, _) => {
-                return Err(ParseError::UnsupportedReaderContext.into())
+            Diff(record) => {
+                return parse_metadata_record(
+                    record,
+                    &self.value_fields,
+                    &self.metadata_column_value,
+                    &self.reserved_fields,
+                    self.session_type,
+                )
             }
+            TokenizedEntries(_, _) => return Err(ParseError::UnsupportedReaderContext.into()),
         };
 
         let is_commit = is_commit_literal(&value);
@@ -760,6 +827,12 @@ impl Parser for IdentityParser {
                         .take()
                         .expect("metadata column should be used exactly once in IdentityParser")
                         .map(|metadata| metadata.unwrap_or(self.metadata_column_value.clone()))
+                } else if SourceMetadata::is_reserved_field(field) {
+                    Ok(self
+                        .reserved_fields
+                        .get(field)
+                        .cloned()
+                        .unwrap_or(Value::None))
                 } else {
                     value
                         .take()
@@ -792,9 +865,8 @@ impl Parser for IdentityParser {
 
     fn on_new_source_started(&mut self, metadata: Option<&SourceMetadata>) {
         if let Some(metadata) = metadata {
-            let metadata_serialized: JsonValue =
-                serde_json::to_value(metadata).expect("internal serialization error");
-            self.metadata_column_value = metadata_serialized.into();
+            self.metadata_column_value = metadata.column_value().into();
+            self.reserved_fields = metadata.reserved_fields();
         }
     }
 
@@ -1350,7 +1422,16 @@ impl Parser for JsonLinesParser {
                     return Err(ParseError::EmptyKafkaPayload.into());
                 }
             }
-            Diff(_) | TokenizedEntries(..) => {
+            Diff(record) => {
+                return parse_metadata_record(
+                    record,
+                    &self.value_field_names,
+                    &self.metadata_column_value,
+                    &ValuesMap::default(),
+                    self.session_type,
+                );
+            }
+            TokenizedEntries(..) => {
                 return Err(ParseError::UnsupportedReaderContext.into());
             }
         };
@@ -1400,9 +1481,7 @@ impl Parser for JsonLinesParser {
 
     fn on_new_source_started(&mut self, metadata: Option<&SourceMetadata>) {
         if let Some(metadata) = metadata {
-            let metadata_serialized: JsonValue =
-                serde_json::to_value(metadata).expect("internal serialization error");
-            self.metadata_column_value = metadata_serialized.into();
+            self.metadata_column_value = metadata.column_value().into();
         }
     }
 
@@ -1424,6 +1503,7 @@ pub struct TransparentParser {
     value_field_names: Vec<String>,
     schema: HashMap<String, InnerSchemaField>,
     session_type: SessionType,
+    reserved_fields: ValuesMap,
 }
 
 impl TransparentParser {
@@ -1438,6 +1518,15 @@ impl TransparentParser {
             value_field_names,
             schema,
             session_type,
+            reserved_fields: ValuesMap::default(),
+        }
+    }
+
+    fn field_value(&self, values: &ValuesMap, name: &str) -> Option<Value> {
+        if SourceMetadata::is_reserved_field(name) {
+            self.reserved_fields.get(name).cloned()
+        } else {
+            values.get(name).cloned()
         }
     }
 }
@@ -1462,7 +1551,7 @@ impl Parser for TransparentParser {
                         .expect(
                             "there should be an entry in the schema for name in key_field_names",
                         )
-                        .adjust_value(name, values.get(name).cloned())
+                        .adjust_value(name, self.field_value(values, name))
                     })
                     .collect()
             }));
@@ -1474,7 +1563,7 @@ impl Parser for TransparentParser {
                 self.schema
                     .get(name)
                     .expect("there should be an entry in the schema for name in value_field_names")
-                    .adjust_value(name, values.get(name).cloned())
+                    .adjust_value(name, self.field_value(values, name))
             })
             .collect();
 
@@ -1483,7 +1572,11 @@ impl Parser for TransparentParser {
         Ok(vec![event])
     }
 
-    fn on_new_source_started(&mut self, _metadata: Option<&SourceMetadata>) {}
+    fn on_new_source_started(&mut self, metadata: Option<&SourceMetadata>) {
+        if let Some(metadata) = metadata {
+            self.reserved_fields = metadata.reserved_fields();
+        }
+    }
 
     fn column_count(&self) -> usize {
         self.value_field_names.len()
@@ -2,7 +2,6 @@
 
 use pyo3::exceptions::PyValueError;
 use rand::Rng;
-use rdkafka::util::Timeout;
 use s3::error::S3Error;
 use std::any::type_name;
 use std::borrow::Cow;
@@ -10,145 +9,341 @@ use std::collections::HashMap;
 use std::collections::HashSet;
 use std::collections::VecDeque;
 use std::env;
-use std::fmt::Debug;
 use std::fs::File;
+use std::future::Future;
 use std::io;
 use std::io::BufRead;
 use std::io::BufReader;
 use std::io::BufWriter;
+use std::io::Read;
 use std::io::Write;
 use std::io::{Seek, SeekFrom};
 use std::mem::take;
 use std::os::windows::ffi::OsStrExt;
 use std::ffi::OsStr;
 use std::path::{Path, PathBuf};
-use std::str::{from_utf8, Utf8Error};
+use std::str::Utf8Error;
+use std::sync::atomic::{AtomicBool, Ordering as AtomicOrdering};
 use std::sync::Arc;
-use std::thread;
 use std::thread::sleep;
 use std::time::{Duration, Instant, SystemTime};
 
-use chrono::{DateTime, FixedOffset};
 use log::{error, warn};
-use postgres::types::ToSql;
+use sha2::{Digest, Sha256};
 use tempfile::{tempdir, TempDir};
 use tokio::runtime::Runtime as TokioRuntime;
+use tokio_tungstenite::tungstenite::Error as WebSocketError;
 use xxhash_rust::xxh3::Xxh3 as Hasher;
 
+use crate::connectors::azure::is_retriable_azure_error;
+use crate::connectors::clock::{Clock, SystemClock};
 use crate::connectors::data_format::FormatterContext;
 use crate::connectors::metadata::SourceMetadata;
-use crate::connectors::offset::EMPTY_OFFSET;
+use crate::connectors::s3::is_retriable_s3_error;
 use crate::connectors::{Offset, OffsetKey, OffsetValue};
-use crate::deepcopy::DeepCopy;
-use crate::engine::time::DateTime as EngineDateTime;
+use crate::engine::error::DynError;
+use crate::engine::Timestamp;
 use crate::engine::Type;
 use crate::engine::Value;
+use crate::engine::{
+    DateTimeNaive as EngineDateTimeNaive, DateTimeUtc as EngineDateTimeUtc,
+    Duration as EngineDuration,
+};
 use crate::fs_helpers::ensure_directory;
 use crate::persistence::frontier::OffsetAntichain;
+use crate::persistence::metadata_backends::{Error as MetadataBackendError, MetadataBackend};
 use crate::persistence::{ExternalPersistentId, PersistentId};
 use crate::python_api::threads::PythonThreadState;
 use crate::python_api::with_gil_and_pool;
 use crate::python_api::PythonSubject;
-use crate::python_api::ValueField;
-use crate::timestamp::current_unix_timestamp_secs;
 
+use apache_avro::types::Value as AvroValue;
+use apache_avro::Error as AvroError;
+use async_nats::Error as NatsError;
+use aws_sdk_dynamodb::error::BuildError as DynamoDbBuildError;
+use aws_sdk_dynamodb::Error as DynamoDbError;
+use azure_core::error::Error as AzureError;
 use bincode::ErrorKind as BincodeError;
 use deltalake::arrow::array::Array as ArrowArray;
 use deltalake::arrow::array::RecordBatch as DTRecordBatch;
 use deltalake::arrow::array::{
     BinaryArray as ArrowBinaryArray, BooleanArray as ArrowBooleanArray,
-    Float64Array as ArrowFloat64Array, Int64Array as ArrowInt64Array,
-    StringArray as ArrowStringArray, TimestampMicrosecondArray as ArrowTimestampArray,
-};
-use deltalake::arrow::datatypes::{
-    DataType as ArrowDataType, Field as ArrowField, Schema as ArrowSchema,
-    TimeUnit as ArrowTimeUnit,
+    DurationMicrosecondArray as ArrowDurationMicrosecondArray, Float64Array as ArrowFloat64Array,
+    Int64Array as ArrowInt64Array, ListArray as ArrowListArray, StringArray as ArrowStringArray,
+    TimestampMicrosecondArray as ArrowTimestampArray,
 };
+use deltalake::arrow::datatypes::{DataType as ArrowDataType, TimeUnit as ArrowTimeUnit};
 use deltalake::arrow::error::ArrowError;
-use deltalake::kernel::DataType as DeltaTableKernelType;
-use deltalake::kernel::PrimitiveType as DeltaTablePrimitiveType;
-use deltalake::kernel::StructField as DeltaTableStructField;
-use deltalake::operations::create::CreateBuilder as DeltaTableCreateBuilder;
-use deltalake::protocol::SaveMode as DeltaTableSaveMode;
-use deltalake::writer::{DeltaWriter, RecordBatchWriter as DTRecordBatchWriter};
-use deltalake::{open_table_with_storage_options as open_delta_table, DeltaTable, DeltaTableError};
-use elasticsearch::{BulkParts, Elasticsearch};
+use deltalake::datafusion::error::DataFusionError;
+use deltalake::datafusion::parquet::errors::ParquetError;
+use deltalake::DeltaTableError;
+use encoding_rs::Encoding;
+use flate2::read::MultiGzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression as GzCompression;
 use glob::Pattern as GlobPattern;
 use glob::PatternError as GlobPatternError;
-use pipe::PipeReader;
-use postgres::Client as PsqlClient;
+#[cfg(feature = "iceberg")]
+use iceberg::Error as IcebergError;
+#[cfg(feature = "iceberg")]
+use iceberg_arrow_schema::ArrowError as IcebergArrowError;
+#[cfg(feature = "clickhouse")]
+use klickhouse::KlickhouseError as ClickHouseError;
+#[cfg(feature = "amqp")]
+use lapin::Error as AmqpError;
+use ndarray::Array1;
+use orc_rust::error::OrcError;
+#[cfg(feature = "pulsar")]
+use pulsar::Error as PulsarError;
 use pyo3::prelude::*;
-use rdkafka::consumer::{BaseConsumer, Consumer, DefaultConsumerContext};
+use pyo3::types::PyDict;
 use rdkafka::error::{KafkaError, RDKafkaErrorCode};
-use rdkafka::message::{Header as KafkaHeader, OwnedHeaders as KafkaHeaders};
-use rdkafka::producer::{BaseRecord, DefaultProducerContext, Producer, ThreadedProducer};
-use rdkafka::topic_partition_list::Offset as KafkaOffset;
-use rdkafka::Message;
-use rusqlite::types::ValueRef as SqliteValue;
-use rusqlite::types::{
-    FromSql as FromSqlite, FromSqlError as FromSqliteError, FromSqlResult as FromSqliteResult,
+use redis::RedisError;
+use reqwest::StatusCode as HttpStatusCode;
+use rumqttc::{
+    ClientError as MqttClientError, ConnectionError as MqttConnectionError,
+    OptionError as MqttOptionError,
 };
-use rusqlite::Connection as SqliteConnection;
 use rusqlite::Error as SqliteError;
-use s3::bucket::Bucket as S3Bucket;
 use serde::{Deserialize, Serialize};
+use zstd::stream::read::Decoder as ZstdDecoder;
+use zstd::stream::write::Encoder as ZstdEncoder;
+
+#[cfg(feature = "amqp")]
+pub use crate::connectors::amqp::{AmqpReader, AmqpWriter};
+pub use crate::connectors::avro::{AvroFileReader, AvroFileWriter};
+pub use crate::connectors::azure::{
+    azure_container_client, AzureBlobCredentials, AzureBlobCsvReader, AzureBlobGenericReader,
+    AzureBlobScanner, AzureBlobWriter,
+};
+pub use crate::connectors::bigquery::BigQueryWriter;
+#[cfg(feature = "clickhouse")]
+pub use crate::connectors::clickhouse::ClickHouseWriter;
+pub use crate::connectors::dead_letter::{DeadLetterReader, DeadLetterWriter};
+pub use crate::connectors::delta_table::{
+    DeltaTableReader, DeltaTableWriteMode, DeltaTableWriter, DELETION_FLAG_FIELD,
+    DURATION_UNIT_METADATA_KEY,
+};
+pub use crate::connectors::dynamodb::DynamoDbWriter;
+pub use crate::connectors::elasticsearch::{ElasticSearchBulkAction, ElasticSearchWriter};
+pub use crate::connectors::gcs::{GcsCsvReader, GcsGenericReader, GCS_XML_API_ENDPOINT};
+pub use crate::connectors::http::HttpReader;
+#[cfg(feature = "iceberg")]
+pub use crate::connectors::iceberg::{IcebergCatalogConfig, IcebergWriter};
+pub use crate::connectors::json_array::JsonArrayFileReader;
+pub use crate::connectors::kafka::{
+    KafkaCompression, KafkaDeliveryContext, KafkaProducerTuning, KafkaReader, KafkaWriter,
+};
+pub use crate::connectors::mqtt::{MqttReader, MqttWriter};
+pub use crate::connectors::multi_writer::{MultiWriter, MultiWriterErrorPolicy};
+pub use crate::connectors::nats::{NatsReader, NatsWriter};
+pub use crate::connectors::orc::{OrcFileReader, OrcFileWriter};
+pub use crate::connectors::parquet::{ParquetFileReader, ParquetFileWriter};
+pub use crate::connectors::psql::PsqlWriter;
+#[cfg(feature = "pulsar")]
+pub use crate::connectors::pulsar::{PulsarReader, PulsarWriter};
+pub use crate::connectors::redis_stream::{RedisStreamReader, RedisStreamWriter};
+pub use crate::connectors::retry::RetryingWriter;
+pub use crate::connectors::s3::{
+    CsvObjectReader, CurrentlyProcessedS3Object, ObjectOrdering, ObjectScanner, ObjectStore,
+    ProcessedS3Objects, S3CsvReader, S3GenericReader, S3Scanner, SkippingWriter,
+};
+pub use crate::connectors::sqlite::{SqliteReader, SqliteWriter};
+pub use crate::connectors::throttle::ThrottledReader;
+pub use crate::connectors::websocket::WebSocketReader;
 
 #[cfg(target_os = "linux")]
 mod inotify_support {
-    use inotify::WatchMask;
-    use std::path::Path;
-    use std::thread::sleep;
+    use glob::Pattern;
+    use inotify::{EventMask, WatchDescriptor, WatchMask};
+    use log::warn;
+    use std::collections::HashMap;
+    use std::ffi::{OsStr, OsString};
+    use std::io;
+    use std::os::fd::AsRawFd;
+    use std::path::{Path, PathBuf};
     use std::time::Duration;
 
-    pub use inotify::Inotify;
+    /// Decides which subdirectories of a watched directory are watched too.
+    #[derive(Clone, Debug)]
+    enum WatchScope {
+        /// The rest of the glob: the subdirectories matching its first component are
+        /// watched with the remaining ones.
+        Patterns(Vec<Pattern>),
+
+        /// All the nested directories are watched, since the directory is matched by
+        /// the glob and is read as a whole, or the glob continues with `**`.
+        Recursive,
+    }
+
+    impl WatchScope {
+        fn new(patterns: Vec<Pattern>) -> Self {
+            match patterns.first() {
+                Some(pattern) if pattern.as_str() != "**" => Self::Patterns(patterns),
+                _ => Self::Recursive,
+            }
+        }
+
+        fn nested(&self, name: &OsStr) -> Option<Self> {
+            match self {
+                Self::Recursive => Some(Self::Recursive),
+                Self::Patterns(patterns) => {
+                    let (first, rest) = patterns.split_first()?;
+                    first
+                        .matches(&name.to_string_lossy())
+                        .then(|| Self::new(rest.to_vec()))
+                }
+            }
+        }
+    }
 
-    #[allow(dead_code)]
-    pub fn subscribe_inotify(path: impl AsRef<Path>) -> Option<Inotify> {
-        let inotify = Inotify::init().ok()?;
+    #[derive(Debug)]
+    pub struct Inotify {
+        inner: inotify::Inotify,
+        watched_directories: HashMap<WatchDescriptor, (PathBuf, WatchScope)>,
+    }
 
-        inotify
-            .watches()
-            .add(
+    impl Inotify {
+        fn watch(&mut self, path: &Path, scope: WatchScope) -> io::Result<()> {
+            let descriptor = self.inner.watches().add(
                 path,
                 WatchMask::ATTRIB
                     | WatchMask::CLOSE_WRITE
+                    | WatchMask::CREATE
                     | WatchMask::DELETE
                     | WatchMask::DELETE_SELF
                     | WatchMask::MOVE_SELF
                     | WatchMask::MOVED_FROM
                     | WatchMask::MOVED_TO,
-            )
-            .ok()?;
+            )?;
+            self.watched_directories
+                .insert(descriptor, (path.to_path_buf(), scope.clone()));
+
+            for entry in std::fs::read_dir(path)? {
+                let entry = entry?;
+                if !entry.file_type()?.is_dir() {
+                    continue;
+                }
+                if let Some(nested_scope) = scope.nested(&entry.file_name()) {
+                    self.watch(&entry.path(), nested_scope)?;
+                }
+            }
 
+            Ok(())
+        }
+    }
+
+    /// Watches the directories that may contain the files matching the globs. Each
+    /// glob is given by the directory it starts from and its remaining components.
+    pub fn subscribe_inotify(roots: &[(PathBuf, Vec<String>)]) -> Option<Inotify> {
+        let mut inotify = Inotify {
+            inner: inotify::Inotify::init().ok()?,
+            watched_directories: HashMap::new(),
+        };
+        for (path, components) in roots {
+            // The globs are validated by the scanner, but if a component still can't
+            // be compiled, everything below the root is watched
+            let scope = components
+                .iter()
+                .map(|component| Pattern::new(component))
+                .collect::<Result<Vec<_>, _>>()
+                .map_or(WatchScope::Recursive, WatchScope::new);
+            if let Err(e) = inotify.watch(path, scope) {
+                warn!(
+                    "Failed to watch {} for changes, falling back to polling: {e}",
+                    path.display()
+                );
+                return None;
+            }
+        }
         Some(inotify)
     }
 
-    #[allow(clippy::unnecessary_wraps)]
-    pub fn wait(_inotify: &mut Inotify) -> Option<()> {
-        sleep(Duration::from_millis(500));
-        None
+    pub fn watched_directories(inotify: &Inotify) -> Vec<PathBuf> {
+        let mut directories: Vec<PathBuf> = inotify
+            .watched_directories
+            .values()
+            .map(|(path, _)| path.clone())
+            .collect();
+        directories.sort();
+        directories
+    }
+
+    /// Waits until a change is reported in the watched directories or until the
+    /// refresh interval passes, whichever comes first. Directories created in the
+    /// meantime are subscribed to as well, if the globs may match the files in them,
+    /// while the watches of the removed directories are dropped.
+    pub fn wait(inotify: &mut Inotify, refresh_interval: Duration) -> Option<()> {
+        let mut poll_fd = libc::pollfd {
+            fd: inotify.inner.as_raw_fd(),
+            events: libc::POLLIN,
+            revents: 0,
+        };
+        let timeout = i32::try_from(refresh_interval.as_millis()).unwrap_or(i32::MAX);
+        let n_ready = unsafe { libc::poll(&mut poll_fd, 1, timeout) };
+        if n_ready < 0 {
+            return None;
+        }
+        if n_ready == 0 {
+            return Some(());
+        }
+
+        let mut buffer = [0; 4096];
+        let events: Vec<(WatchDescriptor, EventMask, Option<OsString>)> = inotify
+            .inner
+            .read_events(&mut buffer)
+            .ok()?
+            .map(|event| (event.wd, event.mask, event.name.map(OsStr::to_os_string)))
+            .collect();
+
+        for (descriptor, mask, name) in events {
+            if mask.contains(EventMask::IGNORED) {
+                // The directory was removed, so the watch was removed with it
+                inotify.watched_directories.remove(&descriptor);
+            } else if mask.contains(EventMask::MOVE_SELF) {
+                // The directory was moved away, possibly out of the reach of the globs
+                inotify.watched_directories.remove(&descriptor);
+                let _ = inotify.inner.watches().remove(descriptor);
+            } else if mask.contains(EventMask::ISDIR)
+                && mask.intersects(EventMask::CREATE | EventMask::MOVED_TO)
+            {
+                let Some(name) = name else {
+                    continue;
+                };
+                let Some((parent, scope)) = inotify.watched_directories.get(&descriptor) else {
+                    continue;
+                };
+                let Some(nested_scope) = scope.nested(&name) else {
+                    continue;
+                };
+                let directory = parent.join(name);
+                if let Err(e) = inotify.watch(&directory, nested_scope) {
+                    warn!("Failed to watch {} for changes: {e}", directory.display());
+                }
+            }
+        }
 
-        // Commented out due to using recursive subdirs
-        //
-        // inotify
-        //     .read_events_blocking(&mut [0; 1024])
-        //     .ok()
-        //     .map(|_events| ())
+        Some(())
     }
 }
 
 #[cfg(not(target_os = "linux"))]
 mod inotify_support {
-    use std::path::Path;
+    use std::path::PathBuf;
+    use std::time::Duration;
 
     #[derive(Debug)]
     pub struct Inotify;
 
-    pub fn subscribe_inotify(_path: impl AsRef<Path>) -> Option<Inotify> {
+    pub fn subscribe_inotify(_roots: &[(PathBuf, Vec<String>)]) -> Option<Inotify> {
         None
     }
 
-    pub fn wait(_inotify: &mut Inotify) -> Option<()> {
+    pub fn watched_directories(_inotify: &Inotify) -> Vec<PathBuf> {
+        Vec::new()
+    }
+
+    pub fn wait(_inotify: &mut Inotify, _refresh_interval: Duration) -> Option<()> {
         None
     }
 }
@@ -170,29 +365,160 @@ pub enum DataEventType {
     Upsert,
 }
 
+impl DataEventType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DataEventType::Insert => "insert",
+            DataEventType::Delete => "delete",
+            DataEventType::Upsert => "upsert",
+        }
+    }
+}
+
 const FINISH_LITERAL: &str = "*FINISH*";
 
-#[derive(Debug, Default, Clone, PartialEq, Eq)]
+/// The names of the columns of the ordered values, along with the position of
+/// each name, so that a value is found without scanning the names.
+#[derive(Debug)]
+pub struct ValuesMapColumns {
+    names: Vec<String>,
+    positions: HashMap<String, usize>,
+}
+
+impl ValuesMapColumns {
+    pub fn new(names: Vec<String>) -> Self {
+        let mut positions = HashMap::with_capacity(names.len());
+        for (index, name) in names.iter().enumerate() {
+            positions.entry(name.clone()).or_insert(index);
+        }
+        Self { names, positions }
+    }
+
+    pub fn names(&self) -> &[String] {
+        &self.names
+    }
+
+    pub fn len(&self) -> usize {
+        self.names.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.names.is_empty()
+    }
+
+    fn position(&self, name: &str) -> Option<usize> {
+        self.positions.get(name).copied()
+    }
+}
+
+impl PartialEq for ValuesMapColumns {
+    fn eq(&self, other: &Self) -> bool {
+        self.names == other.names
+    }
+}
+
+#[derive(Debug, Clone)]
+enum ValuesMapStorage {
+    Hashed(HashMap<String, Value>),
+    // The columns order is provided by the reader once and shared between the rows
+    Ordered {
+        columns: Arc<ValuesMapColumns>,
+        values: Vec<Value>,
+    },
+}
+
+#[derive(Debug, Clone)]
 pub struct ValuesMap {
-    map: HashMap<String, Value>,
-    // TODO: use a vector if performance improvement is needed
-    // then Reader has to be aware of the columns order
+    storage: ValuesMapStorage,
 }
 
 impl ValuesMap {
     const SPECIAL_FIELD_NAME: &'static str = "_pw_special";
+
+    /// Creates a map of `values`, each of them belonging to the column at the same
+    /// position in `columns`. Fails if their numbers differ.
+    pub fn from_ordered(
+        columns: Arc<ValuesMapColumns>,
+        values: Vec<Value>,
+    ) -> Result<Self, ReadError> {
+        if columns.len() != values.len() {
+            return Err(ReadError::ValuesMapLengthMismatch {
+                columns: columns.len(),
+                values: values.len(),
+            });
+        }
+        Ok(ValuesMap {
+            storage: ValuesMapStorage::Ordered { columns, values },
+        })
+    }
+
     pub fn is_special(&self, value: &str) -> bool {
-        self.map.len() == 1 && self.map.get(Self::SPECIAL_FIELD_NAME) == Some(&Value::from(value))
+        self.len() == 1 && self.get(Self::SPECIAL_FIELD_NAME) == Some(&Value::from(value))
     }
 
     pub fn get(&self, key: &str) -> Option<&Value> {
-        self.map.get(key)
+        match &self.storage {
+            ValuesMapStorage::Hashed(map) => map.get(key),
+            ValuesMapStorage::Ordered { columns, values } => {
+                columns.position(key).map(|index| &values[index])
+            }
+        }
+    }
+
+    fn len(&self) -> usize {
+        match &self.storage {
+            ValuesMapStorage::Hashed(map) => map.len(),
+            ValuesMapStorage::Ordered { values, .. } => values.len(),
+        }
+    }
+
+    fn iter(&self) -> Box<dyn Iterator<Item = (&str, &Value)> + '_> {
+        match &self.storage {
+            ValuesMapStorage::Hashed(map) => {
+                Box::new(map.iter().map(|(column, value)| (column.as_str(), value)))
+            }
+            ValuesMapStorage::Ordered { columns, values } => {
+                Box::new(columns.names().iter().map(String::as_str).zip(values))
+            }
+        }
+    }
+}
+
+impl Default for ValuesMap {
+    fn default() -> Self {
+        HashMap::new().into()
     }
 }
 
+impl PartialEq for ValuesMap {
+    fn eq(&self, other: &Self) -> bool {
+        match (&self.storage, &other.storage) {
+            (
+                ValuesMapStorage::Ordered { columns, values },
+                ValuesMapStorage::Ordered {
+                    columns: other_columns,
+                    values: other_values,
+                },
+            ) if Arc::ptr_eq(columns, other_columns) || columns == other_columns => {
+                values == other_values
+            }
+            _ => {
+                self.len() == other.len()
+                    && self
+                        .iter()
+                        .all(|(column, value)| other.get(column) == Some(value))
+            }
+        }
+    }
+}
+
+impl Eq for ValuesMap {}
+
 impl From<HashMap<String, Value>> for ValuesMap {
     fn from(value: HashMap<String, Value>) -> Self {
-        ValuesMap { map: value }
+        ValuesMap {
+            storage: ValuesMapStorage::Hashed(value),
+        }
     }
 }
 
@@ -227,6 +553,19 @@ impl ReaderContext {
     pub fn from_key_value(key: Option<Vec<u8>>, value: Option<Vec<u8>>) -> ReaderContext {
         ReaderContext::KeyValue((key, value))
     }
+
+    /// The size of the raw payload of the entry. The entries that are already parsed
+    /// into values don't have one, so their size is zero.
+    pub fn payload_len(&self) -> usize {
+        match self {
+            ReaderContext::RawBytes(_, raw_bytes) => raw_bytes.len(),
+            ReaderContext::TokenizedEntries(_, tokens) => tokens.iter().map(String::len).sum(),
+            ReaderContext::KeyValue((key, value)) => {
+                key.as_ref().map_or(0, Vec::len) + value.as_ref().map_or(0, Vec::len)
+            }
+            ReaderContext::Diff(_) => 0,
+        }
+    }
 }
 
 #[derive(Debug, Eq, PartialEq)]
@@ -264,11 +603,134 @@ pub enum ReadError {
     #[error(transparent)]
     Bincode(#[from] BincodeError),
 
+    #[error(transparent)]
+    Arrow(#[from] ArrowError),
+
+    #[error(transparent)]
+    Parquet(#[from] ParquetError),
+
+    #[error(transparent)]
+    Orc(#[from] OrcError),
+
+    #[error(transparent)]
+    DeltaTable(#[from] DeltaTableError),
+
+    #[error("arrow type {0} can't be converted into an engine value")]
+    UnsupportedArrowType(ArrowDataType),
+
+    #[error(transparent)]
+    Avro(#[from] AvroError),
+
+    #[error("avro value {0:?} can't be converted into an engine value")]
+    UnsupportedAvroValue(AvroValue),
+
+    #[error(transparent)]
+    Http(#[from] reqwest::Error),
+
+    #[error(transparent)]
+    Json(#[from] serde_json::Error),
+
+    #[error("{values} values were given for {columns} columns")]
+    ValuesMapLengthMismatch { columns: usize, values: usize },
+
+    #[error("no array of items at {0:?} in the HTTP response")]
+    HttpItemsNotFound(String),
+
+    #[error("{0} items of the HTTP page were read, which is more than can be skipped")]
+    HttpPagePositionTooLarge(u64),
+
+    #[error("NATS error: {0}")]
+    Nats(NatsError),
+
+    #[cfg(feature = "pulsar")]
+    #[error("Pulsar error: {0}")]
+    Pulsar(#[from] PulsarError),
+
+    #[cfg(feature = "amqp")]
+    #[error("AMQP error: {0}")]
+    Amqp(#[from] AmqpError),
+
+    #[error("WebSocket error: {0}")]
+    WebSocket(#[from] WebSocketError),
+
+    #[error("Redis error: {0}")]
+    Redis(#[from] RedisError),
+
+    #[error("MQTT connection error: {0}")]
+    MqttConnection(#[from] MqttConnectionError),
+
+    #[error("MQTT client error: {0}")]
+    MqttClient(#[from] MqttClientError),
+
+    #[error("invalid MQTT broker uri: {0}")]
+    MqttOptions(#[from] MqttOptionError),
+
+    /// The entry is given as it was read, for the dead-letter destination.
     #[error("malformed data")]
-    MalformedData,
+    MalformedData(Vec<u8>),
+
+    #[error("entry {entry} of {path:?} doesn't satisfy the schema: {error}")]
+    SchemaNotSatisfied {
+        path: String,
+        entry: u64,
+        raw_entry: Vec<u8>,
+        error: DynError,
+    },
 
     #[error("no objects to read")]
     NoObjectsToRead,
+
+    #[error("projected column {0:?} isn't among the columns of the table")]
+    UnknownProjectedColumn(String),
+
+    #[error("Azure Blob Storage error: {0}")]
+    AzureBlob(#[from] AzureError),
+
+    #[error("JSON pointer {0:?} must be empty or start with '/'")]
+    InvalidJsonPointer(String),
+
+    #[error("no JSON array found at {0:?}")]
+    JsonArrayNotFound(String),
+
+    #[error("malformed JSON array: {0}")]
+    MalformedJsonArray(String),
+
+    #[error("unknown text encoding {0:?}")]
+    UnknownEncoding(String),
+
+    #[error("text encoding {0} isn't compatible with ASCII")]
+    UnsupportedEncoding(&'static str),
+
+    #[error("the text isn't valid {0}")]
+    InvalidEncodedText(&'static str),
+
+    #[error("inconsistent reader state: {0}")]
+    InconsistentState(String),
+
+    #[error("failed to access the persisted reader state: {0}")]
+    StateStorage(#[from] MetadataBackendError),
+
+    #[error("no persisted reader state for version {0}")]
+    StateNotFound(u64),
+
+    #[error("a record is longer than the limit of {0} bytes")]
+    RecordTooLarge(usize),
+
+    #[error("object download thread panicked: {0}")]
+    DownloadThreadPanicked(String),
+}
+
+impl ReadError {
+    /// The entry that caused the error, if it's the entry that is at fault rather
+    /// than the source.
+    pub fn raw_entry(&self) -> Option<&[u8]> {
+        match self {
+            Self::MalformedData(raw_entry) | Self::SchemaNotSatisfied { raw_entry, .. } => {
+                Some(raw_entry)
+            }
+            _ => None,
+        }
+    }
 }
 
 #[derive(Serialize, Deserialize, Clone, Copy, Debug)]
@@ -280,6 +742,23 @@ pub enum StorageType {
     Kafka,
     Python,
     Sqlite,
+    ParquetFilesystem,
+    OrcFilesystem,
+    AvroFilesystem,
+    JsonArrayFilesystem,
+    DeltaTable,
+    Http,
+    Nats,
+    Amqp,
+    WebSocket,
+    GcsCsv,
+    GcsLines,
+    Memory,
+    RedisStream,
+    Mqtt,
+    Pulsar,
+    AzureBlobCsv,
+    AzureBlobLines,
 }
 
 impl StorageType {
@@ -296,10 +775,86 @@ impl StorageType {
             StorageType::Python => PythonReader::merge_two_frontiers(lhs, rhs),
             StorageType::S3Lines => S3GenericReader::merge_two_frontiers(lhs, rhs),
             StorageType::Sqlite => SqliteReader::merge_two_frontiers(lhs, rhs),
+            StorageType::ParquetFilesystem => ParquetFileReader::merge_two_frontiers(lhs, rhs),
+            StorageType::OrcFilesystem => OrcFileReader::merge_two_frontiers(lhs, rhs),
+            StorageType::AvroFilesystem => AvroFileReader::merge_two_frontiers(lhs, rhs),
+            StorageType::JsonArrayFilesystem => JsonArrayFileReader::merge_two_frontiers(lhs, rhs),
+            StorageType::DeltaTable => DeltaTableReader::merge_two_frontiers(lhs, rhs),
+            StorageType::Http => HttpReader::merge_two_frontiers(lhs, rhs),
+            StorageType::Nats => NatsReader::merge_two_frontiers(lhs, rhs),
+            #[cfg(feature = "amqp")]
+            StorageType::Amqp => AmqpReader::merge_two_frontiers(lhs, rhs),
+            #[cfg(not(feature = "amqp"))]
+            StorageType::Amqp => unreachable!("Pathway was built without the AMQP connector"),
+            StorageType::WebSocket => WebSocketReader::merge_two_frontiers(lhs, rhs),
+            StorageType::GcsCsv => GcsCsvReader::merge_two_frontiers(lhs, rhs),
+            StorageType::GcsLines => GcsGenericReader::merge_two_frontiers(lhs, rhs),
+            StorageType::Memory => MemoryReader::merge_two_frontiers(lhs, rhs),
+            StorageType::RedisStream => RedisStreamReader::merge_two_frontiers(lhs, rhs),
+            StorageType::Mqtt => MqttReader::merge_two_frontiers(lhs, rhs),
+            #[cfg(feature = "pulsar")]
+            StorageType::Pulsar => PulsarReader::merge_two_frontiers(lhs, rhs),
+            #[cfg(not(feature = "pulsar"))]
+            StorageType::Pulsar => unreachable!("Pathway was built without the Pulsar connector"),
+            StorageType::AzureBlobCsv => AzureBlobCsvReader::merge_two_frontiers(lhs, rhs),
+            StorageType::AzureBlobLines => AzureBlobGenericReader::merge_two_frontiers(lhs, rhs),
         }
     }
 }
 
+/// A flag asking the readers to stop, shared between the reading threads and the
+/// code handling the termination of the program. The readers that wait for new data
+/// check it at least once per [`ShutdownSignal::CHECK_INTERVAL`] and finish reading
+/// once it's set, so that the frontier reached so far can be committed.
+///
+/// A single signal is shared by all the readers of a computation. It's requested on
+/// the first SIGTERM and when the computation stops because of an error.
+#[derive(Clone, Debug, Default)]
+pub struct ShutdownSignal(Arc<AtomicBool>);
+
+impl ShutdownSignal {
+    const SLEEP_STEP: Duration = Duration::from_millis(100);
+
+    /// The longest time a reader waiting for new data goes without checking the
+    /// signal.
+    pub const CHECK_INTERVAL: Duration = Duration::from_secs(1);
+
+    pub fn request(&self) {
+        self.0.store(true, AtomicOrdering::Release);
+    }
+
+    pub fn is_requested(&self) -> bool {
+        self.0.load(AtomicOrdering::Acquire)
+    }
+
+    /// Sleeps for `duration`, waking up earlier if the shutdown is requested.
+    pub fn sleep(&self, duration: Duration) {
+        let deadline = Instant::now() + duration;
+        while !self.is_requested() {
+            let now = Instant::now();
+            if now >= deadline {
+                break;
+            }
+            sleep((deadline - now).min(Self::SLEEP_STEP));
+        }
+    }
+
+    /// Runs `future` on `runtime` until it completes, checking the signal once per
+    /// `CHECK_INTERVAL`. Returns `None` if the shutdown is requested first. The
+    /// runtime must have the time driver enabled.
+    pub fn block_on<F: Future>(&self, runtime: &TokioRuntime, future: F) -> Option<F::Output> {
+        let mut future = std::pin::pin!(future);
+        runtime.block_on(async {
+            while !self.is_requested() {
+                if let Ok(output) = tokio::time::timeout(Self::CHECK_INTERVAL, &mut future).await {
+                    return Some(output);
+                }
+            }
+            None
+        })
+    }
+}
+
 pub trait Reader {
     fn read(&mut self) -> Result<ReadResult, ReadError>;
 
@@ -309,6 +864,36 @@ pub trait Reader {
     fn update_persistent_id(&mut self, persistent_id: Option<PersistentId>);
     fn persistent_id(&self) -> Option<PersistentId>;
 
+    /// Makes the reader finish with `ReadResult::Finished` once `signal` is
+    /// requested, rather than keep waiting for new data. The readers that don't wait
+    /// for new data ignore it.
+    fn set_shutdown_signal(&mut self, _signal: ShutdownSignal) {}
+
+    /// Makes the reader return `ReadResult::FinishedSource { commit_allowed: true }`
+    /// once it has been waiting for new data for `timeout`, so that the time advances
+    /// even if the source stays idle. It's checked between the polls, hence the actual
+    /// period can be longer by up to a poll interval. The readers that don't wait for
+    /// new data ignore it.
+    fn set_idle_timeout(&mut self, _timeout: Option<Duration>) {}
+
+    /// Gives the reader with a persistent id a key-value storage for the persisted
+    /// state that is too large to be kept in the offsets. The readers that have no
+    /// such state ignore it.
+    fn set_state_storage(&mut self, _storage: Box<dyn MetadataBackend>) {}
+
+    /// Called in the reading thread after the engine has committed the data up to
+    /// `frontier`. With the persistence, it's only called once the commit is
+    /// finalized, so a restarted program never seeks to an earlier frontier. Sources
+    /// that acknowledge the consumed messages do it here.
+    ///
+    /// It's called between the reads, so the readers waiting for new data return
+    /// from `read` periodically, for example with `ReadResult::FinishedSource`, to
+    /// let the commits through.
+    #[allow(clippy::missing_errors_doc)]
+    fn on_commit(&mut self, _frontier: &OffsetAntichain) -> Result<(), ReadError> {
+        Ok(())
+    }
+
     fn merge_two_frontiers(lhs: &OffsetAntichain, rhs: &OffsetAntichain) -> OffsetAntichain
     where
         Self: Sized,
@@ -325,6 +910,69 @@ pub trait Reader {
                             result.advance_offset(offset_key.clone(), other_value.clone());
                         }
                     }
+                    (
+                        OffsetValue::NatsSequence(offset_position),
+                        OffsetValue::NatsSequence(other_position),
+                    )
+                    | (
+                        OffsetValue::SqliteSnapshot {
+                            snapshots_taken: offset_position,
+                        },
+                        OffsetValue::SqliteSnapshot {
+                            snapshots_taken: other_position,
+                        },
+                    ) => {
+                        if other_position > offset_position {
+                            result.advance_offset(offset_key.clone(), other_value.clone());
+                        }
+                    }
+                    (
+                        OffsetValue::AmqpDeliveryTag {
+                            session: offset_session,
+                            delivery_tag: offset_tag,
+                        },
+                        OffsetValue::AmqpDeliveryTag {
+                            session: other_session,
+                            delivery_tag: other_tag,
+                        },
+                    ) => {
+                        // The tags of different channels can't be compared
+                        if offset_session == other_session && other_tag > offset_tag {
+                            result.advance_offset(offset_key.clone(), other_value.clone());
+                        }
+                    }
+                    (
+                        OffsetValue::MqttMessageSequence {
+                            session: offset_session,
+                            sequence: offset_sequence,
+                        },
+                        OffsetValue::MqttMessageSequence {
+                            session: other_session,
+                            sequence: other_sequence,
+                        },
+                    ) => {
+                        if offset_session == other_session && other_sequence > offset_sequence {
+                            result.advance_offset(offset_key.clone(), other_value.clone());
+                        }
+                    }
+                    (
+                        OffsetValue::PulsarMessageId {
+                            ledger_id: offset_ledger_id,
+                            entry_id: offset_entry_id,
+                            batch_index: offset_batch_index,
+                        },
+                        OffsetValue::PulsarMessageId {
+                            ledger_id: other_ledger_id,
+                            entry_id: other_entry_id,
+                            batch_index: other_batch_index,
+                        },
+                    ) => {
+                        if (other_ledger_id, other_entry_id, other_batch_index)
+                            > (offset_ledger_id, offset_entry_id, offset_batch_index)
+                        {
+                            result.advance_offset(offset_key.clone(), other_value.clone());
+                        }
+                    }
                     (
                         OffsetValue::PythonEntrySequentialId(offset_position),
                         OffsetValue::PythonEntrySequentialId(other_position),
@@ -337,41 +985,136 @@ pub trait Reader {
                         OffsetValue::FilePosition {
                             total_entries_read: offset_line_idx,
                             ..
+                        }
+                        | OffsetValue::RecordPosition {
+                            total_entries_read: offset_line_idx,
+                            ..
                         },
                         OffsetValue::FilePosition {
                             total_entries_read: other_line_idx,
                             ..
+                        }
+                        | OffsetValue::RecordPosition {
+                            total_entries_read: other_line_idx,
+                            ..
                         },
                     )
                     | (
                         OffsetValue::S3ObjectPosition {
                             total_entries_read: offset_line_idx,
                             ..
+                        }
+                        | OffsetValue::S3ObjectPositionV2 {
+                            total_entries_read: offset_line_idx,
+                            ..
                         },
                         OffsetValue::S3ObjectPosition {
                             total_entries_read: other_line_idx,
                             ..
+                        }
+                        | OffsetValue::S3ObjectPositionV2 {
+                            total_entries_read: other_line_idx,
+                            ..
+                        },
+                    )
+                    | (
+                        OffsetValue::AzureBlobPosition {
+                            total_entries_read: offset_line_idx,
+                            ..
+                        },
+                        OffsetValue::AzureBlobPosition {
+                            total_entries_read: other_line_idx,
+                            ..
                         },
                     ) => {
                         if other_line_idx > offset_line_idx {
                             result.advance_offset(offset_key.clone(), other_value.clone());
                         }
                     }
-                    (_, _) => {
-                        error!("Incomparable offsets in the frontier: {offset_value:?} and {other_value:?}");
+                    (
+                        OffsetValue::DeltaTablePosition {
+                            version: offset_version,
+                            rows_read_within_version: offset_rows_read,
+                            ..
+                        },
+                        OffsetValue::DeltaTablePosition {
+                            version: other_version,
+                            rows_read_within_version: other_rows_read,
+                            ..
+                        },
+                    ) => {
+                        if (other_version, other_rows_read) > (offset_version, offset_rows_read) {
+                            result.advance_offset(offset_key.clone(), other_value.clone());
+                        }
                     }
-                },
-                None => result.advance_offset(offset_key.clone(), other_value.clone()),
-            }
-        }
-        result
-    }
-
-    fn storage_type(&self) -> StorageType;
-
-    fn max_allowed_consecutive_errors(&self) -> usize {
-        0
+                    (
+                        OffsetValue::RedisStreamId {
+                            milliseconds: offset_milliseconds,
+                            sequence: offset_sequence,
+                        },
+                        OffsetValue::RedisStreamId {
+                            milliseconds: other_milliseconds,
+                            sequence: other_sequence,
+                        },
+                    ) => {
+                        if (other_milliseconds, other_sequence)
+                            > (offset_milliseconds, offset_sequence)
+                        {
+                            result.advance_offset(offset_key.clone(), other_value.clone());
+                        }
+                    }
+                    (
+                        OffsetValue::HttpCursor {
+                            pages_read: offset_pages_read,
+                            items_read_within_page: offset_items_read,
+                            ..
+                        },
+                        OffsetValue::HttpCursor {
+                            pages_read: other_pages_read,
+                            items_read_within_page: other_items_read,
+                            ..
+                        },
+                    ) => {
+                        if (other_pages_read, other_items_read)
+                            > (offset_pages_read, offset_items_read)
+                        {
+                            result.advance_offset(offset_key.clone(), other_value.clone());
+                        }
+                    }
+                    (_, _) => {
+                        error!("Incomparable offsets in the frontier: {offset_value:?} and {other_value:?}");
+                    }
+                },
+                None => result.advance_offset(offset_key.clone(), other_value.clone()),
+            }
+        }
+        result
+    }
+
+    fn storage_type(&self) -> StorageType;
+
+    fn max_allowed_consecutive_errors(&self) -> usize {
+        0
     }
+
+    /// Returns the moment when the reader last produced data, if the reader
+    /// tracks it. Doesn't block, so it can be used to detect stalled readers.
+    fn last_activity(&self) -> Option<Instant> {
+        None
+    }
+
+    fn is_healthy(&self) -> bool {
+        true
+    }
+}
+
+/// Tells whether a reader waiting for new data since `waiting_since` has to report
+/// the idle commit, as configured by `Reader::set_idle_timeout`.
+pub(crate) fn is_idle_timeout_reached(
+    idle_timeout: Option<Duration>,
+    waiting_since: Instant,
+) -> bool {
+    idle_timeout.is_some_and(|timeout| waiting_since.elapsed() >= timeout)
 }
 
 pub trait ReaderBuilder: Send + 'static {
@@ -449,15 +1192,75 @@ pub enum WriteError {
     #[error(transparent)]
     Arrow(#[from] ArrowError),
 
+    #[error(transparent)]
+    Parquet(#[from] ParquetError),
+
+    #[error(transparent)]
+    Orc(#[from] OrcError),
+
     #[error("type mismatch with delta table schema: got {0} expected {1}")]
     TypeMismatchWithSchema(Value, ArrowDataType),
 
+    #[error("column {0:?} is absent in the delta table")]
+    DeltaTableColumnMissing(String),
+
+    #[error(
+        "column {name:?} of the delta table has type {table_type} while {expected_type} is written"
+    )]
+    DeltaTableColumnTypeMismatch {
+        name: String,
+        table_type: ArrowDataType,
+        expected_type: ArrowDataType,
+    },
+
+    #[error("column {0:?} of the delta table is required but isn't written")]
+    DeltaTableRequiredColumnNotWritten(String),
+
+    #[error(
+        "schema of the delta table doesn't match the written columns: {}",
+        .0.iter().map(ToString::to_string).collect::<Vec<_>>().join("; ")
+    )]
+    DeltaTableSchemaMismatch(Vec<WriteError>),
+
+    #[error("key column {0:?} is absent in the written fields")]
+    DeltaTableKeyColumnMissing(String),
+
+    #[error("upsert mode requires at least one key column")]
+    DeltaTableNoKeyColumns,
+
+    #[error("the deletion flag column can only be written in the append mode")]
+    DeltaTableDeletionFlagInUpsertMode,
+
+    #[error("only one-dimensional arrays can be written, got an array of shape {0:?}")]
+    MultidimensionalArray(Vec<usize>),
+
+    #[error(transparent)]
+    DataFusion(#[from] DataFusionError),
+
     #[error("integer value {0} out of range")]
     IntOutOfRange(i64),
 
+    #[error("time {0} doesn't fit into a 64-bit integer column")]
+    TimeOutOfRange(Timestamp),
+
+    #[error("diff {0} doesn't fit into a 64-bit integer column")]
+    DiffOutOfRange(isize),
+
+    #[error("payload of {0} bytes doesn't fit into a length-prefixed frame")]
+    PayloadTooLarge(usize),
+
     #[error("value {0} can't be used as a key because it's neither 'bytes' nor 'string'")]
     IncorrectKeyFieldType(Value),
 
+    #[error("key field index {index} is out of range for {value_fields_count} value fields")]
+    KeyFieldIndexOutOfRange {
+        index: usize,
+        value_fields_count: usize,
+    },
+
+    #[error("key field of {0} bytes is too long to be a part of a composite key")]
+    KeyFieldTooLong(usize),
+
     #[error("unsupported type: {0:?}")]
     UnsupportedType(Type),
 
@@ -469,6 +1272,168 @@ pub enum WriteError {
 
     #[error("elasticsearch client error: {0:?}")]
     Elasticsearch(elasticsearch::Error),
+
+    #[error("elasticsearch failed to index some of the documents: {0}")]
+    ElasticsearchBulkItemFailed(String),
+
+    #[error("elasticsearch rejected {0} actions because of the overload")]
+    ElasticsearchBulkRejected(usize),
+
+    #[error("document id field {0:?} is absent in the written document")]
+    ElasticsearchMissingIdField(String),
+
+    #[error("failed to perform Sqlite request: {0}")]
+    Sqlite(#[from] SqliteError),
+
+    #[error("value {0} has no representation in Sqlite")]
+    SqliteUnsupportedValue(Value),
+
+    #[error("key column {0:?} is not among the written columns")]
+    SqliteUnknownKeyColumn(String),
+
+    #[error("expected {expected} values per row, got {got}")]
+    SqliteColumnCountMismatch { expected: usize, got: usize },
+
+    #[error(transparent)]
+    Avro(#[from] AvroError),
+
+    #[error("type mismatch with avro schema: got {0} expected {1:?}")]
+    AvroTypeMismatch(Value, Type),
+
+    #[error("NATS error: {0}")]
+    Nats(NatsError),
+
+    #[cfg(feature = "pulsar")]
+    #[error("Pulsar error: {0}")]
+    Pulsar(#[from] PulsarError),
+
+    #[cfg(feature = "amqp")]
+    #[error("AMQP error: {0}")]
+    Amqp(#[from] AmqpError),
+
+    #[cfg(feature = "amqp")]
+    #[error("AMQP broker rejected a published message")]
+    AmqpMessageRejected,
+
+    #[error("Redis error: {0}")]
+    Redis(#[from] RedisError),
+
+    #[error("MQTT client error: {0}")]
+    MqttClient(#[from] MqttClientError),
+
+    #[error("invalid MQTT broker uri: {0}")]
+    MqttOptions(#[from] MqttOptionError),
+
+    #[error(transparent)]
+    Http(#[from] reqwest::Error),
+
+    #[error(transparent)]
+    Json(#[from] serde_json::Error),
+
+    #[error("failed to sign the service account token: {0}")]
+    JsonWebToken(#[from] jsonwebtoken::errors::Error),
+
+    #[error("BigQuery request failed with status {status}: {message}")]
+    BigQueryRequestFailed {
+        status: HttpStatusCode,
+        message: String,
+    },
+
+    #[error("BigQuery rejected {rejected_rows} of {total_rows} rows: {first_error}")]
+    BigQueryRowsRejected {
+        rejected_rows: usize,
+        total_rows: usize,
+        first_error: String,
+    },
+
+    #[cfg(feature = "clickhouse")]
+    #[error(transparent)]
+    ClickHouse(#[from] ClickHouseError),
+
+    #[cfg(feature = "clickhouse")]
+    #[error("type mismatch with ClickHouse column: got {0} expected {1:?}")]
+    ClickHouseTypeMismatch(Value, Type),
+
+    #[cfg(feature = "clickhouse")]
+    #[error("invalid ClickHouse DSN: {0}")]
+    ClickHouseInvalidDsn(String),
+
+    #[error("DynamoDB request failed: {0}")]
+    DynamoDb(Box<DynamoDbError>),
+
+    #[error("failed to build DynamoDB request: {0}")]
+    DynamoDbRequestBuild(#[from] DynamoDbBuildError),
+
+    #[error("DynamoDB left {0} items unprocessed after all retries")]
+    DynamoDbUnprocessedItems(usize),
+
+    #[cfg(feature = "iceberg")]
+    #[error(transparent)]
+    Iceberg(#[from] IcebergError),
+
+    #[cfg(feature = "iceberg")]
+    #[error(transparent)]
+    IcebergArrow(#[from] IcebergArrowError),
+
+    #[cfg(feature = "iceberg")]
+    #[error("type mismatch with Iceberg column: got {0} expected {1:?}")]
+    IcebergTypeMismatch(Value, Type),
+
+    #[cfg(feature = "iceberg")]
+    #[error("column {0:?} is absent in the Iceberg table")]
+    IcebergColumnMissing(String),
+
+    #[cfg(feature = "iceberg")]
+    #[error("column {0:?} of the Iceberg table is required but isn't written")]
+    IcebergRequiredColumnNotWritten(String),
+
+    #[error("Azure Blob Storage error: {0}")]
+    AzureBlob(#[from] AzureError),
+
+    #[error("{} outputs failed: {}", .0.len(), join_errors(.0))]
+    MultipleOutputs(Vec<WriteError>),
+}
+
+fn join_errors(errors: &[WriteError]) -> String {
+    errors
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>()
+        .join("; ")
+}
+
+impl WriteError {
+    /// Tells whether the error may go away if the operation is repeated, as with
+    /// the connectivity problems or an overloaded destination.
+    pub fn is_transient(&self) -> bool {
+        match self {
+            WriteError::Io(_) | WriteError::ElasticsearchBulkRejected(_) => true,
+            WriteError::Kafka(error) => matches!(
+                error.rdkafka_error_code(),
+                Some(
+                    RDKafkaErrorCode::BrokerTransportFailure
+                        | RDKafkaErrorCode::AllBrokersDown
+                        | RDKafkaErrorCode::MessageTimedOut
+                        | RDKafkaErrorCode::OperationTimedOut
+                        | RDKafkaErrorCode::RequestTimedOut
+                        | RDKafkaErrorCode::NetworkException
+                        | RDKafkaErrorCode::QueueFull
+                        | RDKafkaErrorCode::LeaderNotAvailable
+                        | RDKafkaErrorCode::NotLeaderForPartition
+                        | RDKafkaErrorCode::NotEnoughReplicas
+                )
+            ),
+            WriteError::S3(_, error) => is_retriable_s3_error(error),
+            // No status means that the request didn't reach the cluster
+            WriteError::Elasticsearch(error) => error.status_code().map_or(true, |status| {
+                status.is_server_error() || status.as_u16() == 429
+            }),
+            WriteError::Http(error) => error.is_connect() || error.is_timeout(),
+            WriteError::AzureBlob(error) => is_retriable_azure_error(error),
+            WriteError::MultipleOutputs(errors) => errors.iter().all(WriteError::is_transient),
+            _ => false,
+        }
+    }
 }
 
 pub trait Writer: Send {
@@ -478,6 +1443,28 @@ pub trait Writer: Send {
         Ok(())
     }
 
+    /// Called after the engine has committed the output up to `time`, before this
+    /// is recorded as persisted. The sinks that deliver data asynchronously wait
+    /// here until everything written so far is durable, so that the output isn't
+    /// lost if the program is restarted from the committed state.
+    fn on_commit(&mut self, _time: Timestamp) -> Result<(), WriteError> {
+        Ok(())
+    }
+
+    /// Called once the output is over. Everything written so far must be delivered
+    /// before it returns, and unlike a flush from `Drop`, a failure is reported to
+    /// the engine instead of being lost.
+    fn finish(mut self: Box<Self>) -> Result<(), WriteError> {
+        self.flush(true)
+    }
+
+    /// Returns the number of rows made durable by the most recent `flush`, the ones
+    /// committed by the writes preceding it included. The writers that don't track
+    /// the delivered rows report zero.
+    fn rows_written_since_last_flush(&self) -> usize {
+        0
+    }
+
     fn retriable(&self) -> bool {
         false
     }
@@ -501,1530 +1488,2345 @@ pub trait Writer: Send {
     }
 }
 
+/// Returns the headers attached by the message broker writers to the messages
+/// produced for `data`: its time, diff and event type, followed by the values of
+/// `header_fields`. The bytes are passed as they are, while the other values are
+/// formatted.
+pub(crate) fn message_headers<'a>(
+    header_fields: &'a [(String, usize)],
+    data: &FormatterContext,
+) -> Vec<(&'a str, Vec<u8>)> {
+    let mut headers = vec![
+        ("pathway_time", data.time.to_string().into_bytes()),
+        ("pathway_diff", data.diff.to_string().into_bytes()),
+        ("pathway_event", data.event_type.as_str().into()),
+    ];
+    for (name, position) in header_fields {
+        let value = match &data.values[*position] {
+            Value::Bytes(b) => b.to_vec(),
+            other => other.to_string().into_bytes(),
+        };
+        headers.push((name.as_str(), value));
+    }
+    headers
+}
+
+/// Counts the rows made durable by a writer. The rows committed between two flushes
+/// are attributed to the later one.
+#[derive(Debug, Default)]
+pub(crate) struct WrittenRowsCounter {
+    pending: usize,
+    last_flush: usize,
+}
+
+impl WrittenRowsCounter {
+    pub(crate) fn add(&mut self, rows: usize) {
+        self.pending += rows;
+    }
+
+    pub(crate) fn on_flush(&mut self) {
+        self.last_flush = take(&mut self.pending);
+    }
+
+    pub(crate) fn last_flush(&self) -> usize {
+        self.last_flush
+    }
+}
+
+/// Defines how the payloads are separated from each other in a file.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum PayloadFraming {
+    /// Each payload is followed by a newline, which suits the text formats.
+    #[default]
+    Newline,
+
+    /// Each payload is preceded by its length as a 4-byte little-endian integer, so
+    /// that binary payloads, such as `MessagePack` ones, can contain any bytes. Such
+    /// files are read with `ReadMethod::LengthPrefixed`.
+    LengthPrefixed,
+}
+
+impl PayloadFraming {
+    fn write_payload(
+        self,
+        writer: &mut dyn Write,
+        prefix: Option<&[u8]>,
+        payload: &[u8],
+    ) -> Result<(), WriteError> {
+        let prefix = prefix.unwrap_or_default();
+        match self {
+            PayloadFraming::Newline => {
+                writer.write_all(prefix)?;
+                writer.write_all(payload)?;
+                writer.write_all(b"\n")?;
+            }
+            PayloadFraming::LengthPrefixed => {
+                let length = prefix.len() + payload.len();
+                let length =
+                    u32::try_from(length).map_err(|_| WriteError::PayloadTooLarge(length))?;
+                writer.write_all(&length.to_le_bytes())?;
+                writer.write_all(prefix)?;
+                writer.write_all(payload)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Writes the payloads into a file.
+///
+/// A flush only passes the buffered data to the OS, so it can still be lost if the
+/// machine crashes. If `fsync_on_flush` is set, the forced flushes and the commits
+/// also wait until the file is synced to the disk. This makes the committed output
+/// durable, but each sync costs a round trip to the disk, so the throughput drops.
 pub struct FileWriter {
     writer: BufWriter<std::fs::File>,
+    framing: PayloadFraming,
+
+    // If set, the payloads of the deletion events are prefixed with it
+    tombstone_marker: Option<Vec<u8>>,
+    fsync_on_flush: bool,
 }
 
 impl FileWriter {
-    pub fn new(writer: BufWriter<std::fs::File>) -> FileWriter {
-        FileWriter { writer }
+    pub fn new(
+        writer: BufWriter<std::fs::File>,
+        framing: PayloadFraming,
+        tombstone_marker: Option<Vec<u8>>,
+        fsync_on_flush: bool,
+    ) -> FileWriter {
+        FileWriter {
+            writer,
+            framing,
+            tombstone_marker,
+            fsync_on_flush,
+        }
+    }
+
+    fn sync_to_disk(&mut self) -> Result<(), WriteError> {
+        self.writer.flush()?;
+        if self.fsync_on_flush {
+            self.writer.get_ref().sync_all()?;
+        }
+        Ok(())
     }
 }
 
 #[derive(Clone, Copy, Debug, Eq, PartialEq)]
 pub enum ReadMethod {
     ByLine,
+    /// Same as `ByLine`, but the `\n` or `\r\n` terminator isn't included into the
+    /// entries, so the last line looks the same whether or not the file ends with it.
+    ByLineWithoutTerminator,
     Full,
+    JsonObjects,
+    LengthPrefixed,
 }
 
 impl ReadMethod {
-    fn read_next_bytes<R>(self, reader: &mut R, buf: &mut Vec<u8>) -> Result<usize, ReadError>
+    fn is_line_based(self) -> bool {
+        matches!(
+            self,
+            ReadMethod::ByLine | ReadMethod::ByLineWithoutTerminator
+        )
+    }
+
+    /// Reads the next record into `buf`, returning the number of consumed bytes.
+    ///
+    /// If `max_record_bytes` is given, a record consuming more bytes than that,
+    /// including its terminator, results in `ReadError::RecordTooLarge`, and no more
+    /// than one byte past the limit is read into memory. For the full reads, the
+    /// limit applies to the whole file.
+    pub(crate) fn read_next_bytes<R>(
+        self,
+        reader: &mut R,
+        buf: &mut Vec<u8>,
+        max_record_bytes: Option<usize>,
+    ) -> Result<usize, ReadError>
+    where
+        R: BufRead,
+    {
+        let Some(max_record_bytes) = max_record_bytes else {
+            return self.read_next_record(reader, buf, None);
+        };
+        // A byte past the limit is enough to tell that the record exceeds it
+        let mut limited_reader = reader.take((max_record_bytes as u64).saturating_add(1));
+        let result = self.read_next_record(&mut limited_reader, buf, Some(max_record_bytes));
+        if limited_reader.limit() == 0 {
+            return Err(ReadError::RecordTooLarge(max_record_bytes));
+        }
+        result
+    }
+
+    fn read_next_record<R>(
+        self,
+        reader: &mut R,
+        buf: &mut Vec<u8>,
+        max_record_bytes: Option<usize>,
+    ) -> Result<usize, ReadError>
     where
         R: BufRead,
     {
         match &self {
             ReadMethod::ByLine => Ok(reader.read_until(b'\n', buf)?),
+            ReadMethod::ByLineWithoutTerminator => {
+                Self::read_next_line_without_terminator(reader, buf)
+            }
             ReadMethod::Full => Ok(reader.read_to_end(buf)?),
+            ReadMethod::JsonObjects => Self::read_next_json_value(reader, buf),
+            ReadMethod::LengthPrefixed => Self::read_next_frame(reader, buf, max_record_bytes),
         }
     }
-}
-
-pub struct FilesystemReader {
-    persistent_id: Option<PersistentId>,
-    read_method: ReadMethod,
-
-    reader: Option<BufReader<std::fs::File>>,
-    filesystem_scanner: FilesystemScanner,
-    total_entries_read: u64,
-    deferred_read_result: Option<ReadResult>,
-}
-
-impl FilesystemReader {
-    pub fn new(
-        path: &str,
-        streaming_mode: ConnectorMode,
-        persistent_id: Option<PersistentId>,
-        read_method: ReadMethod,
-        object_pattern: &str,
-    ) -> Result<FilesystemReader, ReadError> {
-        let filesystem_scanner =
-            FilesystemScanner::new(path, persistent_id, streaming_mode, object_pattern)?;
-
-        Ok(Self {
-            persistent_id,
-
-            reader: None,
-            filesystem_scanner,
-            total_entries_read: 0,
-            read_method,
-            deferred_read_result: None,
-        })
-    }
-}
 
-impl Reader for FilesystemReader {
-    fn seek(&mut self, frontier: &OffsetAntichain) -> Result<(), ReadError> {
-        let offset_value = frontier.get_offset(&OffsetKey::Empty);
-        let Some(OffsetValue::FilePosition {
-            total_entries_read,
-            path: file_path_arc,
-            bytes_offset,
-        }) = offset_value
-        else {
-            if offset_value.is_some() {
-                warn!("Incorrect type of offset value in Filesystem frontier: {offset_value:?}");
+    /// Reads a line into `buf`, leaving out its terminator. Returns the number of
+    /// consumed bytes, which includes the terminator, so that the offsets are the same
+    /// as with `ByLine`.
+    fn read_next_line_without_terminator<R>(
+        reader: &mut R,
+        buf: &mut Vec<u8>,
+    ) -> Result<usize, ReadError>
+    where
+        R: BufRead,
+    {
+        let line_start = buf.len();
+        let consumed = reader.read_until(b'\n', buf)?;
+        if buf[line_start..].ends_with(b"\n") {
+            buf.pop();
+            if buf[line_start..].ends_with(b"\r") {
+                buf.pop();
             }
-            return Ok(());
-        };
-        // Filesystem scanner part: detect already processed file
-        self.filesystem_scanner
-            .seek_to_file(file_path_arc.as_path())?;
+        }
+        Ok(consumed)
+    }
 
-        // Seek within a particular file
-        self.reader = {
-            let file = File::open(file_path_arc.as_path())?;
-            let mut reader = BufReader::new(file);
-            reader.seek(SeekFrom::Start(*bytes_offset))?;
-            Some(reader)
-        };
-        self.total_entries_read = *total_entries_read;
+    /// Reads a payload preceded by its length as a 4-byte little-endian integer, as
+    /// written with `PayloadFraming::LengthPrefixed`. Only the payload is stored into
+    /// `buf`. Returns the number of consumed bytes, or zero if there are no more
+    /// frames.
+    ///
+    /// The frame is rejected before its payload is allocated if it's longer than
+    /// `max_record_bytes`.
+    fn read_next_frame<R>(
+        reader: &mut R,
+        buf: &mut Vec<u8>,
+        max_record_bytes: Option<usize>,
+    ) -> Result<usize, ReadError>
+    where
+        R: BufRead,
+    {
+        if reader.fill_buf()?.is_empty() {
+            return Ok(0);
+        }
 
-        Ok(())
+        let mut length_bytes = [0; 4];
+        reader.read_exact(&mut length_bytes)?;
+        let length = u32::from_le_bytes(length_bytes) as usize;
+        if let Some(max_record_bytes) = max_record_bytes {
+            if length_bytes.len() + length > max_record_bytes {
+                return Err(ReadError::RecordTooLarge(max_record_bytes));
+            }
+        }
+        let payload_start = buf.len();
+        buf.resize(payload_start + length, 0);
+        reader.read_exact(&mut buf[payload_start..])?;
+        Ok(length_bytes.len() + length)
     }
 
-    fn read(&mut self) -> Result<ReadResult, ReadError> {
-        if let Some(deferred_read_result) = self.deferred_read_result.take() {
-            return Ok(deferred_read_result);
-        }
+    /// Reads a single top-level JSON value into `buf`, keeping track of the nesting
+    /// and of the string literals, so that the newlines within a value don't split it.
+    /// The whitespace around the value is consumed but not stored. Returns the number
+    /// of consumed bytes, or zero if there are no more values.
+    fn read_next_json_value<R>(reader: &mut R, buf: &mut Vec<u8>) -> Result<usize, ReadError>
+    where
+        R: BufRead,
+    {
+        let mut consumed = 0;
+        let mut depth: usize = 0;
+        let mut value_started = false;
+        let mut value_finished = false;
+        let mut in_string = false;
+        let mut escaped = false;
 
         loop {
-            if let Some(reader) = &mut self.reader {
-                let mut line = Vec::new();
-                let len = self.read_method.read_next_bytes(reader, &mut line)?;
-                if len > 0 || self.read_method == ReadMethod::Full {
-                    self.total_entries_read += 1;
-
-                    let offset = (
-                        OffsetKey::Empty,
-                        OffsetValue::FilePosition {
-                            total_entries_read: self.total_entries_read,
-                            path: self
-                                .filesystem_scanner
-                                .current_offset_file()
-                                .clone()
-                                .unwrap(),
-                            bytes_offset: reader.stream_position().unwrap(),
-                        },
-                    );
-                    let data_event_type = self
-                        .filesystem_scanner
-                        .data_event_type()
-                        .expect("scanner action can't be empty");
+            let available = reader.fill_buf()?;
+            if available.is_empty() {
+                break;
+            }
 
-                    if self.read_method == ReadMethod::Full {
-                        self.deferred_read_result = Some(ReadResult::FinishedSource {
-                            commit_allowed: !self.filesystem_scanner.has_planned_insertion(),
-                        });
-                        self.reader = None;
+            let mut used = 0;
+            let mut next_value_reached = false;
+            for &byte in available {
+                if value_finished {
+                    if !byte.is_ascii_whitespace() {
+                        next_value_reached = true;
+                        break;
                     }
-
-                    return Ok(ReadResult::Data(
-                        ReaderContext::from_raw_bytes(data_event_type, line),
-                        offset,
-                    ));
+                    used += 1;
+                    continue;
                 }
+                used += 1;
 
-                self.reader = None;
-                return Ok(ReadResult::FinishedSource {
-                    commit_allowed: !self.filesystem_scanner.has_planned_insertion(),
-                });
-            }
+                if !value_started {
+                    if byte.is_ascii_whitespace() {
+                        continue;
+                    }
+                    value_started = true;
+                }
 
-            let next_read_result = self.filesystem_scanner.next_action_determined()?;
-            if let Some(next_read_result) = next_read_result {
-                if let Some(selected_file) = self.filesystem_scanner.current_file() {
-                    let file = File::open(&*selected_file)?;
-                    self.reader = Some(BufReader::new(file));
+                if in_string {
+                    if escaped {
+                        escaped = false;
+                    } else if byte == b'\\' {
+                        escaped = true;
+                    } else if byte == b'"' {
+                        in_string = false;
+                        value_finished = depth == 0;
+                    }
+                    buf.push(byte);
+                    continue;
                 }
-                return Ok(next_read_result);
+
+                match byte {
+                    b'"' => in_string = true,
+                    b'{' | b'[' => depth += 1,
+                    b'}' | b']' => {
+                        depth = depth.saturating_sub(1);
+                        value_finished = depth == 0;
+                    }
+                    _ if depth == 0 && byte.is_ascii_whitespace() => {
+                        // The end of a top-level scalar, such as a number
+                        value_finished = true;
+                        continue;
+                    }
+                    _ => {}
+                }
+                buf.push(byte);
             }
 
-            if self.filesystem_scanner.is_polling_enabled() {
-                self.filesystem_scanner.wait_for_new_files();
-            } else {
-                return Ok(ReadResult::Finished);
+            reader.consume(used);
+            consumed += used;
+            if next_value_reached {
+                break;
             }
         }
-    }
 
-    fn persistent_id(&self) -> Option<PersistentId> {
-        self.persistent_id
+        Ok(if value_started { consumed } else { 0 })
     }
+}
 
-    fn update_persistent_id(&mut self, persistent_id: Option<PersistentId>) {
-        self.persistent_id = persistent_id;
-    }
+/// Defines where a file reader starts reading the files present when it starts.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum FileStartPosition {
+    #[default]
+    Beginning,
 
-    fn storage_type(&self) -> StorageType {
-        StorageType::FileSystem
-    }
+    /// Only the data appended to these files after the start is read. The files that
+    /// appear later are read from the beginning, since all their data is new.
+    ///
+    /// When the uncompressed files are read by lines, the reading starts after the
+    /// last line terminator, so a line that was incomplete at the start is read in
+    /// full once it's finished.
+    End,
 }
 
-impl Writer for FileWriter {
-    fn write(&mut self, data: FormatterContext) -> Result<(), WriteError> {
-        for payload in &data.payloads {
-            self.writer.write_all(payload)?;
-            self.writer.write_all(b"\n")?;
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum CompressionFormat {
+    None,
+    Gzip,
+    Zstd,
+}
+
+impl CompressionFormat {
+    pub fn from_path(path: impl AsRef<Path>) -> Self {
+        match path.as_ref().extension().and_then(OsStr::to_str) {
+            Some("gz") => Self::Gzip,
+            Some("zst" | "zstd") => Self::Zstd,
+            _ => Self::None,
         }
-        Ok(())
     }
 
-    fn flush(&mut self, _forced: bool) -> Result<(), WriteError> {
-        self.writer.flush()?;
-        Ok(())
+    /// Returns the explicitly configured format or, if there is none, the one
+    /// detected from the extension of the object being read.
+    pub(crate) fn resolve(compression: Option<Self>, path: impl AsRef<Path>) -> Self {
+        compression.unwrap_or_else(|| Self::from_path(path))
     }
-}
 
-pub struct KafkaReader {
-    consumer: BaseConsumer<DefaultConsumerContext>,
-    persistent_id: Option<PersistentId>,
-    topic: Arc<String>,
-    positions_for_seek: HashMap<i32, i64>,
+    pub(crate) fn decoder<R>(self, reader: R) -> Result<Box<dyn Read + Send>, ReadError>
+    where
+        R: Read + Send + 'static,
+    {
+        let decoder: Box<dyn Read + Send> = match self {
+            Self::None => Box::new(reader),
+            Self::Gzip => Box::new(MultiGzDecoder::new(reader)),
+            Self::Zstd => Box::new(ZstdDecoder::new(reader)?),
+        };
+        Ok(decoder)
+    }
 }
 
-impl Reader for KafkaReader {
-    fn read(&mut self) -> Result<ReadResult, ReadError> {
-        loop {
-            let kafka_message = self
-                .consumer
-                .poll(Timeout::Never)
-                .expect("poll should never timeout")?;
-            let message_key = kafka_message.key().map(<[u8]>::to_vec);
-            let message_payload = kafka_message.payload().map(<[u8]>::to_vec);
-
-            if let Some(last_read_offset) = self.positions_for_seek.get(&kafka_message.partition())
-            {
-                if last_read_offset >= &kafka_message.offset() {
-                    if let Err(e) = self.consumer.seek(
-                        kafka_message.topic(),
-                        kafka_message.partition(),
-                        KafkaOffset::Offset(*last_read_offset + 1),
-                        None,
-                    ) {
-                        error!(
-                            "Failed to seek topic and partition ({}, {}) to offset {}: {e}",
-                            kafka_message.topic(),
-                            kafka_message.partition(),
-                            *last_read_offset + 1
-                        );
-                    }
-                    continue;
-                }
-                self.positions_for_seek.remove(&kafka_message.partition());
-            }
+/// Defines what happens to the byte sequences that aren't valid in the encoding of
+/// the text being read.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum InvalidBytesPolicy {
+    /// Each invalid sequence is replaced with U+FFFD.
+    #[default]
+    Replace,
 
-            let offset = {
-                let offset_key = OffsetKey::Kafka(self.topic.clone(), kafka_message.partition());
-                let offset_value = OffsetValue::KafkaOffset(kafka_message.offset());
-                (offset_key, offset_value)
-            };
-            let message = ReaderContext::from_key_value(message_key, message_payload);
+    /// The reading fails with `ReadError::InvalidEncodedText`.
+    Error,
+}
+
+/// Encoding of the text files, which are transcoded into UTF-8 before they are
+/// parsed.
+///
+/// Only the encodings compatible with ASCII are supported, so that the line
+/// terminators and the delimiters are found in the raw bytes, and the offsets keep
+/// referring to the positions within the original files.
+#[derive(Clone, Copy, Debug)]
+pub struct TextEncoding {
+    encoding: &'static Encoding,
+    on_invalid_bytes: InvalidBytesPolicy,
+}
 
-            return Ok(ReadResult::Data(message, offset));
+impl TextEncoding {
+    /// Looks up the encoding by one of its WHATWG labels, such as `"latin1"` or
+    /// `"windows-1252"`.
+    pub fn new(label: &str, on_invalid_bytes: InvalidBytesPolicy) -> Result<Self, ReadError> {
+        let encoding = Encoding::for_label(label.as_bytes())
+            .ok_or_else(|| ReadError::UnknownEncoding(label.to_string()))?;
+        if !encoding.is_ascii_compatible() {
+            return Err(ReadError::UnsupportedEncoding(encoding.name()));
         }
+        Ok(Self {
+            encoding,
+            on_invalid_bytes,
+        })
     }
 
-    fn seek(&mut self, frontier: &OffsetAntichain) -> Result<(), ReadError> {
-        // "Lazy" seek implementation
-        for (offset_key, offset_value) in frontier {
-            let OffsetValue::KafkaOffset(position) = offset_value else {
-                warn!("Unexpected type of offset in Kafka frontier: {offset_value:?}");
-                continue;
-            };
-            if let OffsetKey::Kafka(topic, partition) = offset_key {
-                if self.topic != *topic {
-                    warn!(
-                        "Unexpected topic name. Expected: {}, Got: {topic}",
-                        *self.topic
-                    );
-                    continue;
-                }
-
-                /*
-                    Note: we can't do seek straight away, because it works only for
-                    assigned partitions.
+    pub fn name(&self) -> &'static str {
+        self.encoding.name()
+    }
 
-                    We also don't do any kind of assignment here, because it needs
-                    to be done on behalf of rdkafka client, taking account of other
-                    members in its' consumer group.
-                */
-                self.positions_for_seek.insert(*partition, *position);
-            } else {
-                error!("Unexpected offset in Kafka frontier: ({offset_key:?}, {offset_value:?})");
-            }
+    /// Decodes `bytes` into a string. The byte order mark isn't treated specially,
+    /// since the decoded chunks are usually taken from the middle of a file.
+    pub fn decode<'a>(&self, bytes: &'a [u8]) -> Result<Cow<'a, str>, ReadError> {
+        match self.on_invalid_bytes {
+            InvalidBytesPolicy::Replace => Ok(self.encoding.decode_without_bom_handling(bytes).0),
+            InvalidBytesPolicy::Error => self
+                .encoding
+                .decode_without_bom_handling_and_without_replacement(bytes)
+                .ok_or(ReadError::InvalidEncodedText(self.encoding.name())),
         }
-
-        Ok(())
     }
 
-    fn persistent_id(&self) -> Option<PersistentId> {
-        self.persistent_id
+    /// Same as [`TextEncoding::decode`], but returns the UTF-8 bytes, reusing `bytes`
+    /// if they don't change.
+    pub fn transcode(&self, bytes: Vec<u8>) -> Result<Vec<u8>, ReadError> {
+        let transcoded = match self.decode(&bytes)? {
+            Cow::Borrowed(_) => None,
+            Cow::Owned(text) => Some(text.into_bytes()),
+        };
+        Ok(transcoded.unwrap_or(bytes))
     }
+}
 
-    fn update_persistent_id(&mut self, persistent_id: Option<PersistentId>) {
-        self.persistent_id = persistent_id;
-    }
+/// Kind of the offset a file, S3 or Azure Blob Storage reader stores in its frontier.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum SeekOffsetKind {
+    FilePosition,
+    S3ObjectPosition,
+    AzureBlobPosition,
+}
 
-    fn storage_type(&self) -> StorageType {
-        StorageType::Kafka
-    }
+/// Position within a file or an S3 object, which the reader resumes from.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SeekPosition<'a> {
+    pub offset_value: &'a OffsetValue,
+    pub total_entries_read: u64,
+    pub path: Arc<PathBuf>,
+    pub bytes_offset: u64,
 
-    fn max_allowed_consecutive_errors(&self) -> usize {
-        32
-    }
+    // Only the S3 objects and the Azure blobs have it
+    pub last_modified: Option<i64>,
 }
 
-impl KafkaReader {
-    pub fn new(
-        consumer: BaseConsumer<DefaultConsumerContext>,
-        topic: String,
-        persistent_id: Option<PersistentId>,
-    ) -> KafkaReader {
-        KafkaReader {
-            consumer,
-            persistent_id,
-            topic: Arc::new(topic),
-            positions_for_seek: HashMap::new(),
+/// Extracts the position the reader should resume from. If the frontier holds an
+/// offset of another kind, it's reported and ignored, so that the seek does nothing,
+/// as it does for an empty frontier.
+pub fn extract_seek_position(
+    frontier: &OffsetAntichain,
+    expected_kind: SeekOffsetKind,
+) -> Option<SeekPosition<'_>> {
+    let offset_value = frontier.get_offset(&OffsetKey::Empty)?;
+    match (expected_kind, offset_value) {
+        (
+            SeekOffsetKind::FilePosition,
+            OffsetValue::FilePosition {
+                total_entries_read,
+                path,
+                bytes_offset,
+            },
+        ) => Some(SeekPosition {
+            offset_value,
+            total_entries_read: *total_entries_read,
+            path: path.clone(),
+            bytes_offset: *bytes_offset,
+            last_modified: None,
+        }),
+        (
+            SeekOffsetKind::S3ObjectPosition,
+            OffsetValue::S3ObjectPosition {
+                total_entries_read,
+                path,
+                bytes_offset,
+            },
+        ) => Some(SeekPosition {
+            offset_value,
+            total_entries_read: *total_entries_read,
+            path: Arc::new(PathBuf::from(path.as_str())),
+            bytes_offset: *bytes_offset,
+            last_modified: None,
+        }),
+        (
+            SeekOffsetKind::S3ObjectPosition,
+            OffsetValue::S3ObjectPositionV2 {
+                total_entries_read,
+                path,
+                bytes_offset,
+                last_modified,
+            },
+        )
+        | (
+            SeekOffsetKind::AzureBlobPosition,
+            OffsetValue::AzureBlobPosition {
+                total_entries_read,
+                path,
+                bytes_offset,
+                last_modified,
+            },
+        ) => Some(SeekPosition {
+            offset_value,
+            total_entries_read: *total_entries_read,
+            path: Arc::new(PathBuf::from(path.as_str())),
+            bytes_offset: *bytes_offset,
+            last_modified: *last_modified,
+        }),
+        _ => {
+            warn!("Incorrect type of offset value in frontier: expected {expected_kind:?}, got {offset_value:?}");
+            None
         }
     }
 }
 
-#[derive(Debug)]
-enum PosixScannerAction {
-    Read(Arc<PathBuf>),
-    Delete(Arc<PathBuf>),
+/// Returns the record with the metadata of the file a new source refers to, with the
+/// same event type as the contents of the file. The readers emit it right after the
+/// new source, if they're asked to. The offset of the record points at the start of
+/// the contents, so it isn't emitted again if the reading is resumed from there.
+///
+/// The record is a diff with the fields of [`SourceMetadata`], so the parser must
+/// accept such records.
+fn source_metadata_record(
+    filesystem_scanner: &FilesystemScanner,
+    new_source: &ReadResult,
+    total_entries_read: u64,
+    bytes_offset: u64,
+) -> Option<ReadResult> {
+    let ReadResult::NewSource(Some(metadata)) = new_source else {
+        return None;
+    };
+    let offset = (
+        OffsetKey::Empty,
+        OffsetValue::FilePosition {
+            total_entries_read,
+            path: filesystem_scanner.current_offset_file()?,
+            bytes_offset,
+        },
+    );
+    let context = ReaderContext::from_diff(
+        filesystem_scanner.data_event_type()?,
+        None,
+        metadata.fields(),
+    );
+    Some(ReadResult::Data(context, offset))
 }
 
-#[derive(Debug)]
-struct FilesystemScanner {
-    path: GlobPattern,
-    cache_directory_path: Option<PathBuf>,
-    streaming_mode: ConnectorMode,
-    object_pattern: String,
-
-    // Mapping from the path of the loaded file to its modification timestamp
-    known_files: HashMap<PathBuf, u64>,
+/// Returns the position right after the last `\n` in the file, or zero if there is
+/// none. The file is scanned backwards from its end in chunks.
+fn last_line_end(path: &Path) -> Result<u64, ReadError> {
+    const CHUNK_SIZE: u64 = 64 * 1024;
+
+    let mut file = File::open(path)?;
+    let mut chunk_end = file.seek(SeekFrom::End(0))?;
+    let mut chunk = Vec::new();
+    while chunk_end > 0 {
+        let chunk_start = chunk_end.saturating_sub(CHUNK_SIZE);
+        file.seek(SeekFrom::Start(chunk_start))?;
+        chunk.clear();
+        (&mut file)
+            .take(chunk_end - chunk_start)
+            .read_to_end(&mut chunk)?;
+        if let Some(position) = chunk.iter().rposition(|byte| *byte == b'\n') {
+            return Ok(chunk_start + position as u64 + 1);
+        }
+        chunk_end = chunk_start;
+    }
+    Ok(0)
+}
 
-    current_action: Option<PosixScannerAction>,
-    cached_modify_times: HashMap<PathBuf, Option<SystemTime>>,
-    inotify: Option<inotify_support::Inotify>,
-    next_file_for_insertion: Option<PathBuf>,
-    cached_metadata: HashMap<PathBuf, Option<SourceMetadata>>,
+pub struct FilesystemReader {
+    persistent_id: Option<PersistentId>,
+    read_method: ReadMethod,
+    compression: Option<CompressionFormat>,
+    encoding: Option<TextEncoding>,
+    max_record_bytes: Option<usize>,
 
-    // Storage is deleted on object destruction, so we need to store it
-    // for the connector's life time
-    _connector_tmp_storage: Option<TempDir>,
+    reader: Option<BufReader<Box<dyn Read + Send>>>,
+    filesystem_scanner: FilesystemScanner,
+    total_entries_read: u64,
+    current_bytes_read: u64,
+    deferred_read_result: Option<ReadResult>,
+    max_consecutive_errors: usize,
+    last_activity: Option<Instant>,
+    shutdown_signal: ShutdownSignal,
+    idle_timeout: Option<Duration>,
+    emit_metadata_record: bool,
+    pending_metadata_record: Option<ReadResult>,
+    skip_empty_files: bool,
+
+    // The compressed files are tracked by the records rather than by the bytes
+    is_current_file_compressed: bool,
+    entries_read_within_file: u64,
 }
 
-impl FilesystemScanner {
-    fn new(
-        path: &str,
-        persistent_id: Option<PersistentId>,
-        streaming_mode: ConnectorMode,
-        object_pattern: &str,
-    ) -> Result<FilesystemScanner, ReadError> {
-        let path_glob = GlobPattern::new(path)?;
+/// Optional settings of `FilesystemReader`. By default, all the files in the
+/// directories matching the globs are read from the beginning, the full reads
+/// skip the empty files, and there's no limit on the size of an entry.
+#[derive(Clone, Debug)]
+pub struct FilesystemReaderSettings {
+    /// The pattern the files within the matched directories must match.
+    pub object_pattern: String,
+    pub refresh_interval: Option<Duration>,
+
+    /// The compression of the files, detected by their extensions if not given.
+    /// The offsets within the compressed files are `OffsetValue::RecordPosition`,
+    /// so the reading of such a file is resumed by skipping the records read before.
+    pub compression: Option<CompressionFormat>,
+    pub max_consecutive_errors: usize,
+
+    /// A hint for the case when there is no persisted offset: if the reader resumes
+    /// from one, all the files that weren't processed before are read from the
+    /// beginning. With the full reads, every entry is the whole file, so the files
+    /// are always read from the beginning.
+    pub start_position: FileStartPosition,
+
+    /// Whether a diff record with the metadata of each file precedes its contents.
+    pub emit_metadata_record: bool,
+
+    /// Whether the full reads produce no entry for a zero-byte file, in which case
+    /// only the end of its source is reported. It has no effect on the other read
+    /// methods, since an empty file has no lines to produce entries from anyway.
+    pub skip_empty_files: bool,
+
+    /// The encoding each entry is transcoded from into UTF-8, while the offsets
+    /// still count the bytes of the original files.
+    pub encoding: Option<TextEncoding>,
+
+    /// The size, above which an entry fails the reading with
+    /// `ReadError::RecordTooLarge` instead of being loaded into memory. It's
+    /// recommended to set one, such as 64 MiB, when the input may be malformed,
+    /// since otherwise a file without line breaks is read into memory as a whole.
+    pub max_record_bytes: Option<usize>,
+
+    /// How the cached copies of the files are named.
+    pub cache_key_strategy: CacheKeyStrategy,
+
+    /// The directory the copies of the files, which are needed to emit their
+    /// deletions, are cached in. If not given, the one from the
+    /// `PATHWAY_PERSISTENT_STORAGE` environment variable is used.
+    pub cache_directory: Option<PathBuf>,
+}
 
-        // Alternative solution here is to do inotify_support::subscribe_inotify(path)
-        // if streaming mode allows polling.
-        let inotify = None;
+impl Default for FilesystemReaderSettings {
+    fn default() -> Self {
+        Self {
+            object_pattern: "*".to_string(),
+            refresh_interval: None,
+            compression: None,
+            max_consecutive_errors: 0,
+            start_position: FileStartPosition::default(),
+            emit_metadata_record: false,
+            skip_empty_files: true,
+            encoding: None,
+            max_record_bytes: None,
+            cache_key_strategy: CacheKeyStrategy::default(),
+            cache_directory: None,
+        }
+    }
+}
 
-        let (cache_directory_path, connector_tmp_storage) = {
-            if streaming_mode.are_deletions_enabled() {
-                if let Ok(root_dir_str_path) = env::var("PATHWAY_PERSISTENT_STORAGE") {
-                    let root_dir_path = Path::new(&root_dir_str_path);
-                    ensure_directory(root_dir_path)?;
-                    let unique_id =
-                        persistent_id.unwrap_or_else(|| rand::thread_rng().gen::<u128>());
-                    let connector_tmp_directory = root_dir_path.join(format!("cache-{unique_id}"));
-                    ensure_directory(&connector_tmp_directory)?;
-                    (Some(connector_tmp_directory), None)
-                } else {
-                    let cache_tmp_storage = tempdir()?;
-                    let connector_tmp_directory = cache_tmp_storage.path();
-                    (
-                        Some(connector_tmp_directory.to_path_buf()),
-                        Some(cache_tmp_storage),
-                    )
-                }
-            } else {
-                (None, None)
-            }
+impl FilesystemReader {
+    /// Creates a reader of the files matching any of the globs in `paths`, configured
+    /// by `settings`.
+    pub fn new(
+        paths: &[&str],
+        streaming_mode: ConnectorMode,
+        persistent_id: Option<PersistentId>,
+        read_method: ReadMethod,
+        settings: FilesystemReaderSettings,
+    ) -> Result<FilesystemReader, ReadError> {
+        let FilesystemReaderSettings {
+            object_pattern,
+            refresh_interval,
+            compression,
+            max_consecutive_errors,
+            start_position,
+            emit_metadata_record,
+            skip_empty_files,
+            encoding,
+            max_record_bytes,
+            cache_key_strategy,
+            cache_directory,
+        } = settings;
+        let start_position = if read_method == ReadMethod::Full {
+            FileStartPosition::Beginning
+        } else {
+            start_position
         };
+        let filesystem_scanner = FilesystemScanner::new(
+            paths,
+            persistent_id,
+            streaming_mode,
+            &object_pattern,
+            refresh_interval,
+            start_position,
+            Arc::new(SystemClock),
+            cache_key_strategy,
+            cache_directory.as_deref(),
+        )?;
 
         Ok(Self {
-            path: path_glob,
-            streaming_mode,
-            cache_directory_path,
+            persistent_id,
 
-            object_pattern: object_pattern.to_string(),
-            known_files: HashMap::new(),
-            current_action: None,
-            cached_modify_times: HashMap::new(),
-            inotify,
-            next_file_for_insertion: None,
-            cached_metadata: HashMap::new(),
-            _connector_tmp_storage: connector_tmp_storage,
+            reader: None,
+            filesystem_scanner,
+            total_entries_read: 0,
+            current_bytes_read: 0,
+            read_method,
+            compression,
+            encoding,
+            max_record_bytes,
+            deferred_read_result: None,
+            max_consecutive_errors,
+            last_activity: None,
+            shutdown_signal: ShutdownSignal::default(),
+            idle_timeout: None,
+            emit_metadata_record,
+            pending_metadata_record: None,
+            skip_empty_files,
+            is_current_file_compressed: false,
+            entries_read_within_file: 0,
         })
     }
 
-    fn has_planned_insertion(&self) -> bool {
-        self.next_file_for_insertion.is_some()
-    }
-
-    fn is_polling_enabled(&self) -> bool {
-        self.streaming_mode.is_polling_enabled()
-    }
-
-    fn data_event_type(&self) -> Option<DataEventType> {
-        self.current_action
-            .as_ref()
-            .map(|current_action| match current_action {
-                PosixScannerAction::Read(_) => DataEventType::Insert,
-                PosixScannerAction::Delete(_) => DataEventType::Delete,
-            })
-    }
-
-    /// Returns the actual file path, which needs to be read
-    /// It is either a path to the file in the input directory, or a path to the file
-    /// which is saved in cache
-    fn current_file(&self) -> Option<Arc<PathBuf>> {
-        match &self.current_action {
-            Some(PosixScannerAction::Read(path)) => Some(path.clone()),
-            Some(PosixScannerAction::Delete(path)) => self.cached_file_path(path).map(Arc::new),
-            None => None,
+    fn is_skipped_empty_file(&self, path: &Path) -> Result<bool, ReadError> {
+        Ok(self.skip_empty_files
+            && self.read_method == ReadMethod::Full
+            && std::fs::metadata(path)?.len() == 0)
+    }
+
+    fn open_file(&mut self, path: &Path) -> Result<BufReader<Box<dyn Read + Send>>, ReadError> {
+        let file = File::open(path)?;
+        let compression = CompressionFormat::resolve(self.compression, path);
+        self.is_current_file_compressed = compression != CompressionFormat::None;
+        self.entries_read_within_file = 0;
+        self.current_bytes_read = 0;
+        Ok(BufReader::new(compression.decoder(file)?))
+    }
+
+    /// Reads the next entry of the current file into `entry`, returning its length.
+    fn read_next_entry(
+        &mut self,
+        reader: &mut BufReader<Box<dyn Read + Send>>,
+        entry: &mut Vec<u8>,
+    ) -> Result<usize, ReadError> {
+        let len = self
+            .read_method
+            .read_next_bytes(reader, entry, self.max_record_bytes)?;
+        if len > 0 || self.read_method == ReadMethod::Full {
+            self.entries_read_within_file += 1;
+            self.current_bytes_read += len as u64;
         }
-    }
-
-    /// Returns the name of the currently processed file in the input directory
-    fn current_offset_file(&self) -> Option<Arc<PathBuf>> {
-        match &self.current_action {
-            Some(PosixScannerAction::Read(path) | PosixScannerAction::Delete(path)) => {
-                Some(path.clone())
+        Ok(len)
+    }
+
+    /// Skips the entries of the current compressed file until the given position is
+    /// reached. Returns whether the file had enough data for that.
+    fn skip_compressed_entries(
+        &mut self,
+        reader: &mut BufReader<Box<dyn Read + Send>>,
+        bytes_offset: Option<u64>,
+        entries_count: Option<u64>,
+    ) -> Result<bool, ReadError> {
+        let mut entry = Vec::new();
+        while bytes_offset.is_some_and(|offset| self.current_bytes_read < offset)
+            || entries_count.is_some_and(|count| self.entries_read_within_file < count)
+        {
+            entry.clear();
+            if self.read_next_entry(reader, &mut entry)? == 0 {
+                return Ok(false);
             }
-            None => None,
         }
+        Ok(true)
     }
 
-    fn seek_to_file(&mut self, seek_file_path: &Path) -> Result<(), ReadError> {
-        if self.streaming_mode.are_deletions_enabled() {
-            warn!("seek for snapshot mode may not work correctly in case deletions take place");
-        }
-
-        self.known_files.clear();
-        let target_modify_time = match std::fs::metadata(seek_file_path) {
-            Ok(metadata) => metadata.modified()?,
-            Err(e) => {
-                if !matches!(e.kind(), std::io::ErrorKind::NotFound) {
-                    return Err(ReadError::Io(e));
-                }
-                warn!(
-                    "Unable to restore state: last persisted file {seek_file_path:?} not found in directory. Processing all files in directory."
-                );
-                return Ok(());
+    fn current_offset(&self) -> Result<OffsetValue, ReadError> {
+        let path = self.filesystem_scanner.require_current_offset_file()?;
+        let offset = if self.is_current_file_compressed {
+            OffsetValue::RecordPosition {
+                total_entries_read: self.total_entries_read,
+                path,
+                entries_read_within_file: self.entries_read_within_file,
+            }
+        } else {
+            OffsetValue::FilePosition {
+                total_entries_read: self.total_entries_read,
+                path,
+                bytes_offset: self.current_bytes_read,
             }
         };
-        let matching_files: Vec<PathBuf> = self.get_matching_file_paths()?;
-        for entry in matching_files {
-            if !entry.is_file() {
-                continue;
+        Ok(offset)
+    }
+
+    /// Opens the file of the current scanner action, skipping the part that precedes
+    /// the start position of the reader.
+    fn open_current_file(
+        &mut self,
+        path: &Path,
+    ) -> Result<BufReader<Box<dyn Read + Send>>, ReadError> {
+        let offset_file = self.filesystem_scanner.require_current_offset_file()?;
+        let mut reader = self.open_file(path)?;
+
+        if self
+            .filesystem_scanner
+            .take_file_present_at_start(&offset_file)
+        {
+            if !self.is_current_file_compressed && self.read_method.is_line_based() {
+                let start_offset = last_line_end(path)?;
+                let mut file = File::open(path)?;
+                file.seek(SeekFrom::Start(start_offset))?;
+                reader = BufReader::new(CompressionFormat::None.decoder(file)?);
+                self.current_bytes_read = start_offset;
+            } else {
+                // The entry boundaries can only be found by decoding the file
+                let mut entry = Vec::new();
+                loop {
+                    entry.clear();
+                    if self.read_next_entry(&mut reader, &mut entry)? == 0 {
+                        break;
+                    }
+                }
             }
-            let Some(modify_time) = self.modify_time(&entry) else {
-                continue;
+            self.filesystem_scanner
+                .set_start_offset(&offset_file, self.current_bytes_read);
+        } else if let Some(start_offset) = self.filesystem_scanner.start_offset(&offset_file) {
+            let is_skipped = if self.is_current_file_compressed {
+                self.skip_compressed_entries(&mut reader, Some(start_offset), None)?
+            } else {
+                let skipped = io::copy(&mut (&mut reader).take(start_offset), &mut io::sink())?;
+                self.current_bytes_read = skipped;
+                skipped == start_offset
             };
-            if (modify_time, entry.as_path()) <= (target_modify_time, seek_file_path) {
-                let modify_timestamp = modify_time
-                    .duration_since(SystemTime::UNIX_EPOCH)
-                    .expect("System time should be after the Unix epoch")
-                    .as_secs();
-                self.known_files.insert(entry, modify_timestamp);
+            if !is_skipped {
+                warn!("File {offset_file:?} became shorter than it was at the start, reading it from the beginning");
+                self.filesystem_scanner.forget_start_offset(&offset_file);
+                reader = self.open_file(path)?;
             }
         }
-        self.current_action = Some(PosixScannerAction::Read(Arc::new(
-            seek_file_path.to_path_buf(),
-        )));
 
-        Ok(())
+        Ok(reader)
     }
+}
 
-    fn modify_time(&mut self, entry: &Path) -> Option<SystemTime> {
-        if self.streaming_mode.are_deletions_enabled() {
-            // If deletions are enabled, we also need to handle the case when the modification
-            // time of an entry changes. Hence, we can't just memorize it once.
-            entry.metadata().ok()?.modified().ok()
-        } else {
-            *self
-                .cached_modify_times
-                .entry(entry.to_path_buf())
-                .or_insert_with(|| entry.metadata().ok()?.modified().ok())
+impl Reader for FilesystemReader {
+    fn seek(&mut self, frontier: &OffsetAntichain) -> Result<(), ReadError> {
+        if let Some(OffsetValue::RecordPosition {
+            total_entries_read,
+            path,
+            entries_read_within_file,
+        }) = frontier.get_offset(&OffsetKey::Empty)
+        {
+            self.filesystem_scanner.seek_to_file(path.as_path())?;
+            let mut reader = self.open_file(path)?;
+            if !self.skip_compressed_entries(&mut reader, None, Some(*entries_read_within_file))? {
+                warn!("File {path:?} has fewer entries than were read from it before");
+            }
+            self.reader = Some(reader);
+            self.total_entries_read = *total_entries_read;
+            return Ok(());
         }
-    }
 
-    /// Finish reading the current file and find the next one to read from.
-    /// If there is a file to read from, the method returns a `ReadResult`
-    /// specifying the action to be provided downstream.
-    ///
-    /// It can either be a `NewSource` event when the new action is found or
-    /// a `FinishedSource` event when we've had a scheduled action but the
-    /// corresponding file was deleted before we were able to execute this scheduled action.
-    /// scheduled action.
-    fn next_action_determined(&mut self) -> Result<Option<ReadResult>, ReadError> {
-        // Finalize the current processing action
-        if let Some(PosixScannerAction::Delete(path)) = take(&mut self.current_action) {
-            let cached_path = self
-                .cached_file_path(&path)
-                .expect("in case of enabled deletions cache should exist");
-            std::fs::remove_file(cached_path)?;
-        }
+        let Some(SeekPosition {
+            total_entries_read,
+            path: file_path_arc,
+            bytes_offset,
+            ..
+        }) = extract_seek_position(frontier, SeekOffsetKind::FilePosition)
+        else {
+            return Ok(());
+        };
+        // Filesystem scanner part: detect already processed file
+        self.filesystem_scanner
+            .seek_to_file(file_path_arc.as_path())?;
 
-        // File modification is handled as combination of its deletion and insertion
-        // If a file was deleted in the last action, now we must add it, and after that
-        // we may allow commit
-        if let Some(next_file_for_insertion) = take(&mut self.next_file_for_insertion) {
-            if next_file_for_insertion.exists() {
-                return Ok(Some(
-                    self.initiate_file_insertion(&next_file_for_insertion)?,
-                ));
-            }
+        // Seek within a particular file
+        self.reader = {
+            let path = file_path_arc.as_path();
+            let reader = match CompressionFormat::resolve(self.compression, path) {
+                CompressionFormat::None => {
+                    let mut file = File::open(path)?;
+                    file.seek(SeekFrom::Start(bytes_offset))?;
+                    self.is_current_file_compressed = false;
+                    self.entries_read_within_file = 0;
+                    BufReader::new(CompressionFormat::None.decoder(file)?)
+                }
+                CompressionFormat::Gzip | CompressionFormat::Zstd => {
+                    // The offsets saved before the compressed files were tracked by
+                    // the records count the decompressed bytes, so the file is decoded
+                    // from the start and the already processed part is skipped
+                    let mut reader = self.open_file(path)?;
+                    self.skip_compressed_entries(&mut reader, Some(bytes_offset), None)?;
+                    reader
+                }
+            };
+            Some(reader)
+        };
+        self.total_entries_read = total_entries_read;
+        self.current_bytes_read = bytes_offset;
 
-            // The scheduled insertion after deletion is impossible because
-            // the file has already been deleted.
-            // The action was done in full now, and we can allow commits.
-            return Ok(Some(ReadResult::FinishedSource {
-                commit_allowed: true,
-            }));
-        }
+        Ok(())
+    }
 
-        // First check if we need to delete something
-        if self.streaming_mode.are_deletions_enabled() {
-            let next_for_deletion = self.next_deletion_entry();
-            if next_for_deletion.is_some() {
-                return Ok(next_for_deletion);
-            }
+    fn read(&mut self) -> Result<ReadResult, ReadError> {
+        if let Some(metadata_record) = self.pending_metadata_record.take() {
+            return Ok(metadata_record);
+        }
+        if let Some(deferred_read_result) = self.deferred_read_result.take() {
+            return Ok(deferred_read_result);
         }
 
-        // If there is nothing to delete, ingest the new entries
-        self.next_insertion_entry()
-    }
+        let read_started_at = Instant::now();
+        loop {
+            if let Some(reader) = &mut self.reader {
+                let mut line = Vec::new();
+                let len =
+                    self.read_method
+                        .read_next_bytes(reader, &mut line, self.max_record_bytes)?;
+                if len > 0 || self.read_method == ReadMethod::Full {
+                    self.total_entries_read += 1;
+                    self.entries_read_within_file += 1;
+                    self.current_bytes_read += len as u64;
 
-    fn next_deletion_entry(&mut self) -> Option<ReadResult> {
-        let mut path_for_deletion: Option<PathBuf> = None;
-        for (path, modified_at) in &self.known_files {
-            let metadata = std::fs::metadata(path);
-            let needs_deletion = {
-                match metadata {
-                    Err(e) => e.kind() == std::io::ErrorKind::NotFound,
-                    Ok(metadata) => {
-                        if let Ok(new_modification_time) = metadata.modified() {
-                            let modified_at_new = new_modification_time
-                                .duration_since(SystemTime::UNIX_EPOCH)
-                                .expect("System time should be after the Unix epoch")
-                                .as_secs();
-                            modified_at_new != *modified_at
-                        } else {
-                            false
-                        }
+                    let offset = (OffsetKey::Empty, self.current_offset()?);
+                    let data_event_type = self.filesystem_scanner.require_data_event_type()?;
+
+                    if self.read_method == ReadMethod::Full {
+                        self.deferred_read_result = Some(ReadResult::FinishedSource {
+                            commit_allowed: !self.filesystem_scanner.has_planned_insertion(),
+                        });
+                        self.reader = None;
                     }
-                }
-            };
-            if needs_deletion {
-                match &path_for_deletion {
-                    None => path_for_deletion = Some(path.clone()),
-                    Some(other_path) => {
-                        if other_path > path {
-                            path_for_deletion = Some(path.clone());
-                        }
+
+                    if let Some(encoding) = &self.encoding {
+                        line = encoding.transcode(line)?;
                     }
+
+                    self.last_activity = Some(Instant::now());
+                    return Ok(ReadResult::Data(
+                        ReaderContext::from_raw_bytes(data_event_type, line),
+                        offset,
+                    ));
                 }
+
+                self.reader = None;
+                return Ok(ReadResult::FinishedSource {
+                    commit_allowed: !self.filesystem_scanner.has_planned_insertion(),
+                });
             }
-        }
 
-        match path_for_deletion {
-            Some(path) => {
-                // Metadata of the deleted file must be the same as when it was added
-                // so that the deletion event is processed correctly by timely. To achieve
-                // this, we just take the cached metadata
-                let old_metadata = self
-                    .cached_metadata
-                    .remove(&path)
-                    .expect("inconsistency between known_files and cached_metadata");
+            let next_read_result = self.filesystem_scanner.next_action_determined()?;
+            if let Some(next_read_result) = next_read_result {
+                if let Some(selected_file) = self.filesystem_scanner.current_file() {
+                    if self.is_skipped_empty_file(&selected_file)? {
+                        // The scanner has already taken the file, so it isn't read
+                        // again, even though there is no entry with its offset
+                        self.deferred_read_result = Some(ReadResult::FinishedSource {
+                            commit_allowed: !self.filesystem_scanner.has_planned_insertion(),
+                        });
+                        return Ok(next_read_result);
+                    }
+                    self.reader = Some(self.open_current_file(&selected_file)?);
+                    if self.emit_metadata_record {
+                        self.pending_metadata_record = source_metadata_record(
+                            &self.filesystem_scanner,
+                            &next_read_result,
+                            self.total_entries_read,
+                            self.current_bytes_read,
+                        );
+                    }
+                }
+                return Ok(next_read_result);
+            }
 
-                self.known_files.remove(&path.clone().clone());
-                self.current_action = Some(PosixScannerAction::Delete(Arc::new(path.clone())));
-                if path.exists() {
-                    self.next_file_for_insertion = Some(path);
+            if self.filesystem_scanner.is_polling_enabled() && !self.shutdown_signal.is_requested()
+            {
+                if is_idle_timeout_reached(self.idle_timeout, read_started_at) {
+                    return Ok(ReadResult::FinishedSource {
+                        commit_allowed: true,
+                    });
                 }
-                Some(ReadResult::NewSource(old_metadata))
+                self.filesystem_scanner.wait_for_new_files();
+            } else {
+                return Ok(ReadResult::Finished);
             }
-            None => None,
         }
     }
 
-    fn cached_file_path(&self, path: &Path) -> Option<PathBuf> {
-        self.cache_directory_path.as_ref().map(|root_path| {
-            let mut hasher = Hasher::default();
-            hasher.update(path.as_os_str().as_encoded_bytes());
-            root_path.join(format!("{}", hasher.digest128()))
-        })
+    fn persistent_id(&self) -> Option<PersistentId> {
+        self.persistent_id
     }
 
-    fn get_matching_file_paths(&self) -> Result<Vec<PathBuf>, ReadError> {
-        let mut result = Vec::new();
-
-        let file_and_folder_paths = glob::glob(self.path.as_str())?.flatten();
-        for entry in file_and_folder_paths {
-            // If an entry is a file, it should just be added to result
-            if entry.is_file() {
-                result.push(entry);
-                continue;
-            }
+    fn update_persistent_id(&mut self, persistent_id: Option<PersistentId>) {
+        self.persistent_id = persistent_id;
+    }
 
-            // Otherwise scan all files in all subdirectories and add them
-            let Some(path) = entry.to_str() else {
-                error!("Non-unicode paths are not supported. Ignoring: {entry:?}");
-                continue;
-            };
+    fn set_shutdown_signal(&mut self, signal: ShutdownSignal) {
+        self.shutdown_signal = signal;
+    }
 
-            let folder_scan_pattern = format!("{path}/**/{}", self.object_pattern);
-            let folder_contents = glob::glob(&folder_scan_pattern)?.flatten();
-            for nested_entry in folder_contents {
-                if nested_entry.is_file() {
-                    result.push(nested_entry);
-                }
-            }
-        }
+    fn set_idle_timeout(&mut self, timeout: Option<Duration>) {
+        self.idle_timeout = timeout;
+    }
 
-        Ok(result)
+    fn storage_type(&self) -> StorageType {
+        StorageType::FileSystem
     }
 
-    fn next_insertion_entry(&mut self) -> Result<Option<ReadResult>, ReadError> {
-        let matching_files: Vec<PathBuf> = self.get_matching_file_paths()?;
-        let mut selected_file: Option<(PathBuf, SystemTime)> = None;
-        for entry in matching_files {
-            if !entry.is_file() || self.known_files.contains_key(&(*entry)) {
-                continue;
-            }
+    fn max_allowed_consecutive_errors(&self) -> usize {
+        self.max_consecutive_errors
+    }
 
-            let Some(modify_time) = self.modify_time(&entry) else {
-                continue;
-            };
+    fn last_activity(&self) -> Option<Instant> {
+        self.last_activity
+    }
+}
 
-            match &selected_file {
-                Some((currently_selected_name, selected_file_created_at)) => {
-                    if (selected_file_created_at, currently_selected_name) > (&modify_time, &entry)
-                    {
-                        selected_file = Some((entry, modify_time));
-                    }
-                }
-                None => selected_file = Some((entry, modify_time)),
-            }
+impl Writer for FileWriter {
+    fn write(&mut self, data: FormatterContext) -> Result<(), WriteError> {
+        let tombstone_marker = match data.event_type {
+            DataEventType::Delete => self.tombstone_marker.as_deref(),
+            DataEventType::Insert | DataEventType::Upsert => None,
+        };
+        for payload in &data.payloads {
+            self.framing
+                .write_payload(&mut self.writer, tombstone_marker, payload)?;
         }
+        Ok(())
+    }
 
-        match selected_file {
-            Some((new_file_name, _)) => Ok(Some(self.initiate_file_insertion(&new_file_name)?)),
-            None => Ok(None),
+    fn flush(&mut self, forced: bool) -> Result<(), WriteError> {
+        if forced {
+            self.sync_to_disk()
+        } else {
+            self.writer.flush()?;
+            Ok(())
         }
     }
 
-    fn initiate_file_insertion(&mut self, new_file_name: &PathBuf) -> io::Result<ReadResult> {
-        let new_file_meta =
-            SourceMetadata::from_fs_meta(new_file_name, &std::fs::metadata(new_file_name)?);
-        self.cached_metadata
-            .insert(new_file_name.clone(), Some(new_file_meta.clone()));
-        self.known_files.insert(
-            new_file_name.clone(),
-            new_file_meta
-                .modified_at
-                .unwrap_or(current_unix_timestamp_secs()),
-        );
+    fn on_commit(&mut self, _time: Timestamp) -> Result<(), WriteError> {
+        self.sync_to_disk()
+    }
+}
 
-        let cached_path = self.cached_file_path(new_file_name);
-        if let Some(cached_path) = cached_path {
-            std::fs::copy(new_file_name, cached_path)?;
-        }
+enum CompressedStream {
+    Plain(BufWriter<std::fs::File>),
+    Gzip(GzEncoder<BufWriter<std::fs::File>>),
+    Zstd(ZstdEncoder<'static, BufWriter<std::fs::File>>),
+}
 
-        self.current_action = Some(PosixScannerAction::Read(Arc::new(new_file_name.clone())));
-        Ok(ReadResult::NewSource(Some(new_file_meta)))
+impl CompressedStream {
+    fn new(
+        compression: CompressionFormat,
+        writer: BufWriter<std::fs::File>,
+    ) -> Result<Self, WriteError> {
+        Ok(match compression {
+            CompressionFormat::None => Self::Plain(writer),
+            CompressionFormat::Gzip => Self::Gzip(GzEncoder::new(writer, GzCompression::default())),
+            CompressionFormat::Zstd => Self::Zstd(ZstdEncoder::new(writer, 0)?),
+        })
     }
 
-    fn sleep_duration() -> Duration {
-        Duration::from_millis(500)
+    fn get_mut(&mut self) -> &mut dyn Write {
+        match self {
+            Self::Plain(writer) => writer,
+            Self::Gzip(encoder) => encoder,
+            Self::Zstd(encoder) => encoder,
+        }
     }
 
-    fn wait_for_new_files(&mut self) {
-        self.inotify
-            .as_mut()
-            .and_then(inotify_support::wait)
-            .unwrap_or_else(|| {
-                sleep(Self::sleep_duration());
-            });
+    fn finish(self) -> Result<BufWriter<std::fs::File>, WriteError> {
+        let mut writer = match self {
+            Self::Plain(writer) => writer,
+            Self::Gzip(encoder) => encoder.finish()?,
+            Self::Zstd(encoder) => encoder.finish()?,
+        };
+        writer.flush()?;
+        Ok(writer)
     }
 }
 
-#[derive(Clone, Copy, Debug, PartialEq, Eq)]
-pub enum ConnectorMode {
-    Static,
-    Streaming,
+/// Writes the payloads, framed as specified by `framing`, into a compressed file.
+///
+/// A forced flush finishes the current gzip member or zstd frame, so that the file
+/// is complete if the program stops afterwards. The next write then starts a new
+/// one, and the decoders used by the readers handle such concatenated streams. A
+/// non-forced flush only pushes out the data compressed so far.
+pub struct CompressedFileWriter {
+    compression: CompressionFormat,
+    framing: PayloadFraming,
+    stream: Option<CompressedStream>,
+    // The underlying writer while there is no unfinished frame
+    finished_writer: Option<BufWriter<std::fs::File>>,
 }
 
-impl ConnectorMode {
-    pub fn is_polling_enabled(&self) -> bool {
-        match self {
-            ConnectorMode::Static => false,
-            ConnectorMode::Streaming => true,
+impl CompressedFileWriter {
+    pub fn new(
+        writer: BufWriter<std::fs::File>,
+        compression: CompressionFormat,
+        framing: PayloadFraming,
+    ) -> Self {
+        Self {
+            compression,
+            framing,
+            stream: None,
+            finished_writer: Some(writer),
         }
     }
 
-    pub fn are_deletions_enabled(&self) -> bool {
-        match self {
-            ConnectorMode::Static => false,
-            ConnectorMode::Streaming => true,
-        }
+    fn stream(&mut self) -> Result<&mut CompressedStream, WriteError> {
+        let stream = match self.stream.take() {
+            Some(stream) => stream,
+            None => {
+                let writer = self
+                    .finished_writer
+                    .take()
+                    .expect("either a stream or a finished writer must be present");
+                CompressedStream::new(self.compression, writer)?
+            }
+        };
+        Ok(self.stream.insert(stream))
     }
 }
 
-#[derive(Debug)]
-pub struct CsvFilesystemReader {
-    parser_builder: csv::ReaderBuilder,
-    persistent_id: Option<PersistentId>,
+impl Writer for CompressedFileWriter {
+    fn write(&mut self, data: FormatterContext) -> Result<(), WriteError> {
+        let framing = self.framing;
+        let writer = self.stream()?.get_mut();
+        for payload in &data.payloads {
+            framing.write_payload(writer, None, payload)?;
+        }
+        Ok(())
+    }
 
-    reader: Option<csv::Reader<std::fs::File>>,
-    filesystem_scanner: FilesystemScanner,
-    total_entries_read: u64,
-    deferred_read_result: Option<ReadResult>,
+    fn flush(&mut self, forced: bool) -> Result<(), WriteError> {
+        if forced {
+            if let Some(stream) = self.stream.take() {
+                self.finished_writer = Some(stream.finish()?);
+            }
+        } else if let Some(stream) = &mut self.stream {
+            stream.get_mut().flush()?;
+        }
+        Ok(())
+    }
 }
 
-impl CsvFilesystemReader {
-    pub fn new(
-        path: &str,
-        parser_builder: csv::ReaderBuilder,
-        streaming_mode: ConnectorMode,
-        persistent_id: Option<PersistentId>,
-        object_pattern: &str,
-    ) -> Result<CsvFilesystemReader, ReadError> {
-        let filesystem_scanner =
-            FilesystemScanner::new(path, persistent_id, streaming_mode, object_pattern)?;
-        Ok(CsvFilesystemReader {
-            parser_builder,
-            persistent_id,
-
-            reader: None,
-            filesystem_scanner,
-            total_entries_read: 0,
-            deferred_read_result: None,
-        })
+impl Drop for CompressedFileWriter {
+    fn drop(&mut self) {
+        if let Some(stream) = self.stream.take() {
+            if let Err(e) = stream.finish() {
+                error!("Failed to finish the compressed output file: {e}");
+            }
+        }
     }
 }
 
-impl Reader for CsvFilesystemReader {
-    fn seek(&mut self, frontier: &OffsetAntichain) -> Result<(), ReadError> {
-        let offset_value = frontier.get_offset(&OffsetKey::Empty);
-        let Some(OffsetValue::FilePosition {
-            total_entries_read,
-            path: file_path_arc,
-            bytes_offset,
-        }) = offset_value
-        else {
-            if offset_value.is_some() {
-                warn!("Incorrect type of offset value in CsvFilesystem frontier: {offset_value:?}");
+#[derive(Debug)]
+enum PosixScannerAction {
+    Read(Arc<PathBuf>),
+    Delete(Arc<PathBuf>),
+}
+
+/// The hash function naming the cached copies of the files.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum CacheHashFunction {
+    #[default]
+    Xxh3,
+    Sha256,
+    Blake3,
+}
+
+impl CacheHashFunction {
+    fn hex_digest(self, mut input: impl Read) -> io::Result<String> {
+        let mut feed = |update: &mut dyn FnMut(&[u8])| -> io::Result<()> {
+            let mut buffer = [0; 8192];
+            loop {
+                let len = input.read(&mut buffer)?;
+                if len == 0 {
+                    return Ok(());
+                }
+                update(&buffer[..len]);
             }
-            return Ok(());
         };
+        match self {
+            Self::Xxh3 => {
+                let mut hasher = Hasher::default();
+                feed(&mut |bytes| hasher.update(bytes))?;
+                Ok(format!("{}", hasher.digest128()))
+            }
+            Self::Sha256 => {
+                let mut hasher = Sha256::new();
+                feed(&mut |bytes| hasher.update(bytes))?;
+                Ok(format!("{:x}", hasher.finalize()))
+            }
+            Self::Blake3 => {
+                let mut hasher = blake3::Hasher::new();
+                feed(&mut |bytes| {
+                    hasher.update(bytes);
+                })?;
+                Ok(hasher.finalize().to_hex().to_string())
+            }
+        }
+    }
+}
 
-        // Filesystem scanner part: detect already processed file
-        self.filesystem_scanner
-            .seek_to_file(file_path_arc.as_path())?;
-
-        // Seek within a particular file
-        self.total_entries_read = *total_entries_read;
-        self.reader = {
-            // Since it's a CSV reader, we will need to fit the header in the parser first
-            let mut reader = self.parser_builder.from_path(file_path_arc.as_path())?;
-            if *bytes_offset > 0 {
-                let mut header_record = csv::StringRecord::new();
-                if reader.read_record(&mut header_record)? {
-                    let header_reader_context = ReaderContext::from_tokenized_entries(
-                        self.filesystem_scanner
-                            .data_event_type()
-                            .expect("scanner action can't be empty"),
-                        header_record
-                            .iter()
-                            .map(std::string::ToString::to_string)
-                            .collect(),
-                    );
+/// Defines how the cached copies of the files, which are needed to emit their
+/// deletions, are named.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct CacheKeyStrategy {
+    pub hash_function: CacheHashFunction,
 
-                    let offset = (OffsetKey::Empty, offset_value.unwrap().clone());
+    /// Whether the contents of a file are hashed instead of its path. The files with
+    /// the same contents share a single cached copy then.
+    pub hash_contents: bool,
+}
 
-                    let header_read_result = ReadResult::Data(header_reader_context, offset);
-                    self.deferred_read_result = Some(header_read_result);
-                }
-            }
+/// Tracks the files matching a glob pattern and decides which of them should be
+/// read or deleted next. A modified file is deleted and then inserted again.
+#[derive(Debug)]
+pub struct FilesystemScanner {
+    paths: Vec<GlobPattern>,
+    cache_directory_path: Option<PathBuf>,
+    streaming_mode: ConnectorMode,
+    object_pattern: String,
+    refresh_interval: Duration,
 
-            let mut seek_position = csv::Position::new();
-            seek_position.set_byte(*bytes_offset);
-            reader.seek(seek_position)?;
+    // Mapping from the path of the loaded file to its modification timestamp
+    known_files: HashMap<PathBuf, u64>,
 
-            Some(reader)
-        };
+    current_action: Option<PosixScannerAction>,
+    cached_modify_times: HashMap<PathBuf, Option<SystemTime>>,
+    inotify: Option<inotify_support::Inotify>,
+    next_file_for_insertion: Option<PathBuf>,
+    cached_metadata: HashMap<PathBuf, Option<SourceMetadata>>,
+    cache_key_strategy: CacheKeyStrategy,
+    // Mapping from the path of the loaded file to the path of its cached copy
+    cached_paths: HashMap<PathBuf, PathBuf>,
 
-        Ok(())
-    }
+    // The files that were present at the start and are yet to be read from their
+    // current end, and the offsets the reading of such files starts from
+    files_present_at_start: HashSet<PathBuf>,
+    start_offsets: HashMap<PathBuf, u64>,
+    clock: Arc<dyn Clock>,
 
-    fn read(&mut self) -> Result<ReadResult, ReadError> {
-        if let Some(deferred_read_result) = self.deferred_read_result.take() {
-            return Ok(deferred_read_result);
-        }
+    // Storage is deleted on object destruction, so we need to store it
+    // for the connector's life time
+    _connector_tmp_storage: Option<TempDir>,
+}
 
-        loop {
-            match &mut self.reader {
-                Some(reader) => {
-                    let mut current_record = csv::StringRecord::new();
-                    if reader.read_record(&mut current_record)? {
-                        self.total_entries_read += 1;
+impl FilesystemScanner {
+    const DEFAULT_REFRESH_INTERVAL: Duration = Duration::from_millis(500);
 
-                        let offset = (
-                            OffsetKey::Empty,
-                            OffsetValue::FilePosition {
-                                total_entries_read: self.total_entries_read,
-                                path: self
-                                    .filesystem_scanner
-                                    .current_offset_file()
-                                    .clone()
-                                    .unwrap(),
-                                bytes_offset: reader.position().byte(),
-                            },
-                        );
+    /// Creates a scanner of the files matching any of the globs in `paths`, so that
+    /// several directories can form a single source. The files are read in the
+    /// order of their modification times regardless of the glob they match.
+    ///
+    /// The cached copies of the files are kept in `cache_root` if it's given, or in
+    /// the directory from `PATHWAY_PERSISTENT_STORAGE` otherwise. Without either,
+    /// they are kept in a temporary directory removed along with the scanner.
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        paths: &[&str],
+        persistent_id: Option<PersistentId>,
+        streaming_mode: ConnectorMode,
+        object_pattern: &str,
+        refresh_interval: Option<Duration>,
+        start_position: FileStartPosition,
+        clock: Arc<dyn Clock>,
+        cache_key_strategy: CacheKeyStrategy,
+        cache_root: Option<&Path>,
+    ) -> Result<FilesystemScanner, ReadError> {
+        let path_globs = paths
+            .iter()
+            .map(|path| GlobPattern::new(path))
+            .collect::<Result<Vec<_>, _>>()?;
 
-                        return Ok(ReadResult::Data(
-                            ReaderContext::from_tokenized_entries(
-                                self.filesystem_scanner
-                                    .data_event_type()
-                                    .expect("scanner action can't be empty"),
-                                current_record
-                                    .iter()
-                                    .map(std::string::ToString::to_string)
-                                    .collect(),
-                            ),
-                            offset,
-                        ));
-                    }
+        let inotify = if streaming_mode.is_polling_enabled() {
+            let watch_roots: Vec<_> = paths
+                .iter()
+                .map(|path| Self::glob_watch_root(path))
+                .collect();
+            inotify_support::subscribe_inotify(&watch_roots)
+        } else {
+            None
+        };
 
-                    let next_read_result = self.filesystem_scanner.next_action_determined()?;
-                    if let Some(next_read_result) = next_read_result {
-                        if let Some(selected_file) = self.filesystem_scanner.current_file() {
-                            self.reader = Some(self.parser_builder.from_path(&*selected_file)?);
-                        }
-                        return Ok(next_read_result);
-                    }
-                    // The file came to its end, so we should drop the reader
-                    self.reader = None;
-                    return Ok(ReadResult::FinishedSource {
-                        commit_allowed: !self.filesystem_scanner.has_planned_insertion(),
-                    });
-                }
-                None => {
-                    let next_read_result = self.filesystem_scanner.next_action_determined()?;
-                    if let Some(next_read_result) = next_read_result {
-                        if let Some(selected_file) = self.filesystem_scanner.current_file() {
-                            self.reader = Some(
-                                self.parser_builder
-                                    .flexible(true)
-                                    .from_path(&*selected_file)?,
-                            );
-                        }
-                        return Ok(next_read_result);
-                    }
+        let (cache_directory_path, connector_tmp_storage) = {
+            if streaming_mode.are_deletions_enabled() {
+                let root_dir_path = cache_root.map(Path::to_path_buf).or_else(|| {
+                    env::var("PATHWAY_PERSISTENT_STORAGE")
+                        .ok()
+                        .map(PathBuf::from)
+                });
+                if let Some(root_dir_path) = root_dir_path {
+                    ensure_directory(&root_dir_path)?;
+                    let unique_id =
+                        persistent_id.unwrap_or_else(|| rand::thread_rng().gen::<u128>());
+                    let connector_tmp_directory = root_dir_path.join(format!("cache-{unique_id}"));
+                    ensure_directory(&connector_tmp_directory)?;
+                    (Some(connector_tmp_directory), None)
+                } else {
+                    let cache_tmp_storage = tempdir()?;
+                    let connector_tmp_directory = cache_tmp_storage.path();
+                    (
+                        Some(connector_tmp_directory.to_path_buf()),
+                        Some(cache_tmp_storage),
+                    )
                 }
-            }
-
-            if self.filesystem_scanner.is_polling_enabled() {
-                self.filesystem_scanner.wait_for_new_files();
             } else {
-                return Ok(ReadResult::Finished);
+                (None, None)
             }
+        };
+
+        let mut scanner = Self {
+            paths: path_globs,
+            streaming_mode,
+            cache_directory_path,
+
+            object_pattern: object_pattern.to_string(),
+            refresh_interval: refresh_interval.unwrap_or(Self::DEFAULT_REFRESH_INTERVAL),
+            known_files: HashMap::new(),
+            current_action: None,
+            cached_modify_times: HashMap::new(),
+            inotify,
+            next_file_for_insertion: None,
+            cached_metadata: HashMap::new(),
+            cache_key_strategy,
+            cached_paths: HashMap::new(),
+            files_present_at_start: HashSet::new(),
+            start_offsets: HashMap::new(),
+            clock,
+            _connector_tmp_storage: connector_tmp_storage,
+        };
+        if start_position == FileStartPosition::End {
+            scanner.files_present_at_start =
+                scanner.get_matching_file_paths()?.into_iter().collect();
         }
+        Ok(scanner)
     }
 
-    fn persistent_id(&self) -> Option<PersistentId> {
-        self.persistent_id
+    /// Returns whether the file was present at the start and must be read from the
+    /// end it has now. It happens only once for each such file, and then the reader
+    /// is expected to report the offset it started from.
+    fn take_file_present_at_start(&mut self, path: &Path) -> bool {
+        self.files_present_at_start.remove(path)
     }
 
-    fn update_persistent_id(&mut self, persistent_id: Option<PersistentId>) {
-        self.persistent_id = persistent_id;
+    fn set_start_offset(&mut self, path: &Path, offset: u64) {
+        self.start_offsets.insert(path.to_path_buf(), offset);
     }
 
-    fn storage_type(&self) -> StorageType {
-        StorageType::CsvFilesystem
+    /// Returns the offset the reading of the file starts from. It applies both to the
+    /// file and to its cached copy, so that a modified file is deleted and inserted
+    /// again from the same offset.
+    fn start_offset(&self, path: &Path) -> Option<u64> {
+        self.start_offsets.get(path).copied()
     }
-}
 
-pub struct PythonReaderBuilder {
-    subject: Py<PythonSubject>,
-    persistent_id: Option<PersistentId>,
-}
-
-pub struct PythonReader {
-    subject: Py<PythonSubject>,
-    persistent_id: Option<PersistentId>,
-    total_entries_read: u64,
-    is_initialized: bool,
-    is_finished: bool,
+    fn forget_start_offset(&mut self, path: &Path) {
+        self.start_offsets.remove(path);
+    }
 
-    #[allow(unused)]
-    python_thread_state: PythonThreadState,
-}
+    /// Splits the glob into the deepest existing directory within its literal prefix,
+    /// which contains every path matched by it, and the remaining components, which
+    /// limit the directories watched below it.
+    fn glob_watch_root(path: &str) -> (PathBuf, Vec<String>) {
+        let components: Vec<_> = Path::new(path).components().collect();
+        let mut root_length = components
+            .iter()
+            .position(|component| {
+                component
+                    .as_os_str()
+                    .to_string_lossy()
+                    .contains(['*', '?', '['])
+            })
+            .unwrap_or(components.len());
 
-impl PythonReaderBuilder {
-    pub fn new(subject: Py<PythonSubject>, persistent_id: Option<PersistentId>) -> Self {
-        Self {
-            subject,
-            persistent_id,
-        }
+        // The literal prefix may end with a file or with a directory that doesn't
+        // exist yet
+        let root = loop {
+            if root_length == 0 {
+                break PathBuf::from(".");
+            }
+            let root: PathBuf = components[..root_length].iter().collect();
+            if root.is_dir() {
+                break root;
+            }
+            root_length -= 1;
+        };
+        let remaining_components = components[root_length..]
+            .iter()
+            .map(|component| component.as_os_str().to_string_lossy().into_owned())
+            .collect();
+        (root, remaining_components)
     }
-}
 
-impl ReaderBuilder for PythonReaderBuilder {
-    fn build(self: Box<Self>) -> Result<Box<dyn Reader>, ReadError> {
-        let python_thread_state = PythonThreadState::new();
-        let Self {
-            subject,
-            persistent_id,
-        } = *self;
-
-        Ok(Box::new(PythonReader {
-            subject,
-            persistent_id,
-            python_thread_state,
-            total_entries_read: 0,
-            is_initialized: false,
-            is_finished: false,
-        }))
+    /// Returns the directories watched for the changes, which is empty if the changes
+    /// are detected only by polling.
+    pub fn watched_directories(&self) -> Vec<PathBuf> {
+        self.inotify
+            .as_ref()
+            .map(inotify_support::watched_directories)
+            .unwrap_or_default()
     }
 
-    fn is_internal(&self) -> bool {
-        self.subject.get().is_internal
+    fn has_planned_insertion(&self) -> bool {
+        self.next_file_for_insertion.is_some()
     }
 
-    fn persistent_id(&self) -> Option<PersistentId> {
-        self.persistent_id
+    fn is_polling_enabled(&self) -> bool {
+        self.streaming_mode.is_polling_enabled()
     }
 
-    fn update_persistent_id(&mut self, persistent_id: Option<PersistentId>) {
-        self.persistent_id = persistent_id;
+    pub fn data_event_type(&self) -> Option<DataEventType> {
+        self.current_action
+            .as_ref()
+            .map(|current_action| match current_action {
+                PosixScannerAction::Read(_) => DataEventType::Insert,
+                PosixScannerAction::Delete(_) => DataEventType::Delete,
+            })
     }
 
-    fn storage_type(&self) -> StorageType {
-        StorageType::Python
+    /// Returns the actual file path, which needs to be read
+    /// It is either a path to the file in the input directory, or a path to the file
+    /// which is saved in cache
+    pub fn current_file(&self) -> Option<Arc<PathBuf>> {
+        match &self.current_action {
+            Some(PosixScannerAction::Read(path)) => Some(path.clone()),
+            Some(PosixScannerAction::Delete(path)) => self.cached_file_path(path).map(Arc::new),
+            None => None,
+        }
     }
-}
 
-impl Reader for PythonReader {
-    fn seek(&mut self, frontier: &OffsetAntichain) -> Result<(), ReadError> {
-        let offset_value = frontier.get_offset(&OffsetKey::Empty);
-        let Some(OffsetValue::PythonEntrySequentialId(offset_value)) = offset_value else {
-            if offset_value.is_some() {
-                warn!("Incorrect type of offset value in Python frontier: {offset_value:?}");
+    /// Returns the name of the currently processed file in the input directory
+    pub fn current_offset_file(&self) -> Option<Arc<PathBuf>> {
+        match &self.current_action {
+            Some(PosixScannerAction::Read(path) | PosixScannerAction::Delete(path)) => {
+                Some(path.clone())
             }
-            return Ok(());
-        };
+            None => None,
+        }
+    }
 
-        self.total_entries_read = *offset_value;
+    /// Same as `current_offset_file`, but treats the absence of the current action
+    /// as an error, since the readers only ask for it while processing a file.
+    pub fn require_current_offset_file(&self) -> Result<Arc<PathBuf>, ReadError> {
+        self.current_offset_file()
+            .ok_or_else(Self::missing_action_error)
+    }
 
-        Ok(())
+    /// Same as `data_event_type`, but treats the absence of the current action
+    /// as an error.
+    pub fn require_data_event_type(&self) -> Result<DataEventType, ReadError> {
+        self.data_event_type()
+            .ok_or_else(Self::missing_action_error)
     }
 
-    fn read(&mut self) -> Result<ReadResult, ReadError> {
-        if !self.is_initialized {
-            with_gil_and_pool(|py| self.subject.borrow(py).start.call0(py))?;
-            self.is_initialized = true;
-        }
-        if self.is_finished {
-            return Ok(ReadResult::Finished);
-        }
+    fn missing_action_error() -> ReadError {
+        ReadError::InconsistentState("the scanner has no current action".to_string())
+    }
 
-        with_gil_and_pool(|py| {
-            let (event, key, values): (DataEventType, Option<Value>, HashMap<String, Value>) = self
-                .subject
-                .borrow(py)
-                .read
-                .call0(py)?
-                .extract(py)
-                .map_err(ReadError::Py)?;
-            let key = key.map(|key| vec![key]);
-            let values: ValuesMap = values.into();
+    fn seek_to_file(&mut self, seek_file_path: &Path) -> Result<(), ReadError> {
+        if self.streaming_mode.are_deletions_enabled() {
+            warn!("seek for snapshot mode may not work correctly in case deletions take place");
+        }
 
-            if event != DataEventType::Insert && !self.subject.borrow(py).deletions_enabled {
-                return Err(ReadError::Py(PyValueError::new_err(
-                    "Trying to modify a row in the Python connector but deletions_enabled is set to False.",
-                )));
-            }
+        // The reading resumes from the persisted offset, so the files that weren't
+        // processed before must be read in full
+        self.files_present_at_start.clear();
+        self.start_offsets.clear();
 
-            if values.is_special(FINISH_LITERAL) {
-                self.is_finished = true;
-                self.subject.borrow(py).end.call0(py)?;
-                Ok(ReadResult::Finished)
-            } else {
-                // We use simple sequential offset because Python connector is single threaded, as
-                // by default.
-                //
-                // If it's changed, add worker_id to the offset.
-                self.total_entries_read += 1;
-                let offset = (
-                    OffsetKey::Empty,
-                    OffsetValue::PythonEntrySequentialId(self.total_entries_read),
+        self.known_files.clear();
+        self.cached_metadata.clear();
+        self.cached_paths.clear();
+        let target_modify_time = match std::fs::metadata(seek_file_path) {
+            Ok(metadata) => metadata.modified()?,
+            Err(e) => {
+                if !matches!(e.kind(), std::io::ErrorKind::NotFound) {
+                    return Err(ReadError::Io(e));
+                }
+                warn!(
+                    "Unable to restore state: last persisted file {seek_file_path:?} not found in directory. Processing all files in directory."
                 );
-
-                Ok(ReadResult::Data(
-                    ReaderContext::from_diff(event, key, values),
-                    offset,
-                ))
+                return Ok(());
             }
-        })
-    }
-
-    fn persistent_id(&self) -> Option<PersistentId> {
-        self.persistent_id
-    }
+        };
+        let matching_files: Vec<PathBuf> = self.get_matching_file_paths()?;
+        for entry in matching_files {
+            if !entry.is_file() {
+                continue;
+            }
+            let Some(modify_time) = self.modify_time(&entry) else {
+                continue;
+            };
+            if (modify_time, entry.as_path()) <= (target_modify_time, seek_file_path) {
+                let modify_timestamp = modify_time
+                    .duration_since(SystemTime::UNIX_EPOCH)
+                    .expect("System time should be after the Unix epoch")
+                    .as_secs();
+                let metadata = self.load_cached_metadata(&entry);
+                // The file may have been modified since it was read, which is only
+                // seen from its cached metadata
+                let known_timestamp = metadata
+                    .as_ref()
+                    .and_then(|metadata| metadata.modified_at)
+                    .unwrap_or(modify_timestamp);
+                self.cached_metadata.insert(entry.clone(), metadata);
+                if let Some(cached_path) = self.load_cached_file_path(&entry)? {
+                    self.cached_paths.insert(entry.clone(), cached_path);
+                }
+                self.known_files.insert(entry, known_timestamp);
+            }
+        }
+        self.current_action = Some(PosixScannerAction::Read(Arc::new(
+            seek_file_path.to_path_buf(),
+        )));
 
-    fn update_persistent_id(&mut self, persistent_id: Option<PersistentId>) {
-        self.persistent_id = persistent_id;
+        Ok(())
     }
 
-    fn storage_type(&self) -> StorageType {
-        StorageType::Python
+    fn modify_time(&mut self, entry: &Path) -> Option<SystemTime> {
+        if self.streaming_mode.are_deletions_enabled() {
+            // If deletions are enabled, we also need to handle the case when the modification
+            // time of an entry changes. Hence, we can't just memorize it once.
+            entry.metadata().ok()?.modified().ok()
+        } else {
+            *self
+                .cached_modify_times
+                .entry(entry.to_path_buf())
+                .or_insert_with(|| entry.metadata().ok()?.modified().ok())
+        }
     }
-}
-
-pub struct PsqlWriter {
-    client: PsqlClient,
-    max_batch_size: Option<usize>,
-    buffer: Vec<FormatterContext>,
-    snapshot_mode: bool,
-}
 
-impl PsqlWriter {
-    pub fn new(
-        client: PsqlClient,
-        max_batch_size: Option<usize>,
-        snapshot_mode: bool,
-    ) -> PsqlWriter {
-        PsqlWriter {
-            client,
-            max_batch_size,
-            buffer: Vec::new(),
-            snapshot_mode,
+    /// Finish reading the current file and find the next one to read from.
+    /// If there is a file to read from, the method returns a `ReadResult`
+    /// specifying the action to be provided downstream.
+    ///
+    /// It can either be a `NewSource` event when the new action is found or
+    /// a `FinishedSource` event when we've had a scheduled action but the
+    /// corresponding file was deleted before we were able to execute this scheduled action.
+    /// scheduled action.
+    pub fn next_action_determined(&mut self) -> Result<Option<ReadResult>, ReadError> {
+        // Finalize the current processing action
+        if let Some(PosixScannerAction::Delete(path)) = take(&mut self.current_action) {
+            let cached_path = self.cached_paths.remove(path.as_path()).ok_or_else(|| {
+                ReadError::InconsistentState(format!("no cached copy of the deleted file {path:?}"))
+            })?;
+            // The copy may be shared by the files with the same contents
+            if !self
+                .cached_paths
+                .values()
+                .any(|other| *other == cached_path)
+            {
+                std::fs::remove_file(&cached_path)?;
+            }
+            let metadata_path = self.cached_metadata_path(&path)?.ok_or_else(|| {
+                ReadError::InconsistentState(format!(
+                    "no cached metadata of the deleted file {path:?}"
+                ))
+            })?;
+            let copy_name_path = self.cached_copy_name_path(&path)?.ok_or_else(|| {
+                ReadError::InconsistentState(format!(
+                    "no cached copy name of the deleted file {path:?}"
+                ))
+            })?;
+            for saved_path in [metadata_path, copy_name_path] {
+                match std::fs::remove_file(saved_path) {
+                    // The files cached before these were saved don't have them
+                    Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+                    result => result?,
+                }
+            }
         }
-    }
-}
 
-mod to_sql {
-    use std::error::Error;
+        // File modification is handled as combination of its deletion and insertion
+        // If a file was deleted in the last action, now we must add it, and after that
+        // we may allow commit
+        if let Some(next_file_for_insertion) = take(&mut self.next_file_for_insertion) {
+            if next_file_for_insertion.exists() {
+                return Ok(Some(
+                    self.initiate_file_insertion(&next_file_for_insertion)?,
+                ));
+            }
 
-    use bytes::BytesMut;
-    use chrono::{DateTime, NaiveDateTime, Utc};
-    use ordered_float::OrderedFloat;
-    use postgres::types::{to_sql_checked, Format, IsNull, ToSql, Type};
+            // The scheduled insertion after deletion is impossible because
+            // the file has already been deleted.
+            // The action was done in full now, and we can allow commits.
+            return Ok(Some(ReadResult::FinishedSource {
+                commit_allowed: true,
+            }));
+        }
 
-    use crate::engine::time::DateTime as _;
-    use crate::engine::Value;
+        // First check if we need to delete something
+        if self.streaming_mode.are_deletions_enabled() {
+            let next_for_deletion = self.next_deletion_entry()?;
+            if next_for_deletion.is_some() {
+                return Ok(next_for_deletion);
+            }
+        }
 
-    #[derive(Debug, Clone, thiserror::Error)]
-    #[error("cannot convert value of type {pathway_type:?} to Postgres type {postgres_type}")]
-    struct WrongPathwayType {
-        pathway_type: String,
-        postgres_type: Type,
+        // If there is nothing to delete, ingest the new entries
+        self.next_insertion_entry()
     }
 
-    impl ToSql for Value {
-        fn to_sql(
-            &self,
-            ty: &Type,
-            out: &mut BytesMut,
-        ) -> Result<IsNull, Box<dyn Error + Sync + Send>> {
-            macro_rules! try_forward {
-                ($type:ty, $expr:expr) => {
-                    if <$type as ToSql>::accepts(ty) {
-                        let value: $type = $expr.try_into()?;
-                        assert!(matches!(self.encode_format(ty), Format::Binary));
-                        assert!(matches!(value.encode_format(ty), Format::Binary));
-                        return value.to_sql(ty, out);
-                    }
-                };
-            }
-            #[allow(clippy::match_same_arms)]
-            let pathway_type = match self {
-                Self::None => return Ok(IsNull::Yes),
-                Self::Bool(b) => {
-                    try_forward!(bool, *b);
-                    "bool"
-                }
-                Self::Int(i) => {
-                    try_forward!(i64, *i);
-                    try_forward!(i32, *i);
-                    try_forward!(i16, *i);
-                    try_forward!(i8, *i);
-                    #[allow(clippy::cast_precision_loss)]
-                    {
-                        try_forward!(f64, *i as f64);
-                        try_forward!(f32, *i as f32);
+    fn next_deletion_entry(&mut self) -> Result<Option<ReadResult>, ReadError> {
+        let mut path_for_deletion: Option<PathBuf> = None;
+        for (path, modified_at) in &self.known_files {
+            let metadata = std::fs::metadata(path);
+            let needs_deletion = {
+                match metadata {
+                    Err(e) => e.kind() == std::io::ErrorKind::NotFound,
+                    Ok(metadata) => {
+                        if let Ok(new_modification_time) = metadata.modified() {
+                            let modified_at_new = new_modification_time
+                                .duration_since(SystemTime::UNIX_EPOCH)
+                                .expect("System time should be after the Unix epoch")
+                                .as_secs();
+                            modified_at_new != *modified_at
+                        } else {
+                            false
+                        }
                     }
-                    "int"
                 }
-                Self::Float(OrderedFloat(f)) => {
-                    try_forward!(f64, *f);
-                    #[allow(clippy::cast_possible_truncation)]
-                    {
-                        try_forward!(f32, *f as f32);
+            };
+            if needs_deletion {
+                match &path_for_deletion {
+                    None => path_for_deletion = Some(path.clone()),
+                    Some(other_path) => {
+                        if other_path > path {
+                            path_for_deletion = Some(path.clone());
+                        }
                     }
-                    "float"
-                }
-                Self::Pointer(p) => {
-                    try_forward!(String, p.to_string());
-                    "pointer"
-                }
-                Self::String(s) => {
-                    try_forward!(&str, s.as_str());
-                    "string"
-                }
-                Self::Bytes(b) => {
-                    try_forward!(&[u8], &b[..]);
-                    "bytes"
-                }
-                Self::Tuple(t) => {
-                    try_forward!(&[Value], &t[..]);
-                    "tuple"
-                }
-                Self::IntArray(_) => "int array",     // TODO
-                Self::FloatArray(_) => "float array", // TODO
-                Self::DateTimeNaive(dt) => {
-                    try_forward!(NaiveDateTime, dt.as_chrono_datetime());
-                    "naive date/time"
-                }
-                Self::DateTimeUtc(dt) => {
-                    try_forward!(DateTime<Utc>, dt.as_chrono_datetime().and_utc());
-                    "UTC date/time"
-                }
-                Self::Duration(_) => "duration", // TODO
-                Self::Json(j) => {
-                    try_forward!(&serde_json::Value, &**j);
-                    "JSON"
                 }
-                Self::Error => "error",
-                Self::PyObjectWrapper(_) => "PyObjectWrapper",
-            };
-            Err(Box::new(WrongPathwayType {
-                pathway_type: pathway_type.to_owned(),
-                postgres_type: ty.clone(),
-            }))
-        }
-
-        fn accepts(_ty: &Type) -> bool {
-            true // we double-check anyway
-        }
-
-        to_sql_checked!();
-    }
-}
-
-impl Writer for PsqlWriter {
-    fn write(&mut self, data: FormatterContext) -> Result<(), WriteError> {
-        self.buffer.push(data);
-        if let Some(max_batch_size) = self.max_batch_size {
-            if self.buffer.len() == max_batch_size {
-                self.flush(true)?;
             }
         }
-        Ok(())
-    }
-
-    fn flush(&mut self, _forced: bool) -> Result<(), WriteError> {
-        if self.buffer.is_empty() {
-            return Ok(());
-        }
-        let mut transaction = self.client.transaction()?;
-
-        for data in self.buffer.drain(..) {
-            let params: Vec<_> = data
-                .values
-                .iter()
-                .map(|v| v as &(dyn ToSql + Sync))
-                .collect();
 
-            for payload in &data.payloads {
-                let query = from_utf8(payload)?;
+        match path_for_deletion {
+            Some(path) => {
+                // Metadata of the deleted file must be the same as when it was added
+                // so that the deletion event is processed correctly by timely. To achieve
+                // this, we just take the cached metadata
+                let old_metadata = self.cached_metadata.remove(&path).ok_or_else(|| {
+                    ReadError::InconsistentState(format!(
+                        "the file {path:?} is known, but its metadata isn't cached"
+                    ))
+                })?;
 
-                transaction
-                    .execute(query, params.as_slice())
-                    .map_err(|error| WriteError::PsqlQueryFailed {
-                        query: query.to_string(),
-                        error,
-                    })?;
+                self.known_files.remove(&path.clone().clone());
+                self.current_action = Some(PosixScannerAction::Delete(Arc::new(path.clone())));
+                if path.exists() {
+                    self.next_file_for_insertion = Some(path);
+                }
+                Ok(Some(ReadResult::NewSource(old_metadata)))
             }
+            None => Ok(None),
         }
-
-        transaction.commit()?;
-
-        Ok(())
     }
 
-    fn single_threaded(&self) -> bool {
-        self.snapshot_mode
+    fn cached_file_path(&self, path: &Path) -> Option<PathBuf> {
+        self.cached_paths.get(path).cloned()
     }
-}
 
-pub struct CurrentlyProcessedS3Object {
-    loader_thread: std::thread::JoinHandle<Result<(), ReadError>>,
-    path: Arc<String>,
-}
+    /// Computes the path of the cached copy of a file according to the cache key
+    /// strategy. Returns `None` if the files aren't cached.
+    fn new_cached_file_path(&self, path: &Path) -> io::Result<Option<PathBuf>> {
+        let Some(root_path) = &self.cache_directory_path else {
+            return Ok(None);
+        };
+        let hash_function = self.cache_key_strategy.hash_function;
+        let file_name = if self.cache_key_strategy.hash_contents {
+            hash_function.hex_digest(File::open(path)?)?
+        } else {
+            hash_function.hex_digest(path.as_os_str().as_encoded_bytes())?
+        };
+        Ok(Some(root_path.join(file_name)))
+    }
 
-impl CurrentlyProcessedS3Object {
-    pub fn finalize(self) -> Result<(), ReadError> {
-        self.loader_thread.join().expect("s3 thread join failed")
+    /// Returns the path of the metadata saved along with the cached copy of a file.
+    /// Both are kept in the persistent storage, if it's configured, so that a deletion
+    /// after a restart has the same metadata as the insertion before it.
+    fn cached_metadata_path(&self, path: &Path) -> io::Result<Option<PathBuf>> {
+        self.saved_file_path(path, "metadata")
     }
-}
 
-pub struct S3Scanner {
-    /*
-        This class takes responsibility over S3 object selection and streaming.
-        In encapsulates the selection of the next object to stream and streaming
-        the object and provides reader end of the pipe to the outside user.
-    */
-    bucket: S3Bucket,
-    objects_prefix: String,
-    current_object: Option<CurrentlyProcessedS3Object>,
-    processed_objects: HashSet<String>,
-}
+    /// Returns the path of the file keeping the name of the cached copy of a file.
+    /// With the contents hashed, the name can't be computed again after a restart,
+    /// since the file may have been modified in the meantime.
+    fn cached_copy_name_path(&self, path: &Path) -> io::Result<Option<PathBuf>> {
+        self.saved_file_path(path, "copy")
+    }
 
-impl S3Scanner {
-    pub fn new(bucket: S3Bucket, objects_prefix: impl Into<String>) -> Result<Self, ReadError> {
-        let objects_prefix = objects_prefix.into();
+    /// Returns the path of a file saved along with the cached copy of a file. The
+    /// name is always derived from the path, since the cached copy may be shared.
+    fn saved_file_path(&self, path: &Path, extension: &str) -> io::Result<Option<PathBuf>> {
+        let Some(root_path) = &self.cache_directory_path else {
+            return Ok(None);
+        };
+        let path_hash = self
+            .cache_key_strategy
+            .hash_function
+            .hex_digest(path.as_os_str().as_encoded_bytes())?;
+        Ok(Some(root_path.join(format!("{path_hash}.{extension}"))))
+    }
 
-        let object_lists = bucket
-            .list(objects_prefix.clone(), None)
-            .map_err(|e| ReadError::S3(S3CommandName::ListObjectsV2, e))?;
-        let mut has_nonempty_list = false;
-        for list in object_lists {
-            if !list.contents.is_empty() {
-                has_nonempty_list = true;
-                break;
-            }
-        }
-        if !has_nonempty_list {
-            return Err(ReadError::NoObjectsToRead);
+    fn save_cached_copy_name(&self, path: &Path, cached_path: &Path) -> io::Result<()> {
+        if let Some(copy_name_path) = self.cached_copy_name_path(path)? {
+            let copy_name = cached_path.file_name().ok_or_else(|| {
+                io::Error::new(io::ErrorKind::InvalidInput, "cached copy has no name")
+            })?;
+            std::fs::write(copy_name_path, copy_name.as_encoded_bytes())?;
         }
-
-        Ok(S3Scanner {
-            bucket,
-            objects_prefix,
-
-            current_object: None,
-            processed_objects: HashSet::new(),
-        })
+        Ok(())
     }
 
-    pub fn stream_object_from_path_and_bucket(
-        object_path_ref: &str,
-        bucket: S3Bucket,
-    ) -> (CurrentlyProcessedS3Object, PipeReader) {
-        let object_path = object_path_ref.to_string();
-
-        let (pipe_reader, mut pipe_writer) = pipe::pipe();
-        let loader_thread = thread::Builder::new()
-            .name(format!("pathway:s3_get-{object_path_ref}"))
-            .spawn(move || {
-                let code = bucket
-                    .get_object_to_writer(&object_path, &mut pipe_writer)
-                    .map_err(|e| ReadError::S3(S3CommandName::GetObject, e))?;
-                if code != 200 {
-                    return Err(ReadError::S3(S3CommandName::GetObject, S3Error::HttpFail));
+    /// Returns the path of the cached copy of a file saved before a restart.
+    fn load_cached_file_path(&self, path: &Path) -> io::Result<Option<PathBuf>> {
+        let Some(copy_name_path) = self.cached_copy_name_path(path)? else {
+            return Ok(None);
+        };
+        match std::fs::read_to_string(&copy_name_path) {
+            Ok(copy_name) => Ok(Some(copy_name_path.with_file_name(copy_name))),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                // The name doesn't depend on the contents then, so it's still the same
+                if !self.cache_key_strategy.hash_contents {
+                    return self.new_cached_file_path(path);
                 }
-                Ok(())
-            })
-            .expect("s3 thread creation failed");
-
-        (
-            CurrentlyProcessedS3Object {
-                loader_thread,
-                path: Arc::new(object_path_ref.to_string()),
-            },
-            pipe_reader,
-        )
+                warn!("The name of the cached copy of {path:?} is unknown");
+                Ok(None)
+            }
+            Err(e) => Err(e),
+        }
     }
 
-    fn stream_object_from_path(&mut self, object_path_ref: &str) -> PipeReader {
-        let (current_object, pipe_reader) =
-            Self::stream_object_from_path_and_bucket(object_path_ref, self.bucket.deep_copy());
-        self.current_object = Some(current_object);
-        pipe_reader
+    fn save_cached_metadata(&self, path: &Path, metadata: &SourceMetadata) -> io::Result<()> {
+        if let Some(metadata_path) = self.cached_metadata_path(path)? {
+            std::fs::write(metadata_path, serde_json::to_vec(metadata)?)?;
+        }
+        Ok(())
     }
 
-    fn stream_next_object(&mut self) -> Result<Option<PipeReader>, ReadError> {
-        if let Some(state) = self.current_object.take() {
-            state.loader_thread.join().expect("s3 thread panic")?;
-        }
+    fn load_cached_metadata(&self, path: &Path) -> Option<SourceMetadata> {
+        let metadata_path = self.cached_metadata_path(path).ok()??;
+        let serialized_metadata = match std::fs::read(&metadata_path) {
+            Ok(serialized_metadata) => serialized_metadata,
+            Err(e) => {
+                if e.kind() != std::io::ErrorKind::NotFound {
+                    warn!("Failed to read the cached metadata of {path:?}: {e}");
+                }
+                return None;
+            }
+        };
+        serde_json::from_slice(&serialized_metadata)
+            .map_err(|e| warn!("Failed to parse the cached metadata of {path:?}: {e}"))
+            .ok()
+    }
 
-        let object_lists = self
-            .bucket
-            .list(self.objects_prefix.to_string(), None)
-            .map_err(|e| ReadError::S3(S3CommandName::ListObjectsV2, e))?;
+    /// Returns the files matching any of the globs. A file matched by several globs
+    /// is returned once, under the path it has in the first of them, so that it's
+    /// always identified by the same path.
+    fn get_matching_file_paths(&self) -> Result<Vec<PathBuf>, ReadError> {
+        let mut result = Vec::new();
+        let mut seen_files = HashSet::new();
+        let mut add_file = |entry: PathBuf| {
+            let canonical_path = std::fs::canonicalize(&entry).unwrap_or_else(|_| entry.clone());
+            if seen_files.insert(canonical_path) {
+                result.push(entry);
+            }
+        };
 
-        let mut selected_object: Option<(DateTime<FixedOffset>, String)> = None;
-        for list in &object_lists {
-            for object in &list.contents {
-                if self.processed_objects.contains(&object.key) {
+        for path in &self.paths {
+            let file_and_folder_paths = glob::glob(path.as_str())?.flatten();
+            for entry in file_and_folder_paths {
+                // If an entry is a file, it should just be added to result
+                if entry.is_file() {
+                    add_file(entry);
                     continue;
                 }
 
-                let Ok(last_modified) = DateTime::parse_from_rfc3339(&object.last_modified) else {
+                // Otherwise scan all files in all subdirectories and add them
+                let Some(path) = entry.to_str() else {
+                    error!("Non-unicode paths are not supported. Ignoring: {entry:?}");
                     continue;
                 };
 
-                match &selected_object {
-                    Some((earliest_modify_time, selected_object_name)) => {
-                        if (earliest_modify_time, selected_object_name)
-                            > (&last_modified, &object.key)
-                        {
-                            selected_object = Some((last_modified, object.key.clone()));
-                        }
+                let folder_scan_pattern = format!("{path}/**/{}", self.object_pattern);
+                let folder_contents = glob::glob(&folder_scan_pattern)?.flatten();
+                for nested_entry in folder_contents {
+                    if nested_entry.is_file() {
+                        add_file(nested_entry);
                     }
-                    None => selected_object = Some((last_modified, object.key.clone())),
-                };
+                }
             }
         }
 
-        match selected_object {
-            Some((_earliest_modify_time, selected_object_name)) => {
-                let pipe_reader = self.stream_object_from_path(&selected_object_name);
-                self.processed_objects.insert(selected_object_name);
-                Ok(Some(pipe_reader))
-            }
-            None => Ok(None),
-        }
+        Ok(result)
     }
 
-    fn seek_to_object(&mut self, path: &str) -> Result<(), ReadError> {
-        self.processed_objects.clear();
-
-        /*
-            S3 bucket-list calls are considered expensive, because of that we do one.
-            Then, two linear passes detect the files which should be marked.
-        */
-        let object_lists = self
-            .bucket
-            .list(self.objects_prefix.to_string(), None)
-            .map_err(|e| ReadError::S3(S3CommandName::ListObjectsV2, e))?;
-        let mut threshold_modification_time = None;
-        for list in &object_lists {
-            for object in &list.contents {
-                if object.key == path {
-                    let Ok(last_modified) = DateTime::parse_from_rfc3339(&object.last_modified)
-                    else {
-                        continue;
-                    };
-                    threshold_modification_time = Some(last_modified);
-                }
+    fn next_insertion_entry(&mut self) -> Result<Option<ReadResult>, ReadError> {
+        let matching_files: Vec<PathBuf> = self.get_matching_file_paths()?;
+        let mut selected_file: Option<(PathBuf, SystemTime)> = None;
+        for entry in matching_files {
+            if !entry.is_file() || self.known_files.contains_key(&(*entry)) {
+                continue;
             }
-        }
-        if let Some(threshold_modification_time) = threshold_modification_time {
-            let path = path.to_string();
-            for list in object_lists {
-                for object in list.contents {
-                    let Ok(last_modified) = DateTime::parse_from_rfc3339(&object.last_modified)
-                    else {
-                        continue;
-                    };
-                    if (last_modified, &object.key) < (threshold_modification_time, &path) {
-                        self.processed_objects.insert(object.key);
+
+            let Some(modify_time) = self.modify_time(&entry) else {
+                continue;
+            };
+
+            match &selected_file {
+                Some((currently_selected_name, selected_file_created_at)) => {
+                    if (selected_file_created_at, currently_selected_name) > (&modify_time, &entry)
+                    {
+                        selected_file = Some((entry, modify_time));
                     }
                 }
+                None => selected_file = Some((entry, modify_time)),
             }
-            self.processed_objects.insert(path);
-        } else {
-            self.processed_objects.clear();
         }
-
-        Ok(())
+
+        match selected_file {
+            Some((new_file_name, _)) => Ok(Some(self.initiate_file_insertion(&new_file_name)?)),
+            None => Ok(None),
+        }
+    }
+
+    fn initiate_file_insertion(&mut self, new_file_name: &PathBuf) -> io::Result<ReadResult> {
+        let now = self.clock.unix_timestamp_secs();
+        let new_file_meta =
+            SourceMetadata::from_fs_meta(new_file_name, &std::fs::metadata(new_file_name)?, now);
+        self.cached_metadata
+            .insert(new_file_name.clone(), Some(new_file_meta.clone()));
+        self.known_files.insert(
+            new_file_name.clone(),
+            new_file_meta.modified_at.unwrap_or(now),
+        );
+
+        if let Some(cached_path) = self.new_cached_file_path(new_file_name)? {
+            std::fs::copy(new_file_name, &cached_path)?;
+            self.save_cached_copy_name(new_file_name, &cached_path)?;
+            self.cached_paths.insert(new_file_name.clone(), cached_path);
+            self.save_cached_metadata(new_file_name, &new_file_meta)?;
+        }
+
+        self.current_action = Some(PosixScannerAction::Read(Arc::new(new_file_name.clone())));
+        Ok(ReadResult::NewSource(Some(new_file_meta)))
+    }
+
+    /// Waits until a change is reported in the watched directories or until the
+    /// refresh interval passes.
+    pub fn wait_for_new_files(&mut self) {
+        let refresh_interval = self.refresh_interval;
+        self.inotify
+            .as_mut()
+            .and_then(|inotify| inotify_support::wait(inotify, refresh_interval))
+            .unwrap_or_else(|| {
+                sleep(refresh_interval);
+            });
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ConnectorMode {
+    Static,
+    Streaming,
+}
+
+impl ConnectorMode {
+    pub fn is_polling_enabled(&self) -> bool {
+        match self {
+            ConnectorMode::Static => false,
+            ConnectorMode::Streaming => true,
+        }
+    }
+
+    pub fn are_deletions_enabled(&self) -> bool {
+        match self {
+            ConnectorMode::Static => false,
+            ConnectorMode::Streaming => true,
+        }
     }
+}
 
-    fn expect_current_object_path(&self) -> Arc<String> {
-        self.current_object
-            .as_ref()
-            .expect("current object should be present")
-            .path
-            .clone()
+/// The dialect of CSV data. The readers given the same dialect parse the data in the
+/// same way. The header isn't treated specially by the parser, it's read as the
+/// first record.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct CsvDialect {
+    pub delimiter: u8,
+    pub quote: u8,
+    pub escape: Option<u8>,
+    pub double_quote: bool,
+    pub quoting: bool,
+
+    // The lines starting with this byte are skipped. They aren't records, so they
+    // don't count as entries read, and the offsets skip over them on a seek
+    pub comment: Option<u8>,
+
+    // The default one accepts any of `\r`, `\n` and `\r\n`, even mixed in one file
+    pub terminator: csv::Terminator,
+
+    // If set, the records may have different numbers of fields. The ones lacking
+    // the needed columns are then reported by the parser
+    pub flexible: bool,
+}
+
+impl Default for CsvDialect {
+    fn default() -> Self {
+        Self {
+            delimiter: b',',
+            quote: b'"',
+            escape: None,
+            double_quote: true,
+            quoting: true,
+            comment: None,
+            terminator: csv::Terminator::CRLF,
+            flexible: true,
+        }
     }
 }
 
-pub struct S3CsvReader {
-    s3_scanner: S3Scanner,
-    poll_new_objects: bool,
+impl CsvDialect {
+    pub fn parser_builder(&self) -> csv::ReaderBuilder {
+        let mut builder = csv::ReaderBuilder::new();
+        builder
+            .delimiter(self.delimiter)
+            .quote(self.quote)
+            .escape(self.escape)
+            .double_quote(self.double_quote)
+            .quoting(self.quoting)
+            .comment(self.comment)
+            .terminator(self.terminator)
+            .flexible(self.flexible)
+            .has_headers(false);
+        builder
+    }
+}
 
+#[derive(Debug)]
+pub struct CsvFilesystemReader {
     parser_builder: csv::ReaderBuilder,
-    csv_reader: Option<csv::Reader<PipeReader>>,
-
     persistent_id: Option<PersistentId>,
-    deferred_read_result: Option<ReadResult>,
+
+    reader: Option<csv::Reader<std::fs::File>>,
+    filesystem_scanner: FilesystemScanner,
     total_entries_read: u64,
+    deferred_read_result: Option<ReadResult>,
+    max_consecutive_errors: usize,
+    last_activity: Option<Instant>,
+    shutdown_signal: ShutdownSignal,
+    idle_timeout: Option<Duration>,
+    emit_metadata_record: bool,
+    pending_metadata_record: Option<ReadResult>,
+    has_header: bool,
+    discovered_header: Option<Vec<String>>,
+    encoding: Option<TextEncoding>,
 }
 
-impl S3CsvReader {
+impl CsvFilesystemReader {
+    /// Creates a reader of the CSV files matching any of `paths`, configured by
+    /// `settings` in the same way as [`FilesystemReader::new`]. The header, which
+    /// is the first record of a file, is read regardless of the start position,
+    /// and the metadata record precedes it. The compression, the size limit and
+    /// the skipping of empty files don't apply to the CSV files and are ignored.
+    ///
+    /// The records are always parsed as flexible, regardless of `parser_builder`.
+    ///
+    /// If `has_header` is set, the header of each file isn't emitted as an entry.
+    /// Instead, the first header read is available via
+    /// [`CsvFilesystemReader::discovered_header`].
+    ///
+    /// If the encoding is given, the fields are decoded from it, otherwise they must
+    /// be valid UTF-8.
     pub fn new(
-        bucket: S3Bucket,
-        objects_prefix: impl Into<String>,
-        parser_builder: csv::ReaderBuilder,
-        poll_new_objects: bool,
+        paths: &[&str],
+        mut parser_builder: csv::ReaderBuilder,
+        streaming_mode: ConnectorMode,
         persistent_id: Option<PersistentId>,
-    ) -> Result<S3CsvReader, ReadError> {
-        Ok(S3CsvReader {
-            s3_scanner: S3Scanner::new(bucket, objects_prefix)?,
-            poll_new_objects,
-
+        has_header: bool,
+        settings: FilesystemReaderSettings,
+    ) -> Result<CsvFilesystemReader, ReadError> {
+        parser_builder.flexible(true);
+        Self::with_parser_builder(
+            paths,
             parser_builder,
-            csv_reader: None,
+            streaming_mode,
+            persistent_id,
+            has_header,
+            settings,
+        )
+    }
 
+    /// Creates a reader of the CSV files matching any of `paths`, which parses them
+    /// according to `dialect`. Otherwise it's the same as [`CsvFilesystemReader::new`].
+    pub fn with_dialect(
+        paths: &[&str],
+        dialect: CsvDialect,
+        streaming_mode: ConnectorMode,
+        persistent_id: Option<PersistentId>,
+        has_header: bool,
+        settings: FilesystemReaderSettings,
+    ) -> Result<CsvFilesystemReader, ReadError> {
+        Self::with_parser_builder(
+            paths,
+            dialect.parser_builder(),
+            streaming_mode,
             persistent_id,
-            deferred_read_result: None,
+            has_header,
+            settings,
+        )
+    }
+
+    fn with_parser_builder(
+        paths: &[&str],
+        parser_builder: csv::ReaderBuilder,
+        streaming_mode: ConnectorMode,
+        persistent_id: Option<PersistentId>,
+        has_header: bool,
+        settings: FilesystemReaderSettings,
+    ) -> Result<CsvFilesystemReader, ReadError> {
+        let FilesystemReaderSettings {
+            object_pattern,
+            refresh_interval,
+            max_consecutive_errors,
+            start_position,
+            emit_metadata_record,
+            encoding,
+            cache_key_strategy,
+            cache_directory,
+            ..
+        } = settings;
+        let filesystem_scanner = FilesystemScanner::new(
+            paths,
+            persistent_id,
+            streaming_mode,
+            &object_pattern,
+            refresh_interval,
+            start_position,
+            Arc::new(SystemClock),
+            cache_key_strategy,
+            cache_directory.as_deref(),
+        )?;
+        Ok(CsvFilesystemReader {
+            parser_builder,
+            persistent_id,
+
+            reader: None,
+            filesystem_scanner,
             total_entries_read: 0,
+            deferred_read_result: None,
+            max_consecutive_errors,
+            last_activity: None,
+            shutdown_signal: ShutdownSignal::default(),
+            idle_timeout: None,
+            emit_metadata_record,
+            pending_metadata_record: None,
+            has_header,
+            discovered_header: None,
+            encoding,
         })
     }
 
-    fn stream_next_object(&mut self) -> Result<bool, ReadError> {
-        if let Some(pipe_reader) = self.s3_scanner.stream_next_object()? {
-            self.csv_reader = Some(self.parser_builder.from_reader(pipe_reader));
-            Ok(true)
+    /// The header of the first file read, if the files are declared to have headers.
+    pub fn discovered_header(&self) -> Option<&[String]> {
+        self.discovered_header.as_deref()
+    }
+
+    fn discover_header(&mut self, header_record: &[String]) {
+        if self.discovered_header.is_none() {
+            self.discovered_header = Some(header_record.to_vec());
+        }
+    }
+
+    /// Reads the header of a file, so that it isn't emitted as an entry.
+    fn skip_header(&mut self, reader: &mut csv::Reader<File>) -> Result<(), ReadError> {
+        if let Some(header_record) = Self::read_fields(reader, self.encoding)? {
+            self.discover_header(&header_record);
+        }
+        Ok(())
+    }
+
+    /// Reads the next record, decoding its fields from `encoding` if it's given.
+    /// Returns `None` if there are no more records.
+    fn read_fields(
+        reader: &mut csv::Reader<File>,
+        encoding: Option<TextEncoding>,
+    ) -> Result<Option<Vec<String>>, ReadError> {
+        let Some(encoding) = encoding else {
+            let mut record = csv::StringRecord::new();
+            if !reader.read_record(&mut record)? {
+                return Ok(None);
+            }
+            return Ok(Some(
+                record
+                    .iter()
+                    .map(std::string::ToString::to_string)
+                    .collect(),
+            ));
+        };
+
+        let mut record = csv::ByteRecord::new();
+        if !reader.read_byte_record(&mut record)? {
+            return Ok(None);
+        }
+        let fields = record
+            .iter()
+            .map(|field| Ok(encoding.decode(field)?.into_owned()))
+            .collect::<Result<_, ReadError>>()?;
+        Ok(Some(fields))
+    }
+
+    /// Creates a parser over the file at `path`. All the files, including the ones
+    /// reinserted after a deletion, are opened with the same parser configuration.
+    fn open_parser(&self, path: &Path) -> Result<csv::Reader<File>, ReadError> {
+        Ok(self.parser_builder.from_path(path)?)
+    }
+
+    /// Opens the file of the current scanner action. If the reading starts past the
+    /// beginning of the file, the header is emitted right away and the reader is
+    /// moved to the start offset.
+    fn open_current_file(&mut self, path: &Path) -> Result<csv::Reader<File>, ReadError> {
+        let offset_file = self.filesystem_scanner.require_current_offset_file()?;
+        let mut reader = self.open_parser(path)?;
+
+        let is_present_at_start = self
+            .filesystem_scanner
+            .take_file_present_at_start(&offset_file);
+        let start_offset = self.filesystem_scanner.start_offset(&offset_file);
+        if !is_present_at_start && start_offset.is_none() {
+            if self.has_header {
+                self.skip_header(&mut reader)?;
+            }
+            return Ok(reader);
+        }
+
+        let Some(header_record) = Self::read_fields(&mut reader, self.encoding)? else {
+            return Ok(reader);
+        };
+        let start_offset = if let Some(start_offset) = start_offset {
+            if std::fs::metadata(path)?.len() < start_offset {
+                warn!("File {offset_file:?} became shorter than it was at the start, reading it from the beginning");
+                self.filesystem_scanner.forget_start_offset(&offset_file);
+                let mut reader = self.open_parser(path)?;
+                if self.has_header {
+                    self.skip_header(&mut reader)?;
+                }
+                return Ok(reader);
+            }
+            let mut seek_position = csv::Position::new();
+            seek_position.set_byte(start_offset);
+            reader.seek(seek_position)?;
+            start_offset
         } else {
-            Ok(false)
+            let mut byte_record = csv::ByteRecord::new();
+            while reader.read_byte_record(&mut byte_record)? {}
+            let start_offset = reader.position().byte();
+            self.filesystem_scanner
+                .set_start_offset(&offset_file, start_offset);
+            start_offset
+        };
+        if self.has_header {
+            self.discover_header(&header_record);
+            return Ok(reader);
         }
+
+        self.total_entries_read += 1;
+        let header_reader_context = ReaderContext::from_tokenized_entries(
+            self.filesystem_scanner.require_data_event_type()?,
+            header_record,
+        );
+        let offset = (
+            OffsetKey::Empty,
+            OffsetValue::FilePosition {
+                total_entries_read: self.total_entries_read,
+                path: offset_file,
+                bytes_offset: start_offset,
+            },
+        );
+        self.deferred_read_result = Some(ReadResult::Data(header_reader_context, offset));
+
+        Ok(reader)
     }
 
-    fn sleep_duration() -> Duration {
-        Duration::from_millis(10000)
+    /// Opens the file selected by the scanner after `new_source` was determined,
+    /// preparing its metadata record if needed.
+    fn open_next_file(&mut self, new_source: &ReadResult) -> Result<(), ReadError> {
+        let Some(selected_file) = self.filesystem_scanner.current_file() else {
+            return Ok(());
+        };
+        let total_entries_read = self.total_entries_read;
+        let reader = self.open_current_file(&selected_file)?;
+        if self.emit_metadata_record {
+            self.pending_metadata_record = source_metadata_record(
+                &self.filesystem_scanner,
+                new_source,
+                total_entries_read,
+                reader.position().byte(),
+            );
+        }
+        self.reader = Some(reader);
+        Ok(())
     }
 }
 
-impl Reader for S3CsvReader {
+impl Reader for CsvFilesystemReader {
     fn seek(&mut self, frontier: &OffsetAntichain) -> Result<(), ReadError> {
-        let offset_value = frontier.get_offset(&OffsetKey::Empty);
-        let Some(OffsetValue::S3ObjectPosition {
+        let Some(SeekPosition {
+            offset_value,
             total_entries_read,
-            path: path_arc,
+            path: file_path_arc,
             bytes_offset,
-        }) = offset_value
+            ..
+        }) = extract_seek_position(frontier, SeekOffsetKind::FilePosition)
         else {
-            if offset_value.is_some() {
-                warn!("Incorrect type of offset value in S3Csv frontier: {offset_value:?}");
-            }
             return Ok(());
         };
 
-        let path = (**path_arc).clone();
+        // Filesystem scanner part: detect already processed file
+        self.filesystem_scanner
+            .seek_to_file(file_path_arc.as_path())?;
+
+        // Seek within a particular file
+        self.total_entries_read = total_entries_read;
+        self.reader = {
+            // Since it's a CSV reader, we will need to fit the header in the parser first
+            let mut reader = self.open_parser(file_path_arc.as_path())?;
+            if self.has_header {
+                self.skip_header(&mut reader)?;
+            } else if bytes_offset > 0 {
+                if let Some(header_record) = Self::read_fields(&mut reader, self.encoding)? {
+                    let header_reader_context = ReaderContext::from_tokenized_entries(
+                        self.filesystem_scanner.require_data_event_type()?,
+                        header_record,
+                    );
 
-        self.s3_scanner.seek_to_object(&path)?;
-        let pipe_reader = self.s3_scanner.stream_object_from_path(&path);
-        let mut csv_reader = self.parser_builder.from_reader(pipe_reader);
+                    let offset = (OffsetKey::Empty, offset_value.clone());
 
-        let mut current_offset = 0;
-        if *bytes_offset > 0 {
-            let mut header_record = csv::StringRecord::new();
-            if csv_reader.read_record(&mut header_record)? {
-                let header_reader_context = ReaderContext::from_tokenized_entries(
-                    DataEventType::Insert, // Currently no deletions for S3
-                    header_record
-                        .iter()
-                        .map(std::string::ToString::to_string)
-                        .collect(),
-                );
-                let offset = (OffsetKey::Empty, offset_value.unwrap().clone());
-                let header_read_result = ReadResult::Data(header_reader_context, offset);
-                self.deferred_read_result = Some(header_read_result);
-                current_offset = csv_reader.position().byte();
-            } else {
-                error!("Empty S3 object, nothing to rewind");
-                return Ok(());
+                    let header_read_result = ReadResult::Data(header_reader_context, offset);
+                    self.deferred_read_result = Some(header_read_result);
+                }
             }
-        }
 
-        let mut byte_record = csv::ByteRecord::new();
-        while current_offset < *bytes_offset && csv_reader.read_byte_record(&mut byte_record)? {
-            current_offset = csv_reader.position().byte();
-        }
-        if current_offset != *bytes_offset {
-            error!("Inconsistent bytes position in rewinded CSV object: expected {current_offset}, got {}", *bytes_offset);
-        }
+            // The header may be skipped already, so the reader isn't rewound to the
+            // start of the file
+            if bytes_offset > 0 {
+                let mut seek_position = csv::Position::new();
+                seek_position.set_byte(bytes_offset);
+                reader.seek(seek_position)?;
+            }
 
-        self.total_entries_read = *total_entries_read;
-        self.csv_reader = Some(csv_reader);
+            Some(reader)
+        };
 
         Ok(())
     }
 
     fn read(&mut self) -> Result<ReadResult, ReadError> {
+        if let Some(metadata_record) = self.pending_metadata_record.take() {
+            return Ok(metadata_record);
+        }
         if let Some(deferred_read_result) = self.deferred_read_result.take() {
             return Ok(deferred_read_result);
         }
 
+        let read_started_at = Instant::now();
         loop {
-            match &mut self.csv_reader {
-                Some(csv_reader) => {
-                    let mut current_record = csv::StringRecord::new();
-                    if csv_reader.read_record(&mut current_record)? {
+            match &mut self.reader {
+                Some(reader) => {
+                    if let Some(current_record) = Self::read_fields(reader, self.encoding)? {
                         self.total_entries_read += 1;
 
                         let offset = (
                             OffsetKey::Empty,
-                            OffsetValue::S3ObjectPosition {
+                            OffsetValue::FilePosition {
                                 total_entries_read: self.total_entries_read,
-                                path: self.s3_scanner.expect_current_object_path(),
-                                bytes_offset: csv_reader.position().byte(),
+                                path: self.filesystem_scanner.require_current_offset_file()?,
+                                bytes_offset: reader.position().byte(),
                             },
                         );
 
+                        self.last_activity = Some(Instant::now());
                         return Ok(ReadResult::Data(
                             ReaderContext::from_tokenized_entries(
-                                DataEventType::Insert,
-                                current_record
-                                    .iter()
-                                    .map(std::string::ToString::to_string)
-                                    .collect(),
+                                self.filesystem_scanner.require_data_event_type()?,
+                                current_record,
                             ),
                             offset,
                         ));
                     }
-                    if self.stream_next_object()? {
-                        // No metadata is currently provided by S3 scanner
-                        return Ok(ReadResult::NewSource(None));
+
+                    let next_read_result = self.filesystem_scanner.next_action_determined()?;
+                    if let Some(next_read_result) = next_read_result {
+                        self.open_next_file(&next_read_result)?;
+                        return Ok(next_read_result);
                     }
+                    // The file came to its end, so we should drop the reader
+                    self.reader = None;
+                    return Ok(ReadResult::FinishedSource {
+                        commit_allowed: !self.filesystem_scanner.has_planned_insertion(),
+                    });
                 }
                 None => {
-                    if self.stream_next_object()? {
-                        // No metadata is currently provided by S3 scanner
-                        return Ok(ReadResult::NewSource(None));
+                    let next_read_result = self.filesystem_scanner.next_action_determined()?;
+                    if let Some(next_read_result) = next_read_result {
+                        self.open_next_file(&next_read_result)?;
+                        return Ok(next_read_result);
                     }
                 }
             }
 
-            if self.poll_new_objects {
-                sleep(Self::sleep_duration());
+            if self.filesystem_scanner.is_polling_enabled() && !self.shutdown_signal.is_requested()
+            {
+                if is_idle_timeout_reached(self.idle_timeout, read_started_at) {
+                    return Ok(ReadResult::FinishedSource {
+                        commit_allowed: true,
+                    });
+                }
+                self.filesystem_scanner.wait_for_new_files();
             } else {
                 return Ok(ReadResult::Finished);
             }
         }
     }
 
-    fn storage_type(&self) -> StorageType {
-        StorageType::S3Csv
-    }
-
     fn persistent_id(&self) -> Option<PersistentId> {
         self.persistent_id
     }
@@ -2032,340 +3834,279 @@ impl Reader for S3CsvReader {
     fn update_persistent_id(&mut self, persistent_id: Option<PersistentId>) {
         self.persistent_id = persistent_id;
     }
-}
 
-pub struct KafkaWriter {
-    producer: ThreadedProducer<DefaultProducerContext>,
-    topic: String,
-    header_fields: Vec<(String, usize)>,
-    key_field_index: Option<usize>,
-}
+    fn set_shutdown_signal(&mut self, signal: ShutdownSignal) {
+        self.shutdown_signal = signal;
+    }
 
-impl KafkaWriter {
-    pub fn new(
-        producer: ThreadedProducer<DefaultProducerContext>,
-        topic: String,
-        header_fields: Vec<(String, usize)>,
-        key_field_index: Option<usize>,
-    ) -> KafkaWriter {
-        KafkaWriter {
-            producer,
-            topic,
-            header_fields,
-            key_field_index,
-        }
+    fn set_idle_timeout(&mut self, timeout: Option<Duration>) {
+        self.idle_timeout = timeout;
     }
-}
 
-impl Drop for KafkaWriter {
-    fn drop(&mut self) {
-        self.producer.flush(None).expect("kafka commit should work");
+    fn storage_type(&self) -> StorageType {
+        StorageType::CsvFilesystem
+    }
+
+    fn max_allowed_consecutive_errors(&self) -> usize {
+        self.max_consecutive_errors
+    }
+
+    fn last_activity(&self) -> Option<Instant> {
+        self.last_activity
     }
 }
 
-impl Writer for KafkaWriter {
-    fn write(&mut self, data: FormatterContext) -> Result<(), WriteError> {
-        let key_as_bytes = match self.key_field_index {
-            Some(index) => match &data.values[index] {
-                Value::Bytes(bytes) => bytes.to_vec(),
-                Value::String(string) => string.as_bytes().to_vec(),
-                _ => {
-                    return Err(WriteError::IncorrectKeyFieldType(
-                        data.values[index].clone(),
-                    ))
-                }
-            },
-            None => data.key.0.to_le_bytes().to_vec(),
-        };
+pub struct PythonReaderBuilder {
+    subject: Py<PythonSubject>,
+    persistent_id: Option<PersistentId>,
+}
 
-        let mut headers = KafkaHeaders::new_with_capacity(self.header_fields.len() + 2)
-            .insert(KafkaHeader {
-                key: "pathway_time",
-                value: Some(data.time.to_string().as_bytes()),
-            })
-            .insert(KafkaHeader {
-                key: "pathway_diff",
-                value: Some(data.diff.to_string().as_bytes()),
-            });
-        for (name, position) in &self.header_fields {
-            let value: Vec<u8> = match &data.values[*position] {
-                Value::Bytes(b) => (*b).to_vec(),
-                other => (*other.to_string().as_bytes()).to_vec(),
-            };
-            headers = headers.insert(KafkaHeader {
-                key: name,
-                value: Some(&value),
-            });
-        }
+pub struct PythonReader {
+    subject: Py<PythonSubject>,
+    persistent_id: Option<PersistentId>,
+    total_entries_read: u64,
+    is_initialized: bool,
+    is_finished: bool,
+    columns: Option<Arc<ValuesMapColumns>>,
+    shutdown_signal: ShutdownSignal,
 
-        for payload in &data.payloads {
-            let mut entry = BaseRecord::<Vec<u8>, Vec<u8>>::to(&self.topic)
-                .payload(payload)
-                .headers(headers.clone())
-                .key(&key_as_bytes);
-            loop {
-                match self.producer.send(entry) {
-                    Ok(()) => break,
-                    Err((
-                        KafkaError::MessageProduction(RDKafkaErrorCode::QueueFull),
-                        unsent_entry,
-                    )) => {
-                        self.producer.poll(Duration::from_millis(10));
-                        entry = unsent_entry;
-                        continue;
-                    }
-                    Err((e, _unsent_entry)) => return Err(WriteError::Kafka(e)),
-                }
-            }
+    #[allow(unused)]
+    python_thread_state: PythonThreadState,
+}
+
+impl PythonReaderBuilder {
+    pub fn new(subject: Py<PythonSubject>, persistent_id: Option<PersistentId>) -> Self {
+        Self {
+            subject,
+            persistent_id,
         }
-        Ok(())
     }
+}
 
-    fn retriable(&self) -> bool {
-        true
+impl ReaderBuilder for PythonReaderBuilder {
+    fn build(self: Box<Self>) -> Result<Box<dyn Reader>, ReadError> {
+        let python_thread_state = PythonThreadState::new();
+        let Self {
+            subject,
+            persistent_id,
+        } = *self;
+
+        Ok(Box::new(PythonReader {
+            subject,
+            persistent_id,
+            python_thread_state,
+            total_entries_read: 0,
+            is_initialized: false,
+            is_finished: false,
+            columns: None,
+            shutdown_signal: ShutdownSignal::default(),
+        }))
     }
 
-    fn single_threaded(&self) -> bool {
-        false
+    fn is_internal(&self) -> bool {
+        self.subject.get().is_internal
     }
-}
 
-pub struct ElasticSearchWriter {
-    client: Elasticsearch,
-    index_name: String,
-    max_batch_size: Option<usize>,
+    fn persistent_id(&self) -> Option<PersistentId> {
+        self.persistent_id
+    }
 
-    docs_buffer: Vec<Vec<u8>>,
-}
+    fn update_persistent_id(&mut self, persistent_id: Option<PersistentId>) {
+        self.persistent_id = persistent_id;
+    }
 
-impl ElasticSearchWriter {
-    pub fn new(client: Elasticsearch, index_name: String, max_batch_size: Option<usize>) -> Self {
-        ElasticSearchWriter {
-            client,
-            index_name,
-            max_batch_size,
-            docs_buffer: Vec::new(),
-        }
+    fn storage_type(&self) -> StorageType {
+        StorageType::Python
     }
 }
 
-impl Writer for ElasticSearchWriter {
-    fn write(&mut self, data: FormatterContext) -> Result<(), WriteError> {
-        for payload in data.payloads {
-            self.docs_buffer.push(b"{\"index\": {}}".to_vec());
-            self.docs_buffer.push(payload);
+impl PythonReader {
+    /// Python dicts preserve the insertion order, so the rows of a subject usually
+    /// come with the same order of columns, which is then shared between them.
+    fn values_map_from_dict(&mut self, values: &PyDict) -> PyResult<ValuesMap> {
+        let columns_match = self.columns.as_ref().is_some_and(|columns| {
+            columns.len() == values.len()
+                && values
+                    .keys()
+                    .iter()
+                    .zip(columns.names())
+                    .all(|(key, column)| {
+                        key.extract::<&str>()
+                            .is_ok_and(|key| key == column.as_str())
+                    })
+        });
+        if !columns_match {
+            self.columns = Some(Arc::new(ValuesMapColumns::new(values.keys().extract()?)));
         }
+        let columns = self.columns.clone().expect("columns must be set");
+        ValuesMap::from_ordered(columns, values.values().extract()?)
+            .map_err(|e| PyValueError::new_err(e.to_string()))
+    }
+}
 
-        if let Some(max_batch_size) = self.max_batch_size {
-            if self.docs_buffer.len() / 2 >= max_batch_size {
-                self.flush(true)?;
+impl Reader for PythonReader {
+    fn seek(&mut self, frontier: &OffsetAntichain) -> Result<(), ReadError> {
+        let offset_value = frontier.get_offset(&OffsetKey::Empty);
+        let Some(OffsetValue::PythonEntrySequentialId(offset_value)) = offset_value else {
+            if offset_value.is_some() {
+                warn!("Incorrect type of offset value in Python frontier: {offset_value:?}");
             }
-        }
+            return Ok(());
+        };
+
+        self.total_entries_read = *offset_value;
 
         Ok(())
     }
 
-    fn flush(&mut self, _forced: bool) -> Result<(), WriteError> {
-        if self.docs_buffer.is_empty() {
-            return Ok(());
+    fn read(&mut self) -> Result<ReadResult, ReadError> {
+        if !self.is_initialized {
+            with_gil_and_pool(|py| self.subject.borrow(py).start.call0(py))?;
+            self.is_initialized = true;
+        }
+        if self.is_finished {
+            return Ok(ReadResult::Finished);
+        }
+
+        loop {
+            // The subject is asked for an entry with a timeout, so that the shutdown
+            // signal is checked while it has nothing to send
+            let read_result = with_gil_and_pool(|py| {
+                let entry = self
+                    .subject
+                    .borrow(py)
+                    .read
+                    .call1(py, (ShutdownSignal::CHECK_INTERVAL.as_secs_f64(),))?
+                    .into_ref(py);
+                if entry.is_none() {
+                    return Ok(None);
+                }
+                let (event, key, values): (DataEventType, Option<Value>, &PyDict) =
+                    entry.extract().map_err(ReadError::Py)?;
+                let key = key.map(|key| vec![key]);
+                let values = self.values_map_from_dict(values)?;
+
+                if event != DataEventType::Insert && !self.subject.borrow(py).deletions_enabled {
+                    return Err(ReadError::Py(PyValueError::new_err(
+                        "Trying to modify a row in the Python connector but deletions_enabled is set to False.",
+                    )));
+                }
+
+                if values.is_special(FINISH_LITERAL) {
+                    self.is_finished = true;
+                    self.subject.borrow(py).end.call0(py)?;
+                    Ok(Some(ReadResult::Finished))
+                } else {
+                    // We use simple sequential offset because Python connector is single threaded, as
+                    // by default.
+                    //
+                    // If it's changed, add worker_id to the offset.
+                    self.total_entries_read += 1;
+                    let offset = (
+                        OffsetKey::Empty,
+                        OffsetValue::PythonEntrySequentialId(self.total_entries_read),
+                    );
+
+                    Ok(Some(ReadResult::Data(
+                        ReaderContext::from_diff(event, key, values),
+                        offset,
+                    )))
+                }
+            })?;
+            if let Some(read_result) = read_result {
+                return Ok(read_result);
+            }
+            if self.shutdown_signal.is_requested() {
+                // The subject is ended in the same way as when it finishes by itself,
+                // so that an error it raised is reported
+                self.is_finished = true;
+                with_gil_and_pool(|py| self.subject.borrow(py).end.call0(py))?;
+                return Ok(ReadResult::Finished);
+            }
         }
-        tokio::runtime::Builder::new_current_thread()
-            .enable_all()
-            .build()
-            .unwrap()
-            .block_on(async {
-                self.client
-                    .bulk(BulkParts::Index(&self.index_name))
-                    .body(take(&mut self.docs_buffer))
-                    .send()
-                    .await
-                    .map_err(WriteError::Elasticsearch)?
-                    .error_for_status_code()
-                    .map_err(WriteError::Elasticsearch)?;
-
-                Ok(())
-            })
     }
 
-    fn single_threaded(&self) -> bool {
-        false
+    fn persistent_id(&self) -> Option<PersistentId> {
+        self.persistent_id
     }
-}
-
-#[derive(Default, Debug)]
-pub struct NullWriter;
 
-impl NullWriter {
-    pub fn new() -> Self {
-        Self
+    fn update_persistent_id(&mut self, persistent_id: Option<PersistentId>) {
+        self.persistent_id = persistent_id;
     }
-}
 
-impl Writer for NullWriter {
-    fn write(&mut self, _data: FormatterContext) -> Result<(), WriteError> {
-        Ok(())
+    fn set_shutdown_signal(&mut self, signal: ShutdownSignal) {
+        self.shutdown_signal = signal;
     }
 
-    fn single_threaded(&self) -> bool {
-        false
+    fn storage_type(&self) -> StorageType {
+        StorageType::Python
     }
 }
 
-pub struct S3GenericReader {
-    s3_scanner: S3Scanner,
-    poll_new_objects: bool,
-    read_method: ReadMethod,
-
-    reader: Option<BufReader<PipeReader>>,
+/// Reader of the entries given in advance. Allows testing the parsers and the
+/// connectors without touching the filesystem.
+pub struct MemoryReader {
+    entries: VecDeque<ReaderContext>,
     persistent_id: Option<PersistentId>,
     total_entries_read: u64,
-    current_bytes_read: u64,
-    deferred_read_result: Option<ReadResult>,
 }
 
-impl S3GenericReader {
-    pub fn new(
-        bucket: S3Bucket,
-        objects_prefix: impl Into<String>,
-        poll_new_objects: bool,
-        persistent_id: Option<PersistentId>,
-        read_method: ReadMethod,
-    ) -> Result<S3GenericReader, ReadError> {
-        Ok(S3GenericReader {
-            s3_scanner: S3Scanner::new(bucket, objects_prefix)?,
-            poll_new_objects,
-            read_method,
-
-            reader: None,
+impl MemoryReader {
+    pub fn new(entries: Vec<ReaderContext>, persistent_id: Option<PersistentId>) -> MemoryReader {
+        MemoryReader {
+            entries: entries.into(),
             persistent_id,
             total_entries_read: 0,
-            current_bytes_read: 0,
-            deferred_read_result: None,
-        })
-    }
-
-    fn stream_next_object(&mut self) -> Result<bool, ReadError> {
-        if let Some(pipe_reader) = self.s3_scanner.stream_next_object()? {
-            self.current_bytes_read = 0;
-            self.reader = Some(BufReader::new(pipe_reader));
-            Ok(true)
-        } else {
-            Ok(false)
         }
     }
 
-    fn sleep_duration() -> Duration {
-        Duration::from_millis(10000)
+    pub fn from_raw_bytes(
+        entries: Vec<(DataEventType, Vec<u8>)>,
+        persistent_id: Option<PersistentId>,
+    ) -> MemoryReader {
+        Self::new(
+            entries
+                .into_iter()
+                .map(|(event, bytes)| ReaderContext::from_raw_bytes(event, bytes))
+                .collect(),
+            persistent_id,
+        )
     }
 }
 
-impl Reader for S3GenericReader {
+impl Reader for MemoryReader {
     fn seek(&mut self, frontier: &OffsetAntichain) -> Result<(), ReadError> {
         let offset_value = frontier.get_offset(&OffsetKey::Empty);
-        let Some(OffsetValue::S3ObjectPosition {
-            total_entries_read,
-            path: path_arc,
-            bytes_offset,
-        }) = offset_value
-        else {
+        let Some(OffsetValue::PythonEntrySequentialId(offset_value)) = offset_value else {
             if offset_value.is_some() {
-                warn!("Incorrect type of offset value in S3Lines frontier: {offset_value:?}");
+                warn!("Incorrect type of offset value in memory reader frontier: {offset_value:?}");
             }
             return Ok(());
         };
 
-        let path = (**path_arc).clone();
-
-        self.s3_scanner.seek_to_object(&path)?;
-        let pipe_reader = self.s3_scanner.stream_object_from_path(&path);
-
-        let mut reader = BufReader::new(pipe_reader);
-        let mut bytes_read = 0;
-        while bytes_read < *bytes_offset {
-            let mut current_line = Vec::new();
-            let len = self
-                .read_method
-                .read_next_bytes(&mut reader, &mut current_line)?;
-            if len == 0 {
-                break;
-            }
-            bytes_read += len as u64;
-        }
-
-        if bytes_read != *bytes_offset {
-            if bytes_read == *bytes_offset + 1 || bytes_read == *bytes_offset + 2 {
-                error!("Read {} bytes instead of expected {bytes_read}. If the file did not have newline at the end, you can ignore this message", *bytes_offset);
-            } else {
-                error!("Inconsistent bytes position in rewinded plaintext object: expected {bytes_read}, got {}", *bytes_offset);
-            }
-        }
-
-        self.total_entries_read = *total_entries_read;
-        self.current_bytes_read = bytes_read;
-        self.reader = Some(reader);
+        // The offset is the number of entries read, so they are skipped
+        let entries_to_skip = (*offset_value).saturating_sub(self.total_entries_read);
+        let entries_to_skip = usize::try_from(entries_to_skip)
+            .unwrap_or(usize::MAX)
+            .min(self.entries.len());
+        self.entries.drain(..entries_to_skip);
+        self.total_entries_read = *offset_value;
 
         Ok(())
     }
 
     fn read(&mut self) -> Result<ReadResult, ReadError> {
-        if let Some(deferred_read_result) = self.deferred_read_result.take() {
-            return Ok(deferred_read_result);
-        }
-
-        loop {
-            match &mut self.reader {
-                Some(reader) => {
-                    let mut line = Vec::new();
-                    let len = self.read_method.read_next_bytes(reader, &mut line)?;
-                    if len > 0 || self.read_method == ReadMethod::Full {
-                        self.total_entries_read += 1;
-                        self.current_bytes_read += len as u64;
-
-                        let offset = (
-                            OffsetKey::Empty,
-                            OffsetValue::S3ObjectPosition {
-                                total_entries_read: self.total_entries_read,
-                                path: self.s3_scanner.expect_current_object_path(),
-                                bytes_offset: self.current_bytes_read,
-                            },
-                        );
-
-                        if self.read_method == ReadMethod::Full {
-                            self.deferred_read_result = Some(ReadResult::FinishedSource {
-                                commit_allowed: true,
-                            });
-                            self.reader = None;
-                        }
-
-                        return Ok(ReadResult::Data(
-                            ReaderContext::from_raw_bytes(DataEventType::Insert, line), // Currently no deletions for S3
-                            offset,
-                        ));
-                    }
-
-                    if self.stream_next_object()? {
-                        // No metadata is currently provided by S3 scanner
-                        return Ok(ReadResult::NewSource(None));
-                    }
-                }
-                None => {
-                    if self.stream_next_object()? {
-                        // No metadata is currently provided by S3 scanner
-                        return Ok(ReadResult::NewSource(None));
-                    }
-                }
-            }
-
-            if self.poll_new_objects {
-                sleep(Self::sleep_duration());
-            } else {
-                return Ok(ReadResult::Finished);
-            }
-        }
-    }
+        let Some(entry) = self.entries.pop_front() else {
+            return Ok(ReadResult::Finished);
+        };
 
-    fn storage_type(&self) -> StorageType {
-        StorageType::S3Lines
+        // The sequential offsets are the same as the ones of the Python connector
+        self.total_entries_read += 1;
+        let offset = (
+            OffsetKey::Empty,
+            OffsetValue::PythonEntrySequentialId(self.total_entries_read),
+        );
+        Ok(ReadResult::Data(entry, offset))
     }
 
     fn persistent_id(&self) -> Option<PersistentId> {
@@ -2375,448 +4116,309 @@ impl Reader for S3GenericReader {
     fn update_persistent_id(&mut self, persistent_id: Option<PersistentId>) {
         self.persistent_id = persistent_id;
     }
+
+    fn storage_type(&self) -> StorageType {
+        StorageType::Memory
+    }
 }
 
-impl FromSqlite for Value {
-    /// Convert raw `SQLite` field into one of internal value types
-    /// There are only five supported types: null, integer, real, text, blob
-    /// See also: <https://www.sqlite.org/datatype3.html>
-    fn column_result(value: SqliteValue<'_>) -> FromSqliteResult<Self> {
-        match value {
-            SqliteValue::Null => Ok(Value::None),
-            SqliteValue::Integer(val) => Ok(Value::Int(val)),
-            SqliteValue::Real(val) => Ok(Value::Float(val.into())),
-            SqliteValue::Text(val) => {
-                let parsed_string =
-                    from_utf8(val).map_err(|e| FromSqliteError::Other(Box::new(e)))?;
-                Ok(Value::String(parsed_string.into()))
-            }
-            SqliteValue::Blob(val) => Ok(Value::Bytes(val.into())),
+/// Decides when the buffered output is committed: as soon as the buffer holds
+/// `max_batch_size` entries, or on a flush if at least `min_commit_frequency` has
+/// passed since the last commit.
+#[derive(Debug)]
+pub struct CommitPolicy {
+    max_batch_size: Option<usize>,
+    min_commit_frequency: Option<Duration>,
+    last_commit_at: Instant,
+}
+
+impl CommitPolicy {
+    pub fn new(max_batch_size: Option<usize>, min_commit_frequency: Option<Duration>) -> Self {
+        Self {
+            max_batch_size,
+            min_commit_frequency,
+            last_commit_at: Instant::now(),
         }
     }
-}
 
-const SQLITE_DATA_VERSION_PRAGMA: &str = "data_version";
+    pub fn is_batch_full(&self, buffered_entries: usize) -> bool {
+        self.max_batch_size
+            .is_some_and(|max_batch_size| buffered_entries >= max_batch_size)
+    }
 
-pub struct SqliteReader {
-    connection: SqliteConnection,
-    table_name: String,
-    column_names: Vec<String>,
+    pub fn is_commit_due(&self, buffered_entries: usize, forced: bool) -> bool {
+        buffered_entries > 0
+            && (forced
+                || self
+                    .min_commit_frequency
+                    .map_or(true, |f| self.last_commit_at.elapsed() >= f))
+    }
 
-    last_saved_data_version: Option<i64>,
-    stored_state: HashMap<i64, ValuesMap>,
-    queued_updates: VecDeque<ReadResult>,
+    pub fn on_commit(&mut self) {
+        self.last_commit_at = Instant::now();
+    }
 }
 
-impl SqliteReader {
-    pub fn new(
-        connection: SqliteConnection,
-        table_name: String,
-        column_names: Vec<String>,
-    ) -> Self {
-        Self {
-            connection,
-            table_name,
-            column_names,
+#[derive(Default, Debug)]
+pub struct NullWriter;
 
-            last_saved_data_version: None,
-            queued_updates: VecDeque::new(),
-            stored_state: HashMap::new(),
-        }
+impl NullWriter {
+    pub fn new() -> Self {
+        Self
     }
+}
 
-    /// Data version is required to check if there was an update in the database.
-    /// There are also hooks, but they only work for changes happened in the same
-    /// connection.
-    /// More details why hooks don't help here: <https://sqlite.org/forum/forumpost/3174b39eeb79b6a4>
-    pub fn data_version(&self) -> i64 {
-        let version: ::rusqlite::Result<i64> = self.connection.pragma_query_value(
-            Some(::rusqlite::DatabaseName::Main),
-            SQLITE_DATA_VERSION_PRAGMA,
-            |row| row.get(0),
-        );
-        version.expect("pragma.data_version request should not fail")
+impl Writer for NullWriter {
+    fn write(&mut self, _data: FormatterContext) -> Result<(), WriteError> {
+        Ok(())
     }
 
-    fn load_table(&mut self) -> Result<(), ReadError> {
-        let query = format!(
-            "SELECT {},_rowid_ FROM {}",
-            self.column_names.join(","),
-            self.table_name
-        );
+    fn single_threaded(&self) -> bool {
+        false
+    }
+}
 
-        let mut statement = self.connection.prepare(&query)?;
-        let mut rows = statement.query([])?;
-
-        let mut present_rowids = HashSet::new();
-        while let Some(row) = rows.next()? {
-            let rowid: i64 = row.get(self.column_names.len())?;
-            let mut values = HashMap::with_capacity(self.column_names.len());
-            for (column_idx, column_name) in self.column_names.iter().enumerate() {
-                values.insert(column_name.clone(), row.get(column_idx)?);
-            }
-            let values: ValuesMap = values.into();
-            self.stored_state
-                .entry(rowid)
-                .and_modify(|current_values| {
-                    if current_values != &values {
-                        let key = vec![Value::Int(rowid)];
-                        self.queued_updates.push_back(ReadResult::Data(
-                            ReaderContext::from_diff(
-                                DataEventType::Delete,
-                                Some(key.clone()),
-                                take(current_values),
-                            ),
-                            EMPTY_OFFSET,
-                        ));
-                        self.queued_updates.push_back(ReadResult::Data(
-                            ReaderContext::from_diff(
-                                DataEventType::Insert,
-                                Some(key),
-                                values.clone(),
-                            ),
-                            EMPTY_OFFSET,
-                        ));
-                        current_values.clone_from(&values);
-                    }
-                })
-                .or_insert_with(|| {
-                    let key = vec![Value::Int(rowid)];
-                    self.queued_updates.push_back(ReadResult::Data(
-                        ReaderContext::from_diff(DataEventType::Insert, Some(key), values.clone()),
-                        EMPTY_OFFSET,
-                    ));
-                    values
-                });
-            present_rowids.insert(rowid);
-        }
+pub(crate) const SPECIAL_OUTPUT_FIELDS: [(&str, Type); 2] =
+    [("time", Type::Int), ("diff", Type::Int)];
 
-        self.stored_state.retain(|rowid, values| {
-            if present_rowids.contains(rowid) {
-                true
-            } else {
-                let key = vec![Value::Int(*rowid)];
-                self.queued_updates.push_back(ReadResult::Data(
-                    ReaderContext::from_diff(DataEventType::Delete, Some(key), take(values)),
-                    EMPTY_OFFSET,
-                ));
-                false
-            }
-        });
+/// The rows buffered by the columnar writers until a commit, stored by columns. The
+/// values of each row are followed by its `time` and `diff` and, optionally, by the
+/// deletion flag, in the order of the written schema.
+#[derive(Debug)]
+pub(crate) struct BufferedColumns {
+    columns: Vec<Vec<Value>>,
+    value_columns_count: usize,
+    with_deletion_flag: bool,
+}
 
-        if !self.queued_updates.is_empty() {
-            self.queued_updates.push_back(ReadResult::FinishedSource {
-                commit_allowed: true,
-            });
+impl BufferedColumns {
+    pub(crate) fn new(value_columns_count: usize, with_deletion_flag: bool) -> Self {
+        let columns_count =
+            value_columns_count + SPECIAL_OUTPUT_FIELDS.len() + usize::from(with_deletion_flag);
+        Self {
+            columns: vec![Vec::new(); columns_count],
+            value_columns_count,
+            with_deletion_flag,
         }
+    }
 
+    pub(crate) fn push_row(&mut self, data: FormatterContext) -> Result<(), WriteError> {
+        let time = i64::try_from(data.time.0).map_err(|_| WriteError::TimeOutOfRange(data.time))?;
+        let diff = i64::try_from(data.diff).map_err(|_| WriteError::DiffOutOfRange(data.diff))?;
+        for (column, value) in self.columns.iter_mut().zip(data.values) {
+            column.push(value);
+        }
+        let time_column_idx = self.value_columns_count;
+        self.columns[time_column_idx].push(Value::Int(time));
+        self.columns[time_column_idx + 1].push(Value::Int(diff));
+        if self.with_deletion_flag {
+            self.columns[time_column_idx + 2].push(Value::Bool(diff < 0));
+        }
         Ok(())
     }
 
-    fn wait_period() -> Duration {
-        Duration::from_millis(500)
+    pub(crate) fn columns(&self) -> &[Vec<Value>] {
+        &self.columns
     }
-}
 
-impl Reader for SqliteReader {
-    fn seek(&mut self, _frontier: &OffsetAntichain) -> Result<(), ReadError> {
-        todo!("seek is not supported for Sqlite source: persistent history of changes unavailable")
+    #[cfg(feature = "clickhouse")]
+    pub(crate) fn value_columns(&self) -> &[Vec<Value>] {
+        &self.columns[..self.value_columns_count]
     }
 
-    fn read(&mut self) -> Result<ReadResult, ReadError> {
-        loop {
-            if let Some(queued_update) = self.queued_updates.pop_front() {
-                return Ok(queued_update);
-            }
+    pub(crate) fn time_column(&self) -> &[Value] {
+        &self.columns[self.value_columns_count]
+    }
 
-            let current_data_version = self.data_version();
-            if self.last_saved_data_version != Some(current_data_version) {
-                self.load_table()?;
-                self.last_saved_data_version = Some(current_data_version);
-                return Ok(ReadResult::NewSource(None));
-            }
-            // Sleep to avoid non-stop pragma requests of a table
-            // that did not change
-            sleep(Self::wait_period());
-        }
+    pub(crate) fn diff_column(&self) -> &[Value] {
+        &self.columns[self.value_columns_count + 1]
     }
 
-    fn storage_type(&self) -> StorageType {
-        StorageType::Sqlite
+    pub(crate) fn rows_count(&self) -> usize {
+        self.time_column().len()
     }
 
-    fn persistent_id(&self) -> Option<PersistentId> {
-        None
+    pub(crate) fn is_empty(&self) -> bool {
+        self.rows_count() == 0
     }
 
-    fn update_persistent_id(&mut self, persistent_id: Option<PersistentId>) {
-        if persistent_id.is_some() {
-            unimplemented!("persistence is not supported for Sqlite data source")
+    pub(crate) fn clear(&mut self) {
+        for column in &mut self.columns {
+            column.clear();
         }
     }
 }
 
-const SPECIAL_OUTPUT_FIELDS: [(&str, Type); 2] = [("time", Type::Int), ("diff", Type::Int)];
-
-pub struct DeltaTableWriter {
-    table: DeltaTable,
-    writer: DTRecordBatchWriter,
-    schema: Arc<ArrowSchema>,
-    buffered_columns: Vec<Vec<Value>>,
-    min_commit_frequency: Option<Duration>,
-    last_commit_at: Instant,
+pub(crate) fn downcast_arrow_array<T: 'static>(column: &dyn ArrowArray) -> Result<&T, ReadError> {
+    column
+        .as_any()
+        .downcast_ref::<T>()
+        .ok_or_else(|| ReadError::UnsupportedArrowType(column.data_type().clone()))
 }
 
-impl DeltaTableWriter {
-    pub fn new(
-        path: &str,
-        value_fields: &Vec<ValueField>,
-        storage_options: HashMap<String, String>,
-        min_commit_frequency: Option<Duration>,
-    ) -> Result<Self, WriteError> {
-        let schema = Arc::new(Self::construct_schema(value_fields)?);
-        let table = Self::open_table(path, value_fields, storage_options)?;
-        let writer = DTRecordBatchWriter::for_table(&table)?;
-
-        let mut empty_buffered_columns = Vec::new();
-        for _ in 0..schema.all_fields().len() {
-            empty_buffered_columns.push(Vec::new());
-        }
-        Ok(Self {
-            table,
-            writer,
-            schema,
-            buffered_columns: empty_buffered_columns,
-            min_commit_frequency,
-
-            // before the first commit, the time should be
-            // measured from the moment of the start
-            last_commit_at: Instant::now(),
-        })
+/// Converts a single cell of an Arrow column into an engine value.
+/// This is the inverse of `DeltaTableWriter::arrow_array_for_type`.
+fn arrow_value_at(column: &dyn ArrowArray, row_index: usize) -> Result<Value, ReadError> {
+    if column.is_null(row_index) {
+        return Ok(Value::None);
     }
 
-    fn array_of_target_type<ElementType>(
-        values: &Vec<Value>,
-        mut to_simple_type: impl FnMut(&Value) -> Result<ElementType, WriteError>,
-    ) -> Result<Vec<Option<ElementType>>, WriteError> {
-        let mut values_vec: Vec<Option<ElementType>> = Vec::new();
-        for value in values {
-            if matches!(value, Value::None) {
-                values_vec.push(None);
-                continue;
-            }
-            values_vec.push(Some(to_simple_type(value)?));
+    let value = match column.data_type() {
+        ArrowDataType::Boolean => {
+            Value::Bool(downcast_arrow_array::<ArrowBooleanArray>(column)?.value(row_index))
         }
-        Ok(values_vec)
-    }
-
-    fn arrow_array_for_type(
-        type_: &ArrowDataType,
-        values: &Vec<Value>,
-    ) -> Result<Arc<dyn ArrowArray>, WriteError> {
-        match type_ {
-            ArrowDataType::Boolean => {
-                let v = Self::array_of_target_type::<bool>(values, |v| match v {
-                    Value::Bool(b) => Ok(*b),
-                    _ => Err(WriteError::TypeMismatchWithSchema(v.clone(), type_.clone())),
-                })?;
-                Ok(Arc::new(ArrowBooleanArray::from(v)))
-            }
-            ArrowDataType::Int64 => {
-                let v = Self::array_of_target_type::<i64>(values, |v| match v {
-                    Value::Int(i) => Ok(*i),
-                    Value::Duration(d) => Ok(d.microseconds()),
-                    _ => Err(WriteError::TypeMismatchWithSchema(v.clone(), type_.clone())),
-                })?;
-                Ok(Arc::new(ArrowInt64Array::from(v)))
-            }
-            ArrowDataType::Float64 => {
-                let v = Self::array_of_target_type::<f64>(values, |v| match v {
-                    Value::Float(f) => Ok((*f).into()),
-                    _ => Err(WriteError::TypeMismatchWithSchema(v.clone(), type_.clone())),
-                })?;
-                Ok(Arc::new(ArrowFloat64Array::from(v)))
-            }
-            ArrowDataType::Utf8 => {
-                let v = Self::array_of_target_type::<String>(values, |v| match v {
-                    Value::String(s) => Ok(s.to_string()),
-                    Value::Pointer(p) => Ok(p.to_string()),
-                    Value::Json(j) => Ok(j.to_string()),
-                    _ => Err(WriteError::TypeMismatchWithSchema(v.clone(), type_.clone())),
-                })?;
-                Ok(Arc::new(ArrowStringArray::from(v)))
+        ArrowDataType::Int64 => {
+            Value::Int(downcast_arrow_array::<ArrowInt64Array>(column)?.value(row_index))
+        }
+        ArrowDataType::Float64 => Value::Float(
+            downcast_arrow_array::<ArrowFloat64Array>(column)?
+                .value(row_index)
+                .into(),
+        ),
+        ArrowDataType::Utf8 => Value::String(
+            downcast_arrow_array::<ArrowStringArray>(column)?
+                .value(row_index)
+                .into(),
+        ),
+        ArrowDataType::Binary => Value::Bytes(
+            downcast_arrow_array::<ArrowBinaryArray>(column)?
+                .value(row_index)
+                .into(),
+        ),
+        ArrowDataType::Timestamp(ArrowTimeUnit::Microsecond, None) => {
+            let timestamp = downcast_arrow_array::<ArrowTimestampArray>(column)?.value(row_index);
+            Value::DateTimeNaive(EngineDateTimeNaive::new(timestamp * 1_000))
+        }
+        ArrowDataType::Timestamp(ArrowTimeUnit::Microsecond, Some(_)) => {
+            let timestamp = downcast_arrow_array::<ArrowTimestampArray>(column)?.value(row_index);
+            Value::DateTimeUtc(EngineDateTimeUtc::new(timestamp * 1_000))
+        }
+        ArrowDataType::Duration(ArrowTimeUnit::Microsecond) => {
+            let duration =
+                downcast_arrow_array::<ArrowDurationMicrosecondArray>(column)?.value(row_index);
+            Value::Duration(EngineDuration::new(duration * 1_000))
+        }
+        ArrowDataType::List(_) => {
+            let items = downcast_arrow_array::<ArrowListArray>(column)?.value(row_index);
+            if items.null_count() > 0 {
+                return Err(ReadError::UnsupportedArrowType(column.data_type().clone()));
             }
-            ArrowDataType::Binary => {
-                let mut vec_owned = Self::array_of_target_type::<Vec<u8>>(values, |v| match v {
-                    Value::Bytes(b) => Ok(b.to_vec()),
-                    _ => Err(WriteError::TypeMismatchWithSchema(v.clone(), type_.clone())),
-                })?;
-                let mut vec_refs = Vec::new();
-                for item in &mut vec_owned {
-                    vec_refs.push(item.as_mut().map(|v| v.as_slice()));
+            match items.data_type() {
+                ArrowDataType::Int64 => {
+                    let items = downcast_arrow_array::<ArrowInt64Array>(items.as_ref())?;
+                    Value::from(Array1::from_vec(items.values().to_vec()).into_dyn())
                 }
-                Ok(Arc::new(ArrowBinaryArray::from(vec_refs)))
-            }
-            ArrowDataType::Timestamp(ArrowTimeUnit::Microsecond, None) => {
-                let v = Self::array_of_target_type::<i64>(values, |v| match v {
-                    #[allow(clippy::cast_possible_truncation)]
-                    Value::DateTimeNaive(dt) => Ok(dt.timestamp_microseconds()),
-                    _ => Err(WriteError::TypeMismatchWithSchema(v.clone(), type_.clone())),
-                })?;
-                Ok(Arc::new(ArrowTimestampArray::from(v)))
-            }
-            ArrowDataType::Timestamp(ArrowTimeUnit::Microsecond, Some(tz)) => {
-                let v = Self::array_of_target_type::<i64>(values, |v| match v {
-                    #[allow(clippy::cast_possible_truncation)]
-                    Value::DateTimeUtc(dt) => Ok(dt.timestamp_microseconds()),
-                    _ => Err(WriteError::TypeMismatchWithSchema(v.clone(), type_.clone())),
-                })?;
-                Ok(Arc::new(ArrowTimestampArray::from(v).with_timezone(&**tz)))
+                ArrowDataType::Float64 => {
+                    let items = downcast_arrow_array::<ArrowFloat64Array>(items.as_ref())?;
+                    Value::from(Array1::from_vec(items.values().to_vec()).into_dyn())
+                }
+                _ => return Err(ReadError::UnsupportedArrowType(column.data_type().clone())),
             }
-            _ => panic!("provided type {type_} is unknown to the engine"),
         }
+        other => return Err(ReadError::UnsupportedArrowType(other.clone())),
+    };
+    Ok(value)
+}
+
+pub(crate) fn values_map_from_record_batch_row(
+    batch: &DTRecordBatch,
+    row_index: usize,
+) -> Result<ValuesMap, ReadError> {
+    let schema = batch.schema();
+    let mut values = HashMap::with_capacity(batch.num_columns());
+    for (field, column) in schema.fields().iter().zip(batch.columns()) {
+        values.insert(
+            field.name().clone(),
+            arrow_value_at(column.as_ref(), row_index)?,
+        );
     }
+    Ok(values.into())
+}
+
+/// Builds the inner reader and wraps it, so that the wrappers such as
+/// [`ThrottledReader`] also apply to the readers built only on the worker. The
+/// description of the builder is that of the inner one, so the wrapped reader
+/// keeps its name.
+pub struct WrappedReaderBuilder {
+    inner: Box<dyn ReaderBuilder>,
+    wrap: Box<dyn FnOnce(Box<dyn Reader>) -> Box<dyn Reader> + Send>,
+}
 
-    fn prepare_delta_batch(&self) -> Result<DTRecordBatch, WriteError> {
-        let mut data_columns = Vec::new();
-        for (index, column) in self.buffered_columns.iter().enumerate() {
-            data_columns.push(Self::arrow_array_for_type(
-                self.schema.field(index).data_type(),
-                column,
-            )?);
+impl WrappedReaderBuilder {
+    pub fn new(
+        inner: Box<dyn ReaderBuilder>,
+        wrap: impl FnOnce(Box<dyn Reader>) -> Box<dyn Reader> + Send + 'static,
+    ) -> Self {
+        Self {
+            inner,
+            wrap: Box::new(wrap),
         }
-        Ok(DTRecordBatch::try_new(self.schema.clone(), data_columns)?)
     }
+}
 
-    fn delta_table_primitive_type(type_: Type) -> Result<DeltaTableKernelType, WriteError> {
-        Ok(DeltaTableKernelType::Primitive(match type_ {
-            Type::Bool => DeltaTablePrimitiveType::Boolean,
-            Type::Float => DeltaTablePrimitiveType::Double,
-            Type::Pointer | Type::String | Type::Json => DeltaTablePrimitiveType::String,
-            Type::Bytes => DeltaTablePrimitiveType::Binary,
-            Type::DateTimeNaive => DeltaTablePrimitiveType::TimestampNtz,
-            Type::DateTimeUtc => DeltaTablePrimitiveType::Timestamp,
-            Type::Int | Type::Duration => DeltaTablePrimitiveType::Long,
-            Type::Any | Type::Array | Type::Tuple | Type::PyObjectWrapper => {
-                return Err(WriteError::UnsupportedType(type_))
-            }
-        }))
+impl ReaderBuilder for WrappedReaderBuilder {
+    fn build(self: Box<Self>) -> Result<Box<dyn Reader>, ReadError> {
+        let Self { inner, wrap } = *self;
+        Ok(wrap(inner.build()?))
     }
 
-    fn arrow_data_type(type_: Type) -> Result<ArrowDataType, WriteError> {
-        Ok(match type_ {
-            Type::Bool => ArrowDataType::Boolean,
-            Type::Int | Type::Duration => ArrowDataType::Int64,
-            Type::Float => ArrowDataType::Float64,
-            Type::Pointer | Type::String | Type::Json => ArrowDataType::Utf8,
-            Type::Bytes => ArrowDataType::Binary,
-            // DeltaLake timestamps are stored in microseconds:
-            // https://docs.rs/deltalake/latest/deltalake/kernel/enum.PrimitiveType.html#variant.Timestamp
-            Type::DateTimeNaive => ArrowDataType::Timestamp(ArrowTimeUnit::Microsecond, None),
-            Type::DateTimeUtc => {
-                ArrowDataType::Timestamp(ArrowTimeUnit::Microsecond, Some("UTC".into()))
-            }
-            Type::Any | Type::Array | Type::Tuple | Type::PyObjectWrapper => {
-                return Err(WriteError::UnsupportedType(type_))
-            }
-        })
+    fn short_description(&self) -> Cow<'static, str> {
+        self.inner.short_description()
     }
 
-    pub fn construct_schema(value_fields: &Vec<ValueField>) -> Result<ArrowSchema, WriteError> {
-        let mut schema_fields: Vec<ArrowField> = Vec::new();
-        for field in value_fields {
-            schema_fields.push(ArrowField::new(
-                field.name.clone(),
-                Self::arrow_data_type(field.type_)?,
-                field.is_optional,
-            ));
-        }
-        for (field, type_) in SPECIAL_OUTPUT_FIELDS {
-            schema_fields.push(ArrowField::new(field, Self::arrow_data_type(type_)?, false));
-        }
-        Ok(ArrowSchema::new(schema_fields))
+    fn is_internal(&self) -> bool {
+        self.inner.is_internal()
     }
 
-    fn create_async_runtime() -> Result<TokioRuntime, WriteError> {
-        // Deadlocks if new_current_thread is used
-        Ok(tokio::runtime::Builder::new_multi_thread()
-            .enable_all()
-            .build()?)
+    fn persistent_id(&self) -> Option<PersistentId> {
+        self.inner.persistent_id()
     }
 
-    pub fn open_table(
-        path: &str,
-        schema_fields: &Vec<ValueField>,
-        storage_options: HashMap<String, String>,
-    ) -> Result<DeltaTable, WriteError> {
-        let mut struct_fields = Vec::new();
-        for field in schema_fields {
-            struct_fields.push(DeltaTableStructField::new(
-                field.name.clone(),
-                Self::delta_table_primitive_type(field.type_)?,
-                field.is_optional,
-            ));
-        }
-        for (field, type_) in SPECIAL_OUTPUT_FIELDS {
-            struct_fields.push(DeltaTableStructField::new(
-                field,
-                Self::delta_table_primitive_type(type_)?,
-                false,
-            ));
-        }
-
-        let runtime = Self::create_async_runtime()?;
-        let table: DeltaTable = runtime
-            .block_on(async {
-                let builder = DeltaTableCreateBuilder::new()
-                    .with_location(path)
-                    .with_save_mode(DeltaTableSaveMode::Append)
-                    .with_columns(struct_fields)
-                    .with_storage_options(storage_options.clone());
-
-                builder.await
-            })
-            .or_else(
-                |e| {
-                    warn!("Unable to create DeltaTable for output: {e}. Trying to open the existing one by this path.");
-                    runtime.block_on(async {
-                        open_delta_table(path, storage_options).await
-                    })
-                }
-            )?;
-
-        Ok(table)
+    fn update_persistent_id(&mut self, persistent_id: Option<PersistentId>) {
+        self.inner.update_persistent_id(persistent_id);
     }
-}
 
-impl Writer for DeltaTableWriter {
-    fn write(&mut self, data: FormatterContext) -> Result<(), WriteError> {
-        for (index, value) in data.values.into_iter().enumerate() {
-            self.buffered_columns[index].push(value);
-        }
-        let time_column_idx = self.buffered_columns.len() - 2;
-        let diff_column_idx = self.buffered_columns.len() - 1;
-        self.buffered_columns[time_column_idx].push(Value::Int(data.time.0.try_into().unwrap()));
-        self.buffered_columns[diff_column_idx].push(Value::Int(data.diff.try_into().unwrap()));
-        Ok(())
+    fn storage_type(&self) -> StorageType {
+        self.inner.storage_type()
     }
+}
 
-    fn flush(&mut self, forced: bool) -> Result<(), WriteError> {
-        let commit_needed = !self.buffered_columns[0].is_empty()
-            && (self
-                .min_commit_frequency
-                .map_or(true, |f| self.last_commit_at.elapsed() >= f)
-                || forced);
-        if commit_needed {
-            Self::create_async_runtime()?.block_on(async {
-                self.writer.write(self.prepare_delta_batch()?).await?;
-                self.writer.flush_and_commit(&mut self.table).await?;
-                for column in &mut self.buffered_columns {
-                    column.clear();
-                }
-                Ok::<(), WriteError>(())
-            })?;
-        }
-        Ok(())
+#[cfg(test)]
+mod tests {
+    use crate::connectors::data_format::FormatterContext;
+    use crate::engine::{Key, Timestamp, Value};
+
+    use super::{message_headers, DataEventType};
+
+    #[test]
+    fn test_message_headers() {
+        let data = FormatterContext::new_single_payload(
+            b"payload".to_vec(),
+            Key::random(),
+            vec![
+                Value::Int(1),
+                Value::String("sensor".into()),
+                Value::Bytes(vec![0, 255].into()),
+            ],
+            Timestamp(4),
+            1,
+        )
+        .with_event_type(DataEventType::Upsert);
+        let header_fields = [
+            ("id".to_string(), 0),
+            ("source".to_string(), 1),
+            ("raw".to_string(), 2),
+        ];
+        assert_eq!(
+            message_headers(&header_fields, &data),
+            vec![
+                ("pathway_time", b"4".to_vec()),
+                ("pathway_diff", b"1".to_vec()),
+                ("pathway_event", b"upsert".to_vec()),
+                ("id", b"1".to_vec()),
+                ("source", b"\"sensor\"".to_vec()),
+                ("raw", vec![0, 255]),
+            ]
+        );
     }
 }
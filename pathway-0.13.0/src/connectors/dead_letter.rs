@@ -0,0 +1,226 @@
+// Copyright © 2024 Pathway
+
+use std::time::{Duration, Instant};
+
+use log::{error, warn};
+
+use crate::connectors::data_format::FormatterContext;
+use crate::connectors::data_storage::{
+    ReadError, ReadResult, Reader, ShutdownSignal, StorageType, WriteError, Writer,
+};
+use crate::engine::{Key, Timestamp, Value};
+use crate::persistence::frontier::OffsetAntichain;
+use crate::persistence::PersistentId;
+use crate::timestamp::current_unix_timestamp_ms;
+
+/// Builds the record sent to a dead-letter destination: the payload is a JSON object
+/// with the error and the offending data, while the values are the data and the error.
+fn dead_letter_context(data: &[u8], error: &str, key: Key, time: Timestamp) -> FormatterContext {
+    let payload = serde_json::json!({
+        "error": error,
+        "data": String::from_utf8_lossy(data),
+    });
+    FormatterContext::new_single_payload(
+        payload.to_string().into_bytes(),
+        key,
+        vec![Value::Bytes(data.into()), Value::String(error.into())],
+        time,
+        1,
+    )
+}
+
+/// Reads from the inner reader, routing the malformed entries to a dead-letter
+/// writer instead of failing. If there are more than
+/// `max_allowed_consecutive_errors` of the inner reader such entries in a row, the
+/// error is returned, and since the wrapper itself doesn't allow any errors, it stops
+/// the reading.
+///
+/// The dead-letter records contain the entry as it was read, along with the
+/// description of the error. Since the entry doesn't reach the engine, the time of
+/// the record is the time it was read at.
+pub struct DeadLetterReader {
+    inner: Box<dyn Reader>,
+    dead_letter: Box<dyn Writer>,
+    consecutive_errors: usize,
+}
+
+impl DeadLetterReader {
+    pub fn new(inner: Box<dyn Reader>, dead_letter: Box<dyn Writer>) -> Self {
+        Self {
+            inner,
+            dead_letter,
+            consecutive_errors: 0,
+        }
+    }
+}
+
+impl Reader for DeadLetterReader {
+    fn read(&mut self) -> Result<ReadResult, ReadError> {
+        loop {
+            match self.inner.read() {
+                Ok(read_result) => {
+                    self.consecutive_errors = 0;
+                    return Ok(read_result);
+                }
+                Err(error) => {
+                    let Some(raw_entry) = error.raw_entry() else {
+                        return Err(error);
+                    };
+                    self.consecutive_errors += 1;
+                    if self.consecutive_errors > self.inner.max_allowed_consecutive_errors() {
+                        return Err(error);
+                    }
+                    warn!("Sending a malformed entry to the dead-letter destination: {error}");
+                    let description = error.to_string();
+                    let key = Key::for_values(&[
+                        Value::Bytes(raw_entry.into()),
+                        Value::String(description.as_str().into()),
+                    ]);
+                    let time = u64::try_from(current_unix_timestamp_ms())
+                        .expect("number of milliseconds should fit in 64 bits");
+                    let dead_letter_result = self
+                        .dead_letter
+                        .write(dead_letter_context(
+                            raw_entry,
+                            &description,
+                            key,
+                            Timestamp(time),
+                        ))
+                        .and_then(|()| self.dead_letter.flush(false));
+                    if let Err(e) = dead_letter_result {
+                        error!("Failed to write to the dead-letter destination: {e}");
+                        return Err(error);
+                    }
+                }
+            }
+        }
+    }
+
+    fn seek(&mut self, frontier: &OffsetAntichain) -> Result<(), ReadError> {
+        self.inner.seek(frontier)
+    }
+
+    fn update_persistent_id(&mut self, persistent_id: Option<PersistentId>) {
+        self.inner.update_persistent_id(persistent_id);
+    }
+
+    fn persistent_id(&self) -> Option<PersistentId> {
+        self.inner.persistent_id()
+    }
+
+    fn set_shutdown_signal(&mut self, signal: ShutdownSignal) {
+        self.inner.set_shutdown_signal(signal);
+    }
+
+    fn set_idle_timeout(&mut self, timeout: Option<Duration>) {
+        self.inner.set_idle_timeout(timeout);
+    }
+
+    fn on_commit(&mut self, frontier: &OffsetAntichain) -> Result<(), ReadError> {
+        self.inner.on_commit(frontier)
+    }
+
+    fn storage_type(&self) -> StorageType {
+        self.inner.storage_type()
+    }
+
+    fn last_activity(&self) -> Option<Instant> {
+        self.inner.last_activity()
+    }
+
+    fn is_healthy(&self) -> bool {
+        self.inner.is_healthy()
+    }
+}
+
+/// Writes to the inner writer, routing the rows that don't fit the schema of the
+/// destination to a dead-letter writer instead of failing. If there are more than
+/// `max_consecutive_errors` such rows in a row, the error is returned.
+pub struct DeadLetterWriter {
+    inner: Box<dyn Writer>,
+    dead_letter: Box<dyn Writer>,
+    max_consecutive_errors: usize,
+    consecutive_errors: usize,
+}
+
+impl DeadLetterWriter {
+    pub fn new(
+        inner: Box<dyn Writer>,
+        dead_letter: Box<dyn Writer>,
+        max_consecutive_errors: usize,
+    ) -> Self {
+        Self {
+            inner,
+            dead_letter,
+            max_consecutive_errors,
+            consecutive_errors: 0,
+        }
+    }
+
+    fn is_routed_to_dead_letter(error: &WriteError) -> bool {
+        matches!(error, WriteError::TypeMismatchWithSchema(..))
+    }
+}
+
+impl Writer for DeadLetterWriter {
+    fn write(&mut self, data: FormatterContext) -> Result<(), WriteError> {
+        let (key, time) = (data.key, data.time);
+        // If the formatter produced no payloads, the values are the offending data
+        let raw_data = if data.payloads.is_empty() {
+            vec![serde_json::to_vec(&data.values)?]
+        } else {
+            data.payloads.clone()
+        };
+
+        match self.inner.write(data) {
+            Ok(()) => {
+                self.consecutive_errors = 0;
+                Ok(())
+            }
+            Err(error) if Self::is_routed_to_dead_letter(&error) => {
+                self.consecutive_errors += 1;
+                if self.consecutive_errors > self.max_consecutive_errors {
+                    return Err(error);
+                }
+                warn!("Sending a row to the dead-letter destination: {error}");
+                let error = error.to_string();
+                for raw_data in raw_data {
+                    self.dead_letter
+                        .write(dead_letter_context(&raw_data, &error, key, time))?;
+                }
+                Ok(())
+            }
+            Err(error) => Err(error),
+        }
+    }
+
+    fn flush(&mut self, forced: bool) -> Result<(), WriteError> {
+        self.dead_letter.flush(forced)?;
+        self.inner.flush(forced)
+    }
+
+    fn on_commit(&mut self, time: Timestamp) -> Result<(), WriteError> {
+        self.dead_letter.on_commit(time)?;
+        self.inner.on_commit(time)
+    }
+
+    fn finish(self: Box<Self>) -> Result<(), WriteError> {
+        let Self {
+            inner, dead_letter, ..
+        } = *self;
+        dead_letter.finish()?;
+        inner.finish()
+    }
+
+    fn rows_written_since_last_flush(&self) -> usize {
+        self.inner.rows_written_since_last_flush()
+    }
+
+    fn retriable(&self) -> bool {
+        self.inner.retriable() && self.dead_letter.retriable()
+    }
+
+    fn single_threaded(&self) -> bool {
+        self.inner.single_threaded() || self.dead_letter.single_threaded()
+    }
+}
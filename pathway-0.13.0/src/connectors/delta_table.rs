@@ -0,0 +1,1023 @@
+// Copyright © 2024 Pathway
+
+use std::collections::{BTreeSet, HashMap, HashSet, VecDeque};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use deltalake::arrow::array::{
+    new_null_array, Array as ArrowArray, ArrayRef as ArrowArrayRef,
+    BinaryArray as ArrowBinaryArray, BooleanArray as ArrowBooleanArray,
+    DurationMicrosecondArray as ArrowDurationMicrosecondArray, Float64Array as ArrowFloat64Array,
+    Int64Array as ArrowInt64Array, ListArray as ArrowListArray, RecordBatch as DTRecordBatch,
+    StringArray as ArrowStringArray, TimestampMicrosecondArray as ArrowTimestampArray,
+};
+use deltalake::arrow::buffer::{NullBuffer as ArrowNullBuffer, OffsetBuffer as ArrowOffsetBuffer};
+use deltalake::arrow::datatypes::{
+    DataType as ArrowDataType, Field as ArrowField, Schema as ArrowSchema,
+    TimeUnit as ArrowTimeUnit,
+};
+use deltalake::datafusion::common::Column as DataFusionColumn;
+use deltalake::datafusion::parquet::arrow::arrow_reader::{
+    ParquetRecordBatchReader, ParquetRecordBatchReaderBuilder,
+};
+use deltalake::datafusion::prelude::{lit, Expr as DataFusionExpr, SessionContext};
+use deltalake::kernel::{
+    ArrayType as DeltaTableArrayType, DataType as DeltaTableKernelType,
+    MetadataValue as DeltaTableMetadataValue, PrimitiveType as DeltaTablePrimitiveType,
+    StructField as DeltaTableStructField,
+};
+use deltalake::operations::create::CreateBuilder as DeltaTableCreateBuilder;
+use deltalake::protocol::SaveMode as DeltaTableSaveMode;
+use deltalake::writer::{DeltaWriter, RecordBatchWriter as DTRecordBatchWriter};
+use deltalake::{
+    open_table_with_storage_options as open_delta_table, DeltaOps, DeltaTable, DeltaTableError,
+    Path as DeltaTablePath,
+};
+use log::warn;
+use tokio::runtime::Runtime as TokioRuntime;
+
+use crate::connectors::data_format::FormatterContext;
+use crate::connectors::data_storage::{
+    downcast_arrow_array, values_map_from_record_batch_row, BufferedColumns, ConnectorMode,
+    DataEventType, ReadError, ReadResult, Reader, ReaderContext, ShutdownSignal, StorageType,
+    ValuesMap, WriteError, Writer, WrittenRowsCounter, SPECIAL_OUTPUT_FIELDS,
+};
+use crate::connectors::{OffsetKey, OffsetValue};
+use crate::engine::{Type, Value};
+use crate::persistence::frontier::OffsetAntichain;
+use crate::persistence::PersistentId;
+use crate::python_api::ValueField;
+
+/// Name of the optional column marking the deletions in the append mode of the
+/// Delta Lake output.
+pub const DELETION_FLAG_FIELD: &str = "_pw_is_deleted";
+
+/// Metadata key of the Delta Lake columns holding durations. The protocol has no
+/// type for them, so they're stored as integers in the unit given by the value.
+pub const DURATION_UNIT_METADATA_KEY: &str = "pathway.duration_unit";
+
+/// Defines how the changes of the output table are stored in a delta table.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub enum DeltaTableWriteMode {
+    /// Every change is appended as a row along with its `time` and `diff`.
+    #[default]
+    Append,
+
+    /// The table keeps the current state of the output: the changes are merged into
+    /// it by the given key columns, deleting the rows removed from the output.
+    Upsert { key_columns: Vec<String> },
+}
+
+pub struct DeltaTableWriter {
+    table: DeltaTable,
+    writer: DTRecordBatchWriter,
+    write_mode: DeltaTableWriteMode,
+    // Schema of the written fields, used for the merge source in the upsert mode
+    written_schema: Arc<ArrowSchema>,
+    // Schema of the table, which may differ from the written fields in column order
+    schema: Arc<ArrowSchema>,
+    // Index of the table column for each of the buffered columns
+    column_indices: Vec<usize>,
+    buffered_columns: BufferedColumns,
+    min_commit_frequency: Option<Duration>,
+    last_commit_at: Instant,
+    written_rows: WrittenRowsCounter,
+
+    // The runtime is declared last so that it's dropped after the table and the writer,
+    // which may still have pending tasks bound to it
+    runtime: TokioRuntime,
+}
+
+impl DeltaTableWriter {
+    /// Creates a writer for the delta table at `path`.
+    ///
+    /// If `write_deletion_flag` is set, each appended row also gets a boolean
+    /// `_pw_is_deleted` column, which is true for the rows with a negative `diff`.
+    /// It lets the readers unaware of the `diff` semantics filter out the deletions.
+    pub fn new(
+        path: &str,
+        value_fields: &Vec<ValueField>,
+        storage_options: HashMap<String, String>,
+        min_commit_frequency: Option<Duration>,
+        write_mode: DeltaTableWriteMode,
+        write_deletion_flag: bool,
+    ) -> Result<Self, WriteError> {
+        let written_schema = Self::construct_schema(value_fields, write_deletion_flag)?;
+        if let DeltaTableWriteMode::Upsert { key_columns } = &write_mode {
+            if write_deletion_flag {
+                return Err(WriteError::DeltaTableDeletionFlagInUpsertMode);
+            }
+            if key_columns.is_empty() {
+                return Err(WriteError::DeltaTableNoKeyColumns);
+            }
+            for name in key_columns {
+                if !value_fields.iter().any(|field| &field.name == name) {
+                    return Err(WriteError::DeltaTableKeyColumnMissing(name.clone()));
+                }
+            }
+        }
+        let runtime = Self::create_async_runtime()?;
+        let table = Self::open_table(
+            &runtime,
+            path,
+            value_fields,
+            storage_options,
+            write_deletion_flag,
+        )?;
+        let writer = DTRecordBatchWriter::for_table(&table)?;
+        let schema = Arc::new(ArrowSchema::try_from(table.get_schema()?)?);
+        let column_indices = Self::map_columns(&written_schema, &schema)?;
+        let written_schema = Self::with_table_types(&written_schema, &schema, &column_indices);
+
+        Ok(Self {
+            table,
+            writer,
+            write_mode,
+            written_schema: Arc::new(written_schema),
+            schema,
+            column_indices,
+            buffered_columns: BufferedColumns::new(value_fields.len(), write_deletion_flag),
+            min_commit_frequency,
+
+            // before the first commit, the time should be
+            // measured from the moment of the start
+            last_commit_at: Instant::now(),
+            written_rows: WrittenRowsCounter::default(),
+            runtime,
+        })
+    }
+
+    fn array_of_target_type<ElementType>(
+        values: &Vec<Value>,
+        mut to_simple_type: impl FnMut(&Value) -> Result<ElementType, WriteError>,
+    ) -> Result<Vec<Option<ElementType>>, WriteError> {
+        let mut values_vec: Vec<Option<ElementType>> = Vec::new();
+        for value in values {
+            if matches!(value, Value::None) {
+                values_vec.push(None);
+                continue;
+            }
+            values_vec.push(Some(to_simple_type(value)?));
+        }
+        Ok(values_vec)
+    }
+
+    /// Checks that the value fits into a column of the given type, so that a mismatch
+    /// is reported for the row that has it rather than for the whole batch.
+    fn check_value_type(type_: &ArrowDataType, value: &Value) -> Result<(), WriteError> {
+        let is_accepted = match type_ {
+            _ if matches!(value, Value::None) => true,
+            ArrowDataType::Boolean => matches!(value, Value::Bool(_)),
+            // The durations are stored as microseconds in the long columns of the tables
+            ArrowDataType::Int64 => matches!(value, Value::Int(_) | Value::Duration(_)),
+            ArrowDataType::Duration(ArrowTimeUnit::Microsecond) => {
+                matches!(value, Value::Duration(_))
+            }
+            ArrowDataType::Float64 => matches!(value, Value::Float(_)),
+            ArrowDataType::Utf8 => {
+                matches!(value, Value::String(_) | Value::Pointer(_) | Value::Json(_))
+            }
+            ArrowDataType::Binary => matches!(value, Value::Bytes(_)),
+            ArrowDataType::Timestamp(ArrowTimeUnit::Microsecond, None) => {
+                matches!(value, Value::DateTimeNaive(_))
+            }
+            ArrowDataType::Timestamp(ArrowTimeUnit::Microsecond, Some(_)) => {
+                matches!(value, Value::DateTimeUtc(_))
+            }
+            ArrowDataType::List(_) => match value {
+                Value::IntArray(array) => return Self::check_array_is_flat(array.shape()),
+                Value::FloatArray(array) => return Self::check_array_is_flat(array.shape()),
+                _ => false,
+            },
+            _ => true,
+        };
+        if is_accepted {
+            Ok(())
+        } else {
+            Err(WriteError::TypeMismatchWithSchema(
+                value.clone(),
+                type_.clone(),
+            ))
+        }
+    }
+
+    fn arrow_array_for_type(
+        type_: &ArrowDataType,
+        values: &Vec<Value>,
+    ) -> Result<Arc<dyn ArrowArray>, WriteError> {
+        match type_ {
+            ArrowDataType::Boolean => {
+                let v = Self::array_of_target_type::<bool>(values, |v| match v {
+                    Value::Bool(b) => Ok(*b),
+                    _ => Err(WriteError::TypeMismatchWithSchema(v.clone(), type_.clone())),
+                })?;
+                Ok(Arc::new(ArrowBooleanArray::from(v)))
+            }
+            ArrowDataType::Int64 => {
+                let v = Self::array_of_target_type::<i64>(values, |v| match v {
+                    Value::Int(i) => Ok(*i),
+                    Value::Duration(d) => Ok(d.microseconds()),
+                    _ => Err(WriteError::TypeMismatchWithSchema(v.clone(), type_.clone())),
+                })?;
+                Ok(Arc::new(ArrowInt64Array::from(v)))
+            }
+            ArrowDataType::Duration(ArrowTimeUnit::Microsecond) => {
+                let v = Self::array_of_target_type::<i64>(values, |v| match v {
+                    Value::Duration(d) => Ok(d.microseconds()),
+                    _ => Err(WriteError::TypeMismatchWithSchema(v.clone(), type_.clone())),
+                })?;
+                Ok(Arc::new(ArrowDurationMicrosecondArray::from(v)))
+            }
+            ArrowDataType::Float64 => {
+                let v = Self::array_of_target_type::<f64>(values, |v| match v {
+                    Value::Float(f) => Ok((*f).into()),
+                    _ => Err(WriteError::TypeMismatchWithSchema(v.clone(), type_.clone())),
+                })?;
+                Ok(Arc::new(ArrowFloat64Array::from(v)))
+            }
+            ArrowDataType::Utf8 => {
+                let v = Self::array_of_target_type::<String>(values, |v| match v {
+                    Value::String(s) => Ok(s.to_string()),
+                    Value::Pointer(p) => Ok(p.to_string()),
+                    Value::Json(j) => Ok(j.to_string()),
+                    _ => Err(WriteError::TypeMismatchWithSchema(v.clone(), type_.clone())),
+                })?;
+                Ok(Arc::new(ArrowStringArray::from(v)))
+            }
+            ArrowDataType::Binary => {
+                let mut vec_owned = Self::array_of_target_type::<Vec<u8>>(values, |v| match v {
+                    Value::Bytes(b) => Ok(b.to_vec()),
+                    _ => Err(WriteError::TypeMismatchWithSchema(v.clone(), type_.clone())),
+                })?;
+                let mut vec_refs = Vec::new();
+                for item in &mut vec_owned {
+                    vec_refs.push(item.as_mut().map(|v| v.as_slice()));
+                }
+                Ok(Arc::new(ArrowBinaryArray::from(vec_refs)))
+            }
+            ArrowDataType::Timestamp(ArrowTimeUnit::Microsecond, None) => {
+                let v = Self::array_of_target_type::<i64>(values, |v| match v {
+                    #[allow(clippy::cast_possible_truncation)]
+                    Value::DateTimeNaive(dt) => Ok(dt.timestamp_microseconds()),
+                    _ => Err(WriteError::TypeMismatchWithSchema(v.clone(), type_.clone())),
+                })?;
+                Ok(Arc::new(ArrowTimestampArray::from(v)))
+            }
+            ArrowDataType::Timestamp(ArrowTimeUnit::Microsecond, Some(tz)) => {
+                let v = Self::array_of_target_type::<i64>(values, |v| match v {
+                    #[allow(clippy::cast_possible_truncation)]
+                    Value::DateTimeUtc(dt) => Ok(dt.timestamp_microseconds()),
+                    _ => Err(WriteError::TypeMismatchWithSchema(v.clone(), type_.clone())),
+                })?;
+                Ok(Arc::new(ArrowTimestampArray::from(v).with_timezone(&**tz)))
+            }
+            ArrowDataType::List(item_field)
+                if *item_field.data_type() == ArrowDataType::Float64 =>
+            {
+                let mut items = Vec::new();
+                let mut lengths = Vec::with_capacity(values.len());
+                let mut validity = Vec::with_capacity(values.len());
+                for value in values {
+                    let length = match value {
+                        Value::None => 0,
+                        Value::FloatArray(array) => {
+                            Self::check_array_is_flat(array.shape())?;
+                            items.extend(array.iter().copied());
+                            array.len()
+                        }
+                        // The element type of an array column isn't known in advance,
+                        // so the integers are stored as floats too
+                        #[allow(clippy::cast_precision_loss)]
+                        Value::IntArray(array) => {
+                            Self::check_array_is_flat(array.shape())?;
+                            items.extend(array.iter().map(|item| *item as f64));
+                            array.len()
+                        }
+                        _ => {
+                            return Err(WriteError::TypeMismatchWithSchema(
+                                value.clone(),
+                                type_.clone(),
+                            ))
+                        }
+                    };
+                    lengths.push(length);
+                    validity.push(!matches!(value, Value::None));
+                }
+                Ok(Arc::new(ArrowListArray::try_new(
+                    item_field.clone(),
+                    ArrowOffsetBuffer::from_lengths(lengths),
+                    Arc::new(ArrowFloat64Array::from(items)),
+                    Some(ArrowNullBuffer::from(validity)),
+                )?))
+            }
+            _ => panic!("provided type {type_} is unknown to the engine"),
+        }
+    }
+
+    fn check_array_is_flat(shape: &[usize]) -> Result<(), WriteError> {
+        if shape.len() == 1 {
+            Ok(())
+        } else {
+            Err(WriteError::MultidimensionalArray(shape.to_vec()))
+        }
+    }
+
+    /// Matches the written columns with the columns of an existing table by name,
+    /// since the table may have been created with a different order of columns.
+    ///
+    /// All differences between the schemas are collected, so that a misconfigured
+    /// table is reported at once. A single difference is returned as is, while
+    /// several ones are wrapped into `WriteError::DeltaTableSchemaMismatch`.
+    fn map_columns(
+        written_schema: &ArrowSchema,
+        table_schema: &ArrowSchema,
+    ) -> Result<Vec<usize>, WriteError> {
+        let mut column_indices = Vec::with_capacity(written_schema.fields().len());
+        let mut mismatches = Vec::new();
+        for field in written_schema.fields() {
+            let Some((index, table_field)) = table_schema.column_with_name(field.name()) else {
+                mismatches.push(WriteError::DeltaTableColumnMissing(field.name().clone()));
+                continue;
+            };
+            if !Self::fits_table_type(field.data_type(), table_field.data_type())
+                || (field.is_nullable() && !table_field.is_nullable())
+            {
+                mismatches.push(WriteError::DeltaTableColumnTypeMismatch {
+                    name: field.name().clone(),
+                    table_type: table_field.data_type().clone(),
+                    expected_type: field.data_type().clone(),
+                });
+            }
+            column_indices.push(index);
+        }
+
+        // The columns that aren't written are filled with nulls
+        for table_field in table_schema.fields() {
+            if !table_field.is_nullable()
+                && written_schema
+                    .column_with_name(table_field.name())
+                    .is_none()
+            {
+                mismatches.push(WriteError::DeltaTableRequiredColumnNotWritten(
+                    table_field.name().clone(),
+                ));
+            }
+        }
+
+        if mismatches.len() > 1 {
+            Err(WriteError::DeltaTableSchemaMismatch(mismatches))
+        } else if let Some(mismatch) = mismatches.pop() {
+            Err(mismatch)
+        } else {
+            Ok(column_indices)
+        }
+    }
+
+    fn fits_table_type(written_type: &ArrowDataType, table_type: &ArrowDataType) -> bool {
+        match (written_type, table_type) {
+            // The Delta Lake protocol has no durations, so the tables keep them as
+            // microseconds. It's also the case for the tables created before they
+            // were written as such.
+            (ArrowDataType::Duration(ArrowTimeUnit::Microsecond), ArrowDataType::Int64) => true,
+            // The names of the list items differ between the Delta Lake implementations
+            _ => table_type.equals_datatype(written_type),
+        }
+    }
+
+    /// Gives the written fields the types of the matching table columns, so that
+    /// the merge source has the same types as the target.
+    fn with_table_types(
+        written_schema: &ArrowSchema,
+        table_schema: &ArrowSchema,
+        column_indices: &[usize],
+    ) -> ArrowSchema {
+        let fields: Vec<ArrowField> = written_schema
+            .fields()
+            .iter()
+            .zip(column_indices)
+            .map(|(field, index)| {
+                field
+                    .as_ref()
+                    .clone()
+                    .with_data_type(table_schema.field(*index).data_type().clone())
+            })
+            .collect();
+        ArrowSchema::new(fields)
+    }
+
+    fn prepare_delta_batch(&self) -> Result<DTRecordBatch, WriteError> {
+        let n_rows = self.buffered_columns.rows_count();
+        let mut data_columns: Vec<Option<ArrowArrayRef>> = vec![None; self.schema.fields().len()];
+        for (column, index) in self
+            .buffered_columns
+            .columns()
+            .iter()
+            .zip(&self.column_indices)
+        {
+            data_columns[*index] = Some(Self::arrow_array_for_type(
+                self.schema.field(*index).data_type(),
+                column,
+            )?);
+        }
+        let data_columns = data_columns
+            .into_iter()
+            .zip(self.schema.fields())
+            .map(|(column, field)| {
+                column.unwrap_or_else(|| new_null_array(field.data_type(), n_rows))
+            })
+            .collect();
+        Ok(DTRecordBatch::try_new(self.schema.clone(), data_columns)?)
+    }
+
+    /// Leaves only the last change for each key, since a merge fails if several
+    /// source rows match the same target row. Within the same time, the deletions
+    /// go first, so that an update results in the new value of the row.
+    fn collapsed_columns(&self, key_columns: &[String]) -> Vec<Vec<Value>> {
+        let time_column = self.buffered_columns.time_column();
+        let diff_column = self.buffered_columns.diff_column();
+        let key_indices: Vec<usize> = key_columns
+            .iter()
+            .map(|name| {
+                self.written_schema
+                    .index_of(name)
+                    .expect("key columns must be validated in the constructor")
+            })
+            .collect();
+
+        let mut row_indices: Vec<usize> = (0..self.buffered_columns.rows_count()).collect();
+        row_indices.sort_by_key(|index| (&time_column[*index], &diff_column[*index]));
+        let mut last_change_for_key: HashMap<Vec<&Value>, usize> = HashMap::new();
+        for index in row_indices {
+            let key = key_indices
+                .iter()
+                .map(|key_index| &self.buffered_columns.columns()[*key_index][index])
+                .collect();
+            last_change_for_key.insert(key, index);
+        }
+
+        let mut selected_rows: Vec<usize> = last_change_for_key.into_values().collect();
+        selected_rows.sort_unstable();
+        self.buffered_columns
+            .columns()
+            .iter()
+            .map(|column| {
+                selected_rows
+                    .iter()
+                    .map(|index| column[*index].clone())
+                    .collect()
+            })
+            .collect()
+    }
+
+    fn qualified_column(relation: &str, name: &str) -> DataFusionExpr {
+        DataFusionExpr::Column(DataFusionColumn::new(Some(relation), name))
+    }
+
+    fn merge_changes(&mut self, key_columns: &[String]) -> Result<(), WriteError> {
+        let source_batch =
+            Self::record_batch(&self.written_schema, &self.collapsed_columns(key_columns))?;
+        let key_predicate = key_columns
+            .iter()
+            .map(|name| {
+                Self::qualified_column("target", name).eq(Self::qualified_column("source", name))
+            })
+            .reduce(DataFusionExpr::and)
+            .expect("key columns must not be empty");
+        let is_deletion = Self::qualified_column("source", "diff").lt(lit(0));
+        let is_insertion = Self::qualified_column("source", "diff").gt(lit(0));
+        let column_names: Vec<String> = self
+            .written_schema
+            .fields()
+            .iter()
+            .map(|field| field.name().clone())
+            .collect();
+
+        let table = self.runtime.block_on(async {
+            let source = SessionContext::new().read_batch(source_batch)?;
+            let (table, _metrics) = DeltaOps(self.table.clone())
+                .merge(source, key_predicate)
+                .with_source_alias("source")
+                .with_target_alias("target")
+                .when_matched_delete(|delete| delete.predicate(is_deletion))?
+                .when_matched_update(|update| {
+                    column_names.iter().fold(
+                        update.predicate(is_insertion.clone()),
+                        |update, name| {
+                            update.update(name.as_str(), Self::qualified_column("source", name))
+                        },
+                    )
+                })?
+                .when_not_matched_insert(|insert| {
+                    column_names.iter().fold(
+                        insert.predicate(is_insertion.clone()),
+                        |insert, name| {
+                            insert.set(name.as_str(), Self::qualified_column("source", name))
+                        },
+                    )
+                })?
+                .await?;
+            Ok::<DeltaTable, WriteError>(table)
+        })?;
+        self.table = table;
+        Ok(())
+    }
+
+    pub(crate) fn record_batch(
+        schema: &Arc<ArrowSchema>,
+        buffered_columns: &[Vec<Value>],
+    ) -> Result<DTRecordBatch, WriteError> {
+        let mut data_columns = Vec::new();
+        for (index, column) in buffered_columns.iter().enumerate() {
+            data_columns.push(Self::arrow_array_for_type(
+                schema.field(index).data_type(),
+                column,
+            )?);
+        }
+        Ok(DTRecordBatch::try_new(schema.clone(), data_columns)?)
+    }
+
+    fn delta_table_primitive_type(type_: Type) -> Result<DeltaTableKernelType, WriteError> {
+        Ok(DeltaTableKernelType::Primitive(match type_ {
+            Type::Bool => DeltaTablePrimitiveType::Boolean,
+            Type::Float => DeltaTablePrimitiveType::Double,
+            Type::Pointer | Type::String | Type::Json => DeltaTablePrimitiveType::String,
+            Type::Bytes => DeltaTablePrimitiveType::Binary,
+            Type::DateTimeNaive => DeltaTablePrimitiveType::TimestampNtz,
+            Type::DateTimeUtc => DeltaTablePrimitiveType::Timestamp,
+            Type::Int | Type::Duration => DeltaTablePrimitiveType::Long,
+            Type::Array => {
+                return Ok(DeltaTableKernelType::Array(Box::new(
+                    DeltaTableArrayType::new(
+                        DeltaTableKernelType::Primitive(DeltaTablePrimitiveType::Double),
+                        true,
+                    ),
+                )))
+            }
+            // The tuples may hold the values of different types
+            Type::Any | Type::Tuple | Type::PyObjectWrapper => {
+                return Err(WriteError::UnsupportedType(type_))
+            }
+        }))
+    }
+
+    fn arrow_data_type(type_: Type) -> Result<ArrowDataType, WriteError> {
+        Ok(match type_ {
+            Type::Bool => ArrowDataType::Boolean,
+            Type::Int => ArrowDataType::Int64,
+            Type::Duration => ArrowDataType::Duration(ArrowTimeUnit::Microsecond),
+            Type::Float => ArrowDataType::Float64,
+            Type::Pointer | Type::String | Type::Json => ArrowDataType::Utf8,
+            Type::Bytes => ArrowDataType::Binary,
+            // DeltaLake timestamps are stored in microseconds:
+            // https://docs.rs/deltalake/latest/deltalake/kernel/enum.PrimitiveType.html#variant.Timestamp
+            Type::DateTimeNaive => ArrowDataType::Timestamp(ArrowTimeUnit::Microsecond, None),
+            Type::DateTimeUtc => {
+                ArrowDataType::Timestamp(ArrowTimeUnit::Microsecond, Some("UTC".into()))
+            }
+            // Only the one-dimensional arrays are supported, see `arrow_array_for_type`
+            Type::Array => ArrowDataType::List(Arc::new(ArrowField::new(
+                "item",
+                ArrowDataType::Float64,
+                true,
+            ))),
+            Type::Any | Type::Tuple | Type::PyObjectWrapper => {
+                return Err(WriteError::UnsupportedType(type_))
+            }
+        })
+    }
+
+    pub fn construct_schema(
+        value_fields: &Vec<ValueField>,
+        with_deletion_flag: bool,
+    ) -> Result<ArrowSchema, WriteError> {
+        let mut schema_fields: Vec<ArrowField> = Vec::new();
+        for field in value_fields {
+            schema_fields.push(ArrowField::new(
+                field.name.clone(),
+                Self::arrow_data_type(field.type_)?,
+                field.is_optional,
+            ));
+        }
+        for (field, type_) in SPECIAL_OUTPUT_FIELDS {
+            schema_fields.push(ArrowField::new(field, Self::arrow_data_type(type_)?, false));
+        }
+        if with_deletion_flag {
+            schema_fields.push(ArrowField::new(
+                DELETION_FLAG_FIELD,
+                ArrowDataType::Boolean,
+                false,
+            ));
+        }
+        Ok(ArrowSchema::new(schema_fields))
+    }
+
+    fn create_async_runtime() -> Result<TokioRuntime, WriteError> {
+        // Deadlocks if new_current_thread is used
+        Ok(tokio::runtime::Builder::new_multi_thread()
+            .enable_all()
+            .build()?)
+    }
+
+    pub fn open_table(
+        runtime: &TokioRuntime,
+        path: &str,
+        schema_fields: &Vec<ValueField>,
+        storage_options: HashMap<String, String>,
+        with_deletion_flag: bool,
+    ) -> Result<DeltaTable, WriteError> {
+        let mut struct_fields = Vec::new();
+        for field in schema_fields {
+            let mut struct_field = DeltaTableStructField::new(
+                field.name.clone(),
+                Self::delta_table_primitive_type(field.type_)?,
+                field.is_optional,
+            );
+            if field.type_ == Type::Duration {
+                struct_field = struct_field.with_metadata([(
+                    DURATION_UNIT_METADATA_KEY,
+                    DeltaTableMetadataValue::String("us".to_string()),
+                )]);
+            }
+            struct_fields.push(struct_field);
+        }
+        for (field, type_) in SPECIAL_OUTPUT_FIELDS {
+            struct_fields.push(DeltaTableStructField::new(
+                field,
+                Self::delta_table_primitive_type(type_)?,
+                false,
+            ));
+        }
+        if with_deletion_flag {
+            struct_fields.push(DeltaTableStructField::new(
+                DELETION_FLAG_FIELD,
+                Self::delta_table_primitive_type(Type::Bool)?,
+                false,
+            ));
+        }
+
+        let table: DeltaTable = runtime
+            .block_on(async {
+                let builder = DeltaTableCreateBuilder::new()
+                    .with_location(path)
+                    .with_save_mode(DeltaTableSaveMode::Append)
+                    .with_columns(struct_fields)
+                    .with_storage_options(storage_options.clone());
+
+                builder.await
+            })
+            .or_else(
+                |e| {
+                    warn!("Unable to create DeltaTable for output: {e}. Trying to open the existing one by this path.");
+                    runtime.block_on(async {
+                        open_delta_table(path, storage_options).await
+                    })
+                }
+            )?;
+
+        Ok(table)
+    }
+}
+
+impl Writer for DeltaTableWriter {
+    fn write(&mut self, data: FormatterContext) -> Result<(), WriteError> {
+        for (value, field) in data.values.iter().zip(self.written_schema.fields()) {
+            Self::check_value_type(field.data_type(), value)?;
+        }
+        self.buffered_columns.push_row(data)
+    }
+
+    fn flush(&mut self, forced: bool) -> Result<(), WriteError> {
+        let commit_needed = !self.buffered_columns.is_empty()
+            && (self
+                .min_commit_frequency
+                .map_or(true, |f| self.last_commit_at.elapsed() >= f)
+                || forced);
+        if commit_needed {
+            if let DeltaTableWriteMode::Upsert { key_columns } = self.write_mode.clone() {
+                self.merge_changes(&key_columns)?;
+            } else {
+                let prepared_batch = self.prepare_delta_batch()?;
+                let Self {
+                    runtime,
+                    writer,
+                    table,
+                    ..
+                } = self;
+                runtime.block_on(async {
+                    writer.write(prepared_batch).await?;
+                    writer.flush_and_commit(table).await?;
+                    Ok::<(), WriteError>(())
+                })?;
+            }
+            self.written_rows.add(self.buffered_columns.rows_count());
+            self.buffered_columns.clear();
+        }
+        self.written_rows.on_flush();
+        Ok(())
+    }
+
+    fn rows_written_since_last_flush(&self) -> usize {
+        self.written_rows.last_flush()
+    }
+}
+
+pub struct DeltaTableReader {
+    table: DeltaTable,
+    persistent_id: Option<PersistentId>,
+    streaming_mode: ConnectorMode,
+    poll_interval: Duration,
+    // The columns holding the durations, which are stored as microseconds
+    duration_columns: HashSet<String>,
+
+    // The files of `base_version`, against which the changes are computed
+    known_files: BTreeSet<DeltaTablePath>,
+    base_version: Option<i64>,
+    current_version: Option<i64>,
+    is_version_in_progress: bool,
+    files_queue: VecDeque<(DataEventType, DeltaTablePath)>,
+
+    reader: Option<ParquetRecordBatchReader>,
+    current_event_type: DataEventType,
+    current_batch: Option<DTRecordBatch>,
+    current_batch_row: usize,
+    rows_read_within_version: u64,
+    rows_to_skip: u64,
+    shutdown_signal: ShutdownSignal,
+
+    // Declared last so that it's dropped after everything that may use it
+    runtime: TokioRuntime,
+}
+
+impl DeltaTableReader {
+    const DEFAULT_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+    pub fn new(
+        path: &str,
+        storage_options: HashMap<String, String>,
+        streaming_mode: ConnectorMode,
+        persistent_id: Option<PersistentId>,
+        poll_interval: Option<Duration>,
+    ) -> Result<Self, ReadError> {
+        let runtime = tokio::runtime::Builder::new_multi_thread()
+            .enable_all()
+            .build()?;
+        let table = runtime.block_on(open_delta_table(path, storage_options))?;
+        let duration_columns = Self::duration_columns(&table)?;
+
+        Ok(Self {
+            table,
+            persistent_id,
+            streaming_mode,
+            poll_interval: poll_interval.unwrap_or(Self::DEFAULT_POLL_INTERVAL),
+            duration_columns,
+
+            known_files: BTreeSet::new(),
+            base_version: None,
+            current_version: None,
+            is_version_in_progress: false,
+            files_queue: VecDeque::new(),
+
+            reader: None,
+            current_event_type: DataEventType::Insert,
+            current_batch: None,
+            current_batch_row: 0,
+            rows_read_within_version: 0,
+            rows_to_skip: 0,
+            shutdown_signal: ShutdownSignal::default(),
+
+            runtime,
+        })
+    }
+
+    /// Finds the long columns marked as durations by `DeltaTableWriter`. The tables
+    /// created before the marking was introduced have their durations read as integers.
+    fn duration_columns(table: &DeltaTable) -> Result<HashSet<String>, ReadError> {
+        let mut duration_columns = HashSet::new();
+        for field in table.get_schema()?.fields() {
+            if matches!(
+                field.metadata().get(DURATION_UNIT_METADATA_KEY),
+                Some(DeltaTableMetadataValue::String(unit)) if unit == "us"
+            ) {
+                duration_columns.insert(field.name().clone());
+            }
+        }
+        Ok(duration_columns)
+    }
+
+    fn restore_durations(
+        duration_columns: &HashSet<String>,
+        batch: DTRecordBatch,
+    ) -> Result<DTRecordBatch, ReadError> {
+        if duration_columns.is_empty() {
+            return Ok(batch);
+        }
+        let schema = batch.schema();
+        let mut fields = Vec::with_capacity(batch.num_columns());
+        let mut columns = Vec::with_capacity(batch.num_columns());
+        for (field, column) in schema.fields().iter().zip(batch.columns()) {
+            let column: ArrowArrayRef = if duration_columns.contains(field.name()) {
+                let microseconds = downcast_arrow_array::<ArrowInt64Array>(column.as_ref())?;
+                Arc::new(ArrowDurationMicrosecondArray::new(
+                    microseconds.values().clone(),
+                    microseconds.nulls().cloned(),
+                ))
+            } else {
+                column.clone()
+            };
+            fields.push(
+                field
+                    .as_ref()
+                    .clone()
+                    .with_data_type(column.data_type().clone()),
+            );
+            columns.push(column);
+        }
+        Ok(DTRecordBatch::try_new(
+            Arc::new(ArrowSchema::new(fields)),
+            columns,
+        )?)
+    }
+
+    fn table_files(&self) -> Result<BTreeSet<DeltaTablePath>, ReadError> {
+        Ok(self.table.get_files_iter()?.collect())
+    }
+
+    /// Plans reading the rows of the removed files as deletions and the rows of the
+    /// added files as insertions, then makes `files` the known ones.
+    fn plan_changes(&mut self, files: BTreeSet<DeltaTablePath>) {
+        self.files_queue.clear();
+        for removed_file in self.known_files.difference(&files) {
+            self.files_queue
+                .push_back((DataEventType::Delete, removed_file.clone()));
+        }
+        for added_file in files.difference(&self.known_files) {
+            self.files_queue
+                .push_back((DataEventType::Insert, added_file.clone()));
+        }
+        self.known_files = files;
+        self.current_version = Some(self.table.version());
+        self.is_version_in_progress = true;
+        self.rows_read_within_version = 0;
+    }
+
+    /// Checks whether there is an unprocessed version of the table and plans
+    /// reading its changes if so.
+    fn advance_version(&mut self) -> Result<bool, ReadError> {
+        if self.current_version.is_some() {
+            if !self.streaming_mode.is_polling_enabled() {
+                return Ok(false);
+            }
+            self.runtime.block_on(self.table.update())?;
+            if Some(self.table.version()) == self.current_version {
+                return Ok(false);
+            }
+        }
+        self.base_version = self.current_version;
+        self.duration_columns = Self::duration_columns(&self.table)?;
+        let files = self.table_files()?;
+        self.plan_changes(files);
+        Ok(true)
+    }
+
+    fn open_file(&mut self, path: &DeltaTablePath) -> Result<(), ReadError> {
+        let object_store = self.table.object_store();
+        let contents = self
+            .runtime
+            .block_on(async { object_store.get(path).await?.bytes().await })
+            .map_err(DeltaTableError::from)?;
+        self.reader = Some(ParquetRecordBatchReaderBuilder::try_new(contents)?.build()?);
+        self.current_batch = None;
+        self.current_batch_row = 0;
+        Ok(())
+    }
+
+    fn next_row(&mut self) -> Result<Option<ValuesMap>, ReadError> {
+        let Some(reader) = self.reader.as_mut() else {
+            return Ok(None);
+        };
+        loop {
+            if let Some(batch) = &self.current_batch {
+                if self.current_batch_row < batch.num_rows() {
+                    let values = values_map_from_record_batch_row(batch, self.current_batch_row)?;
+                    self.current_batch_row += 1;
+                    return Ok(Some(values));
+                }
+            }
+            match reader.next() {
+                Some(batch) => {
+                    self.current_batch =
+                        Some(Self::restore_durations(&self.duration_columns, batch?)?);
+                    self.current_batch_row = 0;
+                }
+                None => {
+                    self.reader = None;
+                    self.current_batch = None;
+                    return Ok(None);
+                }
+            }
+        }
+    }
+}
+
+impl Reader for DeltaTableReader {
+    fn seek(&mut self, frontier: &OffsetAntichain) -> Result<(), ReadError> {
+        let offset_value = frontier.get_offset(&OffsetKey::Empty);
+        let Some(OffsetValue::DeltaTablePosition {
+            version,
+            base_version,
+            rows_read_within_version,
+        }) = offset_value
+        else {
+            if offset_value.is_some() {
+                warn!("Incorrect type of offset value in DeltaTable frontier: {offset_value:?}");
+            }
+            return Ok(());
+        };
+
+        // The changes of the interrupted version are computed again and the rows
+        // that were already emitted are skipped
+        self.known_files = match base_version {
+            Some(base_version) => {
+                self.runtime
+                    .block_on(self.table.load_version(*base_version))?;
+                self.table_files()?
+            }
+            None => BTreeSet::new(),
+        };
+        self.runtime.block_on(self.table.load_version(*version))?;
+        self.duration_columns = Self::duration_columns(&self.table)?;
+        let files = self.table_files()?;
+        self.base_version = *base_version;
+        self.plan_changes(files);
+        self.rows_to_skip = *rows_read_within_version;
+
+        Ok(())
+    }
+
+    fn read(&mut self) -> Result<ReadResult, ReadError> {
+        loop {
+            if let Some(values) = self.next_row()? {
+                self.rows_read_within_version += 1;
+                if self.rows_read_within_version <= self.rows_to_skip {
+                    continue;
+                }
+
+                let offset = (
+                    OffsetKey::Empty,
+                    OffsetValue::DeltaTablePosition {
+                        version: self.current_version.ok_or_else(|| {
+                            ReadError::InconsistentState(
+                                "the table version is unknown while reading rows".to_string(),
+                            )
+                        })?,
+                        base_version: self.base_version,
+                        rows_read_within_version: self.rows_read_within_version,
+                    },
+                );
+                return Ok(ReadResult::Data(
+                    ReaderContext::from_diff(self.current_event_type, None, values),
+                    offset,
+                ));
+            }
+
+            if let Some((event_type, path)) = self.files_queue.pop_front() {
+                self.open_file(&path)?;
+                self.current_event_type = event_type;
+                continue;
+            }
+
+            if self.is_version_in_progress {
+                self.is_version_in_progress = false;
+                self.rows_to_skip = 0;
+                return Ok(ReadResult::FinishedSource {
+                    commit_allowed: true,
+                });
+            }
+
+            if self.advance_version()? {
+                // No metadata is currently provided for Delta Lake versions
+                return Ok(ReadResult::NewSource(None));
+            }
+            if !self.streaming_mode.is_polling_enabled() || self.shutdown_signal.is_requested() {
+                return Ok(ReadResult::Finished);
+            }
+            self.shutdown_signal.sleep(self.poll_interval);
+        }
+    }
+
+    fn persistent_id(&self) -> Option<PersistentId> {
+        self.persistent_id
+    }
+
+    fn update_persistent_id(&mut self, persistent_id: Option<PersistentId>) {
+        self.persistent_id = persistent_id;
+    }
+
+    fn set_shutdown_signal(&mut self, signal: ShutdownSignal) {
+        self.shutdown_signal = signal;
+    }
+
+    fn storage_type(&self) -> StorageType {
+        StorageType::DeltaTable
+    }
+}
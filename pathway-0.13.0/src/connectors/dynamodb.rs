@@ -0,0 +1,245 @@
+// Copyright © 2024 Pathway
+
+use std::collections::{HashMap, HashSet};
+use std::thread::sleep;
+use std::time::Duration;
+
+use aws_config::BehaviorVersion as AwsBehaviorVersion;
+use aws_sdk_dynamodb::primitives::Blob;
+use aws_sdk_dynamodb::types::{AttributeValue, DeleteRequest, PutRequest, WriteRequest};
+use aws_sdk_dynamodb::Client as DynamoDbClient;
+use log::warn;
+use rand::Rng;
+use tokio::runtime::Runtime as TokioRuntime;
+
+use crate::connectors::data_format::FormatterContext;
+use crate::connectors::data_storage::{DataEventType, WriteError, Writer};
+use crate::engine::{Timestamp, Type, Value};
+use crate::python_api::ValueField;
+
+pub struct DynamoDbWriter {
+    runtime: TokioRuntime,
+    client: DynamoDbClient,
+    table_name: String,
+    field_names: Vec<String>,
+    field_types: Vec<Type>,
+    partition_key_index: usize,
+    sort_key_index: Option<usize>,
+    max_batch_size: Option<usize>,
+
+    // The batch can't contain several requests for the same item, so only the
+    // last request is kept for each key
+    pending_writes: HashMap<Vec<Value>, WriteRequest>,
+
+    // The keys put at the time of the last processed event. The deletion of a
+    // key which was already put at the same time is the retraction of the old
+    // version of the item, so it must not be sent
+    current_time: Option<Timestamp>,
+    keys_put_at_current_time: HashSet<Vec<Value>>,
+}
+
+impl DynamoDbWriter {
+    const BATCH_WRITE_MAX_ITEMS: usize = 25;
+    const MAX_BATCH_WRITE_ATTEMPTS: usize = 8;
+    const UNPROCESSED_RETRY_INITIAL_BACKOFF: Duration = Duration::from_millis(50);
+    const UNPROCESSED_RETRY_MAX_BACKOFF: Duration = Duration::from_secs(5);
+
+    /// Creates a writer maintaining the rows in the table `table_name`. The key schema
+    /// of the table is given by the indices of the partition key field and, optionally,
+    /// of the sort key field among `value_fields`.
+    ///
+    /// The credentials and the region are taken from the default AWS configuration
+    /// sources, such as the environment variables and the shared config files.
+    pub fn new(
+        table_name: String,
+        value_fields: &[ValueField],
+        partition_key_index: usize,
+        sort_key_index: Option<usize>,
+        max_batch_size: Option<usize>,
+    ) -> Result<Self, WriteError> {
+        let value_fields_count = value_fields.len();
+        if let Some(index) = std::iter::once(partition_key_index)
+            .chain(sort_key_index)
+            .find(|index| *index >= value_fields_count)
+        {
+            return Err(WriteError::KeyFieldIndexOutOfRange {
+                index,
+                value_fields_count,
+            });
+        }
+
+        let runtime = tokio::runtime::Builder::new_multi_thread()
+            .enable_all()
+            .build()?;
+        let config = runtime.block_on(aws_config::load_defaults(AwsBehaviorVersion::latest()));
+        Ok(Self {
+            client: DynamoDbClient::new(&config),
+            runtime,
+            table_name,
+            field_names: value_fields
+                .iter()
+                .map(|field| field.name.clone())
+                .collect(),
+            field_types: value_fields.iter().map(|field| field.type_).collect(),
+            partition_key_index,
+            sort_key_index,
+            max_batch_size,
+            pending_writes: HashMap::new(),
+            current_time: None,
+            keys_put_at_current_time: HashSet::new(),
+        })
+    }
+
+    /// Converts the value into the attribute value of the column of the type `type_`.
+    /// Tuples are written as lists, JSON values and pointers are written as strings.
+    pub fn attribute_value(value: &Value, type_: Type) -> Result<AttributeValue, WriteError> {
+        Ok(match value {
+            Value::None => AttributeValue::Null(true),
+            Value::Bool(b) => AttributeValue::Bool(*b),
+            Value::Int(i) => AttributeValue::N(i.to_string()),
+            Value::Float(f) if f.is_finite() => AttributeValue::N(f.to_string()),
+            Value::String(s) => AttributeValue::S(s.to_string()),
+            Value::Pointer(p) => AttributeValue::S(p.to_string()),
+            Value::Json(j) => AttributeValue::S(j.to_string()),
+            Value::Bytes(b) => AttributeValue::B(Blob::new(b.to_vec())),
+            Value::Tuple(values) => AttributeValue::L(
+                values
+                    .iter()
+                    .map(|value| Self::attribute_value(value, type_))
+                    .collect::<Result<_, _>>()?,
+            ),
+            _ => return Err(WriteError::UnsupportedType(type_)),
+        })
+    }
+
+    fn key_indices(&self) -> impl Iterator<Item = usize> {
+        std::iter::once(self.partition_key_index).chain(self.sort_key_index)
+    }
+
+    fn item_attributes(
+        &self,
+        values: &[Value],
+        indices: impl Iterator<Item = usize>,
+    ) -> Result<HashMap<String, AttributeValue>, WriteError> {
+        indices
+            .map(|index| {
+                let value = Self::attribute_value(&values[index], self.field_types[index])?;
+                Ok((self.field_names[index].clone(), value))
+            })
+            .collect()
+    }
+
+    fn write_request(
+        &self,
+        event_type: DataEventType,
+        values: &[Value],
+    ) -> Result<WriteRequest, WriteError> {
+        let request = match event_type {
+            DataEventType::Insert | DataEventType::Upsert => {
+                let item = self.item_attributes(values, 0..values.len())?;
+                let put_request = PutRequest::builder().set_item(Some(item)).build()?;
+                WriteRequest::builder().put_request(put_request).build()
+            }
+            DataEventType::Delete => {
+                let key = self.item_attributes(values, self.key_indices())?;
+                let delete_request = DeleteRequest::builder().set_key(Some(key)).build()?;
+                WriteRequest::builder()
+                    .delete_request(delete_request)
+                    .build()
+            }
+        };
+        Ok(request)
+    }
+
+    fn send_pending_writes(&mut self) -> Result<(), WriteError> {
+        let requests: Vec<_> = self
+            .pending_writes
+            .drain()
+            .map(|(_, request)| request)
+            .collect();
+        for chunk in requests.chunks(Self::BATCH_WRITE_MAX_ITEMS) {
+            self.batch_write_with_retries(chunk.to_vec())?;
+        }
+        Ok(())
+    }
+
+    /// Sends the requests with `BatchWriteItem`. The items which weren't processed
+    /// because of the throttling are resent with an exponential backoff.
+    fn batch_write_with_retries(&self, mut requests: Vec<WriteRequest>) -> Result<(), WriteError> {
+        let mut backoff = Self::UNPROCESSED_RETRY_INITIAL_BACKOFF;
+        for attempt in 1..=Self::MAX_BATCH_WRITE_ATTEMPTS {
+            let output = self
+                .runtime
+                .block_on(
+                    self.client
+                        .batch_write_item()
+                        .request_items(self.table_name.clone(), requests)
+                        .send(),
+                )
+                .map_err(|e| WriteError::DynamoDb(Box::new(e.into())))?;
+            requests = output
+                .unprocessed_items
+                .and_then(|mut items| items.remove(&self.table_name))
+                .unwrap_or_default();
+            if requests.is_empty() {
+                return Ok(());
+            }
+            if attempt < Self::MAX_BATCH_WRITE_ATTEMPTS {
+                warn!(
+                    "DynamoDB didn't process {} items (attempt {attempt} of {}). Retrying",
+                    requests.len(),
+                    Self::MAX_BATCH_WRITE_ATTEMPTS
+                );
+                let jitter = rand::thread_rng().gen_range(0.5..1.5);
+                sleep(backoff.mul_f64(jitter));
+                backoff = (backoff * 2).min(Self::UNPROCESSED_RETRY_MAX_BACKOFF);
+            }
+        }
+        Err(WriteError::DynamoDbUnprocessedItems(requests.len()))
+    }
+}
+
+impl Writer for DynamoDbWriter {
+    fn write(&mut self, data: FormatterContext) -> Result<(), WriteError> {
+        if self.current_time != Some(data.time) {
+            self.current_time = Some(data.time);
+            self.keys_put_at_current_time.clear();
+        }
+
+        let key: Vec<Value> = self
+            .key_indices()
+            .map(|index| data.values[index].clone())
+            .collect();
+        match data.event_type {
+            DataEventType::Delete if self.keys_put_at_current_time.contains(&key) => {
+                return Ok(());
+            }
+            DataEventType::Delete => {}
+            DataEventType::Insert | DataEventType::Upsert => {
+                self.keys_put_at_current_time.insert(key.clone());
+            }
+        }
+
+        let request = self.write_request(data.event_type, &data.values)?;
+        self.pending_writes.insert(key, request);
+
+        if let Some(max_batch_size) = self.max_batch_size {
+            if self.pending_writes.len() >= max_batch_size {
+                self.send_pending_writes()?;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn flush(&mut self, _forced: bool) -> Result<(), WriteError> {
+        if !self.pending_writes.is_empty() {
+            self.send_pending_writes()?;
+        }
+        Ok(())
+    }
+
+    fn single_threaded(&self) -> bool {
+        false
+    }
+}
@@ -0,0 +1,284 @@
+// Copyright © 2024 Pathway
+
+use std::collections::HashSet;
+use std::mem::take;
+
+use elasticsearch::{BulkParts, Elasticsearch};
+use log::warn;
+use tokio::runtime::Runtime as TokioRuntime;
+
+use crate::connectors::data_format::FormatterContext;
+use crate::connectors::data_storage::{DataEventType, WriteError, Writer, WrittenRowsCounter};
+use crate::engine::Timestamp;
+
+/// The bulk action used to write the inserted documents. With `Create`, the
+/// documents whose ids are already present in the index are rejected.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum ElasticSearchBulkAction {
+    #[default]
+    Index,
+    Create,
+}
+
+impl ElasticSearchBulkAction {
+    fn name(self) -> &'static str {
+        match self {
+            Self::Index => "index",
+            Self::Create => "create",
+        }
+    }
+}
+
+pub struct ElasticSearchWriter {
+    client: Elasticsearch,
+    runtime: TokioRuntime,
+    index_name: String,
+    max_batch_size: Option<usize>,
+    bulk_action: ElasticSearchBulkAction,
+
+    // If set, the documents are identified by the value of this field, so that
+    // the deletions and upserts are applied to them. Otherwise, every event is
+    // indexed as a new document
+    id_field: Option<String>,
+
+    // The lines of the bulk request for each buffered action. Once there are
+    // `max_buffered_actions` of them, no more actions are accepted until the
+    // buffer is sent, so that a slow cluster can't make it grow without bound
+    actions_buffer: Vec<Vec<Vec<u8>>>,
+    max_buffered_actions: usize,
+
+    // The ids written at the time of the last processed event. The deletion of
+    // an id which was already written at the same time is the retraction of the
+    // old version of the document, so it must not be sent
+    current_time: Option<Timestamp>,
+    ids_written_at_current_time: HashSet<String>,
+
+    written_rows: WrittenRowsCounter,
+}
+
+impl ElasticSearchWriter {
+    pub const DEFAULT_MAX_BUFFERED_ACTIONS: usize = 100_000;
+
+    // The status of the bulk items rejected because the cluster is overloaded
+    const TOO_MANY_REQUESTS_STATUS: u64 = 429;
+
+    pub fn new(
+        client: Elasticsearch,
+        index_name: String,
+        max_batch_size: Option<usize>,
+        id_field: Option<String>,
+        bulk_action: ElasticSearchBulkAction,
+        max_buffered_actions: usize,
+    ) -> Result<Self, WriteError> {
+        // The runtime is created once, so that the flushes don't pay for its setup
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()?;
+        Ok(ElasticSearchWriter {
+            client,
+            runtime,
+            index_name,
+            max_batch_size,
+            bulk_action,
+            id_field,
+            actions_buffer: Vec::new(),
+            max_buffered_actions,
+            current_time: None,
+            ids_written_at_current_time: HashSet::new(),
+            written_rows: WrittenRowsCounter::default(),
+        })
+    }
+
+    pub fn document_id(payload: &[u8], id_field: &str) -> Result<String, WriteError> {
+        let document: serde_json::Value = serde_json::from_slice(payload)?;
+        match document.get(id_field) {
+            Some(serde_json::Value::String(id)) => Ok(id.clone()),
+            Some(serde_json::Value::Null) | None => Err(WriteError::ElasticsearchMissingIdField(
+                id_field.to_string(),
+            )),
+            Some(id) => Ok(id.to_string()),
+        }
+    }
+
+    /// Returns the lines of the bulk request, which apply the event to the document.
+    /// The inserted documents are written with `bulk_action`.
+    pub fn bulk_action_lines(
+        bulk_action: ElasticSearchBulkAction,
+        event_type: DataEventType,
+        id: Option<&str>,
+        payload: Vec<u8>,
+    ) -> Vec<Vec<u8>> {
+        let action = bulk_action.name();
+        let Some(id) = id else {
+            return vec![format!("{{\"{action}\": {{}}}}").into_bytes(), payload];
+        };
+        let id = serde_json::Value::from(id);
+        match event_type {
+            DataEventType::Insert => vec![
+                format!("{{\"{action}\": {{\"_id\": {id}}}}}").into_bytes(),
+                payload,
+            ],
+            DataEventType::Delete => {
+                vec![format!("{{\"delete\": {{\"_id\": {id}}}}}").into_bytes()]
+            }
+            DataEventType::Upsert => {
+                let mut update = b"{\"doc\": ".to_vec();
+                update.extend_from_slice(&payload);
+                update.extend_from_slice(b", \"doc_as_upsert\": true}");
+                vec![
+                    format!("{{\"update\": {{\"_id\": {id}}}}}").into_bytes(),
+                    update,
+                ]
+            }
+        }
+    }
+
+    fn is_buffer_full(&self) -> bool {
+        self.actions_buffer.len() >= self.max_buffered_actions
+    }
+
+    /// Sends the buffered actions with a single bulk request. The actions which the
+    /// cluster rejected because of the overload are kept in the buffer, so that
+    /// they are sent again with the next request.
+    fn send_buffered_actions(&mut self) -> Result<(), WriteError> {
+        if self.actions_buffer.is_empty() {
+            return Ok(());
+        }
+        let body: Vec<&[u8]> = self
+            .actions_buffer
+            .iter()
+            .flatten()
+            .map(Vec::as_slice)
+            .collect();
+        let response: serde_json::Value = self
+            .runtime
+            .block_on(async {
+                self.client
+                    .bulk(BulkParts::Index(&self.index_name))
+                    .body(body)
+                    .send()
+                    .await?
+                    .error_for_status_code()?
+                    .json()
+                    .await
+            })
+            .map_err(WriteError::Elasticsearch)?;
+
+        // The bulk request succeeds even if some of the documents weren't indexed
+        if response["errors"].as_bool() != Some(true) {
+            self.written_rows.add(self.actions_buffer.len());
+            self.actions_buffer.clear();
+            return Ok(());
+        }
+        let items = response["items"].as_array().map_or(&[][..], Vec::as_slice);
+        let mut first_error = None;
+        let mut rejected_actions = Vec::new();
+        for (action, item) in take(&mut self.actions_buffer).into_iter().zip(items) {
+            // Each item is a single-entry object keyed by the action name
+            let Some(result) = item.as_object().and_then(|item| item.values().next()) else {
+                continue;
+            };
+            if result["status"].as_u64() == Some(Self::TOO_MANY_REQUESTS_STATUS) {
+                rejected_actions.push(action);
+            } else if let Some(error) = result.get("error") {
+                first_error.get_or_insert_with(|| error.to_string());
+            } else {
+                self.written_rows.add(1);
+            }
+        }
+        self.actions_buffer = rejected_actions;
+
+        if let Some(first_error) = first_error {
+            return Err(WriteError::ElasticsearchBulkItemFailed(first_error));
+        }
+        if !self.actions_buffer.is_empty() {
+            return Err(WriteError::ElasticsearchBulkRejected(
+                self.actions_buffer.len(),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Sends the buffered actions. If the cluster is unavailable or overloaded, the
+    /// error is only reported when the buffer is full or the flush is forced.
+    /// Otherwise the actions stay in the buffer until the next attempt.
+    fn try_send_buffered_actions(&mut self, forced: bool) -> Result<(), WriteError> {
+        match self.send_buffered_actions() {
+            Err(
+                error @ (WriteError::Elasticsearch(_) | WriteError::ElasticsearchBulkRejected(_)),
+            ) if !forced && !self.is_buffer_full() => {
+                warn!(
+                    "Failed to send {} actions to Elasticsearch, keeping them in the buffer: {error}",
+                    self.actions_buffer.len()
+                );
+                Ok(())
+            }
+            result => result,
+        }
+    }
+}
+
+impl Writer for ElasticSearchWriter {
+    fn write(&mut self, data: FormatterContext) -> Result<(), WriteError> {
+        // The buffer is sent before the new actions are added, so that if it fails,
+        // the write can be retried by the engine without duplicating them
+        let is_batch_complete = self
+            .max_batch_size
+            .is_some_and(|max_batch_size| self.actions_buffer.len() >= max_batch_size);
+        if is_batch_complete || self.is_buffer_full() {
+            self.try_send_buffered_actions(false)?;
+        }
+
+        if self.current_time != Some(data.time) {
+            self.current_time = Some(data.time);
+            self.ids_written_at_current_time.clear();
+        }
+
+        for payload in data.payloads {
+            let id = match &self.id_field {
+                Some(id_field) => Some(Self::document_id(&payload, id_field)?),
+                None => None,
+            };
+            if let Some(id) = &id {
+                let is_written = self.ids_written_at_current_time.contains(id);
+                match data.event_type {
+                    DataEventType::Delete if is_written => continue,
+                    DataEventType::Delete => {}
+                    DataEventType::Insert | DataEventType::Upsert => {
+                        self.ids_written_at_current_time.insert(id.clone());
+                    }
+                }
+            }
+            self.actions_buffer.push(Self::bulk_action_lines(
+                self.bulk_action,
+                data.event_type,
+                id.as_deref(),
+                payload,
+            ));
+        }
+
+        Ok(())
+    }
+
+    fn flush(&mut self, forced: bool) -> Result<(), WriteError> {
+        self.try_send_buffered_actions(forced)?;
+        self.written_rows.on_flush();
+        Ok(())
+    }
+
+    fn rows_written_since_last_flush(&self) -> usize {
+        self.written_rows.last_flush()
+    }
+
+    fn on_commit(&mut self, _time: Timestamp) -> Result<(), WriteError> {
+        self.flush(true)
+    }
+
+    fn retriable(&self) -> bool {
+        true
+    }
+
+    fn single_threaded(&self) -> bool {
+        false
+    }
+}
@@ -0,0 +1,174 @@
+// Copyright © 2024 Pathway
+
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use s3::bucket::Bucket as S3Bucket;
+
+use crate::connectors::data_format::InnerSchemaField;
+use crate::connectors::data_storage::{
+    CompressionFormat, ReadError, ReadMethod, ReadResult, Reader, ShutdownSignal, StorageType,
+};
+use crate::connectors::s3::{ObjectOrdering, S3CsvReader, S3GenericReader};
+use crate::persistence::frontier::OffsetAntichain;
+use crate::persistence::PersistentId;
+
+/// Google Cloud Storage is read via its S3-compatible XML API, authorized with the
+/// HMAC keys of a service account. Hence the object selection and streaming are
+/// done by `S3Scanner` and the offsets have the same form as for S3.
+pub const GCS_XML_API_ENDPOINT: &str = "https://storage.googleapis.com";
+
+pub struct GcsCsvReader {
+    inner: S3CsvReader,
+}
+
+impl GcsCsvReader {
+    /// Creates a reader of the CSV objects under `objects_prefix`. The bucket must
+    /// be addressed via `GCS_XML_API_ENDPOINT`.
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        bucket: S3Bucket,
+        objects_prefix: impl Into<String>,
+        object_pattern: &str,
+        parser_builder: csv::ReaderBuilder,
+        poll_new_objects: bool,
+        persistent_id: Option<PersistentId>,
+        poll_interval: Option<Duration>,
+        max_consecutive_errors: usize,
+        schema: Option<HashMap<String, InnerSchemaField>>,
+    ) -> Result<GcsCsvReader, ReadError> {
+        Ok(GcsCsvReader {
+            inner: S3CsvReader::new(
+                bucket,
+                objects_prefix,
+                object_pattern,
+                ObjectOrdering::default(),
+                parser_builder,
+                poll_new_objects,
+                persistent_id,
+                poll_interval,
+                max_consecutive_errors,
+                schema,
+                false,
+                None,
+                None,
+            )?,
+        })
+    }
+}
+
+impl Reader for GcsCsvReader {
+    fn seek(&mut self, frontier: &OffsetAntichain) -> Result<(), ReadError> {
+        self.inner.seek(frontier)
+    }
+
+    fn read(&mut self) -> Result<ReadResult, ReadError> {
+        self.inner.read()
+    }
+
+    fn storage_type(&self) -> StorageType {
+        StorageType::GcsCsv
+    }
+
+    fn max_allowed_consecutive_errors(&self) -> usize {
+        self.inner.max_allowed_consecutive_errors()
+    }
+
+    fn last_activity(&self) -> Option<Instant> {
+        self.inner.last_activity()
+    }
+
+    fn persistent_id(&self) -> Option<PersistentId> {
+        self.inner.persistent_id()
+    }
+
+    fn update_persistent_id(&mut self, persistent_id: Option<PersistentId>) {
+        self.inner.update_persistent_id(persistent_id);
+    }
+
+    fn set_shutdown_signal(&mut self, signal: ShutdownSignal) {
+        self.inner.set_shutdown_signal(signal);
+    }
+
+    fn set_idle_timeout(&mut self, timeout: Option<Duration>) {
+        self.inner.set_idle_timeout(timeout);
+    }
+}
+
+pub struct GcsGenericReader {
+    inner: S3GenericReader,
+}
+
+impl GcsGenericReader {
+    /// Creates a reader of the objects under `objects_prefix`. The bucket must be
+    /// addressed via `GCS_XML_API_ENDPOINT`.
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        bucket: S3Bucket,
+        objects_prefix: impl Into<String>,
+        object_pattern: &str,
+        poll_new_objects: bool,
+        persistent_id: Option<PersistentId>,
+        read_method: ReadMethod,
+        compression: Option<CompressionFormat>,
+        poll_interval: Option<Duration>,
+        max_consecutive_errors: usize,
+        max_record_bytes: Option<usize>,
+    ) -> Result<GcsGenericReader, ReadError> {
+        Ok(GcsGenericReader {
+            inner: S3GenericReader::new(
+                bucket,
+                objects_prefix,
+                object_pattern,
+                ObjectOrdering::default(),
+                poll_new_objects,
+                persistent_id,
+                read_method,
+                compression,
+                poll_interval,
+                max_consecutive_errors,
+                None,
+                max_record_bytes,
+                None,
+            )?,
+        })
+    }
+}
+
+impl Reader for GcsGenericReader {
+    fn seek(&mut self, frontier: &OffsetAntichain) -> Result<(), ReadError> {
+        self.inner.seek(frontier)
+    }
+
+    fn read(&mut self) -> Result<ReadResult, ReadError> {
+        self.inner.read()
+    }
+
+    fn storage_type(&self) -> StorageType {
+        StorageType::GcsLines
+    }
+
+    fn max_allowed_consecutive_errors(&self) -> usize {
+        self.inner.max_allowed_consecutive_errors()
+    }
+
+    fn last_activity(&self) -> Option<Instant> {
+        self.inner.last_activity()
+    }
+
+    fn persistent_id(&self) -> Option<PersistentId> {
+        self.inner.persistent_id()
+    }
+
+    fn update_persistent_id(&mut self, persistent_id: Option<PersistentId>) {
+        self.inner.update_persistent_id(persistent_id);
+    }
+
+    fn set_shutdown_signal(&mut self, signal: ShutdownSignal) {
+        self.inner.set_shutdown_signal(signal);
+    }
+
+    fn set_idle_timeout(&mut self, timeout: Option<Duration>) {
+        self.inner.set_idle_timeout(timeout);
+    }
+}
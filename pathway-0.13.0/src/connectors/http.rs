@@ -0,0 +1,259 @@
+// Copyright © 2024 Pathway
+
+use std::collections::VecDeque;
+use std::sync::Arc;
+use std::thread::sleep;
+use std::time::{Duration, Instant};
+
+use log::warn;
+use percent_encoding::{utf8_percent_encode, AsciiSet, NON_ALPHANUMERIC};
+use reqwest::blocking::{Client as HttpClient, Response as HttpResponse};
+use reqwest::header::RETRY_AFTER;
+use reqwest::StatusCode as HttpStatusCode;
+
+use crate::connectors::data_storage::{
+    ConnectorMode, DataEventType, ReadError, ReadResult, Reader, ReaderContext, ShutdownSignal,
+    StorageType,
+};
+use crate::connectors::{OffsetKey, OffsetValue};
+use crate::persistence::frontier::OffsetAntichain;
+use crate::persistence::PersistentId;
+
+/// The characters that are left as is when the cursor is substituted into the URL,
+/// the unreserved ones of RFC 3986.
+const HTTP_CURSOR_UNESCAPED: &AsciiSet = &NON_ALPHANUMERIC
+    .remove(b'-')
+    .remove(b'.')
+    .remove(b'_')
+    .remove(b'~');
+
+pub struct HttpReader {
+    client: HttpClient,
+    url_template: String,
+    headers: Vec<(String, String)>,
+    auth_token: Option<String>,
+    items_path: String,
+    cursor_path: Option<String>,
+    streaming_mode: ConnectorMode,
+    poll_interval: Duration,
+    persistent_id: Option<PersistentId>,
+
+    cursor: Option<Arc<String>>,
+    next_cursor: Option<String>,
+    pages_read: u64,
+    items: VecDeque<Vec<u8>>,
+    items_read_within_page: u64,
+    is_page_fetched: bool,
+    last_activity: Option<Instant>,
+    shutdown_signal: ShutdownSignal,
+}
+
+impl HttpReader {
+    pub const CURSOR_PLACEHOLDER: &'static str = "{cursor}";
+    const DEFAULT_POLL_INTERVAL: Duration = Duration::from_secs(10);
+    const DEFAULT_RETRY_AFTER: Duration = Duration::from_secs(5);
+    pub const MAX_RATE_LIMITED_ATTEMPTS: usize = 10;
+
+    /// Creates a reader requesting the pages from `url_template`, where
+    /// [`Self::CURSOR_PLACEHOLDER`] is substituted with the percent-encoded
+    /// pagination cursor, or with an empty string for the first page.
+    ///
+    /// `items_path` and `cursor_path` are JSON pointers to the array of items and to
+    /// the cursor of the next page within the response.
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        url_template: String,
+        headers: Vec<(String, String)>,
+        auth_token: Option<String>,
+        items_path: String,
+        cursor_path: Option<String>,
+        streaming_mode: ConnectorMode,
+        poll_interval: Option<Duration>,
+        persistent_id: Option<PersistentId>,
+    ) -> Result<HttpReader, ReadError> {
+        Ok(HttpReader {
+            client: HttpClient::builder().build()?,
+            url_template,
+            headers,
+            auth_token,
+            items_path,
+            cursor_path,
+            streaming_mode,
+            poll_interval: poll_interval.unwrap_or(Self::DEFAULT_POLL_INTERVAL),
+            persistent_id,
+
+            cursor: None,
+            next_cursor: None,
+            pages_read: 0,
+            items: VecDeque::new(),
+            items_read_within_page: 0,
+            is_page_fetched: false,
+            last_activity: None,
+            shutdown_signal: ShutdownSignal::default(),
+        })
+    }
+
+    /// Sends the request, waiting as long as the server asks if it's rate limited.
+    /// After [`Self::MAX_RATE_LIMITED_ATTEMPTS`] rate limited attempts, the last
+    /// response is reported as an error.
+    fn get_with_retries(&self, url: &str) -> Result<HttpResponse, ReadError> {
+        let mut attempt = 1;
+        loop {
+            let mut request = self.client.get(url);
+            for (name, value) in &self.headers {
+                request = request.header(name, value);
+            }
+            if let Some(auth_token) = &self.auth_token {
+                request = request.bearer_auth(auth_token);
+            }
+
+            let response = request.send()?;
+            if response.status() != HttpStatusCode::TOO_MANY_REQUESTS
+                || attempt >= Self::MAX_RATE_LIMITED_ATTEMPTS
+            {
+                return Ok(response.error_for_status()?);
+            }
+            let retry_after = response
+                .headers()
+                .get(RETRY_AFTER)
+                .and_then(|value| value.to_str().ok())
+                .and_then(|value| value.trim().parse().ok())
+                .map_or(Self::DEFAULT_RETRY_AFTER, Duration::from_secs);
+            warn!(
+                "HTTP request to {url} was rate limited (attempt {attempt} of {}), retrying in {retry_after:?}",
+                Self::MAX_RATE_LIMITED_ATTEMPTS
+            );
+            sleep(retry_after);
+            attempt += 1;
+        }
+    }
+
+    /// Requests the page for the current cursor. The items that were already
+    /// emitted from this page are skipped.
+    fn fetch_page(&mut self) -> Result<(), ReadError> {
+        let cursor = self.cursor.as_deref().map_or("", String::as_str);
+        let url = self.url_template.replace(
+            Self::CURSOR_PLACEHOLDER,
+            &utf8_percent_encode(cursor, HTTP_CURSOR_UNESCAPED).to_string(),
+        );
+        let response: serde_json::Value = self.get_with_retries(&url)?.json()?;
+
+        let items = response
+            .pointer(&self.items_path)
+            .and_then(serde_json::Value::as_array)
+            .ok_or_else(|| ReadError::HttpItemsNotFound(self.items_path.clone()))?;
+        let items_to_skip = usize::try_from(self.items_read_within_page)
+            .map_err(|_| ReadError::HttpPagePositionTooLarge(self.items_read_within_page))?;
+        self.items.clear();
+        for item in items.iter().skip(items_to_skip) {
+            self.items.push_back(serde_json::to_vec(item)?);
+        }
+
+        self.next_cursor = self
+            .cursor_path
+            .as_ref()
+            .and_then(|cursor_path| response.pointer(cursor_path))
+            .and_then(|cursor| match cursor {
+                serde_json::Value::String(cursor) => Some(cursor.clone()),
+                serde_json::Value::Number(cursor) => Some(cursor.to_string()),
+                _ => None,
+            });
+        self.is_page_fetched = true;
+
+        Ok(())
+    }
+
+    /// Moves to the next page if the last response has pointed to it. Returns
+    /// `false` if the pagination has ended.
+    fn advance_cursor(&mut self) -> bool {
+        match self.next_cursor.take() {
+            Some(next_cursor) if self.cursor.as_deref() != Some(&next_cursor) => {
+                self.cursor = Some(Arc::new(next_cursor));
+                self.pages_read += 1;
+                self.items_read_within_page = 0;
+                true
+            }
+            _ => false,
+        }
+    }
+}
+
+impl Reader for HttpReader {
+    fn seek(&mut self, frontier: &OffsetAntichain) -> Result<(), ReadError> {
+        let offset_value = frontier.get_offset(&OffsetKey::Empty);
+        let Some(OffsetValue::HttpCursor {
+            pages_read,
+            cursor,
+            items_read_within_page,
+        }) = offset_value
+        else {
+            if offset_value.is_some() {
+                warn!("Incorrect type of offset value in Http frontier: {offset_value:?}");
+            }
+            return Ok(());
+        };
+
+        self.pages_read = *pages_read;
+        self.cursor.clone_from(cursor);
+        self.items_read_within_page = *items_read_within_page;
+        self.items.clear();
+        self.is_page_fetched = false;
+
+        Ok(())
+    }
+
+    fn read(&mut self) -> Result<ReadResult, ReadError> {
+        loop {
+            if let Some(item) = self.items.pop_front() {
+                self.items_read_within_page += 1;
+                self.last_activity = Some(Instant::now());
+
+                let offset = (
+                    OffsetKey::Empty,
+                    OffsetValue::HttpCursor {
+                        pages_read: self.pages_read,
+                        cursor: self.cursor.clone(),
+                        items_read_within_page: self.items_read_within_page,
+                    },
+                );
+                return Ok(ReadResult::Data(
+                    ReaderContext::from_raw_bytes(DataEventType::Insert, item),
+                    offset,
+                ));
+            }
+
+            if self.is_page_fetched && !self.advance_cursor() {
+                if !self.streaming_mode.is_polling_enabled() {
+                    return Ok(ReadResult::Finished);
+                }
+                // The last page is requested again, since new items may appear there
+                self.shutdown_signal.sleep(self.poll_interval);
+                if self.shutdown_signal.is_requested() {
+                    return Ok(ReadResult::Finished);
+                }
+            }
+            self.is_page_fetched = false;
+            self.fetch_page()?;
+        }
+    }
+
+    fn persistent_id(&self) -> Option<PersistentId> {
+        self.persistent_id
+    }
+
+    fn update_persistent_id(&mut self, persistent_id: Option<PersistentId>) {
+        self.persistent_id = persistent_id;
+    }
+
+    fn set_shutdown_signal(&mut self, signal: ShutdownSignal) {
+        self.shutdown_signal = signal;
+    }
+
+    fn storage_type(&self) -> StorageType {
+        StorageType::Http
+    }
+
+    fn last_activity(&self) -> Option<Instant> {
+        self.last_activity
+    }
+}
@@ -0,0 +1,453 @@
+// Copyright © 2024 Pathway
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use iceberg::arrow::schema_to_arrow_schema as iceberg_schema_to_arrow_schema;
+use iceberg::spec::{
+    DataFileFormat as IcebergDataFileFormat, NestedField as IcebergNestedField,
+    PrimitiveType as IcebergPrimitiveType, Schema as IcebergSchema, Type as IcebergType,
+};
+use iceberg::table::Table as IcebergTable;
+use iceberg::transaction::Transaction as IcebergTransaction;
+use iceberg::writer::base_writer::data_file_writer::DataFileWriterBuilder as IcebergDataFileWriterBuilder;
+use iceberg::writer::file_writer::location_generator::{
+    DefaultFileNameGenerator as IcebergFileNameGenerator,
+    DefaultLocationGenerator as IcebergLocationGenerator,
+};
+use iceberg::writer::file_writer::ParquetWriterBuilder as IcebergParquetWriterBuilder;
+use iceberg::writer::{IcebergWriter as _, IcebergWriterBuilder as _};
+use iceberg::{
+    Catalog as IcebergCatalog, NamespaceIdent as IcebergNamespaceIdent,
+    TableCreation as IcebergTableCreation, TableIdent as IcebergTableIdent,
+};
+use iceberg_arrow_array::{
+    new_null_array as iceberg_new_null_array, ArrayRef as IcebergArrowArrayRef,
+    BinaryArray as IcebergBinaryArray, BooleanArray as IcebergBooleanArray,
+    Float64Array as IcebergFloat64Array, Int64Array as IcebergInt64Array,
+    RecordBatch as IcebergRecordBatch, StringArray as IcebergStringArray,
+    TimestampMicrosecondArray as IcebergTimestampArray,
+};
+use iceberg_arrow_schema::{Field as IcebergArrowField, Schema as IcebergArrowSchema};
+use iceberg_catalog_glue::{GlueCatalog, GlueCatalogConfig};
+use iceberg_catalog_rest::{RestCatalog, RestCatalogConfig};
+use iceberg_parquet::file::properties::WriterProperties as IcebergWriterProperties;
+use tokio::runtime::Runtime as TokioRuntime;
+use uuid::Uuid;
+
+use crate::connectors::data_format::FormatterContext;
+use crate::connectors::data_storage::{
+    BufferedColumns, CommitPolicy, WriteError, Writer, SPECIAL_OUTPUT_FIELDS,
+};
+use crate::engine::{Type, Value};
+use crate::python_api::ValueField;
+
+/// Defines the catalog managing an Iceberg table.
+#[derive(Clone, Debug)]
+pub enum IcebergCatalogConfig {
+    /// A catalog served over the Iceberg REST protocol at `uri`.
+    Rest {
+        uri: String,
+        warehouse: Option<String>,
+        properties: HashMap<String, String>,
+    },
+
+    /// An AWS Glue catalog, with the table data stored under `warehouse`.
+    Glue {
+        warehouse: String,
+        properties: HashMap<String, String>,
+    },
+}
+
+impl IcebergCatalogConfig {
+    async fn connect(self) -> Result<IcebergCatalogClient, WriteError> {
+        Ok(match self {
+            Self::Rest {
+                uri,
+                warehouse,
+                properties,
+            } => {
+                let config = match warehouse {
+                    Some(warehouse) => RestCatalogConfig::builder()
+                        .uri(uri)
+                        .warehouse(warehouse)
+                        .props(properties)
+                        .build(),
+                    None => RestCatalogConfig::builder()
+                        .uri(uri)
+                        .props(properties)
+                        .build(),
+                };
+                IcebergCatalogClient::Rest(RestCatalog::new(config))
+            }
+            Self::Glue {
+                warehouse,
+                properties,
+            } => {
+                let config = GlueCatalogConfig::builder()
+                    .warehouse(warehouse)
+                    .props(properties)
+                    .build();
+                IcebergCatalogClient::Glue(GlueCatalog::new(config).await?)
+            }
+        })
+    }
+}
+
+// The transactions are committed with a concrete catalog type, so the catalogs
+// are kept in an enum rather than behind a trait object
+enum IcebergCatalogClient {
+    Rest(RestCatalog),
+    Glue(GlueCatalog),
+}
+
+impl IcebergCatalogClient {
+    async fn open_table(
+        &self,
+        namespace: &[String],
+        table_name: &str,
+        schema: IcebergSchema,
+    ) -> Result<IcebergTable, WriteError> {
+        match self {
+            Self::Rest(catalog) => {
+                Self::open_table_in(catalog, namespace, table_name, schema).await
+            }
+            Self::Glue(catalog) => {
+                Self::open_table_in(catalog, namespace, table_name, schema).await
+            }
+        }
+    }
+
+    async fn open_table_in(
+        catalog: &impl IcebergCatalog,
+        namespace: &[String],
+        table_name: &str,
+        schema: IcebergSchema,
+    ) -> Result<IcebergTable, WriteError> {
+        let namespace = IcebergNamespaceIdent::from_strs(namespace)?;
+        let table_ident = IcebergTableIdent::new(namespace.clone(), table_name.to_string());
+        if catalog.table_exists(&table_ident).await? {
+            return Ok(catalog.load_table(&table_ident).await?);
+        }
+
+        if !catalog.namespace_exists(&namespace).await? {
+            catalog.create_namespace(&namespace, HashMap::new()).await?;
+        }
+        let creation = IcebergTableCreation::builder()
+            .name(table_name.to_string())
+            .schema(schema)
+            .build();
+        Ok(catalog.create_table(&namespace, creation).await?)
+    }
+
+    async fn commit(
+        &self,
+        transaction: IcebergTransaction<'_>,
+    ) -> Result<IcebergTable, WriteError> {
+        Ok(match self {
+            Self::Rest(catalog) => transaction.commit(catalog).await?,
+            Self::Glue(catalog) => transaction.commit(catalog).await?,
+        })
+    }
+}
+
+/// Appends the changes of the output table to an Iceberg table along with their
+/// `time` and `diff`. The buffered rows are written into a new data file on each
+/// commit, and the file is added to the table with a fast-append snapshot.
+pub struct IcebergWriter {
+    catalog: IcebergCatalogClient,
+    table: IcebergTable,
+    // Types of the written columns, including the special ones
+    column_types: Vec<Type>,
+    // Schema of the table, which may differ from the written fields in column order
+    schema: Arc<IcebergArrowSchema>,
+    // Index of the table column for each of the buffered columns
+    column_indices: Vec<usize>,
+    buffered_columns: BufferedColumns,
+    commit_policy: CommitPolicy,
+
+    // The runtime is declared last so that it's dropped after the catalog and the
+    // table, which may still have pending tasks bound to it
+    runtime: TokioRuntime,
+}
+
+impl IcebergWriter {
+    /// Opens the table `table_name` in `namespace`, creating both of them if they
+    /// don't exist yet. An existing table must have all of the written columns.
+    pub fn new(
+        catalog_config: IcebergCatalogConfig,
+        namespace: &[String],
+        table_name: &str,
+        value_fields: &[ValueField],
+        min_commit_frequency: Option<Duration>,
+    ) -> Result<Self, WriteError> {
+        let written_schema = Self::construct_schema(value_fields)?;
+        let runtime = tokio::runtime::Builder::new_multi_thread()
+            .enable_all()
+            .build()?;
+        let (catalog, table) = runtime.block_on(async {
+            let catalog = catalog_config.connect().await?;
+            let table = catalog
+                .open_table(namespace, table_name, written_schema)
+                .await?;
+            Ok::<_, WriteError>((catalog, table))
+        })?;
+
+        let schema = Arc::new(iceberg_schema_to_arrow_schema(
+            table.metadata().current_schema(),
+        )?);
+        let mut column_names = Vec::with_capacity(value_fields.len() + 2);
+        let mut column_types = Vec::with_capacity(value_fields.len() + 2);
+        for field in value_fields {
+            column_names.push(field.name.as_str());
+            column_types.push(field.type_);
+        }
+        for (name, type_) in SPECIAL_OUTPUT_FIELDS {
+            column_names.push(name);
+            column_types.push(type_);
+        }
+        let column_indices = Self::map_columns(&column_names, &schema)?;
+
+        Ok(Self {
+            catalog,
+            table,
+            buffered_columns: BufferedColumns::new(value_fields.len(), false),
+            column_types,
+            schema,
+            column_indices,
+            commit_policy: CommitPolicy::new(None, min_commit_frequency),
+            runtime,
+        })
+    }
+
+    fn iceberg_type(type_: Type) -> Result<IcebergType, WriteError> {
+        Ok(IcebergType::Primitive(match type_ {
+            Type::Bool => IcebergPrimitiveType::Boolean,
+            Type::Int | Type::Duration => IcebergPrimitiveType::Long,
+            Type::Float => IcebergPrimitiveType::Double,
+            Type::Pointer | Type::String | Type::Json => IcebergPrimitiveType::String,
+            Type::Bytes => IcebergPrimitiveType::Binary,
+            Type::DateTimeNaive => IcebergPrimitiveType::Timestamp,
+            Type::DateTimeUtc => IcebergPrimitiveType::Timestamptz,
+            Type::Any | Type::Array | Type::Tuple | Type::PyObjectWrapper => {
+                return Err(WriteError::UnsupportedType(type_))
+            }
+        }))
+    }
+
+    pub fn construct_schema(value_fields: &[ValueField]) -> Result<IcebergSchema, WriteError> {
+        let mut fields = Vec::new();
+        let mut next_field_id = 1;
+        for field in value_fields {
+            let field_type = Self::iceberg_type(field.type_)?;
+            fields.push(Arc::new(if field.is_optional {
+                IcebergNestedField::optional(next_field_id, &field.name, field_type)
+            } else {
+                IcebergNestedField::required(next_field_id, &field.name, field_type)
+            }));
+            next_field_id += 1;
+        }
+        for (name, type_) in SPECIAL_OUTPUT_FIELDS {
+            fields.push(Arc::new(IcebergNestedField::required(
+                next_field_id,
+                name,
+                Self::iceberg_type(type_)?,
+            )));
+            next_field_id += 1;
+        }
+        Ok(IcebergSchema::builder().with_fields(fields).build()?)
+    }
+
+    /// Matches the written columns with the columns of the table by name. The
+    /// columns of the table that aren't written are filled with nulls.
+    fn map_columns(
+        column_names: &[&str],
+        table_schema: &IcebergArrowSchema,
+    ) -> Result<Vec<usize>, WriteError> {
+        let mut column_indices = Vec::with_capacity(column_names.len());
+        for name in column_names {
+            let (index, _) = table_schema
+                .column_with_name(name)
+                .ok_or_else(|| WriteError::IcebergColumnMissing((*name).to_string()))?;
+            column_indices.push(index);
+        }
+        for (index, table_field) in table_schema.fields().iter().enumerate() {
+            if !table_field.is_nullable() && !column_indices.contains(&index) {
+                return Err(WriteError::IcebergRequiredColumnNotWritten(
+                    table_field.name().clone(),
+                ));
+            }
+        }
+        Ok(column_indices)
+    }
+
+    fn column_values<T>(
+        values: &[Value],
+        type_: Type,
+        extract: impl Fn(&Value) -> Option<T>,
+    ) -> Result<Vec<Option<T>>, WriteError> {
+        values
+            .iter()
+            .map(|value| match value {
+                Value::None => Ok(None),
+                value => extract(value)
+                    .map(Some)
+                    .ok_or_else(|| WriteError::IcebergTypeMismatch(value.clone(), type_)),
+            })
+            .collect()
+    }
+
+    fn arrow_array(
+        type_: Type,
+        table_field: &IcebergArrowField,
+        values: &[Value],
+    ) -> Result<IcebergArrowArrayRef, WriteError> {
+        Ok(match type_ {
+            Type::Bool => Arc::new(IcebergBooleanArray::from(Self::column_values(
+                values,
+                type_,
+                |value| match value {
+                    Value::Bool(b) => Some(*b),
+                    _ => None,
+                },
+            )?)),
+            Type::Int | Type::Duration => Arc::new(IcebergInt64Array::from(Self::column_values(
+                values,
+                type_,
+                |value| match value {
+                    Value::Int(i) => Some(*i),
+                    Value::Duration(d) => Some(d.microseconds()),
+                    _ => None,
+                },
+            )?)),
+            Type::Float => Arc::new(IcebergFloat64Array::from(Self::column_values(
+                values,
+                type_,
+                |value| match value {
+                    Value::Float(f) => Some((*f).into()),
+                    _ => None,
+                },
+            )?)),
+            Type::Pointer | Type::String | Type::Json => Arc::new(IcebergStringArray::from(
+                Self::column_values(values, type_, |value| match value {
+                    Value::String(s) => Some(s.to_string()),
+                    Value::Pointer(p) => Some(p.to_string()),
+                    Value::Json(j) => Some(j.to_string()),
+                    _ => None,
+                })?,
+            )),
+            Type::Bytes => {
+                let values = Self::column_values(values, type_, |value| match value {
+                    Value::Bytes(b) => Some(b.to_vec()),
+                    _ => None,
+                })?;
+                Arc::new(IcebergBinaryArray::from_iter(values))
+            }
+            Type::DateTimeNaive | Type::DateTimeUtc => {
+                let values = Self::column_values(values, type_, |value| match (value, type_) {
+                    (Value::DateTimeNaive(dt), Type::DateTimeNaive) => {
+                        Some(dt.timestamp_microseconds())
+                    }
+                    (Value::DateTimeUtc(dt), Type::DateTimeUtc) => {
+                        Some(dt.timestamp_microseconds())
+                    }
+                    _ => None,
+                })?;
+                // The data type carries the time zone of the table column, if any
+                Arc::new(
+                    IcebergTimestampArray::from(values)
+                        .with_data_type(table_field.data_type().clone()),
+                )
+            }
+            Type::Any | Type::Array | Type::Tuple | Type::PyObjectWrapper => {
+                return Err(WriteError::UnsupportedType(type_))
+            }
+        })
+    }
+
+    fn prepare_record_batch(&self) -> Result<IcebergRecordBatch, WriteError> {
+        let n_rows = self.buffered_columns.rows_count();
+        let mut data_columns: Vec<Option<IcebergArrowArrayRef>> =
+            vec![None; self.schema.fields().len()];
+        for ((column, type_), index) in self
+            .buffered_columns
+            .columns()
+            .iter()
+            .zip(&self.column_types)
+            .zip(&self.column_indices)
+        {
+            data_columns[*index] = Some(Self::arrow_array(
+                *type_,
+                self.schema.field(*index),
+                column,
+            )?);
+        }
+        let data_columns = data_columns
+            .into_iter()
+            .zip(self.schema.fields())
+            .map(|(column, field)| {
+                column.unwrap_or_else(|| iceberg_new_null_array(field.data_type(), n_rows))
+            })
+            .collect();
+        Ok(IcebergRecordBatch::try_new(
+            self.schema.clone(),
+            data_columns,
+        )?)
+    }
+
+    fn commit_buffer(&mut self) -> Result<(), WriteError> {
+        let record_batch = self.prepare_record_batch()?;
+        let Self {
+            runtime,
+            catalog,
+            table,
+            ..
+        } = self;
+        let updated_table = runtime.block_on(async {
+            let location_generator = IcebergLocationGenerator::new(table.metadata().clone())?;
+            // The random suffix keeps the names unique across the restarts
+            let file_name_generator = IcebergFileNameGenerator::new(
+                "pathway".to_string(),
+                Some(Uuid::new_v4().to_string()),
+                IcebergDataFileFormat::Parquet,
+            );
+            let parquet_writer_builder = IcebergParquetWriterBuilder::new(
+                IcebergWriterProperties::default(),
+                table.metadata().current_schema().clone(),
+                table.file_io().clone(),
+                location_generator,
+                file_name_generator,
+            );
+            let mut data_file_writer =
+                IcebergDataFileWriterBuilder::new(parquet_writer_builder, None)
+                    .build()
+                    .await?;
+            data_file_writer.write(record_batch).await?;
+            let data_files = data_file_writer.close().await?;
+
+            let transaction = IcebergTransaction::new(table);
+            let mut append = transaction.fast_append(None, Vec::new())?;
+            append.add_data_files(data_files)?;
+            catalog.commit(append.apply().await?).await
+        })?;
+        self.table = updated_table;
+        Ok(())
+    }
+}
+
+impl Writer for IcebergWriter {
+    fn write(&mut self, data: FormatterContext) -> Result<(), WriteError> {
+        self.buffered_columns.push_row(data)
+    }
+
+    fn flush(&mut self, forced: bool) -> Result<(), WriteError> {
+        let buffered_rows = self.buffered_columns.rows_count();
+        if self.commit_policy.is_commit_due(buffered_rows, forced) {
+            self.commit_buffer()?;
+            self.buffered_columns.clear();
+            self.commit_policy.on_commit();
+        }
+        Ok(())
+    }
+}
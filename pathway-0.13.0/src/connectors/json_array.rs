@@ -0,0 +1,339 @@
+// Copyright © 2024 Pathway
+
+use std::fs::File;
+use std::io;
+use std::io::{BufRead, BufReader};
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use log::warn;
+
+use crate::connectors::data_storage::{
+    DataEventType, ReadError, ReadResult, Reader, ReaderContext, StorageType,
+};
+use crate::connectors::metadata::SourceMetadata;
+use crate::connectors::{OffsetKey, OffsetValue};
+use crate::persistence::frontier::OffsetAntichain;
+use crate::persistence::PersistentId;
+use crate::timestamp::current_unix_timestamp_secs;
+
+/// Splits a JSON pointer, as defined in RFC 6901, into the reference tokens. The
+/// empty pointer refers to the whole document.
+fn json_pointer_tokens(json_pointer: &str) -> Result<Vec<String>, ReadError> {
+    if json_pointer.is_empty() {
+        return Ok(Vec::new());
+    }
+    let Some(tokens) = json_pointer.strip_prefix('/') else {
+        return Err(ReadError::InvalidJsonPointer(json_pointer.to_string()));
+    };
+    Ok(tokens
+        .split('/')
+        .map(|token| token.replace("~1", "/").replace("~0", "~"))
+        .collect())
+}
+
+/// Skips the whitespace and returns the next byte without consuming it.
+fn peek_json_byte(reader: &mut impl BufRead) -> io::Result<Option<u8>> {
+    loop {
+        let available = reader.fill_buf()?;
+        if available.is_empty() {
+            return Ok(None);
+        }
+        let whitespace_len = available
+            .iter()
+            .take_while(|byte| byte.is_ascii_whitespace())
+            .count();
+        if whitespace_len < available.len() {
+            let byte = available[whitespace_len];
+            reader.consume(whitespace_len);
+            return Ok(Some(byte));
+        }
+        reader.consume(whitespace_len);
+    }
+}
+
+fn expect_json_byte(reader: &mut impl BufRead, expected: u8) -> Result<(), ReadError> {
+    if peek_json_byte(reader)? == Some(expected) {
+        reader.consume(1);
+        Ok(())
+    } else {
+        Err(ReadError::MalformedJsonArray(format!(
+            "expected {:?}",
+            char::from(expected)
+        )))
+    }
+}
+
+/// Reads the JSON value at the current position into `buf`, or skips it if `buf`
+/// is `None`. Only the nesting and the string literals are tracked, the value
+/// itself is validated by the parser. A scalar ends at the first delimiter, which
+/// isn't consumed.
+fn read_raw_json_value(
+    reader: &mut impl BufRead,
+    mut buf: Option<&mut Vec<u8>>,
+) -> Result<(), ReadError> {
+    let mut depth: usize = 0;
+    let mut in_string = false;
+    let mut escaped = false;
+    let mut value_started = false;
+
+    loop {
+        let available = reader.fill_buf()?;
+        if available.is_empty() {
+            if !value_started || depth > 0 || in_string {
+                return Err(ReadError::MalformedJsonArray(
+                    "unexpected end of input".to_string(),
+                ));
+            }
+            return Ok(());
+        }
+
+        let mut used = 0;
+        let mut value_finished = false;
+        for &byte in available {
+            if in_string {
+                used += 1;
+                if escaped {
+                    escaped = false;
+                } else if byte == b'\\' {
+                    escaped = true;
+                } else if byte == b'"' {
+                    in_string = false;
+                    value_finished = depth == 0;
+                }
+            } else {
+                let is_delimiter =
+                    matches!(byte, b',' | b':' | b'}' | b']') || byte.is_ascii_whitespace();
+                if depth == 0 && is_delimiter {
+                    value_finished = true;
+                    break;
+                }
+                used += 1;
+                value_started = true;
+                match byte {
+                    b'"' => in_string = true,
+                    b'{' | b'[' => depth += 1,
+                    b'}' | b']' => {
+                        depth -= 1;
+                        value_finished = depth == 0;
+                    }
+                    _ => {}
+                }
+            }
+            if value_finished {
+                break;
+            }
+        }
+
+        if let Some(buf) = buf.as_mut() {
+            buf.extend_from_slice(&available[..used]);
+        }
+        reader.consume(used);
+        if value_finished {
+            if !value_started {
+                return Err(ReadError::MalformedJsonArray(
+                    "expected a value".to_string(),
+                ));
+            }
+            return Ok(());
+        }
+    }
+}
+
+/// Moves the reader to the value referred to by the tokens of a JSON pointer.
+/// Returns whether the value exists. The values preceding it are skipped without
+/// being stored.
+fn seek_json_pointer(reader: &mut impl BufRead, tokens: &[String]) -> Result<bool, ReadError> {
+    for token in tokens {
+        match peek_json_byte(reader)? {
+            Some(b'{') => {
+                reader.consume(1);
+                loop {
+                    if peek_json_byte(reader)? == Some(b'}') {
+                        return Ok(false);
+                    }
+                    let mut raw_key = Vec::new();
+                    read_raw_json_value(reader, Some(&mut raw_key))?;
+                    let key: String = serde_json::from_slice(&raw_key).map_err(|_| {
+                        ReadError::MalformedJsonArray("expected an object key".to_string())
+                    })?;
+                    expect_json_byte(reader, b':')?;
+                    if key == *token {
+                        break;
+                    }
+                    read_raw_json_value(reader, None)?;
+                    if peek_json_byte(reader)? == Some(b',') {
+                        reader.consume(1);
+                    }
+                }
+            }
+            Some(b'[') => {
+                reader.consume(1);
+                let Ok(index) = token.parse::<usize>() else {
+                    return Ok(false);
+                };
+                for _ in 0..index {
+                    if peek_json_byte(reader)? == Some(b']') {
+                        return Ok(false);
+                    }
+                    read_raw_json_value(reader, None)?;
+                    if peek_json_byte(reader)? == Some(b',') {
+                        reader.consume(1);
+                    }
+                }
+                if peek_json_byte(reader)? == Some(b']') {
+                    return Ok(false);
+                }
+            }
+            _ => return Ok(false),
+        }
+    }
+    Ok(true)
+}
+
+/// Reads the elements of a JSON array from a file. The array may be the whole file
+/// or be nested in it, then it's located by a JSON pointer, such as `/data/items`.
+/// The elements are read one at a time, so only a single element is held in memory,
+/// and each of them is passed as raw bytes, same as a line of a JSON Lines file.
+pub struct JsonArrayFileReader {
+    path: Arc<PathBuf>,
+    json_pointer: String,
+    persistent_id: Option<PersistentId>,
+
+    reader: Option<BufReader<File>>,
+    total_entries_read: u64,
+    entries_to_skip: u64,
+    is_finished: bool,
+}
+
+impl JsonArrayFileReader {
+    pub fn new(
+        path: impl Into<PathBuf>,
+        json_pointer: impl Into<String>,
+        persistent_id: Option<PersistentId>,
+    ) -> Result<Self, ReadError> {
+        let json_pointer = json_pointer.into();
+        json_pointer_tokens(&json_pointer)?;
+        Ok(Self {
+            path: Arc::new(path.into()),
+            json_pointer,
+            persistent_id,
+
+            reader: None,
+            total_entries_read: 0,
+            entries_to_skip: 0,
+            is_finished: false,
+        })
+    }
+
+    fn open_file(&mut self) -> Result<ReadResult, ReadError> {
+        let file = File::open(self.path.as_path())?;
+        let metadata = SourceMetadata::from_fs_meta(
+            self.path.as_path(),
+            &file.metadata()?,
+            current_unix_timestamp_secs(),
+        );
+        let mut reader = BufReader::new(file);
+        let tokens = json_pointer_tokens(&self.json_pointer)?;
+        if !seek_json_pointer(&mut reader, &tokens)? || peek_json_byte(&mut reader)? != Some(b'[') {
+            return Err(ReadError::JsonArrayNotFound(self.json_pointer.clone()));
+        }
+        reader.consume(1);
+        self.reader = Some(reader);
+        Ok(ReadResult::NewSource(Some(metadata)))
+    }
+
+    fn next_element(&mut self) -> Result<Option<Vec<u8>>, ReadError> {
+        let reader = self
+            .reader
+            .as_mut()
+            .expect("the file must be opened before reading elements");
+        if peek_json_byte(reader)? == Some(b']') {
+            return Ok(None);
+        }
+        if self.total_entries_read > 0 {
+            expect_json_byte(reader, b',')?;
+        }
+        let mut element = Vec::new();
+        read_raw_json_value(reader, Some(&mut element))?;
+        Ok(Some(element))
+    }
+}
+
+impl Reader for JsonArrayFileReader {
+    fn seek(&mut self, frontier: &OffsetAntichain) -> Result<(), ReadError> {
+        let offset_value = frontier.get_offset(&OffsetKey::Empty);
+        let Some(OffsetValue::FilePosition {
+            total_entries_read,
+            path,
+            ..
+        }) = offset_value
+        else {
+            if offset_value.is_some() {
+                warn!(
+                    "Incorrect type of offset value in JsonArrayFilesystem frontier: {offset_value:?}"
+                );
+            }
+            return Ok(());
+        };
+
+        if *path != self.path {
+            warn!(
+                "Persisted offset refers to {path:?}, while the reader is configured for {:?}. Reading from the beginning.",
+                self.path
+            );
+            return Ok(());
+        }
+
+        // The elements can only be found by scanning the array, so the ones that
+        // were already processed are skipped when the file is opened.
+        self.entries_to_skip = *total_entries_read;
+        Ok(())
+    }
+
+    fn read(&mut self) -> Result<ReadResult, ReadError> {
+        if self.is_finished {
+            return Ok(ReadResult::Finished);
+        }
+        if self.reader.is_none() {
+            return self.open_file();
+        }
+
+        while let Some(element) = self.next_element()? {
+            self.total_entries_read += 1;
+            if self.total_entries_read <= self.entries_to_skip {
+                continue;
+            }
+
+            let offset = (
+                OffsetKey::Empty,
+                OffsetValue::FilePosition {
+                    total_entries_read: self.total_entries_read,
+                    path: self.path.clone(),
+                    bytes_offset: 0,
+                },
+            );
+            return Ok(ReadResult::Data(
+                ReaderContext::from_raw_bytes(DataEventType::Insert, element),
+                offset,
+            ));
+        }
+
+        self.is_finished = true;
+        Ok(ReadResult::FinishedSource {
+            commit_allowed: true,
+        })
+    }
+
+    fn persistent_id(&self) -> Option<PersistentId> {
+        self.persistent_id
+    }
+
+    fn update_persistent_id(&mut self, persistent_id: Option<PersistentId>) {
+        self.persistent_id = persistent_id;
+    }
+
+    fn storage_type(&self) -> StorageType {
+        StorageType::JsonArrayFilesystem
+    }
+}
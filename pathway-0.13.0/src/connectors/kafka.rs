@@ -0,0 +1,760 @@
+// Copyright © 2024 Pathway
+
+use std::collections::{HashMap, HashSet};
+use std::mem::take;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use log::{error, warn};
+use rdkafka::consumer::{BaseConsumer, Consumer, DefaultConsumerContext};
+use rdkafka::error::{KafkaError, RDKafkaErrorCode};
+use rdkafka::message::{Header as KafkaHeader, OwnedHeaders as KafkaHeaders};
+use rdkafka::producer::{BaseRecord, DeliveryResult, Producer, ProducerContext, ThreadedProducer};
+use rdkafka::topic_partition_list::{
+    Offset as KafkaOffset, TopicPartitionList as KafkaTopicPartitionList,
+};
+use rdkafka::util::Timeout;
+use rdkafka::{ClientConfig, ClientContext, Message};
+
+use crate::connectors::data_format::FormatterContext;
+use crate::connectors::data_storage::{
+    message_headers, ReadError, ReadResult, Reader, ReaderContext, ShutdownSignal, StorageType,
+    WriteError, Writer, WrittenRowsCounter,
+};
+use crate::connectors::{OffsetKey, OffsetValue};
+use crate::engine::{Timestamp, Type, Value};
+use crate::persistence::frontier::OffsetAntichain;
+use crate::persistence::PersistentId;
+
+/// Reads the messages of a Kafka topic.
+///
+/// A reader created with `KafkaReader::new` relies on the consumer subscribed to the
+/// topic, so the partitions are distributed among the members of the consumer group
+/// by rdkafka and the positions from the frontier are applied lazily, once the
+/// messages of the assigned partitions arrive.
+///
+/// A reader created with `KafkaReader::with_assignment` is pinned to the given
+/// partitions, bypassing the consumer group rebalance. They are assigned and
+/// positioned at build time, and `seek` reassigns them at the positions following
+/// the frontier.
+///
+/// The lazily applied positions of the partitions that are never assigned to the
+/// consumer, e.g. because the topic has fewer partitions after a restore, would be
+/// ignored silently. Hence they are reported once, if they remain unresolved for
+/// the seek validation timeout after the consumer gets its first assignment.
+pub struct KafkaReader {
+    consumer: BaseConsumer<DefaultConsumerContext>,
+    persistent_id: Option<PersistentId>,
+    topic: Arc<String>,
+    positions_for_seek: HashMap<i32, i64>,
+    // The partitions that were assigned to the consumer or delivered a message
+    seen_partitions: HashSet<i32>,
+    seek_validation_timeout: Duration,
+    first_assignment_at: Option<Instant>,
+    seek_validation_checked_at: Option<Instant>,
+    unresolved_seek_reported: bool,
+    // The offset to start from for each partition, if the partitions are assigned
+    // explicitly rather than by the consumer group
+    assigned_partitions: Option<HashMap<i32, i64>>,
+    poll_timeout: Option<Duration>,
+    last_activity: Option<Instant>,
+    shutdown_signal: ShutdownSignal,
+}
+
+impl Reader for KafkaReader {
+    fn read(&mut self) -> Result<ReadResult, ReadError> {
+        let deadline = self.poll_timeout.map(|timeout| Instant::now() + timeout);
+        loop {
+            self.validate_seek_positions();
+            // The wait for a message is done in polls bounded by the check interval,
+            // whether there's a timeout or not, so that the shutdown signal and the
+            // seek positions are still checked while the topic is idle
+            let poll_timeout = deadline.map_or(Self::SHUTDOWN_CHECK_INTERVAL, |deadline| {
+                deadline
+                    .saturating_duration_since(Instant::now())
+                    .min(Self::SHUTDOWN_CHECK_INTERVAL)
+            });
+            let Some(kafka_message) = self.consumer.poll(poll_timeout) else {
+                if self.shutdown_signal.is_requested() {
+                    return Ok(ReadResult::Finished);
+                }
+                if deadline.map_or(true, |deadline| Instant::now() < deadline) {
+                    continue;
+                }
+                // No messages have arrived within the timeout, so the control is
+                // returned to the caller, which can commit what's been read so far
+                return Ok(ReadResult::FinishedSource {
+                    commit_allowed: true,
+                });
+            };
+            let kafka_message = kafka_message?;
+            let message_key = kafka_message.key().map(<[u8]>::to_vec);
+            let message_payload = kafka_message.payload().map(<[u8]>::to_vec);
+            self.seen_partitions.insert(kafka_message.partition());
+
+            if let Some(last_read_offset) = self.positions_for_seek.get(&kafka_message.partition())
+            {
+                if last_read_offset >= &kafka_message.offset() {
+                    if let Err(e) = self.consumer.seek(
+                        kafka_message.topic(),
+                        kafka_message.partition(),
+                        KafkaOffset::Offset(*last_read_offset + 1),
+                        None,
+                    ) {
+                        error!(
+                            "Failed to seek topic and partition ({}, {}) to offset {}: {e}",
+                            kafka_message.topic(),
+                            kafka_message.partition(),
+                            *last_read_offset + 1
+                        );
+                    }
+                    continue;
+                }
+                self.positions_for_seek.remove(&kafka_message.partition());
+            }
+
+            let offset = {
+                let offset_key = OffsetKey::Kafka(self.topic.clone(), kafka_message.partition());
+                let offset_value = OffsetValue::KafkaOffset(kafka_message.offset());
+                (offset_key, offset_value)
+            };
+            let message = ReaderContext::from_key_value(message_key, message_payload);
+
+            self.last_activity = Some(Instant::now());
+            return Ok(ReadResult::Data(message, offset));
+        }
+    }
+
+    fn seek(&mut self, frontier: &OffsetAntichain) -> Result<(), ReadError> {
+        if self.assigned_partitions.is_some() {
+            return self.seek_assigned_partitions(frontier);
+        }
+
+        // "Lazy" seek implementation
+        for (offset_key, offset_value) in frontier {
+            let OffsetValue::KafkaOffset(position) = offset_value else {
+                warn!("Unexpected type of offset in Kafka frontier: {offset_value:?}");
+                continue;
+            };
+            if let OffsetKey::Kafka(topic, partition) = offset_key {
+                if self.topic != *topic {
+                    warn!(
+                        "Unexpected topic name. Expected: {}, Got: {topic}",
+                        *self.topic
+                    );
+                    continue;
+                }
+
+                /*
+                    Note: we can't do seek straight away, because it works only for
+                    assigned partitions.
+
+                    We also don't do any kind of assignment here, because it needs
+                    to be done on behalf of rdkafka client, taking account of other
+                    members in its' consumer group.
+                */
+                self.positions_for_seek.insert(*partition, *position);
+                self.unresolved_seek_reported = false;
+            } else {
+                error!("Unexpected offset in Kafka frontier: ({offset_key:?}, {offset_value:?})");
+            }
+        }
+
+        Ok(())
+    }
+
+    fn persistent_id(&self) -> Option<PersistentId> {
+        self.persistent_id
+    }
+
+    fn update_persistent_id(&mut self, persistent_id: Option<PersistentId>) {
+        self.persistent_id = persistent_id;
+    }
+
+    fn set_shutdown_signal(&mut self, signal: ShutdownSignal) {
+        self.shutdown_signal = signal;
+    }
+
+    fn storage_type(&self) -> StorageType {
+        StorageType::Kafka
+    }
+
+    fn max_allowed_consecutive_errors(&self) -> usize {
+        32
+    }
+
+    fn last_activity(&self) -> Option<Instant> {
+        self.last_activity
+    }
+}
+
+impl KafkaReader {
+    const SHUTDOWN_CHECK_INTERVAL: Duration = ShutdownSignal::CHECK_INTERVAL;
+    pub const DEFAULT_SEEK_VALIDATION_TIMEOUT: Duration = Duration::from_secs(60);
+
+    /// Creates a reader relying on the consumer group. The positions from the
+    /// frontier, whose partitions aren't assigned to the consumer within
+    /// `seek_validation_timeout` (a minute by default) after its first assignment,
+    /// are reported.
+    ///
+    /// A read waits for a message at most `poll_timeout`. If none arrives, the
+    /// read ends with [`ReadResult::FinishedSource`]. Without `poll_timeout`, it
+    /// waits until a message arrives or the shutdown is requested.
+    pub fn new(
+        consumer: BaseConsumer<DefaultConsumerContext>,
+        topic: String,
+        persistent_id: Option<PersistentId>,
+        poll_timeout: Option<Duration>,
+        seek_validation_timeout: Option<Duration>,
+    ) -> KafkaReader {
+        KafkaReader {
+            consumer,
+            persistent_id,
+            topic: Arc::new(topic),
+            positions_for_seek: HashMap::new(),
+            seen_partitions: HashSet::new(),
+            seek_validation_timeout: seek_validation_timeout
+                .unwrap_or(Self::DEFAULT_SEEK_VALIDATION_TIMEOUT),
+            first_assignment_at: None,
+            seek_validation_checked_at: None,
+            unresolved_seek_reported: false,
+            assigned_partitions: None,
+            poll_timeout,
+            last_activity: None,
+            shutdown_signal: ShutdownSignal::default(),
+        }
+    }
+
+    /// Creates a reader of the given partitions, each starting from the given
+    /// offset. The consumer must not be subscribed to the topic, since the
+    /// partitions are assigned to it directly.
+    pub fn with_assignment(
+        consumer: BaseConsumer<DefaultConsumerContext>,
+        topic: String,
+        partition_offsets: Vec<(i32, i64)>,
+        persistent_id: Option<PersistentId>,
+        poll_timeout: Option<Duration>,
+    ) -> Result<KafkaReader, ReadError> {
+        let mut reader = KafkaReader {
+            assigned_partitions: Some(partition_offsets.into_iter().collect()),
+            ..Self::new(consumer, topic, persistent_id, poll_timeout, None)
+        };
+        reader.assign_partitions()?;
+        Ok(reader)
+    }
+
+    /// Returns the offsets the explicitly assigned partitions are read from, or
+    /// `None` if the partitions are distributed by the consumer group.
+    pub fn assigned_partitions(&self) -> Option<&HashMap<i32, i64>> {
+        self.assigned_partitions.as_ref()
+    }
+
+    /// Returns the positions from the frontier, whose partitions were neither
+    /// assigned to the consumer nor delivered a message so far, ordered by the
+    /// partition. If they stay unresolved after the consumer is assigned its
+    /// partitions, the partitions of the topic don't match the persisted ones.
+    pub fn unresolved_seek_positions(&self) -> Vec<(i32, i64)> {
+        let mut positions: Vec<(i32, i64)> = self
+            .positions_for_seek
+            .iter()
+            .filter(|(partition, _)| !self.seen_partitions.contains(*partition))
+            .map(|(partition, position)| (*partition, *position))
+            .collect();
+        positions.sort_unstable();
+        positions
+    }
+
+    /// Checks the assignment of the consumer, no more often than once per
+    /// `SHUTDOWN_CHECK_INTERVAL`, and reports the unresolved seek positions once
+    /// the validation timeout has passed since the first assignment.
+    fn validate_seek_positions(&mut self) {
+        if self.unresolved_seek_reported
+            || self
+                .positions_for_seek
+                .keys()
+                .all(|partition| self.seen_partitions.contains(partition))
+        {
+            return;
+        }
+        if self
+            .seek_validation_checked_at
+            .is_some_and(|checked_at| checked_at.elapsed() < Self::SHUTDOWN_CHECK_INTERVAL)
+        {
+            return;
+        }
+        self.seek_validation_checked_at = Some(Instant::now());
+
+        match self.consumer.assignment() {
+            Ok(assignment) => {
+                for element in assignment.elements_for_topic(&self.topic) {
+                    self.seen_partitions.insert(element.partition());
+                }
+            }
+            Err(e) => {
+                warn!("Failed to get the assignment of Kafka consumer: {e}");
+                return;
+            }
+        }
+        if self.seen_partitions.is_empty() {
+            // The consumer group hasn't assigned the partitions yet
+            return;
+        }
+        let first_assignment_at = *self.first_assignment_at.get_or_insert_with(Instant::now);
+        if first_assignment_at.elapsed() < self.seek_validation_timeout {
+            return;
+        }
+
+        self.unresolved_seek_reported = true;
+        let unresolved_positions = self.unresolved_seek_positions();
+        if !unresolved_positions.is_empty() {
+            warn!(
+                "The partitions of Kafka topic {} weren't assigned within {:?}, so their persisted positions aren't applied: {unresolved_positions:?}",
+                *self.topic, self.seek_validation_timeout
+            );
+        }
+    }
+
+    fn assign_partitions(&mut self) -> Result<(), ReadError> {
+        let assigned_partitions = self
+            .assigned_partitions
+            .as_ref()
+            .expect("partitions must be assigned explicitly");
+        let mut assignment = KafkaTopicPartitionList::new();
+        for (partition, offset) in assigned_partitions {
+            assignment.add_partition_offset(
+                &self.topic,
+                *partition,
+                KafkaOffset::Offset(*offset),
+            )?;
+        }
+        self.consumer.assign(&assignment)?;
+        Ok(())
+    }
+
+    /// Moves the explicitly assigned partitions to the positions following the
+    /// frontier. The partitions absent in the frontier keep their start offsets.
+    fn seek_assigned_partitions(&mut self, frontier: &OffsetAntichain) -> Result<(), ReadError> {
+        let assigned_partitions = self
+            .assigned_partitions
+            .as_mut()
+            .expect("partitions must be assigned explicitly");
+        for (offset_key, offset_value) in frontier {
+            let (OffsetKey::Kafka(topic, partition), OffsetValue::KafkaOffset(position)) =
+                (offset_key, offset_value)
+            else {
+                error!("Unexpected offset in Kafka frontier: ({offset_key:?}, {offset_value:?})");
+                continue;
+            };
+            if self.topic != *topic {
+                warn!(
+                    "Unexpected topic name. Expected: {}, Got: {topic}",
+                    *self.topic
+                );
+                continue;
+            }
+            match assigned_partitions.get_mut(partition) {
+                Some(start_offset) => *start_offset = *position + 1,
+                None => {
+                    warn!("Partition {partition} from the frontier isn't assigned to the reader")
+                }
+            }
+        }
+        self.assign_partitions()
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum KafkaCompression {
+    #[default]
+    None,
+    Gzip,
+    Snappy,
+    Lz4,
+    Zstd,
+}
+
+impl KafkaCompression {
+    fn codec(self) -> &'static str {
+        match self {
+            Self::None => "none",
+            Self::Gzip => "gzip",
+            Self::Snappy => "snappy",
+            Self::Lz4 => "lz4",
+            Self::Zstd => "zstd",
+        }
+    }
+}
+
+/// Producer batching settings. The values that are not set are left to librdkafka.
+///
+/// Larger batches and a longer linger keep more messages in the local producer
+/// queue, so the queue fills up sooner. When it's full, `KafkaWriter::write` polls
+/// the producer and retries sending until there is space again, which means that
+/// these settings also bound how long a single write may block.
+///
+/// The `commit_timeout` bounds how long a commit waits for the delivery of the
+/// messages, `KafkaWriter::DEFAULT_COMMIT_TIMEOUT` if it's not set.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct KafkaProducerTuning {
+    pub batch_num_messages: Option<usize>,
+    pub linger: Option<Duration>,
+    pub commit_timeout: Option<Duration>,
+}
+
+/// Keeps the first delivery failure, so that it can be reported on commit instead
+/// of being only logged by librdkafka.
+#[derive(Default)]
+pub struct KafkaDeliveryContext {
+    failed_delivery: Mutex<Option<KafkaError>>,
+}
+
+impl ClientContext for KafkaDeliveryContext {}
+
+impl ProducerContext for KafkaDeliveryContext {
+    type DeliveryOpaque = ();
+
+    fn delivery(&self, delivery_result: &DeliveryResult<'_>, _delivery_opaque: ()) {
+        if let Err((error, _message)) = delivery_result {
+            error!("Failed to deliver a message to Kafka: {error}");
+            self.failed_delivery
+                .lock()
+                .unwrap()
+                .get_or_insert_with(|| error.clone());
+        }
+    }
+}
+
+pub struct KafkaWriter {
+    producer: ThreadedProducer<KafkaDeliveryContext>,
+    topic: String,
+    header_fields: Vec<(String, usize)>,
+    key_field_indices: Vec<usize>,
+    timestamp_field_index: Option<usize>,
+    commit_timeout: Duration,
+
+    // The messages sent since the last wait for the delivery
+    undelivered_messages: usize,
+    written_rows: WrittenRowsCounter,
+    is_finished: bool,
+
+    is_transactional: bool,
+    is_transaction_open: bool,
+}
+
+impl KafkaWriter {
+    pub const DEFAULT_COMMIT_TIMEOUT: Duration = Duration::from_secs(60);
+    const FINISH_TIMEOUT: Duration = Duration::from_secs(60);
+    const INIT_TRANSACTIONS_TIMEOUT: Duration = Duration::from_secs(60);
+
+    /// If `timestamp_field_index` is set, the value of this field becomes the
+    /// timestamp of the produced records, so that the time-based retention and
+    /// compaction work on it. Otherwise the broker assigns the timestamps.
+    ///
+    /// If `transactional_id` is set, or the client config already has a
+    /// `transactional.id`, the writer uses a transactional producer: the messages
+    /// written between two commits of the engine form a single Kafka transaction,
+    /// which is committed by `on_commit` or `finish` and aborted if the output
+    /// fails. Combined with the persistence, it makes the output exactly-once for
+    /// the consumers with `isolation.level=read_committed`. The id must be unique
+    /// per writer and stay the same across the restarts, so that the producer of
+    /// the previous run is fenced off.
+    ///
+    /// The cluster needs at least `transaction.state.log.replication.factor` brokers
+    /// for the transactions, three by default, so a single-broker setup must lower
+    /// it and `transaction.state.log.min.isr` to one. The `transaction.max.timeout.ms`
+    /// of the brokers must not be less than the `transaction.timeout.ms` of the
+    /// producer, and the period between the commits must fit into the latter.
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        mut client_config: ClientConfig,
+        topic: String,
+        header_fields: Vec<(String, usize)>,
+        key_field_indices: Vec<usize>,
+        value_fields_count: usize,
+        compression: KafkaCompression,
+        tuning: KafkaProducerTuning,
+        timestamp_field_index: Option<usize>,
+        transactional_id: Option<String>,
+    ) -> Result<KafkaWriter, WriteError> {
+        if let Some(index) = key_field_indices
+            .iter()
+            .find(|index| **index >= value_fields_count)
+        {
+            return Err(WriteError::KeyFieldIndexOutOfRange {
+                index: *index,
+                value_fields_count,
+            });
+        }
+
+        client_config.set("compression.codec", compression.codec());
+        if let Some(batch_num_messages) = tuning.batch_num_messages {
+            client_config.set("batch.num.messages", batch_num_messages.to_string());
+        }
+        if let Some(linger) = tuning.linger {
+            client_config.set("linger.ms", linger.as_millis().to_string());
+        }
+        if let Some(transactional_id) = transactional_id {
+            client_config.set("transactional.id", transactional_id);
+        }
+        let is_transactional = client_config.get("transactional.id").is_some();
+        let producer: ThreadedProducer<KafkaDeliveryContext> =
+            client_config.create_with_context(KafkaDeliveryContext::default())?;
+        if is_transactional {
+            // Fences off the producers with the same id and completes their
+            // transactions that were left open
+            producer.init_transactions(Self::INIT_TRANSACTIONS_TIMEOUT)?;
+        }
+
+        Ok(KafkaWriter {
+            producer,
+            topic,
+            header_fields,
+            key_field_indices,
+            timestamp_field_index,
+            commit_timeout: tuning
+                .commit_timeout
+                .unwrap_or(Self::DEFAULT_COMMIT_TIMEOUT),
+            undelivered_messages: 0,
+            written_rows: WrittenRowsCounter::default(),
+            is_finished: false,
+            is_transactional,
+            is_transaction_open: false,
+        })
+    }
+
+    fn ensure_transaction_open(&mut self) -> Result<(), WriteError> {
+        if self.is_transactional && !self.is_transaction_open {
+            self.producer.begin_transaction()?;
+            self.is_transaction_open = true;
+        }
+        Ok(())
+    }
+
+    /// Commits the open transaction, if any. The messages of a transaction are only
+    /// counted as written once it's committed.
+    fn commit_transaction(&mut self, timeout: Timeout) -> Result<(), WriteError> {
+        if !self.is_transaction_open {
+            return Ok(());
+        }
+        if let Err(error) = self.producer.commit_transaction(timeout) {
+            self.abort_transaction();
+            return Err(WriteError::Kafka(error));
+        }
+        self.is_transaction_open = false;
+        self.producer
+            .context()
+            .failed_delivery
+            .lock()
+            .unwrap()
+            .take();
+        self.written_rows.add(take(&mut self.undelivered_messages));
+        Ok(())
+    }
+
+    /// Aborts the open transaction, so that none of its messages become visible to
+    /// the consumers reading only the committed ones. The failure to abort is only
+    /// logged, since the transaction is aborted anyway once it times out, or once
+    /// the next producer with the same id starts.
+    fn abort_transaction(&mut self) {
+        if !take(&mut self.is_transaction_open) {
+            return;
+        }
+        self.undelivered_messages = 0;
+        self.producer
+            .context()
+            .failed_delivery
+            .lock()
+            .unwrap()
+            .take();
+        if let Err(e) = self.producer.abort_transaction(Self::FINISH_TIMEOUT) {
+            error!("Failed to abort the Kafka transaction: {e}");
+        }
+    }
+
+    fn wait_for_delivery(&mut self, timeout: Timeout) -> Result<(), WriteError> {
+        self.producer.flush(timeout)?;
+        match self
+            .producer
+            .context()
+            .failed_delivery
+            .lock()
+            .unwrap()
+            .take()
+        {
+            Some(error) => Err(WriteError::Kafka(error)),
+            None => {
+                if !self.is_transactional {
+                    self.written_rows.add(take(&mut self.undelivered_messages));
+                }
+                Ok(())
+            }
+        }
+    }
+
+    fn key_field_bytes(value: &Value) -> Result<&[u8], WriteError> {
+        match value {
+            Value::Bytes(bytes) => Ok(&bytes[..]),
+            Value::String(string) => Ok(string.as_bytes()),
+            _ => Err(WriteError::IncorrectKeyFieldType(value.clone())),
+        }
+    }
+
+    /// A single key field is used as is. With several key fields, each of them is
+    /// prefixed with its length as a big-endian `u32`, so that different values of
+    /// the fields can't produce the same key.
+    pub fn message_key(&self, data: &FormatterContext) -> Result<Vec<u8>, WriteError> {
+        match self.key_field_indices.as_slice() {
+            [] => Ok(data.key.0.to_le_bytes().to_vec()),
+            [index] => Ok(Self::key_field_bytes(&data.values[*index])?.to_vec()),
+            indices => {
+                let mut key = Vec::new();
+                for index in indices {
+                    let field = Self::key_field_bytes(&data.values[*index])?;
+                    let field_len = u32::try_from(field.len())
+                        .map_err(|_| WriteError::KeyFieldTooLong(field.len()))?;
+                    key.extend_from_slice(&field_len.to_be_bytes());
+                    key.extend_from_slice(field);
+                }
+                Ok(key)
+            }
+        }
+    }
+
+    /// Returns the timestamp of the records produced for `data` in milliseconds since
+    /// the epoch. An integer field is taken as such a timestamp already. `None` means
+    /// that the broker default is used.
+    pub fn message_timestamp(&self, data: &FormatterContext) -> Result<Option<i64>, WriteError> {
+        let Some(index) = self.timestamp_field_index else {
+            return Ok(None);
+        };
+        match &data.values[index] {
+            Value::None => Ok(None),
+            Value::Int(timestamp) => Ok(Some(*timestamp)),
+            Value::DateTimeUtc(date_time) => Ok(Some(date_time.timestamp_milliseconds())),
+            other => Err(WriteError::UnsupportedType(
+                other.simple_type().to_type().unwrap_or(Type::Any),
+            )),
+        }
+    }
+
+    /// Returns the headers of the messages produced for `data`. The `pathway_diff`
+    /// header is kept for compatibility, while `pathway_event` also distinguishes
+    /// the upserts from the insertions.
+    pub fn message_headers(&self, data: &FormatterContext) -> KafkaHeaders {
+        let mut headers = KafkaHeaders::new_with_capacity(self.header_fields.len() + 3);
+        for (key, value) in message_headers(&self.header_fields, data) {
+            headers = headers.insert(KafkaHeader {
+                key,
+                value: Some(&value),
+            });
+        }
+        headers
+    }
+}
+
+impl Drop for KafkaWriter {
+    fn drop(&mut self) {
+        // The writer that wasn't finished explicitly, for instance because the
+        // output failed, still tries to deliver what it has sent. An open transaction
+        // is aborted instead, since the engine didn't commit its messages.
+        if self.is_finished {
+            return;
+        }
+        if self.is_transactional {
+            self.abort_transaction();
+            return;
+        }
+        if let Err(e) = self.producer.flush(Self::FINISH_TIMEOUT) {
+            error!("Failed to deliver the remaining Kafka messages: {e}");
+        }
+    }
+}
+
+impl Writer for KafkaWriter {
+    fn write(&mut self, data: FormatterContext) -> Result<(), WriteError> {
+        self.ensure_transaction_open()?;
+        let key_as_bytes = self.message_key(&data)?;
+        let headers = self.message_headers(&data);
+        let timestamp = self.message_timestamp(&data)?;
+
+        for payload in &data.payloads {
+            let mut entry = BaseRecord::<Vec<u8>, Vec<u8>>::to(&self.topic)
+                .payload(payload)
+                .headers(headers.clone())
+                .key(&key_as_bytes);
+            if let Some(timestamp) = timestamp {
+                entry = entry.timestamp(timestamp);
+            }
+            // The local queue is bounded, see `KafkaProducerTuning` for how batching
+            // affects it. Wait for the queued messages to be delivered if it's full.
+            loop {
+                match self.producer.send(entry) {
+                    Ok(()) => {
+                        self.undelivered_messages += 1;
+                        break;
+                    }
+                    Err((
+                        KafkaError::MessageProduction(RDKafkaErrorCode::QueueFull),
+                        unsent_entry,
+                    )) => {
+                        self.producer.poll(Duration::from_millis(10));
+                        entry = unsent_entry;
+                        continue;
+                    }
+                    Err((e, _unsent_entry)) => {
+                        self.abort_transaction();
+                        return Err(WriteError::Kafka(e));
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn flush(&mut self, forced: bool) -> Result<(), WriteError> {
+        if forced {
+            if let Err(error) = self.wait_for_delivery(Timeout::After(self.commit_timeout)) {
+                self.abort_transaction();
+                return Err(error);
+            }
+        }
+        self.written_rows.on_flush();
+        Ok(())
+    }
+
+    fn rows_written_since_last_flush(&self) -> usize {
+        self.written_rows.last_flush()
+    }
+
+    /// Fails if the messages aren't delivered within the commit timeout, so that an
+    /// unreachable broker is reported instead of blocking the output forever.
+    fn on_commit(&mut self, _time: Timestamp) -> Result<(), WriteError> {
+        let timeout = Timeout::After(self.commit_timeout);
+        if self.is_transactional {
+            self.commit_transaction(timeout)
+        } else {
+            self.wait_for_delivery(timeout)
+        }
+    }
+
+    /// Unlike a forced flush, it gives up after `FINISH_TIMEOUT`, so that an
+    /// unreachable broker doesn't block the shutdown forever.
+    fn finish(mut self: Box<Self>) -> Result<(), WriteError> {
+        self.is_finished = true;
+        if self.is_transactional {
+            self.commit_transaction(Timeout::After(Self::FINISH_TIMEOUT))
+        } else {
+            self.wait_for_delivery(Timeout::After(Self::FINISH_TIMEOUT))
+        }
+    }
+
+    // Retrying a single write doesn't help once the transaction is aborted, since
+    // the earlier messages of the transaction are lost with it
+    fn retriable(&self) -> bool {
+        !self.is_transactional
+    }
+
+    fn single_threaded(&self) -> bool {
+        false
+    }
+}
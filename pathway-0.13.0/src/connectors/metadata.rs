@@ -1,15 +1,24 @@
 // Copyright © 2024 Pathway
 
+use std::collections::HashMap;
 use std::path::Path;
 use std::time::{SystemTime, UNIX_EPOCH};
 
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
+use serde_json::Value as JsonValue;
 
-use crate::timestamp::current_unix_timestamp_secs;
+use crate::connectors::data_storage::ValuesMap;
+use crate::engine::Value;
+
+/// Reserved fields, which can be requested in the schema to get the corresponding
+/// parts of the metadata as separate columns
+pub const PATH_FIELD_NAME: &str = "_pw_path";
+pub const MODIFIED_AT_FIELD_NAME: &str = "_pw_modified_at";
+pub const SIZE_FIELD_NAME: &str = "_pw_size";
 
 /// Basic metadata for a file-like object
 #[allow(clippy::module_name_repetitions)]
-#[derive(Clone, Debug, Eq, PartialEq, Serialize)]
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
 pub struct SourceMetadata {
     // Creation and modification time may not be available at some platforms
     // Stored in u64 for easy serialization
@@ -19,6 +28,10 @@ pub struct SourceMetadata {
     // Owner may be unavailable at some platforms and on S3
     owner: Option<String>,
 
+    // Size of the file in bytes. It's kept in the serialized form for the cached
+    // metadata, but it isn't a part of the `_metadata` column, see `column_value`
+    size: Option<u64>,
+
     // Path should always be available. We make it String for two reasons:
     // * S3 path is denoted as a String
     // * This object is directly serialized and passed into a connector row
@@ -30,7 +43,9 @@ pub struct SourceMetadata {
 }
 
 impl SourceMetadata {
-    pub fn from_fs_meta(path: &Path, meta: &std::fs::Metadata) -> Self {
+    /// Creates the metadata of a file, which was seen by the connector at the
+    /// moment `seen_at`, given in seconds since the Unix epoch.
+    pub fn from_fs_meta(path: &Path, meta: &std::fs::Metadata, seen_at: u64) -> Self {
         let created_at = metadata_time_to_unix_timestamp(meta.created().ok());
         let modified_at = metadata_time_to_unix_timestamp(meta.modified().ok());
         let owner = file_owner::get_owner(meta);
@@ -39,9 +54,67 @@ impl SourceMetadata {
             created_at,
             modified_at,
             owner,
+            size: Some(meta.len()),
             path: path.to_string_lossy().to_string(),
-            seen_at: current_unix_timestamp_secs(),
+            seen_at,
+        }
+    }
+
+    pub fn is_reserved_field(field_name: &str) -> bool {
+        [PATH_FIELD_NAME, MODIFIED_AT_FIELD_NAME, SIZE_FIELD_NAME].contains(&field_name)
+    }
+
+    /// Returns the value of the `_metadata` column. The size of the file isn't
+    /// included there, it is only available as the reserved field `_pw_size`.
+    pub fn column_value(&self) -> JsonValue {
+        let mut value = serde_json::to_value(self).expect("internal serialization error");
+        if let JsonValue::Object(fields) = &mut value {
+            fields.remove("size");
         }
+        value
+    }
+
+    /// Returns the fields of the `_metadata` column, named in the same way as there.
+    /// The unavailable ones are `None`.
+    pub fn fields(&self) -> ValuesMap {
+        HashMap::from([
+            (
+                "created_at".to_string(),
+                optional_int_value(self.created_at),
+            ),
+            (
+                "modified_at".to_string(),
+                optional_int_value(self.modified_at),
+            ),
+            (
+                "owner".to_string(),
+                self.owner
+                    .as_deref()
+                    .map_or(Value::None, |owner| Value::String(owner.into())),
+            ),
+            ("path".to_string(), Value::String(self.path.as_str().into())),
+            (
+                "seen_at".to_string(),
+                optional_int_value(Some(self.seen_at)),
+            ),
+        ])
+        .into()
+    }
+
+    /// Returns the values of the reserved fields. The unavailable ones are `None`.
+    pub fn reserved_fields(&self) -> ValuesMap {
+        HashMap::from([
+            (
+                PATH_FIELD_NAME.to_string(),
+                Value::String(self.path.as_str().into()),
+            ),
+            (
+                MODIFIED_AT_FIELD_NAME.to_string(),
+                optional_int_value(self.modified_at),
+            ),
+            (SIZE_FIELD_NAME.to_string(), optional_int_value(self.size)),
+        ])
+        .into()
     }
 }
 
@@ -64,6 +137,12 @@ mod file_owner {
     }
 }
 
+fn optional_int_value(number: Option<u64>) -> Value {
+    number
+        .and_then(|number| i64::try_from(number).ok())
+        .map_or(Value::None, Value::Int)
+}
+
 fn metadata_time_to_unix_timestamp(timestamp: Option<SystemTime>) -> Option<u64> {
     timestamp
         .and_then(|timestamp| timestamp.duration_since(UNIX_EPOCH).ok())
@@ -4,10 +4,11 @@ use differential_dataflow::input::InputSession;
 use itertools::Itertools;
 use log::{error, info, warn};
 use std::cell::RefCell;
+use std::collections::VecDeque;
 use std::env;
 use std::ops::ControlFlow;
 use std::rc::Rc;
-use std::sync::mpsc::{self, Sender, TryRecvError};
+use std::sync::mpsc::{self, Receiver, Sender, TryRecvError};
 use std::sync::{Arc, Mutex};
 use std::thread;
 use std::thread::Thread;
@@ -17,12 +18,44 @@ use scopeguard::guard;
 use timely::dataflow::operators::probe::Handle;
 
 pub mod adaptors;
+#[cfg(feature = "amqp")]
+pub mod amqp;
+pub mod avro;
+pub mod azure;
+pub mod bigquery;
+#[cfg(feature = "clickhouse")]
+pub mod clickhouse;
+pub mod clock;
 pub mod data_format;
 pub mod data_storage;
+pub mod dead_letter;
+pub mod delta_table;
+pub mod dynamodb;
+pub mod elasticsearch;
+pub mod gcs;
+pub mod http;
+#[cfg(feature = "iceberg")]
+pub mod iceberg;
+pub mod json_array;
+pub mod kafka;
 pub mod metadata;
 pub mod monitoring;
+pub mod mqtt;
+pub mod multi_writer;
+pub mod nats;
 pub mod offset;
+pub mod orc;
+pub mod parquet;
+pub mod psql;
+#[cfg(feature = "pulsar")]
+pub mod pulsar;
+pub mod redis_stream;
+pub mod retry;
+pub mod s3;
 pub mod snapshot;
+pub mod sqlite;
+pub mod throttle;
+pub mod websocket;
 
 use crate::connectors::monitoring::ConnectorMonitor;
 use crate::engine::error::{DynError, Trace};
@@ -34,7 +67,7 @@ use crate::engine::{DataError, Key, Value};
 use crate::connectors::adaptors::InputAdaptor;
 use crate::connectors::snapshot::Event as SnapshotEvent;
 use crate::engine::Error as EngineError;
-use crate::engine::Timestamp;
+use crate::engine::{Timestamp, TotalFrontier};
 use crate::persistence::config::ReadersQueryPurpose;
 use crate::persistence::frontier::OffsetAntichain;
 use crate::persistence::tracker::WorkerPersistentStorage;
@@ -42,7 +75,9 @@ use crate::persistence::{ExternalPersistentId, PersistentId, SharedSnapshotWrite
 use crate::timestamp::current_unix_timestamp_ms;
 
 use data_format::{ParseError, ParseResult, ParsedEvent, ParsedEventWithErrors, Parser};
-use data_storage::{DataEventType, ReadResult, Reader, ReaderBuilder, ReaderContext, WriteError};
+use data_storage::{
+    DataEventType, ReadResult, Reader, ReaderBuilder, ReaderContext, ShutdownSignal, WriteError,
+};
 
 pub use adaptors::SessionType;
 pub use data_storage::StorageType;
@@ -94,6 +129,9 @@ pub struct Connector {
     current_frontier: OffsetAntichain,
     skip_all_errors: bool,
     error_logger: Rc<dyn LogError>,
+    // Notifies the reader thread about the frontiers of the committed data, along
+    // with the times they were committed at
+    commit_sender: Option<Sender<(Timestamp, OffsetAntichain)>>,
 }
 
 #[derive(Debug)]
@@ -190,6 +228,7 @@ impl Connector {
             current_frontier: OffsetAntichain::new(),
             skip_all_errors,
             error_logger,
+            commit_sender: None,
         }
     }
 
@@ -286,16 +325,51 @@ impl Connector {
         frontier
     }
 
+    /// Passes the committed frontiers to the reader. With the persistence, a frontier
+    /// is only passed once the time it was committed at is finalized, so that a
+    /// restarted program doesn't read the data before this frontier again.
+    fn deliver_commits(
+        reader: &mut dyn Reader,
+        pending_commits: &mut VecDeque<(Timestamp, OffsetAntichain)>,
+        persistent_storage: Option<&Arc<Mutex<WorkerPersistentStorage>>>,
+    ) {
+        let finalized_time = persistent_storage.map(|persistent_storage| {
+            persistent_storage
+                .lock()
+                .unwrap()
+                .last_finalized_timestamp()
+        });
+        while let Some((time, frontier)) = pending_commits.front() {
+            if finalized_time
+                .is_some_and(|finalized_time| finalized_time < TotalFrontier::At(*time))
+            {
+                break;
+            }
+            if let Err(e) = reader.on_commit(frontier) {
+                error!("Failed to handle the commit in the reader: {e}");
+            }
+            pending_commits.pop_front();
+        }
+    }
+
     pub fn read_realtime_updates(
         reader: &mut dyn Reader,
+        persistent_storage: Option<&Arc<Mutex<WorkerPersistentStorage>>>,
         sender: &Sender<Entry>,
+        commit_receiver: &Receiver<(Timestamp, OffsetAntichain)>,
         main_thread: &Thread,
         error_reporter: &(impl ReportError + 'static),
     ) {
         let use_rare_wakeup = env::var("PATHWAY_YOLO_RARE_WAKEUPS") == Ok("1".to_string());
         let mut amt_send = 0;
         let mut consecutive_errors = 0;
+        let mut pending_commits = VecDeque::new();
         loop {
+            // The readers waiting for new data return periodically, so that the
+            // commits aren't held up until the next entry arrives
+            pending_commits.extend(commit_receiver.try_iter());
+            Self::deliver_commits(reader, &mut pending_commits, persistent_storage);
+
             let row_read_result = reader.read();
             let finished = matches!(row_read_result, Ok(ReadResult::Finished));
 
@@ -331,6 +405,26 @@ impl Connector {
         }
     }
 
+    /// Gives the reader the storage for the state it persists besides the offsets.
+    pub fn install_state_storage(
+        reader: &mut dyn Reader,
+        persistent_storage: Option<&Arc<Mutex<WorkerPersistentStorage>>>,
+    ) {
+        let (Some(persistent_storage), Some(persistent_id)) =
+            (persistent_storage, reader.persistent_id())
+        else {
+            return;
+        };
+        match persistent_storage
+            .lock()
+            .unwrap()
+            .create_reader_state_storage(persistent_id)
+        {
+            Ok(state_storage) => reader.set_state_storage(state_storage),
+            Err(e) => error!("Failed to create the reader state storage: {e}"),
+        }
+    }
+
     pub fn read_snapshot(
         reader: &mut dyn Reader,
         persistent_storage: Option<&Arc<Mutex<WorkerPersistentStorage>>>,
@@ -411,11 +505,14 @@ impl Connector {
         persistence_mode: PersistenceMode,
         snapshot_access: SnapshotAccess,
         error_reporter: impl ReportError + 'static,
+        shutdown_signal: ShutdownSignal,
     ) -> Result<StartedConnectorState, EngineError> {
         assert_eq!(self.num_columns, parser.column_count());
 
         let main_thread = thread::current();
         let (sender, receiver) = mpsc::channel();
+        let (commit_sender, commit_receiver) = mpsc::channel();
+        self.commit_sender = Some(commit_sender);
 
         let thread_name = format!(
             "pathway:connector-{}-{}",
@@ -442,6 +539,8 @@ impl Connector {
                 });
 
                 let mut reader = reader.build()?;
+                reader.set_shutdown_signal(shutdown_signal);
+                Self::install_state_storage(&mut *reader, persistent_storage.as_ref());
                 Self::read_snapshot(
                     &mut *reader,
                     persistent_storage.as_ref(),
@@ -450,7 +549,14 @@ impl Connector {
                     snapshot_access,
                 );
                 if realtime_reader_needed {
-                    Self::read_realtime_updates(&mut *reader, &sender, &main_thread, reporter);
+                    Self::read_realtime_updates(
+                        &mut *reader,
+                        persistent_storage.as_ref(),
+                        &sender,
+                        &commit_receiver,
+                        &main_thread,
+                        reporter,
+                    );
                 }
 
                 Ok(())
@@ -619,12 +725,14 @@ impl Connector {
                         connector_monitor,
                     );
 
+                    // The frontier is also tracked without the persistence, since
+                    // it's passed to the reader on commits
                     let (offset_key, offset_value) = offset;
                     if has_persistent_storage {
                         assert!(*backfilling_finished);
-                        self.current_frontier
-                            .advance_offset(offset_key, offset_value);
                     }
+                    self.current_frontier
+                        .advance_offset(offset_key, offset_value);
                 }
             },
             Entry::RewindFinishSentinel(restored_frontier) => {
@@ -826,6 +934,10 @@ impl Connector {
                             error!("Failed to save time advancement ({time_advanced}) in persistent buffer. Error: {e}");
                         }
                     }
+                    if let Some(commit_sender) = &self.commit_sender {
+                        // The reader thread may have finished, then there is no one to notify
+                        let _ = commit_sender.send((time_advanced, self.current_frontier.clone()));
+                    }
                 }
             };
         }
@@ -154,6 +154,8 @@ pub struct OutputConnectorStats {
     current_writes_duration: Duration,
     messages_written_in_batch: usize,
     messages_written_in_total: usize,
+    rows_flushed_since_report: usize,
+    rows_flushed_in_total: usize,
 }
 
 impl OutputConnectorStats {
@@ -168,6 +170,9 @@ impl OutputConnectorStats {
 
             messages_written_in_batch: 0,
             messages_written_in_total: 0,
+
+            rows_flushed_since_report: 0,
+            rows_flushed_in_total: 0,
         }
     }
 
@@ -211,6 +216,12 @@ impl OutputConnectorStats {
         self.current_writes_duration += elapsed;
     }
 
+    /// Accounts the rows the data sink reported as durable after a flush.
+    pub fn on_rows_flushed(&mut self, rows: usize) {
+        self.rows_flushed_since_report += rows;
+        self.rows_flushed_in_total += rows;
+    }
+
     fn report_stats(&mut self, current_timestamp: Instant, t: Option<u64>) {
         if let Some(t) = t {
             info!(
@@ -230,7 +241,16 @@ impl OutputConnectorStats {
             );
         }
 
+        // The sinks that don't track the delivered rows always report zero
+        if self.rows_flushed_in_total > 0 {
+            info!(
+                "{}: {} rows made durable since the last report, {} in total.",
+                self.name, self.rows_flushed_since_report, self.rows_flushed_in_total,
+            );
+        }
+
         self.messages_written_in_batch = 0;
+        self.rows_flushed_since_report = 0;
         self.last_reported_timestamp = Some(current_timestamp);
         self.current_writes_duration = Duration::ZERO;
     }
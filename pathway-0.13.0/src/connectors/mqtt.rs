@@ -0,0 +1,415 @@
+// Copyright © 2024 Pathway
+
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicBool, Ordering as AtomicOrdering};
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use log::{info, warn};
+use rumqttc::{
+    Client as MqttClient, ClientError as MqttClientError, Connection as MqttConnection,
+    Event as MqttEvent, MqttOptions, Outgoing as MqttOutgoing, Packet as MqttPacket,
+    Publish as MqttPublish, QoS as MqttQoS, RecvTimeoutError as MqttRecvTimeoutError,
+};
+
+use crate::connectors::data_format::FormatterContext;
+use crate::connectors::data_storage::{
+    ReadError, ReadResult, Reader, ReaderContext, ShutdownSignal, StorageType, WriteError, Writer,
+};
+use crate::connectors::{Offset, OffsetKey, OffsetValue};
+use crate::engine::{Timestamp, Value};
+use crate::persistence::frontier::OffsetAntichain;
+use crate::persistence::PersistentId;
+
+/// The number of requests the MQTT client can queue before the event loop takes them.
+const MQTT_REQUESTS_CAPACITY: usize = 1000;
+
+/// Reads the messages published to the topics matching an MQTT topic filter, emitting
+/// the concrete topic of each message as its key.
+///
+/// The broker keeps no offsets, so the reading can't be resumed from a frontier.
+/// Instead, the messages of QoS 1 and 2 are acknowledged only after the engine
+/// commits the data they belong to, and the session is persistent, so the broker
+/// redelivers the messages that weren't committed before a restart. The
+/// acknowledgements are only valid within the connection the messages were received
+/// in, so the offsets also carry a random id of the connection.
+///
+/// The acknowledgements are sent between the reads, so the last messages may stay
+/// unacknowledged until something new arrives.
+pub struct MqttReader {
+    client: MqttClient,
+    connection: MqttConnection,
+    topic_filter: String,
+    qos: MqttQoS,
+    session: u64,
+    last_sequence: u64,
+    committed_sequence: u64,
+    unacknowledged_messages: VecDeque<(u64, MqttPublish)>,
+    persistent_id: Option<PersistentId>,
+    last_activity: Option<Instant>,
+    shutdown_signal: ShutdownSignal,
+}
+
+impl MqttReader {
+    const POLL_TIMEOUT: Duration = Duration::from_secs(1);
+
+    /// Creates a reader connected to the broker at `uri`, which has the form
+    /// `mqtt://host:port?client_id=id`. The subscription is made once the
+    /// connection is established.
+    pub fn new(
+        uri: &str,
+        topic_filter: String,
+        qos: MqttQoS,
+        persistent_id: Option<PersistentId>,
+    ) -> Result<MqttReader, ReadError> {
+        let mut options = MqttOptions::parse_url(uri)?;
+        options.set_manual_acks(true);
+        options.set_clean_session(false);
+        let (client, connection) = MqttClient::new(options, MQTT_REQUESTS_CAPACITY);
+
+        Ok(MqttReader {
+            client,
+            connection,
+            topic_filter,
+            qos,
+            session: rand::random(),
+            last_sequence: 0,
+            committed_sequence: 0,
+            unacknowledged_messages: VecDeque::new(),
+            persistent_id,
+            last_activity: None,
+            shutdown_signal: ShutdownSignal::default(),
+        })
+    }
+
+    /// Acknowledges the committed messages. If the requests queue is full, the rest
+    /// is acknowledged after the event loop takes the queued requests.
+    fn send_acknowledgements(&mut self) -> Result<(), ReadError> {
+        while let Some((sequence, message)) = self.unacknowledged_messages.front() {
+            if *sequence > self.committed_sequence {
+                break;
+            }
+            match self.client.try_ack(message) {
+                Ok(()) => {
+                    self.unacknowledged_messages.pop_front();
+                }
+                Err(MqttClientError::TryRequest(_)) => break,
+                Err(error) => return Err(error.into()),
+            }
+        }
+        Ok(())
+    }
+
+    /// Converts the message number `sequence` received over the connection `session`
+    /// into the entry and the offset pointing at this message. The topic of the
+    /// message becomes the key of the entry.
+    fn message_entry(
+        session: u64,
+        sequence: u64,
+        topic: &str,
+        payload: Vec<u8>,
+    ) -> (ReaderContext, Offset) {
+        let offset = (
+            OffsetKey::Empty,
+            OffsetValue::MqttMessageSequence { session, sequence },
+        );
+        let key = topic.as_bytes().to_vec();
+        (
+            ReaderContext::from_key_value(Some(key), Some(payload)),
+            offset,
+        )
+    }
+
+    /// Returns the number of the last message in `frontier` if it was received over
+    /// the connection `session`, since the messages of the other connections can't
+    /// be acknowledged.
+    fn committed_sequence(session: u64, frontier: &OffsetAntichain) -> Option<u64> {
+        match frontier.get_offset(&OffsetKey::Empty) {
+            Some(OffsetValue::MqttMessageSequence {
+                session: offset_session,
+                sequence,
+            }) if *offset_session == session => Some(*sequence),
+            _ => None,
+        }
+    }
+}
+
+impl Reader for MqttReader {
+    fn seek(&mut self, frontier: &OffsetAntichain) -> Result<(), ReadError> {
+        // There is no position to seek to: the messages that weren't acknowledged
+        // before the restart are delivered again instead
+        if let Some(offset_value) = frontier.get_offset(&OffsetKey::Empty) {
+            info!("MQTT reader doesn't seek, the unacknowledged messages will be redelivered. Last offset: {offset_value:?}");
+        }
+        Ok(())
+    }
+
+    fn read(&mut self) -> Result<ReadResult, ReadError> {
+        loop {
+            self.send_acknowledgements()?;
+            match self.connection.recv_timeout(Self::POLL_TIMEOUT) {
+                Ok(Ok(MqttEvent::Incoming(MqttPacket::Publish(message)))) => {
+                    self.last_activity = Some(Instant::now());
+                    self.last_sequence += 1;
+                    let (context, offset) = Self::message_entry(
+                        self.session,
+                        self.last_sequence,
+                        &message.topic,
+                        message.payload.to_vec(),
+                    );
+                    if message.qos != MqttQoS::AtMostOnce {
+                        self.unacknowledged_messages
+                            .push_back((self.last_sequence, message));
+                    }
+                    return Ok(ReadResult::Data(context, offset));
+                }
+                Ok(Ok(MqttEvent::Incoming(MqttPacket::ConnAck(_)))) => {
+                    // The messages received over the previous connection can't be
+                    // acknowledged anymore, the broker redelivers them instead
+                    self.session = rand::random();
+                    self.committed_sequence = self.last_sequence;
+                    self.unacknowledged_messages.clear();
+                    self.client.try_subscribe(&self.topic_filter, self.qos)?;
+                }
+                Ok(Ok(_)) => {}
+                Ok(Err(error)) => return Err(error.into()),
+                Err(MqttRecvTimeoutError::Timeout) => {
+                    if self.shutdown_signal.is_requested() {
+                        return Ok(ReadResult::Finished);
+                    }
+                }
+                Err(MqttRecvTimeoutError::Disconnected) => return Ok(ReadResult::Finished),
+            }
+        }
+    }
+
+    fn on_commit(&mut self, frontier: &OffsetAntichain) -> Result<(), ReadError> {
+        let Some(sequence) = Self::committed_sequence(self.session, frontier) else {
+            return Ok(());
+        };
+        if sequence <= self.committed_sequence {
+            return Ok(());
+        }
+        self.committed_sequence = sequence;
+        self.send_acknowledgements()
+    }
+
+    fn persistent_id(&self) -> Option<PersistentId> {
+        self.persistent_id
+    }
+
+    fn update_persistent_id(&mut self, persistent_id: Option<PersistentId>) {
+        self.persistent_id = persistent_id;
+    }
+
+    fn set_shutdown_signal(&mut self, signal: ShutdownSignal) {
+        self.shutdown_signal = signal;
+    }
+
+    fn storage_type(&self) -> StorageType {
+        StorageType::Mqtt
+    }
+
+    fn last_activity(&self) -> Option<Instant> {
+        self.last_activity
+    }
+}
+
+/// The numbers of the messages published with QoS 1 or 2 and of the ones the broker
+/// has confirmed, shared with the thread running the event loop of the connection.
+#[derive(Default)]
+struct MqttDeliveries {
+    // The numbers of the published and of the confirmed messages
+    counts: Mutex<(u64, u64)>,
+    confirmed: Condvar,
+    is_closed: AtomicBool,
+}
+
+impl MqttDeliveries {
+    fn on_published(&self) {
+        self.counts.lock().unwrap().0 += 1;
+    }
+
+    fn on_confirmed(&self) {
+        self.counts.lock().unwrap().1 += 1;
+        self.confirmed.notify_all();
+    }
+
+    fn wait_for_confirmations(&self) {
+        let mut counts = self.counts.lock().unwrap();
+        while counts.1 < counts.0 {
+            counts = self.confirmed.wait(counts).unwrap();
+        }
+    }
+}
+
+/// Publishes the payloads to an MQTT topic, which is either fixed or taken from a
+/// field of the entry.
+///
+/// The broker confirms the messages of QoS 1 with PUBACK and the ones of QoS 2 with
+/// PUBCOMP. The writer waits for the confirmations of all the published messages on
+/// the forced flushes and before the commits are recorded as persisted, so the output
+/// committed by the engine isn't lost on a restart.
+pub struct MqttWriter {
+    client: MqttClient,
+    topic: String,
+    topic_field_index: Option<usize>,
+    qos: MqttQoS,
+    deliveries: Arc<MqttDeliveries>,
+    event_loop: Option<thread::JoinHandle<()>>,
+}
+
+impl MqttWriter {
+    const RECONNECT_DELAY: Duration = Duration::from_secs(1);
+
+    /// Creates a writer connected to the broker at `uri`, which has the form
+    /// `mqtt://host:port?client_id=id`. The topic is taken from the field with
+    /// `topic_field_index` if it's set, otherwise `topic` is used for all messages.
+    pub fn new(
+        uri: &str,
+        topic: String,
+        topic_field_index: Option<usize>,
+        qos: MqttQoS,
+    ) -> Result<MqttWriter, WriteError> {
+        let mut options = MqttOptions::parse_url(uri)?;
+        options.set_clean_session(false);
+        let (client, mut connection) = MqttClient::new(options, MQTT_REQUESTS_CAPACITY);
+
+        let deliveries = Arc::new(MqttDeliveries::default());
+        let event_loop = thread::Builder::new()
+            .name("pathway:mqtt_writer".to_string())
+            .spawn({
+                let deliveries = deliveries.clone();
+                move || {
+                    for event in connection.iter() {
+                        match event {
+                            Ok(MqttEvent::Incoming(
+                                MqttPacket::PubAck(_) | MqttPacket::PubComp(_),
+                            )) => deliveries.on_confirmed(),
+                            Ok(MqttEvent::Outgoing(MqttOutgoing::Disconnect)) => break,
+                            Ok(_) => {}
+                            Err(error) => {
+                                if deliveries.is_closed.load(AtomicOrdering::Relaxed) {
+                                    break;
+                                }
+                                // The unconfirmed messages are sent again once the
+                                // connection is reestablished on the next poll
+                                warn!("MQTT connection error, reconnecting: {error}");
+                                thread::sleep(Self::RECONNECT_DELAY);
+                            }
+                        }
+                    }
+                }
+            })?;
+
+        Ok(MqttWriter {
+            client,
+            topic,
+            topic_field_index,
+            qos,
+            deliveries,
+            event_loop: Some(event_loop),
+        })
+    }
+
+    /// Returns the topic to publish the messages produced for `data` to: the value
+    /// of the field with `topic_field_index` if it's set, otherwise `topic`.
+    fn message_topic(
+        topic: &str,
+        topic_field_index: Option<usize>,
+        data: &FormatterContext,
+    ) -> String {
+        match topic_field_index {
+            Some(index) => match &data.values[index] {
+                Value::String(topic) => topic.to_string(),
+                other => other.to_string(),
+            },
+            None => topic.to_string(),
+        }
+    }
+}
+
+impl Drop for MqttWriter {
+    fn drop(&mut self) {
+        self.deliveries
+            .is_closed
+            .store(true, AtomicOrdering::Relaxed);
+        if let Err(error) = self.client.disconnect() {
+            warn!("Failed to disconnect from MQTT broker: {error}");
+        }
+        if let Some(event_loop) = self.event_loop.take() {
+            event_loop.join().expect("MQTT event loop thread panicked");
+        }
+    }
+}
+
+impl Writer for MqttWriter {
+    fn write(&mut self, data: FormatterContext) -> Result<(), WriteError> {
+        let topic = Self::message_topic(&self.topic, self.topic_field_index, &data);
+        for payload in data.payloads {
+            if self.qos != MqttQoS::AtMostOnce {
+                self.deliveries.on_published();
+            }
+            self.client
+                .publish(topic.clone(), self.qos, false, payload)?;
+        }
+        Ok(())
+    }
+
+    fn flush(&mut self, forced: bool) -> Result<(), WriteError> {
+        if forced {
+            self.deliveries.wait_for_confirmations();
+        }
+        Ok(())
+    }
+
+    fn on_commit(&mut self, _time: Timestamp) -> Result<(), WriteError> {
+        self.deliveries.wait_for_confirmations();
+        Ok(())
+    }
+
+    fn single_threaded(&self) -> bool {
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::connectors::data_format::FormatterContext;
+    use crate::engine::{Key, Timestamp, Value};
+    use crate::persistence::frontier::OffsetAntichain;
+
+    use super::{MqttReader, MqttWriter};
+
+    #[test]
+    fn test_committed_sequence() {
+        let mut frontier = OffsetAntichain::new();
+        assert_eq!(MqttReader::committed_sequence(1, &frontier), None);
+
+        let (_, (offset_key, offset_value)) =
+            MqttReader::message_entry(1, 7, "sensors/kitchen", Vec::new());
+        frontier.advance_offset(offset_key, offset_value);
+        assert_eq!(MqttReader::committed_sequence(1, &frontier), Some(7));
+
+        // The messages received over another connection can't be acknowledged
+        assert_eq!(MqttReader::committed_sequence(2, &frontier), None);
+    }
+
+    #[test]
+    fn test_message_topic() {
+        let data = FormatterContext::new_single_payload(
+            b"payload".to_vec(),
+            Key::random(),
+            vec![Value::String("sensors/kitchen".into()), Value::Int(3)],
+            Timestamp(4),
+            1,
+        );
+        assert_eq!(MqttWriter::message_topic("sensors", None, &data), "sensors");
+        assert_eq!(
+            MqttWriter::message_topic("sensors", Some(0), &data),
+            "sensors/kitchen"
+        );
+        assert_eq!(MqttWriter::message_topic("sensors", Some(1), &data), "3");
+    }
+}
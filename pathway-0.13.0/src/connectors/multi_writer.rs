@@ -0,0 +1,113 @@
+// Copyright © 2024 Pathway
+
+use crate::connectors::data_format::FormatterContext;
+use crate::connectors::data_storage::{WriteError, Writer};
+use crate::engine::Timestamp;
+
+/// Defines how `MultiWriter` handles the errors of the writers it forwards to.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum MultiWriterErrorPolicy {
+    /// The first error is returned right away, so the writers following the failed
+    /// one don't get the operation.
+    #[default]
+    FailFast,
+
+    /// Every writer gets the operation, and if several of them fail, their errors
+    /// are returned together as `WriteError::MultipleOutputs`.
+    CollectAll,
+}
+
+/// Forwards the output to several writers, so that the same data goes to all of
+/// them without computing it more than once. The operations are forwarded to the
+/// writers in order, and the errors are handled according to `error_policy`.
+///
+/// A retried write is repeated for all the writers, the ones where it succeeded
+/// included, so these may get the same data more than once. To retry only the
+/// writers that failed, wrap them into a [`RetryingWriter`] each.
+pub struct MultiWriter {
+    inner: Vec<Box<dyn Writer>>,
+    error_policy: MultiWriterErrorPolicy,
+}
+
+impl MultiWriter {
+    pub fn new(inner: Vec<Box<dyn Writer>>, error_policy: MultiWriterErrorPolicy) -> Self {
+        Self {
+            inner,
+            error_policy,
+        }
+    }
+
+    fn for_each_writer(
+        &mut self,
+        mut operation: impl FnMut(&mut dyn Writer) -> Result<(), WriteError>,
+    ) -> Result<(), WriteError> {
+        let mut errors = Vec::new();
+        for writer in &mut self.inner {
+            if let Err(error) = operation(writer.as_mut()) {
+                if self.error_policy == MultiWriterErrorPolicy::FailFast {
+                    return Err(error);
+                }
+                errors.push(error);
+            }
+        }
+        Self::combine_errors(errors)
+    }
+
+    fn combine_errors(mut errors: Vec<WriteError>) -> Result<(), WriteError> {
+        match errors.len() {
+            0 => Ok(()),
+            1 => Err(errors.pop().unwrap()),
+            _ => Err(WriteError::MultipleOutputs(errors)),
+        }
+    }
+}
+
+impl Writer for MultiWriter {
+    fn write(&mut self, data: FormatterContext) -> Result<(), WriteError> {
+        self.for_each_writer(|writer| writer.write(data.clone()))
+    }
+
+    fn flush(&mut self, forced: bool) -> Result<(), WriteError> {
+        self.for_each_writer(|writer| writer.flush(forced))
+    }
+
+    fn on_commit(&mut self, time: Timestamp) -> Result<(), WriteError> {
+        self.for_each_writer(|writer| writer.on_commit(time))
+    }
+
+    /// With `MultiWriterErrorPolicy::FailFast`, the writers following the failed one
+    /// aren't finished explicitly, but they are still dropped.
+    fn finish(self: Box<Self>) -> Result<(), WriteError> {
+        let Self {
+            inner,
+            error_policy,
+        } = *self;
+        let mut errors = Vec::new();
+        for writer in inner {
+            if let Err(error) = writer.finish() {
+                if error_policy == MultiWriterErrorPolicy::FailFast {
+                    return Err(error);
+                }
+                errors.push(error);
+            }
+        }
+        Self::combine_errors(errors)
+    }
+
+    // The rows are durable only once all the writers have made them durable
+    fn rows_written_since_last_flush(&self) -> usize {
+        self.inner
+            .iter()
+            .map(|writer| writer.rows_written_since_last_flush())
+            .min()
+            .unwrap_or(0)
+    }
+
+    fn retriable(&self) -> bool {
+        self.inner.iter().all(|writer| writer.retriable())
+    }
+
+    fn single_threaded(&self) -> bool {
+        self.inner.iter().any(|writer| writer.single_threaded())
+    }
+}
@@ -0,0 +1,292 @@
+// Copyright © 2024 Pathway
+
+use std::io;
+use std::time::Instant;
+
+use async_nats::jetstream::consumer::pull::{
+    Config as NatsPullConsumerConfig, Stream as NatsMessageStream,
+};
+use async_nats::jetstream::consumer::{
+    AckPolicy as NatsAckPolicy, DeliverPolicy as NatsDeliverPolicy,
+};
+use async_nats::jetstream::context::{Context as NatsContext, PublishAckFuture as NatsPublishAck};
+use async_nats::jetstream::stream::Stream as NatsStream;
+use async_nats::{Error as NatsError, HeaderMap as NatsHeaders};
+use futures::StreamExt;
+use log::warn;
+use tokio::runtime::Runtime as TokioRuntime;
+
+use crate::connectors::data_format::FormatterContext;
+use crate::connectors::data_storage::{
+    message_headers, ReadError, ReadResult, Reader, ReaderContext, ShutdownSignal, StorageType,
+    WriteError, Writer,
+};
+use crate::connectors::{Offset, OffsetKey, OffsetValue};
+use crate::persistence::frontier::OffsetAntichain;
+use crate::persistence::PersistentId;
+
+fn create_nats_runtime() -> io::Result<TokioRuntime> {
+    tokio::runtime::Builder::new_multi_thread()
+        .enable_all()
+        .build()
+}
+
+async fn connect_to_jetstream(uri: &str) -> Result<NatsContext, NatsError> {
+    let client = async_nats::connect(uri).await?;
+    Ok(async_nats::jetstream::new(client))
+}
+
+pub struct NatsReader {
+    stream: NatsStream,
+    subject: String,
+    durable_name: String,
+    persistent_id: Option<PersistentId>,
+    start_sequence: Option<u64>,
+    messages: Option<NatsMessageStream>,
+    last_activity: Option<Instant>,
+    shutdown_signal: ShutdownSignal,
+
+    // Declared last so that it's dropped after everything that may use it
+    runtime: TokioRuntime,
+}
+
+impl NatsReader {
+    pub fn new(
+        uri: &str,
+        subject: String,
+        durable_name: String,
+        persistent_id: Option<PersistentId>,
+    ) -> Result<NatsReader, ReadError> {
+        let runtime = create_nats_runtime()?;
+        let stream = runtime
+            .block_on(async {
+                let context = connect_to_jetstream(uri).await?;
+                let stream_name = context.stream_by_subject(subject.as_str()).await?;
+                Ok::<_, NatsError>(context.get_stream(stream_name).await?)
+            })
+            .map_err(ReadError::Nats)?;
+
+        Ok(NatsReader {
+            stream,
+            subject,
+            durable_name,
+            persistent_id,
+            start_sequence: None,
+            messages: None,
+            last_activity: None,
+            shutdown_signal: ShutdownSignal::default(),
+            runtime,
+        })
+    }
+
+    fn subscribe(&self) -> Result<NatsMessageStream, ReadError> {
+        let deliver_policy = match self.start_sequence {
+            Some(start_sequence) => NatsDeliverPolicy::ByStartSequence { start_sequence },
+            None => NatsDeliverPolicy::All,
+        };
+        let config = NatsPullConsumerConfig {
+            durable_name: Some(self.durable_name.clone()),
+            filter_subject: self.subject.clone(),
+            deliver_policy,
+            // The position is tracked by the durable consumer and by the frontier
+            ack_policy: NatsAckPolicy::None,
+            ..Default::default()
+        };
+        self.runtime
+            .block_on(async {
+                if self.start_sequence.is_some() {
+                    // The position of a durable consumer can't be changed, so it's
+                    // recreated to start right after the position from the frontier
+                    let _ = self.stream.delete_consumer(&self.durable_name).await;
+                }
+                let consumer = self
+                    .stream
+                    .get_or_create_consumer(&self.durable_name, config)
+                    .await?;
+                Ok::<_, NatsError>(consumer.messages().await?)
+            })
+            .map_err(ReadError::Nats)
+    }
+
+    /// Converts the message with the stream sequence number `sequence` into the
+    /// entry and the offset pointing at this message.
+    fn message_entry(sequence: u64, payload: Vec<u8>) -> (ReaderContext, Offset) {
+        (
+            ReaderContext::from_key_value(None, Some(payload)),
+            (OffsetKey::Empty, OffsetValue::NatsSequence(sequence)),
+        )
+    }
+
+    /// Returns the sequence number of the message following the last one in
+    /// `frontier`, from which the reading is resumed.
+    fn start_sequence(frontier: &OffsetAntichain) -> Option<u64> {
+        let offset_value = frontier.get_offset(&OffsetKey::Empty);
+        let Some(OffsetValue::NatsSequence(sequence)) = offset_value else {
+            if offset_value.is_some() {
+                warn!("Incorrect type of offset value in Nats frontier: {offset_value:?}");
+            }
+            return None;
+        };
+        Some(sequence + 1)
+    }
+}
+
+impl Reader for NatsReader {
+    fn seek(&mut self, frontier: &OffsetAntichain) -> Result<(), ReadError> {
+        let Some(start_sequence) = Self::start_sequence(frontier) else {
+            return Ok(());
+        };
+
+        // "Lazy" seek: the consumer is created on the first read
+        self.start_sequence = Some(start_sequence);
+        self.messages = None;
+
+        Ok(())
+    }
+
+    fn read(&mut self) -> Result<ReadResult, ReadError> {
+        if self.messages.is_none() {
+            self.messages = Some(self.subscribe()?);
+        }
+        let messages = self.messages.as_mut().unwrap();
+
+        loop {
+            let Some(Some(message)) = self
+                .shutdown_signal
+                .block_on(&self.runtime, messages.next())
+            else {
+                return Ok(ReadResult::Finished);
+            };
+            let message = message.map_err(|e| ReadError::Nats(e.into()))?;
+            let sequence = message.info().map_err(ReadError::Nats)?.stream_sequence;
+            if self
+                .start_sequence
+                .is_some_and(|start_sequence| sequence < start_sequence)
+            {
+                continue;
+            }
+
+            self.last_activity = Some(Instant::now());
+            let (message, offset) = Self::message_entry(sequence, message.payload.to_vec());
+            return Ok(ReadResult::Data(message, offset));
+        }
+    }
+
+    fn persistent_id(&self) -> Option<PersistentId> {
+        self.persistent_id
+    }
+
+    fn update_persistent_id(&mut self, persistent_id: Option<PersistentId>) {
+        self.persistent_id = persistent_id;
+    }
+
+    fn set_shutdown_signal(&mut self, signal: ShutdownSignal) {
+        self.shutdown_signal = signal;
+    }
+
+    fn storage_type(&self) -> StorageType {
+        StorageType::Nats
+    }
+
+    fn last_activity(&self) -> Option<Instant> {
+        self.last_activity
+    }
+}
+
+pub struct NatsWriter {
+    context: NatsContext,
+    subject: String,
+    header_fields: Vec<(String, usize)>,
+    pending_acks: Vec<NatsPublishAck>,
+
+    // Declared last so that it's dropped after everything that may use it
+    runtime: TokioRuntime,
+}
+
+impl NatsWriter {
+    pub fn new(
+        uri: &str,
+        subject: String,
+        header_fields: Vec<(String, usize)>,
+    ) -> Result<NatsWriter, WriteError> {
+        let runtime = create_nats_runtime()?;
+        let context = runtime
+            .block_on(connect_to_jetstream(uri))
+            .map_err(WriteError::Nats)?;
+        Ok(NatsWriter {
+            context,
+            subject,
+            header_fields,
+            pending_acks: Vec::new(),
+            runtime,
+        })
+    }
+
+    /// Returns the headers of the messages produced for `data`, with the values that
+    /// aren't valid UTF-8 converted lossily, since NATS headers are strings.
+    fn message_headers(header_fields: &[(String, usize)], data: &FormatterContext) -> NatsHeaders {
+        let mut headers = NatsHeaders::new();
+        for (name, value) in message_headers(header_fields, data) {
+            headers.insert(name, String::from_utf8_lossy(&value).as_ref());
+        }
+        headers
+    }
+}
+
+impl Writer for NatsWriter {
+    fn write(&mut self, data: FormatterContext) -> Result<(), WriteError> {
+        let headers = Self::message_headers(&self.header_fields, &data);
+        for payload in data.payloads {
+            let ack = self
+                .runtime
+                .block_on(self.context.publish_with_headers(
+                    self.subject.clone(),
+                    headers.clone(),
+                    payload.into(),
+                ))
+                .map_err(|e| WriteError::Nats(e.into()))?;
+            self.pending_acks.push(ack);
+        }
+        Ok(())
+    }
+
+    fn flush(&mut self, _forced: bool) -> Result<(), WriteError> {
+        // The messages are published without waiting, and the acknowledgements
+        // from the server are collected once per batch
+        for ack in self.pending_acks.drain(..) {
+            self.runtime
+                .block_on(ack)
+                .map_err(|e| WriteError::Nats(e.into()))?;
+        }
+        Ok(())
+    }
+
+    fn retriable(&self) -> bool {
+        true
+    }
+
+    fn single_threaded(&self) -> bool {
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::connectors::OffsetValue;
+    use crate::persistence::frontier::OffsetAntichain;
+
+    use super::NatsReader;
+
+    #[test]
+    fn test_reading_resumed_after_last_message() {
+        let mut frontier = OffsetAntichain::new();
+        assert_eq!(NatsReader::start_sequence(&frontier), None);
+
+        let (_, (offset_key, offset_value)) = NatsReader::message_entry(7, Vec::new());
+        frontier.advance_offset(offset_key.clone(), offset_value);
+        assert_eq!(NatsReader::start_sequence(&frontier), Some(8));
+
+        frontier.advance_offset(offset_key, OffsetValue::KafkaOffset(7));
+        assert_eq!(NatsReader::start_sequence(&frontier), None);
+    }
+}
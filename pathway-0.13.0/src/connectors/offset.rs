@@ -28,6 +28,8 @@ impl HashInto for OffsetKey {
     }
 }
 
+/// The offsets are persisted with their variant index, so the new variants are
+/// only appended, and the existing ones keep their fields.
 #[allow(clippy::module_name_repetitions)]
 #[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize, Ord, PartialOrd)]
 pub enum OffsetValue {
@@ -37,6 +39,8 @@ pub enum OffsetValue {
         path: Arc<PathBuf>,
         bytes_offset: u64,
     },
+    // Written before the modification times of the objects were tracked, and
+    // resumed from as an `S3ObjectPositionV2` without one
     S3ObjectPosition {
         total_entries_read: u64,
         path: Arc<String>,
@@ -44,12 +48,104 @@ pub enum OffsetValue {
     },
     PythonEntrySequentialId(u64),
     Empty,
+    DeltaTablePosition {
+        version: i64,
+        // The version the changes of `version` are computed against, if any
+        base_version: Option<i64>,
+        rows_read_within_version: u64,
+    },
+    HttpCursor {
+        pages_read: u64,
+        // The cursor the current page was requested with
+        cursor: Option<Arc<String>>,
+        items_read_within_page: u64,
+    },
+    NatsSequence(u64),
+    AmqpDeliveryTag {
+        // Random id of the channel the delivery was received in
+        session: u64,
+        delivery_tag: u64,
+    },
+    // Number of the batch, the state of the table after which is kept in the
+    // reader state storage
+    SqliteSnapshot {
+        snapshots_taken: u64,
+    },
+    RedisStreamId {
+        milliseconds: u64,
+        sequence: u64,
+    },
+    MqttMessageSequence {
+        // Random id of the connection the message was received in
+        session: u64,
+        sequence: u64,
+    },
+    // Id of a message in a non-partitioned Pulsar topic, the batch index is -1 for
+    // the messages that weren't sent in a batch
+    PulsarMessageId {
+        ledger_id: u64,
+        entry_id: u64,
+        batch_index: i32,
+    },
+    // Position in a compressed file, where the byte offsets don't map onto the
+    // decompressed stream, so the reading is resumed by replaying the records
+    RecordPosition {
+        total_entries_read: u64,
+        path: Arc<PathBuf>,
+        entries_read_within_file: u64,
+    },
+    // Same as `S3ObjectPositionV2`, but for a blob in Azure Blob Storage
+    AzureBlobPosition {
+        total_entries_read: u64,
+        path: Arc<String>,
+        bytes_offset: u64,
+        last_modified: Option<i64>,
+    },
+    S3ObjectPositionV2 {
+        total_entries_read: u64,
+        path: Arc<String>,
+        // Number of the decompressed bytes consumed by the records read from the
+        // object so far, including the delimiters
+        bytes_offset: u64,
+        // Modification time of the object in nanoseconds since the epoch,
+        // used to order the objects when the reading is resumed
+        last_modified: Option<i64>,
+    },
 }
 
 impl HashInto for OffsetValue {
     fn hash_into(&self, hasher: &mut Hasher) {
         match self {
             OffsetValue::KafkaOffset(offset) => offset.hash_into(hasher),
+            OffsetValue::NatsSequence(sequence) => sequence.hash_into(hasher),
+            OffsetValue::AmqpDeliveryTag {
+                session,
+                delivery_tag,
+            } => {
+                session.hash_into(hasher);
+                delivery_tag.hash_into(hasher);
+            }
+            OffsetValue::SqliteSnapshot { snapshots_taken } => snapshots_taken.hash_into(hasher),
+            OffsetValue::RedisStreamId {
+                milliseconds,
+                sequence,
+            } => {
+                milliseconds.hash_into(hasher);
+                sequence.hash_into(hasher);
+            }
+            OffsetValue::MqttMessageSequence { session, sequence } => {
+                session.hash_into(hasher);
+                sequence.hash_into(hasher);
+            }
+            OffsetValue::PulsarMessageId {
+                ledger_id,
+                entry_id,
+                batch_index,
+            } => {
+                ledger_id.hash_into(hasher);
+                entry_id.hash_into(hasher);
+                batch_index.hash_into(hasher);
+            }
             OffsetValue::FilePosition {
                 path, bytes_offset, ..
             } => {
@@ -58,13 +154,43 @@ impl HashInto for OffsetValue {
             }
             OffsetValue::S3ObjectPosition {
                 path, bytes_offset, ..
+            }
+            | OffsetValue::S3ObjectPositionV2 {
+                path, bytes_offset, ..
+            }
+            | OffsetValue::AzureBlobPosition {
+                path, bytes_offset, ..
             } => {
                 hasher.update(path.as_bytes());
                 bytes_offset.hash_into(hasher);
             }
+            OffsetValue::RecordPosition {
+                path,
+                entries_read_within_file,
+                ..
+            } => {
+                hasher.update(path.as_os_str().as_encoded_bytes());
+                entries_read_within_file.hash_into(hasher);
+            }
             OffsetValue::PythonEntrySequentialId(sequential_id) => {
                 sequential_id.hash_into(hasher);
             }
+            OffsetValue::DeltaTablePosition {
+                version,
+                rows_read_within_version,
+                ..
+            } => {
+                version.hash_into(hasher);
+                rows_read_within_version.hash_into(hasher);
+            }
+            OffsetValue::HttpCursor {
+                pages_read,
+                items_read_within_page,
+                ..
+            } => {
+                pages_read.hash_into(hasher);
+                items_read_within_page.hash_into(hasher);
+            }
             OffsetValue::Empty => {}
         };
     }
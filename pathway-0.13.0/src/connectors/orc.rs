@@ -0,0 +1,321 @@
+// Copyright © 2024 Pathway
+
+use std::fs::File;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use deltalake::arrow::array::RecordBatch as DTRecordBatch;
+use deltalake::arrow::compute::cast as arrow_cast;
+use deltalake::arrow::datatypes::{
+    DataType as ArrowDataType, Field as ArrowField, Schema as ArrowSchema,
+    TimeUnit as ArrowTimeUnit,
+};
+use log::warn;
+use orc_rust::arrow_reader::{
+    ArrowReader as OrcArrowReader, ArrowReaderBuilder as OrcArrowReaderBuilder,
+};
+use orc_rust::arrow_writer::ArrowWriterBuilder as OrcArrowWriterBuilder;
+
+use crate::connectors::data_format::FormatterContext;
+use crate::connectors::data_storage::{
+    values_map_from_record_batch_row, BufferedColumns, DataEventType, ReadError, ReadResult,
+    Reader, ReaderContext, StorageType, ValuesMap, WriteError, Writer, SPECIAL_OUTPUT_FIELDS,
+};
+use crate::connectors::delta_table::DeltaTableWriter;
+use crate::connectors::metadata::SourceMetadata;
+use crate::connectors::{OffsetKey, OffsetValue};
+use crate::engine::Type;
+use crate::persistence::frontier::OffsetAntichain;
+use crate::persistence::PersistentId;
+use crate::python_api::ValueField;
+use crate::timestamp::current_unix_timestamp_secs;
+
+pub struct OrcFileWriter {
+    path_prefix: String,
+    schema: Arc<ArrowSchema>,
+    buffered_columns: BufferedColumns,
+    min_commit_frequency: Option<Duration>,
+    last_commit_at: Instant,
+    next_file_index: usize,
+}
+
+impl OrcFileWriter {
+    /// Creates a writer producing a new file `{path_prefix}-{index}.orc` on each commit.
+    /// Each file holds a single stripe with the rows buffered since the previous commit,
+    /// since the stripes only become readable once the file footer is written.
+    pub fn new(
+        path_prefix: String,
+        value_fields: &[ValueField],
+        min_commit_frequency: Option<Duration>,
+    ) -> Result<Self, WriteError> {
+        let schema = Arc::new(Self::construct_schema(value_fields)?);
+        let mut writer = Self {
+            path_prefix,
+            schema: schema.clone(),
+            buffered_columns: BufferedColumns::new(value_fields.len(), false),
+            min_commit_frequency,
+
+            // before the first commit, the time should be
+            // measured from the moment of the start
+            last_commit_at: Instant::now(),
+            next_file_index: 0,
+        };
+        // Don't overwrite the files written before a restart
+        while writer.file_path(writer.next_file_index).exists() {
+            writer.next_file_index += 1;
+        }
+        Ok(writer)
+    }
+
+    fn orc_data_type(type_: Type) -> Result<ArrowDataType, WriteError> {
+        Ok(match type_ {
+            Type::Bool => ArrowDataType::Boolean,
+            Type::Int | Type::Duration => ArrowDataType::Int64,
+            Type::Float => ArrowDataType::Float64,
+            Type::Pointer | Type::String | Type::Json => ArrowDataType::Utf8,
+            Type::Bytes => ArrowDataType::Binary,
+            // The ORC writer can't encode timestamps yet
+            Type::DateTimeNaive
+            | Type::DateTimeUtc
+            | Type::Any
+            | Type::Array
+            | Type::Tuple
+            | Type::PyObjectWrapper => return Err(WriteError::UnsupportedType(type_)),
+        })
+    }
+
+    pub fn construct_schema(value_fields: &[ValueField]) -> Result<ArrowSchema, WriteError> {
+        let mut schema_fields: Vec<ArrowField> = Vec::new();
+        for field in value_fields {
+            schema_fields.push(ArrowField::new(
+                field.name.clone(),
+                Self::orc_data_type(field.type_)?,
+                field.is_optional,
+            ));
+        }
+        for (field, type_) in SPECIAL_OUTPUT_FIELDS {
+            schema_fields.push(ArrowField::new(field, Self::orc_data_type(type_)?, false));
+        }
+        Ok(ArrowSchema::new(schema_fields))
+    }
+
+    fn file_path(&self, index: usize) -> PathBuf {
+        PathBuf::from(format!("{}-{index}.orc", self.path_prefix))
+    }
+}
+
+impl Writer for OrcFileWriter {
+    fn write(&mut self, data: FormatterContext) -> Result<(), WriteError> {
+        self.buffered_columns.push_row(data)
+    }
+
+    fn flush(&mut self, forced: bool) -> Result<(), WriteError> {
+        let commit_needed = !self.buffered_columns.is_empty()
+            && (self
+                .min_commit_frequency
+                .map_or(true, |f| self.last_commit_at.elapsed() >= f)
+                || forced);
+        if commit_needed {
+            let prepared_batch =
+                DeltaTableWriter::record_batch(&self.schema, self.buffered_columns.columns())?;
+            let file = File::create(self.file_path(self.next_file_index))?;
+            let mut writer = OrcArrowWriterBuilder::new(file, self.schema.clone()).try_build()?;
+            writer.write(&prepared_batch)?;
+            writer.close()?;
+
+            self.next_file_index += 1;
+            self.last_commit_at = Instant::now();
+            self.buffered_columns.clear();
+        }
+        Ok(())
+    }
+}
+
+/// Casts the columns read from an ORC file into the Arrow types supported by
+/// `arrow_value_at`: the narrower numbers are widened, the dates and the timestamps
+/// of any precision become microsecond timestamps, and the decimals become floats.
+fn orc_batch_into_engine_types(batch: &DTRecordBatch) -> Result<DTRecordBatch, ReadError> {
+    let schema = batch.schema();
+    let mut fields = Vec::with_capacity(batch.num_columns());
+    let mut columns = Vec::with_capacity(batch.num_columns());
+    for (field, column) in schema.fields().iter().zip(batch.columns()) {
+        let target_type = match column.data_type() {
+            ArrowDataType::Int8 | ArrowDataType::Int16 | ArrowDataType::Int32 => {
+                Some(ArrowDataType::Int64)
+            }
+            ArrowDataType::Float32 | ArrowDataType::Decimal128(_, _) => {
+                Some(ArrowDataType::Float64)
+            }
+            ArrowDataType::LargeUtf8 => Some(ArrowDataType::Utf8),
+            ArrowDataType::LargeBinary => Some(ArrowDataType::Binary),
+            ArrowDataType::Date32 => {
+                Some(ArrowDataType::Timestamp(ArrowTimeUnit::Microsecond, None))
+            }
+            ArrowDataType::Timestamp(unit, timezone) if *unit != ArrowTimeUnit::Microsecond => {
+                Some(ArrowDataType::Timestamp(
+                    ArrowTimeUnit::Microsecond,
+                    timezone.clone(),
+                ))
+            }
+            _ => None,
+        };
+        let column = match target_type {
+            Some(target_type) => arrow_cast(column, &target_type)?,
+            None => column.clone(),
+        };
+        fields.push(ArrowField::new(
+            field.name().clone(),
+            column.data_type().clone(),
+            field.is_nullable(),
+        ));
+        columns.push(column);
+    }
+    Ok(DTRecordBatch::try_new(
+        Arc::new(ArrowSchema::new(fields)),
+        columns,
+    )?)
+}
+
+pub struct OrcFileReader {
+    path: Arc<PathBuf>,
+    persistent_id: Option<PersistentId>,
+
+    reader: Option<OrcArrowReader<File>>,
+    current_batch: Option<DTRecordBatch>,
+    current_batch_row: usize,
+    total_entries_read: u64,
+    entries_to_skip: u64,
+    is_finished: bool,
+}
+
+impl OrcFileReader {
+    pub fn new(path: impl Into<PathBuf>, persistent_id: Option<PersistentId>) -> Self {
+        Self {
+            path: Arc::new(path.into()),
+            persistent_id,
+
+            reader: None,
+            current_batch: None,
+            current_batch_row: 0,
+            total_entries_read: 0,
+            entries_to_skip: 0,
+            is_finished: false,
+        }
+    }
+
+    fn open_file(&mut self) -> Result<ReadResult, ReadError> {
+        let file = File::open(self.path.as_path())?;
+        let metadata = SourceMetadata::from_fs_meta(
+            self.path.as_path(),
+            &file.metadata()?,
+            current_unix_timestamp_secs(),
+        );
+        self.reader = Some(OrcArrowReaderBuilder::try_new(file)?.build());
+        Ok(ReadResult::NewSource(Some(metadata)))
+    }
+
+    /// Returns the next row of the file, loading new stripes when needed.
+    fn next_row(&mut self) -> Result<Option<ValuesMap>, ReadError> {
+        loop {
+            if let Some(batch) = &self.current_batch {
+                if self.current_batch_row < batch.num_rows() {
+                    let values = values_map_from_record_batch_row(batch, self.current_batch_row)?;
+                    self.current_batch_row += 1;
+                    return Ok(Some(values));
+                }
+            }
+
+            let reader = self
+                .reader
+                .as_mut()
+                .expect("the file must be opened before reading rows");
+            match reader.next() {
+                Some(batch) => {
+                    self.current_batch = Some(orc_batch_into_engine_types(&batch?)?);
+                    self.current_batch_row = 0;
+                }
+                None => {
+                    self.current_batch = None;
+                    return Ok(None);
+                }
+            }
+        }
+    }
+}
+
+impl Reader for OrcFileReader {
+    fn seek(&mut self, frontier: &OffsetAntichain) -> Result<(), ReadError> {
+        let offset_value = frontier.get_offset(&OffsetKey::Empty);
+        let Some(OffsetValue::FilePosition {
+            total_entries_read,
+            path,
+            ..
+        }) = offset_value
+        else {
+            if offset_value.is_some() {
+                warn!("Incorrect type of offset value in OrcFilesystem frontier: {offset_value:?}");
+            }
+            return Ok(());
+        };
+
+        if *path != self.path {
+            warn!(
+                "Persisted offset refers to {path:?}, while the reader is configured for {:?}. Reading from the beginning.",
+                self.path
+            );
+            return Ok(());
+        }
+
+        // ORC files are read by stripes, so the rows that were
+        // already processed are skipped when the file is opened.
+        self.entries_to_skip = *total_entries_read;
+        Ok(())
+    }
+
+    fn read(&mut self) -> Result<ReadResult, ReadError> {
+        if self.is_finished {
+            return Ok(ReadResult::Finished);
+        }
+        if self.reader.is_none() {
+            return self.open_file();
+        }
+
+        while let Some(values) = self.next_row()? {
+            self.total_entries_read += 1;
+            if self.total_entries_read <= self.entries_to_skip {
+                continue;
+            }
+
+            let offset = (
+                OffsetKey::Empty,
+                OffsetValue::FilePosition {
+                    total_entries_read: self.total_entries_read,
+                    path: self.path.clone(),
+                    bytes_offset: 0,
+                },
+            );
+            return Ok(ReadResult::Data(
+                ReaderContext::from_diff(DataEventType::Insert, None, values),
+                offset,
+            ));
+        }
+
+        self.is_finished = true;
+        Ok(ReadResult::FinishedSource {
+            commit_allowed: true,
+        })
+    }
+
+    fn persistent_id(&self) -> Option<PersistentId> {
+        self.persistent_id
+    }
+
+    fn update_persistent_id(&mut self, persistent_id: Option<PersistentId>) {
+        self.persistent_id = persistent_id;
+    }
+
+    fn storage_type(&self) -> StorageType {
+        StorageType::OrcFilesystem
+    }
+}
@@ -0,0 +1,248 @@
+// Copyright © 2024 Pathway
+
+use std::fs::File;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use deltalake::arrow::array::RecordBatch as DTRecordBatch;
+use deltalake::arrow::datatypes::Schema as ArrowSchema;
+use deltalake::datafusion::parquet::arrow::arrow_reader::{
+    ParquetRecordBatchReader, ParquetRecordBatchReaderBuilder,
+};
+use deltalake::datafusion::parquet::arrow::ArrowWriter;
+use deltalake::datafusion::parquet::file::properties::WriterProperties;
+use log::warn;
+
+use crate::connectors::data_format::FormatterContext;
+use crate::connectors::data_storage::{
+    values_map_from_record_batch_row, BufferedColumns, DataEventType, ReadError, ReadResult,
+    Reader, ReaderContext, StorageType, ValuesMap, WriteError, Writer,
+};
+use crate::connectors::delta_table::DeltaTableWriter;
+use crate::connectors::metadata::SourceMetadata;
+use crate::connectors::{OffsetKey, OffsetValue};
+use crate::persistence::frontier::OffsetAntichain;
+use crate::persistence::PersistentId;
+use crate::python_api::ValueField;
+use crate::timestamp::current_unix_timestamp_secs;
+
+pub struct ParquetFileWriter {
+    path_prefix: String,
+    schema: Arc<ArrowSchema>,
+    properties: WriterProperties,
+    buffered_columns: BufferedColumns,
+    min_commit_frequency: Option<Duration>,
+    last_commit_at: Instant,
+    next_file_index: usize,
+}
+
+impl ParquetFileWriter {
+    /// Creates a writer producing a new file `{path_prefix}-{index}.parquet` on each
+    /// commit, since a Parquet file can't be appended to once it's closed.
+    pub fn new(
+        path_prefix: String,
+        value_fields: &Vec<ValueField>,
+        max_row_group_size: Option<usize>,
+        min_commit_frequency: Option<Duration>,
+    ) -> Result<Self, WriteError> {
+        let schema = Arc::new(DeltaTableWriter::construct_schema(value_fields, false)?);
+        let mut properties = WriterProperties::builder();
+        if let Some(max_row_group_size) = max_row_group_size {
+            properties = properties.set_max_row_group_size(max_row_group_size);
+        }
+
+        let mut writer = Self {
+            path_prefix,
+            schema: schema.clone(),
+            properties: properties.build(),
+            buffered_columns: BufferedColumns::new(value_fields.len(), false),
+            min_commit_frequency,
+
+            // before the first commit, the time should be
+            // measured from the moment of the start
+            last_commit_at: Instant::now(),
+            next_file_index: 0,
+        };
+        // Don't overwrite the files written before a restart
+        while writer.file_path(writer.next_file_index).exists() {
+            writer.next_file_index += 1;
+        }
+        Ok(writer)
+    }
+
+    fn file_path(&self, index: usize) -> PathBuf {
+        PathBuf::from(format!("{}-{index}.parquet", self.path_prefix))
+    }
+}
+
+impl Writer for ParquetFileWriter {
+    fn write(&mut self, data: FormatterContext) -> Result<(), WriteError> {
+        self.buffered_columns.push_row(data)
+    }
+
+    fn flush(&mut self, forced: bool) -> Result<(), WriteError> {
+        let commit_needed = !self.buffered_columns.is_empty()
+            && (self
+                .min_commit_frequency
+                .map_or(true, |f| self.last_commit_at.elapsed() >= f)
+                || forced);
+        if commit_needed {
+            let prepared_batch =
+                DeltaTableWriter::record_batch(&self.schema, self.buffered_columns.columns())?;
+            let file = File::create(self.file_path(self.next_file_index))?;
+            let mut writer =
+                ArrowWriter::try_new(file, self.schema.clone(), Some(self.properties.clone()))?;
+            writer.write(&prepared_batch)?;
+            writer.close()?;
+
+            self.next_file_index += 1;
+            self.last_commit_at = Instant::now();
+            self.buffered_columns.clear();
+        }
+        Ok(())
+    }
+}
+
+pub struct ParquetFileReader {
+    path: Arc<PathBuf>,
+    persistent_id: Option<PersistentId>,
+
+    reader: Option<ParquetRecordBatchReader>,
+    current_batch: Option<DTRecordBatch>,
+    current_batch_row: usize,
+    total_entries_read: u64,
+    entries_to_skip: u64,
+    is_finished: bool,
+}
+
+impl ParquetFileReader {
+    pub fn new(path: impl Into<PathBuf>, persistent_id: Option<PersistentId>) -> Self {
+        Self {
+            path: Arc::new(path.into()),
+            persistent_id,
+
+            reader: None,
+            current_batch: None,
+            current_batch_row: 0,
+            total_entries_read: 0,
+            entries_to_skip: 0,
+            is_finished: false,
+        }
+    }
+
+    fn open_file(&mut self) -> Result<ReadResult, ReadError> {
+        let file = File::open(self.path.as_path())?;
+        let metadata = SourceMetadata::from_fs_meta(
+            self.path.as_path(),
+            &file.metadata()?,
+            current_unix_timestamp_secs(),
+        );
+        self.reader = Some(ParquetRecordBatchReaderBuilder::try_new(file)?.build()?);
+        Ok(ReadResult::NewSource(Some(metadata)))
+    }
+
+    /// Returns the next row of the file, loading new record batches when needed.
+    fn next_row(&mut self) -> Result<Option<ValuesMap>, ReadError> {
+        loop {
+            if let Some(batch) = &self.current_batch {
+                if self.current_batch_row < batch.num_rows() {
+                    let values = values_map_from_record_batch_row(batch, self.current_batch_row)?;
+                    self.current_batch_row += 1;
+                    return Ok(Some(values));
+                }
+            }
+
+            let reader = self
+                .reader
+                .as_mut()
+                .expect("the file must be opened before reading rows");
+            match reader.next() {
+                Some(batch) => {
+                    self.current_batch = Some(batch?);
+                    self.current_batch_row = 0;
+                }
+                None => {
+                    self.current_batch = None;
+                    return Ok(None);
+                }
+            }
+        }
+    }
+}
+
+impl Reader for ParquetFileReader {
+    fn seek(&mut self, frontier: &OffsetAntichain) -> Result<(), ReadError> {
+        let offset_value = frontier.get_offset(&OffsetKey::Empty);
+        let Some(OffsetValue::FilePosition {
+            total_entries_read,
+            path,
+            ..
+        }) = offset_value
+        else {
+            if offset_value.is_some() {
+                warn!("Incorrect type of offset value in ParquetFilesystem frontier: {offset_value:?}");
+            }
+            return Ok(());
+        };
+
+        if *path != self.path {
+            warn!(
+                "Persisted offset refers to {path:?}, while the reader is configured for {:?}. Reading from the beginning.",
+                self.path
+            );
+            return Ok(());
+        }
+
+        // Parquet files are read by record batches, so the rows that were
+        // already processed are skipped when the file is opened.
+        self.entries_to_skip = *total_entries_read;
+        Ok(())
+    }
+
+    fn read(&mut self) -> Result<ReadResult, ReadError> {
+        if self.is_finished {
+            return Ok(ReadResult::Finished);
+        }
+        if self.reader.is_none() {
+            return self.open_file();
+        }
+
+        while let Some(values) = self.next_row()? {
+            self.total_entries_read += 1;
+            if self.total_entries_read <= self.entries_to_skip {
+                continue;
+            }
+
+            let offset = (
+                OffsetKey::Empty,
+                OffsetValue::FilePosition {
+                    total_entries_read: self.total_entries_read,
+                    path: self.path.clone(),
+                    bytes_offset: 0,
+                },
+            );
+            return Ok(ReadResult::Data(
+                ReaderContext::from_diff(DataEventType::Insert, None, values),
+                offset,
+            ));
+        }
+
+        self.is_finished = true;
+        Ok(ReadResult::FinishedSource {
+            commit_allowed: true,
+        })
+    }
+
+    fn persistent_id(&self) -> Option<PersistentId> {
+        self.persistent_id
+    }
+
+    fn update_persistent_id(&mut self, persistent_id: Option<PersistentId>) {
+        self.persistent_id = persistent_id;
+    }
+
+    fn storage_type(&self) -> StorageType {
+        StorageType::ParquetFilesystem
+    }
+}
@@ -0,0 +1,474 @@
+// Copyright © 2024 Pathway
+
+use std::collections::HashMap;
+use std::str::from_utf8;
+use std::time::Duration;
+
+use chrono::DateTime;
+use log::warn;
+use postgres::binary_copy::BinaryCopyInWriter as PsqlBinaryCopyInWriter;
+use postgres::types::ToSql;
+use postgres::{Client as PsqlClient, Transaction as PsqlTransaction};
+use uuid::Uuid;
+
+use crate::connectors::data_format::FormatterContext;
+use crate::connectors::data_storage::{CommitPolicy, WriteError, Writer, WrittenRowsCounter};
+use crate::engine::{Type, Value};
+
+pub struct PsqlWriter {
+    client: PsqlClient,
+    commit_policy: CommitPolicy,
+    buffer: Vec<FormatterContext>,
+    snapshot_mode: bool,
+    written_rows: WrittenRowsCounter,
+    timescale_copy: Option<TimescaleCopy>,
+}
+
+impl PsqlWriter {
+    /// Creates a writer, which executes the queries from the formatter in batches.
+    ///
+    /// If `timescale_time_column` is given, the batches of inserts into the
+    /// TimescaleDB hypertables with this column are written with `COPY`, ordered
+    /// by the time column, which is much faster than the inserts. The tables that
+    /// aren't hypertables are written in the standard way.
+    pub fn new(
+        client: PsqlClient,
+        max_batch_size: Option<usize>,
+        min_commit_frequency: Option<Duration>,
+        snapshot_mode: bool,
+        timescale_time_column: Option<String>,
+    ) -> PsqlWriter {
+        PsqlWriter {
+            client,
+            commit_policy: CommitPolicy::new(max_batch_size, min_commit_frequency),
+            buffer: Vec::new(),
+            snapshot_mode,
+            written_rows: WrittenRowsCounter::default(),
+            timescale_copy: timescale_time_column.map(TimescaleCopy::new),
+        }
+    }
+
+    fn commit_buffer(&mut self) -> Result<(), WriteError> {
+        let mut transaction = self.client.transaction()?;
+        let mut insert_batch = PsqlInsertBatch::default();
+        let mut affected_rows = 0;
+
+        for data in &self.buffer {
+            for payload in &data.payloads {
+                let query = from_utf8(payload)?;
+
+                if let Some(statement) = PsqlInsertStatement::parse(query) {
+                    if !insert_batch.accepts(&statement, data.values.len()) {
+                        affected_rows +=
+                            insert_batch.execute(&mut transaction, self.timescale_copy.as_mut())?;
+                    }
+                    insert_batch.push(&statement, &data.values);
+                    continue;
+                }
+
+                // Statements that can't be grouped are executed one by one,
+                // preserving the order with respect to the grouped ones
+                affected_rows +=
+                    insert_batch.execute(&mut transaction, self.timescale_copy.as_mut())?;
+                let params: Vec<_> = data
+                    .values
+                    .iter()
+                    .map(|v| v as &(dyn ToSql + Sync))
+                    .collect();
+                affected_rows += execute_psql_query(&mut transaction, query, &params)?;
+            }
+        }
+        affected_rows += insert_batch.execute(&mut transaction, self.timescale_copy.as_mut())?;
+        transaction.commit()?;
+
+        self.written_rows
+            .add(usize::try_from(affected_rows).unwrap_or(usize::MAX));
+        self.buffer.clear();
+        self.commit_policy.on_commit();
+
+        Ok(())
+    }
+}
+
+/// Executes the query and returns the number of the rows it affected.
+fn execute_psql_query(
+    transaction: &mut PsqlTransaction,
+    query: &str,
+    params: &[&(dyn ToSql + Sync)],
+) -> Result<u64, WriteError> {
+    transaction
+        .execute(query, params)
+        .map_err(|error| WriteError::PsqlQueryFailed {
+            query: query.to_string(),
+            error,
+        })
+}
+
+/// A single-row `INSERT INTO ... VALUES (...)` statement without any trailing
+/// clauses, so that it can be merged with other statements of the same shape.
+#[derive(Clone, Copy)]
+struct PsqlInsertStatement<'a> {
+    head: &'a str,
+    values: &'a str,
+}
+
+impl<'a> PsqlInsertStatement<'a> {
+    fn parse(query: &'a str) -> Option<Self> {
+        let query = query.trim_end();
+        if !query.starts_with("INSERT INTO ") {
+            return None;
+        }
+        let (head, values_tuple) = query.rsplit_once(" VALUES ")?;
+        let values = values_tuple.strip_prefix('(')?.strip_suffix(')')?;
+        let is_plain_tuple = !values
+            .chars()
+            .any(|c| c.is_whitespace() || c == '(' || c == ')' || c == '\'' || c == ';');
+        is_plain_tuple.then_some(Self { head, values })
+    }
+
+    /// Checks if the values are exactly the parameters `$1`, ..., `$n_params`, so
+    /// that the row consists of the parameters in their order.
+    fn is_positional(&self, n_params: usize) -> bool {
+        let mut n_elements = 0;
+        let all_positional = self.values.split(',').enumerate().all(|(index, element)| {
+            n_elements += 1;
+            element.strip_prefix('$') == Some(&(index + 1).to_string())
+        });
+        all_positional && n_elements == n_params
+    }
+
+    /// Splits the head into the table name and the list of the columns.
+    fn table_and_columns(&self) -> Option<(&'a str, &'a str)> {
+        let target = self.head.strip_prefix("INSERT INTO ")?;
+        let (table, columns) = target.split_once('(')?;
+        Some((table.trim(), columns.trim_end().strip_suffix(')')?))
+    }
+}
+
+/// Accumulates consecutive INSERT statements with the same columns into a single
+/// multi-row statement, renumbering the parameter placeholders.
+#[derive(Default)]
+struct PsqlInsertBatch<'a> {
+    head: Option<PsqlInsertStatement<'a>>,
+    query: String,
+    params: Vec<&'a (dyn ToSql + Sync)>,
+    rows: Vec<&'a [Value]>,
+    is_positional: bool,
+}
+
+impl<'a> PsqlInsertBatch<'a> {
+    // Postgres protocol limits the number of parameters in a statement
+    const MAX_PARAMS: usize = 65_535;
+
+    fn accepts(&self, statement: &PsqlInsertStatement, n_params: usize) -> bool {
+        match &self.head {
+            Some(head) => {
+                head.head == statement.head && self.params.len() + n_params <= Self::MAX_PARAMS
+            }
+            None => true,
+        }
+    }
+
+    fn push(&mut self, statement: &PsqlInsertStatement<'a>, values: &'a [Value]) {
+        if self.head.is_none() {
+            self.head = Some(*statement);
+            self.is_positional = true;
+            self.query.push_str(statement.head);
+            self.query.push_str(" VALUES ");
+        } else {
+            self.query.push(',');
+        }
+        self.is_positional &= statement.is_positional(values.len());
+        self.rows.push(values);
+
+        let params_offset = self.params.len();
+        self.query.push('(');
+        for (index, element) in statement.values.split(',').enumerate() {
+            if index > 0 {
+                self.query.push(',');
+            }
+            match element
+                .strip_prefix('$')
+                .and_then(|position| position.parse::<usize>().ok())
+            {
+                Some(position) => {
+                    self.query.push('$');
+                    self.query.push_str(&(position + params_offset).to_string());
+                }
+                None => self.query.push_str(element),
+            }
+        }
+        self.query.push(')');
+
+        self.params
+            .extend(values.iter().map(|v| v as &(dyn ToSql + Sync)));
+    }
+
+    /// Executes the accumulated statements. If `timescale_copy` is given and the
+    /// target is a hypertable, the rows are copied instead.
+    fn execute(
+        &mut self,
+        transaction: &mut PsqlTransaction,
+        timescale_copy: Option<&mut TimescaleCopy>,
+    ) -> Result<u64, WriteError> {
+        let Some(statement) = self.head.take() else {
+            return Ok(0);
+        };
+        let copied_rows = match timescale_copy {
+            Some(timescale_copy) if self.is_positional => {
+                timescale_copy.copy(transaction, &statement, &mut self.rows)?
+            }
+            _ => None,
+        };
+        let affected_rows = match copied_rows {
+            Some(copied_rows) => copied_rows,
+            None => execute_psql_query(transaction, &self.query, &self.params)?,
+        };
+        self.query.clear();
+        self.params.clear();
+        self.rows.clear();
+        Ok(affected_rows)
+    }
+}
+
+/// Writes the batches of inserts into the TimescaleDB hypertables with `COPY`,
+/// sorting the rows by the time column, so that they go to the chunks in order.
+struct TimescaleCopy {
+    time_column: String,
+    is_hypertable: HashMap<String, bool>,
+}
+
+impl TimescaleCopy {
+    const HYPERTABLE_QUERY: &'static str = "SELECT EXISTS (SELECT 1 FROM timescaledb_information.hypertables WHERE format('%I.%I', hypertable_schema, hypertable_name)::regclass = $1::text::regclass)";
+
+    fn new(time_column: String) -> Self {
+        Self {
+            time_column,
+            is_hypertable: HashMap::new(),
+        }
+    }
+
+    /// Checks whether the table is a hypertable. If the check fails, for example,
+    /// because there is no TimescaleDB extension, the table is considered a regular one.
+    fn is_hypertable(
+        &mut self,
+        transaction: &mut PsqlTransaction,
+        table: &str,
+    ) -> Result<bool, WriteError> {
+        if let Some(is_hypertable) = self.is_hypertable.get(table) {
+            return Ok(*is_hypertable);
+        }
+        // A failed query aborts the transaction, so it's run within a savepoint
+        let mut savepoint = transaction.transaction()?;
+        let is_hypertable = match savepoint.query_one(Self::HYPERTABLE_QUERY, &[&table]) {
+            Ok(row) => row.try_get(0).unwrap_or(false),
+            Err(e) => {
+                warn!("Failed to check if {table} is a TimescaleDB hypertable, writing it as a regular table: {e}");
+                false
+            }
+        };
+        drop(savepoint);
+        self.is_hypertable.insert(table.to_string(), is_hypertable);
+        Ok(is_hypertable)
+    }
+
+    /// Copies the rows of the given single-row insert statements into the table.
+    /// Returns `None` if the rows need to be inserted in the standard way: the
+    /// table isn't a hypertable, it doesn't have the time column or the copy failed.
+    fn copy(
+        &mut self,
+        transaction: &mut PsqlTransaction,
+        statement: &PsqlInsertStatement,
+        rows: &mut [&[Value]],
+    ) -> Result<Option<u64>, WriteError> {
+        let Some((table, columns)) = statement.table_and_columns() else {
+            return Ok(None);
+        };
+        let Some(time_column_index) = columns
+            .split(',')
+            .position(|column| column.trim().trim_matches('"') == self.time_column)
+        else {
+            return Ok(None);
+        };
+        if !self.is_hypertable(transaction, table)? {
+            return Ok(None);
+        }
+        rows.sort_by(|lhs, rhs| lhs[time_column_index].cmp(&rhs[time_column_index]));
+
+        let insert_query = format!("{} VALUES ({})", statement.head, statement.values);
+        let copy_query = format!("COPY {table} ({columns}) FROM STDIN BINARY");
+        let mut savepoint = transaction.transaction()?;
+        let copy_result = Self::copy_rows(&mut savepoint, &insert_query, &copy_query, rows);
+        match copy_result {
+            Ok(copied_rows) => {
+                savepoint.commit()?;
+                Ok(Some(copied_rows))
+            }
+            Err(e) => {
+                warn!("Failed to copy the rows into {table}, switching to the inserts: {e}");
+                drop(savepoint);
+                self.is_hypertable.insert(table.to_string(), false);
+                Ok(None)
+            }
+        }
+    }
+
+    fn copy_rows(
+        transaction: &mut PsqlTransaction,
+        insert_query: &str,
+        copy_query: &str,
+        rows: &[&[Value]],
+    ) -> Result<u64, postgres::Error> {
+        // The types of the columns are taken from the insert statement parameters
+        let column_types = transaction.prepare(insert_query)?.params().to_vec();
+        let mut writer =
+            PsqlBinaryCopyInWriter::new(transaction.copy_in(copy_query)?, &column_types);
+        for row in rows {
+            let params: Vec<_> = row.iter().map(|v| v as &(dyn ToSql + Sync)).collect();
+            writer.write(&params)?;
+        }
+        writer.finish()
+    }
+}
+
+mod to_sql {
+    use std::error::Error;
+
+    use bytes::BytesMut;
+    use chrono::{DateTime, NaiveDateTime, Utc};
+    use ordered_float::OrderedFloat;
+    use postgres::types::{to_sql_checked, Format, IsNull, ToSql, Type};
+    use uuid::Uuid;
+
+    use crate::engine::time::DateTime as _;
+    use crate::engine::Value;
+
+    #[derive(Debug, Clone, thiserror::Error)]
+    #[error("cannot convert value of type {pathway_type:?} to Postgres type {postgres_type}")]
+    struct WrongPathwayType {
+        pathway_type: String,
+        postgres_type: Type,
+    }
+
+    impl ToSql for Value {
+        fn to_sql(
+            &self,
+            ty: &Type,
+            out: &mut BytesMut,
+        ) -> Result<IsNull, Box<dyn Error + Sync + Send>> {
+            macro_rules! try_forward {
+                ($type:ty, $expr:expr) => {
+                    if <$type as ToSql>::accepts(ty) {
+                        let value: $type = $expr.try_into()?;
+                        assert!(matches!(self.encode_format(ty), Format::Binary));
+                        assert!(matches!(value.encode_format(ty), Format::Binary));
+                        return value.to_sql(ty, out);
+                    }
+                };
+            }
+            #[allow(clippy::match_same_arms)]
+            let pathway_type = match self {
+                Self::None => return Ok(IsNull::Yes),
+                Self::Bool(b) => {
+                    try_forward!(bool, *b);
+                    "bool"
+                }
+                Self::Int(i) => {
+                    try_forward!(i64, *i);
+                    try_forward!(i32, *i);
+                    try_forward!(i16, *i);
+                    try_forward!(i8, *i);
+                    #[allow(clippy::cast_precision_loss)]
+                    {
+                        try_forward!(f64, *i as f64);
+                        try_forward!(f32, *i as f32);
+                    }
+                    "int"
+                }
+                Self::Float(OrderedFloat(f)) => {
+                    try_forward!(f64, *f);
+                    #[allow(clippy::cast_possible_truncation)]
+                    {
+                        try_forward!(f32, *f as f32);
+                    }
+                    "float"
+                }
+                Self::Pointer(p) => {
+                    try_forward!(String, p.to_string());
+                    // The binary forms keep the byte order of the textual one
+                    try_forward!(Uuid, Uuid::from_bytes(u128::from(p.0).to_le_bytes()));
+                    try_forward!(Vec<u8>, p.0.to_le_bytes().to_vec());
+                    "pointer"
+                }
+                Self::String(s) => {
+                    try_forward!(&str, s.as_str());
+                    "string"
+                }
+                Self::Bytes(b) => {
+                    try_forward!(&[u8], &b[..]);
+                    "bytes"
+                }
+                Self::Tuple(t) => {
+                    try_forward!(&[Value], &t[..]);
+                    "tuple"
+                }
+                Self::IntArray(_) => "int array",     // TODO
+                Self::FloatArray(_) => "float array", // TODO
+                Self::DateTimeNaive(dt) => {
+                    try_forward!(NaiveDateTime, dt.as_chrono_datetime());
+                    "naive date/time"
+                }
+                Self::DateTimeUtc(dt) => {
+                    try_forward!(DateTime<Utc>, dt.as_chrono_datetime().and_utc());
+                    "UTC date/time"
+                }
+                Self::Duration(_) => "duration", // TODO
+                Self::Json(j) => {
+                    try_forward!(&serde_json::Value, &**j);
+                    "JSON"
+                }
+                Self::Error => "error",
+                Self::PyObjectWrapper(_) => "PyObjectWrapper",
+            };
+            Err(Box::new(WrongPathwayType {
+                pathway_type: pathway_type.to_owned(),
+                postgres_type: ty.clone(),
+            }))
+        }
+
+        fn accepts(_ty: &Type) -> bool {
+            true // we double-check anyway
+        }
+
+        to_sql_checked!();
+    }
+}
+
+impl Writer for PsqlWriter {
+    fn write(&mut self, data: FormatterContext) -> Result<(), WriteError> {
+        self.buffer.push(data);
+        // The buffer is committed as soon as it reaches the limit, so it never holds
+        // more than `max_batch_size` entries between the writes
+        if self.commit_policy.is_batch_full(self.buffer.len()) {
+            self.commit_buffer()?;
+        }
+        debug_assert!(!self.commit_policy.is_batch_full(self.buffer.len()));
+        Ok(())
+    }
+
+    fn flush(&mut self, forced: bool) -> Result<(), WriteError> {
+        if self.commit_policy.is_commit_due(self.buffer.len(), forced) {
+            self.commit_buffer()?;
+        }
+        self.written_rows.on_flush();
+        Ok(())
+    }
+
+    fn rows_written_since_last_flush(&self) -> usize {
+        self.written_rows.last_flush()
+    }
+
+    fn single_threaded(&self) -> bool {
+        self.snapshot_mode
+    }
+}
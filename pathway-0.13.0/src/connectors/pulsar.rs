@@ -0,0 +1,405 @@
+// Copyright © 2024 Pathway
+
+use std::collections::HashMap;
+use std::time::Instant;
+
+use futures::TryStreamExt;
+use log::{error, warn};
+use pulsar::consumer::InitialPosition as PulsarInitialPosition;
+use pulsar::message::proto::MessageIdData as PulsarMessageIdData;
+use pulsar::producer::{
+    Message as PulsarMessage, ProducerOptions as PulsarProducerOptions,
+    SendFuture as PulsarSendFuture,
+};
+use pulsar::{
+    Consumer as PulsarConsumer, ConsumerOptions as PulsarConsumerOptions, Error as PulsarError,
+    Producer as PulsarProducer, Pulsar, SubType as PulsarSubType, TokioExecutor,
+};
+use tokio::runtime::Runtime as TokioRuntime;
+
+use crate::connectors::data_format::FormatterContext;
+use crate::connectors::data_storage::{
+    message_headers, ReadError, ReadResult, Reader, ReaderContext, ShutdownSignal, StorageType,
+    WriteError, Writer,
+};
+use crate::connectors::{Offset, OffsetKey, OffsetValue};
+use crate::persistence::frontier::OffsetAntichain;
+use crate::persistence::PersistentId;
+
+/// Consumes an Apache Pulsar topic with an exclusive subscription.
+///
+/// The offsets are the ids of the messages, which only grow within a
+/// non-partitioned topic, so the persistence assumes that the topic isn't
+/// partitioned. The messages are acknowledged as soon as they are read, so
+/// without persistence a restarted reader resumes where the subscription stopped.
+/// After a seek the subscription is moved back to the message from the frontier,
+/// and everything up to this message is skipped.
+pub struct PulsarReader {
+    client: Pulsar<TokioExecutor>,
+    topic: String,
+    subscription: String,
+    persistent_id: Option<PersistentId>,
+    start_after: Option<(u64, u64, i32)>,
+    consumer: Option<PulsarConsumer<Vec<u8>, TokioExecutor>>,
+    last_activity: Option<Instant>,
+    shutdown_signal: ShutdownSignal,
+
+    // Declared last so that it's dropped after everything that may use it
+    runtime: TokioRuntime,
+}
+
+impl PulsarReader {
+    pub fn new(
+        service_url: &str,
+        topic: String,
+        subscription: String,
+        persistent_id: Option<PersistentId>,
+    ) -> Result<PulsarReader, ReadError> {
+        let runtime = tokio::runtime::Builder::new_multi_thread()
+            .enable_all()
+            .build()?;
+        let client = runtime.block_on(Pulsar::builder(service_url, TokioExecutor).build())?;
+
+        Ok(PulsarReader {
+            client,
+            topic,
+            subscription,
+            persistent_id,
+            start_after: None,
+            consumer: None,
+            last_activity: None,
+            shutdown_signal: ShutdownSignal::default(),
+            runtime,
+        })
+    }
+
+    fn subscribe(&self) -> Result<PulsarConsumer<Vec<u8>, TokioExecutor>, ReadError> {
+        let options = PulsarConsumerOptions {
+            initial_position: PulsarInitialPosition::Earliest,
+            ..Default::default()
+        };
+        let consumer = self.runtime.block_on(async {
+            let mut consumer = self
+                .client
+                .consumer()
+                .with_topic(&self.topic)
+                .with_subscription(&self.subscription)
+                .with_subscription_type(PulsarSubType::Exclusive)
+                .with_options(options)
+                .build::<Vec<u8>>()
+                .await?;
+            if let Some((ledger_id, entry_id, batch_index)) = self.start_after {
+                let message_id = PulsarMessageIdData {
+                    ledger_id,
+                    entry_id,
+                    batch_index: (batch_index >= 0).then_some(batch_index),
+                    ..Default::default()
+                };
+                consumer
+                    .seek(None, Some(message_id), None, self.client.clone())
+                    .await?;
+            }
+            Ok::<_, PulsarError>(consumer)
+        })?;
+        Ok(consumer)
+    }
+
+    /// Converts the message at `position`, which consists of its ledger id, entry id
+    /// and batch index, into the entry and the offset pointing at this message.
+    fn message_entry(position: (u64, u64, i32), payload: Vec<u8>) -> (ReaderContext, Offset) {
+        let (ledger_id, entry_id, batch_index) = position;
+        let offset = (
+            OffsetKey::Empty,
+            OffsetValue::PulsarMessageId {
+                ledger_id,
+                entry_id,
+                batch_index,
+            },
+        );
+        (ReaderContext::from_key_value(None, Some(payload)), offset)
+    }
+
+    /// Returns the position of the last message in `frontier`, after which the
+    /// reading is resumed.
+    fn start_after(frontier: &OffsetAntichain) -> Option<(u64, u64, i32)> {
+        let offset_value = frontier.get_offset(&OffsetKey::Empty);
+        let Some(OffsetValue::PulsarMessageId {
+            ledger_id,
+            entry_id,
+            batch_index,
+        }) = offset_value
+        else {
+            if offset_value.is_some() {
+                warn!("Incorrect type of offset value in Pulsar frontier: {offset_value:?}");
+            }
+            return None;
+        };
+        Some((*ledger_id, *entry_id, *batch_index))
+    }
+}
+
+impl Reader for PulsarReader {
+    fn seek(&mut self, frontier: &OffsetAntichain) -> Result<(), ReadError> {
+        let Some(start_after) = Self::start_after(frontier) else {
+            return Ok(());
+        };
+
+        // "Lazy" seek: the consumer is created on the first read
+        self.start_after = Some(start_after);
+        self.consumer = None;
+
+        Ok(())
+    }
+
+    fn read(&mut self) -> Result<ReadResult, ReadError> {
+        if self.consumer.is_none() {
+            self.consumer = Some(self.subscribe()?);
+        }
+        let consumer = self.consumer.as_mut().unwrap();
+
+        loop {
+            let Some(Some(message)) = self
+                .shutdown_signal
+                .block_on(&self.runtime, consumer.try_next())
+                .transpose()?
+            else {
+                return Ok(ReadResult::Finished);
+            };
+            self.runtime
+                .block_on(consumer.ack(&message))
+                .map_err(PulsarError::from)?;
+
+            let message_id = message.message_id();
+            let position = (
+                message_id.ledger_id,
+                message_id.entry_id,
+                message_id.batch_index.unwrap_or(-1),
+            );
+            // Seeking into a batch redelivers the whole batch
+            if self
+                .start_after
+                .is_some_and(|start_after| position <= start_after)
+            {
+                continue;
+            }
+
+            self.last_activity = Some(Instant::now());
+            let (message, offset) = Self::message_entry(position, message.payload.data);
+            return Ok(ReadResult::Data(message, offset));
+        }
+    }
+
+    fn persistent_id(&self) -> Option<PersistentId> {
+        self.persistent_id
+    }
+
+    fn update_persistent_id(&mut self, persistent_id: Option<PersistentId>) {
+        self.persistent_id = persistent_id;
+    }
+
+    fn set_shutdown_signal(&mut self, signal: ShutdownSignal) {
+        self.shutdown_signal = signal;
+    }
+
+    fn storage_type(&self) -> StorageType {
+        StorageType::Pulsar
+    }
+
+    fn last_activity(&self) -> Option<Instant> {
+        self.last_activity
+    }
+}
+
+/// Publishes the payloads to an Apache Pulsar topic.
+///
+/// The properties of the messages mirror the headers of `KafkaWriter`, and the
+/// partition key is taken from the field at `key_field_index` if it's given. The
+/// messages are accumulated by a batching producer, which sends them once
+/// `max_batch_size` of them are pending or on a flush, and the flush also waits
+/// until the broker receives them.
+pub struct PulsarWriter {
+    producer: PulsarProducer<TokioExecutor>,
+    header_fields: Vec<(String, usize)>,
+    key_field_index: Option<usize>,
+    max_batch_size: usize,
+    pending_receipts: Vec<PulsarSendFuture>,
+    is_finished: bool,
+
+    // Declared last so that it's dropped after everything that may use it
+    runtime: TokioRuntime,
+}
+
+impl PulsarWriter {
+    pub const DEFAULT_MAX_BATCH_SIZE: usize = 1000;
+
+    pub fn new(
+        service_url: &str,
+        topic: String,
+        header_fields: Vec<(String, usize)>,
+        key_field_index: Option<usize>,
+        max_batch_size: Option<usize>,
+    ) -> Result<PulsarWriter, WriteError> {
+        let max_batch_size = max_batch_size.unwrap_or(Self::DEFAULT_MAX_BATCH_SIZE);
+        let runtime = tokio::runtime::Builder::new_multi_thread()
+            .enable_all()
+            .build()?;
+        let producer = runtime.block_on(async {
+            let client = Pulsar::builder(service_url, TokioExecutor).build().await?;
+            client
+                .producer()
+                .with_topic(topic)
+                .with_options(PulsarProducerOptions {
+                    batch_size: Some(max_batch_size.try_into().unwrap_or(u32::MAX)),
+                    ..Default::default()
+                })
+                .build()
+                .await
+        })?;
+
+        Ok(PulsarWriter {
+            producer,
+            header_fields,
+            key_field_index,
+            max_batch_size,
+            pending_receipts: Vec::new(),
+            is_finished: false,
+            runtime,
+        })
+    }
+
+    /// Returns the properties of the messages produced for `data`, the same as the
+    /// headers of the messages produced by `KafkaWriter`.
+    fn message_properties(
+        header_fields: &[(String, usize)],
+        data: &FormatterContext,
+    ) -> HashMap<String, String> {
+        message_headers(header_fields, data)
+            .into_iter()
+            .map(|(name, value)| {
+                (
+                    name.to_string(),
+                    String::from_utf8_lossy(&value).to_string(),
+                )
+            })
+            .collect()
+    }
+
+    /// Sends the accumulated batch and waits until the broker receives every
+    /// message sent so far.
+    fn send_pending(&mut self) -> Result<(), WriteError> {
+        if self.pending_receipts.is_empty() {
+            return Ok(());
+        }
+        let Self {
+            producer,
+            pending_receipts,
+            runtime,
+            ..
+        } = self;
+        runtime.block_on(async {
+            producer.send_batch().await?;
+            for receipt in pending_receipts.drain(..) {
+                receipt.await?;
+            }
+            Ok::<_, PulsarError>(())
+        })?;
+        Ok(())
+    }
+
+    fn close(&mut self) -> Result<(), WriteError> {
+        let delivered = self.send_pending();
+        self.runtime.block_on(self.producer.close())?;
+        delivered
+    }
+}
+
+impl Drop for PulsarWriter {
+    fn drop(&mut self) {
+        // The writer that wasn't finished explicitly, for instance because the
+        // output failed, still tries to deliver what it has sent
+        if self.is_finished {
+            return;
+        }
+        if let Err(e) = self.close() {
+            error!("Failed to deliver the remaining Pulsar messages: {e}");
+        }
+    }
+}
+
+impl Writer for PulsarWriter {
+    fn write(&mut self, data: FormatterContext) -> Result<(), WriteError> {
+        let properties = Self::message_properties(&self.header_fields, &data);
+        let partition_key = self
+            .key_field_index
+            .map(|index| Self::value_as_string(&data.values[index]));
+
+        for payload in data.payloads {
+            let message = PulsarMessage {
+                payload,
+                properties: properties.clone(),
+                partition_key: partition_key.clone(),
+                ..Default::default()
+            };
+            let receipt = self
+                .runtime
+                .block_on(self.producer.send_non_blocking(message))?;
+            self.pending_receipts.push(receipt);
+            if self.pending_receipts.len() >= self.max_batch_size {
+                self.send_pending()?;
+            }
+        }
+        Ok(())
+    }
+
+    fn flush(&mut self, _forced: bool) -> Result<(), WriteError> {
+        self.send_pending()
+    }
+
+    fn finish(mut self: Box<Self>) -> Result<(), WriteError> {
+        self.is_finished = true;
+        self.close()
+    }
+
+    fn retriable(&self) -> bool {
+        true
+    }
+
+    fn single_threaded(&self) -> bool {
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::connectors::data_storage::StorageType;
+    use crate::persistence::frontier::OffsetAntichain;
+
+    use super::PulsarReader;
+
+    #[test]
+    fn test_reading_resumed_after_last_message() {
+        assert_eq!(PulsarReader::start_after(&OffsetAntichain::new()), None);
+
+        // The frontiers of the messages are merged by the ledger, then by the entry and
+        // then by the position within the batch
+        for (lhs, rhs, expected) in [
+            ((3, 10, -1), (4, 0, -1), (4, 0, -1)),
+            ((3, 5, -1), (3, 7, -1), (3, 7, -1)),
+            ((3, 5, 0), (3, 5, 2), (3, 5, 2)),
+        ] {
+            let mut lhs_frontier = OffsetAntichain::new();
+            let (_, (offset_key, offset_value)) = PulsarReader::message_entry(lhs, Vec::new());
+            lhs_frontier.advance_offset(offset_key, offset_value);
+            let mut rhs_frontier = OffsetAntichain::new();
+            let (_, (offset_key, offset_value)) = PulsarReader::message_entry(rhs, Vec::new());
+            rhs_frontier.advance_offset(offset_key, offset_value);
+
+            for merged in [
+                StorageType::Pulsar.merge_two_frontiers(&lhs_frontier, &rhs_frontier),
+                StorageType::Pulsar.merge_two_frontiers(&rhs_frontier, &lhs_frontier),
+            ] {
+                assert_eq!(PulsarReader::start_after(&merged), Some(expected));
+            }
+        }
+    }
+}
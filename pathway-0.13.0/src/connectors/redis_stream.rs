@@ -0,0 +1,427 @@
+// Copyright © 2024 Pathway
+
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+use log::warn;
+use redis::streams::{
+    StreamId as RedisStreamEntry, StreamReadOptions as RedisStreamReadOptions,
+    StreamReadReply as RedisStreamReadReply,
+};
+use redis::{
+    Client as RedisClient, Commands as _, Connection as RedisConnection, Pipeline as RedisPipeline,
+    RedisResult,
+};
+
+use crate::connectors::data_format::FormatterContext;
+use crate::connectors::data_storage::{
+    message_headers, ReadError, ReadResult, Reader, ReaderContext, ShutdownSignal, StorageType,
+    WriteError, Writer,
+};
+use crate::connectors::{Offset, OffsetKey, OffsetValue};
+use crate::persistence::frontier::OffsetAntichain;
+use crate::persistence::PersistentId;
+
+/// The field of a Redis stream entry holding the message key, if there is one.
+const REDIS_STREAM_KEY_FIELD: &str = "key";
+
+/// The field of a Redis stream entry holding the message payload.
+const REDIS_STREAM_PAYLOAD_FIELD: &str = "data";
+
+/// Parses the id of a Redis stream entry, which consists of the milliseconds
+/// time and the sequence number within this millisecond, separated by a dash.
+fn parse_redis_stream_id(id: &str) -> Option<(u64, u64)> {
+    let (milliseconds, sequence) = id.split_once('-')?;
+    Some((milliseconds.parse().ok()?, sequence.parse().ok()?))
+}
+
+/// Reads the entries of a Redis stream, emitting the values of the
+/// `REDIS_STREAM_KEY_FIELD` and `REDIS_STREAM_PAYLOAD_FIELD` fields as the key
+/// and the payload of the message.
+///
+/// Without a consumer group, the stream is read with `XREAD` after the id of the
+/// last read entry, so the reading is resumed from the frontier after a restart.
+///
+/// With a consumer group, the entries are read with `XREADGROUP` and acknowledged
+/// only after the engine commits the data they belong to. On start, the entries
+/// delivered to the consumer before but never acknowledged are read first.
+pub struct RedisStreamReader {
+    connection: RedisConnection,
+    stream_key: String,
+    consumer_group: Option<(String, String)>,
+    reading_pending_entries: bool,
+    last_pending_id: Option<String>,
+
+    // The entries up to this id have already been processed, so they are skipped
+    last_read_id: Option<(u64, u64)>,
+    entries: VecDeque<RedisStreamEntry>,
+    unacked_ids: VecDeque<(u64, u64)>,
+
+    persistent_id: Option<PersistentId>,
+    last_activity: Option<Instant>,
+    shutdown_signal: ShutdownSignal,
+}
+
+impl RedisStreamReader {
+    const READ_BATCH_SIZE: usize = 1000;
+    const READ_BLOCK_TIMEOUT: Duration = Duration::from_secs(1);
+
+    /// Creates a reader of the stream `stream_key`. If `consumer_group` is given as
+    /// the pair of the group name and the consumer name, the group is created
+    /// unless it already exists.
+    pub fn new(
+        uri: &str,
+        stream_key: String,
+        consumer_group: Option<(String, String)>,
+        persistent_id: Option<PersistentId>,
+    ) -> Result<RedisStreamReader, ReadError> {
+        let mut connection = RedisClient::open(uri)?.get_connection()?;
+        if let Some((group_name, _)) = &consumer_group {
+            let created: RedisResult<()> =
+                connection.xgroup_create_mkstream(&stream_key, group_name, "0");
+            match created {
+                Err(e) if e.code() != Some("BUSYGROUP") => return Err(e.into()),
+                _ => {}
+            }
+        }
+
+        Ok(RedisStreamReader {
+            connection,
+            stream_key,
+            reading_pending_entries: consumer_group.is_some(),
+            consumer_group,
+            last_pending_id: None,
+            last_read_id: None,
+            entries: VecDeque::new(),
+            unacked_ids: VecDeque::new(),
+            persistent_id,
+            last_activity: None,
+            shutdown_signal: ShutdownSignal::default(),
+        })
+    }
+
+    fn fetch_entries(&mut self) -> Result<(), ReadError> {
+        let mut options = RedisStreamReadOptions::default()
+            .count(Self::READ_BATCH_SIZE)
+            .block(Self::READ_BLOCK_TIMEOUT.as_millis().try_into().unwrap());
+        let start_id = match &self.consumer_group {
+            Some((group_name, consumer_name)) => {
+                options = options.group(group_name, consumer_name);
+                if self.reading_pending_entries {
+                    // The pending entries stay in the list until acknowledged, so
+                    // they are paged through by the id of the last one received
+                    self.last_pending_id
+                        .clone()
+                        .unwrap_or_else(|| "0".to_string())
+                } else {
+                    ">".to_string()
+                }
+            }
+            None => match self.last_read_id {
+                Some((milliseconds, sequence)) => format!("{milliseconds}-{sequence}"),
+                None => "0-0".to_string(),
+            },
+        };
+
+        let reply: RedisStreamReadReply =
+            self.connection
+                .xread_options(&[&self.stream_key], &[start_id], &options)?;
+        let mut has_entries = false;
+        for stream in reply.keys {
+            if let Some(last_entry) = stream.ids.last() {
+                has_entries = true;
+                if self.reading_pending_entries {
+                    self.last_pending_id = Some(last_entry.id.clone());
+                }
+            }
+            self.entries.extend(stream.ids);
+        }
+        if self.reading_pending_entries && !has_entries {
+            // All entries left from the previous runs are read, proceed to the new ones
+            self.reading_pending_entries = false;
+        }
+        Ok(())
+    }
+
+    /// Converts the stream entry with the parsed `id` into the entry and the offset
+    /// pointing at it.
+    fn message_entry(id: (u64, u64), entry: &RedisStreamEntry) -> (ReaderContext, Offset) {
+        let offset = (
+            OffsetKey::Empty,
+            OffsetValue::RedisStreamId {
+                milliseconds: id.0,
+                sequence: id.1,
+            },
+        );
+        let message = ReaderContext::from_key_value(
+            entry.get(REDIS_STREAM_KEY_FIELD),
+            entry.get(REDIS_STREAM_PAYLOAD_FIELD),
+        );
+        (message, offset)
+    }
+
+    /// Returns the id of the last entry in `frontier`, after which the reading is
+    /// resumed.
+    fn last_read_id(frontier: &OffsetAntichain) -> Option<(u64, u64)> {
+        let offset_value = frontier.get_offset(&OffsetKey::Empty);
+        let Some(OffsetValue::RedisStreamId {
+            milliseconds,
+            sequence,
+        }) = offset_value
+        else {
+            if offset_value.is_some() {
+                warn!("Incorrect type of offset value in Redis stream frontier: {offset_value:?}");
+            }
+            return None;
+        };
+        Some((*milliseconds, *sequence))
+    }
+}
+
+impl Reader for RedisStreamReader {
+    fn seek(&mut self, frontier: &OffsetAntichain) -> Result<(), ReadError> {
+        let Some(last_read_id) = Self::last_read_id(frontier) else {
+            return Ok(());
+        };
+
+        self.last_read_id = Some(last_read_id);
+        self.entries.clear();
+        Ok(())
+    }
+
+    fn read(&mut self) -> Result<ReadResult, ReadError> {
+        loop {
+            let Some(entry) = self.entries.pop_front() else {
+                // The fetch waits for new entries at most `READ_BLOCK_TIMEOUT`
+                if self.shutdown_signal.is_requested() {
+                    return Ok(ReadResult::Finished);
+                }
+                self.fetch_entries()?;
+                continue;
+            };
+            let id = parse_redis_stream_id(&entry.id)
+                .ok_or_else(|| ReadError::MalformedData(entry.id.clone().into_bytes()))?;
+            if self.consumer_group.is_some() {
+                self.unacked_ids.push_back(id);
+            }
+            if self
+                .last_read_id
+                .is_some_and(|last_read_id| id <= last_read_id)
+            {
+                continue;
+            }
+
+            self.last_read_id = Some(id);
+            self.last_activity = Some(Instant::now());
+            let (message, offset) = Self::message_entry(id, &entry);
+            return Ok(ReadResult::Data(message, offset));
+        }
+    }
+
+    fn on_commit(&mut self, frontier: &OffsetAntichain) -> Result<(), ReadError> {
+        let Some((group_name, _)) = &self.consumer_group else {
+            return Ok(());
+        };
+        let Some(OffsetValue::RedisStreamId {
+            milliseconds,
+            sequence,
+        }) = frontier.get_offset(&OffsetKey::Empty)
+        else {
+            return Ok(());
+        };
+
+        let mut committed_ids = Vec::new();
+        while let Some(id) = self.unacked_ids.front() {
+            if *id > (*milliseconds, *sequence) {
+                break;
+            }
+            committed_ids.push(format!("{}-{}", id.0, id.1));
+            self.unacked_ids.pop_front();
+        }
+        if !committed_ids.is_empty() {
+            let _: usize = self
+                .connection
+                .xack(&self.stream_key, group_name, &committed_ids)?;
+        }
+        Ok(())
+    }
+
+    fn persistent_id(&self) -> Option<PersistentId> {
+        self.persistent_id
+    }
+
+    fn update_persistent_id(&mut self, persistent_id: Option<PersistentId>) {
+        self.persistent_id = persistent_id;
+    }
+
+    fn set_shutdown_signal(&mut self, signal: ShutdownSignal) {
+        self.shutdown_signal = signal;
+    }
+
+    fn storage_type(&self) -> StorageType {
+        StorageType::RedisStream
+    }
+
+    fn last_activity(&self) -> Option<Instant> {
+        self.last_activity
+    }
+}
+
+/// Appends the payloads to a Redis stream with `XADD`. The entries written within
+/// a batch are sent in a single pipeline.
+pub struct RedisStreamWriter {
+    connection: RedisConnection,
+    stream_key: String,
+    header_fields: Vec<(String, usize)>,
+    pipeline: RedisPipeline,
+    pending_entries: usize,
+}
+
+impl RedisStreamWriter {
+    pub fn new(
+        uri: &str,
+        stream_key: String,
+        header_fields: Vec<(String, usize)>,
+    ) -> Result<RedisStreamWriter, WriteError> {
+        let connection = RedisClient::open(uri)?.get_connection()?;
+        Ok(RedisStreamWriter {
+            connection,
+            stream_key,
+            header_fields,
+            pipeline: RedisPipeline::new(),
+            pending_entries: 0,
+        })
+    }
+
+    /// Returns the fields of the entries produced for `data` besides the payload.
+    fn entry_fields(
+        header_fields: &[(String, usize)],
+        data: &FormatterContext,
+    ) -> Vec<(String, Vec<u8>)> {
+        message_headers(header_fields, data)
+            .into_iter()
+            .map(|(name, value)| (name.to_string(), value))
+            .collect()
+    }
+}
+
+impl Writer for RedisStreamWriter {
+    fn write(&mut self, data: FormatterContext) -> Result<(), WriteError> {
+        let fields = Self::entry_fields(&self.header_fields, &data);
+
+        for payload in data.payloads {
+            let mut entry_fields = fields.clone();
+            entry_fields.push((REDIS_STREAM_PAYLOAD_FIELD.to_string(), payload));
+            self.pipeline
+                .xadd(&self.stream_key, "*", &entry_fields)
+                .ignore();
+            self.pending_entries += 1;
+        }
+        Ok(())
+    }
+
+    fn flush(&mut self, _forced: bool) -> Result<(), WriteError> {
+        if self.pending_entries > 0 {
+            self.pipeline.query::<()>(&mut self.connection)?;
+            self.pipeline.clear();
+            self.pending_entries = 0;
+        }
+        Ok(())
+    }
+
+    fn retriable(&self) -> bool {
+        true
+    }
+
+    fn single_threaded(&self) -> bool {
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use redis::streams::StreamId as RedisStreamEntry;
+    use redis::Value as RedisValue;
+
+    use crate::connectors::data_storage::{ReaderContext, StorageType};
+    use crate::persistence::frontier::OffsetAntichain;
+
+    use super::{
+        parse_redis_stream_id, RedisStreamReader, REDIS_STREAM_KEY_FIELD,
+        REDIS_STREAM_PAYLOAD_FIELD,
+    };
+
+    fn stream_entry(fields: &[(&str, &[u8])]) -> RedisStreamEntry {
+        let map: HashMap<String, RedisValue> = fields
+            .iter()
+            .map(|(name, value)| ((*name).to_string(), RedisValue::Data(value.to_vec())))
+            .collect();
+        RedisStreamEntry {
+            id: String::new(),
+            map,
+        }
+    }
+
+    #[test]
+    fn test_parse_redis_stream_id() {
+        assert_eq!(
+            parse_redis_stream_id("1526919030474-55"),
+            Some((1_526_919_030_474, 55))
+        );
+        assert_eq!(parse_redis_stream_id("0-0"), Some((0, 0)));
+        assert_eq!(parse_redis_stream_id("1526919030474"), None);
+        assert_eq!(parse_redis_stream_id("abc-1"), None);
+        assert_eq!(parse_redis_stream_id("1-"), None);
+    }
+
+    #[test]
+    fn test_message_entry_key_is_optional() {
+        let entry = stream_entry(&[
+            (REDIS_STREAM_KEY_FIELD, b"key"),
+            (REDIS_STREAM_PAYLOAD_FIELD, b"payload"),
+            ("pathway_time", b"4"),
+        ]);
+        let (message, _) = RedisStreamReader::message_entry((5, 1), &entry);
+        assert_eq!(
+            message,
+            ReaderContext::KeyValue((Some(b"key".to_vec()), Some(b"payload".to_vec())))
+        );
+
+        let entry = stream_entry(&[(REDIS_STREAM_PAYLOAD_FIELD, b"payload")]);
+        let (message, _) = RedisStreamReader::message_entry((5, 2), &entry);
+        assert_eq!(
+            message,
+            ReaderContext::KeyValue((None, Some(b"payload".to_vec())))
+        );
+    }
+
+    #[test]
+    fn test_reading_resumed_after_last_entry() {
+        assert_eq!(
+            RedisStreamReader::last_read_id(&OffsetAntichain::new()),
+            None
+        );
+
+        // The frontiers of the entries are merged by the milliseconds time and then by
+        // the sequence number
+        for (lhs, rhs, expected) in [((5, 1), (5, 2), (5, 2)), ((4, 9), (5, 0), (5, 0))] {
+            let mut lhs_frontier = OffsetAntichain::new();
+            let (_, (offset_key, offset_value)) =
+                RedisStreamReader::message_entry(lhs, &stream_entry(&[]));
+            lhs_frontier.advance_offset(offset_key, offset_value);
+            let mut rhs_frontier = OffsetAntichain::new();
+            let (_, (offset_key, offset_value)) =
+                RedisStreamReader::message_entry(rhs, &stream_entry(&[]));
+            rhs_frontier.advance_offset(offset_key, offset_value);
+
+            for merged in [
+                StorageType::RedisStream.merge_two_frontiers(&lhs_frontier, &rhs_frontier),
+                StorageType::RedisStream.merge_two_frontiers(&rhs_frontier, &lhs_frontier),
+            ] {
+                assert_eq!(RedisStreamReader::last_read_id(&merged), Some(expected));
+            }
+        }
+    }
+}
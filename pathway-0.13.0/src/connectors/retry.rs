@@ -0,0 +1,98 @@
+// Copyright © 2024 Pathway
+
+use std::thread::sleep;
+use std::time::Duration;
+
+use log::warn;
+use rand::Rng;
+
+use crate::connectors::data_format::FormatterContext;
+use crate::connectors::data_storage::{WriteError, Writer};
+use crate::engine::Timestamp;
+
+/// Repeats the writes and flushes of a retriable writer that failed with a
+/// transient error, with an exponential backoff starting from `initial_backoff`.
+/// At most `max_attempts` attempts are made in total, after which the last error
+/// is returned. The other errors, as well as all errors of a non-retriable writer,
+/// are returned right away.
+pub struct RetryingWriter {
+    inner: Box<dyn Writer>,
+    max_attempts: usize,
+    initial_backoff: Duration,
+}
+
+impl RetryingWriter {
+    pub const DEFAULT_MAX_ATTEMPTS: usize = 5;
+    pub const DEFAULT_INITIAL_BACKOFF: Duration = Duration::from_millis(500);
+    const MAX_BACKOFF: Duration = Duration::from_secs(30);
+
+    pub fn new(inner: Box<dyn Writer>, max_attempts: usize, initial_backoff: Duration) -> Self {
+        Self {
+            inner,
+            max_attempts,
+            initial_backoff,
+        }
+    }
+
+    fn with_retries(
+        &mut self,
+        mut operation: impl FnMut(&mut dyn Writer) -> Result<(), WriteError>,
+    ) -> Result<(), WriteError> {
+        let mut backoff = self.initial_backoff;
+        let mut attempt = 1;
+        loop {
+            let error = match operation(self.inner.as_mut()) {
+                Ok(()) => return Ok(()),
+                Err(error) => error,
+            };
+            if !self.inner.retriable() || !error.is_transient() || attempt >= self.max_attempts {
+                return Err(error);
+            }
+
+            warn!(
+                "Output failed (attempt {attempt} of {}): {error}. Retrying",
+                self.max_attempts
+            );
+            let jitter = rand::thread_rng().gen_range(0.5..1.5);
+            sleep(backoff.mul_f64(jitter));
+            backoff = (backoff * 2).min(Self::MAX_BACKOFF);
+            attempt += 1;
+        }
+    }
+}
+
+impl Writer for RetryingWriter {
+    fn write(&mut self, data: FormatterContext) -> Result<(), WriteError> {
+        if !self.inner.retriable() {
+            return self.inner.write(data);
+        }
+        self.with_retries(|inner| inner.write(data.clone()))
+    }
+
+    fn flush(&mut self, forced: bool) -> Result<(), WriteError> {
+        self.with_retries(|inner| inner.flush(forced))
+    }
+
+    fn on_commit(&mut self, time: Timestamp) -> Result<(), WriteError> {
+        self.inner.on_commit(time)
+    }
+
+    /// The final flush is retried, then the inner writer is finished.
+    fn finish(mut self: Box<Self>) -> Result<(), WriteError> {
+        self.flush(true)?;
+        self.inner.finish()
+    }
+
+    fn rows_written_since_last_flush(&self) -> usize {
+        self.inner.rows_written_since_last_flush()
+    }
+
+    // The retries are already done here, so the engine doesn't repeat them
+    fn retriable(&self) -> bool {
+        false
+    }
+
+    fn single_threaded(&self) -> bool {
+        self.inner.single_threaded()
+    }
+}
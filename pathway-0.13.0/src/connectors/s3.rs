@@ -0,0 +1,1401 @@
+// Copyright © 2024 Pathway
+
+use std::any::Any;
+use std::cmp::Ordering;
+use std::collections::{BTreeSet, HashMap, HashSet, VecDeque};
+use std::io::{BufReader, Read, Write};
+use std::sync::Arc;
+use std::thread::sleep;
+use std::time::{Duration, Instant};
+use std::{io, thread};
+
+use chrono::DateTime;
+use glob::Pattern as GlobPattern;
+use log::{error, warn};
+use pipe::PipeReader;
+use rand::Rng;
+use s3::bucket::Bucket as S3Bucket;
+use s3::error::S3Error;
+
+use crate::connectors::data_format::InnerSchemaField;
+use crate::connectors::data_storage::{
+    extract_seek_position, is_idle_timeout_reached, CompressionFormat, CsvDialect, DataEventType,
+    ReadError, ReadMethod, ReadResult, Reader, ReaderContext, S3CommandName, SeekOffsetKind,
+    SeekPosition, ShutdownSignal, StorageType,
+};
+use crate::connectors::{Offset, OffsetKey, OffsetValue};
+use crate::deepcopy::DeepCopy;
+use crate::engine::error::DynError;
+use crate::persistence::frontier::OffsetAntichain;
+use crate::persistence::PersistentId;
+
+/// Forwards the written data to the inner writer, dropping the first `to_skip`
+/// bytes. This way a restarted download doesn't deliver the same prefix twice.
+pub struct SkippingWriter<'a, W> {
+    inner: &'a mut W,
+    to_skip: u64,
+    position: u64,
+    inner_failed: bool,
+}
+
+impl<'a, W: Write> SkippingWriter<'a, W> {
+    pub fn new(inner: &'a mut W, to_skip: u64) -> Self {
+        Self {
+            inner,
+            to_skip,
+            position: 0,
+            inner_failed: false,
+        }
+    }
+
+    /// The number of bytes written so far, the skipped ones included.
+    pub fn position(&self) -> u64 {
+        self.position
+    }
+
+    /// Whether a write into the inner writer failed. Such a failure, like the
+    /// pipe closed by the reader, isn't fixed by restarting the download.
+    pub fn inner_failed(&self) -> bool {
+        self.inner_failed
+    }
+}
+
+impl<W: Write> Write for SkippingWriter<'_, W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let skipped = usize::try_from(self.to_skip.saturating_sub(self.position))
+            .unwrap_or(usize::MAX)
+            .min(buf.len());
+        if skipped < buf.len() {
+            if let Err(e) = self.inner.write_all(&buf[skipped..]) {
+                self.inner_failed = true;
+                return Err(e);
+            }
+        }
+        self.position += buf.len() as u64;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+pub(crate) fn is_retriable_s3_status_code(code: u16) -> bool {
+    matches!(code, 429 | 500 | 502 | 503 | 504)
+}
+
+pub(crate) fn is_retriable_s3_error(error: &S3Error) -> bool {
+    match error {
+        S3Error::HttpFailWithBody(code, _) => is_retriable_s3_status_code(*code),
+        S3Error::Io(_) | S3Error::Atto(_) => true,
+        _ => false,
+    }
+}
+
+pub struct CurrentlyProcessedS3Object {
+    loader_thread: std::thread::JoinHandle<Result<(), ReadError>>,
+    path: Arc<String>,
+    last_modified: Option<i64>,
+}
+
+impl CurrentlyProcessedS3Object {
+    /// Waits for the end of the download. A panic of the download thread is turned
+    /// into an error, so that it fails the reader rather than the whole process.
+    ///
+    /// The download can't hang forever on a stalled connection, since the store
+    /// clients time out the requests, like `S3Bucket` does with its
+    /// `request_timeout`.
+    pub fn finalize(self) -> Result<(), ReadError> {
+        self.loader_thread.join().unwrap_or_else(|payload| {
+            Err(ReadError::DownloadThreadPanicked(panic_message(
+                payload.as_ref(),
+            )))
+        })
+    }
+}
+
+/// Extracts the message from the payload of a panic, which is a string unless the
+/// panic was raised with a custom payload.
+fn panic_message(payload: &(dyn Any + Send)) -> String {
+    if let Some(message) = payload.downcast_ref::<&str>() {
+        (*message).to_string()
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        message.clone()
+    } else {
+        "unknown cause".to_string()
+    }
+}
+
+/// The order in which the objects of a bucket are read.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ObjectOrdering {
+    /// By the modification time, the objects modified at the same time are ordered
+    /// by key.
+    #[default]
+    ModifiedTimeThenKey,
+
+    /// By key, so that the modification times aren't relied on. Suits the keys that
+    /// are ordered by themselves, like date-partitioned ones.
+    KeyOnly,
+
+    /// By key, in the reverse lexicographical order.
+    KeyDescending,
+}
+
+impl ObjectOrdering {
+    /// Compares the objects given as `(last_modified, key)` pairs in the reading order.
+    pub fn compare(self, lhs: (i64, &str), rhs: (i64, &str)) -> Ordering {
+        match self {
+            Self::ModifiedTimeThenKey => lhs.cmp(&rhs),
+            Self::KeyOnly => lhs.1.cmp(rhs.1),
+            Self::KeyDescending => rhs.1.cmp(lhs.1),
+        }
+    }
+}
+
+/// An object waiting to be read. The pending objects are sorted in the reading order.
+#[derive(Debug, PartialEq, Eq)]
+struct PendingS3Object {
+    ordering: ObjectOrdering,
+    last_modified: i64,
+    key: String,
+}
+
+impl Ord for PendingS3Object {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.ordering.compare(
+            (self.last_modified, &self.key),
+            (other.last_modified, &other.key),
+        )
+    }
+}
+
+impl PartialOrd for PendingS3Object {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// The keys of the processed S3 objects. Only the most recently processed ones are
+/// kept: when an object is evicted, the watermark moves to it, and everything that
+/// doesn't go after the watermark in the reading order is considered processed.
+/// Since the objects are read in the order of `(last_modified, key)` by default, the
+/// evicted ones are the earliest and the watermark doesn't cover the unprocessed
+/// objects, unless they appear in the bucket with a modification time from the past.
+/// For the other orderings, it's the same with the keys instead of the times.
+pub struct ProcessedS3Objects {
+    keys: HashSet<String>,
+    order: VecDeque<(i64, String)>,
+    capacity: usize,
+    watermark: Option<(i64, String)>,
+    ordering: ObjectOrdering,
+}
+
+impl ProcessedS3Objects {
+    pub const DEFAULT_CAPACITY: usize = 100_000;
+
+    pub fn new(capacity: usize) -> Self {
+        Self::with_ordering(capacity, ObjectOrdering::default())
+    }
+
+    pub fn with_ordering(capacity: usize, ordering: ObjectOrdering) -> Self {
+        Self {
+            keys: HashSet::new(),
+            order: VecDeque::new(),
+            capacity: capacity.max(1),
+            watermark: None,
+            ordering,
+        }
+    }
+
+    pub fn contains(&self, key: &str, last_modified: i64) -> bool {
+        self.keys.contains(key)
+            || self
+                .watermark
+                .as_ref()
+                .is_some_and(|(watermark_last_modified, watermark_key)| {
+                    self.ordering.compare(
+                        (last_modified, key),
+                        (*watermark_last_modified, watermark_key.as_str()),
+                    ) != Ordering::Greater
+                })
+    }
+
+    pub fn insert(&mut self, key: String, last_modified: i64) {
+        if !self.keys.insert(key.clone()) {
+            return;
+        }
+        self.order.push_back((last_modified, key));
+        if self.order.len() > self.capacity {
+            let evicted = self.order.pop_front().expect("the order can't be empty");
+            self.keys.remove(&evicted.1);
+            if self.watermark.as_ref().map_or(true, |watermark| {
+                self.ordering
+                    .compare((watermark.0, &watermark.1), (evicted.0, &evicted.1))
+                    == Ordering::Less
+            }) {
+                self.watermark = Some(evicted);
+            }
+        }
+    }
+
+    /// Forgets all the processed objects and sets the watermark, so that the
+    /// objects up to the given one, inclusive, are considered processed.
+    pub fn reset(&mut self, watermark: Option<(i64, String)>) {
+        self.keys.clear();
+        self.order.clear();
+        self.watermark = watermark;
+    }
+}
+
+/// The storage that `ObjectScanner` reads the objects from, such as an S3 bucket or
+/// an Azure Blob Storage container. A copy of the store is moved into the thread
+/// downloading each object, so the copies must be usable independently.
+pub trait ObjectStore: DeepCopy + Send + 'static {
+    /// Whether the listing can be continued after a given key. Otherwise, the
+    /// store is listed in full once the pending objects are exhausted.
+    const LISTS_AFTER_KEY: bool;
+    /// What the objects are called in the logs.
+    const OBJECT_KIND: &'static str;
+    const DEFAULT_MAX_GET_ATTEMPTS: usize = 5;
+
+    /// Lists the objects with the keys after `start_after`, or all of them if it's
+    /// `None`. Each object is passed to `on_object` along with its modification
+    /// time in nanoseconds since the epoch. Returns the last listed key.
+    fn list_objects(
+        &self,
+        start_after: Option<&str>,
+        on_object: &mut dyn FnMut(&str, i64),
+    ) -> Result<Option<String>, ReadError>;
+
+    /// Downloads the object into the writer in a single attempt.
+    fn get_object_to_writer(
+        &self,
+        key: &str,
+        writer: &mut (dyn Write + Send),
+    ) -> Result<(), ReadError>;
+
+    /// Whether a failed download is worth another attempt.
+    fn is_retriable_error(error: &ReadError) -> bool;
+}
+
+fn compile_object_pattern(object_pattern: &str) -> Result<GlobPattern, ReadError> {
+    Ok(GlobPattern::new(&format!("**/{object_pattern}"))?)
+}
+
+/// An S3 bucket as an `ObjectStore`. Only the objects under `objects_prefix`
+/// are listed.
+pub struct S3Scanner {
+    bucket: S3Bucket,
+    objects_prefix: String,
+}
+
+impl S3Scanner {
+    pub fn new(bucket: S3Bucket, objects_prefix: impl Into<String>) -> Self {
+        Self {
+            bucket,
+            objects_prefix: objects_prefix.into(),
+        }
+    }
+
+    pub fn stream_object_from_path_and_bucket(
+        object_path_ref: &str,
+        bucket: S3Bucket,
+        max_get_attempts: usize,
+    ) -> (CurrentlyProcessedS3Object, PipeReader) {
+        ObjectScanner::stream_object_from_store(
+            Self::new(bucket, ""),
+            object_path_ref,
+            max_get_attempts,
+        )
+    }
+
+    /// Compiles the pattern that the object keys are matched against.
+    pub fn compile_object_pattern(object_pattern: &str) -> Result<GlobPattern, ReadError> {
+        compile_object_pattern(object_pattern)
+    }
+
+    /// Parses the modification time reported by S3 into nanoseconds since the epoch.
+    pub fn parse_last_modified(last_modified: &str) -> Option<i64> {
+        DateTime::parse_from_rfc3339(last_modified)
+            .ok()?
+            .timestamp_nanos_opt()
+    }
+
+    /// Returns the keys of the objects that are read no later than the object
+    /// `path` modified at `last_modified`. The objects are read in the order of
+    /// `(last_modified, key)`, so everything preceding the given object in this
+    /// order has already been processed.
+    pub fn objects_processed_before<'a>(
+        objects: impl IntoIterator<Item = (&'a str, &'a str)>,
+        path: &str,
+        last_modified: i64,
+    ) -> HashSet<String> {
+        let mut processed_objects: HashSet<String> = objects
+            .into_iter()
+            .filter_map(|(key, object_last_modified)| {
+                let object_last_modified = Self::parse_last_modified(object_last_modified)?;
+                let is_before = ObjectOrdering::default()
+                    .compare((object_last_modified, key), (last_modified, path))
+                    == Ordering::Less;
+                is_before.then(|| key.to_string())
+            })
+            .collect();
+        processed_objects.insert(path.to_string());
+        processed_objects
+    }
+}
+
+impl DeepCopy for S3Scanner {
+    fn deep_copy(&self) -> Self {
+        Self {
+            bucket: self.bucket.deep_copy(),
+            objects_prefix: self.objects_prefix.clone(),
+        }
+    }
+}
+
+impl ObjectStore for S3Scanner {
+    const LISTS_AFTER_KEY: bool = true;
+    const OBJECT_KIND: &'static str = "S3 object";
+
+    /// The objects are listed page by page, so that only one page is held in
+    /// memory at a time.
+    fn list_objects(
+        &self,
+        start_after: Option<&str>,
+        on_object: &mut dyn FnMut(&str, i64),
+    ) -> Result<Option<String>, ReadError> {
+        let mut last_listed_key = None;
+        let mut continuation_token = None;
+        loop {
+            let (page, _) = self
+                .bucket
+                .list_page(
+                    self.objects_prefix.clone(),
+                    None,
+                    continuation_token,
+                    start_after.map(ToString::to_string),
+                    None,
+                )
+                .map_err(|e| ReadError::S3(S3CommandName::ListObjectsV2, e))?;
+            for object in &page.contents {
+                if let Some(last_modified) = Self::parse_last_modified(&object.last_modified) {
+                    on_object(&object.key, last_modified);
+                }
+            }
+            if let Some(object) = page.contents.last() {
+                last_listed_key = Some(object.key.clone());
+            }
+            continuation_token = page.next_continuation_token;
+            if !page.is_truncated || continuation_token.is_none() {
+                return Ok(last_listed_key);
+            }
+        }
+    }
+
+    fn get_object_to_writer(
+        &self,
+        key: &str,
+        mut writer: &mut (dyn Write + Send),
+    ) -> Result<(), ReadError> {
+        match self.bucket.get_object_to_writer(key, &mut writer) {
+            Ok(200) => Ok(()),
+            Ok(code) => Err(ReadError::S3(
+                S3CommandName::GetObject,
+                S3Error::HttpFailWithBody(code, String::new()),
+            )),
+            Err(e) => Err(ReadError::S3(S3CommandName::GetObject, e)),
+        }
+    }
+
+    fn is_retriable_error(error: &ReadError) -> bool {
+        matches!(error, ReadError::S3(_, e) if is_retriable_s3_error(e))
+    }
+}
+
+pub struct ObjectScanner<S: ObjectStore> {
+    /*
+        This class takes responsibility over object selection and streaming.
+        In encapsulates the selection of the next object to stream and streaming
+        the object and provides reader end of the pipe to the outside user.
+
+        The listing is cached: the unprocessed objects are kept in the reading
+        order, which is `(last_modified, key)` unless another ordering is
+        requested, and the subsequent listings only request the keys after the
+        last listed one. The keys that appear before it are picked up by
+        a full listing, which is done once `full_listing_interval` passes since
+        the previous one, regardless of what the listings in between found.
+        If the store can't continue a listing after a given key, it's listed in
+        full whenever the pending objects run out.
+
+        Up to `max_in_flight_objects` objects are downloaded at once: the ones
+        following the current object are taken from the pending ones in the
+        same order and queued, so that their downloads are already started when
+        they become current.
+    */
+    store: S,
+    object_pattern: GlobPattern,
+    max_get_attempts: usize,
+    max_in_flight_objects: usize,
+    current_object: Option<CurrentlyProcessedS3Object>,
+    prefetched_objects: VecDeque<(CurrentlyProcessedS3Object, PipeReader)>,
+    processed_objects: ProcessedS3Objects,
+    pending_objects: BTreeSet<PendingS3Object>,
+    object_ordering: ObjectOrdering,
+    last_listed_key: Option<String>,
+    full_listing_interval: Duration,
+    last_full_listing_at: Instant,
+}
+
+impl<S: ObjectStore> ObjectScanner<S> {
+    const GET_RETRY_INITIAL_BACKOFF: Duration = Duration::from_millis(500);
+    const GET_RETRY_MAX_BACKOFF: Duration = Duration::from_secs(30);
+    pub const DEFAULT_MAX_IN_FLIGHT_OBJECTS: usize = 1;
+    pub const DEFAULT_FULL_LISTING_INTERVAL: Duration = Duration::from_secs(60);
+
+    /// Creates a scanner of the objects in `store` whose keys match
+    /// `object_pattern`. As for the filesystem, the pattern may match any number of
+    /// path components after the prefix, so `"*"` selects all of the objects.
+    /// The objects are read in the order given by `object_ordering`.
+    ///
+    /// At most `max_in_flight_objects` objects, the current one included, are
+    /// downloaded at the same time. By default, the next object is requested only
+    /// after the current one is read.
+    pub fn new(
+        store: S,
+        object_pattern: &str,
+        object_ordering: ObjectOrdering,
+        max_get_attempts: usize,
+        max_in_flight_objects: Option<usize>,
+    ) -> Result<Self, ReadError> {
+        let mut scanner = ObjectScanner {
+            store,
+            object_pattern: compile_object_pattern(object_pattern)?,
+            max_get_attempts,
+            max_in_flight_objects: max_in_flight_objects
+                .unwrap_or(Self::DEFAULT_MAX_IN_FLIGHT_OBJECTS)
+                .max(1),
+
+            current_object: None,
+            prefetched_objects: VecDeque::new(),
+            processed_objects: ProcessedS3Objects::with_ordering(
+                ProcessedS3Objects::DEFAULT_CAPACITY,
+                object_ordering,
+            ),
+            pending_objects: BTreeSet::new(),
+            object_ordering,
+            last_listed_key: None,
+            full_listing_interval: Self::DEFAULT_FULL_LISTING_INTERVAL,
+            last_full_listing_at: Instant::now(),
+        };
+        scanner.list_all_objects()?;
+        if scanner.pending_objects.is_empty() {
+            return Err(ReadError::NoObjectsToRead);
+        }
+
+        Ok(scanner)
+    }
+
+    /// Adds the unprocessed objects matching the pattern with the keys after
+    /// `start_after` to the pending ones.
+    fn list_pending_objects_after(&mut self, start_after: Option<&str>) -> Result<(), ReadError> {
+        let object_pattern = &self.object_pattern;
+        let processed_objects = &self.processed_objects;
+        let pending_objects = &mut self.pending_objects;
+        let ordering = self.object_ordering;
+        let last_listed_key = self
+            .store
+            .list_objects(start_after, &mut |key, last_modified| {
+                if object_pattern.matches(key) && !processed_objects.contains(key, last_modified) {
+                    pending_objects.insert(PendingS3Object {
+                        ordering,
+                        last_modified,
+                        key: key.to_string(),
+                    });
+                }
+            })?;
+        if last_listed_key.is_some() {
+            self.last_listed_key = last_listed_key;
+        }
+        Ok(())
+    }
+
+    /// Lists the whole store again. It also drops the pending objects that were
+    /// deleted since they were listed.
+    fn list_all_objects(&mut self) -> Result<(), ReadError> {
+        self.pending_objects.clear();
+        self.last_listed_key = None;
+        self.last_full_listing_at = Instant::now();
+        self.list_pending_objects_after(None)
+    }
+
+    /// Sets the time after which the store is listed in full again, so that the
+    /// new objects with the keys before the last listed one are found.
+    pub fn set_full_listing_interval(&mut self, interval: Duration) {
+        self.full_listing_interval = interval;
+    }
+
+    /// Lists the objects that appeared after the previous listing.
+    fn list_new_objects(&mut self) -> Result<(), ReadError> {
+        if !S::LISTS_AFTER_KEY {
+            if self.pending_objects.is_empty() {
+                self.list_all_objects()?;
+            }
+            return Ok(());
+        }
+
+        // The keys are listed in the lexicographical order, so the new ones that
+        // go before the last listed key are found only by a full listing
+        if self.last_full_listing_at.elapsed() >= self.full_listing_interval {
+            return self.list_all_objects();
+        }
+        let last_listed_key = self.last_listed_key.clone();
+        self.list_pending_objects_after(last_listed_key.as_deref())
+    }
+
+    /// Downloads the object into the writer. Transient failures are retried with
+    /// an exponential backoff, at most `max_attempts` attempts are made in total.
+    fn get_object_with_retries(
+        store: &S,
+        object_path: &str,
+        writer: &mut (impl Write + Send),
+        max_attempts: usize,
+    ) -> Result<(), ReadError> {
+        let mut backoff = Self::GET_RETRY_INITIAL_BACKOFF;
+        let mut bytes_delivered = 0;
+        let mut attempt = 1;
+        loop {
+            let mut skipping_writer = SkippingWriter::new(&mut *writer, bytes_delivered);
+            let result = store.get_object_to_writer(object_path, &mut skipping_writer);
+            bytes_delivered = bytes_delivered.max(skipping_writer.position());
+
+            // If the data can't be passed on, e.g. the reader has closed the pipe,
+            // another attempt fails in the same way
+            let error = match result {
+                Ok(()) => return Ok(()),
+                Err(e) if !skipping_writer.inner_failed() && S::is_retriable_error(&e) => e,
+                Err(e) => return Err(e),
+            };
+            if attempt >= max_attempts {
+                return Err(error);
+            }
+
+            warn!(
+                "Failed to download {} {object_path} (attempt {attempt} of {max_attempts}): {error}. Retrying",
+                S::OBJECT_KIND
+            );
+            let jitter = rand::thread_rng().gen_range(0.5..1.5);
+            sleep(backoff.mul_f64(jitter));
+            backoff = (backoff * 2).min(Self::GET_RETRY_MAX_BACKOFF);
+            attempt += 1;
+        }
+    }
+
+    /// Starts downloading the object from `store` in a separate thread. The
+    /// contents can be read from the returned pipe.
+    pub fn stream_object_from_store(
+        store: S,
+        object_path_ref: &str,
+        max_get_attempts: usize,
+    ) -> (CurrentlyProcessedS3Object, PipeReader) {
+        let object_path = object_path_ref.to_string();
+
+        let (pipe_reader, mut pipe_writer) = pipe::pipe();
+        let loader_thread = thread::Builder::new()
+            .name(format!("pathway:object_get-{object_path_ref}"))
+            .spawn(move || {
+                Self::get_object_with_retries(
+                    &store,
+                    &object_path,
+                    &mut pipe_writer,
+                    max_get_attempts,
+                )
+            })
+            .expect("object download thread creation failed");
+
+        (
+            CurrentlyProcessedS3Object {
+                loader_thread,
+                path: Arc::new(object_path_ref.to_string()),
+                last_modified: None,
+            },
+            pipe_reader,
+        )
+    }
+
+    fn start_object_download(
+        &self,
+        object_path_ref: &str,
+        last_modified: Option<i64>,
+    ) -> (CurrentlyProcessedS3Object, PipeReader) {
+        let (mut object, pipe_reader) = Self::stream_object_from_store(
+            self.store.deep_copy(),
+            object_path_ref,
+            self.max_get_attempts,
+        );
+        object.last_modified = last_modified;
+        (object, pipe_reader)
+    }
+
+    pub(crate) fn stream_object_from_path(
+        &mut self,
+        object_path_ref: &str,
+        last_modified: Option<i64>,
+    ) -> PipeReader {
+        let (current_object, pipe_reader) =
+            self.start_object_download(object_path_ref, last_modified);
+        self.current_object = Some(current_object);
+        pipe_reader
+    }
+
+    /// Starts the downloads of the pending objects that go next in the reading
+    /// order, until `queue_length` of them are queued.
+    fn prefetch_pending_objects(&mut self, queue_length: usize) {
+        // The pending objects are kept in the reading order, regardless of the
+        // listing pages they came from, so the first one goes next
+        while self.prefetched_objects.len() < queue_length {
+            let Some(PendingS3Object {
+                last_modified, key, ..
+            }) = self.pending_objects.pop_first()
+            else {
+                break;
+            };
+            if self.processed_objects.contains(&key, last_modified) {
+                continue;
+            }
+            let prefetched_object = self.start_object_download(&key, Some(last_modified));
+            self.prefetched_objects.push_back(prefetched_object);
+            self.processed_objects.insert(key, last_modified);
+        }
+    }
+
+    /// Finishes the download of the current object and starts streaming the next
+    /// one. Returns the pipe to read the next object from, or `None` if there are
+    /// no more objects for now. The current object must be read in full before.
+    pub fn stream_next_object(&mut self) -> Result<Option<PipeReader>, ReadError> {
+        if let Some(state) = self.current_object.take() {
+            state.finalize()?;
+        }
+
+        // The objects already downloading go first, so the listing is needed only
+        // to refill the queue
+        if self.prefetched_objects.is_empty() {
+            self.list_new_objects()?;
+            self.prefetch_pending_objects(1);
+        }
+
+        let Some((current_object, pipe_reader)) = self.prefetched_objects.pop_front() else {
+            return Ok(None);
+        };
+        self.current_object = Some(current_object);
+        // The current object counts as one of the objects in flight
+        self.prefetch_pending_objects(self.max_in_flight_objects - 1);
+        Ok(Some(pipe_reader))
+    }
+
+    /// Marks the objects preceding `path` as processed. Returns the modification
+    /// time the object is resumed with, or `None` if the object itself is no longer
+    /// present in the store.
+    pub(crate) fn seek_to_object(
+        &mut self,
+        path: &str,
+        last_modified: Option<i64>,
+    ) -> Result<Option<i64>, ReadError> {
+        // The downloads that were started in advance are abandoned: their threads
+        // stop once they fail to write into the dropped pipes
+        self.prefetched_objects.clear();
+        self.processed_objects.reset(None);
+        self.list_all_objects()?;
+
+        let current_object_last_modified = self
+            .pending_objects
+            .iter()
+            .find_map(|object| (object.key == path).then_some(object.last_modified));
+        // The modification time saved in the offset is preferred, since the object
+        // may have been overwritten or deleted after it was read. The objects that
+        // precede it in the reading order, as well as the object itself, are processed
+        let threshold_modification_time = last_modified.or(current_object_last_modified);
+        if let Some(threshold_modification_time) = threshold_modification_time {
+            self.processed_objects
+                .reset(Some((threshold_modification_time, path.to_string())));
+            self.processed_objects
+                .insert(path.to_string(), threshold_modification_time);
+        }
+
+        Ok(current_object_last_modified.and(threshold_modification_time))
+    }
+
+    fn require_current_object(&self) -> Result<&CurrentlyProcessedS3Object, ReadError> {
+        self.current_object.as_ref().ok_or_else(|| {
+            ReadError::InconsistentState("no object is being read by the scanner".to_string())
+        })
+    }
+
+    pub(crate) fn require_current_object_path(&self) -> Result<Arc<String>, ReadError> {
+        Ok(self.require_current_object()?.path.clone())
+    }
+
+    pub(crate) fn require_current_object_last_modified(&self) -> Result<Option<i64>, ReadError> {
+        Ok(self.require_current_object()?.last_modified)
+    }
+}
+
+/// Interval between the scans of an S3 bucket. It grows exponentially while the
+/// scans find nothing new and returns to the base value once an object appears.
+pub(crate) struct S3PollingInterval {
+    base: Duration,
+    current: Duration,
+}
+
+impl S3PollingInterval {
+    const DEFAULT: Duration = Duration::from_secs(10);
+    const MAX: Duration = Duration::from_secs(300);
+
+    pub(crate) fn new(base: Option<Duration>) -> Self {
+        let base = base.unwrap_or(Self::DEFAULT);
+        Self {
+            base,
+            current: base,
+        }
+    }
+
+    pub(crate) fn sleep(&mut self, shutdown_signal: &ShutdownSignal) {
+        shutdown_signal.sleep(self.current);
+        self.current = (self.current * 2).min(Self::MAX.max(self.base));
+    }
+
+    fn reset(&mut self) {
+        self.current = self.base;
+    }
+}
+
+/// A CSV object, which starts with its header. The header is read when the object is
+/// opened, so it's never returned as a data record. The byte positions are counted
+/// from the beginning of the object.
+pub struct CsvObjectReader<R: Read> {
+    csv_reader: csv::Reader<R>,
+    header: Option<Vec<String>>,
+}
+
+impl<R: Read> CsvObjectReader<R> {
+    pub fn new(parser_builder: &csv::ReaderBuilder, reader: R) -> Result<Self, ReadError> {
+        let mut csv_reader = parser_builder.from_reader(reader);
+        let mut header_record = csv::StringRecord::new();
+        let header = if csv_reader.read_record(&mut header_record)? {
+            Some(
+                header_record
+                    .iter()
+                    .map(std::string::ToString::to_string)
+                    .collect(),
+            )
+        } else {
+            None
+        };
+        Ok(Self { csv_reader, header })
+    }
+
+    /// The header of the object or `None` if the object is empty.
+    pub fn header(&self) -> Option<&[String]> {
+        self.header.as_deref()
+    }
+
+    /// The byte position right after the last read record, the header included.
+    pub fn position(&self) -> u64 {
+        self.csv_reader.position().byte()
+    }
+
+    pub fn next_record(&mut self) -> Result<Option<Vec<String>>, ReadError> {
+        let mut record = csv::StringRecord::new();
+        if !self.csv_reader.read_record(&mut record)? {
+            return Ok(None);
+        }
+        Ok(Some(
+            record
+                .iter()
+                .map(std::string::ToString::to_string)
+                .collect(),
+        ))
+    }
+
+    /// Skips the records until the position reaches `bytes_offset` and returns the
+    /// position where the skipping stopped.
+    pub fn skip_to(&mut self, bytes_offset: u64) -> Result<u64, ReadError> {
+        let mut byte_record = csv::ByteRecord::new();
+        while self.position() < bytes_offset
+            && self.csv_reader.read_byte_record(&mut byte_record)?
+        {}
+        Ok(self.position())
+    }
+}
+
+pub struct S3CsvReader {
+    s3_scanner: ObjectScanner<S3Scanner>,
+    poll_new_objects: bool,
+    poll_interval: S3PollingInterval,
+
+    parser_builder: csv::ReaderBuilder,
+    object_reader: Option<CsvObjectReader<PipeReader>>,
+
+    persistent_id: Option<PersistentId>,
+    deferred_read_result: Option<ReadResult>,
+    total_entries_read: u64,
+    max_consecutive_errors: usize,
+    last_activity: Option<Instant>,
+    shutdown_signal: ShutdownSignal,
+    idle_timeout: Option<Duration>,
+    has_header: bool,
+    discovered_header: Option<Vec<String>>,
+
+    // If the schema is given, the entries are checked against it as they are read.
+    // The header of the current object maps the columns to the schema fields
+    schema: Option<HashMap<String, InnerSchemaField>>,
+}
+
+impl S3CsvReader {
+    /// Creates a reader of the CSV objects under `objects_prefix`. If `schema` is
+    /// given, the tokens of the columns named as its fields must be parsable into the
+    /// declared types, otherwise the entry is rejected with an error.
+    ///
+    /// If `has_header` is set, the header of each object isn't emitted as an entry,
+    /// and the first header read is available via [`S3CsvReader::discovered_header`].
+    ///
+    /// The `max_in_flight_objects` limit and the `max_get_attempts` of each object
+    /// are passed to [`ObjectScanner::new`]. The attempts default to
+    /// [`ObjectStore::DEFAULT_MAX_GET_ATTEMPTS`].
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        bucket: S3Bucket,
+        objects_prefix: impl Into<String>,
+        object_pattern: &str,
+        object_ordering: ObjectOrdering,
+        parser_builder: csv::ReaderBuilder,
+        poll_new_objects: bool,
+        persistent_id: Option<PersistentId>,
+        poll_interval: Option<Duration>,
+        max_consecutive_errors: usize,
+        schema: Option<HashMap<String, InnerSchemaField>>,
+        has_header: bool,
+        max_in_flight_objects: Option<usize>,
+        max_get_attempts: Option<usize>,
+    ) -> Result<S3CsvReader, ReadError> {
+        Ok(S3CsvReader {
+            s3_scanner: ObjectScanner::new(
+                S3Scanner::new(bucket, objects_prefix),
+                object_pattern,
+                object_ordering,
+                max_get_attempts.unwrap_or(S3Scanner::DEFAULT_MAX_GET_ATTEMPTS),
+                max_in_flight_objects,
+            )?,
+            poll_new_objects,
+            poll_interval: S3PollingInterval::new(poll_interval),
+
+            parser_builder,
+            object_reader: None,
+
+            persistent_id,
+            deferred_read_result: None,
+            total_entries_read: 0,
+            max_consecutive_errors,
+            last_activity: None,
+            shutdown_signal: ShutdownSignal::default(),
+            idle_timeout: None,
+            has_header,
+            discovered_header: None,
+
+            schema,
+        })
+    }
+
+    /// Creates a reader of the CSV objects under `objects_prefix`, which parses them
+    /// according to `dialect`. Otherwise it's the same as [`S3CsvReader::new`].
+    #[allow(clippy::too_many_arguments)]
+    pub fn with_dialect(
+        bucket: S3Bucket,
+        objects_prefix: impl Into<String>,
+        object_pattern: &str,
+        object_ordering: ObjectOrdering,
+        dialect: CsvDialect,
+        poll_new_objects: bool,
+        persistent_id: Option<PersistentId>,
+        poll_interval: Option<Duration>,
+        max_consecutive_errors: usize,
+        schema: Option<HashMap<String, InnerSchemaField>>,
+        has_header: bool,
+        max_in_flight_objects: Option<usize>,
+        max_get_attempts: Option<usize>,
+    ) -> Result<S3CsvReader, ReadError> {
+        Self::new(
+            bucket,
+            objects_prefix,
+            object_pattern,
+            object_ordering,
+            dialect.parser_builder(),
+            poll_new_objects,
+            persistent_id,
+            poll_interval,
+            max_consecutive_errors,
+            schema,
+            has_header,
+            max_in_flight_objects,
+            max_get_attempts,
+        )
+    }
+
+    /// The header of the first object read, if the objects are declared to have
+    /// headers.
+    pub fn discovered_header(&self) -> Option<&[String]> {
+        self.discovered_header.as_deref()
+    }
+
+    /// Starts the next object. Unless the objects are declared to have headers, its
+    /// header is passed to the parser right after the new source is announced, and
+    /// isn't counted as a read entry.
+    fn stream_next_object(&mut self) -> Result<bool, ReadError> {
+        if let Some(pipe_reader) = self.s3_scanner.stream_next_object()? {
+            let object_reader = CsvObjectReader::new(&self.parser_builder, pipe_reader)?;
+            let offset = (
+                OffsetKey::Empty,
+                OffsetValue::S3ObjectPositionV2 {
+                    total_entries_read: self.total_entries_read,
+                    path: self.s3_scanner.require_current_object_path()?,
+                    last_modified: self.s3_scanner.require_current_object_last_modified()?,
+                    bytes_offset: object_reader.position(),
+                },
+            );
+            self.defer_header(&object_reader, offset);
+            self.object_reader = Some(object_reader);
+            self.poll_interval.reset();
+            Ok(true)
+        } else {
+            Ok(false)
+        }
+    }
+
+    fn defer_header(&mut self, object_reader: &CsvObjectReader<PipeReader>, offset: Offset) {
+        if self.has_header {
+            if self.discovered_header.is_none() {
+                self.discovered_header = object_reader.header().map(<[String]>::to_vec);
+            }
+            return;
+        }
+        self.deferred_read_result = object_reader.header().map(|header| {
+            ReadResult::Data(
+                ReaderContext::from_tokenized_entries(
+                    DataEventType::Insert, // Currently no deletions for S3
+                    header.to_vec(),
+                ),
+                offset,
+            )
+        });
+    }
+
+    /// Checks that the tokens of the columns named in `header` as the schema fields
+    /// are parsable into the types of these fields.
+    pub fn check_tokens(
+        schema: &HashMap<String, InnerSchemaField>,
+        header: &[String],
+        tokens: &[String],
+    ) -> Result<(), DynError> {
+        for (name, token) in header.iter().zip(tokens) {
+            if let Some(schema_field) = schema.get(name) {
+                schema_field.parse_token(name, token)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Checks the tokens of the entry against the schema.
+    fn validate_tokens(&self, tokens: &[String]) -> Result<(), ReadError> {
+        let Some(schema) = &self.schema else {
+            return Ok(());
+        };
+        let Some(header) = self
+            .object_reader
+            .as_ref()
+            .and_then(CsvObjectReader::header)
+        else {
+            return Ok(());
+        };
+        match Self::check_tokens(schema, header, tokens) {
+            Ok(()) => Ok(()),
+            Err(error) => Err(ReadError::SchemaNotSatisfied {
+                path: self.s3_scanner.require_current_object_path()?.to_string(),
+                entry: self.total_entries_read,
+                raw_entry: serde_json::to_vec(tokens)?,
+                error,
+            }),
+        }
+    }
+}
+
+impl Reader for S3CsvReader {
+    fn seek(&mut self, frontier: &OffsetAntichain) -> Result<(), ReadError> {
+        let Some(SeekPosition {
+            offset_value,
+            total_entries_read,
+            path: path_arc,
+            bytes_offset,
+            last_modified,
+        }) = extract_seek_position(frontier, SeekOffsetKind::S3ObjectPosition)
+        else {
+            return Ok(());
+        };
+
+        let path = path_arc.to_string_lossy().into_owned();
+
+        let Some(last_modified) = self.s3_scanner.seek_to_object(&path, last_modified)? else {
+            warn!("S3 object {path} is no longer present, proceeding to the next object");
+            self.total_entries_read = total_entries_read;
+            return Ok(());
+        };
+        let pipe_reader = self
+            .s3_scanner
+            .stream_object_from_path(&path, Some(last_modified));
+        let mut object_reader = CsvObjectReader::new(&self.parser_builder, pipe_reader)?;
+        if object_reader.header().is_none() {
+            error!("Empty S3 object, nothing to rewind");
+            return Ok(());
+        }
+        self.defer_header(&object_reader, (OffsetKey::Empty, offset_value.clone()));
+
+        let current_offset = object_reader.skip_to(bytes_offset)?;
+        if current_offset != bytes_offset {
+            error!("Inconsistent bytes position in rewinded CSV object: expected {current_offset}, got {bytes_offset}");
+        }
+
+        self.total_entries_read = total_entries_read;
+        self.object_reader = Some(object_reader);
+
+        Ok(())
+    }
+
+    fn read(&mut self) -> Result<ReadResult, ReadError> {
+        if let Some(deferred_read_result) = self.deferred_read_result.take() {
+            return Ok(deferred_read_result);
+        }
+
+        let read_started_at = Instant::now();
+        loop {
+            match &mut self.object_reader {
+                Some(object_reader) => {
+                    if let Some(tokens) = object_reader.next_record()? {
+                        self.total_entries_read += 1;
+
+                        let offset = (
+                            OffsetKey::Empty,
+                            OffsetValue::S3ObjectPositionV2 {
+                                total_entries_read: self.total_entries_read,
+                                path: self.s3_scanner.require_current_object_path()?,
+                                last_modified: self
+                                    .s3_scanner
+                                    .require_current_object_last_modified()?,
+                                bytes_offset: object_reader.position(),
+                            },
+                        );
+
+                        self.last_activity = Some(Instant::now());
+                        self.validate_tokens(&tokens)?;
+                        return Ok(ReadResult::Data(
+                            ReaderContext::from_tokenized_entries(DataEventType::Insert, tokens),
+                            offset,
+                        ));
+                    }
+                    if self.stream_next_object()? {
+                        // No metadata is currently provided by S3 scanner
+                        return Ok(ReadResult::NewSource(None));
+                    }
+                }
+                None => {
+                    if self.stream_next_object()? {
+                        // No metadata is currently provided by S3 scanner
+                        return Ok(ReadResult::NewSource(None));
+                    }
+                }
+            }
+
+            if self.poll_new_objects && !self.shutdown_signal.is_requested() {
+                if is_idle_timeout_reached(self.idle_timeout, read_started_at) {
+                    return Ok(ReadResult::FinishedSource {
+                        commit_allowed: true,
+                    });
+                }
+                self.poll_interval.sleep(&self.shutdown_signal);
+            } else {
+                return Ok(ReadResult::Finished);
+            }
+        }
+    }
+
+    fn storage_type(&self) -> StorageType {
+        StorageType::S3Csv
+    }
+
+    fn max_allowed_consecutive_errors(&self) -> usize {
+        self.max_consecutive_errors
+    }
+
+    fn last_activity(&self) -> Option<Instant> {
+        self.last_activity
+    }
+
+    fn persistent_id(&self) -> Option<PersistentId> {
+        self.persistent_id
+    }
+
+    fn update_persistent_id(&mut self, persistent_id: Option<PersistentId>) {
+        self.persistent_id = persistent_id;
+    }
+
+    fn set_shutdown_signal(&mut self, signal: ShutdownSignal) {
+        self.shutdown_signal = signal;
+    }
+
+    fn set_idle_timeout(&mut self, timeout: Option<Duration>) {
+        self.idle_timeout = timeout;
+    }
+}
+
+pub struct S3GenericReader {
+    s3_scanner: ObjectScanner<S3Scanner>,
+    poll_new_objects: bool,
+    poll_interval: S3PollingInterval,
+    read_method: ReadMethod,
+    compression: Option<CompressionFormat>,
+    max_record_bytes: Option<usize>,
+
+    reader: Option<BufReader<Box<dyn Read + Send>>>,
+    persistent_id: Option<PersistentId>,
+    total_entries_read: u64,
+    current_bytes_read: u64,
+    deferred_read_result: Option<ReadResult>,
+    max_consecutive_errors: usize,
+    last_activity: Option<Instant>,
+    shutdown_signal: ShutdownSignal,
+    idle_timeout: Option<Duration>,
+}
+
+impl S3GenericReader {
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        bucket: S3Bucket,
+        objects_prefix: impl Into<String>,
+        object_pattern: &str,
+        object_ordering: ObjectOrdering,
+        poll_new_objects: bool,
+        persistent_id: Option<PersistentId>,
+        read_method: ReadMethod,
+        compression: Option<CompressionFormat>,
+        poll_interval: Option<Duration>,
+        max_consecutive_errors: usize,
+        max_in_flight_objects: Option<usize>,
+        max_record_bytes: Option<usize>,
+        max_get_attempts: Option<usize>,
+    ) -> Result<S3GenericReader, ReadError> {
+        Ok(S3GenericReader {
+            s3_scanner: ObjectScanner::new(
+                S3Scanner::new(bucket, objects_prefix),
+                object_pattern,
+                object_ordering,
+                max_get_attempts.unwrap_or(S3Scanner::DEFAULT_MAX_GET_ATTEMPTS),
+                max_in_flight_objects,
+            )?,
+            poll_new_objects,
+            poll_interval: S3PollingInterval::new(poll_interval),
+            read_method,
+            compression,
+            max_record_bytes,
+
+            reader: None,
+            persistent_id,
+            total_entries_read: 0,
+            current_bytes_read: 0,
+            deferred_read_result: None,
+            max_consecutive_errors,
+            last_activity: None,
+            shutdown_signal: ShutdownSignal::default(),
+            idle_timeout: None,
+        })
+    }
+
+    fn stream_next_object(&mut self) -> Result<bool, ReadError> {
+        if let Some(pipe_reader) = self.s3_scanner.stream_next_object()? {
+            let path = self.s3_scanner.require_current_object_path()?;
+            self.current_bytes_read = 0;
+            self.reader = Some(self.decoded_reader(pipe_reader, path.as_str())?);
+            self.poll_interval.reset();
+            Ok(true)
+        } else {
+            Ok(false)
+        }
+    }
+
+    fn decoded_reader(
+        &self,
+        pipe_reader: PipeReader,
+        path: &str,
+    ) -> Result<BufReader<Box<dyn Read + Send>>, ReadError> {
+        let decoder = CompressionFormat::resolve(self.compression, path).decoder(pipe_reader)?;
+        Ok(BufReader::new(decoder))
+    }
+}
+
+impl Reader for S3GenericReader {
+    fn seek(&mut self, frontier: &OffsetAntichain) -> Result<(), ReadError> {
+        let Some(SeekPosition {
+            total_entries_read,
+            path: path_arc,
+            bytes_offset,
+            last_modified,
+        }) = extract_seek_position(frontier, SeekOffsetKind::S3ObjectPosition)
+        else {
+            return Ok(());
+        };
+
+        let path = path_arc.to_string_lossy().into_owned();
+
+        let Some(last_modified) = self.s3_scanner.seek_to_object(&path, last_modified)? else {
+            warn!("S3 object {path} is no longer present, proceeding to the next object");
+            self.total_entries_read = total_entries_read;
+            return Ok(());
+        };
+        let pipe_reader = self
+            .s3_scanner
+            .stream_object_from_path(&path, Some(last_modified));
+
+        // The offset is counted in decompressed bytes, so compressed objects are
+        // replayed from the start in the same way as the plain ones
+        let mut reader = self.decoded_reader(pipe_reader, &path)?;
+        let mut bytes_read = 0;
+        while bytes_read < bytes_offset {
+            let mut current_line = Vec::new();
+            let len = self.read_method.read_next_bytes(
+                &mut reader,
+                &mut current_line,
+                self.max_record_bytes,
+            )?;
+            if len == 0 {
+                break;
+            }
+            bytes_read += len as u64;
+        }
+
+        // The offset holds the exact number of bytes consumed by the records, including
+        // their delimiters, so replaying the same records of an unchanged object always
+        // ends at the same position
+        if bytes_read != bytes_offset {
+            error!("S3 object {path} has changed since it was read: the records read before took {bytes_offset} bytes, but now they take {bytes_read}");
+        }
+
+        self.total_entries_read = total_entries_read;
+        self.current_bytes_read = bytes_read;
+        // The whole object is a single record for the full read, so there's nothing
+        // left to read in it
+        self.reader = if self.read_method == ReadMethod::Full {
+            None
+        } else {
+            Some(reader)
+        };
+
+        Ok(())
+    }
+
+    fn read(&mut self) -> Result<ReadResult, ReadError> {
+        if let Some(deferred_read_result) = self.deferred_read_result.take() {
+            return Ok(deferred_read_result);
+        }
+
+        let read_started_at = Instant::now();
+        loop {
+            match &mut self.reader {
+                Some(reader) => {
+                    let mut line = Vec::new();
+                    let len = self.read_method.read_next_bytes(
+                        reader,
+                        &mut line,
+                        self.max_record_bytes,
+                    )?;
+                    if len > 0 || self.read_method == ReadMethod::Full {
+                        self.total_entries_read += 1;
+                        self.current_bytes_read += len as u64;
+
+                        let offset = (
+                            OffsetKey::Empty,
+                            OffsetValue::S3ObjectPositionV2 {
+                                total_entries_read: self.total_entries_read,
+                                path: self.s3_scanner.require_current_object_path()?,
+                                last_modified: self
+                                    .s3_scanner
+                                    .require_current_object_last_modified()?,
+                                bytes_offset: self.current_bytes_read,
+                            },
+                        );
+
+                        if self.read_method == ReadMethod::Full {
+                            self.deferred_read_result = Some(ReadResult::FinishedSource {
+                                commit_allowed: true,
+                            });
+                            self.reader = None;
+                        }
+
+                        self.last_activity = Some(Instant::now());
+                        return Ok(ReadResult::Data(
+                            ReaderContext::from_raw_bytes(DataEventType::Insert, line), // Currently no deletions for S3
+                            offset,
+                        ));
+                    }
+
+                    if self.stream_next_object()? {
+                        // No metadata is currently provided by S3 scanner
+                        return Ok(ReadResult::NewSource(None));
+                    }
+                }
+                None => {
+                    if self.stream_next_object()? {
+                        // No metadata is currently provided by S3 scanner
+                        return Ok(ReadResult::NewSource(None));
+                    }
+                }
+            }
+
+            if self.poll_new_objects && !self.shutdown_signal.is_requested() {
+                if is_idle_timeout_reached(self.idle_timeout, read_started_at) {
+                    return Ok(ReadResult::FinishedSource {
+                        commit_allowed: true,
+                    });
+                }
+                self.poll_interval.sleep(&self.shutdown_signal);
+            } else {
+                return Ok(ReadResult::Finished);
+            }
+        }
+    }
+
+    fn storage_type(&self) -> StorageType {
+        StorageType::S3Lines
+    }
+
+    fn max_allowed_consecutive_errors(&self) -> usize {
+        self.max_consecutive_errors
+    }
+
+    fn last_activity(&self) -> Option<Instant> {
+        self.last_activity
+    }
+
+    fn persistent_id(&self) -> Option<PersistentId> {
+        self.persistent_id
+    }
+
+    fn update_persistent_id(&mut self, persistent_id: Option<PersistentId>) {
+        self.persistent_id = persistent_id;
+    }
+
+    fn set_shutdown_signal(&mut self, signal: ShutdownSignal) {
+        self.shutdown_signal = signal;
+    }
+
+    fn set_idle_timeout(&mut self, timeout: Option<Duration>) {
+        self.idle_timeout = timeout;
+    }
+}
@@ -24,7 +24,7 @@ use serde::{Deserialize, Serialize};
 
 use crate::connectors::data_storage::S3CommandName;
 use crate::connectors::data_storage::{
-    CurrentlyProcessedS3Object, ReadError, S3Scanner, WriteError,
+    CurrentlyProcessedS3Object, ObjectStore, ReadError, S3Scanner, WriteError,
 };
 use crate::deepcopy::DeepCopy;
 use crate::engine::{Key, Value};
@@ -373,6 +373,7 @@ impl ReadSnapshotEvent for S3SnapshotReader {
                         S3Scanner::stream_object_from_path_and_bucket(
                             &current_file_path,
                             self.bucket.deep_copy(),
+                            S3Scanner::DEFAULT_MAX_GET_ATTEMPTS,
                         );
 
                     if let Some(state) = self.current_state.take() {
@@ -406,6 +407,7 @@ impl ReadSnapshotEvent for S3SnapshotReader {
                 S3Scanner::stream_object_from_path_and_bucket(
                     &object_for_truncation,
                     self.bucket.deep_copy(),
+                    S3Scanner::DEFAULT_MAX_GET_ATTEMPTS,
                 );
             let mut writer = S3Writer::new(self.bucket.deep_copy(), &object_after_truncation)
                 .map_err(|(command, error)| ReadError::S3(command, error))?;
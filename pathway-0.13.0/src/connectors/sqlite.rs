@@ -0,0 +1,645 @@
+// Copyright © 2024 Pathway
+
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::mem::take;
+use std::str::from_utf8;
+use std::sync::Arc;
+use std::thread::sleep;
+use std::time::{Duration, Instant};
+
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine as _;
+use log::warn;
+use rusqlite::types::{
+    FromSql as FromSqlite, FromSqlError as FromSqliteError, FromSqlResult as FromSqliteResult,
+    Value as SqliteOwnedValue, ValueRef as SqliteValue,
+};
+use rusqlite::Connection as SqliteConnection;
+
+use crate::connectors::data_format::FormatterContext;
+use crate::connectors::data_storage::{
+    is_idle_timeout_reached, DataEventType, ReadError, ReadResult, Reader, ReaderContext,
+    ShutdownSignal, StorageType, ValuesMap, ValuesMapColumns, WriteError, Writer,
+};
+use crate::connectors::offset::EMPTY_OFFSET;
+use crate::connectors::{Offset, OffsetKey, OffsetValue};
+use crate::engine::Value;
+use crate::persistence::frontier::OffsetAntichain;
+use crate::persistence::metadata_backends::MetadataBackend;
+use crate::persistence::PersistentId;
+
+impl FromSqlite for Value {
+    /// Convert raw `SQLite` field into one of internal value types
+    /// There are only five supported types: null, integer, real, text, blob
+    /// See also: <https://www.sqlite.org/datatype3.html>
+    fn column_result(value: SqliteValue<'_>) -> FromSqliteResult<Self> {
+        match value {
+            SqliteValue::Null => Ok(Value::None),
+            SqliteValue::Integer(val) => Ok(Value::Int(val)),
+            SqliteValue::Real(val) => Ok(Value::Float(val.into())),
+            SqliteValue::Text(val) => {
+                let parsed_string =
+                    from_utf8(val).map_err(|e| FromSqliteError::Other(Box::new(e)))?;
+                Ok(Value::String(parsed_string.into()))
+            }
+            SqliteValue::Blob(val) => Ok(Value::Bytes(val.into())),
+        }
+    }
+}
+
+const SQLITE_DATA_VERSION_PRAGMA: &str = "data_version";
+
+/// Reads a table and emits the changes between its consecutive versions.
+///
+/// `SQLite` keeps no log of changes, so the persistence doesn't replay the history.
+/// Instead, if a persistent id is set, the state of the table after each batch of
+/// changes is saved in the reader state storage under the number of the batch, and
+/// only this number is kept in the offsets. On seek, the state of the committed
+/// batch is restored and the table is compared against it, so only the changes made
+/// since then are emitted. The states of the earlier batches are removed once a
+/// later one is committed. The data version isn't persisted, since it's only
+/// comparable within the same connection.
+///
+/// Only the projected columns are selected, and if a filter is given, only the rows
+/// satisfying it are kept in the state. A row that stops satisfying the filter is
+/// reported as deleted.
+///
+/// If a tracking column is given, its value must strictly increase on every change
+/// of a row, like an integer version or an `updated_at` timestamp. Then only the rows
+/// with the value greater than the largest one seen so far are fetched, and the
+/// deletions are detected by checking which rowids are still present. Otherwise,
+/// the whole table is read and compared with the state on every change.
+pub struct SqliteReader {
+    connection: SqliteConnection,
+    table_name: String,
+    column_names: Arc<ValuesMapColumns>,
+    filter: Option<String>,
+    tracking_column: Option<String>,
+    persistent_id: Option<PersistentId>,
+    state_storage: Option<Box<dyn MetadataBackend>>,
+
+    last_saved_data_version: Option<i64>,
+    last_seen_tracking_value: Option<Value>,
+    stored_state: HashMap<i64, ValuesMap>,
+    queued_updates: VecDeque<ReadResult>,
+    snapshots_taken: u64,
+    idle_timeout: Option<Duration>,
+    shutdown_signal: ShutdownSignal,
+}
+
+impl SqliteReader {
+    pub fn new(
+        connection: SqliteConnection,
+        table_name: String,
+        column_names: Vec<String>,
+        projection: Option<Vec<String>>,
+        filter: Option<String>,
+        tracking_column: Option<String>,
+        persistent_id: Option<PersistentId>,
+    ) -> Result<Self, ReadError> {
+        let column_names = match projection {
+            Some(projection) => {
+                if let Some(unknown_column) =
+                    projection.iter().find(|name| !column_names.contains(name))
+                {
+                    return Err(ReadError::UnknownProjectedColumn(unknown_column.clone()));
+                }
+                projection
+            }
+            None => column_names,
+        };
+        Ok(Self {
+            connection,
+            table_name,
+            column_names: Arc::new(ValuesMapColumns::new(column_names)),
+            filter,
+            tracking_column,
+            persistent_id,
+            state_storage: None,
+
+            last_saved_data_version: None,
+            last_seen_tracking_value: None,
+            queued_updates: VecDeque::new(),
+            stored_state: HashMap::new(),
+            snapshots_taken: 0,
+            idle_timeout: None,
+            shutdown_signal: ShutdownSignal::default(),
+        })
+    }
+
+    /// Saves the current state under the next batch number and returns the offset
+    /// that refers to it.
+    fn snapshot_offset(&mut self) -> Result<Offset, ReadError> {
+        self.snapshots_taken += 1;
+        if let Some(state_storage) = &mut self.state_storage {
+            let state = Self::ordered_state(&self.stored_state, &self.column_names);
+            let serialized_state = bincode::serialize(&state).map_err(|e| *e)?;
+            state_storage.put_value(
+                &self.snapshots_taken.to_string(),
+                &BASE64.encode(serialized_state),
+            )?;
+        }
+        Ok((
+            OffsetKey::Empty,
+            OffsetValue::SqliteSnapshot {
+                snapshots_taken: self.snapshots_taken,
+            },
+        ))
+    }
+
+    // The rows by rowid, with the values in the order of the columns
+    fn ordered_state(
+        stored_state: &HashMap<i64, ValuesMap>,
+        column_names: &ValuesMapColumns,
+    ) -> Vec<(i64, Vec<Value>)> {
+        let mut state: Vec<(i64, Vec<Value>)> = stored_state
+            .iter()
+            .map(|(rowid, values)| {
+                let values = column_names
+                    .names()
+                    .iter()
+                    .map(|name| values.get(name).cloned().unwrap_or(Value::None))
+                    .collect();
+                (*rowid, values)
+            })
+            .collect();
+        state.sort_unstable_by_key(|(rowid, _)| *rowid);
+        state
+    }
+
+    fn load_state(&self, snapshots_taken: u64) -> Result<HashMap<i64, ValuesMap>, ReadError> {
+        let Some(state_storage) = &self.state_storage else {
+            return Err(ReadError::StateNotFound(snapshots_taken));
+        };
+        let key = snapshots_taken.to_string();
+        if !state_storage.list_keys()?.contains(&key) {
+            return Err(ReadError::StateNotFound(snapshots_taken));
+        }
+        let serialized_state = BASE64
+            .decode(state_storage.get_value(&key)?.trim_end())
+            .map_err(|e| ReadError::InconsistentState(e.to_string()))?;
+        let state: Vec<(i64, Vec<Value>)> =
+            bincode::deserialize(&serialized_state).map_err(|e| *e)?;
+        state
+            .into_iter()
+            .map(|(rowid, values)| {
+                let values = ValuesMap::from_ordered(self.column_names.clone(), values)?;
+                Ok((rowid, values))
+            })
+            .collect()
+    }
+
+    /// Removes the saved states for which `is_obsolete` holds for the batch number.
+    fn remove_states(&self, is_obsolete: impl Fn(u64) -> bool) -> Result<(), ReadError> {
+        let Some(state_storage) = &self.state_storage else {
+            return Ok(());
+        };
+        for key in state_storage.list_keys()? {
+            match key.parse::<u64>() {
+                Ok(snapshots_taken) if is_obsolete(snapshots_taken) => {
+                    state_storage.remove_key(&key)?;
+                }
+                Ok(_) => {}
+                Err(_) => warn!("Unexpected key in the Sqlite reader state storage: {key}"),
+            }
+        }
+        Ok(())
+    }
+
+    /// Data version is required to check if there was an update in the database.
+    /// There are also hooks, but they only work for changes happened in the same
+    /// connection.
+    /// More details why hooks don't help here: <https://sqlite.org/forum/forumpost/3174b39eeb79b6a4>
+    pub fn data_version(&self) -> Result<i64, ReadError> {
+        let version: ::rusqlite::Result<i64> = self.connection.pragma_query_value(
+            Some(::rusqlite::DatabaseName::Main),
+            SQLITE_DATA_VERSION_PRAGMA,
+            |row| row.get(0),
+        );
+        Ok(version?)
+    }
+
+    /// Reads the table within a deferred transaction, so that all of the queries
+    /// see the same point-in-time snapshot even if the other connections commit
+    /// in the middle of the scan. The data version is taken before the transaction
+    /// begins, hence a commit that isn't in the snapshot changes it and is picked
+    /// up by the next scan.
+    fn load_table(&mut self) -> Result<(), ReadError> {
+        self.connection.execute_batch("BEGIN DEFERRED")?;
+        let result = self.scan_table();
+        // Nothing is written, so closing the read transaction can't lose anything
+        let end_result = self.connection.execute_batch("COMMIT");
+        result?;
+        Ok(end_result?)
+    }
+
+    fn scan_table(&mut self) -> Result<(), ReadError> {
+        let mut query = format!("SELECT {},_rowid_", self.column_names.names().join(","));
+        if let Some(tracking_column) = &self.tracking_column {
+            query += &format!(",{tracking_column}");
+        }
+        query += &format!(" FROM {}", self.table_name);
+        let mut conditions = Vec::new();
+        if let Some(filter) = &self.filter {
+            conditions.push(format!("({filter})"));
+        }
+        let last_seen_tracking_value = match (&self.tracking_column, &self.last_seen_tracking_value)
+        {
+            (Some(tracking_column), Some(last_seen)) => {
+                conditions.push(format!("{tracking_column} > ?1"));
+                Some(
+                    sqlite_value(last_seen)
+                        .expect("a value read from Sqlite must be convertible back"),
+                )
+            }
+            _ => None,
+        };
+        if !conditions.is_empty() {
+            query += &format!(" WHERE {}", conditions.join(" AND "));
+        }
+        let is_incremental = last_seen_tracking_value.is_some();
+
+        let mut statement = self.connection.prepare(&query)?;
+        let mut rows = statement.query(rusqlite::params_from_iter(last_seen_tracking_value))?;
+
+        let mut present_rowids = HashSet::new();
+        while let Some(row) = rows.next()? {
+            let rowid: i64 = row.get(self.column_names.len())?;
+            let mut values = Vec::with_capacity(self.column_names.len());
+            for column_idx in 0..self.column_names.len() {
+                values.push(row.get(column_idx)?);
+            }
+            if self.tracking_column.is_some() {
+                let tracking_value: Value = row.get(self.column_names.len() + 1)?;
+                if self.last_seen_tracking_value.as_ref() < Some(&tracking_value) {
+                    self.last_seen_tracking_value = Some(tracking_value);
+                }
+            }
+            let values = ValuesMap::from_ordered(self.column_names.clone(), values)?;
+            self.stored_state
+                .entry(rowid)
+                .and_modify(|current_values| {
+                    if current_values != &values {
+                        let key = vec![Value::Int(rowid)];
+                        self.queued_updates.push_back(ReadResult::Data(
+                            ReaderContext::from_diff(
+                                DataEventType::Delete,
+                                Some(key.clone()),
+                                take(current_values),
+                            ),
+                            EMPTY_OFFSET,
+                        ));
+                        self.queued_updates.push_back(ReadResult::Data(
+                            ReaderContext::from_diff(
+                                DataEventType::Insert,
+                                Some(key),
+                                values.clone(),
+                            ),
+                            EMPTY_OFFSET,
+                        ));
+                        current_values.clone_from(&values);
+                    }
+                })
+                .or_insert_with(|| {
+                    let key = vec![Value::Int(rowid)];
+                    self.queued_updates.push_back(ReadResult::Data(
+                        ReaderContext::from_diff(DataEventType::Insert, Some(key), values.clone()),
+                        EMPTY_OFFSET,
+                    ));
+                    values
+                });
+            present_rowids.insert(rowid);
+        }
+        drop(rows);
+        drop(statement);
+
+        // Only the changed rows were fetched, so the deleted ones are found
+        // by the separate pass over the rowids
+        if is_incremental {
+            present_rowids = self.load_present_rowids()?;
+        }
+
+        self.stored_state.retain(|rowid, values| {
+            if present_rowids.contains(rowid) {
+                true
+            } else {
+                let key = vec![Value::Int(*rowid)];
+                self.queued_updates.push_back(ReadResult::Data(
+                    ReaderContext::from_diff(DataEventType::Delete, Some(key), take(values)),
+                    EMPTY_OFFSET,
+                ));
+                false
+            }
+        });
+
+        if !self.queued_updates.is_empty() {
+            // The commits aren't allowed within the batch, so all of its entries
+            // can carry the state after the batch
+            if self.persistent_id.is_some() {
+                let offset = self.snapshot_offset()?;
+                for update in &mut self.queued_updates {
+                    if let ReadResult::Data(_, update_offset) = update {
+                        update_offset.clone_from(&offset);
+                    }
+                }
+            }
+            self.queued_updates.push_back(ReadResult::FinishedSource {
+                commit_allowed: true,
+            });
+        }
+
+        Ok(())
+    }
+
+    fn load_present_rowids(&self) -> Result<HashSet<i64>, ReadError> {
+        let mut query = format!("SELECT _rowid_ FROM {}", self.table_name);
+        if let Some(filter) = &self.filter {
+            query += &format!(" WHERE ({filter})");
+        }
+        let mut statement = self.connection.prepare(&query)?;
+        let rowids = statement
+            .query_map([], |row| row.get(0))?
+            .collect::<Result<_, _>>()?;
+        Ok(rowids)
+    }
+
+    fn wait_period() -> Duration {
+        Duration::from_millis(500)
+    }
+}
+
+impl Reader for SqliteReader {
+    fn seek(&mut self, frontier: &OffsetAntichain) -> Result<(), ReadError> {
+        let offset_value = frontier.get_offset(&OffsetKey::Empty);
+        let Some(OffsetValue::SqliteSnapshot { snapshots_taken }) = offset_value else {
+            if offset_value.is_some() {
+                warn!("Incorrect type of offset value in Sqlite frontier: {offset_value:?}");
+            }
+            return Ok(());
+        };
+
+        self.snapshots_taken = *snapshots_taken;
+        self.stored_state = self.load_state(*snapshots_taken)?;
+        // The states saved after the committed one weren't committed, and their
+        // numbers are taken again by the next batches
+        let snapshots_taken = *snapshots_taken;
+        self.remove_states(|other| other != snapshots_taken)?;
+        // Force the full reload, so that the table is compared with the restored state
+        self.last_saved_data_version = None;
+        self.last_seen_tracking_value = None;
+        self.queued_updates.clear();
+
+        Ok(())
+    }
+
+    fn read(&mut self) -> Result<ReadResult, ReadError> {
+        let read_started_at = Instant::now();
+        loop {
+            if let Some(queued_update) = self.queued_updates.pop_front() {
+                return Ok(queued_update);
+            }
+
+            let current_data_version = self.data_version()?;
+            if self.last_saved_data_version != Some(current_data_version) {
+                self.load_table()?;
+                self.last_saved_data_version = Some(current_data_version);
+                return Ok(ReadResult::NewSource(None));
+            }
+            if self.shutdown_signal.is_requested() {
+                return Ok(ReadResult::Finished);
+            }
+            if is_idle_timeout_reached(self.idle_timeout, read_started_at) {
+                return Ok(ReadResult::FinishedSource {
+                    commit_allowed: true,
+                });
+            }
+            // Sleep to avoid non-stop pragma requests of a table
+            // that did not change
+            sleep(Self::wait_period());
+        }
+    }
+
+    fn storage_type(&self) -> StorageType {
+        StorageType::Sqlite
+    }
+
+    fn persistent_id(&self) -> Option<PersistentId> {
+        self.persistent_id
+    }
+
+    fn update_persistent_id(&mut self, persistent_id: Option<PersistentId>) {
+        self.persistent_id = persistent_id;
+    }
+
+    fn set_idle_timeout(&mut self, timeout: Option<Duration>) {
+        self.idle_timeout = timeout;
+    }
+
+    fn set_shutdown_signal(&mut self, signal: ShutdownSignal) {
+        self.shutdown_signal = signal;
+    }
+
+    fn set_state_storage(&mut self, storage: Box<dyn MetadataBackend>) {
+        self.state_storage = Some(storage);
+    }
+
+    fn on_commit(&mut self, frontier: &OffsetAntichain) -> Result<(), ReadError> {
+        if let Some(OffsetValue::SqliteSnapshot { snapshots_taken }) =
+            frontier.get_offset(&OffsetKey::Empty)
+        {
+            let snapshots_taken = *snapshots_taken;
+            self.remove_states(|other| other < snapshots_taken)?;
+        }
+        Ok(())
+    }
+}
+
+/// Convert an internal value into one of the five `SQLite` storage classes.
+/// This is the reverse of `FromSqlite for Value`, extended with the lossless
+/// textual representations for pointers and JSON, and integers for booleans.
+fn sqlite_value(value: &Value) -> Result<SqliteOwnedValue, WriteError> {
+    match value {
+        Value::None => Ok(SqliteOwnedValue::Null),
+        Value::Bool(b) => Ok(SqliteOwnedValue::Integer((*b).into())),
+        Value::Int(i) => Ok(SqliteOwnedValue::Integer(*i)),
+        Value::Float(f) => Ok(SqliteOwnedValue::Real((*f).into())),
+        Value::String(s) => Ok(SqliteOwnedValue::Text(s.to_string())),
+        Value::Bytes(b) => Ok(SqliteOwnedValue::Blob(b.to_vec())),
+        Value::Pointer(p) => Ok(SqliteOwnedValue::Text(p.to_string())),
+        Value::Json(j) => Ok(SqliteOwnedValue::Text(j.to_string())),
+        _ => Err(WriteError::SqliteUnsupportedValue(value.clone())),
+    }
+}
+
+/// Quotes an identifier, so that the names with spaces or coinciding with the
+/// keywords can be used for the tables and columns.
+fn quote_sqlite_identifier(name: &str) -> String {
+    format!("\"{}\"", name.replace('"', "\"\""))
+}
+
+pub struct SqliteWriter {
+    connection: SqliteConnection,
+    column_names: Vec<String>,
+    key_column_indices: Vec<usize>,
+    buffer: Vec<FormatterContext>,
+
+    insert_query: String,
+    delete_query: String,
+}
+
+impl SqliteWriter {
+    /// Creates a writer to the table `table_name`, where the values are stored in the
+    /// columns `column_names`.
+    ///
+    /// If `key_column_names` is not empty, the positive updates are written as upserts
+    /// by this key and the deletions are done by it. Otherwise, the positive updates are
+    /// plain inserts and a deletion removes a single row with all values equal to the
+    /// deleted ones.
+    ///
+    /// Within a single time, the deletions are applied before the positive updates,
+    /// so that an update of a key, which consists of the deletion of the old row and
+    /// the upsert of the new one, never removes the new row.
+    pub fn new(
+        connection: SqliteConnection,
+        table_name: &str,
+        column_names: Vec<String>,
+        key_column_names: &[String],
+    ) -> Result<Self, WriteError> {
+        let mut key_column_indices = Vec::with_capacity(key_column_names.len());
+        for key_column_name in key_column_names {
+            let index = column_names
+                .iter()
+                .position(|name| name == key_column_name)
+                .ok_or_else(|| WriteError::SqliteUnknownKeyColumn(key_column_name.clone()))?;
+            key_column_indices.push(index);
+        }
+
+        let table_name = quote_sqlite_identifier(table_name);
+        let quoted_column_names: Vec<_> = column_names
+            .iter()
+            .map(|name| quote_sqlite_identifier(name))
+            .collect();
+        let quoted_key_column_names: Vec<_> = key_column_names
+            .iter()
+            .map(|name| quote_sqlite_identifier(name))
+            .collect();
+
+        let placeholders = vec!["?"; column_names.len()].join(",");
+        let mut insert_query = format!(
+            "INSERT INTO {table_name} ({}) VALUES ({placeholders})",
+            quoted_column_names.join(",")
+        );
+        let delete_query = if key_column_names.is_empty() {
+            let conditions: Vec<_> = quoted_column_names
+                .iter()
+                .map(|name| format!("{name} IS ?"))
+                .collect();
+            format!(
+                "DELETE FROM {table_name} WHERE _rowid_ IN (SELECT _rowid_ FROM {table_name} WHERE {} LIMIT 1)",
+                conditions.join(" AND ")
+            )
+        } else {
+            let updates: Vec<_> = quoted_column_names
+                .iter()
+                .map(|name| format!("{name}=excluded.{name}"))
+                .collect();
+            insert_query.push_str(&format!(
+                " ON CONFLICT({}) DO UPDATE SET {}",
+                quoted_key_column_names.join(","),
+                updates.join(",")
+            ));
+            let conditions: Vec<_> = quoted_key_column_names
+                .iter()
+                .map(|name| format!("{name}=?"))
+                .collect();
+            format!(
+                "DELETE FROM {table_name} WHERE {}",
+                conditions.join(" AND ")
+            )
+        };
+
+        Ok(Self {
+            connection,
+            column_names,
+            key_column_indices,
+            buffer: Vec::new(),
+
+            insert_query,
+            delete_query,
+        })
+    }
+
+    fn data_event_type(data: &FormatterContext, is_keyed: bool) -> DataEventType {
+        if data.diff < 0 {
+            DataEventType::Delete
+        } else if is_keyed {
+            DataEventType::Upsert
+        } else {
+            DataEventType::Insert
+        }
+    }
+
+    fn delete_params(&self, data: &FormatterContext) -> Result<Vec<SqliteOwnedValue>, WriteError> {
+        if self.key_column_indices.is_empty() {
+            data.values.iter().map(sqlite_value).collect()
+        } else {
+            self.key_column_indices
+                .iter()
+                .map(|index| sqlite_value(&data.values[*index]))
+                .collect()
+        }
+    }
+}
+
+impl Writer for SqliteWriter {
+    fn write(&mut self, data: FormatterContext) -> Result<(), WriteError> {
+        if data.values.len() != self.column_names.len() {
+            return Err(WriteError::SqliteColumnCountMismatch {
+                expected: self.column_names.len(),
+                got: data.values.len(),
+            });
+        }
+        self.buffer.push(data);
+        Ok(())
+    }
+
+    fn flush(&mut self, _forced: bool) -> Result<(), WriteError> {
+        if self.buffer.is_empty() {
+            return Ok(());
+        }
+
+        let is_keyed = !self.key_column_indices.is_empty();
+        let mut params = Vec::with_capacity(self.buffer.len());
+        for data in &self.buffer {
+            let event_type = Self::data_event_type(data, is_keyed);
+            let row_params = match event_type {
+                DataEventType::Insert | DataEventType::Upsert => data
+                    .values
+                    .iter()
+                    .map(sqlite_value)
+                    .collect::<Result<Vec<_>, _>>()?,
+                DataEventType::Delete => self.delete_params(data)?,
+            };
+            params.push((data.time, event_type, row_params));
+        }
+        // The sort is stable, so the order of the changes of the same kind is kept
+        params.sort_by_key(|(time, event_type, _)| (*time, *event_type != DataEventType::Delete));
+
+        let transaction = self.connection.transaction()?;
+        {
+            let mut insert_statement = transaction.prepare_cached(&self.insert_query)?;
+            let mut delete_statement = transaction.prepare_cached(&self.delete_query)?;
+            for (_, event_type, row_params) in params {
+                let statement = match event_type {
+                    DataEventType::Insert | DataEventType::Upsert => &mut insert_statement,
+                    DataEventType::Delete => &mut delete_statement,
+                };
+                statement.execute(rusqlite::params_from_iter(row_params))?;
+            }
+        }
+        transaction.commit()?;
+        self.buffer.clear();
+
+        Ok(())
+    }
+}
@@ -0,0 +1,145 @@
+// Copyright © 2024 Pathway
+
+use std::mem::take;
+use std::time::{Duration, Instant};
+
+use crate::connectors::data_storage::{ReadError, ReadResult, Reader, ShutdownSignal, StorageType};
+use crate::persistence::frontier::OffsetAntichain;
+use crate::persistence::PersistentId;
+
+/// A token bucket holding up to a second worth of tokens. The tokens may be taken
+/// beyond the ones available, then the debt has to be paid off before the next take.
+struct TokenBucket {
+    rate: f64,
+    tokens: f64,
+    refilled_at: Instant,
+}
+
+impl TokenBucket {
+    fn new(rate: f64) -> Self {
+        assert!(rate > 0.0, "the rate limit must be positive");
+        Self {
+            rate,
+            tokens: rate,
+            refilled_at: Instant::now(),
+        }
+    }
+
+    fn refill(&mut self) {
+        let now = Instant::now();
+        let elapsed = now.duration_since(self.refilled_at).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.rate).min(self.rate);
+        self.refilled_at = now;
+    }
+
+    /// The time left until the debt is paid off.
+    fn time_until_available(&mut self) -> Duration {
+        self.refill();
+        if self.tokens >= 0.0 {
+            Duration::ZERO
+        } else {
+            Duration::from_secs_f64(-self.tokens / self.rate)
+        }
+    }
+
+    #[allow(clippy::cast_precision_loss)]
+    fn take(&mut self, amount: usize) {
+        self.refill();
+        self.tokens -= amount as f64;
+    }
+}
+
+/// Reads from the inner reader, capping the number of entries read per second and,
+/// optionally, the number of their payload bytes per second. A burst of up to a
+/// second worth of entries is allowed, after which the reader sleeps before
+/// the `read` calls to keep up with the limits. Only the data entries count, and
+/// the parsed entries have no payload, so they aren't limited by the bytes.
+///
+/// Everything except `read` is delegated to the inner reader, so the wrapper
+/// doesn't change the offsets and the persistence of the inner one. The sleeping
+/// is interrupted by the shutdown signal, after which the inner reader is
+/// expected to finish.
+pub struct ThrottledReader {
+    inner: Box<dyn Reader>,
+    records_bucket: TokenBucket,
+    bytes_bucket: Option<TokenBucket>,
+    shutdown_signal: ShutdownSignal,
+}
+
+impl ThrottledReader {
+    /// Creates a throttled reader. The limits must be positive.
+    pub fn new(
+        inner: Box<dyn Reader>,
+        max_records_per_second: f64,
+        max_bytes_per_second: Option<f64>,
+    ) -> Self {
+        Self {
+            inner,
+            records_bucket: TokenBucket::new(max_records_per_second),
+            bytes_bucket: max_bytes_per_second.map(TokenBucket::new),
+            shutdown_signal: ShutdownSignal::default(),
+        }
+    }
+}
+
+impl Reader for ThrottledReader {
+    fn read(&mut self) -> Result<ReadResult, ReadError> {
+        let mut delay = self.records_bucket.time_until_available();
+        if let Some(bytes_bucket) = &mut self.bytes_bucket {
+            delay = delay.max(bytes_bucket.time_until_available());
+        }
+        if !delay.is_zero() {
+            self.shutdown_signal.sleep(delay);
+        }
+
+        let read_result = self.inner.read()?;
+        if let ReadResult::Data(context, _) = &read_result {
+            self.records_bucket.take(1);
+            if let Some(bytes_bucket) = &mut self.bytes_bucket {
+                bytes_bucket.take(context.payload_len());
+            }
+        }
+        Ok(read_result)
+    }
+
+    fn seek(&mut self, frontier: &OffsetAntichain) -> Result<(), ReadError> {
+        self.inner.seek(frontier)
+    }
+
+    fn update_persistent_id(&mut self, persistent_id: Option<PersistentId>) {
+        self.inner.update_persistent_id(persistent_id);
+    }
+
+    fn persistent_id(&self) -> Option<PersistentId> {
+        self.inner.persistent_id()
+    }
+
+    fn set_shutdown_signal(&mut self, signal: ShutdownSignal) {
+        self.shutdown_signal = signal.clone();
+        self.inner.set_shutdown_signal(signal);
+    }
+
+    fn set_idle_timeout(&mut self, timeout: Option<Duration>) {
+        self.inner.set_idle_timeout(timeout);
+    }
+
+    fn on_commit(&mut self, frontier: &OffsetAntichain) -> Result<(), ReadError> {
+        self.inner.on_commit(frontier)
+    }
+
+    fn storage_type(&self) -> StorageType {
+        self.inner.storage_type()
+    }
+
+    fn max_allowed_consecutive_errors(&self) -> usize {
+        self.inner.max_allowed_consecutive_errors()
+    }
+
+    fn last_activity(&self) -> Option<Instant> {
+        self.inner.last_activity()
+    }
+
+    fn is_healthy(&self) -> bool {
+        self.inner.is_healthy()
+    }
+}
@@ -0,0 +1,229 @@
+// Copyright © 2024 Pathway
+
+use std::time::{Duration, Instant};
+
+use futures::{SinkExt, StreamExt};
+use log::{info, warn};
+use rand::Rng;
+use tokio::runtime::Runtime as TokioRuntime;
+use tokio_tungstenite::tungstenite::Message as WebSocketMessage;
+use tokio_tungstenite::{connect_async, MaybeTlsStream, WebSocketStream};
+
+use crate::connectors::data_storage::{
+    DataEventType, ReadError, ReadResult, Reader, ReaderContext, ShutdownSignal, StorageType,
+};
+use crate::connectors::offset::EMPTY_OFFSET;
+use crate::connectors::OffsetKey;
+use crate::persistence::frontier::OffsetAntichain;
+use crate::persistence::PersistentId;
+
+type WebSocketConnection = WebSocketStream<MaybeTlsStream<tokio::net::TcpStream>>;
+
+/// Reads the frames of a WebSocket feed, emitting each text or binary frame as a
+/// separate entry.
+///
+/// The connection is reestablished with an exponential backoff whenever it's lost,
+/// and the subscription message, if any, is sent again after each connect. If the
+/// ping interval is set, a ping is sent after this long without frames from the
+/// server, and the connection is considered lost if nothing arrives during one more
+/// interval.
+///
+/// A WebSocket feed has no position to resume from, so the frames sent by the
+/// server while the reader wasn't connected are lost.
+pub struct WebSocketReader {
+    url: String,
+    subscription_message: Option<String>,
+    ping_interval: Option<Duration>,
+    connection: Option<WebSocketConnection>,
+    awaiting_pong: bool,
+    persistent_id: Option<PersistentId>,
+    last_activity: Option<Instant>,
+    shutdown_signal: ShutdownSignal,
+
+    // Declared last so that it's dropped after the connection that may use it
+    runtime: TokioRuntime,
+}
+
+impl WebSocketReader {
+    const RECONNECT_INITIAL_BACKOFF: Duration = Duration::from_millis(500);
+    const RECONNECT_MAX_BACKOFF: Duration = Duration::from_secs(30);
+
+    pub fn new(
+        url: String,
+        subscription_message: Option<String>,
+        ping_interval: Option<Duration>,
+        persistent_id: Option<PersistentId>,
+    ) -> Result<WebSocketReader, ReadError> {
+        let runtime = tokio::runtime::Builder::new_multi_thread()
+            .enable_all()
+            .build()?;
+        let mut reader = WebSocketReader {
+            url,
+            subscription_message,
+            ping_interval,
+            connection: None,
+            awaiting_pong: false,
+            persistent_id,
+            last_activity: None,
+            shutdown_signal: ShutdownSignal::default(),
+            runtime,
+        };
+        // The first connection is established eagerly, so that a wrong URL is
+        // reported on the start rather than retried forever
+        reader.connection = Some(reader.connect()?);
+        Ok(reader)
+    }
+
+    fn connect(&self) -> Result<WebSocketConnection, ReadError> {
+        self.runtime.block_on(async {
+            let (mut connection, _) = connect_async(self.url.as_str()).await?;
+            if let Some(subscription_message) = &self.subscription_message {
+                connection
+                    .send(WebSocketMessage::Text(subscription_message.clone()))
+                    .await?;
+            }
+            Ok::<_, ReadError>(connection)
+        })
+    }
+
+    /// Reconnects with the exponential backoff. Gives up once the shutdown is
+    /// requested, leaving the reader without a connection.
+    fn reconnect(&mut self) {
+        self.connection = None;
+        self.awaiting_pong = false;
+        let mut backoff = Self::RECONNECT_INITIAL_BACKOFF;
+        while !self.shutdown_signal.is_requested() {
+            match self.connect() {
+                Ok(connection) => {
+                    info!("Reconnected to WebSocket {}", self.url);
+                    self.connection = Some(connection);
+                    return;
+                }
+                Err(e) => {
+                    warn!(
+                        "Failed to reconnect to WebSocket {}: {e}. Retrying in {backoff:?}",
+                        self.url
+                    );
+                    let jitter = rand::thread_rng().gen_range(0.5..1.5);
+                    self.shutdown_signal.sleep(backoff.mul_f64(jitter));
+                    backoff = (backoff * 2).min(Self::RECONNECT_MAX_BACKOFF);
+                }
+            }
+        }
+    }
+}
+
+impl Reader for WebSocketReader {
+    fn seek(&mut self, frontier: &OffsetAntichain) -> Result<(), ReadError> {
+        if frontier.get_offset(&OffsetKey::Empty).is_some() {
+            warn!("WebSocket reader can't resume from a saved position, only the new frames will be read");
+        }
+        Ok(())
+    }
+
+    fn read(&mut self) -> Result<ReadResult, ReadError> {
+        loop {
+            if self.shutdown_signal.is_requested() {
+                return Ok(ReadResult::Finished);
+            }
+            let Some(connection) = &mut self.connection else {
+                self.reconnect();
+                continue;
+            };
+
+            // `None` means that nothing has arrived during the ping interval
+            let ping_interval = self.ping_interval;
+            let next_frame = self.shutdown_signal.block_on(&self.runtime, async {
+                match ping_interval {
+                    Some(ping_interval) => tokio::time::timeout(ping_interval, connection.next())
+                        .await
+                        .ok(),
+                    None => Some(connection.next().await),
+                }
+            });
+            let Some(next_frame) = next_frame else {
+                return Ok(ReadResult::Finished);
+            };
+
+            let message = match next_frame {
+                None if self.awaiting_pong => {
+                    warn!(
+                        "WebSocket {} didn't respond to ping, reconnecting",
+                        self.url
+                    );
+                    self.reconnect();
+                    continue;
+                }
+                None => {
+                    let ping = connection.send(WebSocketMessage::Ping(Vec::new()));
+                    if let Err(e) = self.runtime.block_on(ping) {
+                        warn!("Failed to ping WebSocket {}: {e}, reconnecting", self.url);
+                        self.reconnect();
+                    } else {
+                        self.awaiting_pong = true;
+                    }
+                    continue;
+                }
+                Some(None) => {
+                    warn!("WebSocket {} was closed, reconnecting", self.url);
+                    self.reconnect();
+                    continue;
+                }
+                Some(Some(Err(e))) => {
+                    warn!("WebSocket {} failed: {e}, reconnecting", self.url);
+                    self.reconnect();
+                    continue;
+                }
+                Some(Some(Ok(message))) => message,
+            };
+
+            self.awaiting_pong = false;
+            let payload = match message {
+                WebSocketMessage::Text(text) => text.into_bytes(),
+                WebSocketMessage::Binary(data) => data,
+                // The pings are answered by the connection itself
+                WebSocketMessage::Ping(_)
+                | WebSocketMessage::Pong(_)
+                | WebSocketMessage::Frame(_) => continue,
+                WebSocketMessage::Close(_) => {
+                    warn!(
+                        "WebSocket {} was closed by the server, reconnecting",
+                        self.url
+                    );
+                    self.reconnect();
+                    continue;
+                }
+            };
+
+            self.last_activity = Some(Instant::now());
+            return Ok(ReadResult::Data(
+                ReaderContext::from_raw_bytes(DataEventType::Insert, payload),
+                EMPTY_OFFSET,
+            ));
+        }
+    }
+
+    fn persistent_id(&self) -> Option<PersistentId> {
+        self.persistent_id
+    }
+
+    fn update_persistent_id(&mut self, persistent_id: Option<PersistentId>) {
+        self.persistent_id = persistent_id;
+    }
+
+    fn set_shutdown_signal(&mut self, signal: ShutdownSignal) {
+        self.shutdown_signal = signal;
+    }
+
+    fn storage_type(&self) -> StorageType {
+        StorageType::WebSocket
+    }
+
+    fn last_activity(&self) -> Option<Instant> {
+        self.last_activity
+    }
+
+    fn is_healthy(&self) -> bool {
+        self.connection.is_some()
+    }
+}
@@ -7,6 +7,10 @@ pub trait DeepCopy {
     fn deep_copy(&self) -> Self;
 }
 
+/// The copy has the same region, including a custom endpoint, path style flag,
+/// extra headers and query parameters, such as the server-side encryption ones, and
+/// request timeout, since they're cloned as is. Only the credentials get a lock of
+/// their own, so that the copies used by the other threads don't share it.
 impl DeepCopy for S3Bucket {
     fn deep_copy(&self) -> Self {
         let mut result = self.clone();
@@ -12,7 +12,7 @@ pub mod shard;
 
 use crate::connectors::adaptors::{GenericValues, ValuesSessionAdaptor};
 use crate::connectors::data_format::{Formatter, Parser};
-use crate::connectors::data_storage::{ReaderBuilder, Writer};
+use crate::connectors::data_storage::{ReaderBuilder, ShutdownSignal, Writer};
 use crate::connectors::monitoring::{ConnectorMonitor, ConnectorStats, OutputConnectorStats};
 use crate::connectors::snapshot::Event as SnapshotEvent;
 use crate::connectors::{read_persisted_state, ARTIFICIAL_TIME_ON_REWIND_START};
@@ -774,6 +774,7 @@ struct DataflowGraphInner<S: MaybeTotalScope> {
     persistence_config: Option<PersistenceManagerConfig>,
     worker_persistent_storage: SharedWorkerPersistentStorage,
     persisted_states_count: u64,
+    shutdown_signal: ShutdownSignal,
     config: Arc<Config>,
     terminate_on_error: bool,
     default_error_log: Option<ErrorLog>,
@@ -965,6 +966,7 @@ impl<S: MaybeTotalScope> DataflowGraphInner<S> {
         error_reporter: ErrorReporter,
         ignore_asserts: bool,
         persistence_config: Option<PersistenceManagerConfig>,
+        shutdown_signal: ShutdownSignal,
         config: Arc<Config>,
         terminate_on_error: bool,
         default_error_log: Option<ErrorLog>,
@@ -999,6 +1001,7 @@ impl<S: MaybeTotalScope> DataflowGraphInner<S> {
             persistence_config,
             worker_persistent_storage,
             persisted_states_count: 0,
+            shutdown_signal,
             config,
             terminate_on_error,
             default_error_log,
@@ -3302,6 +3305,7 @@ impl<S: MaybeTotalScope<MaybeTotalTimestamp = Timestamp>> DataflowGraphInner<S>
                 persistence_mode,
                 snapshot_access,
                 self.error_reporter.clone(),
+                self.shutdown_signal.clone(),
             )?;
 
             self.pollers.push(state.poller);
@@ -3450,10 +3454,23 @@ impl<S: MaybeTotalScope<MaybeTotalTimestamp = Timestamp>> DataflowGraphInner<S>
         }
         stats.on_batch_finished();
         data_sink.flush(false).map_err(DynError::from)?;
+        stats.on_rows_flushed(data_sink.rows_written_since_last_flush());
 
         Ok(())
     }
 
+    /// Finishes the sink once the output is over. Since it can't be asked about the
+    /// delivered rows afterwards, the rest of the output is flushed first, so that the
+    /// rows made durable by that flush are counted in the stats.
+    fn finish_output(
+        stats: &mut OutputConnectorStats,
+        mut data_sink: Box<dyn Writer>,
+    ) -> Result<(), DynError> {
+        data_sink.flush(true).map_err(DynError::from)?;
+        stats.on_rows_flushed(data_sink.rows_written_since_last_flush());
+        data_sink.finish().map_err(DynError::from)
+    }
+
     fn commit_output_time(
         stats: &mut OutputConnectorStats,
         t: Option<Timestamp>,
@@ -3527,19 +3544,32 @@ impl<S: MaybeTotalScope<MaybeTotalTimestamp = Timestamp>> DataflowGraphInner<S>
                                     &worker_persistent_storage,
                                 )?;
                             }
-                            Ok(OutputEvent::Commit(t)) => {
+                            Ok(OutputEvent::Commit(Some(t))) => {
+                                // The sink must confirm that the output is durable before
+                                // the time is committed in the persistent storage
+                                data_sink.on_commit(t).map_err(DynError::from)?;
+                                Self::commit_output_time(
+                                    &mut stats,
+                                    Some(t),
+                                    sink_id,
+                                    &worker_persistent_storage,
+                                );
+                            }
+                            Ok(OutputEvent::Commit(None)) => {
+                                // The output is over, so the sink is finished rather than
+                                // dropped, and a failure to deliver the rest is reported
+                                Self::finish_output(&mut stats, data_sink)?;
                                 Self::commit_output_time(
                                     &mut stats,
-                                    t,
+                                    None,
                                     sink_id,
                                     &worker_persistent_storage,
                                 );
-                                if t.is_none() {
-                                    data_sink.flush(true).map_err(DynError::from)?;
-                                    break Ok(());
-                                }
+                                break Ok(());
+                            }
+                            Err(mpsc::RecvError) => {
+                                break Self::finish_output(&mut stats, data_sink);
                             }
-                            Err(mpsc::RecvError) => break Ok(()),
                         }
                     },
                 )
@@ -4216,6 +4246,7 @@ impl<S: MaybeTotalScope> InnerDataflowGraph<S> {
             error_reporter,
             ignore_asserts,
             None,
+            ShutdownSignal::default(),
             config,
             terminate_on_error,
             default_error_log,
@@ -4800,6 +4831,7 @@ impl<S: MaybeTotalScope<MaybeTotalTimestamp = Timestamp>> OuterDataflowGraph<S>
         error_reporter: ErrorReporter,
         ignore_asserts: bool,
         persistence_config: Option<PersistenceManagerOuterConfig>,
+        shutdown_signal: ShutdownSignal,
         config: Arc<Config>,
         terminate_on_error: bool,
     ) -> Result<Self> {
@@ -4810,6 +4842,7 @@ impl<S: MaybeTotalScope<MaybeTotalTimestamp = Timestamp>> OuterDataflowGraph<S>
             error_reporter,
             ignore_asserts,
             persistence_config.map(|cfg| cfg.into_inner(worker_idx, total_workers)),
+            shutdown_signal,
             config,
             terminate_on_error,
             None,
@@ -5440,6 +5473,7 @@ pub fn run_with_new_dataflow_graph<R, R2>(
     #[allow(unused)] license: &License,
     telemetry_config: TelemetryConfig,
     terminate_on_error: bool,
+    shutdown_signal: ShutdownSignal,
 ) -> Result<Vec<R2>>
 where
     R: 'static,
@@ -5458,6 +5492,7 @@ where
     let (error_reporter, error_receiver) = ErrorReporter::create();
     let failed = Arc::new(AtomicBool::new(false));
     let failed_2 = failed.clone();
+    let shutdown_signal_2 = shutdown_signal.clone();
 
     let guards = execute(config.to_timely_config(), move |worker| {
         catch_unwind(AssertUnwindSafe(|| {
@@ -5489,6 +5524,7 @@ where
                     error_reporter.clone(),
                     ignore_asserts,
                     persistence_config.clone(),
+                    shutdown_signal.clone(),
                     config.clone(),
                     terminate_on_error,
                 )
@@ -5623,6 +5659,9 @@ where
     match res {
         Ok(()) => {}
         Err(error) => {
+            // Let the connector threads stop waiting for new data, so that they
+            // don't outlive the computation
+            shutdown_signal_2.request();
             failed_2.store(true, Ordering::SeqCst);
             for handle in guards.guards() {
                 handle.thread().unpark();
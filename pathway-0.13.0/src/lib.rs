@@ -13,11 +13,11 @@ pub mod deepcopy;
 pub mod engine;
 pub mod external_integration;
 pub mod persistence;
+mod pipe;
 pub mod python_api;
 
 mod env;
 mod fs_helpers;
 mod mat_mul;
-mod pipe;
 mod timestamp;
 
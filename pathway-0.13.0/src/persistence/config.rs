@@ -30,6 +30,7 @@ use crate::persistence::state::MetadataAccessor;
 use crate::persistence::{PersistentId, SharedSnapshotWriter};
 
 const STREAMS_DIRECTORY_NAME: &str = "streams";
+const READERS_DIRECTORY_NAME: &str = "readers";
 
 pub type ConnectorWorkerPair = (PersistentId, usize);
 
@@ -258,6 +259,34 @@ impl PersistenceManagerConfig {
         }
     }
 
+    /// Creates the key-value storage for the state of the reader with the given
+    /// persistent id that doesn't fit into the offsets. It's kept next to the
+    /// snapshots, and unlike them, isn't split by worker, since a persisted source is
+    /// read by a single worker.
+    pub fn create_reader_state_storage(
+        &self,
+        persistent_id: PersistentId,
+    ) -> Result<Box<dyn MetadataBackend>, MetadataBackendError> {
+        match &self.stream_storage {
+            StreamStorageConfig::Filesystem(root_path) => {
+                ensure_directory(root_path)?;
+                let readers_path = root_path.join(READERS_DIRECTORY_NAME);
+                ensure_directory(&readers_path)?;
+                Ok(Box::new(FilesystemKVStorage::new(
+                    &readers_path.join(persistent_id.to_string()),
+                )?))
+            }
+            StreamStorageConfig::S3 { bucket, root_path } => {
+                let state_path = format!(
+                    "{}/{READERS_DIRECTORY_NAME}/{persistent_id}",
+                    root_path.strip_suffix('/').unwrap_or(root_path)
+                );
+                Ok(Box::new(S3KVStorage::new(bucket.deep_copy(), &state_path)))
+            }
+            StreamStorageConfig::Mock(_) => Ok(Box::new(MockKVStorage {})),
+        }
+    }
+
     fn snapshot_writer_path(
         &self,
         root_path: &Path,
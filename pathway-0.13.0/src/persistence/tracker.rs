@@ -11,6 +11,7 @@ use crate::connectors::PersistenceMode;
 use crate::engine::{Timestamp, TotalFrontier};
 use crate::persistence::config::{PersistenceManagerConfig, ReadersQueryPurpose};
 use crate::persistence::metadata_backends::Error as MetadataBackendError;
+use crate::persistence::metadata_backends::MetadataBackend;
 use crate::persistence::state::MetadataAccessor;
 use crate::persistence::{PersistentId, SharedSnapshotWriter};
 
@@ -189,6 +190,13 @@ impl WorkerPersistentStorage {
         )
     }
 
+    pub fn create_reader_state_storage(
+        &self,
+        persistent_id: PersistentId,
+    ) -> Result<Box<dyn MetadataBackend>, MetadataBackendError> {
+        self.config.create_reader_state_storage(persistent_id)
+    }
+
     pub fn create_snapshot_writer(
         &mut self,
         persistent_id: PersistentId,
@@ -11,10 +11,13 @@ use std::io::{Error};
 #[cfg(unix)]
 use std::os::fd::{AsFd, AsRawFd, OwnedFd};
 
-use std::os::windows::io::{AsHandle, AsRawHandle, FromRawHandle, OwnedHandle};
+use std::os::windows::io::{AsHandle, AsRawHandle, BorrowedHandle, FromRawHandle, OwnedHandle};
 
 use cfg_if::cfg_if;
+use winapi::shared::minwindef::DWORD;
 use winapi::um::handleapi::INVALID_HANDLE_VALUE;
+use winapi::um::namedpipeapi::SetNamedPipeHandleState;
+use winapi::um::winbase::PIPE_NOWAIT;
 
 #[cfg(unix)]
 use nix::unistd;
@@ -45,82 +48,41 @@ pub struct Pipe {
 
 use std::ptr::null_mut;
 
-fn i32_to_owned_handle(handle: i32) -> OwnedHandle {
-    // Safety: Ensure the handle is valid and not null before converting
-    if handle == 0 {
-        panic!("Invalid handle value");
+/// Converts a C runtime descriptor into the OS handle it wraps. The descriptor
+/// owns its handle, so a duplicate of it is returned and the descriptor is closed.
+fn crt_fd_to_owned_handle(fd: libc::c_int) -> io::Result<OwnedHandle> {
+    let raw_handle = unsafe { libc::get_osfhandle(fd) };
+    if raw_handle == -1 || raw_handle == -2 {
+        unsafe { libc::close(fd) };
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("invalid pipe descriptor: {fd}"),
+        ));
     }
-    unsafe { OwnedHandle::from_raw_handle(handle as *mut _) }
+    // Safety: the handle stays valid until the descriptor is closed below
+    let handle = unsafe { BorrowedHandle::borrow_raw(raw_handle as RawHandle) };
+    let owned_handle = handle.try_clone_to_owned();
+    unsafe { libc::close(fd) };
+    owned_handle
 }
 
-// fn set_non_blocking(fd: impl AsFd) -> io::Result<()> {
-//     let fd = fd.as_fd();
-//     let flags = fcntl(fd.as_raw_fd(), FcntlArg::F_GETFL)?;
-//     let flags = OFlag::from_bits_retain(flags);
-//     fcntl(fd.as_raw_fd(), FcntlArg::F_SETFL(flags | OFlag::O_NONBLOCK))?;
-//     Ok(())
-// }
-
-// #[cfg_attr(target_os = "linux", allow(dead_code))]
-// fn set_cloexec(fd: impl AsFd) -> io::Result<()> {
-//     let fd = fd.as_fd();
-//     let flags = fcntl(fd.as_raw_fd(), FcntlArg::F_GETFD)?;
-//     let flags = FdFlag::from_bits_retain(flags);
-//     fcntl(
-//         fd.as_raw_fd(),
-//         FcntlArg::F_SETFD(flags | FdFlag::FD_CLOEXEC),
-//     )?;
-//     Ok(())
-// }
-
-/*
-pub fn pipe(reader_type: ReaderType, writer_type: WriterType) -> io::Result<(RawHandle, RawHandle)> {
-    cfg_if! {
-        if #[cfg(target_os = "linux")] {
-            let (reader, writer) = unistd::pipe2(OFlag::O_CLOEXEC)?;
-        } else if #[cfg(target_os = "windows")] {
-            let mut read_handle: RawHandle = INVALID_HANDLE_VALUE;
-            let mut write_handle: RawHandle = INVALID_HANDLE_VALUE;
-            unsafe {
-                if pipe(ReaderType::Blocking, WriterType::Blocking).is_ok() {
-                    return Err(io::Error::last_os_error());
-                }
-            }
-        } else {
-            return Err(io::Error::new(io::ErrorKind::Other, "Unsupported platform"));
-        }
+/// Switches the pipe end into the `PIPE_NOWAIT` mode, so that reads on an empty
+/// pipe and writes to a full pipe return immediately instead of blocking.
+fn set_non_blocking(handle: &OwnedHandle) -> io::Result<()> {
+    let mut mode: DWORD = PIPE_NOWAIT;
+    let res = unsafe {
+        SetNamedPipeHandleState(
+            handle.as_raw_handle().cast(),
+            &mut mode,
+            null_mut(),
+            null_mut(),
+        )
+    };
+    if res == 0 {
+        return Err(io::Error::last_os_error());
     }
-
-    // Windows-specific or cross-platform post-creation adjustments
-    // For example, setting non-blocking mode if required
-
-    Ok((read_handle, write_handle))
-}
-*/
-
-
-
-/*
-fn set_non_blocking(fd: impl AsFd) -> io::Result<()> {
-    let fd = fd.as_fd();
-    let flags = fcntl(fd.as_raw_fd(), FcntlArg::F_GETFL)?;
-    let flags = OFlag::from_bits_retain(flags);
-    fcntl(fd.as_raw_fd(), FcntlArg::F_SETFL(flags | OFlag::O_NONBLOCK))?;
     Ok(())
 }
-*/
-/*
-#[cfg_attr(target_os = "linux", allow(dead_code))]
-fn set_cloexec(fd: impl AsFd) -> io::Result<()> {
-    let fd = fd.as_fd();
-    let flags = fcntl(fd.as_raw_fd(), FcntlArg::F_GETFD)?;
-    let flags = FdFlag::from_bits_retain(flags);
-    fcntl(
-        fd.as_raw_fd(),
-        FcntlArg::F_SETFD(flags | FdFlag::FD_CLOEXEC),
-    )?;
-    Ok(())
-}*/
 
 pub fn fpipe(
 ) -> std::result::Result<(std::os::windows::io::OwnedHandle, std::os::windows::io::OwnedHandle), Error> {
@@ -132,13 +94,17 @@ pub fn fpipe(
     #[cfg(windows)]
     let res = unsafe { libcpipe(fds.as_mut_ptr().cast(),4096, O_BINARY) };
     
- //   Error::result(res)?;
+    if res == -1 {
+        return Err(io::Error::last_os_error());
+    }
     let [read, write] = unsafe { fds.assume_init() };
 
-    let rd=i32_to_owned_handle(read);
-    let wt=i32_to_owned_handle(write);
+    // Both descriptors are converted before checking the results, so that
+    // neither of them leaks if the other one fails
+    let rd = crt_fd_to_owned_handle(read);
+    let wt = crt_fd_to_owned_handle(write);
 
-    Ok((rd, wt))
+    Ok((rd?, wt?))
 }
 
 
@@ -149,11 +115,9 @@ pub fn pipe(reader_type: ReaderType, writer_type: WriterType) -> io::Result<Pipe
             let (reader, writer) = unistd::pipe2(OFlag::O_CLOEXEC)?;
         } else {
             let (reader, writer) = fpipe()?;
-        //    set_cloexec(&reader)?;
-        //    set_cloexec(&writer)?;
         }
     }
-/*
+
     if let ReaderType::NonBlocking = reader_type {
         set_non_blocking(&reader)?;
     }
@@ -161,6 +125,43 @@ pub fn pipe(reader_type: ReaderType, writer_type: WriterType) -> io::Result<Pipe
     if let WriterType::NonBlocking = writer_type {
         set_non_blocking(&writer)?;
     }
-*/
+
     Ok(Pipe { reader, writer })
 }
+
+#[cfg(test)]
+mod tests {
+    use std::fs::File;
+    use std::io::{self, Read, Write};
+
+    use super::{pipe, ReaderType, WriterType};
+
+    fn round_trip(reader_type: ReaderType, writer_type: WriterType) -> io::Result<()> {
+        let pipe = pipe(reader_type, writer_type)?;
+        let mut writer = File::from(pipe.writer);
+        let mut reader = File::from(pipe.reader);
+
+        writer.write_all(b"wakeup")?;
+        let mut buffer = [0; 6];
+        reader.read_exact(&mut buffer)?;
+        assert_eq!(&buffer, b"wakeup");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_blocking_pipe() -> io::Result<()> {
+        round_trip(ReaderType::Blocking, WriterType::Blocking)
+    }
+
+    #[test]
+    fn test_non_blocking_writer_pipe() -> io::Result<()> {
+        // The configuration of the wakeup pipe of the Python connectors
+        round_trip(ReaderType::Blocking, WriterType::NonBlocking)
+    }
+
+    #[test]
+    fn test_non_blocking_pipe() -> io::Result<()> {
+        round_trip(ReaderType::NonBlocking, WriterType::NonBlocking)
+    }
+}
@@ -14,7 +14,7 @@ use crate::engine::{
     ShardPolicy, TotalFrontier,
 };
 use crate::persistence::frontier::OffsetAntichain;
-use csv::ReaderBuilder as CsvReaderBuilder;
+use azure_storage_blobs::prelude::ContainerClient;
 use elasticsearch::{
     auth::Credentials as ESCredentials,
     http::{
@@ -24,24 +24,26 @@ use elasticsearch::{
     Elasticsearch,
 };
 use itertools::Itertools;
-use log::warn;
+use log::{error, info, warn};
 use numpy::{PyArray, PyReadonlyArrayDyn};
 use once_cell::sync::Lazy;
 use postgres::{Client, NoTls};
 use pyo3::exceptions::{
-    PyBaseException, PyException, PyIOError, PyIndexError, PyKeyError, PyRuntimeError, PyTypeError,
-    PyValueError, PyZeroDivisionError,
+    PyBaseException, PyException, PyIOError, PyIndexError, PyKeyError, PyRuntimeError,
+    PySystemExit, PyTypeError, PyValueError, PyZeroDivisionError,
 };
 use pyo3::marker::Ungil;
 use pyo3::prelude::*;
 use pyo3::pyclass::CompareOp;
 use pyo3::sync::GILOnceCell;
-use pyo3::types::{PyBool, PyBytes, PyDict, PyFloat, PyInt, PyString, PyTuple, PyType};
+use pyo3::types::{
+    PyBool, PyBytes, PyCFunction, PyDict, PyFloat, PyInt, PyString, PyTuple, PyType,
+};
 use pyo3::{AsPyPointer, PyTypeInfo};
 use pyo3_log::ResetHandle;
 use rdkafka::consumer::{BaseConsumer, Consumer};
-use rdkafka::producer::{DefaultProducerContext, ThreadedProducer};
 use rdkafka::ClientConfig;
+use rumqttc::QoS as MqttQoS;
 use rusqlite::Connection as SqliteConnection;
 use rusqlite::OpenFlags as SqliteOpenFlags;
 use s3::bucket::Bucket as S3Bucket;
@@ -52,13 +54,13 @@ use std::cell::RefCell;
 use std::cmp::Ordering;
 use std::collections::{HashMap, HashSet};
 use std::fs::File;
-use std::io::{BufWriter, Read};
+use std::io::{self, BufWriter, Read};
 use std::mem::take;
 
 #[cfg(unix)]
 use std::os::unix::prelude::*;
 
-use std::os::windows::io::{AsRawHandle, AsHandle, OwnedHandle};
+use std::os::windows::io::{FromRawHandle, IntoRawHandle, OwnedHandle};
 use std::sync::{Arc, Mutex};
 use std::thread;
 use std::time;
@@ -73,12 +75,30 @@ use crate::connectors::data_format::{
     IdentityParser, InnerSchemaField, JsonLinesFormatter, JsonLinesParser, NullFormatter, Parser,
     PsqlSnapshotFormatter, PsqlUpdatesFormatter, SingleColumnFormatter, TransparentParser,
 };
+#[cfg(feature = "clickhouse")]
+use crate::connectors::data_storage::ClickHouseWriter;
 use crate::connectors::data_storage::{
-    ConnectorMode, CsvFilesystemReader, DataEventType, DeltaTableWriter, ElasticSearchWriter,
-    FileWriter, FilesystemReader, KafkaReader, KafkaWriter, NullWriter, PsqlWriter,
-    PythonReaderBuilder, ReadMethod, ReaderBuilder, S3CsvReader, S3GenericReader, SqliteReader,
-    Writer,
+    azure_container_client, AvroFileReader, AvroFileWriter, AzureBlobCredentials,
+    AzureBlobCsvReader, AzureBlobGenericReader, AzureBlobWriter, BigQueryWriter, CacheHashFunction,
+    CacheKeyStrategy, CompressedFileWriter, CompressionFormat, ConnectorMode, CsvDialect,
+    CsvFilesystemReader, DataEventType, DeadLetterReader, DeadLetterWriter, DeltaTableReader,
+    DeltaTableWriteMode, DeltaTableWriter, DynamoDbWriter, ElasticSearchBulkAction,
+    ElasticSearchWriter, FileStartPosition, FileWriter, FilesystemReader, FilesystemReaderSettings,
+    GcsCsvReader, GcsGenericReader, HttpReader, InvalidBytesPolicy, JsonArrayFileReader,
+    KafkaCompression, KafkaProducerTuning, KafkaReader, KafkaWriter, MqttReader, MqttWriter,
+    MultiWriter, MultiWriterErrorPolicy, NatsReader, NatsWriter, NullWriter, ObjectOrdering,
+    OrcFileReader, OrcFileWriter, ParquetFileReader, ParquetFileWriter, PayloadFraming, PsqlWriter,
+    PythonReaderBuilder, ReadMethod, Reader, ReaderBuilder, RedisStreamReader, RedisStreamWriter,
+    RetryingWriter, S3CsvReader, S3GenericReader, ShutdownSignal, SqliteReader, SqliteWriter,
+    TextEncoding, ThrottledReader, WebSocketReader, WrappedReaderBuilder, Writer,
+    GCS_XML_API_ENDPOINT,
 };
+#[cfg(feature = "amqp")]
+use crate::connectors::data_storage::{AmqpReader, AmqpWriter};
+#[cfg(feature = "iceberg")]
+use crate::connectors::data_storage::{IcebergCatalogConfig, IcebergWriter};
+#[cfg(feature = "pulsar")]
+use crate::connectors::data_storage::{PulsarReader, PulsarWriter};
 use crate::connectors::snapshot::Event as SnapshotEvent;
 use crate::connectors::{PersistenceMode, SessionType, SnapshotAccess};
 use crate::engine::dataflow::Config;
@@ -121,6 +141,7 @@ pub fn with_gil_and_pool<R>(f: impl FnOnce(Python) -> R + Ungil) -> R {
 }
 
 const S3_PATH_PREFIX: &str = "s3://";
+const GCS_PATH_PREFIX: &str = "gs://";
 static CONVERT: GILOnceCell<PyObject> = GILOnceCell::new();
 
 fn get_convert_python_module(py: Python<'_>) -> &PyAny {
@@ -432,6 +453,102 @@ impl IntoPy<PyObject> for ReadMethod {
     }
 }
 
+impl<'source> FromPyObject<'source> for CompressionFormat {
+    fn extract(ob: &'source PyAny) -> PyResult<Self> {
+        Ok(ob.extract::<PyRef<PyCompressionFormat>>()?.0)
+    }
+}
+
+impl IntoPy<PyObject> for CompressionFormat {
+    fn into_py(self, py: Python<'_>) -> PyObject {
+        PyCompressionFormat(self).into_py(py)
+    }
+}
+
+impl<'source> FromPyObject<'source> for ObjectOrdering {
+    fn extract(ob: &'source PyAny) -> PyResult<Self> {
+        Ok(ob.extract::<PyRef<PyObjectOrdering>>()?.0)
+    }
+}
+
+impl IntoPy<PyObject> for ObjectOrdering {
+    fn into_py(self, py: Python<'_>) -> PyObject {
+        PyObjectOrdering(self).into_py(py)
+    }
+}
+
+impl<'source> FromPyObject<'source> for InvalidBytesPolicy {
+    fn extract(ob: &'source PyAny) -> PyResult<Self> {
+        Ok(ob.extract::<PyRef<PyInvalidBytesPolicy>>()?.0)
+    }
+}
+
+impl IntoPy<PyObject> for InvalidBytesPolicy {
+    fn into_py(self, py: Python<'_>) -> PyObject {
+        PyInvalidBytesPolicy(self).into_py(py)
+    }
+}
+
+impl<'source> FromPyObject<'source> for FileStartPosition {
+    fn extract(ob: &'source PyAny) -> PyResult<Self> {
+        Ok(ob.extract::<PyRef<PyFileStartPosition>>()?.0)
+    }
+}
+
+impl IntoPy<PyObject> for FileStartPosition {
+    fn into_py(self, py: Python<'_>) -> PyObject {
+        PyFileStartPosition(self).into_py(py)
+    }
+}
+
+impl<'source> FromPyObject<'source> for KafkaCompression {
+    fn extract(ob: &'source PyAny) -> PyResult<Self> {
+        Ok(ob.extract::<PyRef<PyKafkaCompression>>()?.0)
+    }
+}
+
+impl IntoPy<PyObject> for KafkaCompression {
+    fn into_py(self, py: Python<'_>) -> PyObject {
+        PyKafkaCompression(self).into_py(py)
+    }
+}
+
+impl<'source> FromPyObject<'source> for ElasticSearchBulkAction {
+    fn extract(ob: &'source PyAny) -> PyResult<Self> {
+        Ok(ob.extract::<PyRef<PyElasticSearchBulkAction>>()?.0)
+    }
+}
+
+impl IntoPy<PyObject> for ElasticSearchBulkAction {
+    fn into_py(self, py: Python<'_>) -> PyObject {
+        PyElasticSearchBulkAction(self).into_py(py)
+    }
+}
+
+impl<'source> FromPyObject<'source> for CacheHashFunction {
+    fn extract(ob: &'source PyAny) -> PyResult<Self> {
+        Ok(ob.extract::<PyRef<PyCacheHashFunction>>()?.0)
+    }
+}
+
+impl IntoPy<PyObject> for CacheHashFunction {
+    fn into_py(self, py: Python<'_>) -> PyObject {
+        PyCacheHashFunction(self).into_py(py)
+    }
+}
+
+impl<'source> FromPyObject<'source> for MultiWriterErrorPolicy {
+    fn extract(ob: &'source PyAny) -> PyResult<Self> {
+        Ok(ob.extract::<PyRef<PyMultiWriterErrorPolicy>>()?.0)
+    }
+}
+
+impl IntoPy<PyObject> for MultiWriterErrorPolicy {
+    fn into_py(self, py: Python<'_>) -> PyObject {
+        PyMultiWriterErrorPolicy(self).into_py(py)
+    }
+}
+
 impl<'source> FromPyObject<'source> for ConnectorMode {
     fn extract(ob: &'source PyAny) -> PyResult<Self> {
         Ok(ob.extract::<PyRef<PyConnectorMode>>()?.0)
@@ -1445,7 +1562,113 @@ impl PyReadMethod {
     #[classattr]
     pub const BY_LINE: ReadMethod = ReadMethod::ByLine;
     #[classattr]
+    pub const BY_LINE_WITHOUT_TERMINATOR: ReadMethod = ReadMethod::ByLineWithoutTerminator;
+    #[classattr]
     pub const FULL: ReadMethod = ReadMethod::Full;
+    #[classattr]
+    pub const JSON_OBJECTS: ReadMethod = ReadMethod::JsonObjects;
+    #[classattr]
+    pub const LENGTH_PREFIXED: ReadMethod = ReadMethod::LengthPrefixed;
+}
+
+#[pyclass(module = "pathway.engine", frozen, name = "CompressionFormat")]
+pub struct PyCompressionFormat(CompressionFormat);
+
+#[pymethods]
+impl PyCompressionFormat {
+    #[classattr]
+    pub const NONE: CompressionFormat = CompressionFormat::None;
+    #[classattr]
+    pub const GZIP: CompressionFormat = CompressionFormat::Gzip;
+    #[classattr]
+    pub const ZSTD: CompressionFormat = CompressionFormat::Zstd;
+}
+
+#[pyclass(module = "pathway.engine", frozen, name = "FileStartPosition")]
+pub struct PyFileStartPosition(FileStartPosition);
+
+#[pymethods]
+impl PyFileStartPosition {
+    #[classattr]
+    pub const BEGINNING: FileStartPosition = FileStartPosition::Beginning;
+    #[classattr]
+    pub const END: FileStartPosition = FileStartPosition::End;
+}
+
+#[pyclass(module = "pathway.engine", frozen, name = "InvalidBytesPolicy")]
+pub struct PyInvalidBytesPolicy(InvalidBytesPolicy);
+
+#[pymethods]
+impl PyInvalidBytesPolicy {
+    #[classattr]
+    pub const REPLACE: InvalidBytesPolicy = InvalidBytesPolicy::Replace;
+    #[classattr]
+    pub const ERROR: InvalidBytesPolicy = InvalidBytesPolicy::Error;
+}
+
+#[pyclass(module = "pathway.engine", frozen, name = "ObjectOrdering")]
+pub struct PyObjectOrdering(ObjectOrdering);
+
+#[pymethods]
+impl PyObjectOrdering {
+    #[classattr]
+    pub const MODIFIED_TIME_THEN_KEY: ObjectOrdering = ObjectOrdering::ModifiedTimeThenKey;
+    #[classattr]
+    pub const KEY_ONLY: ObjectOrdering = ObjectOrdering::KeyOnly;
+    #[classattr]
+    pub const KEY_DESCENDING: ObjectOrdering = ObjectOrdering::KeyDescending;
+}
+
+#[pyclass(module = "pathway.engine", frozen, name = "KafkaCompression")]
+pub struct PyKafkaCompression(KafkaCompression);
+
+#[pymethods]
+impl PyKafkaCompression {
+    #[classattr]
+    pub const NONE: KafkaCompression = KafkaCompression::None;
+    #[classattr]
+    pub const GZIP: KafkaCompression = KafkaCompression::Gzip;
+    #[classattr]
+    pub const SNAPPY: KafkaCompression = KafkaCompression::Snappy;
+    #[classattr]
+    pub const LZ4: KafkaCompression = KafkaCompression::Lz4;
+    #[classattr]
+    pub const ZSTD: KafkaCompression = KafkaCompression::Zstd;
+}
+
+#[pyclass(module = "pathway.engine", frozen, name = "ElasticSearchBulkAction")]
+pub struct PyElasticSearchBulkAction(ElasticSearchBulkAction);
+
+#[pymethods]
+impl PyElasticSearchBulkAction {
+    #[classattr]
+    pub const INDEX: ElasticSearchBulkAction = ElasticSearchBulkAction::Index;
+    #[classattr]
+    pub const CREATE: ElasticSearchBulkAction = ElasticSearchBulkAction::Create;
+}
+
+#[pyclass(module = "pathway.engine", frozen, name = "CacheHashFunction")]
+pub struct PyCacheHashFunction(CacheHashFunction);
+
+#[pymethods]
+impl PyCacheHashFunction {
+    #[classattr]
+    pub const XXH3: CacheHashFunction = CacheHashFunction::Xxh3;
+    #[classattr]
+    pub const SHA256: CacheHashFunction = CacheHashFunction::Sha256;
+    #[classattr]
+    pub const BLAKE3: CacheHashFunction = CacheHashFunction::Blake3;
+}
+
+#[pyclass(module = "pathway.engine", frozen, name = "MultiWriterErrorPolicy")]
+pub struct PyMultiWriterErrorPolicy(MultiWriterErrorPolicy);
+
+#[pymethods]
+impl PyMultiWriterErrorPolicy {
+    #[classattr]
+    pub const FAIL_FAST: MultiWriterErrorPolicy = MultiWriterErrorPolicy::FailFast;
+    #[classattr]
+    pub const COLLECT_ALL: MultiWriterErrorPolicy = MultiWriterErrorPolicy::CollectAll;
 }
 
 #[pyclass(module = "pathway.engine", frozen, name = "ConnectorMode")]
@@ -2119,7 +2342,11 @@ impl Scope {
             }
         }
 
-        let (reader_impl, parallel_readers) = data_source.borrow().construct_reader(py)?;
+        let worker_index = self_.borrow().graph.worker_index();
+        let (reader_impl, parallel_readers) =
+            data_source
+                .borrow()
+                .construct_reader(py, &data_format.borrow(), worker_index)?;
 
         let parser_impl = data_format.borrow().construct_parser(py)?;
 
@@ -2827,9 +3054,11 @@ impl Scope {
     ) -> PyResult<()> {
         let py = self_.py();
 
-        let sink_impl = data_sink
-            .borrow()
-            .construct_writer(py, &data_format.borrow())?;
+        let worker_index = self_.borrow().graph.worker_index();
+        let sink_impl =
+            data_sink
+                .borrow()
+                .construct_writer(py, &data_format.borrow(), worker_index)?;
         let format_impl = data_format.borrow().construct_formatter(py)?;
 
         self_
@@ -3048,7 +3277,7 @@ pub fn run_with_new_graph(
     let license = License::new(license_key);
     let telemetry_config =
         EngineTelemetryConfig::create(&license, run_id, monitoring_server, trace_parent)?;
-    let results: Vec<Vec<_>> = run_with_wakeup_receiver(py, |wakeup_receiver| {
+    let results: Vec<Vec<_>> = run_with_wakeup_receiver(py, |wakeup_receiver, shutdown| {
         py.allow_threads(|| {
             run_with_new_dataflow_graph(
                 move |graph| {
@@ -3080,6 +3309,7 @@ pub fn run_with_new_graph(
                 &license,
                 telemetry_config,
                 terminate_on_error,
+                shutdown,
             )
         })
     })??;
@@ -3135,6 +3365,7 @@ pub struct AwsS3Settings {
     secret_access_key: Option<String>,
     with_path_style: bool,
     profile: Option<String>,
+    request_timeout_ms: Option<u64>,
 }
 
 #[pymethods]
@@ -3148,7 +3379,9 @@ impl AwsS3Settings {
         region = None,
         endpoint = None,
         profile = None,
+        request_timeout_ms = None,
     ))]
+    #[allow(clippy::too_many_arguments)]
     fn new(
         bucket_name: Option<String>,
         access_key: Option<String>,
@@ -3157,6 +3390,7 @@ impl AwsS3Settings {
         region: Option<String>,
         endpoint: Option<String>,
         profile: Option<String>,
+        request_timeout_ms: Option<u64>,
     ) -> PyResult<Self> {
         Ok(AwsS3Settings {
             bucket_name,
@@ -3165,6 +3399,7 @@ impl AwsS3Settings {
             secret_access_key,
             with_path_style,
             profile,
+            request_timeout_ms,
         })
     }
 }
@@ -3250,16 +3485,7 @@ impl AwsS3Settings {
     }
 
     fn deduce_bucket_and_path(s3_path: &str) -> (Option<String>, Option<String>) {
-        if !s3_path.starts_with(S3_PATH_PREFIX) {
-            return (None, Some(s3_path.to_string()));
-        }
-        let bucket_and_path = &s3_path[S3_PATH_PREFIX.len()..];
-        let bucket_and_path_tokenized: Vec<&str> = bucket_and_path.split('/').collect();
-
-        let bucket = bucket_and_path_tokenized[0];
-        let path = bucket_and_path_tokenized[1..].join("/");
-
-        (Some(bucket.to_string()), Some(path))
+        deduce_bucket_and_path(S3_PATH_PREFIX, s3_path)
     }
 
     fn construct_bucket(&self, name_override: Option<&str>) -> PyResult<S3Bucket> {
@@ -3291,11 +3517,182 @@ impl AwsS3Settings {
         if self.with_path_style {
             bucket = bucket.with_path_style();
         }
+        // Bounds each request, so that a stalled connection fails the download
+        // instead of blocking it forever. The client default is kept otherwise.
+        if let Some(request_timeout_ms) = self.request_timeout_ms {
+            bucket.set_request_timeout(Some(time::Duration::from_millis(request_timeout_ms)));
+        }
 
         Ok(bucket)
     }
 }
 
+fn deduce_bucket_and_path(prefix: &str, path: &str) -> (Option<String>, Option<String>) {
+    let Some(bucket_and_path) = path.strip_prefix(prefix) else {
+        return (None, Some(path.to_string()));
+    };
+    let bucket_and_path_tokenized: Vec<&str> = bucket_and_path.split('/').collect();
+
+    let bucket = bucket_and_path_tokenized[0];
+    let path = bucket_and_path_tokenized[1..].join("/");
+
+    (Some(bucket.to_string()), Some(path))
+}
+
+#[pyclass(module = "pathway.engine", frozen)]
+pub struct AzureBlobSettings {
+    account: String,
+    container: String,
+    account_key: Option<String>,
+    sas_token: Option<String>,
+}
+
+#[pymethods]
+impl AzureBlobSettings {
+    #[new]
+    #[pyo3(signature = (account, container, account_key = None, sas_token = None))]
+    fn new(
+        account: String,
+        container: String,
+        account_key: Option<String>,
+        sas_token: Option<String>,
+    ) -> Self {
+        AzureBlobSettings {
+            account,
+            container,
+            account_key,
+            sas_token,
+        }
+    }
+}
+
+impl AzureBlobSettings {
+    fn construct_container_client(&self) -> PyResult<ContainerClient> {
+        let credentials = match (&self.account_key, &self.sas_token) {
+            (Some(account_key), None) => AzureBlobCredentials::AccountKey(account_key.clone()),
+            (None, Some(sas_token)) => AzureBlobCredentials::SasToken(sas_token.clone()),
+            _ => return Err(PyValueError::new_err(
+                "Exactly one of account_key and sas_token must be specified for Azure Blob Storage",
+            )),
+        };
+        azure_container_client(&self.account, &self.container, &credentials).map_err(|err| {
+            PyRuntimeError::new_err(format!("Failed to connect to Azure container: {err}"))
+        })
+    }
+}
+
+#[pyclass(module = "pathway.engine", frozen)]
+pub struct GcsSettings {
+    bucket_name: Option<String>,
+    access_key: String,
+    secret_access_key: String,
+}
+
+#[pymethods]
+impl GcsSettings {
+    #[new]
+    #[pyo3(signature = (access_key, secret_access_key, bucket_name = None))]
+    fn new(access_key: String, secret_access_key: String, bucket_name: Option<String>) -> Self {
+        GcsSettings {
+            bucket_name,
+            access_key,
+            secret_access_key,
+        }
+    }
+}
+
+impl GcsSettings {
+    fn deduce_bucket_and_path(gcs_path: &str) -> (Option<String>, Option<String>) {
+        deduce_bucket_and_path(GCS_PATH_PREFIX, gcs_path)
+    }
+
+    /// The bucket is accessed via the S3-compatible XML API, so the HMAC keys of a
+    /// service account are used as the credentials.
+    fn construct_bucket(&self, deduced_name: Option<&str>) -> PyResult<S3Bucket> {
+        let bucket_name = self
+            .bucket_name
+            .as_deref()
+            .or(deduced_name)
+            .ok_or_else(|| {
+                PyRuntimeError::new_err("bucket_name not specified and isn't in the gs path")
+            })?;
+        let credentials = AwsCredentials::new(
+            Some(&self.access_key),
+            Some(&self.secret_access_key),
+            None,
+            None,
+            None,
+        )
+        .map_err(|err| {
+            PyRuntimeError::new_err(format!("Unable to form credentials to GCS: {err}"))
+        })?;
+        let region = s3::region::Region::Custom {
+            region: "auto".to_string(),
+            endpoint: GCS_XML_API_ENDPOINT.to_string(),
+        };
+        let bucket = S3Bucket::new(bucket_name, region, credentials).map_err(|err| {
+            PyRuntimeError::new_err(format!("Failed to connect to GCS bucket: {err}"))
+        })?;
+        Ok(bucket.with_path_style())
+    }
+}
+
+#[pyclass(module = "pathway.engine", frozen)]
+#[cfg_attr(not(feature = "iceberg"), allow(dead_code))]
+pub struct IcebergCatalogSettings {
+    catalog_type: String,
+    uri: Option<String>,
+    warehouse: Option<String>,
+}
+
+#[pymethods]
+impl IcebergCatalogSettings {
+    #[new]
+    #[pyo3(signature = (catalog_type, uri = None, warehouse = None))]
+    fn new(catalog_type: String, uri: Option<String>, warehouse: Option<String>) -> Self {
+        IcebergCatalogSettings {
+            catalog_type,
+            uri,
+            warehouse,
+        }
+    }
+}
+
+#[cfg(feature = "iceberg")]
+impl IcebergCatalogSettings {
+    fn catalog_config(
+        &self,
+        properties: HashMap<String, String>,
+    ) -> PyResult<IcebergCatalogConfig> {
+        match self.catalog_type.as_str() {
+            "rest" => {
+                let uri = self.uri.clone().ok_or_else(|| {
+                    PyValueError::new_err("For the Iceberg REST catalog, uri must be specified")
+                })?;
+                Ok(IcebergCatalogConfig::Rest {
+                    uri,
+                    warehouse: self.warehouse.clone(),
+                    properties,
+                })
+            }
+            "glue" => {
+                let warehouse = self.warehouse.clone().ok_or_else(|| {
+                    PyValueError::new_err(
+                        "For the Iceberg Glue catalog, warehouse must be specified",
+                    )
+                })?;
+                Ok(IcebergCatalogConfig::Glue {
+                    warehouse,
+                    properties,
+                })
+            }
+            other => Err(PyValueError::new_err(format!(
+                "Unknown Iceberg catalog type: {other}"
+            ))),
+        }
+    }
+}
+
 #[pyclass(module = "pathway.engine", frozen)]
 pub struct ElasticSearchAuth {
     auth_type: String,
@@ -3379,16 +3776,37 @@ pub struct ElasticSearchParams {
     host: String,
     index_name: String,
     auth: Py<ElasticSearchAuth>,
+    id_field: Option<String>,
+    bulk_action: ElasticSearchBulkAction,
+    max_buffered_actions: usize,
 }
 
 #[pymethods]
 impl ElasticSearchParams {
     #[new]
-    fn new(host: String, index_name: String, auth: Py<ElasticSearchAuth>) -> Self {
+    #[pyo3(signature = (
+        host,
+        index_name,
+        auth,
+        id_field = None,
+        bulk_action = ElasticSearchBulkAction::Index,
+        max_buffered_actions = ElasticSearchWriter::DEFAULT_MAX_BUFFERED_ACTIONS,
+    ))]
+    fn new(
+        host: String,
+        index_name: String,
+        auth: Py<ElasticSearchAuth>,
+        id_field: Option<String>,
+        bulk_action: ElasticSearchBulkAction,
+        max_buffered_actions: usize,
+    ) -> Self {
         ElasticSearchParams {
             host,
             index_name,
             auth,
+            id_field,
+            bulk_action,
+            max_buffered_actions,
         }
     }
 }
@@ -3415,6 +3833,38 @@ impl ElasticSearchParams {
     }
 }
 
+#[pyclass(module = "pathway.engine", frozen)]
+pub struct HttpReaderSettings {
+    headers: HashMap<String, String>,
+    auth_token: Option<String>,
+    items_path: String,
+    cursor_path: Option<String>,
+}
+
+#[pymethods]
+impl HttpReaderSettings {
+    #[new]
+    #[pyo3(signature = (
+        items_path,
+        headers = HashMap::new(),
+        auth_token = None,
+        cursor_path = None,
+    ))]
+    fn new(
+        items_path: String,
+        headers: HashMap<String, String>,
+        auth_token: Option<String>,
+        cursor_path: Option<String>,
+    ) -> Self {
+        HttpReaderSettings {
+            headers,
+            auth_token,
+            items_path,
+            cursor_path,
+        }
+    }
+}
+
 #[derive(Clone, Debug)]
 #[pyclass(module = "pathway.engine", frozen, get_all)]
 pub struct DataStorage {
@@ -3441,6 +3891,61 @@ pub struct DataStorage {
     key_field_index: Option<usize>,
     storage_options: Option<HashMap<String, String>>,
     min_commit_frequency: Option<u64>,
+    refresh_interval: Option<u64>,
+    compression: Option<CompressionFormat>,
+    max_consecutive_errors: usize,
+    http_reader_settings: Option<Py<HttpReaderSettings>>,
+    consumer_name: Option<String>,
+    kafka_compression: Option<KafkaCompression>,
+    kafka_batch_num_messages: Option<usize>,
+    kafka_linger_ms: Option<u64>,
+    primary_key: Option<Vec<String>>,
+    #[cfg_attr(not(feature = "amqp"), allow(dead_code))]
+    routing_key: Option<String>,
+    subscription_message: Option<String>,
+    ping_interval_ms: Option<u64>,
+    start_position: Option<FileStartPosition>,
+    tombstone_marker: Option<String>,
+    gcs_settings: Option<Py<GcsSettings>>,
+    sort_key_field_index: Option<usize>,
+    consumer_group: Option<String>,
+    #[cfg_attr(not(feature = "iceberg"), allow(dead_code))]
+    iceberg_catalog_settings: Option<Py<IcebergCatalogSettings>>,
+    partition_offsets: Option<Vec<(i32, i64)>>,
+    mqtt_qos: u8,
+    emit_metadata_record: bool,
+    timescale_time_column: Option<String>,
+    idle_timeout_ms: Option<u64>,
+    timestamp_field_index: Option<usize>,
+    azure_blob_settings: Option<Py<AzureBlobSettings>>,
+    sqlite_projection: Option<Vec<String>>,
+    sqlite_filter: Option<String>,
+    sqlite_tracking_column: Option<String>,
+    object_ordering: Option<ObjectOrdering>,
+    skip_empty_files: Option<bool>,
+    json_pointer: Option<String>,
+    fsync_on_flush: bool,
+    write_deletion_flag: bool,
+    encoding: Option<String>,
+    invalid_bytes_policy: Option<InvalidBytesPolicy>,
+    max_in_flight_objects: Option<usize>,
+    max_record_bytes: Option<usize>,
+    kafka_transactional_id: Option<String>,
+    extra_paths: Option<Vec<String>>,
+    kafka_seek_validation_timeout_ms: Option<u64>,
+    max_get_attempts: Option<usize>,
+    kafka_poll_timeout_ms: Option<u64>,
+    key_field_indices: Option<Vec<usize>>,
+    kafka_commit_timeout_ms: Option<u64>,
+    cache_hash_function: Option<CacheHashFunction>,
+    cache_hash_contents: bool,
+    dead_letter: Option<(Py<DataStorage>, Py<DataFormat>)>,
+    max_records_per_second: Option<f64>,
+    max_bytes_per_second: Option<f64>,
+    max_write_attempts: Option<usize>,
+    write_retry_backoff_ms: Option<u64>,
+    extra_outputs: Option<Vec<Py<DataStorage>>>,
+    output_error_policy: Option<MultiWriterErrorPolicy>,
 }
 
 #[pyclass(module = "pathway.engine", frozen, name = "PersistenceMode")]
@@ -3751,6 +4256,59 @@ impl DataStorage {
         key_field_index = None,
         storage_options = None,
         min_commit_frequency = None,
+        refresh_interval = None,
+        compression = None,
+        max_consecutive_errors = 0,
+        http_reader_settings = None,
+        consumer_name = None,
+        kafka_compression = None,
+        kafka_batch_num_messages = None,
+        kafka_linger_ms = None,
+        primary_key = None,
+        routing_key = None,
+        subscription_message = None,
+        ping_interval_ms = None,
+        start_position = None,
+        tombstone_marker = None,
+        gcs_settings = None,
+        sort_key_field_index = None,
+        consumer_group = None,
+        iceberg_catalog_settings = None,
+        partition_offsets = None,
+        mqtt_qos = 1,
+        emit_metadata_record = false,
+        timescale_time_column = None,
+        idle_timeout_ms = None,
+        timestamp_field_index = None,
+        azure_blob_settings = None,
+        sqlite_projection = None,
+        sqlite_filter = None,
+        sqlite_tracking_column = None,
+        object_ordering = None,
+        skip_empty_files = None,
+        json_pointer = None,
+        fsync_on_flush = false,
+        write_deletion_flag = false,
+        encoding = None,
+        invalid_bytes_policy = None,
+        max_in_flight_objects = None,
+        max_record_bytes = None,
+        kafka_transactional_id = None,
+        extra_paths = None,
+        kafka_seek_validation_timeout_ms = None,
+        max_get_attempts = None,
+        kafka_poll_timeout_ms = None,
+        key_field_indices = None,
+        kafka_commit_timeout_ms = None,
+        cache_hash_function = None,
+        cache_hash_contents = false,
+        dead_letter = None,
+        max_records_per_second = None,
+        max_bytes_per_second = None,
+        max_write_attempts = None,
+        write_retry_backoff_ms = None,
+        extra_outputs = None,
+        output_error_policy = None,
     ))]
     #[allow(clippy::too_many_arguments)]
     fn new(
@@ -3777,6 +4335,59 @@ impl DataStorage {
         key_field_index: Option<usize>,
         storage_options: Option<HashMap<String, String>>,
         min_commit_frequency: Option<u64>,
+        refresh_interval: Option<u64>,
+        compression: Option<CompressionFormat>,
+        max_consecutive_errors: usize,
+        http_reader_settings: Option<Py<HttpReaderSettings>>,
+        consumer_name: Option<String>,
+        kafka_compression: Option<KafkaCompression>,
+        kafka_batch_num_messages: Option<usize>,
+        kafka_linger_ms: Option<u64>,
+        primary_key: Option<Vec<String>>,
+        routing_key: Option<String>,
+        subscription_message: Option<String>,
+        ping_interval_ms: Option<u64>,
+        start_position: Option<FileStartPosition>,
+        tombstone_marker: Option<String>,
+        gcs_settings: Option<Py<GcsSettings>>,
+        sort_key_field_index: Option<usize>,
+        consumer_group: Option<String>,
+        iceberg_catalog_settings: Option<Py<IcebergCatalogSettings>>,
+        partition_offsets: Option<Vec<(i32, i64)>>,
+        mqtt_qos: u8,
+        emit_metadata_record: bool,
+        timescale_time_column: Option<String>,
+        idle_timeout_ms: Option<u64>,
+        timestamp_field_index: Option<usize>,
+        azure_blob_settings: Option<Py<AzureBlobSettings>>,
+        sqlite_projection: Option<Vec<String>>,
+        sqlite_filter: Option<String>,
+        sqlite_tracking_column: Option<String>,
+        object_ordering: Option<ObjectOrdering>,
+        skip_empty_files: Option<bool>,
+        json_pointer: Option<String>,
+        fsync_on_flush: bool,
+        write_deletion_flag: bool,
+        encoding: Option<String>,
+        invalid_bytes_policy: Option<InvalidBytesPolicy>,
+        max_in_flight_objects: Option<usize>,
+        max_record_bytes: Option<usize>,
+        kafka_transactional_id: Option<String>,
+        extra_paths: Option<Vec<String>>,
+        kafka_seek_validation_timeout_ms: Option<u64>,
+        max_get_attempts: Option<usize>,
+        kafka_poll_timeout_ms: Option<u64>,
+        key_field_indices: Option<Vec<usize>>,
+        kafka_commit_timeout_ms: Option<u64>,
+        cache_hash_function: Option<CacheHashFunction>,
+        cache_hash_contents: bool,
+        dead_letter: Option<(Py<DataStorage>, Py<DataFormat>)>,
+        max_records_per_second: Option<f64>,
+        max_bytes_per_second: Option<f64>,
+        max_write_attempts: Option<usize>,
+        write_retry_backoff_ms: Option<u64>,
+        extra_outputs: Option<Vec<Py<DataStorage>>>,
+        output_error_policy: Option<MultiWriterErrorPolicy>,
     ) -> Self {
         DataStorage {
             storage_type,
@@ -3802,6 +4413,59 @@ impl DataStorage {
             key_field_index,
             storage_options,
             min_commit_frequency,
+            refresh_interval,
+            compression,
+            max_consecutive_errors,
+            http_reader_settings,
+            consumer_name,
+            kafka_compression,
+            kafka_batch_num_messages,
+            kafka_linger_ms,
+            primary_key,
+            routing_key,
+            subscription_message,
+            ping_interval_ms,
+            start_position,
+            tombstone_marker,
+            gcs_settings,
+            sort_key_field_index,
+            consumer_group,
+            iceberg_catalog_settings,
+            partition_offsets,
+            mqtt_qos,
+            emit_metadata_record,
+            timescale_time_column,
+            idle_timeout_ms,
+            timestamp_field_index,
+            azure_blob_settings,
+            sqlite_projection,
+            sqlite_filter,
+            sqlite_tracking_column,
+            object_ordering,
+            skip_empty_files,
+            json_pointer,
+            fsync_on_flush,
+            write_deletion_flag,
+            encoding,
+            invalid_bytes_policy,
+            max_in_flight_objects,
+            max_record_bytes,
+            kafka_transactional_id,
+            extra_paths,
+            kafka_seek_validation_timeout_ms,
+            max_get_attempts,
+            kafka_poll_timeout_ms,
+            key_field_indices,
+            kafka_commit_timeout_ms,
+            cache_hash_function,
+            cache_hash_contents,
+            dead_letter,
+            max_records_per_second,
+            max_bytes_per_second,
+            max_write_attempts,
+            write_retry_backoff_ms,
+            extra_outputs,
+            output_error_policy,
         }
     }
 }
@@ -3862,6 +4526,7 @@ pub struct CsvParserSettings {
     pub enable_double_quote_escapes: bool,
     pub enable_quoting: bool,
     pub comment_character: Option<u8>,
+    pub terminator: Option<u8>,
 }
 
 #[pymethods]
@@ -3874,6 +4539,7 @@ impl CsvParserSettings {
         enable_double_quote_escapes = true,
         enable_quoting = true,
         comment_character = None,
+        terminator = None,
     ))]
     pub fn new(
         delimiter: char,
@@ -3882,6 +4548,7 @@ impl CsvParserSettings {
         enable_double_quote_escapes: bool,
         enable_quoting: bool,
         comment_character: Option<char>,
+        terminator: Option<char>,
     ) -> PyResult<CsvParserSettings> {
         let mut comment_character_ascii: Option<u8> = None;
         if let Some(comment_character) = comment_character {
@@ -3892,6 +4559,14 @@ impl CsvParserSettings {
             })?);
         }
 
+        let terminator = terminator
+            .map(|terminator| {
+                u8::try_from(terminator).map_err(|_| {
+                    PyValueError::new_err("Terminator, if specified, should be an ASCII character")
+                })
+            })
+            .transpose()?;
+
         Ok(CsvParserSettings {
             delimiter: u8::try_from(delimiter).map_err(|_| {
                 PyValueError::new_err("Delimiter, if specified, should be an ASCII character")
@@ -3903,22 +4578,25 @@ impl CsvParserSettings {
             enable_double_quote_escapes,
             enable_quoting,
             comment_character: comment_character_ascii,
+            terminator,
         })
     }
 }
 
 impl CsvParserSettings {
-    fn build_csv_reader_builder(&self) -> CsvReaderBuilder {
-        let mut builder = CsvReaderBuilder::new();
-        builder
-            .delimiter(self.delimiter)
-            .quote(self.quote)
-            .escape(self.escape)
-            .double_quote(self.enable_double_quote_escapes)
-            .quoting(self.enable_quoting)
-            .comment(self.comment_character)
-            .has_headers(false);
-        builder
+    fn dialect(&self) -> CsvDialect {
+        CsvDialect {
+            delimiter: self.delimiter,
+            quote: self.quote,
+            escape: self.escape,
+            double_quote: self.enable_double_quote_escapes,
+            quoting: self.enable_quoting,
+            comment: self.comment_character,
+            terminator: self
+                .terminator
+                .map_or(csv::Terminator::CRLF, csv::Terminator::Any),
+            ..CsvDialect::default()
+        }
     }
 }
 
@@ -3932,6 +4610,14 @@ impl DataStorage {
         Ok(path)
     }
 
+    /// Returns the globs of a filesystem source: the `path` followed by the
+    /// `extra_paths`, which are read as a part of the same source.
+    fn fs_paths(&self) -> PyResult<Vec<&str>> {
+        let mut paths = vec![self.path()?];
+        paths.extend(self.extra_paths.iter().flatten().map(String::as_str));
+        Ok(paths)
+    }
+
     fn connection_string(&self) -> PyResult<&str> {
         let connection_string = self
             .connection_string
@@ -3956,6 +4642,31 @@ impl DataStorage {
         Ok(bucket)
     }
 
+    fn gcs_bucket(&self, py: pyo3::Python) -> PyResult<S3Bucket> {
+        let (bucket_name, _) = GcsSettings::deduce_bucket_and_path(self.path()?);
+        let bucket = self
+            .gcs_settings
+            .as_ref()
+            .ok_or_else(|| {
+                PyValueError::new_err("For GCS storage, gcs_settings must be specified")
+            })?
+            .borrow(py)
+            .construct_bucket(bucket_name.as_deref())?;
+        Ok(bucket)
+    }
+
+    fn azure_container_client(&self, py: pyo3::Python) -> PyResult<ContainerClient> {
+        self.azure_blob_settings
+            .as_ref()
+            .ok_or_else(|| {
+                PyValueError::new_err(
+                    "For Azure Blob Storage, azure_blob_settings must be specified",
+                )
+            })?
+            .borrow(py)
+            .construct_container_client()
+    }
+
     fn kafka_client_config(&self) -> PyResult<ClientConfig> {
         let rdkafka_settings = self.rdkafka_settings.as_ref().ok_or_else(|| {
             PyValueError::new_err("For kafka input, rdkafka_settings must be specified")
@@ -3979,75 +4690,271 @@ impl DataStorage {
         Ok(topic)
     }
 
-    fn build_csv_parser_settings(&self, py: pyo3::Python) -> CsvReaderBuilder {
-        match &self.csv_parser_settings {
-            Some(parser_settings) => parser_settings.borrow(py).build_csv_reader_builder(),
-            None => {
-                let mut builder = CsvReaderBuilder::new();
-                builder.has_headers(false);
-                builder
-            }
-        }
+    fn nats_subject(&self) -> PyResult<&str> {
+        let subject = self
+            .topic
+            .as_ref()
+            .ok_or_else(|| PyValueError::new_err("For NATS, topic must be specified"))?;
+
+        Ok(subject)
     }
 
-    fn internal_persistent_id(&self) -> Option<PersistentId> {
-        self.persistent_id
-            .clone()
-            .map(IntoPersistentId::into_persistent_id)
+    #[cfg(feature = "amqp")]
+    fn amqp_target(&self) -> PyResult<&str> {
+        let target = self.topic.as_ref().ok_or_else(|| {
+            PyValueError::new_err("For AMQP, topic must be specified as the queue or exchange name")
+        })?;
+
+        Ok(target)
     }
 
-    fn construct_fs_reader(&self) -> PyResult<(Box<dyn ReaderBuilder>, usize)> {
-        let storage = FilesystemReader::new(
-            self.path()?,
+    fn mqtt_topic(&self) -> PyResult<&str> {
+        let topic = self
+            .topic
+            .as_ref()
+            .ok_or_else(|| PyValueError::new_err("For MQTT, topic must be specified"))?;
+
+        Ok(topic)
+    }
+
+    fn mqtt_qos(&self) -> PyResult<MqttQoS> {
+        match self.mqtt_qos {
+            0 => Ok(MqttQoS::AtMostOnce),
+            1 => Ok(MqttQoS::AtLeastOnce),
+            2 => Ok(MqttQoS::ExactlyOnce),
+            other => Err(PyValueError::new_err(format!(
+                "MQTT QoS must be 0, 1 or 2, got {other}"
+            ))),
+        }
+    }
+
+    fn csv_dialect(&self, py: pyo3::Python) -> CsvDialect {
+        match &self.csv_parser_settings {
+            Some(parser_settings) => parser_settings.borrow(py).dialect(),
+            None => CsvDialect::default(),
+        }
+    }
+
+    fn internal_persistent_id(&self) -> Option<PersistentId> {
+        self.persistent_id
+            .clone()
+            .map(IntoPersistentId::into_persistent_id)
+    }
+
+    fn refresh_interval(&self) -> Option<time::Duration> {
+        self.refresh_interval.map(time::Duration::from_millis)
+    }
+
+    fn idle_timeout(&self) -> Option<time::Duration> {
+        self.idle_timeout_ms.map(time::Duration::from_millis)
+    }
+
+    fn kafka_poll_timeout(&self) -> Option<time::Duration> {
+        self.kafka_poll_timeout_ms.map(time::Duration::from_millis)
+    }
+
+    // A composite key is given as the list of the fields, while a single field can
+    // also be given as the only key field, as it's done for the other writers
+    fn kafka_key_field_indices(&self) -> Vec<usize> {
+        match &self.key_field_indices {
+            Some(key_field_indices) => key_field_indices.clone(),
+            None => self.key_field_index.into_iter().collect(),
+        }
+    }
+
+    fn text_encoding(&self) -> PyResult<Option<TextEncoding>> {
+        self.encoding
+            .as_deref()
+            .map(|label| TextEncoding::new(label, self.invalid_bytes_policy.unwrap_or_default()))
+            .transpose()
+            .map_err(|e| PyValueError::new_err(e.to_string()))
+    }
+
+    fn cache_key_strategy(&self) -> CacheKeyStrategy {
+        CacheKeyStrategy {
+            hash_function: self.cache_hash_function.unwrap_or_default(),
+            hash_contents: self.cache_hash_contents,
+        }
+    }
+
+    fn construct_fs_reader(&self) -> PyResult<(Box<dyn ReaderBuilder>, usize)> {
+        let mut storage = FilesystemReader::new(
+            &self.fs_paths()?,
             self.mode,
             self.internal_persistent_id(),
             self.read_method,
-            &self.object_pattern,
+            FilesystemReaderSettings {
+                object_pattern: self.object_pattern.clone(),
+                refresh_interval: self.refresh_interval(),
+                compression: self.compression,
+                max_consecutive_errors: self.max_consecutive_errors,
+                start_position: self.start_position.unwrap_or_default(),
+                emit_metadata_record: self.emit_metadata_record,
+                skip_empty_files: self.skip_empty_files.unwrap_or(true),
+                encoding: self.text_encoding()?,
+                max_record_bytes: self.max_record_bytes,
+                cache_key_strategy: self.cache_key_strategy(),
+                cache_directory: None,
+            },
         )
         .map_err(|e| PyIOError::new_err(format!("Failed to initialize Filesystem reader: {e}")))?;
+        storage.set_idle_timeout(self.idle_timeout());
         Ok((Box::new(storage), 1))
     }
 
     fn construct_s3_reader(&self, py: pyo3::Python) -> PyResult<(Box<dyn ReaderBuilder>, usize)> {
         let (_, deduced_path) = AwsS3Settings::deduce_bucket_and_path(self.path()?);
-        let storage = S3GenericReader::new(
+        let mut storage = S3GenericReader::new(
             self.s3_bucket(py)?,
             deduced_path.unwrap_or(self.path()?.to_string()),
+            &self.object_pattern,
+            self.object_ordering.unwrap_or_default(),
             self.mode.is_polling_enabled(),
             self.internal_persistent_id(),
             self.read_method,
+            self.compression,
+            self.refresh_interval(),
+            self.max_consecutive_errors,
+            self.max_in_flight_objects,
+            self.max_record_bytes,
+            self.max_get_attempts,
         )
         .map_err(|e| PyRuntimeError::new_err(format!("Creating S3 reader failed: {e}")))?;
+        storage.set_idle_timeout(self.idle_timeout());
         Ok((Box::new(storage), 1))
     }
 
     fn construct_s3_csv_reader(
         &self,
         py: pyo3::Python,
+        data_format: &DataFormat,
     ) -> PyResult<(Box<dyn ReaderBuilder>, usize)> {
         let (_, deduced_path) = AwsS3Settings::deduce_bucket_and_path(self.path()?);
-        let storage = S3CsvReader::new(
+        let mut storage = S3CsvReader::with_dialect(
             self.s3_bucket(py)?,
             deduced_path.unwrap_or(self.path()?.to_string()),
-            self.build_csv_parser_settings(py),
+            &self.object_pattern,
+            self.object_ordering.unwrap_or_default(),
+            self.csv_dialect(py),
             self.mode.is_polling_enabled(),
             self.internal_persistent_id(),
+            self.refresh_interval(),
+            self.max_consecutive_errors,
+            Some(data_format.schema(py)?),
+            false,
+            self.max_in_flight_objects,
+            self.max_get_attempts,
         )
         .map_err(|e| PyRuntimeError::new_err(format!("Creating S3 reader failed: {e}")))?;
+        storage.set_idle_timeout(self.idle_timeout());
         Ok((Box::new(storage), 1))
     }
 
-    fn construct_csv_reader(&self, py: pyo3::Python) -> PyResult<(Box<dyn ReaderBuilder>, usize)> {
-        let reader = CsvFilesystemReader::new(
+    fn construct_gcs_reader(&self, py: pyo3::Python) -> PyResult<(Box<dyn ReaderBuilder>, usize)> {
+        let (_, deduced_path) = GcsSettings::deduce_bucket_and_path(self.path()?);
+        let mut storage = GcsGenericReader::new(
+            self.gcs_bucket(py)?,
+            deduced_path.unwrap_or(self.path()?.to_string()),
+            &self.object_pattern,
+            self.mode.is_polling_enabled(),
+            self.internal_persistent_id(),
+            self.read_method,
+            self.compression,
+            self.refresh_interval(),
+            self.max_consecutive_errors,
+            self.max_record_bytes,
+        )
+        .map_err(|e| PyRuntimeError::new_err(format!("Creating GCS reader failed: {e}")))?;
+        storage.set_idle_timeout(self.idle_timeout());
+        Ok((Box::new(storage), 1))
+    }
+
+    fn construct_gcs_csv_reader(
+        &self,
+        py: pyo3::Python,
+        data_format: &DataFormat,
+    ) -> PyResult<(Box<dyn ReaderBuilder>, usize)> {
+        let (_, deduced_path) = GcsSettings::deduce_bucket_and_path(self.path()?);
+        let mut storage = GcsCsvReader::new(
+            self.gcs_bucket(py)?,
+            deduced_path.unwrap_or(self.path()?.to_string()),
+            &self.object_pattern,
+            self.csv_dialect(py).parser_builder(),
+            self.mode.is_polling_enabled(),
+            self.internal_persistent_id(),
+            self.refresh_interval(),
+            self.max_consecutive_errors,
+            Some(data_format.schema(py)?),
+        )
+        .map_err(|e| PyRuntimeError::new_err(format!("Creating GCS reader failed: {e}")))?;
+        storage.set_idle_timeout(self.idle_timeout());
+        Ok((Box::new(storage), 1))
+    }
+
+    fn construct_azure_blob_reader(
+        &self,
+        py: pyo3::Python,
+    ) -> PyResult<(Box<dyn ReaderBuilder>, usize)> {
+        let mut storage = AzureBlobGenericReader::new(
+            self.azure_container_client(py)?,
             self.path()?,
-            self.build_csv_parser_settings(py),
-            self.mode,
+            &self.object_pattern,
+            self.mode.is_polling_enabled(),
             self.internal_persistent_id(),
+            self.read_method,
+            self.compression,
+            self.refresh_interval(),
+            self.max_consecutive_errors,
+            self.max_record_bytes,
+        )
+        .map_err(|e| PyRuntimeError::new_err(format!("Creating Azure Blob reader failed: {e}")))?;
+        storage.set_idle_timeout(self.idle_timeout());
+        Ok((Box::new(storage), 1))
+    }
+
+    fn construct_azure_blob_csv_reader(
+        &self,
+        py: pyo3::Python,
+        data_format: &DataFormat,
+    ) -> PyResult<(Box<dyn ReaderBuilder>, usize)> {
+        let mut storage = AzureBlobCsvReader::new(
+            self.azure_container_client(py)?,
+            self.path()?,
             &self.object_pattern,
+            self.csv_dialect(py).parser_builder(),
+            self.mode.is_polling_enabled(),
+            self.internal_persistent_id(),
+            self.refresh_interval(),
+            self.max_consecutive_errors,
+            Some(data_format.schema(py)?),
+        )
+        .map_err(|e| PyRuntimeError::new_err(format!("Creating Azure Blob reader failed: {e}")))?;
+        storage.set_idle_timeout(self.idle_timeout());
+        Ok((Box::new(storage), 1))
+    }
+
+    fn construct_csv_reader(&self, py: pyo3::Python) -> PyResult<(Box<dyn ReaderBuilder>, usize)> {
+        let mut reader = CsvFilesystemReader::with_dialect(
+            &self.fs_paths()?,
+            self.csv_dialect(py),
+            self.mode,
+            self.internal_persistent_id(),
+            false,
+            FilesystemReaderSettings {
+                object_pattern: self.object_pattern.clone(),
+                refresh_interval: self.refresh_interval(),
+                max_consecutive_errors: self.max_consecutive_errors,
+                start_position: self.start_position.unwrap_or_default(),
+                emit_metadata_record: self.emit_metadata_record,
+                encoding: self.text_encoding()?,
+                cache_key_strategy: self.cache_key_strategy(),
+                ..FilesystemReaderSettings::default()
+            },
         )
         .map_err(|e| {
             PyIOError::new_err(format!("Failed to initialize CsvFilesystem reader: {e}"))
         })?;
+        reader.set_idle_timeout(self.idle_timeout());
         Ok((Box::new(reader), 1))
     }
 
@@ -4059,11 +4966,32 @@ impl DataStorage {
             .map_err(|e| PyValueError::new_err(format!("Creating Kafka consumer failed: {e}")))?;
 
         let topic = self.kafka_topic()?;
+        if let Some(partition_offsets) = &self.partition_offsets {
+            let reader = KafkaReader::with_assignment(
+                consumer,
+                topic.to_string(),
+                partition_offsets.clone(),
+                self.internal_persistent_id(),
+                self.kafka_poll_timeout(),
+            )
+            .map_err(|e| {
+                PyIOError::new_err(format!("Assignment of Kafka partitions failed: {e}"))
+            })?;
+            // A single reader is pinned to all of the given partitions
+            return Ok((Box::new(reader), 1));
+        }
         consumer
             .subscribe(&[topic])
             .map_err(|e| PyIOError::new_err(format!("Subscription to Kafka topic failed: {e}")))?;
 
-        let reader = KafkaReader::new(consumer, topic.to_string(), self.internal_persistent_id());
+        let reader = KafkaReader::new(
+            consumer,
+            topic.to_string(),
+            self.internal_persistent_id(),
+            self.kafka_poll_timeout(),
+            self.kafka_seek_validation_timeout_ms
+                .map(time::Duration::from_millis),
+        );
         Ok((Box::new(reader), self.parallel_readers.unwrap_or(256)))
     }
 
@@ -4097,19 +5025,292 @@ impl DataStorage {
         let column_names = self.column_names.clone().ok_or_else(|| {
             PyValueError::new_err("For Sqlite connector, column_names should be specified")
         })?;
-        let reader = SqliteReader::new(connection, table_name, column_names);
+        let mut reader = SqliteReader::new(
+            connection,
+            table_name,
+            column_names,
+            self.sqlite_projection.clone(),
+            self.sqlite_filter.clone(),
+            self.sqlite_tracking_column.clone(),
+            self.internal_persistent_id(),
+        )
+        .map_err(|e| PyValueError::new_err(format!("Failed to create Sqlite reader: {e}")))?;
+        reader.set_idle_timeout(self.idle_timeout());
+        Ok((Box::new(reader), 1))
+    }
+
+    fn construct_parquet_reader(&self) -> PyResult<(Box<dyn ReaderBuilder>, usize)> {
+        let reader = ParquetFileReader::new(self.path()?, self.internal_persistent_id());
+        Ok((Box::new(reader), 1))
+    }
+
+    fn construct_orc_reader(&self) -> PyResult<(Box<dyn ReaderBuilder>, usize)> {
+        let reader = OrcFileReader::new(self.path()?, self.internal_persistent_id());
+        Ok((Box::new(reader), 1))
+    }
+
+    fn construct_avro_reader(&self) -> PyResult<(Box<dyn ReaderBuilder>, usize)> {
+        let reader = AvroFileReader::new(self.path()?, self.internal_persistent_id());
+        Ok((Box::new(reader), 1))
+    }
+
+    fn construct_json_array_reader(&self) -> PyResult<(Box<dyn ReaderBuilder>, usize)> {
+        let reader = JsonArrayFileReader::new(
+            self.path()?,
+            self.json_pointer.clone().unwrap_or_default(),
+            self.internal_persistent_id(),
+        )
+        .map_err(|e| PyValueError::new_err(format!("Failed to create JSON array reader: {e}")))?;
+        Ok((Box::new(reader), 1))
+    }
+
+    fn construct_http_reader(&self, py: pyo3::Python) -> PyResult<(Box<dyn ReaderBuilder>, usize)> {
+        let settings = self
+            .http_reader_settings
+            .as_ref()
+            .ok_or_else(|| {
+                PyValueError::new_err("For HTTP input, http_reader_settings must be specified")
+            })?
+            .borrow(py);
+        let reader = HttpReader::new(
+            self.path()?.to_string(),
+            settings.headers.clone().into_iter().collect(),
+            settings.auth_token.clone(),
+            settings.items_path.clone(),
+            settings.cursor_path.clone(),
+            self.mode,
+            self.refresh_interval(),
+            self.internal_persistent_id(),
+        )
+        .map_err(|e| PyIOError::new_err(format!("Failed to initialize HTTP reader: {e}")))?;
+        Ok((Box::new(reader), 1))
+    }
+
+    fn construct_nats_reader(&self) -> PyResult<(Box<dyn ReaderBuilder>, usize)> {
+        let consumer_name = self.consumer_name.as_ref().ok_or_else(|| {
+            PyValueError::new_err("For NATS input, consumer_name must be specified")
+        })?;
+        let reader = NatsReader::new(
+            self.path()?,
+            self.nats_subject()?.to_string(),
+            consumer_name.clone(),
+            self.internal_persistent_id(),
+        )
+        .map_err(|e| PyIOError::new_err(format!("Failed to initialize NATS reader: {e}")))?;
         Ok((Box::new(reader), 1))
     }
 
-    fn construct_reader(&self, py: pyo3::Python) -> PyResult<(Box<dyn ReaderBuilder>, usize)> {
+    #[cfg(feature = "pulsar")]
+    fn pulsar_topic(&self) -> PyResult<&str> {
+        let topic = self
+            .topic
+            .as_ref()
+            .ok_or_else(|| PyValueError::new_err("For Pulsar, topic must be specified"))?;
+
+        Ok(topic)
+    }
+
+    #[cfg(feature = "pulsar")]
+    fn construct_pulsar_reader(&self) -> PyResult<(Box<dyn ReaderBuilder>, usize)> {
+        let subscription = self.consumer_name.as_ref().ok_or_else(|| {
+            PyValueError::new_err(
+                "For Pulsar input, consumer_name must be specified as the subscription name",
+            )
+        })?;
+        let reader = PulsarReader::new(
+            self.path()?,
+            self.pulsar_topic()?.to_string(),
+            subscription.clone(),
+            self.internal_persistent_id(),
+        )
+        .map_err(|e| PyIOError::new_err(format!("Failed to initialize Pulsar reader: {e}")))?;
+        Ok((Box::new(reader), 1))
+    }
+
+    fn redis_stream_key(&self) -> PyResult<&str> {
+        let stream_key = self.topic.as_ref().ok_or_else(|| {
+            PyValueError::new_err("For Redis streams, topic must be specified as the stream key")
+        })?;
+
+        Ok(stream_key)
+    }
+
+    fn construct_redis_stream_reader(&self) -> PyResult<(Box<dyn ReaderBuilder>, usize)> {
+        let consumer_group = match &self.consumer_group {
+            Some(group_name) => {
+                let consumer_name = self.consumer_name.as_ref().ok_or_else(|| {
+                    PyValueError::new_err(
+                        "For Redis stream input with a consumer group, consumer_name must be specified",
+                    )
+                })?;
+                Some((group_name.clone(), consumer_name.clone()))
+            }
+            None => None,
+        };
+        let reader = RedisStreamReader::new(
+            self.path()?,
+            self.redis_stream_key()?.to_string(),
+            consumer_group,
+            self.internal_persistent_id(),
+        )
+        .map_err(|e| {
+            PyIOError::new_err(format!("Failed to initialize Redis stream reader: {e}"))
+        })?;
+        Ok((Box::new(reader), 1))
+    }
+
+    #[cfg(feature = "amqp")]
+    fn construct_amqp_reader(&self) -> PyResult<(Box<dyn ReaderBuilder>, usize)> {
+        let reader = AmqpReader::new(
+            self.path()?,
+            self.amqp_target()?,
+            self.consumer_name.as_deref().unwrap_or_default(),
+            self.internal_persistent_id(),
+        )
+        .map_err(|e| PyIOError::new_err(format!("Failed to initialize AMQP reader: {e}")))?;
+        Ok((Box::new(reader), 1))
+    }
+
+    fn construct_mqtt_reader(&self) -> PyResult<(Box<dyn ReaderBuilder>, usize)> {
+        let reader = MqttReader::new(
+            self.path()?,
+            self.mqtt_topic()?.to_string(),
+            self.mqtt_qos()?,
+            self.internal_persistent_id(),
+        )
+        .map_err(|e| PyIOError::new_err(format!("Failed to initialize MQTT reader: {e}")))?;
+        Ok((Box::new(reader), 1))
+    }
+
+    fn construct_websocket_reader(&self) -> PyResult<(Box<dyn ReaderBuilder>, usize)> {
+        let reader = WebSocketReader::new(
+            self.path()?.to_string(),
+            self.subscription_message.clone(),
+            self.ping_interval_ms.map(time::Duration::from_millis),
+            self.internal_persistent_id(),
+        )
+        .map_err(|e| PyIOError::new_err(format!("Failed to initialize WebSocket reader: {e}")))?;
+        Ok((Box::new(reader), 1))
+    }
+
+    fn construct_deltalake_reader(&self) -> PyResult<(Box<dyn ReaderBuilder>, usize)> {
+        let reader = DeltaTableReader::new(
+            self.path()?,
+            self.storage_options.clone().unwrap_or_default(),
+            self.mode,
+            self.internal_persistent_id(),
+            self.refresh_interval(),
+        )
+        .map_err(|e| {
+            PyIOError::new_err(format!("Unable to start DeltaTable input connector: {e}"))
+        })?;
+        Ok((Box::new(reader), 1))
+    }
+
+    fn construct_reader(
+        &self,
+        py: pyo3::Python,
+        data_format: &DataFormat,
+        worker_index: usize,
+    ) -> PyResult<(Box<dyn ReaderBuilder>, usize)> {
+        let (reader, parallel_readers) = self.construct_storage_reader(py, data_format)?;
+        let dead_letter = self.construct_dead_letter_writer(py, worker_index)?;
+        let rate_limits = self.rate_limits()?;
+        if dead_letter.is_none() && rate_limits.is_none() {
+            return Ok((reader, parallel_readers));
+        }
+        let reader = WrappedReaderBuilder::new(reader, move |mut reader| {
+            if let Some(dead_letter) = dead_letter {
+                reader = Box::new(DeadLetterReader::new(reader, dead_letter));
+            }
+            if let Some((max_records_per_second, max_bytes_per_second)) = rate_limits {
+                reader = Box::new(ThrottledReader::new(
+                    reader,
+                    max_records_per_second,
+                    max_bytes_per_second,
+                ));
+            }
+            reader
+        });
+        Ok((Box::new(reader), parallel_readers))
+    }
+
+    /// Returns the limits of the records and the bytes read per second, if the reading
+    /// is throttled.
+    fn rate_limits(&self) -> PyResult<Option<(f64, Option<f64>)>> {
+        let limits = [self.max_records_per_second, self.max_bytes_per_second];
+        if limits
+            .into_iter()
+            .flatten()
+            .any(|limit| limit.is_nan() || limit <= 0.0)
+        {
+            return Err(PyValueError::new_err("rate limits must be positive"));
+        }
+        match (self.max_records_per_second, self.max_bytes_per_second) {
+            (Some(max_records_per_second), max_bytes_per_second) => {
+                Ok(Some((max_records_per_second, max_bytes_per_second)))
+            }
+            (None, Some(_)) => Err(PyValueError::new_err(
+                "max_bytes_per_second requires max_records_per_second",
+            )),
+            (None, None) => Ok(None),
+        }
+    }
+
+    fn construct_dead_letter_writer(
+        &self,
+        py: pyo3::Python,
+        worker_index: usize,
+    ) -> PyResult<Option<Box<dyn Writer>>> {
+        self.dead_letter
+            .as_ref()
+            .map(|(storage, format)| {
+                storage
+                    .borrow(py)
+                    .construct_writer(py, &format.borrow(py), worker_index)
+            })
+            .transpose()
+    }
+
+    fn construct_storage_reader(
+        &self,
+        py: pyo3::Python,
+        data_format: &DataFormat,
+    ) -> PyResult<(Box<dyn ReaderBuilder>, usize)> {
         match self.storage_type.as_ref() {
             "fs" => self.construct_fs_reader(),
             "s3" => self.construct_s3_reader(py),
-            "s3_csv" => self.construct_s3_csv_reader(py),
+            "s3_csv" => self.construct_s3_csv_reader(py, data_format),
+            "gcs" => self.construct_gcs_reader(py),
+            "gcs_csv" => self.construct_gcs_csv_reader(py, data_format),
+            "azure_blob" => self.construct_azure_blob_reader(py),
+            "azure_blob_csv" => self.construct_azure_blob_csv_reader(py, data_format),
             "csv" => self.construct_csv_reader(py),
             "kafka" => self.construct_kafka_reader(),
             "python" => self.construct_python_reader(py),
             "sqlite" => self.construct_sqlite_reader(),
+            "parquet" => self.construct_parquet_reader(),
+            "orc" => self.construct_orc_reader(),
+            "avro" => self.construct_avro_reader(),
+            "json_array" => self.construct_json_array_reader(),
+            "deltalake" => self.construct_deltalake_reader(),
+            "http" => self.construct_http_reader(py),
+            "nats" => self.construct_nats_reader(),
+            #[cfg(feature = "pulsar")]
+            "pulsar" => self.construct_pulsar_reader(),
+            #[cfg(not(feature = "pulsar"))]
+            "pulsar" => Err(PyValueError::new_err(
+                "Pathway was built without the Pulsar connector",
+            )),
+            #[cfg(feature = "amqp")]
+            "amqp" => self.construct_amqp_reader(),
+            #[cfg(not(feature = "amqp"))]
+            "amqp" => Err(PyValueError::new_err(
+                "Pathway was built without the AMQP connector",
+            )),
+            "mqtt" => self.construct_mqtt_reader(),
+            "redis_stream" => self.construct_redis_stream_reader(),
+            "websocket" => self.construct_websocket_reader(),
             other => Err(PyValueError::new_err(format!(
                 "Unknown data source {other:?}"
             ))),
@@ -4183,50 +5384,210 @@ impl DataStorage {
         &self,
         py: pyo3::Python,
         data_format: &DataFormat,
+        worker_index: usize,
+    ) -> PyResult<Box<dyn Writer>> {
+        let mut writer = self.construct_storage_writer(py, data_format, worker_index)?;
+        if let Some(dead_letter) = self.construct_dead_letter_writer(py, worker_index)? {
+            writer = Box::new(DeadLetterWriter::new(
+                writer,
+                dead_letter,
+                self.max_consecutive_errors,
+            ));
+        }
+        if self.max_write_attempts.is_some() || self.write_retry_backoff_ms.is_some() {
+            let max_attempts = self
+                .max_write_attempts
+                .unwrap_or(RetryingWriter::DEFAULT_MAX_ATTEMPTS);
+            let initial_backoff = self.write_retry_backoff_ms.map_or(
+                RetryingWriter::DEFAULT_INITIAL_BACKOFF,
+                time::Duration::from_millis,
+            );
+            writer = Box::new(RetryingWriter::new(writer, max_attempts, initial_backoff));
+        }
+        if let Some(extra_outputs) = &self.extra_outputs {
+            // The same formatted output goes to all the storages
+            let mut writers = vec![writer];
+            for storage in extra_outputs {
+                writers.push(
+                    storage
+                        .borrow(py)
+                        .construct_writer(py, data_format, worker_index)?,
+                );
+            }
+            writer = Box::new(MultiWriter::new(
+                writers,
+                self.output_error_policy.unwrap_or_default(),
+            ));
+        }
+        Ok(writer)
+    }
+
+    fn construct_storage_writer(
+        &self,
+        py: pyo3::Python,
+        data_format: &DataFormat,
+        worker_index: usize,
     ) -> PyResult<Box<dyn Writer>> {
         match self.storage_type.as_ref() {
             "fs" => {
                 let path = self.path()?;
-                let storage = {
+                let compression = self
+                    .compression
+                    .unwrap_or_else(|| CompressionFormat::from_path(path));
+                // The payloads are framed the same way as the files written are read
+                let framing = if self.read_method == ReadMethod::LengthPrefixed {
+                    PayloadFraming::LengthPrefixed
+                } else {
+                    PayloadFraming::Newline
+                };
+                let storage: Box<dyn Writer> = {
                     let file = File::create(path);
                     match file {
                         Ok(f) => {
                             let buf_writer = BufWriter::new(f);
-                            FileWriter::new(buf_writer)
+                            if compression == CompressionFormat::None {
+                                Box::new(FileWriter::new(
+                                    buf_writer,
+                                    framing,
+                                    self.tombstone_marker.clone().map(String::into_bytes),
+                                    self.fsync_on_flush,
+                                ))
+                            } else {
+                                Box::new(CompressedFileWriter::new(
+                                    buf_writer,
+                                    compression,
+                                    framing,
+                                ))
+                            }
                         }
                         Err(_) => {
                             return Err(PyIOError::new_err("Filesystem operation (create) failed"))
                         }
                     }
                 };
-                Ok(Box::new(storage))
+                Ok(storage)
             }
             "kafka" => {
                 let client_config = self.kafka_client_config()?;
-
-                let producer: ThreadedProducer<DefaultProducerContext> =
-                    match client_config.create() {
-                        Ok(producer) => producer,
-                        Err(_) => return Err(PyIOError::new_err("Producer creation failed")),
-                    };
+                let tuning = KafkaProducerTuning {
+                    batch_num_messages: self.kafka_batch_num_messages,
+                    linger: self.kafka_linger_ms.map(time::Duration::from_millis),
+                    commit_timeout: self
+                        .kafka_commit_timeout_ms
+                        .map(time::Duration::from_millis),
+                };
 
                 let topic = self.kafka_topic()?;
                 let writer = KafkaWriter::new(
-                    producer,
+                    client_config,
                     topic.to_string(),
                     self.header_fields.clone(),
-                    self.key_field_index,
-                );
+                    self.kafka_key_field_indices(),
+                    data_format.value_fields.len(),
+                    self.kafka_compression.unwrap_or_default(),
+                    tuning,
+                    self.timestamp_field_index,
+                    // Each worker has its own producer, so they must not share the id
+                    self.kafka_transactional_id
+                        .as_ref()
+                        .map(|transactional_id| format!("{transactional_id}-{worker_index}")),
+                )
+                .map_err(|e| {
+                    PyValueError::new_err(format!("Unable to start Kafka output connector: {e}"))
+                })?;
 
                 Ok(Box::new(writer))
             }
+            "azure_blob" => {
+                let writer = AzureBlobWriter::new(&self.azure_container_client(py)?, self.path()?)
+                    .map_err(|e| {
+                        PyIOError::new_err(format!(
+                            "Unable to start Azure Blob output connector: {e}"
+                        ))
+                    })?;
+                Ok(Box::new(writer))
+            }
+            "nats" => {
+                let writer = NatsWriter::new(
+                    self.path()?,
+                    self.nats_subject()?.to_string(),
+                    self.header_fields.clone(),
+                )
+                .map_err(|e| {
+                    PyIOError::new_err(format!("Unable to start NATS output connector: {e}"))
+                })?;
+                Ok(Box::new(writer))
+            }
+            #[cfg(feature = "pulsar")]
+            "pulsar" => {
+                let writer = PulsarWriter::new(
+                    self.path()?,
+                    self.pulsar_topic()?.to_string(),
+                    self.header_fields.clone(),
+                    self.key_field_index,
+                    self.max_batch_size,
+                )
+                .map_err(|e| {
+                    PyIOError::new_err(format!("Unable to start Pulsar output connector: {e}"))
+                })?;
+                Ok(Box::new(writer))
+            }
+            #[cfg(not(feature = "pulsar"))]
+            "pulsar" => Err(PyValueError::new_err(
+                "Pathway was built without the Pulsar connector",
+            )),
+            "redis_stream" => {
+                let writer = RedisStreamWriter::new(
+                    self.path()?,
+                    self.redis_stream_key()?.to_string(),
+                    self.header_fields.clone(),
+                )
+                .map_err(|e| {
+                    PyIOError::new_err(format!(
+                        "Unable to start Redis stream output connector: {e}"
+                    ))
+                })?;
+                Ok(Box::new(writer))
+            }
+            #[cfg(feature = "amqp")]
+            "amqp" => {
+                let writer = AmqpWriter::new(
+                    self.path()?,
+                    self.amqp_target()?.to_string(),
+                    self.routing_key.clone().unwrap_or_default(),
+                    self.key_field_index,
+                    self.header_fields.clone(),
+                )
+                .map_err(|e| {
+                    PyIOError::new_err(format!("Unable to start AMQP output connector: {e}"))
+                })?;
+                Ok(Box::new(writer))
+            }
+            #[cfg(not(feature = "amqp"))]
+            "amqp" => Err(PyValueError::new_err(
+                "Pathway was built without the AMQP connector",
+            )),
+            "mqtt" => {
+                let writer = MqttWriter::new(
+                    self.path()?,
+                    self.mqtt_topic()?.to_string(),
+                    self.key_field_index,
+                    self.mqtt_qos()?,
+                )
+                .map_err(|e| {
+                    PyIOError::new_err(format!("Unable to start MQTT output connector: {e}"))
+                })?;
+                Ok(Box::new(writer))
+            }
             "postgres" => {
                 let connection_string = self.connection_string()?;
                 let storage = match Client::connect(connection_string, NoTls) {
                     Ok(client) => PsqlWriter::new(
                         client,
                         self.max_batch_size,
+                        self.min_commit_frequency.map(time::Duration::from_millis),
                         self.snapshot_maintenance_on_output,
+                        self.timescale_time_column.clone(),
                     ),
                     Err(e) => {
                         return Err(PyIOError::new_err(format!(
@@ -4240,9 +5601,20 @@ impl DataStorage {
                 let elasticsearch_client_params = self.elasticsearch_client_params(py)?;
                 let client = elasticsearch_client_params.client(py)?;
                 let index_name = elasticsearch_client_params.index_name.clone();
+                let id_field = elasticsearch_client_params.id_field.clone();
                 let max_batch_size = self.max_batch_size;
 
-                let writer = ElasticSearchWriter::new(client, index_name, max_batch_size);
+                let writer = ElasticSearchWriter::new(
+                    client,
+                    index_name,
+                    max_batch_size,
+                    id_field,
+                    elasticsearch_client_params.bulk_action,
+                    elasticsearch_client_params.max_buffered_actions,
+                )
+                .map_err(|e| {
+                    PyRuntimeError::new_err(format!("Creating Elasticsearch writer failed: {e}"))
+                })?;
                 Ok(Box::new(writer))
             }
             "deltalake" => {
@@ -4251,17 +5623,214 @@ impl DataStorage {
                 for field in &data_format.value_fields {
                     value_fields.push(field.borrow(py).clone());
                 }
+                let write_mode = match &self.primary_key {
+                    Some(key_columns) => DeltaTableWriteMode::Upsert {
+                        key_columns: key_columns.clone(),
+                    },
+                    None => DeltaTableWriteMode::Append,
+                };
                 let writer = DeltaTableWriter::new(
                     path,
                     &value_fields,
                     self.storage_options.clone().unwrap_or_default(),
                     self.min_commit_frequency.map(time::Duration::from_millis),
+                    write_mode,
+                    self.write_deletion_flag,
                 )
                 .map_err(|e| {
                     PyIOError::new_err(format!("Unable to start DeltaTable output connector: {e}"))
                 })?;
                 Ok(Box::new(writer))
             }
+            #[cfg(feature = "iceberg")]
+            "iceberg" => {
+                let catalog_settings = self.iceberg_catalog_settings.as_ref().ok_or_else(|| {
+                    PyValueError::new_err("For Iceberg output, catalog settings must be specified")
+                })?;
+                let table_name = self.table_name.as_ref().ok_or_else(|| {
+                    PyValueError::new_err("For Iceberg output, table_name should be specified")
+                })?;
+                // The table name is qualified with its namespace, which may be nested
+                let mut namespace: Vec<String> =
+                    table_name.split('.').map(ToString::to_string).collect();
+                let table_name = namespace.pop().expect("split always returns an element");
+                if namespace.is_empty() {
+                    return Err(PyValueError::new_err(
+                        "For Iceberg output, table_name must be in the format namespace.table",
+                    ));
+                }
+                let catalog_config = catalog_settings
+                    .borrow(py)
+                    .catalog_config(self.storage_options.clone().unwrap_or_default())?;
+                let mut value_fields = Vec::new();
+                for field in &data_format.value_fields {
+                    value_fields.push(field.borrow(py).clone());
+                }
+                let writer = IcebergWriter::new(
+                    catalog_config,
+                    &namespace,
+                    &table_name,
+                    &value_fields,
+                    self.min_commit_frequency.map(time::Duration::from_millis),
+                )
+                .map_err(|e| {
+                    PyIOError::new_err(format!("Unable to start Iceberg output connector: {e}"))
+                })?;
+                Ok(Box::new(writer))
+            }
+            #[cfg(not(feature = "iceberg"))]
+            "iceberg" => Err(PyValueError::new_err(
+                "Pathway was built without the Iceberg connector",
+            )),
+            "bigquery" => {
+                let table_name = self.table_name.as_ref().ok_or_else(|| {
+                    PyValueError::new_err("For BigQuery output, table_name should be specified")
+                })?;
+                let [project_id, dataset_id, table_id] = table_name
+                    .split('.')
+                    .collect::<Vec<_>>()
+                    .try_into()
+                    .map_err(|_| {
+                        PyValueError::new_err(
+                            "For BigQuery output, table_name should be in the form project.dataset.table",
+                        )
+                    })?;
+                let mut value_fields = Vec::new();
+                for field in &data_format.value_fields {
+                    value_fields.push(field.borrow(py).clone());
+                }
+                let writer = BigQueryWriter::new(
+                    self.path()?,
+                    project_id,
+                    dataset_id,
+                    table_id,
+                    &value_fields,
+                    self.max_batch_size,
+                    self.min_commit_frequency.map(time::Duration::from_millis),
+                )
+                .map_err(|e| {
+                    PyIOError::new_err(format!("Unable to start BigQuery output connector: {e}"))
+                })?;
+                Ok(Box::new(writer))
+            }
+            #[cfg(feature = "clickhouse")]
+            "clickhouse" => {
+                let dsn = self.connection_string.as_ref().ok_or_else(|| {
+                    PyValueError::new_err(
+                        "For ClickHouse output, connection string must be specified",
+                    )
+                })?;
+                let table_name = self.table_name.as_ref().ok_or_else(|| {
+                    PyValueError::new_err("For ClickHouse output, table_name should be specified")
+                })?;
+                let mut value_fields = Vec::new();
+                for field in &data_format.value_fields {
+                    value_fields.push(field.borrow(py).clone());
+                }
+                let writer = ClickHouseWriter::new(
+                    dsn,
+                    table_name.clone(),
+                    &value_fields,
+                    self.max_batch_size,
+                    self.min_commit_frequency.map(time::Duration::from_millis),
+                )
+                .map_err(|e| {
+                    PyIOError::new_err(format!("Unable to start ClickHouse output connector: {e}"))
+                })?;
+                Ok(Box::new(writer))
+            }
+            #[cfg(not(feature = "clickhouse"))]
+            "clickhouse" => Err(PyValueError::new_err(
+                "Pathway was built without the ClickHouse connector",
+            )),
+            "dynamodb" => {
+                let table_name = self.table_name.as_ref().ok_or_else(|| {
+                    PyValueError::new_err("For DynamoDB output, table_name should be specified")
+                })?;
+                let partition_key_index = self.key_field_index.ok_or_else(|| {
+                    PyValueError::new_err(
+                        "For DynamoDB output, the partition key field should be specified",
+                    )
+                })?;
+                let mut value_fields = Vec::new();
+                for field in &data_format.value_fields {
+                    value_fields.push(field.borrow(py).clone());
+                }
+                let writer = DynamoDbWriter::new(
+                    table_name.clone(),
+                    &value_fields,
+                    partition_key_index,
+                    self.sort_key_field_index,
+                    self.max_batch_size,
+                )
+                .map_err(|e| {
+                    PyIOError::new_err(format!("Unable to start DynamoDB output connector: {e}"))
+                })?;
+                Ok(Box::new(writer))
+            }
+            "avro" => {
+                let file = File::create(self.path()?).map_err(|e| {
+                    PyIOError::new_err(format!("Failed to create Avro output file: {e}"))
+                })?;
+                let mut value_fields = Vec::new();
+                for field in &data_format.value_fields {
+                    value_fields.push(field.borrow(py).clone());
+                }
+                let writer =
+                    AvroFileWriter::new(BufWriter::new(file), &value_fields).map_err(|e| {
+                        PyIOError::new_err(format!("Unable to start Avro output connector: {e}"))
+                    })?;
+                Ok(Box::new(writer))
+            }
+            "parquet" => {
+                let mut value_fields = Vec::new();
+                for field in &data_format.value_fields {
+                    value_fields.push(field.borrow(py).clone());
+                }
+                let writer = ParquetFileWriter::new(
+                    self.path()?.to_string(),
+                    &value_fields,
+                    self.max_batch_size,
+                    self.min_commit_frequency.map(time::Duration::from_millis),
+                )
+                .map_err(|e| {
+                    PyIOError::new_err(format!("Unable to start Parquet output connector: {e}"))
+                })?;
+                Ok(Box::new(writer))
+            }
+            "orc" => {
+                let mut value_fields = Vec::new();
+                for field in &data_format.value_fields {
+                    value_fields.push(field.borrow(py).clone());
+                }
+                let writer = OrcFileWriter::new(
+                    self.path()?.to_string(),
+                    &value_fields,
+                    self.min_commit_frequency.map(time::Duration::from_millis),
+                )
+                .map_err(|e| {
+                    PyIOError::new_err(format!("Unable to start ORC output connector: {e}"))
+                })?;
+                Ok(Box::new(writer))
+            }
+            "sqlite" => {
+                let connection = SqliteConnection::open(self.path()?).map_err(|e| {
+                    PyRuntimeError::new_err(format!("Failed to open Sqlite connection: {e}"))
+                })?;
+                let table_name = self.table_name.as_ref().ok_or_else(|| {
+                    PyValueError::new_err("For Sqlite output, table_name should be specified")
+                })?;
+                let writer = SqliteWriter::new(
+                    connection,
+                    table_name,
+                    data_format.value_field_names(py),
+                    data_format.key_field_names.as_deref().unwrap_or_default(),
+                )
+                .map_err(|e| {
+                    PyValueError::new_err(format!("Unable to start Sqlite output connector: {e}"))
+                })?;
+                Ok(Box::new(writer))
+            }
             "null" => Ok(Box::new(NullWriter::new())),
             other => Err(PyValueError::new_err(format!(
                 "Unknown data sink {other:?}"
@@ -4704,25 +6273,51 @@ impl PyExportedTable {
 
 struct WakeupHandler<'py> {
     py: Python<'py>,
-    _fd: OwnedHandle,
+    _fd: WakeupFd,
     set_wakeup_fd: &'py PyAny,
     old_wakeup_fd: &'py PyAny,
+    set_signal_handler: &'py PyAny,
+    sigterm: &'py PyAny,
+    old_sigterm_handler: &'py PyAny,
+}
+
+/// A C runtime descriptor of the writer end of the wakeup pipe, which is what
+/// `signal.set_wakeup_fd` expects. The descriptor owns the handle it's opened for,
+/// so closing it closes the pipe end as well.
+struct WakeupFd(libc::c_int);
+
+impl WakeupFd {
+    fn new(handle: OwnedHandle) -> io::Result<Self> {
+        let raw_handle = handle.into_raw_handle();
+        let fd = unsafe { libc::open_osfhandle(raw_handle as libc::intptr_t, libc::O_WRONLY) };
+        if fd == -1 {
+            // The handle wasn't taken over by a descriptor, so it's closed here
+            drop(unsafe { OwnedHandle::from_raw_handle(raw_handle) });
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "failed to open a descriptor for the wakeup pipe",
+            ));
+        }
+        Ok(Self(fd))
+    }
 }
 
-fn convert_void_ptr_to_i32(ptr: *mut libc::c_void) -> i32 {
-    // Convert the *mut libc::c_void to *mut i32
-    let int_ptr: *mut i32 = ptr as *mut i32;
-
-    // Dereference the pointer to get the i32 value
-    unsafe { *int_ptr }
+impl Drop for WakeupFd {
+    fn drop(&mut self) {
+        unsafe { libc::close(self.0) };
+    }
 }
 
-
 impl<'py> WakeupHandler<'py> {
-    fn new(py: Python<'py>, fd: OwnedHandle) -> PyResult<Option<Self>> {
+    fn new(
+        py: Python<'py>,
+        fd: OwnedHandle,
+        shutdown_signal: &ShutdownSignal,
+    ) -> PyResult<Option<Self>> {
         let signal_module = py.import("signal")?;
         let set_wakeup_fd = signal_module.getattr("set_wakeup_fd")?;
-        let args = PyTuple::new(py, [convert_void_ptr_to_i32(fd.as_raw_handle())]);
+        let fd = WakeupFd::new(fd)?;
+        let args = PyTuple::new(py, [fd.0]);
         let old_wakeup_fd = set_wakeup_fd.call1(args);
         if let Err(ref error) = old_wakeup_fd {
             if error.is_instance_of::<PyValueError>(py) {
@@ -4731,11 +6326,42 @@ impl<'py> WakeupHandler<'py> {
             }
         }
         let old_wakeup_fd = old_wakeup_fd?;
+
+        // The first SIGTERM makes the readers finish, so that the computation ends
+        // with the data read so far committed. The second one stops it right away.
+        let set_signal_handler = signal_module.getattr("signal")?;
+        let sigterm = signal_module.getattr("SIGTERM")?;
+        let shutdown_signal = shutdown_signal.clone();
+        let sigterm_handler = PyCFunction::new_closure(
+            py,
+            Some("pathway_sigterm_handler\0"),
+            None,
+            move |_args, _kwargs| -> PyResult<()> {
+                if shutdown_signal.is_requested() {
+                    return Err(PySystemExit::new_err("terminated by SIGTERM"));
+                }
+                info!("SIGTERM received, finishing the reading");
+                shutdown_signal.request();
+                Ok(())
+            },
+        )?;
+        let old_sigterm_handler = set_signal_handler.call1((sigterm, sigterm_handler))?;
+        // A handler not installed from Python is reported as None, which can't be
+        // set back, so the default one is restored instead
+        let old_sigterm_handler = if old_sigterm_handler.is_none() {
+            signal_module.getattr("SIG_DFL")?
+        } else {
+            old_sigterm_handler
+        };
+
         let res = Some(Self {
             py,
             _fd: fd,
             set_wakeup_fd,
             old_wakeup_fd,
+            set_signal_handler,
+            sigterm,
+            old_sigterm_handler,
         });
         py.check_signals()?;
         Ok(res)
@@ -4749,15 +6375,22 @@ impl<'py> Drop for WakeupHandler<'py> {
         self.set_wakeup_fd
             .call1(args)
             .expect("restoring the wakeup fd should not fail");
+        if let Err(error) = self
+            .set_signal_handler
+            .call1((self.sigterm, self.old_sigterm_handler))
+        {
+            error!("Failed to restore the SIGTERM handler: {error}");
+        }
     }
 }
 
 fn run_with_wakeup_receiver<R>(
     py: Python,
-    logic: impl FnOnce(Option<WakeupReceiver>) -> R,
+    logic: impl FnOnce(Option<WakeupReceiver>, ShutdownSignal) -> R,
 ) -> PyResult<R> {
+    let shutdown_signal = ShutdownSignal::default();
     let wakeup_pipe = pipe(ReaderType::Blocking, WriterType::NonBlocking)?;
-    let wakeup_handler = WakeupHandler::new(py, wakeup_pipe.writer)?;
+    let wakeup_handler = WakeupHandler::new(py, wakeup_pipe.writer, &shutdown_signal)?;
     let mut wakeup_reader = File::from(wakeup_pipe.reader);
     let (wakeup_sender, wakeup_receiver): (_, WakeupReceiver) = crossbeam_channel::unbounded();
     let wakeup_thread = thread::Builder::new()
@@ -4782,7 +6415,7 @@ fn run_with_wakeup_receiver<R>(
         drop(wakeup_handler);
         wakeup_thread.join().unwrap()
     }
-    Ok(logic(Some(wakeup_receiver)))
+    Ok(logic(Some(wakeup_receiver), shutdown_signal))
 }
 
 static LOGGING_RESET_HANDLE: Lazy<ResetHandle> = Lazy::new(logging::init);
@@ -4829,6 +6462,14 @@ fn module(_py: Python<'_>, m: &PyModule) -> PyResult<()> {
     m.add_class::<PyDataEventType>()?;
     m.add_class::<PyDebeziumDBType>()?;
     m.add_class::<PyReadMethod>()?;
+    m.add_class::<PyCompressionFormat>()?;
+    m.add_class::<PyFileStartPosition>()?;
+    m.add_class::<PyObjectOrdering>()?;
+    m.add_class::<PyInvalidBytesPolicy>()?;
+    m.add_class::<PyKafkaCompression>()?;
+    m.add_class::<PyElasticSearchBulkAction>()?;
+    m.add_class::<PyCacheHashFunction>()?;
+    m.add_class::<PyMultiWriterErrorPolicy>()?;
     m.add_class::<PyMonitoringLevel>()?;
     m.add_class::<Universe>()?;
     m.add_class::<Column>()?;
@@ -4840,8 +6481,12 @@ fn module(_py: Python<'_>, m: &PyModule) -> PyResult<()> {
     m.add_class::<Context>()?;
 
     m.add_class::<AwsS3Settings>()?;
+    m.add_class::<GcsSettings>()?;
+    m.add_class::<AzureBlobSettings>()?;
+    m.add_class::<IcebergCatalogSettings>()?;
     m.add_class::<ElasticSearchParams>()?;
     m.add_class::<ElasticSearchAuth>()?;
+    m.add_class::<HttpReaderSettings>()?;
     m.add_class::<CsvParserSettings>()?;
     m.add_class::<ValueField>()?;
     m.add_class::<DataStorage>()?;
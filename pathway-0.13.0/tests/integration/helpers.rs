@@ -1,11 +1,14 @@
 // Copyright © 2024 Pathway
 
+use std::cell::RefCell;
 use std::collections::HashMap;
 use std::path::Path;
-use std::sync::{mpsc, mpsc::Receiver, Arc, Mutex};
+use std::sync::{mpsc, mpsc::Receiver, Arc, Mutex, Once};
 use std::thread;
 use std::time::Duration;
 
+use log::{Level, LevelFilter, Log, Metadata, Record};
+
 use pathway_engine::engine::error::DynError;
 use pathway_engine::engine::{report_error::ReportError, Error};
 use pathway_engine::persistence::config::{
@@ -17,10 +20,10 @@ use pathway_engine::connectors::data_format::{
     ErrorRemovalLogic, ParseResult, ParsedEvent, ParsedEventWithErrors, Parser,
 };
 use pathway_engine::connectors::data_storage::{
-    DataEventType, ReadResult, Reader, ReaderBuilder, ReaderContext,
+    DataEventType, ReadError, ReadResult, Reader, ReaderBuilder, ReaderContext, ValuesMap,
 };
 use pathway_engine::connectors::snapshot::Event as SnapshotEvent;
-use pathway_engine::connectors::{Connector, Entry, PersistenceMode, SnapshotAccess};
+use pathway_engine::connectors::{Connector, Entry, Offset, PersistenceMode, SnapshotAccess};
 use pathway_engine::engine::{Key, Timestamp, TotalFrontier, Value};
 use pathway_engine::persistence::frontier::OffsetAntichain;
 
@@ -64,6 +67,7 @@ pub fn full_cycle_read(
             .unwrap();
 
     let mut reader = reader.build().expect("building the reader failed");
+    Connector::install_state_storage(&mut *reader, persistent_storage);
     Connector::read_snapshot(
         &mut *reader,
         persistent_storage,
@@ -73,7 +77,16 @@ pub fn full_cycle_read(
     );
 
     let reporter = PanicErrorReporter::default();
-    Connector::read_realtime_updates(&mut *reader, &sender, &main_thread, &reporter);
+    // Nothing is committed while the whole source is read
+    let (_commit_sender, commit_receiver) = mpsc::channel();
+    Connector::read_realtime_updates(
+        &mut *reader,
+        persistent_storage,
+        &sender,
+        &commit_receiver,
+        &main_thread,
+        &reporter,
+    );
     let result = get_entries_in_receiver(receiver);
 
     let has_persistent_storage = persistent_storage.is_some();
@@ -207,6 +220,43 @@ pub fn read_data_from_reader(
     Ok(read_lines)
 }
 
+/// Reads the entries along with their offsets until the reader finishes. The
+/// results that carry no data are skipped.
+pub fn read_entries(reader: &mut dyn Reader) -> Result<Vec<(ReaderContext, Offset)>, ReadError> {
+    let mut entries = Vec::new();
+    loop {
+        match reader.read()? {
+            ReadResult::Data(context, offset) => entries.push((context, offset)),
+            ReadResult::Finished => return Ok(entries),
+            ReadResult::NewSource(_) | ReadResult::FinishedSource { .. } => {}
+        }
+    }
+}
+
+/// Returns the bytes of a `ReaderContext::RawBytes` entry.
+pub fn raw_bytes(context: ReaderContext) -> Vec<u8> {
+    match context {
+        ReaderContext::RawBytes(_, bytes) => bytes,
+        other => panic!("unexpected reader context: {other:?}"),
+    }
+}
+
+/// Returns the event and the tokens of a `ReaderContext::TokenizedEntries` entry.
+pub fn tokenized_entry(context: ReaderContext) -> (DataEventType, Vec<String>) {
+    match context {
+        ReaderContext::TokenizedEntries(event, tokens) => (event, tokens),
+        other => panic!("unexpected reader context: {other:?}"),
+    }
+}
+
+/// Returns the event and the values of a `ReaderContext::Diff` entry.
+pub fn diff_entry(context: ReaderContext) -> (DataEventType, ValuesMap) {
+    match context {
+        ReaderContext::Diff((event, _, values)) => (event, values),
+        other => panic!("unexpected reader context: {other:?}"),
+    }
+}
+
 pub fn create_persistence_manager(
     fs_path: &Path,
     recreate: bool,
@@ -355,3 +405,44 @@ impl ReplaceErrors for ParsedEventWithErrors {
         self.remove_errors(&logic).expect("key shouldn't be error")
     }
 }
+
+thread_local! {
+    static CAPTURED_WARNINGS: RefCell<Option<Vec<String>>> = const { RefCell::new(None) };
+}
+
+/// Logger, which keeps the warnings of the threads that are capturing them.
+struct WarningsCollector;
+
+impl Log for WarningsCollector {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= Level::Warn
+    }
+
+    fn log(&self, record: &Record) {
+        if self.enabled(record.metadata()) {
+            CAPTURED_WARNINGS.with_borrow_mut(|warnings| {
+                if let Some(warnings) = warnings {
+                    warnings.push(record.args().to_string());
+                }
+            });
+        }
+    }
+
+    fn flush(&self) {}
+}
+
+/// Runs `f` and returns its result along with the warnings and errors it logged.
+/// Only the current thread is captured, so the tests running in parallel don't mix.
+pub fn capture_warnings<T>(f: impl FnOnce() -> T) -> (T, Vec<String>) {
+    static INSTALL_LOGGER: Once = Once::new();
+    INSTALL_LOGGER.call_once(|| {
+        log::set_boxed_logger(Box::new(WarningsCollector))
+            .expect("no other logger must be installed in the tests");
+        log::set_max_level(LevelFilter::Warn);
+    });
+
+    CAPTURED_WARNINGS.set(Some(Vec::new()));
+    let result = f();
+    let warnings = CAPTURED_WARNINGS.take().unwrap_or_default();
+    (result, warnings)
+}
@@ -3,29 +3,66 @@
 mod helpers;
 mod operator_test_utils;
 
+mod test_avro;
+mod test_azure_blob;
+mod test_bigquery;
 mod test_bytes;
+#[cfg(feature = "clickhouse")]
+mod test_clickhouse;
+mod test_compression;
 mod test_connector_field_defaults;
+mod test_csv_dialect;
+mod test_csv_reinsertion;
 mod test_dd_distinct_total;
+mod test_dead_letter;
 mod test_debezium;
 mod test_deltalake;
 mod test_dsv;
 mod test_dsv_dir;
 mod test_dsv_output;
+mod test_dynamodb;
+mod test_elasticsearch;
+mod test_encoding;
 mod test_file_kv;
+mod test_fs_scanner;
+mod test_gcs;
+mod test_http;
+#[cfg(feature = "iceberg")]
+mod test_iceberg;
+mod test_idle_timeout;
+mod test_json_array;
 mod test_json_output;
 mod test_jsonlines;
+mod test_kafka_input;
+mod test_kafka_output;
+mod test_line_terminators;
+mod test_memory_reader;
 mod test_metadata;
+mod test_multi_writer;
 mod test_null_writer;
+mod test_object_scanner;
 mod test_offsets_storage;
+mod test_orc;
+mod test_output_event_types;
+mod test_parquet;
 mod test_parser;
 mod test_parser_errors;
+mod test_payload_framing;
 mod test_prev_next;
 mod test_psql_output;
 mod test_psql_snapshot;
+mod test_record_size;
+mod test_retrying_writer;
+mod test_s3_csv;
+mod test_s3_scanner;
 mod test_seek;
+mod test_shutdown_signal;
 mod test_sqlite;
+mod test_start_position;
 mod test_stream_snapshot;
+mod test_throttled_reader;
 mod test_time;
 mod test_time_column;
 mod test_upsert_session;
 mod test_value_to_sql;
+mod test_websocket;
@@ -0,0 +1,164 @@
+// Copyright © 2024 Pathway
+
+use super::helpers::read_entries;
+
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::Path;
+use std::sync::Arc;
+
+use tempfile::tempdir;
+
+use pathway_engine::connectors::data_format::FormatterContext;
+use pathway_engine::connectors::data_storage::{
+    AvroFileReader, AvroFileWriter, DataEventType, Reader, ReaderContext, WriteError, Writer,
+};
+use pathway_engine::connectors::{OffsetKey, OffsetValue};
+use pathway_engine::engine::{DateTimeNaive, DateTimeUtc, Key, Timestamp, Type, Value};
+use pathway_engine::persistence::frontier::OffsetAntichain;
+use pathway_engine::python_api::ValueField;
+
+fn value_fields() -> Vec<ValueField> {
+    vec![
+        ValueField {
+            name: "id".to_string(),
+            type_: Type::Int,
+            is_optional: false,
+            default: None,
+        },
+        ValueField {
+            name: "name".to_string(),
+            type_: Type::String,
+            is_optional: true,
+            default: None,
+        },
+        ValueField {
+            name: "created_at".to_string(),
+            type_: Type::DateTimeNaive,
+            is_optional: false,
+            default: None,
+        },
+        ValueField {
+            name: "updated_at".to_string(),
+            type_: Type::DateTimeUtc,
+            is_optional: false,
+            default: None,
+        },
+    ]
+}
+
+fn row_values(id: i64, name: Value) -> Vec<Value> {
+    vec![
+        Value::Int(id),
+        name,
+        Value::DateTimeNaive(DateTimeNaive::new(id * 1_000_000)),
+        Value::DateTimeUtc(DateTimeUtc::new(id * 2_000_000)),
+    ]
+}
+
+fn write_rows(path: &Path, rows: &[Vec<Value>]) -> Result<(), WriteError> {
+    let mut writer = AvroFileWriter::new(BufWriter::new(File::create(path)?), &value_fields())?;
+    for (index, values) in rows.iter().enumerate() {
+        writer.write(FormatterContext::new(
+            Vec::new(),
+            Key::random(),
+            values.clone(),
+            Timestamp(2),
+            1,
+        ))?;
+        // Flush in the middle, so that several blocks are appended to the file
+        if index % 2 == 0 {
+            writer.flush(false)?;
+        }
+    }
+    writer.flush(true)
+}
+
+fn read_all_rows(reader: &mut AvroFileReader) -> eyre::Result<Vec<ReaderContext>> {
+    Ok(read_entries(reader)?
+        .into_iter()
+        .map(|(context, _)| context)
+        .collect())
+}
+
+fn expected_row(id: i64, name: Value) -> ReaderContext {
+    let field_names = ["id", "name", "created_at", "updated_at"];
+    let mut values: HashMap<String, Value> = field_names
+        .iter()
+        .map(|name| (*name).to_string())
+        .zip(row_values(id, name))
+        .collect();
+    values.insert("time".to_string(), Value::Int(2));
+    values.insert("diff".to_string(), Value::Int(1));
+    ReaderContext::from_diff(DataEventType::Insert, None, values.into())
+}
+
+#[test]
+fn test_avro_round_trip() -> eyre::Result<()> {
+    let test_storage = tempdir()?;
+    let path = test_storage.path().join("output.avro");
+    write_rows(
+        &path,
+        &[
+            row_values(1, Value::String("a".into())),
+            row_values(2, Value::None),
+            row_values(3, Value::String("c".into())),
+        ],
+    )?;
+
+    let mut reader = AvroFileReader::new(&path, None);
+    assert_eq!(
+        read_all_rows(&mut reader)?,
+        vec![
+            expected_row(1, Value::String("a".into())),
+            expected_row(2, Value::None),
+            expected_row(3, Value::String("c".into())),
+        ]
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_avro_seek() -> eyre::Result<()> {
+    let test_storage = tempdir()?;
+    let path = test_storage.path().join("output.avro");
+    write_rows(
+        &path,
+        &[
+            row_values(1, Value::String("a".into())),
+            row_values(2, Value::String("b".into())),
+        ],
+    )?;
+
+    let mut frontier = OffsetAntichain::new();
+    frontier.advance_offset(
+        OffsetKey::Empty,
+        OffsetValue::FilePosition {
+            total_entries_read: 1,
+            path: Arc::new(path.clone()),
+            bytes_offset: 0,
+        },
+    );
+
+    let mut reader = AvroFileReader::new(&path, None);
+    reader.seek(&frontier)?;
+    assert_eq!(
+        read_all_rows(&mut reader)?,
+        vec![expected_row(2, Value::String("b".into()))]
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_avro_type_mismatch() -> eyre::Result<()> {
+    let test_storage = tempdir()?;
+    let path = test_storage.path().join("output.avro");
+
+    let result = write_rows(&path, &[row_values(1, Value::Int(5))]);
+    assert!(matches!(result, Err(WriteError::AvroTypeMismatch(..))));
+
+    Ok(())
+}
@@ -0,0 +1,64 @@
+// Copyright © 2024 Pathway
+
+use std::sync::Arc;
+
+use pathway_engine::connectors::data_storage::{AzureBlobWriter, StorageType};
+use pathway_engine::connectors::{OffsetKey, OffsetValue};
+use pathway_engine::persistence::frontier::OffsetAntichain;
+
+fn blob_position(total_entries_read: u64, path: &str) -> OffsetValue {
+    OffsetValue::AzureBlobPosition {
+        total_entries_read,
+        path: Arc::new(path.to_string()),
+        bytes_offset: total_entries_read * 10,
+        last_modified: Some(1),
+    }
+}
+
+fn frontier(offset_value: OffsetValue) -> OffsetAntichain {
+    let mut frontier = OffsetAntichain::new();
+    frontier.advance_offset(OffsetKey::Empty, offset_value);
+    frontier
+}
+
+#[test]
+fn test_blob_positions_merged_by_entries_read() {
+    let earlier = blob_position(3, "prefix/b.csv");
+    let later = blob_position(5, "prefix/a.csv");
+    for storage_type in [StorageType::AzureBlobCsv, StorageType::AzureBlobLines] {
+        let merged =
+            storage_type.merge_two_frontiers(&frontier(earlier.clone()), &frontier(later.clone()));
+        assert_eq!(merged.get_offset(&OffsetKey::Empty), Some(&later));
+        let merged =
+            storage_type.merge_two_frontiers(&frontier(later.clone()), &frontier(earlier.clone()));
+        assert_eq!(merged.get_offset(&OffsetKey::Empty), Some(&later));
+    }
+}
+
+#[test]
+fn test_small_buffer_appended_as_one_block() {
+    assert_eq!(AzureBlobWriter::block_len(b"a\nbb\n"), 5);
+    assert_eq!(AzureBlobWriter::block_len(b""), 0);
+}
+
+#[test]
+fn test_blocks_end_at_row_boundaries() {
+    let row = vec![b'x'; AzureBlobWriter::MAX_BLOCK_SIZE / 3];
+    let mut buffer = Vec::new();
+    for _ in 0..4 {
+        buffer.extend_from_slice(&row);
+        buffer.push(b'\n');
+    }
+
+    // Three rows with their delimiters don't fit, so only two go to the first block
+    assert_eq!(AzureBlobWriter::block_len(&buffer), 2 * (row.len() + 1));
+}
+
+#[test]
+fn test_long_row_split_between_blocks() {
+    let buffer = vec![b'x'; AzureBlobWriter::MAX_BLOCK_SIZE + 1];
+    assert_eq!(
+        AzureBlobWriter::block_len(&buffer),
+        AzureBlobWriter::MAX_BLOCK_SIZE
+    );
+}
@@ -0,0 +1,103 @@
+// Copyright © 2024 Pathway
+
+use std::sync::Arc;
+
+use assert_matches::assert_matches;
+use serde_json::json;
+
+use pathway_engine::connectors::data_storage::{BigQueryWriter, WriteError};
+use pathway_engine::engine::{DateTimeNaive, DateTimeUtc, Duration, Type, Value};
+use pathway_engine::python_api::ValueField;
+
+fn value_field(name: &str, type_: Type, is_optional: bool) -> ValueField {
+    ValueField {
+        name: name.to_string(),
+        type_,
+        is_optional,
+        default: None,
+    }
+}
+
+#[test]
+fn test_bigquery_schema() -> eyre::Result<()> {
+    let schema = BigQueryWriter::construct_schema(&[
+        value_field("id", Type::Int, false),
+        value_field("name", Type::String, true),
+        value_field("created_at", Type::DateTimeUtc, false),
+        value_field("payload", Type::Json, true),
+    ])?;
+    assert_eq!(
+        schema,
+        json!({
+            "fields": [
+                {"name": "id", "type": "INT64", "mode": "REQUIRED"},
+                {"name": "name", "type": "STRING", "mode": "NULLABLE"},
+                {"name": "created_at", "type": "TIMESTAMP", "mode": "REQUIRED"},
+                {"name": "payload", "type": "JSON", "mode": "NULLABLE"},
+                {"name": "time", "type": "INT64", "mode": "REQUIRED"},
+                {"name": "diff", "type": "INT64", "mode": "REQUIRED"},
+            ]
+        })
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_bigquery_schema_unsupported_type() {
+    for type_ in [Type::Array, Type::Tuple, Type::PyObjectWrapper] {
+        let result = BigQueryWriter::construct_schema(&[value_field("data", type_, false)]);
+        assert_matches!(result, Err(WriteError::UnsupportedType(t)) if t == type_);
+    }
+}
+
+#[test]
+fn test_bigquery_values() -> eyre::Result<()> {
+    let cases = [
+        (Value::None, Type::Int, json!(null)),
+        (Value::Bool(true), Type::Bool, json!(true)),
+        (Value::Int(42), Type::Int, json!(42)),
+        (Value::Float(1.5.into()), Type::Float, json!(1.5)),
+        (Value::Float(f64::NAN.into()), Type::Float, json!("NaN")),
+        (
+            Value::Float(f64::NEG_INFINITY.into()),
+            Type::Float,
+            json!("-Infinity"),
+        ),
+        (Value::String("abc".into()), Type::String, json!("abc")),
+        (
+            Value::Bytes(Arc::from(&b"abc"[..])),
+            Type::Bytes,
+            json!("YWJj"),
+        ),
+        (
+            Value::DateTimeNaive(DateTimeNaive::new(1_500_000_000)),
+            Type::DateTimeNaive,
+            json!("1970-01-01T00:00:01.500000"),
+        ),
+        (
+            Value::DateTimeUtc(DateTimeUtc::new(1_500_000_000)),
+            Type::DateTimeUtc,
+            json!("1970-01-01T00:00:01.500000Z"),
+        ),
+        (
+            Value::Duration(Duration::new(2_000_000)),
+            Type::Duration,
+            json!(2000),
+        ),
+    ];
+    for (value, type_, expected) in cases {
+        assert_eq!(BigQueryWriter::bigquery_value(&value, type_)?, expected);
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_bigquery_tuple_value_unsupported() {
+    let value = Value::Tuple(Arc::from([Value::Int(1), Value::Int(2)]));
+    assert_matches!(
+        BigQueryWriter::bigquery_value(&value, Type::Tuple),
+        Err(WriteError::UnsupportedType(Type::Tuple))
+    );
+}
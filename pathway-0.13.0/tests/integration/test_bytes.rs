@@ -2,7 +2,7 @@
 
 use pathway_engine::connectors::data_format::{IdentityParser, ParseResult, ParsedEvent, Parser};
 use pathway_engine::connectors::data_storage::{
-    ConnectorMode, FilesystemReader, ReadMethod, ReadResult, Reader,
+    ConnectorMode, FilesystemReader, FilesystemReaderSettings, ReadMethod, ReadResult, Reader,
 };
 use pathway_engine::connectors::SessionType;
 use pathway_engine::engine::Value;
@@ -10,8 +10,23 @@ use pathway_engine::engine::Value;
 use crate::helpers::ReplaceErrors;
 
 fn read_bytes_from_path(path: &str) -> eyre::Result<Vec<ParsedEvent>> {
-    let mut reader =
-        FilesystemReader::new(path, ConnectorMode::Static, None, ReadMethod::Full, "*")?;
+    read_bytes_from_path_with_empty_files(path, false)
+}
+
+fn read_bytes_from_path_with_empty_files(
+    path: &str,
+    skip_empty_files: bool,
+) -> eyre::Result<Vec<ParsedEvent>> {
+    let mut reader = FilesystemReader::new(
+        &[path],
+        ConnectorMode::Static,
+        None,
+        ReadMethod::Full,
+        FilesystemReaderSettings {
+            skip_empty_files,
+            ..Default::default()
+        },
+    )?;
     let mut parser = IdentityParser::new(vec!["data".to_string()], false, SessionType::Native);
     let mut events = Vec::new();
 
@@ -72,6 +87,30 @@ fn test_empty_files_folder() -> eyre::Result<()> {
     Ok(())
 }
 
+#[test]
+fn test_empty_files_skipped() -> eyre::Result<()> {
+    assert_eq!(
+        read_bytes_from_path_with_empty_files("tests/data/empty_files/", true)?,
+        Vec::new()
+    );
+    assert_eq!(
+        read_bytes_from_path_with_empty_files("tests/data/empty", true)?,
+        Vec::new()
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_skip_empty_files_keeps_non_empty() -> eyre::Result<()> {
+    assert_eq!(
+        read_bytes_from_path_with_empty_files("tests/data/csvdir", true)?,
+        read_bytes_from_path("tests/data/csvdir")?
+    );
+
+    Ok(())
+}
+
 #[test]
 fn test_bytes_read_from_folder() -> eyre::Result<()> {
     let events = read_bytes_from_path("tests/data/csvdir")?;
@@ -0,0 +1,258 @@
+// Copyright © 2024 Pathway
+
+use super::helpers::{raw_bytes, read_entries};
+
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+use std::sync::Arc;
+
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use tempfile::tempdir;
+
+use pathway_engine::connectors::data_format::FormatterContext;
+use pathway_engine::connectors::data_storage::{
+    CompressedFileWriter, CompressionFormat, ConnectorMode, FilesystemReader,
+    FilesystemReaderSettings, PayloadFraming, ReadMethod, Reader, StorageType, Writer,
+};
+use pathway_engine::connectors::{OffsetKey, OffsetValue};
+use pathway_engine::engine::{Key, Timestamp};
+use pathway_engine::persistence::frontier::OffsetAntichain;
+
+const CONTENTS: &[u8] = b"a\nbb\nccc\n";
+
+fn write_gzip(path: &Path) -> eyre::Result<()> {
+    let mut encoder = GzEncoder::new(File::create(path)?, Compression::default());
+    encoder.write_all(CONTENTS)?;
+    encoder.finish()?;
+    Ok(())
+}
+
+fn write_zstd(path: &Path) -> eyre::Result<()> {
+    let mut encoder = zstd::stream::write::Encoder::new(File::create(path)?, 0)?;
+    encoder.write_all(CONTENTS)?;
+    encoder.finish()?;
+    Ok(())
+}
+
+fn reader_for(
+    path: &Path,
+    compression: Option<CompressionFormat>,
+) -> eyre::Result<FilesystemReader> {
+    Ok(FilesystemReader::new(
+        &[path.to_str().unwrap()],
+        ConnectorMode::Static,
+        None,
+        ReadMethod::ByLine,
+        FilesystemReaderSettings {
+            compression,
+            ..Default::default()
+        },
+    )?)
+}
+
+fn read_lines(reader: &mut FilesystemReader) -> eyre::Result<Vec<Vec<u8>>> {
+    Ok(read_entries(reader)?
+        .into_iter()
+        .map(|(context, _)| raw_bytes(context))
+        .collect())
+}
+
+fn expected_lines(lines: &[&[u8]]) -> Vec<Vec<u8>> {
+    lines.iter().map(|line| line.to_vec()).collect()
+}
+
+#[test]
+fn test_gzip_detected_by_extension() -> eyre::Result<()> {
+    let test_storage = tempdir()?;
+    let path = test_storage.path().join("input.gz");
+    write_gzip(&path)?;
+
+    let mut reader = reader_for(&path, None)?;
+    assert_eq!(
+        read_lines(&mut reader)?,
+        expected_lines(&[b"a\n", b"bb\n", b"ccc\n"])
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_zstd_explicit_format() -> eyre::Result<()> {
+    let test_storage = tempdir()?;
+    let path = test_storage.path().join("input.log");
+    write_zstd(&path)?;
+
+    let mut reader = reader_for(&path, Some(CompressionFormat::Zstd))?;
+    assert_eq!(
+        read_lines(&mut reader)?,
+        expected_lines(&[b"a\n", b"bb\n", b"ccc\n"])
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_compression_disabled_explicitly() -> eyre::Result<()> {
+    let test_storage = tempdir()?;
+    let path = test_storage.path().join("input.gz");
+    std::fs::write(&path, CONTENTS)?;
+
+    let mut reader = reader_for(&path, Some(CompressionFormat::None))?;
+    assert_eq!(
+        read_lines(&mut reader)?,
+        expected_lines(&[b"a\n", b"bb\n", b"ccc\n"])
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_gzip_seek() -> eyre::Result<()> {
+    let test_storage = tempdir()?;
+    let path = test_storage.path().join("input.gz");
+    write_gzip(&path)?;
+
+    let mut frontier = OffsetAntichain::new();
+    frontier.advance_offset(
+        OffsetKey::Empty,
+        OffsetValue::FilePosition {
+            total_entries_read: 2,
+            path: Arc::new(path.clone()),
+            bytes_offset: 5,
+        },
+    );
+
+    let mut reader = reader_for(&path, None)?;
+    reader.seek(&frontier)?;
+    assert_eq!(read_lines(&mut reader)?, expected_lines(&[b"ccc\n"]));
+
+    Ok(())
+}
+
+#[test]
+fn test_gzip_offsets_count_records() -> eyre::Result<()> {
+    let test_storage = tempdir()?;
+    let path = test_storage.path().join("input.gz");
+    write_gzip(&path)?;
+
+    let mut reader = reader_for(&path, None)?;
+    let offsets: Vec<_> = read_entries(&mut reader)?
+        .into_iter()
+        .map(|(_, (_, offset))| offset)
+        .collect();
+
+    let expected_offsets: Vec<_> = (1..=3)
+        .map(|entries_read| OffsetValue::RecordPosition {
+            total_entries_read: entries_read,
+            path: Arc::new(path.clone()),
+            entries_read_within_file: entries_read,
+        })
+        .collect();
+    assert_eq!(offsets, expected_offsets);
+
+    Ok(())
+}
+
+#[test]
+fn test_gzip_seek_by_records() -> eyre::Result<()> {
+    let test_storage = tempdir()?;
+    let path = test_storage.path().join("input.gz");
+    write_gzip(&path)?;
+
+    let mut frontier = OffsetAntichain::new();
+    frontier.advance_offset(
+        OffsetKey::Empty,
+        OffsetValue::RecordPosition {
+            total_entries_read: 2,
+            path: Arc::new(path.clone()),
+            entries_read_within_file: 2,
+        },
+    );
+
+    let mut reader = reader_for(&path, None)?;
+    reader.seek(&frontier)?;
+    assert_eq!(read_lines(&mut reader)?, expected_lines(&[b"ccc\n"]));
+
+    Ok(())
+}
+
+#[test]
+fn test_record_positions_merged_by_entries_read() {
+    let frontier = |offset_value: OffsetValue| {
+        let mut frontier = OffsetAntichain::new();
+        frontier.advance_offset(OffsetKey::Empty, offset_value);
+        frontier
+    };
+    let compressed = OffsetValue::RecordPosition {
+        total_entries_read: 5,
+        path: Arc::new("input.gz".into()),
+        entries_read_within_file: 2,
+    };
+    let uncompressed = OffsetValue::FilePosition {
+        total_entries_read: 3,
+        path: Arc::new("input.txt".into()),
+        bytes_offset: 100,
+    };
+
+    let merged = StorageType::FileSystem.merge_two_frontiers(
+        &frontier(uncompressed.clone()),
+        &frontier(compressed.clone()),
+    );
+    assert_eq!(merged.get_offset(&OffsetKey::Empty), Some(&compressed));
+    let merged = StorageType::FileSystem
+        .merge_two_frontiers(&frontier(compressed.clone()), &frontier(uncompressed));
+    assert_eq!(merged.get_offset(&OffsetKey::Empty), Some(&compressed));
+}
+
+fn write_compressed(path: &Path, compression: CompressionFormat) -> eyre::Result<()> {
+    let mut writer = CompressedFileWriter::new(
+        BufWriter::new(File::create(path)?),
+        compression,
+        PayloadFraming::Newline,
+    );
+    for (index, line) in [&b"a"[..], b"bb", b"ccc"].into_iter().enumerate() {
+        writer.write(FormatterContext::new_single_payload(
+            line.to_vec(),
+            Key::random(),
+            Vec::new(),
+            Timestamp(0),
+            1,
+        ))?;
+        // The non-forced flush must keep the stream open, while the forced one
+        // finishes it, so that the last line goes into a separate frame
+        writer.flush(index == 1)?;
+    }
+    Ok(())
+}
+
+#[test]
+fn test_gzip_writer_round_trip() -> eyre::Result<()> {
+    let test_storage = tempdir()?;
+    let path = test_storage.path().join("output.gz");
+    write_compressed(&path, CompressionFormat::Gzip)?;
+
+    let mut reader = reader_for(&path, None)?;
+    assert_eq!(
+        read_lines(&mut reader)?,
+        expected_lines(&[b"a\n", b"bb\n", b"ccc\n"])
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_zstd_writer_round_trip() -> eyre::Result<()> {
+    let test_storage = tempdir()?;
+    let path = test_storage.path().join("output.zst");
+    write_compressed(&path, CompressionFormat::Zstd)?;
+
+    let mut reader = reader_for(&path, None)?;
+    assert_eq!(
+        read_lines(&mut reader)?,
+        expected_lines(&[b"a\n", b"bb\n", b"ccc\n"])
+    );
+
+    Ok(())
+}
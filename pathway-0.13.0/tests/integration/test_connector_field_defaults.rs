@@ -8,7 +8,7 @@ use pathway_engine::connectors::data_format::{
     DsvParser, DsvSettings, InnerSchemaField, JsonLinesParser, ParsedEvent,
 };
 use pathway_engine::connectors::data_storage::{
-    ConnectorMode, CsvFilesystemReader, FilesystemReader, ReadMethod,
+    ConnectorMode, CsvFilesystemReader, FilesystemReader, FilesystemReaderSettings, ReadMethod,
 };
 use pathway_engine::connectors::SessionType;
 use pathway_engine::engine::{Type, Value};
@@ -26,11 +26,12 @@ fn test_dsv_with_default_end_of_line() -> eyre::Result<()> {
     );
 
     let reader = CsvFilesystemReader::new(
-        "tests/data/dsv_with_skips.txt",
+        &["tests/data/dsv_with_skips.txt"],
         builder,
         ConnectorMode::Static,
         None,
-        "*",
+        false,
+        FilesystemReaderSettings::default(),
     )?;
     let parser = DsvParser::new(
         DsvSettings::new(
@@ -80,11 +81,12 @@ fn test_dsv_with_default_middle_of_line() -> eyre::Result<()> {
     );
 
     let reader = CsvFilesystemReader::new(
-        "tests/data/dsv_with_skips2.txt",
+        &["tests/data/dsv_with_skips2.txt"],
         builder,
         ConnectorMode::Static,
         None,
-        "*",
+        false,
+        FilesystemReaderSettings::default(),
     )?;
     let parser = DsvParser::new(
         DsvSettings::new(
@@ -133,11 +135,12 @@ fn test_dsv_fails_without_default() -> eyre::Result<()> {
     );
 
     let reader = CsvFilesystemReader::new(
-        "tests/data/dsv_with_skips.txt",
+        &["tests/data/dsv_with_skips.txt"],
         builder,
         ConnectorMode::Static,
         None,
-        "*",
+        false,
+        FilesystemReaderSettings::default(),
     )?;
     let parser = DsvParser::new(
         DsvSettings::new(
@@ -187,11 +190,12 @@ fn test_dsv_with_default_nullable() -> eyre::Result<()> {
     );
 
     let reader = CsvFilesystemReader::new(
-        "tests/data/dsv_with_skips.txt",
+        &["tests/data/dsv_with_skips.txt"],
         builder,
         ConnectorMode::Static,
         None,
-        "*",
+        false,
+        FilesystemReaderSettings::default(),
     )?;
     let parser = DsvParser::new(
         DsvSettings::new(
@@ -231,11 +235,11 @@ fn test_dsv_with_default_nullable() -> eyre::Result<()> {
 #[test]
 fn test_jsonlines_fails_without_default() -> eyre::Result<()> {
     let reader = FilesystemReader::new(
-        "tests/data/jsonlines.txt",
+        &["tests/data/jsonlines.txt"],
         ConnectorMode::Static,
         None,
         ReadMethod::ByLine,
-        "*",
+        FilesystemReaderSettings::default(),
     )?;
     let parser = JsonLinesParser::new(
         Some(vec!["a".to_string()]),
@@ -278,11 +282,11 @@ fn test_jsonlines_with_default() -> eyre::Result<()> {
     );
 
     let reader = FilesystemReader::new(
-        "tests/data/jsonlines_with_skips.txt",
+        &["tests/data/jsonlines_with_skips.txt"],
         ConnectorMode::Static,
         None,
         ReadMethod::ByLine,
-        "*",
+        FilesystemReaderSettings::default(),
     )?;
     let parser = JsonLinesParser::new(
         Some(vec!["a".to_string()]),
@@ -331,11 +335,11 @@ fn test_jsonlines_with_default_at_jsonpath() -> eyre::Result<()> {
     );
 
     let reader = FilesystemReader::new(
-        "tests/data/jsonlines_with_skips.txt",
+        &["tests/data/jsonlines_with_skips.txt"],
         ConnectorMode::Static,
         None,
         ReadMethod::ByLine,
-        "*",
+        FilesystemReaderSettings::default(),
     )?;
     let parser = JsonLinesParser::new(
         Some(vec!["a".to_string()]),
@@ -378,11 +382,11 @@ fn test_jsonlines_explicit_null_not_overridden() -> eyre::Result<()> {
     );
 
     let reader = FilesystemReader::new(
-        "tests/data/jsonlines_with_skips_and_nulls.txt",
+        &["tests/data/jsonlines_with_skips_and_nulls.txt"],
         ConnectorMode::Static,
         None,
         ReadMethod::ByLine,
-        "*",
+        FilesystemReaderSettings::default(),
     )?;
     let parser = JsonLinesParser::new(
         Some(vec!["a".to_string()]),
@@ -0,0 +1,190 @@
+// Copyright © 2024 Pathway
+
+use super::helpers::{read_entries, tokenized_entry};
+
+use std::path::Path;
+
+use tempfile::tempdir;
+
+use pathway_engine::connectors::data_storage::{
+    ConnectorMode, CsvDialect, CsvFilesystemReader, CsvObjectReader, FilesystemReaderSettings,
+    Reader,
+};
+use pathway_engine::connectors::{OffsetKey, OffsetValue};
+use pathway_engine::persistence::frontier::OffsetAntichain;
+
+const CONTENTS: &[u8] = b"key;value\n'a;b';c\n# comment\nd;'e''f'\ng\n";
+
+fn dialect() -> CsvDialect {
+    CsvDialect {
+        delimiter: b';',
+        quote: b'\'',
+        comment: Some(b'#'),
+        ..CsvDialect::default()
+    }
+}
+
+fn tokens(values: &[&str]) -> Vec<String> {
+    values.iter().map(|value| (*value).to_string()).collect()
+}
+
+fn expected_records() -> Vec<Vec<String>> {
+    vec![
+        tokens(&["key", "value"]),
+        tokens(&["a;b", "c"]),
+        tokens(&["d", "e'f"]),
+        tokens(&["g"]),
+    ]
+}
+
+#[test]
+fn test_filesystem_reader_with_dialect() -> eyre::Result<()> {
+    let test_storage = tempdir()?;
+    let path = test_storage.path().join("input.csv");
+    std::fs::write(&path, CONTENTS)?;
+
+    let mut reader = CsvFilesystemReader::with_dialect(
+        &[path.to_str().unwrap()],
+        dialect(),
+        ConnectorMode::Static,
+        None,
+        false,
+        FilesystemReaderSettings::default(),
+    )?;
+    let records: Vec<_> = read_entries(&mut reader)?
+        .into_iter()
+        .map(|(context, _)| tokenized_entry(context).1)
+        .collect();
+    assert_eq!(records, expected_records());
+
+    Ok(())
+}
+
+#[test]
+fn test_object_reader_with_dialect() -> eyre::Result<()> {
+    // The S3 reader parses the objects in the same way as the filesystem reader
+    // parses the files, given the same dialect
+    let mut object_reader = CsvObjectReader::new(&dialect().parser_builder(), CONTENTS)?;
+    let mut records = vec![object_reader.header().unwrap().to_vec()];
+    while let Some(record) = object_reader.next_record()? {
+        records.push(record);
+    }
+    assert_eq!(records, expected_records());
+
+    Ok(())
+}
+
+#[test]
+fn test_dialect_without_flexible_records() -> eyre::Result<()> {
+    let dialect = CsvDialect {
+        flexible: false,
+        ..dialect()
+    };
+    let mut object_reader = CsvObjectReader::new(&dialect.parser_builder(), CONTENTS)?;
+    assert!(object_reader.next_record().is_ok());
+    assert!(object_reader.next_record().is_ok());
+    assert!(object_reader.next_record().is_err());
+
+    Ok(())
+}
+
+// The comment lines are interleaved with the records and the terminators are mixed
+const COMMENTED_CONTENTS: &[u8] =
+    b"# exported data\r\nkey,value\r\na,1\n# a comment\r\nb,2\r\n#\nc,3\n# trailing comment";
+
+fn commented_file_reader(path: &Path) -> eyre::Result<CsvFilesystemReader> {
+    let dialect = CsvDialect {
+        comment: Some(b'#'),
+        ..CsvDialect::default()
+    };
+    Ok(CsvFilesystemReader::with_dialect(
+        &[path.to_str().unwrap()],
+        dialect,
+        ConnectorMode::Static,
+        None,
+        true,
+        FilesystemReaderSettings::default(),
+    )?)
+}
+
+fn read_records_with_offsets(
+    reader: &mut CsvFilesystemReader,
+) -> eyre::Result<Vec<(Vec<String>, OffsetValue)>> {
+    Ok(read_entries(reader)?
+        .into_iter()
+        .map(|(context, (_, offset))| (tokenized_entry(context).1, offset))
+        .collect())
+}
+
+fn entries_read(offset: &OffsetValue) -> u64 {
+    match offset {
+        OffsetValue::FilePosition {
+            total_entries_read, ..
+        } => *total_entries_read,
+        other => panic!("unexpected offset {other:?}"),
+    }
+}
+
+#[test]
+fn test_comment_lines_not_counted_as_entries() -> eyre::Result<()> {
+    let test_storage = tempdir()?;
+    let path = test_storage.path().join("input.csv");
+    std::fs::write(&path, COMMENTED_CONTENTS)?;
+
+    let mut reader = commented_file_reader(&path)?;
+    let records = read_records_with_offsets(&mut reader)?;
+    assert_eq!(
+        records
+            .iter()
+            .map(|(tokens, offset)| (tokens.clone(), entries_read(offset)))
+            .collect::<Vec<_>>(),
+        vec![
+            (tokens(&["a", "1"]), 1),
+            (tokens(&["b", "2"]), 2),
+            (tokens(&["c", "3"]), 3),
+        ]
+    );
+    assert_eq!(
+        reader.discovered_header(),
+        Some(&tokens(&["key", "value"])[..])
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_seek_past_comment_lines() -> eyre::Result<()> {
+    let test_storage = tempdir()?;
+    let path = test_storage.path().join("input.csv");
+    std::fs::write(&path, COMMENTED_CONTENTS)?;
+
+    let mut reader = commented_file_reader(&path)?;
+    let records = read_records_with_offsets(&mut reader)?;
+    let mut frontier = OffsetAntichain::new();
+    frontier.advance_offset(OffsetKey::Empty, records[0].1.clone());
+
+    // The reading resumes right after the first record, skipping the comment that
+    // follows it, and the entries are counted from the persisted position
+    let mut reader = commented_file_reader(&path)?;
+    reader.seek(&frontier)?;
+    let resumed_records = read_records_with_offsets(&mut reader)?;
+    assert_eq!(resumed_records, records[1..]);
+
+    Ok(())
+}
+
+#[test]
+fn test_custom_terminator() -> eyre::Result<()> {
+    let dialect = CsvDialect {
+        terminator: csv::Terminator::Any(b'|'),
+        ..CsvDialect::default()
+    };
+    let mut object_reader =
+        CsvObjectReader::new(&dialect.parser_builder(), &b"key,value|a,\"1\n2\"|b,3|"[..])?;
+    assert_eq!(object_reader.header(), Some(&tokens(&["key", "value"])[..]));
+    assert_eq!(object_reader.next_record()?, Some(tokens(&["a", "1\n2"])));
+    assert_eq!(object_reader.next_record()?, Some(tokens(&["b", "3"])));
+    assert_eq!(object_reader.next_record()?, None);
+
+    Ok(())
+}
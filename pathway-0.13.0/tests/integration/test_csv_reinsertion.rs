@@ -0,0 +1,80 @@
+// Copyright © 2024 Pathway
+
+use super::helpers::{read_entries, tokenized_entry};
+
+use std::fs::File;
+use std::path::Path;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use tempfile::tempdir;
+
+use pathway_engine::connectors::data_storage::{
+    ConnectorMode, CsvFilesystemReader, DataEventType, FilesystemReaderSettings, Reader,
+    ShutdownSignal,
+};
+
+const RAGGED_CSV: &[u8] = b"key,value\n1\n2,b,extra\n";
+
+fn set_mtime(path: &Path, mtime: SystemTime) -> eyre::Result<()> {
+    File::options()
+        .write(true)
+        .open(path)?
+        .set_modified(mtime)?;
+    Ok(())
+}
+
+// The shutdown is requested beforehand, so that the reader returns what is
+// present in the directory instead of waiting for the new files
+fn read_available_entries(
+    reader: &mut CsvFilesystemReader,
+) -> eyre::Result<Vec<(DataEventType, Vec<String>)>> {
+    Ok(read_entries(reader)?
+        .into_iter()
+        .map(|(context, _)| tokenized_entry(context))
+        .collect())
+}
+
+fn expected_entries(event: DataEventType) -> Vec<(DataEventType, Vec<String>)> {
+    [&["key", "value"][..], &["1"], &["2", "b", "extra"]]
+        .into_iter()
+        .map(|tokens| (event, tokens.iter().map(|t| (*t).to_string()).collect()))
+        .collect()
+}
+
+#[test]
+fn test_ragged_csv_reinsertion() -> eyre::Result<()> {
+    let test_storage = tempdir()?;
+    let path = test_storage.path().join("input.csv");
+    std::fs::write(&path, RAGGED_CSV)?;
+    let initial_mtime = UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+    set_mtime(&path, initial_mtime)?;
+
+    let mut reader = CsvFilesystemReader::new(
+        &[test_storage.path().to_str().unwrap()],
+        csv::ReaderBuilder::new(),
+        ConnectorMode::Streaming,
+        None,
+        false,
+        FilesystemReaderSettings {
+            refresh_interval: Some(Duration::from_millis(10)),
+            ..Default::default()
+        },
+    )?;
+    let shutdown_signal = ShutdownSignal::default();
+    shutdown_signal.request();
+    reader.set_shutdown_signal(shutdown_signal);
+    assert_eq!(
+        read_available_entries(&mut reader)?,
+        expected_entries(DataEventType::Insert)
+    );
+
+    // The modification makes the file deleted and then inserted again. Both the
+    // deletion and the reinsertion must be parsed as leniently as the first read
+    std::fs::write(&path, RAGGED_CSV)?;
+    set_mtime(&path, initial_mtime + Duration::from_secs(10))?;
+    let mut expected = expected_entries(DataEventType::Delete);
+    expected.extend(expected_entries(DataEventType::Insert));
+    assert_eq!(read_available_entries(&mut reader)?, expected);
+
+    Ok(())
+}
@@ -0,0 +1,216 @@
+// Copyright © 2024 Pathway
+
+use std::collections::{HashMap, VecDeque};
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::Path;
+
+use assert_matches::assert_matches;
+use tempfile::tempdir;
+
+use pathway_engine::connectors::data_format::{Formatter, IdentityFormatter};
+use pathway_engine::connectors::data_storage::{
+    DeadLetterReader, DeadLetterWriter, DeltaTableWriteMode, DeltaTableWriter, FileWriter,
+    PayloadFraming, ReadError, ReadResult, Reader, ReaderBuilder, StorageType,
+    WrappedReaderBuilder, WriteError, Writer,
+};
+use pathway_engine::engine::{Key, Timestamp, Type, Value};
+use pathway_engine::persistence::frontier::OffsetAntichain;
+use pathway_engine::persistence::PersistentId;
+use pathway_engine::python_api::ValueField;
+
+struct ScriptedReader {
+    results: VecDeque<Result<ReadResult, ReadError>>,
+    max_consecutive_errors: usize,
+}
+
+impl Reader for ScriptedReader {
+    fn read(&mut self) -> Result<ReadResult, ReadError> {
+        self.results.pop_front().unwrap_or(Ok(ReadResult::Finished))
+    }
+
+    fn seek(&mut self, _frontier: &OffsetAntichain) -> Result<(), ReadError> {
+        Ok(())
+    }
+
+    fn update_persistent_id(&mut self, _persistent_id: Option<PersistentId>) {}
+
+    fn persistent_id(&self) -> Option<PersistentId> {
+        None
+    }
+
+    fn storage_type(&self) -> StorageType {
+        StorageType::FileSystem
+    }
+
+    fn max_allowed_consecutive_errors(&self) -> usize {
+        self.max_consecutive_errors
+    }
+}
+
+fn dead_letter_writer(path: &Path) -> eyre::Result<Box<dyn Writer>> {
+    Ok(Box::new(FileWriter::new(
+        BufWriter::new(File::create(path)?),
+        PayloadFraming::Newline,
+        None,
+        false,
+    )))
+}
+
+fn dead_letter_errors(path: &Path) -> eyre::Result<Vec<String>> {
+    let mut errors = Vec::new();
+    for line in std::fs::read_to_string(path)?.lines() {
+        let record: serde_json::Value = serde_json::from_str(line)?;
+        errors.push(record["error"].as_str().unwrap().to_string());
+    }
+    Ok(errors)
+}
+
+fn dead_letter_data(path: &Path) -> eyre::Result<Vec<String>> {
+    let mut data = Vec::new();
+    for line in std::fs::read_to_string(path)?.lines() {
+        let record: serde_json::Value = serde_json::from_str(line)?;
+        data.push(record["data"].as_str().unwrap().to_string());
+    }
+    Ok(data)
+}
+
+fn delta_writer(path: &Path) -> eyre::Result<Box<dyn Writer>> {
+    let value_fields = vec![ValueField {
+        name: "id".to_string(),
+        type_: Type::Int,
+        is_optional: false,
+        default: None,
+    }];
+    Ok(Box::new(DeltaTableWriter::new(
+        path.to_str().unwrap(),
+        &value_fields,
+        HashMap::new(),
+        None,
+        DeltaTableWriteMode::Append,
+        false,
+    )?))
+}
+
+fn write_values(writer: &mut dyn Writer, values: &[Value]) -> Result<(), WriteError> {
+    let mut formatter = IdentityFormatter::new();
+    for value in values {
+        let context = formatter
+            .format(&Key::random(), &[value.clone()], Timestamp(0), 1)
+            .expect("formatter failed");
+        writer.write(context)?;
+    }
+    Ok(())
+}
+
+#[test]
+fn test_dead_letter_writer_routes_mismatched_rows() -> eyre::Result<()> {
+    let test_storage = tempdir()?;
+    let dead_letter_path = test_storage.path().join("dead_letter.jsonl");
+
+    let mut writer = DeadLetterWriter::new(
+        delta_writer(&test_storage.path().join("table"))?,
+        dead_letter_writer(&dead_letter_path)?,
+        1,
+    );
+    write_values(
+        &mut writer,
+        &[Value::Int(1), Value::String("two".into()), Value::Int(3)],
+    )?;
+    writer.flush(true)?;
+
+    assert_eq!(writer.rows_written_since_last_flush(), 2);
+    let errors = dead_letter_errors(&dead_letter_path)?;
+    assert_eq!(errors.len(), 1);
+    assert!(errors[0].starts_with("type mismatch"), "{errors:?}");
+
+    Ok(())
+}
+
+#[test]
+fn test_dead_letter_writer_aborts_after_threshold() -> eyre::Result<()> {
+    let test_storage = tempdir()?;
+    let mut writer = DeadLetterWriter::new(
+        delta_writer(&test_storage.path().join("table"))?,
+        dead_letter_writer(&test_storage.path().join("dead_letter.jsonl"))?,
+        1,
+    );
+
+    let result = write_values(
+        &mut writer,
+        &[Value::String("one".into()), Value::String("two".into())],
+    );
+    assert_matches!(result, Err(WriteError::TypeMismatchWithSchema(..)));
+
+    Ok(())
+}
+
+#[test]
+fn test_dead_letter_reader_skips_malformed_entries() -> eyre::Result<()> {
+    let test_storage = tempdir()?;
+    let dead_letter_path = test_storage.path().join("dead_letter.jsonl");
+
+    let inner = ScriptedReader {
+        results: VecDeque::from([
+            Err(ReadError::MalformedData(b"first".to_vec())),
+            Ok(ReadResult::FinishedSource {
+                commit_allowed: true,
+            }),
+            Err(ReadError::MalformedData(b"second".to_vec())),
+            Err(ReadError::MalformedData(b"third".to_vec())),
+        ]),
+        max_consecutive_errors: 1,
+    };
+    let mut reader = DeadLetterReader::new(Box::new(inner), dead_letter_writer(&dead_letter_path)?);
+
+    assert_eq!(
+        reader.read()?,
+        ReadResult::FinishedSource {
+            commit_allowed: true
+        }
+    );
+    // The second error in a row exceeds the threshold of the inner reader
+    assert_matches!(reader.read(), Err(ReadError::MalformedData(entry)) if entry == b"third");
+    assert_eq!(reader.max_allowed_consecutive_errors(), 0);
+    assert_eq!(
+        dead_letter_errors(&dead_letter_path)?,
+        vec!["malformed data".to_string(), "malformed data".to_string()]
+    );
+    // The records carry the offending entries
+    assert_eq!(
+        dead_letter_data(&dead_letter_path)?,
+        vec!["first".to_string(), "second".to_string()]
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_wrapped_reader_builder_applies_dead_letter() -> eyre::Result<()> {
+    let test_storage = tempdir()?;
+    let dead_letter_path = test_storage.path().join("dead_letter.jsonl");
+
+    let inner = ScriptedReader {
+        results: VecDeque::from([Err(ReadError::MalformedData(b"first".to_vec()))]),
+        max_consecutive_errors: 1,
+    };
+    let dead_letter = dead_letter_writer(&dead_letter_path)?;
+    let builder = WrappedReaderBuilder::new(Box::new(inner), move |reader| {
+        Box::new(DeadLetterReader::new(reader, dead_letter))
+    });
+    // The reader is named after the wrapped one
+    assert!(
+        builder.short_description().ends_with("ScriptedReader"),
+        "{}",
+        builder.short_description()
+    );
+
+    let mut reader = Box::new(builder).build()?;
+    assert_eq!(reader.read()?, ReadResult::Finished);
+    assert_eq!(
+        dead_letter_data(&dead_letter_path)?,
+        vec!["first".to_string()]
+    );
+
+    Ok(())
+}
@@ -13,7 +13,7 @@ use pathway_engine::connectors::data_format::{
     DebeziumDBType, DebeziumMessageParser, ParseError, ParsedEvent, Parser,
 };
 use pathway_engine::connectors::data_storage::{
-    ConnectorMode, FilesystemReader, ReadMethod, ReaderContext,
+    ConnectorMode, FilesystemReader, FilesystemReaderSettings, ReadMethod, ReaderContext,
 };
 use pathway_engine::connectors::SessionType;
 use pathway_engine::engine::Value;
@@ -21,11 +21,11 @@ use pathway_engine::engine::Value;
 #[test]
 fn test_debezium_reads_ok() -> eyre::Result<()> {
     let reader = FilesystemReader::new(
-        "tests/data/sample_debezium.txt",
+        &["tests/data/sample_debezium.txt"],
         ConnectorMode::Static,
         None,
         ReadMethod::ByLine,
-        "*",
+        FilesystemReaderSettings::default(),
     )?;
     let parser = DebeziumMessageParser::new(
         Some(vec!["id".to_string()]),
@@ -171,11 +171,11 @@ fn test_debezium_tokens_amt_mismatch() -> eyre::Result<()> {
 #[test]
 fn test_debezium_mongodb_format() -> eyre::Result<()> {
     let reader = FilesystemReader::new(
-        "tests/data/sample_debezium_mongodb.txt",
+        &["tests/data/sample_debezium_mongodb.txt"],
         ConnectorMode::Static,
         None,
         ReadMethod::ByLine,
-        "*",
+        FilesystemReaderSettings::default(),
     )?;
     let parser = DebeziumMessageParser::new(
         Some(vec!["id".to_string()]),
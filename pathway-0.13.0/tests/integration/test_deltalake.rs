@@ -1,5 +1,7 @@
 // Copyright © 2024 Pathway
 
+use super::helpers::{diff_entry, read_entries};
+
 use std::collections::HashMap;
 use std::path::Path;
 
@@ -10,8 +12,13 @@ use serde_json::json;
 use tempfile::tempdir;
 
 use pathway_engine::connectors::data_format::{Formatter, IdentityFormatter};
-use pathway_engine::connectors::data_storage::{DeltaTableWriter, WriteError, Writer};
+use pathway_engine::connectors::data_storage::{
+    ConnectorMode, DataEventType, DeltaTableReader, DeltaTableWriteMode, DeltaTableWriter, Reader,
+    WriteError, Writer,
+};
+use pathway_engine::connectors::{OffsetKey, OffsetValue};
 use pathway_engine::engine::{DateTimeNaive, DateTimeUtc, Duration, Key, Timestamp, Type, Value};
+use pathway_engine::persistence::frontier::OffsetAntichain;
 use pathway_engine::python_api::ValueField;
 
 const BASE32_ALPHABET: base32::Alphabet = base32::Alphabet::Crockford;
@@ -32,6 +39,8 @@ fn run_single_column_save(type_: Type, values: &[Value]) -> Result<(), WriteErro
         &value_fields,
         HashMap::new(),
         None,
+        DeltaTableWriteMode::Append,
+        false,
     )?;
     let mut formatter = IdentityFormatter::new();
 
@@ -202,10 +211,574 @@ fn test_save_json() -> eyre::Result<()> {
 
 #[test]
 fn test_unsupported_types_fail_as_expected() -> eyre::Result<()> {
-    let unsupported_types = &[Type::Any, Type::Array, Type::PyObjectWrapper, Type::Tuple];
+    let unsupported_types = &[Type::Any, Type::PyObjectWrapper, Type::Tuple];
     for t in unsupported_types {
         let save_result = run_single_column_save(*t, &[]);
         assert_matches!(save_result, Err(WriteError::UnsupportedType(_)));
     }
     Ok(())
 }
+
+fn write_int_batches(path: &str, batches: &[&[i64]]) -> eyre::Result<()> {
+    let value_fields = vec![ValueField {
+        name: "id".to_string(),
+        type_: Type::Int,
+        is_optional: false,
+        default: None,
+    }];
+    let mut writer = DeltaTableWriter::new(
+        path,
+        &value_fields,
+        HashMap::new(),
+        None,
+        DeltaTableWriteMode::Append,
+        false,
+    )?;
+    let mut formatter = IdentityFormatter::new();
+    for batch in batches {
+        for id in *batch {
+            let context = formatter
+                .format(&Key::random(), &[Value::Int(*id)], Timestamp(0), 1)
+                .expect("formatter failed");
+            writer.write(context)?;
+        }
+        // Each forced flush commits a new version of the table
+        writer.flush(true)?;
+    }
+    Ok(())
+}
+
+fn read_ids(reader: &mut DeltaTableReader) -> eyre::Result<Vec<i64>> {
+    let mut ids = Vec::new();
+    for (context, _) in read_entries(reader)? {
+        let (event, values) = diff_entry(context);
+        assert_eq!(event, DataEventType::Insert);
+        match values.get("id") {
+            Some(Value::Int(id)) => ids.push(*id),
+            other => panic!("unexpected id value: {other:?}"),
+        }
+    }
+    ids.sort_unstable();
+    Ok(ids)
+}
+
+#[test]
+fn test_read_table_snapshot() -> eyre::Result<()> {
+    let test_storage = tempdir()?;
+    let path = test_storage.path().to_str().unwrap();
+    write_int_batches(path, &[&[1, 2], &[3]])?;
+
+    let mut reader =
+        DeltaTableReader::new(path, HashMap::new(), ConnectorMode::Static, None, None)?;
+    assert_eq!(read_ids(&mut reader)?, vec![1, 2, 3]);
+
+    Ok(())
+}
+
+#[test]
+fn test_read_table_seek() -> eyre::Result<()> {
+    let test_storage = tempdir()?;
+    let path = test_storage.path().to_str().unwrap();
+    write_int_batches(path, &[&[1, 2, 3]])?;
+
+    // Version 0 is the creation of the table, version 1 is the first commit
+    let mut frontier = OffsetAntichain::new();
+    frontier.advance_offset(
+        OffsetKey::Empty,
+        OffsetValue::DeltaTablePosition {
+            version: 1,
+            base_version: None,
+            rows_read_within_version: 1,
+        },
+    );
+
+    let mut reader =
+        DeltaTableReader::new(path, HashMap::new(), ConnectorMode::Static, None, None)?;
+    reader.seek(&frontier)?;
+    assert_eq!(read_ids(&mut reader)?.len(), 2);
+
+    Ok(())
+}
+
+fn int_value_fields(names: &[&str]) -> Vec<ValueField> {
+    names
+        .iter()
+        .map(|name| ValueField {
+            name: (*name).to_string(),
+            type_: Type::Int,
+            is_optional: false,
+            default: None,
+        })
+        .collect()
+}
+
+#[test]
+fn test_write_with_different_column_order() -> eyre::Result<()> {
+    let test_storage = tempdir()?;
+    let path = test_storage.path().to_str().unwrap();
+    DeltaTableWriter::new(
+        path,
+        &int_value_fields(&["a", "b"]),
+        HashMap::new(),
+        None,
+        DeltaTableWriteMode::Append,
+        false,
+    )?;
+
+    let mut writer = DeltaTableWriter::new(
+        path,
+        &int_value_fields(&["b", "a"]),
+        HashMap::new(),
+        None,
+        DeltaTableWriteMode::Append,
+        false,
+    )?;
+    let mut formatter = IdentityFormatter::new();
+    let context = formatter
+        .format(
+            &Key::random(),
+            &[Value::Int(2), Value::Int(1)],
+            Timestamp(0),
+            1,
+        )
+        .expect("formatter failed");
+    writer.write(context)?;
+    writer.flush(true)?;
+
+    let mut reader =
+        DeltaTableReader::new(path, HashMap::new(), ConnectorMode::Static, None, None)?;
+    let rows: Vec<_> = read_entries(&mut reader)?
+        .into_iter()
+        .map(|(context, _)| {
+            let (_, values) = diff_entry(context);
+            (values.get("a").cloned(), values.get("b").cloned())
+        })
+        .collect();
+    assert_eq!(rows, vec![(Some(Value::Int(1)), Some(Value::Int(2)))]);
+
+    Ok(())
+}
+
+#[test]
+fn test_write_with_missing_column() -> eyre::Result<()> {
+    let test_storage = tempdir()?;
+    let path = test_storage.path().to_str().unwrap();
+    DeltaTableWriter::new(
+        path,
+        &int_value_fields(&["a"]),
+        HashMap::new(),
+        None,
+        DeltaTableWriteMode::Append,
+        false,
+    )?;
+
+    let result = DeltaTableWriter::new(
+        path,
+        &int_value_fields(&["a", "c"]),
+        HashMap::new(),
+        None,
+        DeltaTableWriteMode::Append,
+        false,
+    );
+    assert!(matches!(result, Err(WriteError::DeltaTableColumnMissing(name)) if name == "c"));
+
+    Ok(())
+}
+
+#[test]
+fn test_write_with_several_schema_mismatches() -> eyre::Result<()> {
+    let test_storage = tempdir()?;
+    let path = test_storage.path().to_str().unwrap();
+    DeltaTableWriter::new(
+        path,
+        &int_value_fields(&["a", "b"]),
+        HashMap::new(),
+        None,
+        DeltaTableWriteMode::Append,
+        false,
+    )?;
+
+    let mut value_fields = int_value_fields(&["a", "c"]);
+    value_fields[0].type_ = Type::String;
+    let result = DeltaTableWriter::new(
+        path,
+        &value_fields,
+        HashMap::new(),
+        None,
+        DeltaTableWriteMode::Append,
+        false,
+    );
+    let Err(WriteError::DeltaTableSchemaMismatch(mismatches)) = result else {
+        panic!("all schema mismatches must be reported together");
+    };
+    assert_eq!(mismatches.len(), 3);
+    assert_matches!(
+        &mismatches[0],
+        WriteError::DeltaTableColumnTypeMismatch { name, .. } if name == "a"
+    );
+    assert_matches!(&mismatches[1], WriteError::DeltaTableColumnMissing(name) if name == "c");
+    assert_matches!(
+        &mismatches[2],
+        WriteError::DeltaTableRequiredColumnNotWritten(name) if name == "b"
+    );
+
+    Ok(())
+}
+
+fn write_upsert_batches(path: &str, batches: &[&[(i64, i64, isize)]]) -> eyre::Result<()> {
+    let mut writer = DeltaTableWriter::new(
+        path,
+        &int_value_fields(&["id", "value"]),
+        HashMap::new(),
+        None,
+        DeltaTableWriteMode::Upsert {
+            key_columns: vec!["id".to_string()],
+        },
+        false,
+    )?;
+    let mut formatter = IdentityFormatter::new();
+    for batch in batches {
+        for (id, value, diff) in *batch {
+            let context = formatter
+                .format(
+                    &Key::random(),
+                    &[Value::Int(*id), Value::Int(*value)],
+                    Timestamp(2),
+                    *diff,
+                )
+                .expect("formatter failed");
+            writer.write(context)?;
+        }
+        writer.flush(true)?;
+    }
+    Ok(())
+}
+
+fn read_current_state(path: &str) -> eyre::Result<Vec<(Value, Value)>> {
+    let mut reader =
+        DeltaTableReader::new(path, HashMap::new(), ConnectorMode::Static, None, None)?;
+    let mut rows = Vec::new();
+    for (context, _) in read_entries(&mut reader)? {
+        let (event, values) = diff_entry(context);
+        let row = (
+            values.get("id").unwrap().clone(),
+            values.get("value").unwrap().clone(),
+        );
+        match event {
+            DataEventType::Insert => rows.push(row),
+            DataEventType::Delete => {
+                let position = rows.iter().position(|r| *r == row).unwrap();
+                rows.remove(position);
+            }
+            DataEventType::Upsert => panic!("unexpected upsert event"),
+        }
+    }
+    rows.sort();
+    Ok(rows)
+}
+
+#[test]
+fn test_upsert_mode_keeps_current_state() -> eyre::Result<()> {
+    let test_storage = tempdir()?;
+    let path = test_storage.path().to_str().unwrap();
+    write_upsert_batches(
+        path,
+        &[
+            &[(1, 10, 1), (2, 20, 1), (3, 30, 1)],
+            &[(1, 10, -1), (2, 21, 1), (2, 20, -1)],
+        ],
+    )?;
+
+    assert_eq!(
+        read_current_state(path)?,
+        vec![
+            (Value::Int(2), Value::Int(21)),
+            (Value::Int(3), Value::Int(30)),
+        ]
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_upsert_mode_validates_key_columns() -> eyre::Result<()> {
+    let test_storage = tempdir()?;
+    let path = test_storage.path().to_str().unwrap();
+
+    let result = DeltaTableWriter::new(
+        path,
+        &int_value_fields(&["id", "value"]),
+        HashMap::new(),
+        None,
+        DeltaTableWriteMode::Upsert {
+            key_columns: vec!["key".to_string()],
+        },
+        false,
+    );
+    assert!(matches!(result, Err(WriteError::DeltaTableKeyColumnMissing(name)) if name == "key"));
+
+    let result = DeltaTableWriter::new(
+        path,
+        &int_value_fields(&["id", "value"]),
+        HashMap::new(),
+        None,
+        DeltaTableWriteMode::Upsert {
+            key_columns: Vec::new(),
+        },
+        false,
+    );
+    assert!(matches!(result, Err(WriteError::DeltaTableNoKeyColumns)));
+
+    Ok(())
+}
+
+#[test]
+fn test_rows_written_since_last_flush() -> eyre::Result<()> {
+    let test_storage = tempdir()?;
+    let value_fields = vec![ValueField {
+        name: "id".to_string(),
+        type_: Type::Int,
+        is_optional: false,
+        default: None,
+    }];
+    let mut writer = DeltaTableWriter::new(
+        test_storage.path().to_str().unwrap(),
+        &value_fields,
+        HashMap::new(),
+        None,
+        DeltaTableWriteMode::Append,
+        false,
+    )?;
+    let mut formatter = IdentityFormatter::new();
+    assert_eq!(writer.rows_written_since_last_flush(), 0);
+
+    for id in [1, 2, 3] {
+        let context = formatter
+            .format(&Key::random(), &[Value::Int(id)], Timestamp(0), 1)
+            .expect("formatter failed");
+        writer.write(context)?;
+    }
+    // The rows are only counted once they are committed
+    assert_eq!(writer.rows_written_since_last_flush(), 0);
+    writer.flush(true)?;
+    assert_eq!(writer.rows_written_since_last_flush(), 3);
+
+    // A flush without the buffered rows doesn't write anything
+    writer.flush(true)?;
+    assert_eq!(writer.rows_written_since_last_flush(), 0);
+
+    Ok(())
+}
+
+fn read_deletion_flags(path: &str) -> Vec<(i64, bool)> {
+    let table = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .unwrap()
+        .block_on(deltalake::open_table(path))
+        .unwrap();
+    let mut rows = Vec::new();
+    for file_name in table.get_file_uris().unwrap() {
+        let full_path = Path::new(path).join(file_name);
+        let reader = SerializedFileReader::try_from(full_path.as_path()).unwrap();
+        for row in reader {
+            let mut id = None;
+            let mut is_deleted = None;
+            for (name, field) in row.expect("row reading failed").get_column_iter() {
+                match (name.as_str(), field) {
+                    ("id", ParquetField::Long(value)) => id = Some(*value),
+                    ("_pw_is_deleted", ParquetField::Bool(value)) => is_deleted = Some(*value),
+                    _ => {}
+                }
+            }
+            rows.push((id.unwrap(), is_deleted.unwrap()));
+        }
+    }
+    rows.sort_unstable();
+    rows
+}
+
+#[test]
+fn test_append_mode_deletion_flag() -> eyre::Result<()> {
+    let test_storage = tempdir()?;
+    let path = test_storage.path().to_str().unwrap();
+    let mut writer = DeltaTableWriter::new(
+        path,
+        &int_value_fields(&["id"]),
+        HashMap::new(),
+        None,
+        DeltaTableWriteMode::Append,
+        true,
+    )?;
+    let mut formatter = IdentityFormatter::new();
+    for (id, diff) in [(1, 1), (2, 1), (1, -1)] {
+        let context = formatter
+            .format(&Key::random(), &[Value::Int(id)], Timestamp(0), diff)
+            .expect("formatter failed");
+        writer.write(context)?;
+    }
+    writer.flush(true)?;
+
+    assert_eq!(
+        read_deletion_flags(path),
+        vec![(1, false), (1, true), (2, false)]
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_deletion_flag_rejected_in_upsert_mode() {
+    let test_storage = tempdir().expect("tempdir creation failed");
+    let result = DeltaTableWriter::new(
+        test_storage.path().to_str().unwrap(),
+        &int_value_fields(&["id", "value"]),
+        HashMap::new(),
+        None,
+        DeltaTableWriteMode::Upsert {
+            key_columns: vec!["id".to_string()],
+        },
+        true,
+    );
+    assert!(matches!(
+        result,
+        Err(WriteError::DeltaTableDeletionFlagInUpsertMode)
+    ));
+}
+
+#[test]
+fn test_array_column_round_trip() -> eyre::Result<()> {
+    let test_storage = tempdir()?;
+    let path = test_storage.path().to_str().unwrap();
+    let value_fields = vec![ValueField {
+        name: "values".to_string(),
+        type_: Type::Array,
+        is_optional: true,
+        default: None,
+    }];
+    let mut writer = DeltaTableWriter::new(
+        path,
+        &value_fields,
+        HashMap::new(),
+        None,
+        DeltaTableWriteMode::Append,
+        false,
+    )?;
+    let mut formatter = IdentityFormatter::new();
+    let values = [
+        Value::from(ndarray::arr1(&[1.5, -2.0]).into_dyn()),
+        Value::from(ndarray::arr1(&[3_i64, 4, 5]).into_dyn()),
+        Value::from(ndarray::Array1::<f64>::zeros(0).into_dyn()),
+        Value::None,
+    ];
+    for value in &values {
+        let context = formatter
+            .format(&Key::random(), &[value.clone()], Timestamp(0), 1)
+            .expect("formatter failed");
+        writer.write(context)?;
+    }
+
+    // Only the one-dimensional arrays fit into a list column
+    let context = formatter
+        .format(
+            &Key::random(),
+            &[Value::from(ndarray::arr2(&[[1.0], [2.0]]).into_dyn())],
+            Timestamp(0),
+            1,
+        )
+        .expect("formatter failed");
+    assert!(matches!(
+        writer.write(context),
+        Err(WriteError::MultidimensionalArray(shape)) if shape == vec![2, 1]
+    ));
+    writer.flush(true)?;
+
+    let mut reader =
+        DeltaTableReader::new(path, HashMap::new(), ConnectorMode::Static, None, None)?;
+    let reread_values: Vec<_> = read_entries(&mut reader)?
+        .into_iter()
+        .map(|(context, _)| diff_entry(context).1.get("values").unwrap().clone())
+        .collect();
+
+    // The integer arrays are stored as the float ones
+    assert_eq!(
+        reread_values,
+        vec![
+            values[0].clone(),
+            Value::from(ndarray::arr1(&[3.0, 4.0, 5.0]).into_dyn()),
+            values[2].clone(),
+            Value::None,
+        ]
+    );
+
+    Ok(())
+}
+
+fn write_single_column(path: &str, type_: Type, values: &[Value]) -> eyre::Result<()> {
+    let value_fields = vec![ValueField {
+        name: "field".to_string(),
+        type_,
+        is_optional: true,
+        default: None,
+    }];
+    let mut writer = DeltaTableWriter::new(
+        path,
+        &value_fields,
+        HashMap::new(),
+        None,
+        DeltaTableWriteMode::Append,
+        false,
+    )?;
+    let mut formatter = IdentityFormatter::new();
+    for value in values {
+        let context = formatter
+            .format(&Key::random(), &[value.clone()], Timestamp(0), 1)
+            .expect("formatter failed");
+        writer.write(context)?;
+    }
+    writer.flush(true)?;
+    Ok(())
+}
+
+fn read_single_column(path: &str) -> eyre::Result<Vec<Value>> {
+    let mut reader =
+        DeltaTableReader::new(path, HashMap::new(), ConnectorMode::Static, None, None)?;
+    Ok(read_entries(&mut reader)?
+        .into_iter()
+        .map(|(context, _)| diff_entry(context).1.get("field").unwrap().clone())
+        .collect())
+}
+
+#[test]
+fn test_duration_column_round_trip() -> eyre::Result<()> {
+    let test_storage = tempdir()?;
+    let path = test_storage.path().to_str().unwrap();
+    let values = [
+        Value::Duration(Duration::new(1_500_000)),
+        Value::Duration(Duration::new(-10_000_000_000_000)),
+        Value::None,
+    ];
+    write_single_column(path, Type::Duration, &values)?;
+    assert_eq!(read_single_column(path)?, values);
+
+    Ok(())
+}
+
+#[test]
+fn test_duration_written_into_int_column() -> eyre::Result<()> {
+    // A table created before the duration columns were marked as such
+    let test_storage = tempdir()?;
+    let path = test_storage.path().to_str().unwrap();
+    write_single_column(path, Type::Int, &[Value::Int(1)])?;
+
+    write_single_column(
+        path,
+        Type::Duration,
+        &[Value::Duration(Duration::new(2_000))],
+    )?;
+    let mut reread_values = read_single_column(path)?;
+    reread_values.sort();
+    assert_eq!(reread_values, vec![Value::Int(1), Value::Int(2)]);
+
+    Ok(())
+}
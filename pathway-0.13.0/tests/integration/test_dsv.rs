@@ -12,18 +12,19 @@ use pathway_engine::connectors::data_format::{
     DsvParser, DsvSettings, InnerSchemaField, ParseResult, ParsedEvent, Parser,
 };
 use pathway_engine::connectors::data_storage::{
-    ConnectorMode, FilesystemReader, ReadMethod, ReadResult, ReadResult::Data, Reader,
+    ConnectorMode, FilesystemReader, FilesystemReaderSettings, ReadMethod, ReadResult,
+    ReadResult::Data, Reader,
 };
 use pathway_engine::engine::{Key, Type, Value};
 
 #[test]
 fn test_dsv_read_ok() -> eyre::Result<()> {
     let mut reader = FilesystemReader::new(
-        "tests/data/sample.txt",
+        &["tests/data/sample.txt"],
         ConnectorMode::Static,
         None,
         ReadMethod::ByLine,
-        "*",
+        FilesystemReaderSettings::default(),
     )?;
     let mut parser = DsvParser::new(
         DsvSettings::new(Some(vec!["a".to_string()]), vec!["b".to_string()], ','),
@@ -67,11 +68,11 @@ fn test_dsv_read_ok() -> eyre::Result<()> {
 #[test]
 fn test_dsv_column_does_not_exist() -> eyre::Result<()> {
     let reader = FilesystemReader::new(
-        "tests/data/sample.txt",
+        &["tests/data/sample.txt"],
         ConnectorMode::Static,
         None,
         ReadMethod::ByLine,
-        "*",
+        FilesystemReaderSettings::default(),
     )?;
     let parser = DsvParser::new(
         DsvSettings::new(Some(vec!["a".to_string()]), vec!["c".to_string()], ','),
@@ -91,11 +92,11 @@ fn test_dsv_column_does_not_exist() -> eyre::Result<()> {
 #[test]
 fn test_dsv_rows_parsing_ignore_type() -> eyre::Result<()> {
     let mut reader = FilesystemReader::new(
-        "tests/data/sample_str_int.txt",
+        &["tests/data/sample_str_int.txt"],
         ConnectorMode::Static,
         None,
         ReadMethod::ByLine,
-        "*",
+        FilesystemReaderSettings::default(),
     )?;
     let mut parser = DsvParser::new(
         DsvSettings::new(Some(vec!["a".to_string()]), vec!["b".to_string()], ','),
@@ -127,11 +128,11 @@ fn test_dsv_rows_parsing_ignore_type() -> eyre::Result<()> {
 #[test]
 fn test_dsv_not_enough_columns() -> eyre::Result<()> {
     let mut reader = FilesystemReader::new(
-        "tests/data/sample_bad_lines.txt",
+        &["tests/data/sample_bad_lines.txt"],
         ConnectorMode::Static,
         None,
         ReadMethod::ByLine,
-        "*",
+        FilesystemReaderSettings::default(),
     )?;
     let mut parser = DsvParser::new(
         DsvSettings::new(Some(vec!["a".to_string()]), vec!["b".to_string()], ','),
@@ -173,11 +174,11 @@ fn test_dsv_not_enough_columns() -> eyre::Result<()> {
 #[test]
 fn test_dsv_autogenerate_pkey() -> eyre::Result<()> {
     let mut reader = FilesystemReader::new(
-        "tests/data/sample.txt",
+        &["tests/data/sample.txt"],
         ConnectorMode::Static,
         None,
         ReadMethod::ByLine,
-        "*",
+        FilesystemReaderSettings::default(),
     )?;
     let mut parser = DsvParser::new(
         DsvSettings::new(None, vec!["a".to_string(), "b".to_string()], ','),
@@ -217,11 +218,11 @@ fn test_dsv_autogenerate_pkey() -> eyre::Result<()> {
 #[test]
 fn test_dsv_composite_pkey() -> eyre::Result<()> {
     let mut reader = FilesystemReader::new(
-        "tests/data/sample_composite_pkey.txt",
+        &["tests/data/sample_composite_pkey.txt"],
         ConnectorMode::Static,
         None,
         ReadMethod::ByLine,
-        "*",
+        FilesystemReaderSettings::default(),
     )?;
     let mut parser = DsvParser::new(
         DsvSettings::new(
@@ -287,11 +288,11 @@ fn test_dsv_read_schema_ok() -> eyre::Result<()> {
     );
 
     let mut reader = FilesystemReader::new(
-        "tests/data/schema.txt",
+        &["tests/data/schema.txt"],
         ConnectorMode::Static,
         None,
         ReadMethod::ByLine,
-        "*",
+        FilesystemReaderSettings::default(),
     )?;
     let mut parser = DsvParser::new(
         DsvSettings::new(
@@ -367,11 +368,11 @@ fn test_dsv_read_schema_nonparsable() -> eyre::Result<()> {
     );
 
     let mut reader = FilesystemReader::new(
-        "tests/data/incorrect_types.txt",
+        &["tests/data/incorrect_types.txt"],
         ConnectorMode::Static,
         None,
         ReadMethod::ByLine,
-        "*",
+        FilesystemReaderSettings::default(),
     )?;
     let mut parser = DsvParser::new(
         DsvSettings::new(
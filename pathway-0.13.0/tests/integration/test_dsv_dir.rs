@@ -1,13 +1,18 @@
 // Copyright © 2024 Pathway
 
-use super::helpers::read_data_from_reader;
+use super::helpers::{read_data_from_reader, read_entries, tokenized_entry};
 
 use std::collections::HashMap;
+use std::sync::Arc;
 
 use pathway_engine::connectors::data_format::ParsedEvent;
 use pathway_engine::connectors::data_format::{DsvParser, DsvSettings};
-use pathway_engine::connectors::data_storage::{ConnectorMode, CsvFilesystemReader};
+use pathway_engine::connectors::data_storage::{
+    ConnectorMode, CsvFilesystemReader, FilesystemReaderSettings, Reader,
+};
+use pathway_engine::connectors::{OffsetKey, OffsetValue};
 use pathway_engine::engine::Value;
+use pathway_engine::persistence::frontier::OffsetAntichain;
 
 #[test]
 fn test_dsv_dir_ok() -> eyre::Result<()> {
@@ -15,11 +20,12 @@ fn test_dsv_dir_ok() -> eyre::Result<()> {
     builder.has_headers(false);
 
     let reader = CsvFilesystemReader::new(
-        "tests/data/csvdir",
+        &["tests/data/csvdir"],
         builder,
         ConnectorMode::Static,
         None,
-        "*",
+        false,
+        FilesystemReaderSettings::default(),
     )?;
     let parser = DsvParser::new(
         DsvSettings::new(Some(vec!["key".to_string()]), vec!["foo".to_string()], ','),
@@ -48,11 +54,12 @@ fn test_single_file_ok() -> eyre::Result<()> {
     builder.has_headers(false);
 
     let reader = CsvFilesystemReader::new(
-        "tests/data/sample.txt",
+        &["tests/data/sample.txt"],
         builder,
         ConnectorMode::Static,
         None,
-        "*",
+        false,
+        FilesystemReaderSettings::default(),
     )?;
     let parser = DsvParser::new(
         DsvSettings::new(Some(vec!["a".to_string()]), vec!["b".to_string()], ','),
@@ -73,11 +80,12 @@ fn test_custom_delimiter() -> eyre::Result<()> {
     builder.has_headers(false);
 
     let reader = CsvFilesystemReader::new(
-        "tests/data/sql_injection.txt",
+        &["tests/data/sql_injection.txt"],
         builder,
         ConnectorMode::Static,
         None,
-        "*",
+        false,
+        FilesystemReaderSettings::default(),
     )?;
     let parser = DsvParser::new(
         DsvSettings::new(
@@ -100,11 +108,12 @@ fn test_escape_fields() -> eyre::Result<()> {
     builder.has_headers(false);
 
     let reader = CsvFilesystemReader::new(
-        "tests/data/csv_fields_escaped.txt",
+        &["tests/data/csv_fields_escaped.txt"],
         builder,
         ConnectorMode::Static,
         None,
-        "*",
+        false,
+        FilesystemReaderSettings::default(),
     )?;
     let parser = DsvParser::new(
         DsvSettings::new(
@@ -146,11 +155,12 @@ fn test_escape_newlines() -> eyre::Result<()> {
     builder.has_headers(false);
 
     let reader = CsvFilesystemReader::new(
-        "tests/data/csv_escaped_newlines.txt",
+        &["tests/data/csv_escaped_newlines.txt"],
         builder,
         ConnectorMode::Static,
         None,
-        "*",
+        false,
+        FilesystemReaderSettings::default(),
     )?;
     let parser = DsvParser::new(
         DsvSettings::new(
@@ -182,11 +192,12 @@ fn test_nonexistent_file() -> eyre::Result<()> {
     builder.has_headers(false);
 
     let reader = CsvFilesystemReader::new(
-        "tests/data/nonexistent_file.txt",
+        &["tests/data/nonexistent_file.txt"],
         builder,
         ConnectorMode::Static,
         None,
-        "*",
+        false,
+        FilesystemReaderSettings::default(),
     );
 
     // We treat this path as a glob pattern, so the situation is normal:
@@ -203,11 +214,12 @@ fn test_special_fields() -> eyre::Result<()> {
     builder.has_headers(false);
 
     let reader = CsvFilesystemReader::new(
-        "tests/data/csv_special_fields.txt",
+        &["tests/data/csv_special_fields.txt"],
         builder,
         ConnectorMode::Static,
         None,
-        "*",
+        false,
+        FilesystemReaderSettings::default(),
     )?;
     let parser = DsvParser::new(
         DsvSettings::new(
@@ -231,3 +243,104 @@ fn test_special_fields() -> eyre::Result<()> {
 
     Ok(())
 }
+
+fn parser_builder() -> csv::ReaderBuilder {
+    let mut builder = csv::ReaderBuilder::new();
+    builder.has_headers(false);
+    builder
+}
+
+fn read_records(reader: &mut CsvFilesystemReader) -> eyre::Result<Vec<Vec<String>>> {
+    Ok(read_entries(reader)?
+        .into_iter()
+        .map(|(context, _)| tokenized_entry(context).1)
+        .collect())
+}
+
+fn records(rows: &[[&str; 2]]) -> Vec<Vec<String>> {
+    rows.iter()
+        .map(|row| row.iter().map(|token| (*token).to_string()).collect())
+        .collect()
+}
+
+#[test]
+fn test_header_discovered() -> eyre::Result<()> {
+    let mut reader = CsvFilesystemReader::new(
+        &["tests/data/csvdir"],
+        parser_builder(),
+        ConnectorMode::Static,
+        None,
+        true,
+        FilesystemReaderSettings::default(),
+    )?;
+    assert_eq!(reader.discovered_header(), None);
+
+    // The headers of all files are skipped, not only the first one
+    assert_eq!(
+        read_records(&mut reader)?,
+        records(&[
+            ["1", "abc"],
+            ["2", "def"],
+            ["3", "ghi"],
+            ["4", "jkl"],
+            ["5", "mno"],
+            ["6", "pqr"],
+        ])
+    );
+    assert_eq!(
+        reader.discovered_header(),
+        Some(&["key".to_string(), "foo".to_string()][..])
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_header_not_discovered_by_default() -> eyre::Result<()> {
+    let mut reader = CsvFilesystemReader::new(
+        &["tests/data/csvdir/a.txt"],
+        parser_builder(),
+        ConnectorMode::Static,
+        None,
+        false,
+        FilesystemReaderSettings::default(),
+    )?;
+    assert_eq!(
+        read_records(&mut reader)?,
+        records(&[["key", "foo"], ["1", "abc"], ["2", "def"]])
+    );
+    assert_eq!(reader.discovered_header(), None);
+
+    Ok(())
+}
+
+#[test]
+fn test_header_skipped_after_seek() -> eyre::Result<()> {
+    let path = std::path::PathBuf::from("tests/data/csvdir/a.txt");
+    let mut frontier = OffsetAntichain::new();
+    frontier.advance_offset(
+        OffsetKey::Empty,
+        OffsetValue::FilePosition {
+            total_entries_read: 1,
+            path: Arc::new(path),
+            bytes_offset: "key,foo\n1,abc\n".len() as u64,
+        },
+    );
+
+    let mut reader = CsvFilesystemReader::new(
+        &["tests/data/csvdir/a.txt"],
+        parser_builder(),
+        ConnectorMode::Static,
+        None,
+        true,
+        FilesystemReaderSettings::default(),
+    )?;
+    reader.seek(&frontier)?;
+    assert_eq!(read_records(&mut reader)?, records(&[["2", "def"]]));
+    assert_eq!(
+        reader.discovered_header(),
+        Some(&["key".to_string(), "foo".to_string()][..])
+    );
+
+    Ok(())
+}
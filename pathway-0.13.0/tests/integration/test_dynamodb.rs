@@ -0,0 +1,71 @@
+// Copyright © 2024 Pathway
+
+use std::sync::Arc;
+
+use aws_sdk_dynamodb::primitives::Blob;
+use aws_sdk_dynamodb::types::AttributeValue;
+
+use pathway_engine::connectors::data_storage::{DynamoDbWriter, WriteError};
+use pathway_engine::engine::{DateTimeNaive, Type, Value};
+
+#[test]
+fn test_scalar_attribute_values() -> eyre::Result<()> {
+    assert_eq!(
+        DynamoDbWriter::attribute_value(&Value::None, Type::Int)?,
+        AttributeValue::Null(true)
+    );
+    assert_eq!(
+        DynamoDbWriter::attribute_value(&Value::Bool(true), Type::Bool)?,
+        AttributeValue::Bool(true)
+    );
+    assert_eq!(
+        DynamoDbWriter::attribute_value(&Value::Int(-42), Type::Int)?,
+        AttributeValue::N("-42".to_string())
+    );
+    assert_eq!(
+        DynamoDbWriter::attribute_value(&Value::Float(1.5.into()), Type::Float)?,
+        AttributeValue::N("1.5".to_string())
+    );
+    assert_eq!(
+        DynamoDbWriter::attribute_value(&Value::String("abc".into()), Type::String)?,
+        AttributeValue::S("abc".to_string())
+    );
+    assert_eq!(
+        DynamoDbWriter::attribute_value(&Value::Bytes(Arc::from(&b"\x00\x01"[..])), Type::Bytes)?,
+        AttributeValue::B(Blob::new(vec![0, 1]))
+    );
+    Ok(())
+}
+
+#[test]
+fn test_tuple_attribute_value() -> eyre::Result<()> {
+    let value = Value::Tuple(Arc::from([
+        Value::Int(1),
+        Value::String("a".into()),
+        Value::None,
+    ]));
+    assert_eq!(
+        DynamoDbWriter::attribute_value(&value, Type::Tuple)?,
+        AttributeValue::L(vec![
+            AttributeValue::N("1".to_string()),
+            AttributeValue::S("a".to_string()),
+            AttributeValue::Null(true),
+        ])
+    );
+    Ok(())
+}
+
+#[test]
+fn test_unrepresentable_values() {
+    assert!(matches!(
+        DynamoDbWriter::attribute_value(&Value::Float(f64::NAN.into()), Type::Float),
+        Err(WriteError::UnsupportedType(Type::Float))
+    ));
+    assert!(matches!(
+        DynamoDbWriter::attribute_value(
+            &Value::DateTimeNaive(DateTimeNaive::new(0)),
+            Type::DateTimeNaive
+        ),
+        Err(WriteError::UnsupportedType(Type::DateTimeNaive))
+    ));
+}
@@ -0,0 +1,105 @@
+// Copyright © 2024 Pathway
+
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::TcpListener;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::thread;
+
+use elasticsearch::http::transport::Transport;
+use elasticsearch::Elasticsearch;
+
+use pathway_engine::connectors::data_format::FormatterContext;
+use pathway_engine::connectors::data_storage::{
+    ElasticSearchBulkAction, ElasticSearchWriter, Writer,
+};
+use pathway_engine::engine::{Key, Timestamp};
+
+/// Starts a server accepting every bulk request. Returns its URL and the counter
+/// of the received requests.
+fn start_bulk_server() -> eyre::Result<(String, Arc<AtomicUsize>)> {
+    let listener = TcpListener::bind("127.0.0.1:0")?;
+    let address = listener.local_addr()?;
+    let requests_received = Arc::new(AtomicUsize::new(0));
+    let requests_counter = requests_received.clone();
+    thread::spawn(move || {
+        for stream in listener.incoming() {
+            let mut stream = stream.unwrap();
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+            let mut content_length = 0;
+            loop {
+                let mut header_line = String::new();
+                reader.read_line(&mut header_line).unwrap();
+                if header_line.trim().is_empty() {
+                    break;
+                }
+                if let Some((name, value)) = header_line.split_once(':') {
+                    if name.eq_ignore_ascii_case("content-length") {
+                        content_length = value.trim().parse().unwrap();
+                    }
+                }
+            }
+            let mut body = vec![0; content_length];
+            reader.read_exact(&mut body).unwrap();
+            requests_counter.fetch_add(1, Ordering::SeqCst);
+
+            let body = r#"{"took": 1, "errors": false, "items": []}"#;
+            let response = format!(
+                "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nX-Elastic-Product: Elasticsearch\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+                body.len()
+            );
+            stream.write_all(response.as_bytes()).unwrap();
+        }
+    });
+    Ok((format!("http://{address}"), requests_received))
+}
+
+fn thread_count() -> eyre::Result<usize> {
+    Ok(std::fs::read_dir("/proc/self/task")?.count())
+}
+
+fn write_document(writer: &mut ElasticSearchWriter, id: i64) -> eyre::Result<()> {
+    writer.write(FormatterContext::new_single_payload(
+        format!(r#"{{"id": {id}}}"#).into_bytes(),
+        Key::random(),
+        Vec::new(),
+        Timestamp(0),
+        1,
+    ))?;
+    Ok(())
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn test_flushes_reuse_runtime() -> eyre::Result<()> {
+    let (url, requests_received) = start_bulk_server()?;
+    let client = Elasticsearch::new(Transport::single_node(&url)?);
+    let mut writer = ElasticSearchWriter::new(
+        client,
+        "index".to_string(),
+        None,
+        None,
+        ElasticSearchBulkAction::Index,
+        ElasticSearchWriter::DEFAULT_MAX_BUFFERED_ACTIONS,
+    )?;
+
+    write_document(&mut writer, 0)?;
+    writer.flush(true)?;
+    let threads_before = thread_count()?;
+
+    for id in 1..=100 {
+        write_document(&mut writer, id)?;
+        writer.flush(true)?;
+    }
+    assert_eq!(requests_received.load(Ordering::SeqCst), 101);
+
+    // The other tests may start their threads meanwhile, so a small growth is
+    // tolerated, but not a thread per flush
+    let threads_after = thread_count()?;
+    assert!(
+        threads_after < threads_before + 10,
+        "{threads_before} threads before the flushes, {threads_after} after"
+    );
+
+    Ok(())
+}
@@ -0,0 +1,136 @@
+// Copyright © 2024 Pathway
+
+use super::helpers::{raw_bytes, read_entries, tokenized_entry};
+
+use std::path::Path;
+use std::sync::Arc;
+
+use tempfile::tempdir;
+
+use pathway_engine::connectors::data_storage::{
+    ConnectorMode, CsvFilesystemReader, FilesystemReader, FilesystemReaderSettings,
+    InvalidBytesPolicy, ReadError, ReadMethod, TextEncoding,
+};
+use pathway_engine::connectors::{OffsetKey, OffsetValue};
+
+fn latin1() -> TextEncoding {
+    TextEncoding::new("latin1", InvalidBytesPolicy::Replace).unwrap()
+}
+
+fn read_lines(
+    path: &Path,
+    encoding: TextEncoding,
+) -> Result<Vec<(String, OffsetValue)>, ReadError> {
+    let mut reader = FilesystemReader::new(
+        &[path.to_str().unwrap()],
+        ConnectorMode::Static,
+        None,
+        ReadMethod::ByLine,
+        FilesystemReaderSettings {
+            encoding: Some(encoding),
+            ..Default::default()
+        },
+    )?;
+    Ok(read_entries(&mut reader)?
+        .into_iter()
+        .map(|(context, (_, offset))| (String::from_utf8(raw_bytes(context)).unwrap(), offset))
+        .collect())
+}
+
+#[test]
+fn test_lines_transcoded_into_utf8() -> eyre::Result<()> {
+    let test_storage = tempdir()?;
+    let path = test_storage.path().join("input.txt");
+    std::fs::write(&path, b"caf\xe9\nna\xefve\n")?;
+
+    let lines = read_lines(&path, latin1())?;
+
+    // The offsets count the bytes of the file, not the ones of the decoded lines
+    let expected_offset = |total_entries_read, bytes_offset| OffsetValue::FilePosition {
+        total_entries_read,
+        path: Arc::new(path.clone()),
+        bytes_offset,
+    };
+    assert_eq!(
+        lines,
+        vec![
+            ("café\n".to_string(), expected_offset(1, 5)),
+            ("naïve\n".to_string(), expected_offset(2, 11)),
+        ]
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_csv_fields_transcoded_into_utf8() -> eyre::Result<()> {
+    let test_storage = tempdir()?;
+    let path = test_storage.path().join("input.csv");
+    std::fs::write(&path, b"name,city\nJos\xe9,M\xe1laga\n")?;
+
+    let mut reader = CsvFilesystemReader::new(
+        &[path.to_str().unwrap()],
+        csv::ReaderBuilder::new(),
+        ConnectorMode::Static,
+        None,
+        true,
+        FilesystemReaderSettings {
+            encoding: Some(latin1()),
+            ..Default::default()
+        },
+    )?;
+    let records: Vec<_> = read_entries(&mut reader)?
+        .into_iter()
+        .map(|(context, _)| tokenized_entry(context).1)
+        .collect();
+    assert_eq!(
+        records,
+        vec![vec!["José".to_string(), "Málaga".to_string()]]
+    );
+    assert_eq!(
+        reader.discovered_header(),
+        Some(&["name".to_string(), "city".to_string()][..])
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_invalid_bytes_policy() -> eyre::Result<()> {
+    let test_storage = tempdir()?;
+    let path = test_storage.path().join("input.txt");
+    std::fs::write(&path, b"ok\nbad \xff\n")?;
+
+    let encoding = TextEncoding::new("utf-8", InvalidBytesPolicy::Replace)?;
+    let lines: Vec<_> = read_lines(&path, encoding)?
+        .into_iter()
+        .map(|(line, _)| line)
+        .collect();
+    assert_eq!(lines, vec!["ok\n", "bad \u{fffd}\n"]);
+
+    let encoding = TextEncoding::new("utf-8", InvalidBytesPolicy::Error)?;
+    let result = read_lines(&path, encoding);
+    assert!(
+        matches!(result, Err(ReadError::InvalidEncodedText("UTF-8"))),
+        "{result:?}"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_unsupported_encodings() {
+    let result = TextEncoding::new("no-such-encoding", InvalidBytesPolicy::Replace);
+    assert!(
+        matches!(result, Err(ReadError::UnknownEncoding(ref label)) if label == "no-such-encoding")
+    );
+
+    // The terminators and the delimiters can't be found in the raw UTF-16 bytes
+    let result = TextEncoding::new("utf-16le", InvalidBytesPolicy::Replace);
+    assert!(matches!(
+        result,
+        Err(ReadError::UnsupportedEncoding("UTF-16LE"))
+    ));
+
+    assert_eq!(latin1().name(), "windows-1252");
+}
@@ -0,0 +1,386 @@
+// Copyright © 2024 Pathway
+
+use std::fs::File;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use sha2::{Digest, Sha256};
+use tempfile::tempdir;
+
+use pathway_engine::connectors::clock::{Clock, MockClock};
+use pathway_engine::connectors::data_storage::{
+    CacheHashFunction, CacheKeyStrategy, ConnectorMode, DataEventType, FileStartPosition,
+    FilesystemScanner, ReadError, ReadResult,
+};
+
+const INITIAL_MTIME_SECS: u64 = 1_700_000_000;
+const SEEN_AT_SECS: u64 = 1_710_000_000;
+
+fn set_mtime(path: &Path, mtime: SystemTime) -> eyre::Result<()> {
+    File::options()
+        .write(true)
+        .open(path)?
+        .set_modified(mtime)?;
+    Ok(())
+}
+
+fn scanner_for(path: &Path, clock: &MockClock) -> eyre::Result<FilesystemScanner> {
+    scanner_with_cache_key_strategy(path, clock, CacheKeyStrategy::default())
+}
+
+fn scanner_with_cache_key_strategy(
+    path: &Path,
+    clock: &MockClock,
+    cache_key_strategy: CacheKeyStrategy,
+) -> eyre::Result<FilesystemScanner> {
+    Ok(FilesystemScanner::new(
+        &[path.to_str().unwrap()],
+        None,
+        ConnectorMode::Streaming,
+        "*",
+        None,
+        FileStartPosition::Beginning,
+        Arc::new(clock.clone()),
+        cache_key_strategy,
+        None,
+    )?)
+}
+
+fn seen_at(read_result: &ReadResult) -> u64 {
+    let ReadResult::NewSource(Some(metadata)) = read_result else {
+        panic!("unexpected read result: {read_result:?}");
+    };
+    serde_json::to_value(metadata).unwrap()["seen_at"]
+        .as_u64()
+        .unwrap()
+}
+
+fn next_action(scanner: &mut FilesystemScanner) -> eyre::Result<(ReadResult, PathBuf)> {
+    let read_result = scanner
+        .next_action_determined()?
+        .expect("scanner must have an action");
+    let path = scanner
+        .current_offset_file()
+        .expect("action must refer to a file");
+    Ok((read_result, path.to_path_buf()))
+}
+
+#[test]
+fn test_modified_file_is_deleted_and_inserted_again() -> eyre::Result<()> {
+    let test_storage = tempdir()?;
+    let path = test_storage.path().join("input.txt");
+    std::fs::write(&path, b"a\n")?;
+    let initial_mtime = UNIX_EPOCH + Duration::from_secs(INITIAL_MTIME_SECS);
+    set_mtime(&path, initial_mtime)?;
+
+    let clock = MockClock::new(UNIX_EPOCH + Duration::from_secs(SEEN_AT_SECS));
+    let mut scanner = scanner_for(test_storage.path(), &clock)?;
+
+    let (read_result, current_path) = next_action(&mut scanner)?;
+    assert_eq!(current_path, path);
+    assert_eq!(scanner.data_event_type(), Some(DataEventType::Insert));
+    assert_eq!(seen_at(&read_result), SEEN_AT_SECS);
+    assert_eq!(scanner.next_action_determined()?, None);
+
+    std::fs::write(&path, b"b\n")?;
+    set_mtime(&path, initial_mtime + Duration::from_secs(10))?;
+    clock.advance(Duration::from_secs(60));
+
+    // The deletion carries the metadata of the original insertion
+    let (read_result, current_path) = next_action(&mut scanner)?;
+    assert_eq!(current_path, path);
+    assert_eq!(scanner.data_event_type(), Some(DataEventType::Delete));
+    assert_eq!(seen_at(&read_result), SEEN_AT_SECS);
+
+    let (read_result, current_path) = next_action(&mut scanner)?;
+    assert_eq!(current_path, path);
+    assert_eq!(scanner.data_event_type(), Some(DataEventType::Insert));
+    assert_eq!(seen_at(&read_result), SEEN_AT_SECS + 60);
+    assert_eq!(scanner.next_action_determined()?, None);
+
+    Ok(())
+}
+
+#[test]
+fn test_subsecond_modification_is_not_detected() -> eyre::Result<()> {
+    let test_storage = tempdir()?;
+    let path = test_storage.path().join("input.txt");
+    std::fs::write(&path, b"a\n")?;
+    let initial_mtime = UNIX_EPOCH + Duration::from_secs(INITIAL_MTIME_SECS);
+    set_mtime(&path, initial_mtime)?;
+
+    let clock = MockClock::new(UNIX_EPOCH + Duration::from_secs(SEEN_AT_SECS));
+    let mut scanner = scanner_for(test_storage.path(), &clock)?;
+    next_action(&mut scanner)?;
+    assert_eq!(scanner.next_action_determined()?, None);
+
+    // The modification times are compared with the precision of seconds
+    set_mtime(&path, initial_mtime + Duration::from_millis(500))?;
+    assert_eq!(scanner.next_action_determined()?, None);
+
+    Ok(())
+}
+
+#[test]
+fn test_removed_file_is_deleted_without_reinsertion() -> eyre::Result<()> {
+    let test_storage = tempdir()?;
+    let path = test_storage.path().join("input.txt");
+    std::fs::write(&path, b"a\n")?;
+    set_mtime(&path, UNIX_EPOCH + Duration::from_secs(INITIAL_MTIME_SECS))?;
+
+    let clock = MockClock::new(UNIX_EPOCH + Duration::from_secs(SEEN_AT_SECS));
+    let mut scanner = scanner_for(test_storage.path(), &clock)?;
+    next_action(&mut scanner)?;
+
+    std::fs::remove_file(&path)?;
+    let (read_result, current_path) = next_action(&mut scanner)?;
+    assert_eq!(current_path, path);
+    assert_eq!(scanner.data_event_type(), Some(DataEventType::Delete));
+    assert_eq!(seen_at(&read_result), SEEN_AT_SECS);
+    assert_eq!(scanner.next_action_determined()?, None);
+
+    Ok(())
+}
+
+#[test]
+fn test_mock_clock_is_shared_between_clones() {
+    let clock = MockClock::new(UNIX_EPOCH + Duration::from_secs(SEEN_AT_SECS));
+    let shared: Arc<dyn Clock> = Arc::new(clock.clone());
+    clock.advance(Duration::from_secs(5));
+    assert_eq!(shared.unix_timestamp_secs(), SEEN_AT_SECS + 5);
+    clock.set(UNIX_EPOCH);
+    assert_eq!(shared.unix_timestamp_secs(), 0);
+}
+
+#[test]
+fn test_cached_copy_named_by_hash_function() -> eyre::Result<()> {
+    for hash_function in [
+        CacheHashFunction::Xxh3,
+        CacheHashFunction::Sha256,
+        CacheHashFunction::Blake3,
+    ] {
+        let test_storage = tempdir()?;
+        let path = test_storage.path().join("input.txt");
+        std::fs::write(&path, b"a\n")?;
+
+        let clock = MockClock::new(UNIX_EPOCH + Duration::from_secs(SEEN_AT_SECS));
+        let cache_key_strategy = CacheKeyStrategy {
+            hash_function,
+            hash_contents: false,
+        };
+        let mut scanner =
+            scanner_with_cache_key_strategy(test_storage.path(), &clock, cache_key_strategy)?;
+        next_action(&mut scanner)?;
+
+        std::fs::remove_file(&path)?;
+        next_action(&mut scanner)?;
+        assert_eq!(scanner.data_event_type(), Some(DataEventType::Delete));
+        let cached_path = scanner.current_file().expect("deletion must be cached");
+        assert_eq!(std::fs::read(cached_path.as_path())?, b"a\n");
+
+        let cached_name = cached_path.file_name().unwrap().to_str().unwrap();
+        match hash_function {
+            CacheHashFunction::Xxh3 => {
+                assert_eq!(
+                    cached_name,
+                    xxhash_rust::xxh3::xxh3_128(path.as_os_str().as_encoded_bytes()).to_string()
+                );
+            }
+            CacheHashFunction::Sha256 => {
+                assert_eq!(
+                    cached_name,
+                    format!("{:x}", Sha256::digest(path.as_os_str().as_encoded_bytes()))
+                );
+            }
+            CacheHashFunction::Blake3 => {
+                assert_eq!(
+                    cached_name,
+                    blake3::hash(path.as_os_str().as_encoded_bytes())
+                        .to_hex()
+                        .as_str()
+                );
+            }
+        }
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_cached_copy_shared_by_same_contents() -> eyre::Result<()> {
+    let test_storage = tempdir()?;
+    let first_path = test_storage.path().join("first.txt");
+    let second_path = test_storage.path().join("second.txt");
+    std::fs::write(&first_path, b"a\n")?;
+    std::fs::write(&second_path, b"a\n")?;
+
+    let clock = MockClock::new(UNIX_EPOCH + Duration::from_secs(SEEN_AT_SECS));
+    let cache_key_strategy = CacheKeyStrategy {
+        hash_function: CacheHashFunction::Sha256,
+        hash_contents: true,
+    };
+    let mut scanner =
+        scanner_with_cache_key_strategy(test_storage.path(), &clock, cache_key_strategy)?;
+    next_action(&mut scanner)?;
+    next_action(&mut scanner)?;
+
+    std::fs::remove_file(&first_path)?;
+    let (_, current_path) = next_action(&mut scanner)?;
+    assert_eq!(current_path, first_path);
+    let first_cached_path = scanner.current_file().expect("deletion must be cached");
+    assert_eq!(
+        first_cached_path.file_name().unwrap().to_str().unwrap(),
+        format!("{:x}", Sha256::digest(b"a\n"))
+    );
+
+    // The copy is still needed for the deletion of the second file
+    std::fs::remove_file(&second_path)?;
+    let (_, current_path) = next_action(&mut scanner)?;
+    assert_eq!(current_path, second_path);
+    let second_cached_path = scanner.current_file().expect("deletion must be cached");
+    assert_eq!(second_cached_path, first_cached_path);
+    assert_eq!(std::fs::read(second_cached_path.as_path())?, b"a\n");
+
+    assert_eq!(scanner.next_action_determined()?, None);
+    assert!(!second_cached_path.exists());
+
+    Ok(())
+}
+
+#[test]
+fn test_missing_action_is_reported_as_error() -> eyre::Result<()> {
+    let test_storage = tempdir()?;
+    std::fs::write(test_storage.path().join("input.txt"), b"a\n")?;
+    let clock = MockClock::new(UNIX_EPOCH + Duration::from_secs(SEEN_AT_SECS));
+    let mut scanner = scanner_for(test_storage.path(), &clock)?;
+
+    // No file is processed before the first action is determined
+    assert!(matches!(
+        scanner.require_current_offset_file(),
+        Err(ReadError::InconsistentState(_))
+    ));
+    assert!(matches!(
+        scanner.require_data_event_type(),
+        Err(ReadError::InconsistentState(_))
+    ));
+
+    next_action(&mut scanner)?;
+    assert_eq!(
+        scanner.require_current_offset_file()?.as_path(),
+        test_storage.path().join("input.txt")
+    );
+    assert_eq!(scanner.require_data_event_type()?, DataEventType::Insert);
+
+    Ok(())
+}
+
+#[test]
+fn test_several_globs_read_as_one_source() -> eyre::Result<()> {
+    let test_storage = tempdir()?;
+    let first_directory = test_storage.path().join("first");
+    let second_directory = test_storage.path().join("second");
+    std::fs::create_dir_all(first_directory.join("nested"))?;
+    std::fs::create_dir(&second_directory)?;
+
+    // The modification times alternate between the directories
+    let files = [
+        (first_directory.join("nested/1.csv"), 3),
+        (second_directory.join("2.json"), 1),
+        (first_directory.join("3.csv"), 2),
+        (second_directory.join("4.csv"), 0),
+    ];
+    for (path, mtime_offset) in &files {
+        std::fs::write(path, b"a\n")?;
+        set_mtime(
+            path,
+            UNIX_EPOCH + Duration::from_secs(INITIAL_MTIME_SECS + mtime_offset),
+        )?;
+    }
+
+    let first_glob = format!("{}/**/*.csv", first_directory.display());
+    let second_glob = format!("{}/*.json", second_directory.display());
+    // A file matched by several globs is read only once
+    let overlapping_glob = format!("{}/*/3.csv", test_storage.path().display());
+    let clock = MockClock::new(UNIX_EPOCH + Duration::from_secs(SEEN_AT_SECS));
+    let mut scanner = FilesystemScanner::new(
+        &[
+            first_glob.as_str(),
+            second_glob.as_str(),
+            overlapping_glob.as_str(),
+        ],
+        None,
+        ConnectorMode::Streaming,
+        "*",
+        None,
+        FileStartPosition::Beginning,
+        Arc::new(clock),
+        CacheKeyStrategy::default(),
+        None,
+    )?;
+
+    let mut read_paths = Vec::new();
+    while scanner.next_action_determined()?.is_some() {
+        if let Some(path) = scanner.current_offset_file() {
+            read_paths.push(path.to_path_buf());
+        }
+    }
+    assert_eq!(
+        read_paths,
+        vec![
+            second_directory.join("2.json"),
+            first_directory.join("3.csv"),
+            first_directory.join("nested/1.csv"),
+        ]
+    );
+
+    Ok(())
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn test_watches_limited_to_glob() -> eyre::Result<()> {
+    let test_storage = tempdir()?;
+    let root = test_storage.path();
+    for directory in ["logs/a/nested", "logs/b", "other"] {
+        std::fs::create_dir_all(root.join(directory))?;
+    }
+
+    let glob = format!("{}/logs/*", root.display());
+    let clock = MockClock::new(UNIX_EPOCH + Duration::from_secs(SEEN_AT_SECS));
+    let mut scanner = FilesystemScanner::new(
+        &[glob.as_str()],
+        None,
+        ConnectorMode::Streaming,
+        "*",
+        Some(Duration::from_millis(10)),
+        FileStartPosition::Beginning,
+        Arc::new(clock),
+        CacheKeyStrategy::default(),
+        None,
+    )?;
+    // The directories matched by the glob are read as a whole, so they're watched
+    // recursively, while the directories outside of the glob aren't watched
+    assert_eq!(
+        scanner.watched_directories(),
+        vec![
+            root.join("logs"),
+            root.join("logs/a"),
+            root.join("logs/a/nested"),
+            root.join("logs/b"),
+        ]
+    );
+
+    std::fs::remove_dir_all(root.join("logs/a"))?;
+    std::fs::create_dir(root.join("logs/c"))?;
+    std::fs::create_dir(root.join("other/d"))?;
+    for _ in 0..10 {
+        scanner.wait_for_new_files();
+    }
+    assert_eq!(
+        scanner.watched_directories(),
+        vec![root.join("logs"), root.join("logs/b"), root.join("logs/c")]
+    );
+
+    Ok(())
+}
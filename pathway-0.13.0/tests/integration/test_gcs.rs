@@ -0,0 +1,35 @@
+// Copyright © 2024 Pathway
+
+use std::sync::Arc;
+
+use pathway_engine::connectors::data_storage::StorageType;
+use pathway_engine::connectors::{OffsetKey, OffsetValue};
+use pathway_engine::persistence::frontier::OffsetAntichain;
+
+fn object_position(total_entries_read: u64) -> OffsetValue {
+    OffsetValue::S3ObjectPositionV2 {
+        total_entries_read,
+        path: Arc::new("prefix/object.csv".to_string()),
+        bytes_offset: total_entries_read * 10,
+        last_modified: Some(1_714_557_600_000_000_000),
+    }
+}
+
+fn frontier_at(total_entries_read: u64) -> OffsetAntichain {
+    let mut frontier = OffsetAntichain::new();
+    frontier.advance_offset(OffsetKey::Empty, object_position(total_entries_read));
+    frontier
+}
+
+#[test]
+fn test_gcs_frontiers_merge_by_entries_read() {
+    for storage_type in [StorageType::GcsCsv, StorageType::GcsLines] {
+        for (lhs, rhs) in [(3, 7), (7, 3)] {
+            let merged = storage_type.merge_two_frontiers(&frontier_at(lhs), &frontier_at(rhs));
+            assert_eq!(
+                merged.get_offset(&OffsetKey::Empty),
+                Some(&object_position(7))
+            );
+        }
+    }
+}
@@ -0,0 +1,175 @@
+// Copyright © 2024 Pathway
+
+use super::helpers::{raw_bytes, read_entries};
+
+use std::io::{BufRead, BufReader, Write};
+use std::net::TcpListener;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::thread;
+
+use pathway_engine::connectors::data_storage::{ConnectorMode, HttpReader, ReadError, Reader};
+use pathway_engine::connectors::{OffsetKey, OffsetValue};
+use pathway_engine::persistence::frontier::OffsetAntichain;
+
+/// Starts a server answering each request with the response built for its path.
+/// Returns the base URL of the server.
+fn start_server(
+    respond: impl Fn(&str) -> (u16, Vec<(&'static str, &'static str)>, String) + Send + 'static,
+) -> eyre::Result<String> {
+    let listener = TcpListener::bind("127.0.0.1:0")?;
+    let address = listener.local_addr()?;
+    thread::spawn(move || {
+        for stream in listener.incoming() {
+            let mut stream = stream.unwrap();
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+            let mut request_line = String::new();
+            reader.read_line(&mut request_line).unwrap();
+            loop {
+                let mut header_line = String::new();
+                reader.read_line(&mut header_line).unwrap();
+                if header_line.trim().is_empty() {
+                    break;
+                }
+            }
+
+            let path = request_line.split_whitespace().nth(1).unwrap();
+            let (status, headers, body) = respond(path);
+            let mut response = format!("HTTP/1.1 {status} Status\r\n");
+            for (name, value) in headers {
+                response.push_str(&format!("{name}: {value}\r\n"));
+            }
+            response.push_str(&format!(
+                "Content-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+                body.len()
+            ));
+            stream.write_all(response.as_bytes()).unwrap();
+        }
+    });
+    Ok(format!("http://{address}"))
+}
+
+fn paginated_response(path: &str) -> (u16, Vec<(&'static str, &'static str)>, String) {
+    let body = match path {
+        "/items?cursor=" => r#"{"data": [{"id": 1}, {"id": 2}], "next": "b"}"#,
+        "/items?cursor=b" => r#"{"data": [{"id": 3}], "next": null}"#,
+        _ => return (404, Vec::new(), "{}".to_string()),
+    };
+    (200, Vec::new(), body.to_string())
+}
+
+fn reader_for(base_url: &str) -> eyre::Result<HttpReader> {
+    Ok(HttpReader::new(
+        format!("{base_url}/items?cursor={}", HttpReader::CURSOR_PLACEHOLDER),
+        Vec::new(),
+        None,
+        "/data".to_string(),
+        Some("/next".to_string()),
+        ConnectorMode::Static,
+        None,
+        None,
+    )?)
+}
+
+fn read_items(reader: &mut HttpReader) -> eyre::Result<Vec<String>> {
+    let mut items = Vec::new();
+    for (context, _) in read_entries(reader)? {
+        items.push(String::from_utf8(raw_bytes(context))?);
+    }
+    Ok(items)
+}
+
+#[test]
+fn test_http_pagination() -> eyre::Result<()> {
+    let base_url = start_server(paginated_response)?;
+    let mut reader = reader_for(&base_url)?;
+    assert_eq!(
+        read_items(&mut reader)?,
+        vec![r#"{"id":1}"#, r#"{"id":2}"#, r#"{"id":3}"#]
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_http_seek() -> eyre::Result<()> {
+    let base_url = start_server(paginated_response)?;
+
+    let mut frontier = OffsetAntichain::new();
+    frontier.advance_offset(
+        OffsetKey::Empty,
+        OffsetValue::HttpCursor {
+            pages_read: 0,
+            cursor: None,
+            items_read_within_page: 1,
+        },
+    );
+
+    let mut reader = reader_for(&base_url)?;
+    reader.seek(&frontier)?;
+    assert_eq!(read_items(&mut reader)?, vec![r#"{"id":2}"#, r#"{"id":3}"#]);
+
+    Ok(())
+}
+
+#[test]
+fn test_http_retry_after() -> eyre::Result<()> {
+    let n_requests = Arc::new(AtomicUsize::new(0));
+    let base_url = {
+        let n_requests = n_requests.clone();
+        start_server(move |path| {
+            if n_requests.fetch_add(1, Ordering::SeqCst) == 0 {
+                (429, vec![("Retry-After", "0")], "{}".to_string())
+            } else {
+                paginated_response(path)
+            }
+        })?
+    };
+
+    let mut reader = reader_for(&base_url)?;
+    assert_eq!(read_items(&mut reader)?.len(), 3);
+    assert_eq!(n_requests.load(Ordering::SeqCst), 3);
+
+    Ok(())
+}
+
+#[test]
+fn test_http_rate_limited_attempts_capped() -> eyre::Result<()> {
+    let n_requests = Arc::new(AtomicUsize::new(0));
+    let base_url = {
+        let n_requests = n_requests.clone();
+        start_server(move |_| {
+            n_requests.fetch_add(1, Ordering::SeqCst);
+            (429, vec![("Retry-After", "0")], "{}".to_string())
+        })?
+    };
+
+    let mut reader = reader_for(&base_url)?;
+    let error = reader.read().expect_err("the read should fail");
+    assert!(
+        matches!(error, ReadError::Http(e) if e.status() == Some(reqwest::StatusCode::TOO_MANY_REQUESTS))
+    );
+    assert_eq!(
+        n_requests.load(Ordering::SeqCst),
+        HttpReader::MAX_RATE_LIMITED_ATTEMPTS
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_http_cursor_percent_encoded() -> eyre::Result<()> {
+    let base_url = start_server(|path| {
+        let body = match path {
+            "/items?cursor=" => r#"{"data": [{"id": 1}], "next": "a b&c=d/e+f"}"#,
+            "/items?cursor=a%20b%26c%3Dd%2Fe%2Bf" => r#"{"data": [{"id": 2}], "next": null}"#,
+            _ => return (404, Vec::new(), "{}".to_string()),
+        };
+        (200, Vec::new(), body.to_string())
+    })?;
+
+    let mut reader = reader_for(&base_url)?;
+    assert_eq!(read_items(&mut reader)?, vec![r#"{"id":1}"#, r#"{"id":2}"#]);
+
+    Ok(())
+}
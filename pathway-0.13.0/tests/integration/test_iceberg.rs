@@ -0,0 +1,48 @@
+// Copyright © 2024 Pathway
+
+use assert_matches::assert_matches;
+use iceberg::spec::{PrimitiveType, Type as IcebergType};
+
+use pathway_engine::connectors::data_storage::{IcebergWriter, WriteError};
+use pathway_engine::engine::Type;
+use pathway_engine::python_api::ValueField;
+
+fn value_field(name: &str, type_: Type, is_optional: bool) -> ValueField {
+    ValueField {
+        name: name.to_string(),
+        type_,
+        is_optional,
+        default: None,
+    }
+}
+
+#[test]
+fn test_iceberg_schema() -> eyre::Result<()> {
+    let schema = IcebergWriter::construct_schema(&[
+        value_field("id", Type::Int, false),
+        value_field("name", Type::String, true),
+        value_field("created_at", Type::DateTimeUtc, false),
+    ])?;
+
+    let expected_fields = [
+        ("id", 1, PrimitiveType::Long, true),
+        ("name", 2, PrimitiveType::String, false),
+        ("created_at", 3, PrimitiveType::Timestamptz, true),
+        ("time", 4, PrimitiveType::Long, true),
+        ("diff", 5, PrimitiveType::Long, true),
+    ];
+    for (name, id, primitive_type, required) in expected_fields {
+        let field = schema.field_by_name(name).unwrap();
+        assert_eq!(field.id, id);
+        assert_eq!(field.required, required);
+        assert_eq!(*field.field_type, IcebergType::Primitive(primitive_type));
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_iceberg_schema_unsupported_type() {
+    let result = IcebergWriter::construct_schema(&[value_field("values", Type::Tuple, false)]);
+    assert_matches!(result, Err(WriteError::UnsupportedType(Type::Tuple)));
+}
@@ -0,0 +1,112 @@
+// Copyright © 2024 Pathway
+
+use super::helpers::raw_bytes;
+
+use std::time::{Duration, Instant};
+
+use rusqlite::Connection as SqliteConnection;
+use rusqlite::OpenFlags as SqliteOpenFlags;
+use tempfile::tempdir;
+
+use pathway_engine::connectors::data_storage::{
+    ConnectorMode, FilesystemReader, FilesystemReaderSettings, ReadMethod, ReadResult, Reader,
+    ReaderContext, SqliteReader,
+};
+
+const IDLE_TIMEOUT: Duration = Duration::from_millis(300);
+
+#[test]
+fn test_idle_filesystem_reader_allows_commit() -> eyre::Result<()> {
+    let test_storage = tempdir()?;
+    std::fs::write(test_storage.path().join("a.txt"), b"a\n")?;
+
+    let mut reader = FilesystemReader::new(
+        &[test_storage.path().to_str().unwrap()],
+        ConnectorMode::Streaming,
+        None,
+        ReadMethod::ByLine,
+        FilesystemReaderSettings {
+            refresh_interval: Some(Duration::from_millis(50)),
+            ..Default::default()
+        },
+    )?;
+    reader.set_idle_timeout(Some(IDLE_TIMEOUT));
+
+    assert!(matches!(reader.read()?, ReadResult::NewSource(_)));
+    assert!(matches!(
+        reader.read()?,
+        ReadResult::Data(ReaderContext::RawBytes(_, line), _) if line == b"a\n"
+    ));
+    assert_eq!(
+        reader.read()?,
+        ReadResult::FinishedSource {
+            commit_allowed: true
+        }
+    );
+
+    // There are no new files, so the reader reports an empty commit instead of
+    // waiting for them indefinitely
+    let wait_started_at = Instant::now();
+    assert_eq!(
+        reader.read()?,
+        ReadResult::FinishedSource {
+            commit_allowed: true
+        }
+    );
+    assert!(wait_started_at.elapsed() >= IDLE_TIMEOUT);
+
+    // The reading continues normally once a new file appears
+    std::fs::write(test_storage.path().join("b.txt"), b"b\n")?;
+    let mut lines = Vec::new();
+    while lines.is_empty() {
+        match reader.read()? {
+            ReadResult::Data(context, _) => lines.push(raw_bytes(context)),
+            ReadResult::Finished => panic!("streaming reader must not finish"),
+            ReadResult::NewSource(_) | ReadResult::FinishedSource { .. } => {}
+        }
+    }
+    assert_eq!(lines, vec![b"b\n".to_vec()]);
+
+    Ok(())
+}
+
+#[test]
+fn test_idle_sqlite_reader_allows_commit() -> eyre::Result<()> {
+    let connection = SqliteConnection::open_with_flags(
+        "tests/data/sqlite/goods_test.db",
+        SqliteOpenFlags::SQLITE_OPEN_READ_ONLY,
+    )?;
+    let value_field_names = vec![
+        "id".to_string(),
+        "name".to_string(),
+        "price".to_string(),
+        "photo".to_string(),
+    ];
+    let mut reader = SqliteReader::new(
+        connection,
+        "goods".to_string(),
+        value_field_names,
+        None,
+        None,
+        None,
+        None,
+    )?;
+    reader.set_idle_timeout(Some(IDLE_TIMEOUT));
+    loop {
+        if matches!(reader.read()?, ReadResult::FinishedSource { .. }) {
+            break;
+        }
+    }
+
+    // The table doesn't change, so the following reads only report the idle commits
+    for _ in 0..2 {
+        assert_eq!(
+            reader.read()?,
+            ReadResult::FinishedSource {
+                commit_allowed: true
+            }
+        );
+    }
+
+    Ok(())
+}
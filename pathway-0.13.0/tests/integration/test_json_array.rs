@@ -0,0 +1,129 @@
+// Copyright © 2024 Pathway
+
+use super::helpers::{raw_bytes, read_entries};
+
+use std::path::Path;
+use std::sync::Arc;
+
+use tempfile::tempdir;
+
+use pathway_engine::connectors::data_storage::{JsonArrayFileReader, ReadError, Reader};
+use pathway_engine::connectors::{OffsetKey, OffsetValue};
+use pathway_engine::persistence::frontier::OffsetAntichain;
+
+fn read_elements(reader: &mut JsonArrayFileReader) -> Result<Vec<String>, ReadError> {
+    Ok(read_entries(reader)?
+        .into_iter()
+        .map(|(context, _)| String::from_utf8(raw_bytes(context)).unwrap())
+        .collect())
+}
+
+fn read_file(path: &Path, contents: &str, json_pointer: &str) -> Result<Vec<String>, ReadError> {
+    std::fs::write(path, contents)?;
+    let mut reader = JsonArrayFileReader::new(path, json_pointer, None)?;
+    read_elements(&mut reader)
+}
+
+#[test]
+fn test_top_level_array() -> eyre::Result<()> {
+    let test_storage = tempdir()?;
+    let path = test_storage.path().join("input.json");
+    let elements = read_file(
+        &path,
+        "[\n  {\"id\": 1, \"tags\": [\"a\", \"b\"]},\n  {\"id\": 2, \"note\": \"],\\\"\"}\n]\n",
+        "",
+    )?;
+    assert_eq!(
+        elements,
+        vec![
+            r#"{"id": 1, "tags": ["a", "b"]}"#,
+            r#"{"id": 2, "note": "],\""}"#
+        ]
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_array_located_by_pointer() -> eyre::Result<()> {
+    let test_storage = tempdir()?;
+    let path = test_storage.path().join("input.json");
+    let contents = r#"{
+        "count": 3,
+        "meta": {"items": "not these"},
+        "data": {"a/b": [0, [5, -1.5e3, true, null, "x"]]}
+    }"#;
+    let elements = read_file(&path, contents, "/data/a~1b/1")?;
+    assert_eq!(elements, vec!["5", "-1.5e3", "true", "null", "\"x\""]);
+
+    let elements = read_file(&path, r#"{"items": []}"#, "/items")?;
+    assert!(elements.is_empty());
+
+    Ok(())
+}
+
+#[test]
+fn test_missing_array() -> eyre::Result<()> {
+    let test_storage = tempdir()?;
+    let path = test_storage.path().join("input.json");
+    for (contents, json_pointer) in [
+        (r#"{"data": {"items": []}}"#, "/data/other"),
+        (r#"{"data": {"items": {}}}"#, "/data/items"),
+        ("[1, 2]", "/2"),
+    ] {
+        let result = read_file(&path, contents, json_pointer);
+        assert!(
+            matches!(result, Err(ReadError::JsonArrayNotFound(ref pointer)) if pointer == json_pointer),
+            "{result:?}"
+        );
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_malformed_array() -> eyre::Result<()> {
+    let test_storage = tempdir()?;
+    let path = test_storage.path().join("input.json");
+    for contents in ["[1, 2", "[1 2]", "[1,]"] {
+        let result = read_file(&path, contents, "");
+        assert!(
+            matches!(result, Err(ReadError::MalformedJsonArray(_))),
+            "{result:?}"
+        );
+    }
+
+    let result = JsonArrayFileReader::new(&path, "data", None);
+    assert!(matches!(result, Err(ReadError::InvalidJsonPointer(_))));
+
+    Ok(())
+}
+
+#[test]
+fn test_seek_skips_read_elements() -> eyre::Result<()> {
+    let test_storage = tempdir()?;
+    let path = test_storage.path().join("input.json");
+    std::fs::write(&path, r#"{"items": [{"id": 1}, {"id": 2}, {"id": 3}]}"#)?;
+
+    let mut reader = JsonArrayFileReader::new(&path, "/items", None)?;
+    let offsets: Vec<_> = read_entries(&mut reader)?
+        .into_iter()
+        .map(|(_, (_, offset))| offset)
+        .collect();
+    let expected_offsets: Vec<_> = (1..=3)
+        .map(|total_entries_read| OffsetValue::FilePosition {
+            total_entries_read,
+            path: Arc::new(path.clone()),
+            bytes_offset: 0,
+        })
+        .collect();
+    assert_eq!(offsets, expected_offsets);
+
+    let mut frontier = OffsetAntichain::new();
+    frontier.advance_offset(OffsetKey::Empty, offsets[1].clone());
+    let mut reader = JsonArrayFileReader::new(&path, "/items", None)?;
+    reader.seek(&frontier)?;
+    assert_eq!(read_elements(&mut reader)?, vec![r#"{"id": 3}"#]);
+
+    Ok(())
+}
@@ -9,18 +9,20 @@ use std::collections::HashMap;
 use std::sync::Arc;
 
 use pathway_engine::connectors::data_format::{JsonLinesParser, ParsedEvent};
-use pathway_engine::connectors::data_storage::{ConnectorMode, FilesystemReader, ReadMethod};
+use pathway_engine::connectors::data_storage::{
+    ConnectorMode, FilesystemReader, FilesystemReaderSettings, ReadMethod,
+};
 use pathway_engine::connectors::SessionType;
 use pathway_engine::engine::Value;
 
 #[test]
 fn test_jsonlines_ok() -> eyre::Result<()> {
     let reader = FilesystemReader::new(
-        "tests/data/jsonlines.txt",
+        &["tests/data/jsonlines.txt"],
         ConnectorMode::Static,
         None,
         ReadMethod::ByLine,
-        "*",
+        FilesystemReaderSettings::default(),
     )?;
     let parser = JsonLinesParser::new(
         Some(vec!["a".to_string()]),
@@ -56,11 +58,11 @@ fn test_jsonlines_ok() -> eyre::Result<()> {
 #[test]
 fn test_jsonlines_incorrect_key() -> eyre::Result<()> {
     let reader = FilesystemReader::new(
-        "tests/data/jsonlines.txt",
+        &["tests/data/jsonlines.txt"],
         ConnectorMode::Static,
         None,
         ReadMethod::ByLine,
-        "*",
+        FilesystemReaderSettings::default(),
     )?;
     let parser = JsonLinesParser::new(
         Some(vec!["a".to_string(), "d".to_string()]),
@@ -84,11 +86,11 @@ fn test_jsonlines_incorrect_key() -> eyre::Result<()> {
 #[test]
 fn test_jsonlines_incomplete_key_to_null() -> eyre::Result<()> {
     let reader = FilesystemReader::new(
-        "tests/data/jsonlines.txt",
+        &["tests/data/jsonlines.txt"],
         ConnectorMode::Static,
         None,
         ReadMethod::ByLine,
-        "*",
+        FilesystemReaderSettings::default(),
     )?;
     let parser = JsonLinesParser::new(
         Some(vec!["a".to_string(), "d".to_string()]),
@@ -108,11 +110,11 @@ fn test_jsonlines_incomplete_key_to_null() -> eyre::Result<()> {
 #[test]
 fn test_jsonlines_incorrect_values() -> eyre::Result<()> {
     let reader = FilesystemReader::new(
-        "tests/data/jsonlines.txt",
+        &["tests/data/jsonlines.txt"],
         ConnectorMode::Static,
         None,
         ReadMethod::ByLine,
-        "*",
+        FilesystemReaderSettings::default(),
     )?;
     let parser = JsonLinesParser::new(
         Some(vec!["a".to_string()]),
@@ -136,11 +138,11 @@ fn test_jsonlines_incorrect_values() -> eyre::Result<()> {
 #[test]
 fn test_jsonlines_types_parsing() -> eyre::Result<()> {
     let reader = FilesystemReader::new(
-        "tests/data/jsonlines_types.txt",
+        &["tests/data/jsonlines_types.txt"],
         ConnectorMode::Static,
         None,
         ReadMethod::ByLine,
-        "*",
+        FilesystemReaderSettings::default(),
     )?;
     let parser = JsonLinesParser::new(
         Some(vec!["a".to_string()]),
@@ -187,11 +189,11 @@ fn test_jsonlines_types_parsing() -> eyre::Result<()> {
 #[test]
 fn test_jsonlines_complex_paths() -> eyre::Result<()> {
     let reader = FilesystemReader::new(
-        "tests/data/json_complex_paths.txt",
+        &["tests/data/json_complex_paths.txt"],
         ConnectorMode::Static,
         None,
         ReadMethod::ByLine,
-        "*",
+        FilesystemReaderSettings::default(),
     )?;
 
     let mut routes = HashMap::new();
@@ -244,11 +246,11 @@ fn test_jsonlines_complex_paths() -> eyre::Result<()> {
 #[test]
 fn test_jsonlines_complex_paths_error() -> eyre::Result<()> {
     let reader = FilesystemReader::new(
-        "tests/data/json_complex_paths.txt",
+        &["tests/data/json_complex_paths.txt"],
         ConnectorMode::Static,
         None,
         ReadMethod::ByLine,
-        "*",
+        FilesystemReaderSettings::default(),
     )?;
 
     let mut routes = HashMap::new();
@@ -287,11 +289,11 @@ fn test_jsonlines_complex_paths_error() -> eyre::Result<()> {
 #[test]
 fn test_jsonlines_complex_path_ignore_errors() -> eyre::Result<()> {
     let reader = FilesystemReader::new(
-        "tests/data/json_complex_paths.txt",
+        &["tests/data/json_complex_paths.txt"],
         ConnectorMode::Static,
         None,
         ReadMethod::ByLine,
-        "*",
+        FilesystemReaderSettings::default(),
     )?;
 
     let mut routes = HashMap::new();
@@ -326,11 +328,11 @@ fn test_jsonlines_complex_path_ignore_errors() -> eyre::Result<()> {
 #[test]
 fn test_jsonlines_incorrect_key_verbose_error() -> eyre::Result<()> {
     let reader = FilesystemReader::new(
-        "tests/data/jsonlines.txt",
+        &["tests/data/jsonlines.txt"],
         ConnectorMode::Static,
         None,
         ReadMethod::ByLine,
-        "*",
+        FilesystemReaderSettings::default(),
     )?;
     let parser = JsonLinesParser::new(
         Some(vec!["a".to_string(), "d".to_string()]),
@@ -357,11 +359,11 @@ fn test_jsonlines_incorrect_jsonpointer_verbose_error() -> eyre::Result<()> {
     routes.insert("d".to_string(), "/non/existent/path".to_string());
 
     let reader = FilesystemReader::new(
-        "tests/data/jsonlines.txt",
+        &["tests/data/jsonlines.txt"],
         ConnectorMode::Static,
         None,
         ReadMethod::ByLine,
-        "*",
+        FilesystemReaderSettings::default(),
     )?;
     let parser = JsonLinesParser::new(
         Some(vec!["a".to_string(), "d".to_string()]),
@@ -385,11 +387,11 @@ fn test_jsonlines_incorrect_jsonpointer_verbose_error() -> eyre::Result<()> {
 #[test]
 fn test_jsonlines_failed_to_parse_field() -> eyre::Result<()> {
     let reader = FilesystemReader::new(
-        "tests/data/json_complex_paths.txt",
+        &["tests/data/json_complex_paths.txt"],
         ConnectorMode::Static,
         None,
         ReadMethod::ByLine,
-        "*",
+        FilesystemReaderSettings::default(),
     )?;
     let parser = JsonLinesParser::new(
         None,
@@ -409,3 +411,42 @@ fn test_jsonlines_failed_to_parse_field() -> eyre::Result<()> {
 
     Ok(())
 }
+
+#[test]
+fn test_jsonlines_split_by_objects() -> eyre::Result<()> {
+    let reader = FilesystemReader::new(
+        &["tests/data/jsonlines_multiline.txt"],
+        ConnectorMode::Static,
+        None,
+        ReadMethod::JsonObjects,
+        FilesystemReaderSettings::default(),
+    )?;
+    let parser = JsonLinesParser::new(
+        Some(vec!["a".to_string()]),
+        vec!["b".to_string(), "c".to_string()],
+        HashMap::new(),
+        true,
+        HashMap::new(),
+        SessionType::Native,
+    );
+
+    let entries = read_data_from_reader(Box::new(reader), Box::new(parser))?;
+
+    let expected_values = vec![
+        ParsedEvent::Insert((
+            Some(vec![Value::from("abc")]),
+            vec![Value::Int(7), Value::Int(15)],
+        )),
+        ParsedEvent::Insert((
+            Some(vec![Value::from("d\nef")]),
+            vec![Value::Int(1), Value::Int(3)],
+        )),
+        ParsedEvent::Insert((
+            Some(vec![Value::from("g}h{i\"")]),
+            vec![Value::Int(2), Value::Int(4)],
+        )),
+    ];
+    assert_eq!(entries, expected_values);
+
+    Ok(())
+}
@@ -0,0 +1,133 @@
+// Copyright © 2024 Pathway
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use rdkafka::consumer::BaseConsumer;
+use rdkafka::ClientConfig;
+
+use pathway_engine::connectors::data_storage::{KafkaReader, ReadResult, Reader, ShutdownSignal};
+use pathway_engine::connectors::{OffsetKey, OffsetValue};
+use pathway_engine::persistence::frontier::OffsetAntichain;
+
+const TOPIC: &str = "test-topic";
+
+fn consumer() -> eyre::Result<BaseConsumer> {
+    let mut client_config = ClientConfig::new();
+    client_config.set("bootstrap.servers", "localhost:9092");
+    client_config.set("group.id", "test-group");
+    Ok(client_config.create()?)
+}
+
+#[test]
+fn test_kafka_reader_modes() -> eyre::Result<()> {
+    let reader = KafkaReader::new(consumer()?, TOPIC.to_string(), None, None, None);
+    assert_eq!(reader.assigned_partitions(), None);
+
+    let reader =
+        KafkaReader::with_assignment(consumer()?, TOPIC.to_string(), vec![(0, 5)], None, None)?;
+    assert_eq!(reader.assigned_partitions(), Some(&HashMap::from([(0, 5)])));
+
+    Ok(())
+}
+
+#[test]
+fn test_kafka_assigned_partitions_seek() -> eyre::Result<()> {
+    let mut reader = KafkaReader::with_assignment(
+        consumer()?,
+        TOPIC.to_string(),
+        vec![(0, 5), (1, 0)],
+        None,
+        None,
+    )?;
+
+    let mut frontier = OffsetAntichain::new();
+    frontier.advance_offset(
+        OffsetKey::Kafka(Arc::new(TOPIC.to_string()), 1),
+        OffsetValue::KafkaOffset(10),
+    );
+    // Not assigned to the reader, hence ignored
+    frontier.advance_offset(
+        OffsetKey::Kafka(Arc::new(TOPIC.to_string()), 2),
+        OffsetValue::KafkaOffset(3),
+    );
+    reader.seek(&frontier)?;
+
+    assert_eq!(
+        reader.assigned_partitions(),
+        Some(&HashMap::from([(0, 5), (1, 11)]))
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_kafka_unresolved_seek_positions() -> eyre::Result<()> {
+    let mut reader = KafkaReader::new(consumer()?, TOPIC.to_string(), None, None, None);
+    assert_eq!(reader.unresolved_seek_positions(), Vec::new());
+
+    let mut frontier = OffsetAntichain::new();
+    frontier.advance_offset(
+        OffsetKey::Kafka(Arc::new(TOPIC.to_string()), 2),
+        OffsetValue::KafkaOffset(3),
+    );
+    frontier.advance_offset(
+        OffsetKey::Kafka(Arc::new(TOPIC.to_string()), 0),
+        OffsetValue::KafkaOffset(10),
+    );
+    // Belongs to another topic, hence ignored
+    frontier.advance_offset(
+        OffsetKey::Kafka(Arc::new("other-topic".to_string()), 1),
+        OffsetValue::KafkaOffset(5),
+    );
+    reader.seek(&frontier)?;
+
+    // No partitions are assigned to the consumer yet, so the positions are pending
+    assert_eq!(reader.unresolved_seek_positions(), vec![(0, 10), (2, 3)]);
+
+    Ok(())
+}
+
+#[test]
+fn test_kafka_read_returns_on_poll_timeout() -> eyre::Result<()> {
+    // The consumer isn't subscribed to anything, so no messages arrive
+    let mut reader = KafkaReader::new(
+        consumer()?,
+        TOPIC.to_string(),
+        None,
+        Some(Duration::from_millis(100)),
+        None,
+    );
+    let started_at = Instant::now();
+    assert!(matches!(
+        reader.read()?,
+        ReadResult::FinishedSource {
+            commit_allowed: true
+        }
+    ));
+    assert!(started_at.elapsed() < Duration::from_secs(10));
+
+    Ok(())
+}
+
+#[test]
+fn test_kafka_long_poll_timeout_does_not_delay_shutdown() -> eyre::Result<()> {
+    let mut reader = KafkaReader::new(
+        consumer()?,
+        TOPIC.to_string(),
+        None,
+        Some(Duration::from_secs(3600)),
+        None,
+    );
+    let signal = ShutdownSignal::default();
+    reader.set_shutdown_signal(signal.clone());
+    signal.request();
+
+    // The poll is bounded by the check interval, not by the hour-long timeout
+    let started_at = Instant::now();
+    assert!(matches!(reader.read()?, ReadResult::Finished));
+    assert!(started_at.elapsed() < Duration::from_secs(10));
+
+    Ok(())
+}
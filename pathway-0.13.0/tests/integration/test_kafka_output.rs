@@ -0,0 +1,276 @@
+// Copyright © 2024 Pathway
+
+use std::time::{Duration, Instant};
+
+use assert_matches::assert_matches;
+use rdkafka::message::Headers;
+use rdkafka::ClientConfig;
+
+use pathway_engine::connectors::data_format::FormatterContext;
+use pathway_engine::connectors::data_storage::{
+    DataEventType, KafkaCompression, KafkaProducerTuning, KafkaWriter, WriteError, Writer,
+};
+use pathway_engine::engine::{DateTimeUtc, Key, Timestamp, Type, Value};
+
+fn client_config() -> ClientConfig {
+    let mut client_config = ClientConfig::new();
+    client_config.set("bootstrap.servers", "localhost:9092");
+    client_config
+}
+
+fn writer_with_key_indices(key_field_indices: Vec<usize>) -> Result<KafkaWriter, WriteError> {
+    KafkaWriter::new(
+        client_config(),
+        "topic".to_string(),
+        Vec::new(),
+        key_field_indices,
+        3,
+        KafkaCompression::None,
+        KafkaProducerTuning::default(),
+        None,
+        None,
+    )
+}
+
+#[test]
+fn test_kafka_composite_key_indices_validated() {
+    let writer = writer_with_key_indices(vec![0, 2]);
+    assert!(writer.is_ok());
+
+    let writer = writer_with_key_indices(vec![0, 3]);
+    assert_matches!(
+        writer.err(),
+        Some(WriteError::KeyFieldIndexOutOfRange {
+            index: 3,
+            value_fields_count: 3
+        })
+    );
+}
+
+#[test]
+fn test_kafka_message_key() -> eyre::Result<()> {
+    let key = Key::random();
+    let data = FormatterContext::new_single_payload(
+        b"payload".to_vec(),
+        key,
+        vec![
+            Value::String("ab".into()),
+            Value::Bytes(vec![1].into()),
+            Value::Int(3),
+        ],
+        Timestamp(4),
+        1,
+    );
+
+    // Without key fields, the key of the row is used
+    let writer = writer_with_key_indices(Vec::new())?;
+    assert_eq!(writer.message_key(&data)?, key.0.to_le_bytes().to_vec());
+
+    let writer = writer_with_key_indices(vec![0])?;
+    assert_eq!(writer.message_key(&data)?, b"ab".to_vec());
+
+    // The fields of a composite key are prefixed with their lengths
+    let writer = writer_with_key_indices(vec![0, 1])?;
+    assert_eq!(
+        writer.message_key(&data)?,
+        vec![0, 0, 0, 2, b'a', b'b', 0, 0, 0, 1, 1]
+    );
+
+    let writer = writer_with_key_indices(vec![0, 2])?;
+    assert_matches!(
+        writer.message_key(&data),
+        Err(WriteError::IncorrectKeyFieldType(Value::Int(3)))
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_kafka_compression_and_tuning_applied() {
+    for compression in [
+        KafkaCompression::Gzip,
+        KafkaCompression::Snappy,
+        KafkaCompression::Lz4,
+        KafkaCompression::Zstd,
+    ] {
+        let writer = KafkaWriter::new(
+            client_config(),
+            "topic".to_string(),
+            Vec::new(),
+            Vec::new(),
+            1,
+            compression,
+            KafkaProducerTuning {
+                batch_num_messages: Some(1000),
+                linger: Some(Duration::from_millis(50)),
+                commit_timeout: None,
+            },
+            None,
+            None,
+        );
+        assert!(writer.is_ok(), "{compression:?} is not supported");
+    }
+}
+
+#[test]
+fn test_kafka_event_type_headers() -> eyre::Result<()> {
+    let writer = KafkaWriter::new(
+        client_config(),
+        "topic".to_string(),
+        vec![("source".to_string(), 0)],
+        Vec::new(),
+        1,
+        KafkaCompression::None,
+        KafkaProducerTuning::default(),
+        None,
+        None,
+    )?;
+
+    for (diff, event_type, expected_event) in [
+        (1, DataEventType::Insert, "insert"),
+        (-1, DataEventType::Delete, "delete"),
+        (1, DataEventType::Upsert, "upsert"),
+    ] {
+        let data = FormatterContext::new_single_payload(
+            b"payload".to_vec(),
+            Key::random(),
+            vec![Value::String("sensor".into())],
+            Timestamp(4),
+            diff,
+        )
+        .with_event_type(event_type);
+        let headers: Vec<(String, Option<String>)> = writer
+            .message_headers(&data)
+            .iter()
+            .map(|header| {
+                (
+                    header.key.to_string(),
+                    header
+                        .value
+                        .map(|value| String::from_utf8_lossy(value).to_string()),
+                )
+            })
+            .collect();
+        assert_eq!(
+            headers,
+            vec![
+                ("pathway_time".to_string(), Some("4".to_string())),
+                ("pathway_diff".to_string(), Some(diff.to_string())),
+                (
+                    "pathway_event".to_string(),
+                    Some(expected_event.to_string())
+                ),
+                ("source".to_string(), Some("\"sensor\"".to_string())),
+            ]
+        );
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_kafka_message_timestamp_field() -> eyre::Result<()> {
+    let writer = KafkaWriter::new(
+        client_config(),
+        "topic".to_string(),
+        Vec::new(),
+        Vec::new(),
+        2,
+        KafkaCompression::None,
+        KafkaProducerTuning::default(),
+        Some(1),
+        None,
+    )?;
+    let data_with_timestamp = |timestamp: Value| {
+        FormatterContext::new_single_payload(
+            b"payload".to_vec(),
+            Key::random(),
+            vec![Value::String("sensor".into()), timestamp],
+            Timestamp(4),
+            1,
+        )
+    };
+
+    assert_eq!(
+        writer.message_timestamp(&data_with_timestamp(Value::Int(1_700_000_000_123)))?,
+        Some(1_700_000_000_123)
+    );
+    assert_eq!(
+        writer.message_timestamp(&data_with_timestamp(Value::DateTimeUtc(DateTimeUtc::new(
+            1_700_000_000_123_456_789
+        ))))?,
+        Some(1_700_000_000_123)
+    );
+    // The broker assigns the timestamp if the field is empty
+    assert_eq!(
+        writer.message_timestamp(&data_with_timestamp(Value::None))?,
+        None
+    );
+    assert_matches!(
+        writer.message_timestamp(&data_with_timestamp(Value::String("now".into()))),
+        Err(WriteError::UnsupportedType(Type::String))
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_kafka_message_timestamp_not_set_by_default() -> eyre::Result<()> {
+    let writer = writer_with_key_indices(Vec::new())?;
+    let data = FormatterContext::new_single_payload(
+        b"payload".to_vec(),
+        Key::random(),
+        vec![Value::Int(1), Value::Int(2), Value::Int(3)],
+        Timestamp(4),
+        1,
+    );
+    assert_eq!(writer.message_timestamp(&data)?, None);
+
+    Ok(())
+}
+
+#[test]
+fn test_kafka_writer_not_transactional_by_default() -> eyre::Result<()> {
+    // The writes of a non-transactional producer can be retried one by one, while
+    // a transactional producer can only redo the whole transaction
+    let writer = writer_with_key_indices(Vec::new())?;
+    assert!(writer.retriable());
+    assert!(!writer.single_threaded());
+
+    Ok(())
+}
+
+#[test]
+fn test_kafka_commit_times_out_without_broker() -> eyre::Result<()> {
+    let mut client_config = ClientConfig::new();
+    client_config.set("bootstrap.servers", "127.0.0.1:1");
+    // Lets the undelivered message expire when the writer is dropped
+    client_config.set("message.timeout.ms", "1000");
+    let mut writer = KafkaWriter::new(
+        client_config,
+        "topic".to_string(),
+        Vec::new(),
+        Vec::new(),
+        1,
+        KafkaCompression::None,
+        KafkaProducerTuning {
+            commit_timeout: Some(Duration::from_millis(100)),
+            ..KafkaProducerTuning::default()
+        },
+        None,
+        None,
+    )?;
+    writer.write(FormatterContext::new_single_payload(
+        b"payload".to_vec(),
+        Key::random(),
+        vec![Value::Int(1)],
+        Timestamp(4),
+        1,
+    ))?;
+
+    let started_at = Instant::now();
+    assert_matches!(writer.on_commit(Timestamp(4)), Err(WriteError::Kafka(_)));
+    assert!(started_at.elapsed() < KafkaWriter::DEFAULT_COMMIT_TIMEOUT);
+
+    Ok(())
+}
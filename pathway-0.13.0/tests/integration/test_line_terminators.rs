@@ -0,0 +1,105 @@
+// Copyright © 2024 Pathway
+
+use super::helpers::raw_bytes;
+
+use std::path::Path;
+use std::sync::Arc;
+
+use tempfile::tempdir;
+
+use pathway_engine::connectors::data_storage::{
+    ConnectorMode, FilesystemReader, FilesystemReaderSettings, ReadMethod, Reader,
+};
+use pathway_engine::connectors::{OffsetKey, OffsetValue};
+use pathway_engine::persistence::frontier::OffsetAntichain;
+
+fn reader_for(path: &Path, read_method: ReadMethod) -> eyre::Result<FilesystemReader> {
+    Ok(FilesystemReader::new(
+        &[path.to_str().unwrap()],
+        ConnectorMode::Static,
+        None,
+        read_method,
+        FilesystemReaderSettings::default(),
+    )?)
+}
+
+fn read_entries(reader: &mut FilesystemReader) -> eyre::Result<Vec<(Vec<u8>, OffsetValue)>> {
+    Ok(read_entries(reader)?
+        .into_iter()
+        .map(|(context, (_, offset))| (raw_bytes(context), offset))
+        .collect())
+}
+
+fn file_position(path: &Path, total_entries_read: u64, bytes_offset: u64) -> OffsetValue {
+    OffsetValue::FilePosition {
+        total_entries_read,
+        path: Arc::new(path.to_path_buf()),
+        bytes_offset,
+    }
+}
+
+#[test]
+fn test_terminators_stripped() -> eyre::Result<()> {
+    let test_storage = tempdir()?;
+    let path = test_storage.path().join("input.txt");
+    std::fs::write(&path, "a\r\nbb\n\nccc")?;
+
+    let mut reader = reader_for(&path, ReadMethod::ByLineWithoutTerminator)?;
+    assert_eq!(
+        read_entries(&mut reader)?,
+        vec![
+            (b"a".to_vec(), file_position(&path, 1, 3)),
+            (b"bb".to_vec(), file_position(&path, 2, 6)),
+            (Vec::new(), file_position(&path, 3, 7)),
+            (b"ccc".to_vec(), file_position(&path, 4, 10)),
+        ]
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_last_line_same_with_trailing_newline() -> eyre::Result<()> {
+    let test_storage = tempdir()?;
+    let with_newline = test_storage.path().join("with_newline.txt");
+    let without_newline = test_storage.path().join("without_newline.txt");
+    std::fs::write(&with_newline, "a\nbb\n")?;
+    std::fs::write(&without_newline, "a\nbb")?;
+
+    for path in [&with_newline, &without_newline] {
+        let mut reader = reader_for(path, ReadMethod::ByLineWithoutTerminator)?;
+        let entries: Vec<_> = read_entries(&mut reader)?
+            .into_iter()
+            .map(|(entry, _)| entry)
+            .collect();
+        assert_eq!(entries, vec![b"a".to_vec(), b"bb".to_vec()]);
+    }
+
+    // The terminators are kept by the plain line-by-line reading
+    let mut reader = reader_for(&with_newline, ReadMethod::ByLine)?;
+    let entries: Vec<_> = read_entries(&mut reader)?
+        .into_iter()
+        .map(|(entry, _)| entry)
+        .collect();
+    assert_eq!(entries, vec![b"a\n".to_vec(), b"bb\n".to_vec()]);
+
+    Ok(())
+}
+
+#[test]
+fn test_seek_by_raw_bytes_offset() -> eyre::Result<()> {
+    let test_storage = tempdir()?;
+    let path = test_storage.path().join("input.txt");
+    std::fs::write(&path, "a\r\nbb\r\nccc\r\n")?;
+
+    let mut frontier = OffsetAntichain::new();
+    frontier.advance_offset(OffsetKey::Empty, file_position(&path, 2, 7));
+    let mut reader = reader_for(&path, ReadMethod::ByLineWithoutTerminator)?;
+    reader.seek(&frontier)?;
+    assert_eq!(
+        read_entries(&mut reader)?,
+        vec![(b"ccc".to_vec(), file_position(&path, 3, 12))]
+    );
+
+    Ok(())
+}
@@ -0,0 +1,78 @@
+// Copyright © 2024 Pathway
+
+use super::helpers::read_data_from_reader;
+
+use pathway_engine::connectors::data_format::{IdentityParser, ParsedEvent};
+use pathway_engine::connectors::data_storage::{
+    DataEventType, MemoryReader, ReadResult, Reader, ReaderContext,
+};
+use pathway_engine::connectors::{OffsetKey, OffsetValue, SessionType};
+use pathway_engine::engine::Value;
+use pathway_engine::persistence::frontier::OffsetAntichain;
+
+fn reader() -> MemoryReader {
+    MemoryReader::from_raw_bytes(
+        vec![
+            (DataEventType::Insert, b"a".to_vec()),
+            (DataEventType::Insert, b"b".to_vec()),
+            (DataEventType::Delete, b"a".to_vec()),
+        ],
+        None,
+    )
+}
+
+fn parser() -> IdentityParser {
+    IdentityParser::new(vec!["data".to_string()], true, SessionType::Native)
+}
+
+#[test]
+fn test_memory_reader_entries_parsed() -> eyre::Result<()> {
+    let data_read = read_data_from_reader(Box::new(reader()), Box::new(parser()))?;
+    assert_eq!(
+        data_read,
+        vec![
+            ParsedEvent::Insert((None, vec![Value::String("a".into())])),
+            ParsedEvent::Insert((None, vec![Value::String("b".into())])),
+            ParsedEvent::Delete((None, vec![Value::String("a".into())])),
+        ]
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_memory_reader_sequential_offsets() -> eyre::Result<()> {
+    let mut reader = reader();
+    for expected_id in 1..=3 {
+        let ReadResult::Data(_, (offset_key, offset_value)) = reader.read()? else {
+            panic!("data expected");
+        };
+        assert_eq!(offset_key, OffsetKey::Empty);
+        assert_eq!(
+            offset_value,
+            OffsetValue::PythonEntrySequentialId(expected_id)
+        );
+    }
+    assert_eq!(reader.read()?, ReadResult::Finished);
+
+    Ok(())
+}
+
+#[test]
+fn test_memory_reader_seek() -> eyre::Result<()> {
+    let mut frontier = OffsetAntichain::new();
+    frontier.advance_offset(OffsetKey::Empty, OffsetValue::PythonEntrySequentialId(2));
+
+    let mut reader = reader();
+    reader.seek(&frontier)?;
+    assert_eq!(
+        reader.read()?,
+        ReadResult::Data(
+            ReaderContext::from_raw_bytes(DataEventType::Delete, b"a".to_vec()),
+            (OffsetKey::Empty, OffsetValue::PythonEntrySequentialId(3))
+        )
+    );
+    assert_eq!(reader.read()?, ReadResult::Finished);
+
+    Ok(())
+}
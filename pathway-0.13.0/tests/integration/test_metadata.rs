@@ -1,17 +1,25 @@
 // Copyright © 2024 Pathway
 
-use super::helpers::read_data_from_reader;
+use super::helpers::{read_data_from_reader, read_entries};
 
 use std::collections::HashMap;
+use std::fs::File;
+use std::sync::Arc;
+use std::time::{Duration, UNIX_EPOCH};
+
+use tempfile::tempdir;
 
 use pathway_engine::connectors::data_format::{
     DsvParser, DsvSettings, IdentityParser, JsonLinesParser, ParsedEvent,
 };
 use pathway_engine::connectors::data_storage::{
-    ConnectorMode, CsvFilesystemReader, FilesystemReader, ReadMethod,
+    CacheKeyStrategy, ConnectorMode, CsvFilesystemReader, DataEventType, FilesystemReader,
+    FilesystemReaderSettings, ReadMethod, ReadResult, Reader, ReaderContext,
 };
-use pathway_engine::connectors::SessionType;
+use pathway_engine::connectors::metadata::SourceMetadata;
+use pathway_engine::connectors::{OffsetKey, OffsetValue, SessionType};
 use pathway_engine::engine::Value;
+use pathway_engine::persistence::frontier::OffsetAntichain;
 
 /// This function requires that _metadata field is the last in the `value_names_list`
 fn check_file_name_in_metadata(data_read: &ParsedEvent, name: &str) {
@@ -30,11 +38,11 @@ fn check_file_name_in_metadata(data_read: &ParsedEvent, name: &str) {
 #[test]
 fn test_metadata_fs_dir() -> eyre::Result<()> {
     let reader = FilesystemReader::new(
-        "tests/data/csvdir/",
+        &["tests/data/csvdir/"],
         ConnectorMode::Static,
         None,
         ReadMethod::ByLine,
-        "*",
+        FilesystemReaderSettings::default(),
     )?;
     let parser = DsvParser::new(
         DsvSettings::new(
@@ -60,11 +68,11 @@ fn test_metadata_fs_dir() -> eyre::Result<()> {
 #[test]
 fn test_metadata_fs_file() -> eyre::Result<()> {
     let reader = FilesystemReader::new(
-        "tests/data/minimal.txt",
+        &["tests/data/minimal.txt"],
         ConnectorMode::Static,
         None,
         ReadMethod::ByLine,
-        "*",
+        FilesystemReaderSettings::default(),
     )?;
     let parser = DsvParser::new(
         DsvSettings::new(
@@ -91,11 +99,12 @@ fn test_metadata_csv_dir() -> eyre::Result<()> {
     builder.has_headers(false);
 
     let reader = CsvFilesystemReader::new(
-        "tests/data/csvdir/",
+        &["tests/data/csvdir/"],
         builder,
         ConnectorMode::Static,
         None,
-        "*",
+        false,
+        FilesystemReaderSettings::default(),
     )?;
     let parser = DsvParser::new(
         DsvSettings::new(
@@ -124,11 +133,12 @@ fn test_metadata_csv_file() -> eyre::Result<()> {
     builder.has_headers(false);
 
     let reader = CsvFilesystemReader::new(
-        "tests/data/minimal.txt",
+        &["tests/data/minimal.txt"],
         builder,
         ConnectorMode::Static,
         None,
-        "*",
+        false,
+        FilesystemReaderSettings::default(),
     )?;
     let parser = DsvParser::new(
         DsvSettings::new(
@@ -152,11 +162,11 @@ fn test_metadata_csv_file() -> eyre::Result<()> {
 #[test]
 fn test_metadata_json_file() -> eyre::Result<()> {
     let reader = FilesystemReader::new(
-        "tests/data/jsonlines.txt",
+        &["tests/data/jsonlines.txt"],
         ConnectorMode::Static,
         None,
         ReadMethod::ByLine,
-        "*",
+        FilesystemReaderSettings::default(),
     )?;
     let parser = JsonLinesParser::new(
         None,
@@ -176,11 +186,11 @@ fn test_metadata_json_file() -> eyre::Result<()> {
 #[test]
 fn test_metadata_json_dir() -> eyre::Result<()> {
     let reader = FilesystemReader::new(
-        "tests/data/jsonlines/",
+        &["tests/data/jsonlines/"],
         ConnectorMode::Static,
         None,
         ReadMethod::ByLine,
-        "*",
+        FilesystemReaderSettings::default(),
     )?;
     let parser = JsonLinesParser::new(
         None,
@@ -201,11 +211,11 @@ fn test_metadata_json_dir() -> eyre::Result<()> {
 #[test]
 fn test_metadata_identity_file() -> eyre::Result<()> {
     let reader = FilesystemReader::new(
-        "tests/data/jsonlines.txt",
+        &["tests/data/jsonlines.txt"],
         ConnectorMode::Static,
         None,
         ReadMethod::ByLine,
-        "*",
+        FilesystemReaderSettings::default(),
     )?;
     let parser = IdentityParser::new(
         vec!["data".to_string(), "_metadata".to_string()],
@@ -222,11 +232,11 @@ fn test_metadata_identity_file() -> eyre::Result<()> {
 #[test]
 fn test_metadata_identity_dir() -> eyre::Result<()> {
     let reader = FilesystemReader::new(
-        "tests/data/jsonlines/",
+        &["tests/data/jsonlines/"],
         ConnectorMode::Static,
         None,
         ReadMethod::ByLine,
-        "*",
+        FilesystemReaderSettings::default(),
     )?;
     let parser = IdentityParser::new(
         vec!["data".to_string(), "_metadata".to_string()],
@@ -240,3 +250,368 @@ fn test_metadata_identity_dir() -> eyre::Result<()> {
 
     Ok(())
 }
+
+#[test]
+fn test_reserved_metadata_fields_full_read() -> eyre::Result<()> {
+    let test_storage = tempdir()?;
+    let path = test_storage.path().join("document.txt");
+    std::fs::write(&path, "first line\nsecond line\n")?;
+    File::options()
+        .write(true)
+        .open(&path)?
+        .set_modified(UNIX_EPOCH + Duration::from_secs(1_700_000_000))?;
+
+    let reader = FilesystemReader::new(
+        &[path.to_str().unwrap()],
+        ConnectorMode::Static,
+        None,
+        ReadMethod::Full,
+        FilesystemReaderSettings::default(),
+    )?;
+    let parser = IdentityParser::new(
+        vec![
+            "data".to_string(),
+            "_pw_path".to_string(),
+            "_pw_modified_at".to_string(),
+            "_pw_size".to_string(),
+        ],
+        true,
+        SessionType::Native,
+    );
+
+    let data_read = read_data_from_reader(Box::new(reader), Box::new(parser))?;
+    assert_eq!(
+        data_read,
+        vec![ParsedEvent::Insert((
+            None,
+            vec![
+                Value::String("first line\nsecond line".into()),
+                Value::String(path.to_string_lossy().as_ref().into()),
+                Value::Int(1_700_000_000),
+                Value::Int(23),
+            ]
+        ))]
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_reserved_metadata_fields() -> eyre::Result<()> {
+    let test_storage = tempdir()?;
+    let path = test_storage.path().join("document.txt");
+    std::fs::write(&path, "abc")?;
+
+    let metadata = SourceMetadata::from_fs_meta(&path, &std::fs::metadata(&path)?, 0);
+    let reserved_fields = metadata.reserved_fields();
+    assert_eq!(
+        reserved_fields.get("_pw_path"),
+        Some(&Value::String(path.to_string_lossy().as_ref().into()))
+    );
+    assert_eq!(reserved_fields.get("_pw_size"), Some(&Value::Int(3)));
+    // The size isn't a part of the `_metadata` column
+    assert!(metadata.column_value().get("size").is_none());
+    assert_eq!(metadata.fields().get("size"), None);
+    assert!(SourceMetadata::is_reserved_field("_pw_modified_at"));
+    assert!(!SourceMetadata::is_reserved_field("_metadata"));
+
+    Ok(())
+}
+
+fn read_contexts(reader: &mut dyn Reader) -> eyre::Result<Vec<ReaderContext>> {
+    Ok(read_entries(reader)?
+        .into_iter()
+        .map(|(context, _)| context)
+        .collect())
+}
+
+#[test]
+fn test_metadata_record_precedes_contents() -> eyre::Result<()> {
+    let test_storage = tempdir()?;
+    let path = test_storage.path().join("document.txt");
+    std::fs::write(&path, "a\nb\n")?;
+
+    let mut reader = FilesystemReader::new(
+        &[path.to_str().unwrap()],
+        ConnectorMode::Static,
+        None,
+        ReadMethod::ByLine,
+        FilesystemReaderSettings {
+            emit_metadata_record: true,
+            ..Default::default()
+        },
+    )?;
+    let contexts = read_contexts(&mut reader)?;
+    assert_eq!(contexts.len(), 3);
+    let ReaderContext::Diff((DataEventType::Insert, None, fields)) = &contexts[0] else {
+        panic!("the metadata record must go first: {contexts:?}");
+    };
+    assert_eq!(
+        fields.get("path"),
+        Some(&Value::String(path.to_string_lossy().as_ref().into()))
+    );
+    assert_eq!(fields.get("size"), None);
+    assert_eq!(
+        contexts[1..],
+        [
+            ReaderContext::from_raw_bytes(DataEventType::Insert, b"a\n".to_vec()),
+            ReaderContext::from_raw_bytes(DataEventType::Insert, b"b\n".to_vec()),
+        ]
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_metadata_record_parsed_by_field_names() -> eyre::Result<()> {
+    let test_storage = tempdir()?;
+    let path = test_storage.path().join("words.jsonl");
+    std::fs::write(&path, "{\"word\": \"a\"}\n")?;
+
+    let reader = FilesystemReader::new(
+        &[path.to_str().unwrap()],
+        ConnectorMode::Static,
+        None,
+        ReadMethod::ByLine,
+        FilesystemReaderSettings {
+            emit_metadata_record: true,
+            ..Default::default()
+        },
+    )?;
+    let parser = JsonLinesParser::new(
+        None,
+        vec![
+            "path".to_string(),
+            "word".to_string(),
+            "_metadata".to_string(),
+        ],
+        HashMap::new(),
+        false,
+        HashMap::new(),
+        SessionType::Native,
+    );
+
+    let data_read = read_data_from_reader(Box::new(reader), Box::new(parser))?;
+    assert_eq!(data_read.len(), 2);
+    for event in &data_read {
+        check_file_name_in_metadata(event, "words.jsonl\"");
+    }
+    let ParsedEvent::Insert((None, values)) = &data_read[0] else {
+        panic!("the metadata record must be inserted first: {data_read:?}");
+    };
+    assert_eq!(
+        values[..2],
+        [
+            Value::String(path.to_string_lossy().as_ref().into()),
+            Value::None
+        ]
+    );
+    let ParsedEvent::Insert((None, values)) = &data_read[1] else {
+        panic!("the contents must follow the metadata record: {data_read:?}");
+    };
+    assert_eq!(values[..2], [Value::None, Value::String("a".into())]);
+
+    Ok(())
+}
+
+#[test]
+fn test_metadata_record_parsed_before_csv_header() -> eyre::Result<()> {
+    let test_storage = tempdir()?;
+    let path = test_storage.path().join("table.csv");
+    std::fs::write(&path, "key,foo\n1,a\n")?;
+
+    let mut builder = csv::ReaderBuilder::new();
+    builder.has_headers(false);
+    let reader = CsvFilesystemReader::new(
+        &[path.to_str().unwrap()],
+        builder,
+        ConnectorMode::Static,
+        None,
+        false,
+        FilesystemReaderSettings {
+            emit_metadata_record: true,
+            ..Default::default()
+        },
+    )?;
+    let parser = DsvParser::new(
+        DsvSettings::new(None, vec!["foo".to_string(), "_metadata".to_string()], ','),
+        HashMap::new(),
+    );
+
+    let data_read = read_data_from_reader(Box::new(reader), Box::new(parser))?;
+    assert_eq!(data_read.len(), 2);
+    for event in &data_read {
+        check_file_name_in_metadata(event, "table.csv\"");
+    }
+    let ParsedEvent::Insert((None, values)) = &data_read[0] else {
+        panic!("the metadata record must be inserted first: {data_read:?}");
+    };
+    assert_eq!(values[0], Value::None);
+    let ParsedEvent::Insert((None, values)) = &data_read[1] else {
+        panic!("the contents must follow the metadata record: {data_read:?}");
+    };
+    assert_eq!(values[0], Value::String("a".into()));
+
+    Ok(())
+}
+
+#[test]
+fn test_metadata_record_not_emitted_after_seek() -> eyre::Result<()> {
+    let test_storage = tempdir()?;
+    let path = test_storage.path().join("table.csv");
+    std::fs::write(&path, "key,foo\n1,a\n2,b\n")?;
+
+    let mut frontier = OffsetAntichain::new();
+    frontier.advance_offset(
+        OffsetKey::Empty,
+        OffsetValue::FilePosition {
+            total_entries_read: 2,
+            path: Arc::new(path.clone()),
+            bytes_offset: 12,
+        },
+    );
+
+    let mut builder = csv::ReaderBuilder::new();
+    builder.has_headers(false);
+    let mut reader = CsvFilesystemReader::new(
+        &[path.to_str().unwrap()],
+        builder,
+        ConnectorMode::Static,
+        None,
+        false,
+        FilesystemReaderSettings {
+            emit_metadata_record: true,
+            ..Default::default()
+        },
+    )?;
+    reader.seek(&frontier)?;
+
+    let tokenized = |tokens: &[&str]| {
+        ReaderContext::from_tokenized_entries(
+            DataEventType::Insert,
+            tokens.iter().map(|token| (*token).to_string()).collect(),
+        )
+    };
+    assert_eq!(
+        read_contexts(&mut reader)?,
+        vec![tokenized(&["key", "foo"]), tokenized(&["2", "b"])]
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_deletion_after_restart_keeps_metadata() -> eyre::Result<()> {
+    let persistent_storage = tempdir()?;
+    let test_storage = tempdir()?;
+    let path = test_storage.path().join("input.txt");
+    std::fs::write(&path, "a\n")?;
+    let new_reader = || {
+        FilesystemReader::new(
+            &[test_storage.path().to_str().unwrap()],
+            ConnectorMode::Streaming,
+            Some(1),
+            ReadMethod::ByLine,
+            FilesystemReaderSettings {
+                cache_directory: Some(persistent_storage.path().to_path_buf()),
+                ..Default::default()
+            },
+        )
+    };
+
+    let mut reader = new_reader()?;
+    let ReadResult::NewSource(Some(inserted_metadata)) = reader.read()? else {
+        panic!("the file must be inserted first");
+    };
+    let ReadResult::Data(_, (_, offset)) = reader.read()? else {
+        panic!("the file contents must follow its insertion");
+    };
+    assert!(matches!(reader.read()?, ReadResult::FinishedSource { .. }));
+    drop(reader);
+
+    let mut frontier = OffsetAntichain::new();
+    frontier.advance_offset(OffsetKey::Empty, offset);
+    let mut reader = new_reader()?;
+    reader.seek(&frontier)?;
+    std::fs::remove_file(&path)?;
+
+    assert!(matches!(reader.read()?, ReadResult::FinishedSource { .. }));
+    assert_eq!(
+        reader.read()?,
+        ReadResult::NewSource(Some(inserted_metadata))
+    );
+    let ReadResult::Data(context, _) = reader.read()? else {
+        panic!("the file contents must follow its deletion");
+    };
+    assert_eq!(
+        context,
+        ReaderContext::from_raw_bytes(DataEventType::Delete, b"a\n".to_vec())
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_modification_after_restart_deletes_cached_contents() -> eyre::Result<()> {
+    let persistent_storage = tempdir()?;
+    let test_storage = tempdir()?;
+    let path = test_storage.path().join("input.txt");
+    let set_mtime = |secs| -> eyre::Result<()> {
+        File::options()
+            .write(true)
+            .open(&path)?
+            .set_modified(UNIX_EPOCH + Duration::from_secs(secs))?;
+        Ok(())
+    };
+    std::fs::write(&path, "a\n")?;
+    set_mtime(1_700_000_000)?;
+    let new_reader = || {
+        FilesystemReader::new(
+            &[test_storage.path().to_str().unwrap()],
+            ConnectorMode::Streaming,
+            Some(1),
+            ReadMethod::ByLine,
+            FilesystemReaderSettings {
+                cache_key_strategy: CacheKeyStrategy {
+                    hash_contents: true,
+                    ..Default::default()
+                },
+                cache_directory: Some(persistent_storage.path().to_path_buf()),
+                ..Default::default()
+            },
+        )
+    };
+
+    let mut reader = new_reader()?;
+    assert!(matches!(reader.read()?, ReadResult::NewSource(_)));
+    let ReadResult::Data(_, (_, offset)) = reader.read()? else {
+        panic!("the file contents must follow its insertion");
+    };
+    assert!(matches!(reader.read()?, ReadResult::FinishedSource { .. }));
+    drop(reader);
+
+    // The cached copy is named by the old contents, so it can't be found by the
+    // new ones after the restart
+    std::fs::write(&path, "b\n")?;
+    set_mtime(1_700_000_001)?;
+    let mut frontier = OffsetAntichain::new();
+    frontier.advance_offset(OffsetKey::Empty, offset);
+    let mut reader = new_reader()?;
+    reader.seek(&frontier)?;
+
+    let mut contexts = Vec::new();
+    while contexts.len() < 2 {
+        if let ReadResult::Data(context, _) = reader.read()? {
+            contexts.push(context);
+        }
+    }
+    assert_eq!(
+        contexts,
+        vec![
+            ReaderContext::from_raw_bytes(DataEventType::Delete, b"a\n".to_vec()),
+            ReaderContext::from_raw_bytes(DataEventType::Insert, b"b\n".to_vec()),
+        ]
+    );
+
+    Ok(())
+}
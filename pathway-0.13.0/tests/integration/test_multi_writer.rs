@@ -0,0 +1,172 @@
+// Copyright © 2024 Pathway
+
+use std::fs::File;
+use std::io::{self, BufWriter};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use tempfile::tempdir;
+
+use pathway_engine::connectors::data_format::FormatterContext;
+use pathway_engine::connectors::data_storage::{
+    FileWriter, MultiWriter, MultiWriterErrorPolicy, NullWriter, PayloadFraming, RetryingWriter,
+    WriteError, Writer,
+};
+use pathway_engine::engine::{Key, Timestamp};
+
+/// Fails the first `failures` writes, then records the written payloads.
+struct RecordingWriter {
+    failures: usize,
+    retriable: bool,
+    written: Arc<Mutex<Vec<Vec<u8>>>>,
+}
+
+impl RecordingWriter {
+    const ALWAYS_FAILS: usize = usize::MAX;
+
+    fn new(failures: usize, retriable: bool) -> (Box<dyn Writer>, Arc<Mutex<Vec<Vec<u8>>>>) {
+        let written = Arc::new(Mutex::new(Vec::new()));
+        let writer = Self {
+            failures,
+            retriable,
+            written: written.clone(),
+        };
+        (Box::new(writer), written)
+    }
+}
+
+impl Writer for RecordingWriter {
+    fn write(&mut self, data: FormatterContext) -> Result<(), WriteError> {
+        if self.failures > 0 {
+            self.failures -= 1;
+            return Err(WriteError::Io(io::Error::from(
+                io::ErrorKind::ConnectionReset,
+            )));
+        }
+        self.written.lock().unwrap().extend(data.payloads);
+        Ok(())
+    }
+
+    fn retriable(&self) -> bool {
+        self.retriable
+    }
+
+    fn single_threaded(&self) -> bool {
+        false
+    }
+}
+
+fn context(payload: &[u8]) -> FormatterContext {
+    FormatterContext::new_single_payload(
+        payload.to_vec(),
+        Key::random(),
+        Vec::new(),
+        Timestamp(0),
+        1,
+    )
+}
+
+#[test]
+fn test_fan_out_to_null_and_file_writers() -> eyre::Result<()> {
+    let test_storage = tempdir()?;
+    let path = test_storage.path().join("output.txt");
+    let file_writer = FileWriter::new(
+        BufWriter::new(File::create(&path)?),
+        PayloadFraming::Newline,
+        None,
+        false,
+    );
+    let mut writer = Box::new(MultiWriter::new(
+        vec![
+            Box::new(NullWriter::new()),
+            Box::new(NullWriter::new()),
+            Box::new(file_writer),
+        ],
+        MultiWriterErrorPolicy::default(),
+    ));
+
+    // The file writer requires a single thread, unlike the null writers
+    assert!(writer.single_threaded());
+    assert!(!writer.retriable());
+
+    writer.write(context(b"a"))?;
+    writer.write(context(b"bb"))?;
+    writer.flush(false)?;
+    writer.finish()?;
+    assert_eq!(std::fs::read_to_string(&path)?, "a\nbb\n");
+
+    Ok(())
+}
+
+#[test]
+fn test_flags_of_inner_writers_combined() {
+    let writer = MultiWriter::new(
+        vec![Box::new(NullWriter::new()), Box::new(NullWriter::new())],
+        MultiWriterErrorPolicy::default(),
+    );
+    assert!(!writer.single_threaded());
+
+    let (first, _) = RecordingWriter::new(0, true);
+    let (second, _) = RecordingWriter::new(0, true);
+    let writer = MultiWriter::new(vec![first, second], MultiWriterErrorPolicy::default());
+    assert!(writer.retriable());
+
+    let (first, _) = RecordingWriter::new(0, true);
+    let writer = MultiWriter::new(
+        vec![first, Box::new(NullWriter::new())],
+        MultiWriterErrorPolicy::default(),
+    );
+    assert!(!writer.retriable());
+}
+
+#[test]
+fn test_only_failed_writer_retried() -> eyre::Result<()> {
+    let (flaky, flaky_written) = RecordingWriter::new(1, true);
+    let (succeeding, written) = RecordingWriter::new(0, true);
+    let mut writer = MultiWriter::new(
+        vec![
+            Box::new(RetryingWriter::new(flaky, 2, Duration::ZERO)),
+            Box::new(RetryingWriter::new(succeeding, 2, Duration::ZERO)),
+        ],
+        MultiWriterErrorPolicy::default(),
+    );
+
+    writer.write(context(b"a"))?;
+    assert_eq!(*flaky_written.lock().unwrap(), vec![b"a".to_vec()]);
+    assert_eq!(*written.lock().unwrap(), vec![b"a".to_vec()]);
+
+    Ok(())
+}
+
+#[test]
+fn test_fail_fast_stops_at_first_error() {
+    let (failing, _) = RecordingWriter::new(RecordingWriter::ALWAYS_FAILS, true);
+    let (succeeding, written) = RecordingWriter::new(0, true);
+    let mut writer = MultiWriter::new(vec![failing, succeeding], MultiWriterErrorPolicy::FailFast);
+
+    let result = writer.write(context(b"a"));
+    assert!(matches!(result, Err(WriteError::Io(_))), "{result:?}");
+    assert!(written.lock().unwrap().is_empty());
+}
+
+#[test]
+fn test_collect_all_errors() {
+    let (first_failing, _) = RecordingWriter::new(RecordingWriter::ALWAYS_FAILS, true);
+    let (succeeding, written) = RecordingWriter::new(0, true);
+    let (second_failing, _) = RecordingWriter::new(RecordingWriter::ALWAYS_FAILS, true);
+    let mut writer = MultiWriter::new(
+        vec![first_failing, succeeding, second_failing],
+        MultiWriterErrorPolicy::CollectAll,
+    );
+
+    let error = writer
+        .write(context(b"a"))
+        .expect_err("the write should fail");
+    assert_eq!(*written.lock().unwrap(), vec![b"a".to_vec()]);
+    assert!(
+        matches!(&error, WriteError::MultipleOutputs(errors) if errors.len() == 2),
+        "{error:?}"
+    );
+    assert!(error.is_transient());
+    assert!(error.to_string().starts_with("2 outputs failed: "));
+}
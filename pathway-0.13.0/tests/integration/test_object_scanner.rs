@@ -0,0 +1,346 @@
+// Copyright © 2024 Pathway
+
+use std::collections::BTreeMap;
+use std::io::{self, Read, Write};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use pathway_engine::connectors::data_storage::{
+    ObjectOrdering, ObjectScanner, ObjectStore, ReadError, SkippingWriter,
+};
+use pathway_engine::deepcopy::DeepCopy;
+
+/// An in-memory store, where each object fails a given number of downloads after
+/// writing a part of its contents. The keys of the requested objects are recorded.
+#[derive(Clone, Default)]
+struct MemoryStore {
+    objects: Arc<Mutex<BTreeMap<String, (i64, Vec<u8>, usize)>>>,
+    requested_keys: Arc<Mutex<Vec<String>>>,
+}
+
+impl MemoryStore {
+    fn with_object(self, key: &str, contents: &[u8], failures: usize) -> Self {
+        self.objects
+            .lock()
+            .unwrap()
+            .insert(key.to_string(), (1, contents.to_vec(), failures));
+        self
+    }
+}
+
+impl DeepCopy for MemoryStore {
+    fn deep_copy(&self) -> Self {
+        self.clone()
+    }
+}
+
+impl ObjectStore for MemoryStore {
+    const LISTS_AFTER_KEY: bool = true;
+    const OBJECT_KIND: &'static str = "memory object";
+
+    fn list_objects(
+        &self,
+        start_after: Option<&str>,
+        on_object: &mut dyn FnMut(&str, i64),
+    ) -> Result<Option<String>, ReadError> {
+        let objects = self.objects.lock().unwrap();
+        let mut last_listed_key = None;
+        for (key, (last_modified, _, _)) in objects.iter() {
+            if start_after.is_some_and(|start_after| key.as_str() <= start_after) {
+                continue;
+            }
+            on_object(key, *last_modified);
+            last_listed_key = Some(key.clone());
+        }
+        Ok(last_listed_key)
+    }
+
+    fn get_object_to_writer(
+        &self,
+        key: &str,
+        writer: &mut (dyn Write + Send),
+    ) -> Result<(), ReadError> {
+        self.requested_keys.lock().unwrap().push(key.to_string());
+        // The lock isn't held while writing, since the write blocks until the
+        // contents are read, and the other objects may be downloaded meanwhile
+        let (contents, failed) = {
+            let mut objects = self.objects.lock().unwrap();
+            let (_, contents, failures) = objects
+                .get_mut(key)
+                .ok_or_else(|| io::Error::from(io::ErrorKind::NotFound))?;
+            let failed = *failures > 0;
+            if failed {
+                *failures -= 1;
+            }
+            (contents.clone(), failed)
+        };
+        if failed {
+            writer.write_all(&contents[..contents.len() / 2])?;
+            return Err(io::Error::from(io::ErrorKind::ConnectionReset).into());
+        }
+        writer.write_all(&contents)?;
+        Ok(())
+    }
+
+    // Like S3, the broken pipes are retriable, since they may come from the
+    // connection to the store
+    fn is_retriable_error(error: &ReadError) -> bool {
+        matches!(
+            error,
+            ReadError::Io(e)
+                if matches!(e.kind(), io::ErrorKind::ConnectionReset | io::ErrorKind::BrokenPipe)
+        )
+    }
+}
+
+/// A store, where each download panics.
+#[derive(Clone)]
+struct PanickingStore;
+
+impl DeepCopy for PanickingStore {
+    fn deep_copy(&self) -> Self {
+        self.clone()
+    }
+}
+
+impl ObjectStore for PanickingStore {
+    const LISTS_AFTER_KEY: bool = true;
+    const OBJECT_KIND: &'static str = "panicking object";
+
+    fn list_objects(
+        &self,
+        _start_after: Option<&str>,
+        _on_object: &mut dyn FnMut(&str, i64),
+    ) -> Result<Option<String>, ReadError> {
+        Ok(None)
+    }
+
+    fn get_object_to_writer(
+        &self,
+        key: &str,
+        _writer: &mut (dyn Write + Send),
+    ) -> Result<(), ReadError> {
+        panic!("unexpected object {key}");
+    }
+
+    fn is_retriable_error(_error: &ReadError) -> bool {
+        false
+    }
+}
+
+fn download(store: MemoryStore, key: &str, max_get_attempts: usize) -> (String, ReadError) {
+    let (current_object, mut pipe_reader) =
+        ObjectScanner::stream_object_from_store(store, key, max_get_attempts);
+    let mut contents = String::new();
+    pipe_reader.read_to_string(&mut contents).unwrap();
+    let error = current_object
+        .finalize()
+        .expect_err("the download should fail");
+    (contents, error)
+}
+
+#[test]
+fn test_no_matching_objects() {
+    let store = MemoryStore::default().with_object("prefix/_SUCCESS", b"", 0);
+    let result = ObjectScanner::new(
+        store,
+        "*.csv",
+        ObjectOrdering::default(),
+        MemoryStore::DEFAULT_MAX_GET_ATTEMPTS,
+        None,
+    );
+    assert!(matches!(result, Err(ReadError::NoObjectsToRead)));
+}
+
+#[test]
+fn test_retried_download_resumes() -> eyre::Result<()> {
+    let store = MemoryStore::default().with_object("prefix/one.csv", b"id,name\n1,a\n", 1);
+    let (current_object, mut pipe_reader) =
+        ObjectScanner::stream_object_from_store(store, "prefix/one.csv", 2);
+    let mut contents = String::new();
+    pipe_reader.read_to_string(&mut contents)?;
+    current_object.finalize()?;
+
+    // The part delivered by the failed attempt isn't repeated
+    assert_eq!(contents, "id,name\n1,a\n");
+
+    Ok(())
+}
+
+#[test]
+fn test_download_resumed_after_several_failures() -> eyre::Result<()> {
+    let store = MemoryStore::default().with_object("prefix/one.csv", b"id,name\n1,a\n", 2);
+    let (current_object, mut pipe_reader) =
+        ObjectScanner::stream_object_from_store(store.clone(), "prefix/one.csv", 3);
+    let mut contents = String::new();
+    pipe_reader.read_to_string(&mut contents)?;
+    current_object.finalize()?;
+
+    assert_eq!(contents, "id,name\n1,a\n");
+    assert_eq!(store.requested_keys.lock().unwrap().len(), 3);
+
+    Ok(())
+}
+
+#[test]
+fn test_skipping_writer() -> eyre::Result<()> {
+    let mut output = Vec::new();
+    let mut writer = SkippingWriter::new(&mut output, 4);
+    for chunk in [b"abc".as_slice(), b"def", b"gh"] {
+        writer.write_all(chunk)?;
+    }
+    assert_eq!(writer.position(), 8);
+    assert!(!writer.inner_failed());
+    assert_eq!(output, b"efgh");
+
+    Ok(())
+}
+
+#[test]
+fn test_closed_pipe_not_retried() {
+    let store = MemoryStore::default().with_object("prefix/one.csv", b"id,name\n1,a\n", 0);
+    let (current_object, pipe_reader) =
+        ObjectScanner::stream_object_from_store(store.clone(), "prefix/one.csv", 5);
+    drop(pipe_reader);
+
+    // The reader is gone, so the download isn't attempted again
+    let error = current_object
+        .finalize()
+        .expect_err("the download should fail");
+    assert!(matches!(error, ReadError::Io(e) if e.kind() == io::ErrorKind::BrokenPipe));
+    assert_eq!(store.requested_keys.lock().unwrap().len(), 1);
+}
+
+#[test]
+fn test_download_attempts_exhausted() {
+    let store = MemoryStore::default().with_object("prefix/one.csv", b"id,name\n1,a\n", 2);
+    let (contents, error) = download(store, "prefix/one.csv", 2);
+    assert_eq!(contents, "id,nam");
+    assert!(matches!(error, ReadError::Io(e) if e.kind() == io::ErrorKind::ConnectionReset));
+}
+
+#[test]
+fn test_non_retriable_error_not_retried() {
+    let (contents, error) = download(MemoryStore::default(), "prefix/one.csv", 5);
+    assert_eq!(contents, "");
+    assert!(matches!(error, ReadError::Io(e) if e.kind() == io::ErrorKind::NotFound));
+}
+
+/// Waits until at least `count` objects are requested from the store, and returns
+/// their keys in the alphabetical order.
+fn wait_for_requests(store: &MemoryStore, count: usize) -> Vec<String> {
+    let started_at = Instant::now();
+    loop {
+        let mut requested_keys = store.requested_keys.lock().unwrap().clone();
+        if requested_keys.len() >= count || started_at.elapsed() > Duration::from_secs(10) {
+            requested_keys.sort();
+            return requested_keys;
+        }
+        thread::sleep(Duration::from_millis(10));
+    }
+}
+
+fn store_with_three_objects() -> MemoryStore {
+    MemoryStore::default()
+        .with_object("prefix/1.csv", b"one\n", 0)
+        .with_object("prefix/2.csv", b"two\n", 0)
+        .with_object("prefix/3.csv", b"three\n", 0)
+}
+
+fn read_all_objects(scanner: &mut ObjectScanner<MemoryStore>) -> eyre::Result<String> {
+    let mut contents = String::new();
+    while let Some(mut pipe_reader) = scanner.stream_next_object()? {
+        pipe_reader.read_to_string(&mut contents)?;
+    }
+    Ok(contents)
+}
+
+#[test]
+fn test_next_object_prefetched() -> eyre::Result<()> {
+    let store = store_with_three_objects();
+    let mut scanner = ObjectScanner::new(
+        store.clone(),
+        "*.csv",
+        ObjectOrdering::default(),
+        1,
+        Some(2),
+    )?;
+
+    // The next object is requested before the current one is read, but there are
+    // no more than two objects in flight
+    let mut contents = String::new();
+    let mut pipe_reader = scanner.stream_next_object()?.unwrap();
+    assert_eq!(
+        wait_for_requests(&store, 2),
+        vec!["prefix/1.csv", "prefix/2.csv"]
+    );
+    pipe_reader.read_to_string(&mut contents)?;
+
+    // The objects are still read in order
+    contents.push_str(&read_all_objects(&mut scanner)?);
+    assert_eq!(contents, "one\ntwo\nthree\n");
+    assert_eq!(wait_for_requests(&store, 3).len(), 3);
+
+    Ok(())
+}
+
+#[test]
+fn test_one_object_in_flight_by_default() -> eyre::Result<()> {
+    let store = store_with_three_objects();
+    let mut scanner =
+        ObjectScanner::new(store.clone(), "*.csv", ObjectOrdering::default(), 1, None)?;
+
+    let mut contents = String::new();
+    let mut pipe_reader = scanner.stream_next_object()?.unwrap();
+    assert_eq!(wait_for_requests(&store, 1), vec!["prefix/1.csv"]);
+    pipe_reader.read_to_string(&mut contents)?;
+    // The next object isn't requested before the current one is finished
+    assert_eq!(store.requested_keys.lock().unwrap().len(), 1);
+
+    contents.push_str(&read_all_objects(&mut scanner)?);
+    assert_eq!(contents, "one\ntwo\nthree\n");
+
+    Ok(())
+}
+
+#[test]
+fn test_download_thread_panic_reported() {
+    let (current_object, mut pipe_reader) =
+        ObjectScanner::stream_object_from_store(PanickingStore, "prefix/one.csv", 1);
+    let mut contents = String::new();
+    pipe_reader.read_to_string(&mut contents).unwrap();
+    assert_eq!(contents, "");
+
+    let error = current_object
+        .finalize()
+        .expect_err("the download should fail");
+    assert!(
+        matches!(&error, ReadError::DownloadThreadPanicked(message) if message == "unexpected object prefix/one.csv"),
+        "{error:?}"
+    );
+}
+
+#[test]
+fn test_object_before_last_listed_key_found_by_full_listing() -> eyre::Result<()> {
+    let store = MemoryStore::default().with_object("prefix/2.csv", b"two\n", 0);
+    let mut scanner =
+        ObjectScanner::new(store.clone(), "*.csv", ObjectOrdering::default(), 1, None)?;
+    scanner.set_full_listing_interval(Duration::from_secs(1));
+    assert_eq!(read_all_objects(&mut scanner)?, "two\n");
+
+    // The new key goes before the last listed one, so the listings continuing
+    // after it don't return the object
+    let store = store.with_object("prefix/1.csv", b"one\n", 0);
+    assert_eq!(read_all_objects(&mut scanner)?, "");
+
+    // The store is listed in full once the interval passes
+    thread::sleep(Duration::from_secs(1));
+    assert_eq!(read_all_objects(&mut scanner)?, "one\n");
+    assert_eq!(
+        wait_for_requests(&store, 2),
+        vec!["prefix/1.csv", "prefix/2.csv"]
+    );
+
+    Ok(())
+}
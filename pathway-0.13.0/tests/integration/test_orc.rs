@@ -0,0 +1,192 @@
+// Copyright © 2024 Pathway
+
+use super::helpers::read_entries;
+
+use std::collections::HashMap;
+use std::fs::File;
+use std::path::Path;
+use std::sync::Arc;
+
+use deltalake::arrow::array::{Int32Array, RecordBatch, StringArray};
+use deltalake::arrow::datatypes::{DataType, Field, Schema};
+use orc_rust::arrow_writer::ArrowWriterBuilder;
+use tempfile::tempdir;
+
+use pathway_engine::connectors::data_format::FormatterContext;
+use pathway_engine::connectors::data_storage::{
+    DataEventType, OrcFileReader, OrcFileWriter, Reader, ReaderContext, WriteError, Writer,
+};
+use pathway_engine::connectors::{OffsetKey, OffsetValue};
+use pathway_engine::engine::{Key, Timestamp, Type, Value};
+use pathway_engine::persistence::frontier::OffsetAntichain;
+use pathway_engine::python_api::ValueField;
+
+fn create_orc_file(path: &Path) -> eyre::Result<()> {
+    // Hive tables commonly use 32-bit integers, which are widened when read
+    let schema = Arc::new(Schema::new(vec![
+        Field::new("id", DataType::Int32, false),
+        Field::new("name", DataType::Utf8, true),
+    ]));
+    let batch = RecordBatch::try_new(
+        schema.clone(),
+        vec![
+            Arc::new(Int32Array::from(vec![1, 2, 3])),
+            Arc::new(StringArray::from(vec![Some("a"), None, Some("c")])),
+        ],
+    )?;
+
+    let mut writer = ArrowWriterBuilder::new(File::create(path)?, schema).try_build()?;
+    writer.write(&batch)?;
+    writer.close()?;
+    Ok(())
+}
+
+fn read_all_rows(reader: &mut OrcFileReader) -> eyre::Result<Vec<ReaderContext>> {
+    Ok(read_entries(reader)?
+        .into_iter()
+        .map(|(context, _)| context)
+        .collect())
+}
+
+fn expected_row(values: &[(&str, Value)]) -> ReaderContext {
+    ReaderContext::from_diff(
+        DataEventType::Insert,
+        None,
+        values
+            .iter()
+            .map(|(name, value)| ((*name).to_owned(), value.clone()))
+            .collect::<HashMap<_, _>>()
+            .into(),
+    )
+}
+
+fn value_field(name: &str, type_: Type, is_optional: bool) -> ValueField {
+    ValueField {
+        name: name.to_string(),
+        type_,
+        is_optional,
+        default: None,
+    }
+}
+
+#[test]
+fn test_orc_read_file() -> eyre::Result<()> {
+    let test_storage = tempdir()?;
+    let path = test_storage.path().join("input.orc");
+    create_orc_file(&path)?;
+
+    let mut reader = OrcFileReader::new(&path, None);
+    assert_eq!(
+        read_all_rows(&mut reader)?,
+        vec![
+            expected_row(&[("id", Value::Int(1)), ("name", Value::String("a".into()))]),
+            expected_row(&[("id", Value::Int(2)), ("name", Value::None)]),
+            expected_row(&[("id", Value::Int(3)), ("name", Value::String("c".into()))]),
+        ]
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_orc_seek() -> eyre::Result<()> {
+    let test_storage = tempdir()?;
+    let path = test_storage.path().join("input.orc");
+    create_orc_file(&path)?;
+
+    let mut frontier = OffsetAntichain::new();
+    frontier.advance_offset(
+        OffsetKey::Empty,
+        OffsetValue::FilePosition {
+            total_entries_read: 2,
+            path: Arc::new(path.clone()),
+            bytes_offset: 0,
+        },
+    );
+
+    let mut reader = OrcFileReader::new(&path, None);
+    reader.seek(&frontier)?;
+    assert_eq!(
+        read_all_rows(&mut reader)?,
+        vec![expected_row(&[
+            ("id", Value::Int(3)),
+            ("name", Value::String("c".into()))
+        ])]
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_orc_writer_round_trip() -> eyre::Result<()> {
+    let test_storage = tempdir()?;
+    let path_prefix = test_storage.path().join("output");
+    let value_fields = vec![
+        value_field("id", Type::Int, false),
+        value_field("name", Type::String, true),
+        value_field("flag", Type::Bool, false),
+        value_field("payload", Type::Bytes, false),
+    ];
+
+    let mut writer = OrcFileWriter::new(
+        path_prefix.to_str().unwrap().to_string(),
+        &value_fields,
+        None,
+    )?;
+    let rows = [
+        (1, Value::String("a".into()), true, 1),
+        (2, Value::None, false, -1),
+    ];
+    for (id, name, flag, diff) in rows.clone() {
+        writer.write(FormatterContext::new(
+            Vec::new(),
+            Key::random(),
+            vec![
+                Value::Int(id),
+                name,
+                Value::Bool(flag),
+                Value::Bytes(vec![1, 2].into()),
+            ],
+            Timestamp(2),
+            diff,
+        ))?;
+    }
+    writer.flush(true)?;
+
+    let path = test_storage.path().join("output-0.orc");
+    let mut reader = OrcFileReader::new(&path, None);
+    let expected_rows: Vec<_> = rows
+        .into_iter()
+        .map(|(id, name, flag, diff)| {
+            expected_row(&[
+                ("id", Value::Int(id)),
+                ("name", name),
+                ("flag", Value::Bool(flag)),
+                ("payload", Value::Bytes(vec![1, 2].into())),
+                ("time", Value::Int(2)),
+                ("diff", Value::Int(diff)),
+            ])
+        })
+        .collect();
+    assert_eq!(read_all_rows(&mut reader)?, expected_rows);
+
+    // A flush without the buffered rows doesn't produce a file
+    writer.flush(true)?;
+    assert!(!test_storage.path().join("output-1.orc").exists());
+
+    Ok(())
+}
+
+#[test]
+fn test_orc_writer_unsupported_type() {
+    let test_storage = tempdir().expect("tempdir creation failed");
+    let path_prefix = test_storage.path().join("output");
+    for type_ in [Type::Tuple, Type::Any] {
+        let result = OrcFileWriter::new(
+            path_prefix.to_str().unwrap().to_string(),
+            &[value_field("data", type_, false)],
+            None,
+        );
+        assert!(matches!(result, Err(WriteError::UnsupportedType(t)) if t == type_));
+    }
+}
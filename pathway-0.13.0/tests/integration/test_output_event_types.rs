@@ -0,0 +1,208 @@
+// Copyright © 2024 Pathway
+
+use std::fs::File;
+use std::io::BufWriter;
+
+use tempfile::tempdir;
+
+use pathway_engine::connectors::data_format::FormatterContext;
+use pathway_engine::connectors::data_storage::{
+    DataEventType, ElasticSearchBulkAction, ElasticSearchWriter, FileWriter, PayloadFraming,
+    WriteError, Writer,
+};
+use pathway_engine::engine::{Key, Timestamp};
+
+fn action_lines(event_type: DataEventType, id: Option<&str>) -> Vec<String> {
+    bulk_action_lines(ElasticSearchBulkAction::Index, event_type, id)
+}
+
+fn bulk_action_lines(
+    bulk_action: ElasticSearchBulkAction,
+    event_type: DataEventType,
+    id: Option<&str>,
+) -> Vec<String> {
+    ElasticSearchWriter::bulk_action_lines(
+        bulk_action,
+        event_type,
+        id,
+        br#"{"id":"a","v":1}"#.to_vec(),
+    )
+    .into_iter()
+    .map(|line| String::from_utf8(line).unwrap())
+    .collect()
+}
+
+#[test]
+fn test_event_type_derived_from_diff() {
+    let insertion = FormatterContext::new(Vec::new(), Key::random(), Vec::new(), Timestamp(0), 1);
+    assert_eq!(insertion.event_type, DataEventType::Insert);
+
+    let deletion = FormatterContext::new(Vec::new(), Key::random(), Vec::new(), Timestamp(0), -1);
+    assert_eq!(deletion.event_type, DataEventType::Delete);
+
+    let upsert = insertion.with_event_type(DataEventType::Upsert);
+    assert_eq!(upsert.event_type, DataEventType::Upsert);
+}
+
+#[test]
+fn test_file_writer_tombstone_marker() -> eyre::Result<()> {
+    let test_storage = tempdir()?;
+    let path = test_storage.path().join("output.txt");
+
+    let mut writer = FileWriter::new(
+        BufWriter::new(File::create(&path)?),
+        PayloadFraming::Newline,
+        Some(b"DELETED ".to_vec()),
+        false,
+    );
+    for (payload, diff) in [(b"a", 1), (b"b", -1), (b"c", 1)] {
+        writer.write(FormatterContext::new_single_payload(
+            payload.to_vec(),
+            Key::random(),
+            Vec::new(),
+            Timestamp(0),
+            diff,
+        ))?;
+    }
+    writer.flush(true)?;
+
+    assert_eq!(std::fs::read_to_string(&path)?, "a\nDELETED b\nc\n");
+
+    Ok(())
+}
+
+#[test]
+fn test_file_writer_without_tombstone_marker() -> eyre::Result<()> {
+    let test_storage = tempdir()?;
+    let path = test_storage.path().join("output.txt");
+
+    let mut writer = FileWriter::new(
+        BufWriter::new(File::create(&path)?),
+        PayloadFraming::Newline,
+        None,
+        false,
+    );
+    writer.write(FormatterContext::new_single_payload(
+        b"a".to_vec(),
+        Key::random(),
+        Vec::new(),
+        Timestamp(0),
+        -1,
+    ))?;
+    writer.flush(true)?;
+
+    assert_eq!(std::fs::read_to_string(&path)?, "a\n");
+
+    Ok(())
+}
+
+#[test]
+fn test_file_writer_fsync_on_flush() -> eyre::Result<()> {
+    let test_storage = tempdir()?;
+    let path = test_storage.path().join("output.txt");
+
+    let mut writer = FileWriter::new(
+        BufWriter::new(File::create(&path)?),
+        PayloadFraming::Newline,
+        None,
+        true,
+    );
+    for (time, payload) in [(0, b"a"), (2, b"b")] {
+        writer.write(FormatterContext::new_single_payload(
+            payload.to_vec(),
+            Key::random(),
+            Vec::new(),
+            Timestamp(time),
+            1,
+        ))?;
+        writer.flush(false)?;
+        // The commit syncs the file, so the data written so far is on the disk
+        writer.on_commit(Timestamp(time))?;
+    }
+    writer.flush(true)?;
+
+    assert_eq!(std::fs::read_to_string(&path)?, "a\nb\n");
+
+    Ok(())
+}
+
+#[test]
+fn test_elasticsearch_actions_without_id() {
+    // Without the id field every event is indexed as a new document
+    for event_type in [
+        DataEventType::Insert,
+        DataEventType::Delete,
+        DataEventType::Upsert,
+    ] {
+        assert_eq!(
+            action_lines(event_type, None),
+            vec![r#"{"index": {}}"#, r#"{"id":"a","v":1}"#]
+        );
+    }
+}
+
+#[test]
+fn test_elasticsearch_actions_with_id() {
+    assert_eq!(
+        action_lines(DataEventType::Insert, Some("a")),
+        vec![r#"{"index": {"_id": "a"}}"#, r#"{"id":"a","v":1}"#]
+    );
+    assert_eq!(
+        action_lines(DataEventType::Delete, Some("a")),
+        vec![r#"{"delete": {"_id": "a"}}"#]
+    );
+    assert_eq!(
+        action_lines(DataEventType::Upsert, Some("a")),
+        vec![
+            r#"{"update": {"_id": "a"}}"#,
+            r#"{"doc": {"id":"a","v":1}, "doc_as_upsert": true}"#
+        ]
+    );
+}
+
+#[test]
+fn test_elasticsearch_create_action() {
+    assert_eq!(
+        bulk_action_lines(ElasticSearchBulkAction::Create, DataEventType::Insert, None),
+        vec![r#"{"create": {}}"#, r#"{"id":"a","v":1}"#]
+    );
+    assert_eq!(
+        bulk_action_lines(
+            ElasticSearchBulkAction::Create,
+            DataEventType::Insert,
+            Some("a")
+        ),
+        vec![r#"{"create": {"_id": "a"}}"#, r#"{"id":"a","v":1}"#]
+    );
+    // Only the insertions depend on the configured action
+    assert_eq!(
+        bulk_action_lines(
+            ElasticSearchBulkAction::Create,
+            DataEventType::Delete,
+            Some("a")
+        ),
+        vec![r#"{"delete": {"_id": "a"}}"#]
+    );
+}
+
+#[test]
+fn test_elasticsearch_document_id() -> eyre::Result<()> {
+    assert_eq!(
+        ElasticSearchWriter::document_id(br#"{"id":"a\"b"}"#, "id")?,
+        "a\"b"
+    );
+    assert_eq!(
+        ElasticSearchWriter::document_id(br#"{"id":42}"#, "id")?,
+        "42"
+    );
+    assert!(matches!(
+        ElasticSearchWriter::document_id(br#"{"id":null}"#, "id"),
+        Err(WriteError::ElasticsearchMissingIdField(field)) if field == "id"
+    ));
+    assert!(matches!(
+        ElasticSearchWriter::document_id(br#"{"v":1}"#, "id"),
+        Err(WriteError::ElasticsearchMissingIdField(_))
+    ));
+
+    Ok(())
+}
@@ -0,0 +1,212 @@
+// Copyright © 2024 Pathway
+
+use super::helpers::read_entries;
+
+use std::collections::HashMap;
+use std::fs::File;
+use std::path::Path;
+use std::sync::Arc;
+
+use deltalake::arrow::array::{Int64Array, RecordBatch, StringArray};
+use deltalake::arrow::datatypes::{DataType, Field, Schema};
+use deltalake::datafusion::parquet::arrow::ArrowWriter;
+use tempfile::tempdir;
+
+use pathway_engine::connectors::data_format::FormatterContext;
+use pathway_engine::connectors::data_storage::{
+    DataEventType, ParquetFileReader, ParquetFileWriter, Reader, ReaderContext, Writer,
+};
+use pathway_engine::connectors::{OffsetKey, OffsetValue};
+use pathway_engine::engine::{Duration, Key, Timestamp, Type, Value};
+use pathway_engine::persistence::frontier::OffsetAntichain;
+use pathway_engine::python_api::ValueField;
+
+fn create_parquet_file(path: &Path) -> eyre::Result<()> {
+    let schema = Arc::new(Schema::new(vec![
+        Field::new("id", DataType::Int64, false),
+        Field::new("name", DataType::Utf8, true),
+    ]));
+    let batch = RecordBatch::try_new(
+        schema.clone(),
+        vec![
+            Arc::new(Int64Array::from(vec![1, 2, 3])),
+            Arc::new(StringArray::from(vec![Some("a"), None, Some("c")])),
+        ],
+    )?;
+
+    let mut writer = ArrowWriter::try_new(File::create(path)?, schema, None)?;
+    writer.write(&batch)?;
+    writer.close()?;
+    Ok(())
+}
+
+fn read_all_rows(reader: &mut ParquetFileReader) -> eyre::Result<Vec<ReaderContext>> {
+    Ok(read_entries(reader)?
+        .into_iter()
+        .map(|(context, _)| context)
+        .collect())
+}
+
+fn expected_row(id: i64, name: Value) -> ReaderContext {
+    ReaderContext::from_diff(
+        DataEventType::Insert,
+        None,
+        HashMap::from([("id".to_owned(), Value::Int(id)), ("name".to_owned(), name)]).into(),
+    )
+}
+
+#[test]
+fn test_parquet_read_file() -> eyre::Result<()> {
+    let test_storage = tempdir()?;
+    let path = test_storage.path().join("input.parquet");
+    create_parquet_file(&path)?;
+
+    let mut reader = ParquetFileReader::new(&path, None);
+    assert_eq!(
+        read_all_rows(&mut reader)?,
+        vec![
+            expected_row(1, Value::String("a".into())),
+            expected_row(2, Value::None),
+            expected_row(3, Value::String("c".into())),
+        ]
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_parquet_seek() -> eyre::Result<()> {
+    let test_storage = tempdir()?;
+    let path = test_storage.path().join("input.parquet");
+    create_parquet_file(&path)?;
+
+    let mut frontier = OffsetAntichain::new();
+    frontier.advance_offset(
+        OffsetKey::Empty,
+        OffsetValue::FilePosition {
+            total_entries_read: 2,
+            path: Arc::new(path.clone()),
+            bytes_offset: 0,
+        },
+    );
+
+    let mut reader = ParquetFileReader::new(&path, None);
+    reader.seek(&frontier)?;
+    assert_eq!(
+        read_all_rows(&mut reader)?,
+        vec![expected_row(3, Value::String("c".into()))]
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_parquet_writer_rotates_files() -> eyre::Result<()> {
+    let test_storage = tempdir()?;
+    let path_prefix = test_storage.path().join("output");
+    let value_fields = vec![
+        ValueField {
+            name: "id".to_string(),
+            type_: Type::Int,
+            is_optional: false,
+            default: None,
+        },
+        ValueField {
+            name: "name".to_string(),
+            type_: Type::String,
+            is_optional: true,
+            default: None,
+        },
+    ];
+
+    let mut writer = ParquetFileWriter::new(
+        path_prefix.to_str().unwrap().to_string(),
+        &value_fields,
+        Some(1),
+        None,
+    )?;
+    for (id, name) in [(1, Value::String("a".into())), (2, Value::None)] {
+        writer.write(FormatterContext::new(
+            Vec::new(),
+            Key::random(),
+            vec![Value::Int(id), name],
+            Timestamp(2),
+            1,
+        ))?;
+        writer.flush(true)?;
+    }
+
+    for (index, (id, name)) in [(1, Value::String("a".into())), (2, Value::None)]
+        .into_iter()
+        .enumerate()
+    {
+        let path = test_storage.path().join(format!("output-{index}.parquet"));
+        let mut reader = ParquetFileReader::new(&path, None);
+        assert_eq!(
+            read_all_rows(&mut reader)?,
+            vec![ReaderContext::from_diff(
+                DataEventType::Insert,
+                None,
+                HashMap::from([
+                    ("id".to_owned(), Value::Int(id)),
+                    ("name".to_owned(), name),
+                    ("time".to_owned(), Value::Int(2)),
+                    ("diff".to_owned(), Value::Int(1)),
+                ])
+                .into(),
+            )]
+        );
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_parquet_duration_round_trip() -> eyre::Result<()> {
+    let test_storage = tempdir()?;
+    let path_prefix = test_storage.path().join("output");
+    let value_fields = vec![ValueField {
+        name: "duration".to_string(),
+        type_: Type::Duration,
+        is_optional: true,
+        default: None,
+    }];
+
+    let mut writer = ParquetFileWriter::new(
+        path_prefix.to_str().unwrap().to_string(),
+        &value_fields,
+        None,
+        None,
+    )?;
+    let values = [Value::Duration(Duration::new(-2_500_000)), Value::None];
+    for value in &values {
+        writer.write(FormatterContext::new(
+            Vec::new(),
+            Key::random(),
+            vec![value.clone()],
+            Timestamp(2),
+            1,
+        ))?;
+    }
+    writer.flush(true)?;
+
+    let mut reader = ParquetFileReader::new(test_storage.path().join("output-0.parquet"), None);
+    let expected_rows: Vec<_> = values
+        .into_iter()
+        .map(|value| {
+            ReaderContext::from_diff(
+                DataEventType::Insert,
+                None,
+                HashMap::from([
+                    ("duration".to_owned(), value),
+                    ("time".to_owned(), Value::Int(2)),
+                    ("diff".to_owned(), Value::Int(1)),
+                ])
+                .into(),
+            )
+        })
+        .collect();
+    assert_eq!(read_all_rows(&mut reader)?, expected_rows);
+
+    Ok(())
+}
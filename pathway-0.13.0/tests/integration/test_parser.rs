@@ -1,6 +1,7 @@
 // Copyright © 2024 Pathway
 
 use std::collections::HashMap;
+use std::sync::Arc;
 
 use crate::helpers::ReplaceErrors;
 
@@ -8,7 +9,9 @@ use itertools::Itertools;
 use pathway_engine::connectors::data_format::{
     InnerSchemaField, ParsedEvent, Parser, TransparentParser,
 };
-use pathway_engine::connectors::data_storage::{DataEventType, ReaderContext};
+use pathway_engine::connectors::data_storage::{
+    DataEventType, ReadError, ReaderContext, ValuesMap, ValuesMapColumns,
+};
 use pathway_engine::connectors::SessionType;
 use pathway_engine::engine::{Type, Value};
 
@@ -177,3 +180,68 @@ fn test_transparent_parser_upsert() -> eyre::Result<()> {
     }
     Ok(())
 }
+
+#[test]
+fn test_transparent_parser_ordered_values() -> eyre::Result<()> {
+    let value_field_names = vec!["a".to_owned(), "b".to_owned()];
+    let schema = HashMap::from([
+        (
+            "a".to_owned(),
+            InnerSchemaField::new(Type::Int, false, None),
+        ),
+        (
+            "b".to_owned(),
+            InnerSchemaField::new(Type::String, true, None),
+        ),
+    ]);
+    let mut parser = TransparentParser::new(None, value_field_names, schema, SessionType::Native);
+
+    // The order of columns provided by the reader differs from the order of fields
+    let columns = Arc::new(ValuesMapColumns::new(vec!["b".to_owned(), "a".to_owned()]));
+    let values = ValuesMap::from_ordered(columns, vec![Value::from("abc"), Value::Int(3)])?;
+    assert_eq!(
+        values,
+        ValuesMap::from(HashMap::from([
+            ("a".to_owned(), Value::Int(3)),
+            ("b".to_owned(), Value::from("abc")),
+        ]))
+    );
+
+    let context = ReaderContext::from_diff(DataEventType::Insert, None, values);
+    assert_eq!(
+        parser
+            .parse(&context)
+            .expect("creating message should not fail")
+            .into_iter()
+            .exactly_one()?
+            .replace_errors(),
+        ParsedEvent::Insert((None, vec![Value::from(3), Value::from("abc")]))
+    );
+    Ok(())
+}
+
+#[test]
+fn test_ordered_values_map() -> eyre::Result<()> {
+    let columns = Arc::new(ValuesMapColumns::new(vec![
+        "b".to_owned(),
+        "a".to_owned(),
+        "c".to_owned(),
+    ]));
+    let values = ValuesMap::from_ordered(
+        columns.clone(),
+        vec![Value::from("abc"), Value::Int(3), Value::None],
+    )?;
+    assert_eq!(values.get("a"), Some(&Value::Int(3)));
+    assert_eq!(values.get("b"), Some(&Value::from("abc")));
+    assert_eq!(values.get("c"), Some(&Value::None));
+    assert_eq!(values.get("d"), None);
+
+    assert!(matches!(
+        ValuesMap::from_ordered(columns, vec![Value::Int(3)]),
+        Err(ReadError::ValuesMapLengthMismatch {
+            columns: 3,
+            values: 1
+        })
+    ));
+    Ok(())
+}
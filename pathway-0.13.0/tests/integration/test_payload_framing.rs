@@ -0,0 +1,146 @@
+// Copyright © 2024 Pathway
+
+use super::helpers::{raw_bytes, read_entries};
+
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::Path;
+use std::sync::Arc;
+
+use tempfile::tempdir;
+
+use pathway_engine::connectors::data_format::FormatterContext;
+use pathway_engine::connectors::data_storage::{
+    CompressedFileWriter, CompressionFormat, ConnectorMode, FileWriter, FilesystemReader,
+    FilesystemReaderSettings, PayloadFraming, ReadMethod, Reader, Writer,
+};
+use pathway_engine::connectors::{OffsetKey, OffsetValue};
+use pathway_engine::engine::{Key, Timestamp};
+use pathway_engine::persistence::frontier::OffsetAntichain;
+
+// Binary payloads containing the newlines and the zero bytes
+const PAYLOADS: [&[u8]; 3] = [b"\x81\xa1a\x01", b"\n\x00\n", b""];
+
+fn write_payloads(writer: &mut dyn Writer, diffs: &[isize]) -> eyre::Result<()> {
+    for (payload, diff) in PAYLOADS.into_iter().zip(diffs) {
+        writer.write(FormatterContext::new_single_payload(
+            payload.to_vec(),
+            Key::random(),
+            Vec::new(),
+            Timestamp(0),
+            *diff,
+        ))?;
+    }
+    writer.flush(true)?;
+    Ok(())
+}
+
+fn reader_for(path: &Path) -> eyre::Result<FilesystemReader> {
+    Ok(FilesystemReader::new(
+        &[path.to_str().unwrap()],
+        ConnectorMode::Static,
+        None,
+        ReadMethod::LengthPrefixed,
+        FilesystemReaderSettings::default(),
+    )?)
+}
+
+fn read_payloads(reader: &mut FilesystemReader) -> eyre::Result<Vec<Vec<u8>>> {
+    Ok(read_entries(reader)?
+        .into_iter()
+        .map(|(context, _)| raw_bytes(context))
+        .collect())
+}
+
+fn expected_payloads(payloads: &[&[u8]]) -> Vec<Vec<u8>> {
+    payloads.iter().map(|payload| payload.to_vec()).collect()
+}
+
+#[test]
+fn test_length_prefixed_round_trip() -> eyre::Result<()> {
+    let test_storage = tempdir()?;
+    let path = test_storage.path().join("output.bin");
+    let mut writer = FileWriter::new(
+        BufWriter::new(File::create(&path)?),
+        PayloadFraming::LengthPrefixed,
+        None,
+        false,
+    );
+    write_payloads(&mut writer, &[1, 1, 1])?;
+
+    let mut reader = reader_for(&path)?;
+    assert_eq!(read_payloads(&mut reader)?, expected_payloads(&PAYLOADS));
+
+    Ok(())
+}
+
+#[test]
+fn test_length_prefixed_tombstone_in_frame() -> eyre::Result<()> {
+    let test_storage = tempdir()?;
+    let path = test_storage.path().join("output.bin");
+    let mut writer = FileWriter::new(
+        BufWriter::new(File::create(&path)?),
+        PayloadFraming::LengthPrefixed,
+        Some(b"DELETED ".to_vec()),
+        false,
+    );
+    write_payloads(&mut writer, &[1, -1, 1])?;
+
+    let mut reader = reader_for(&path)?;
+    assert_eq!(
+        read_payloads(&mut reader)?,
+        expected_payloads(&[PAYLOADS[0], b"DELETED \n\x00\n", PAYLOADS[2]])
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_length_prefixed_compressed_round_trip() -> eyre::Result<()> {
+    let test_storage = tempdir()?;
+    let path = test_storage.path().join("output.bin.gz");
+    let mut writer = CompressedFileWriter::new(
+        BufWriter::new(File::create(&path)?),
+        CompressionFormat::Gzip,
+        PayloadFraming::LengthPrefixed,
+    );
+    write_payloads(&mut writer, &[1, 1, 1])?;
+
+    let mut reader = reader_for(&path)?;
+    assert_eq!(read_payloads(&mut reader)?, expected_payloads(&PAYLOADS));
+
+    Ok(())
+}
+
+#[test]
+fn test_length_prefixed_seek() -> eyre::Result<()> {
+    let test_storage = tempdir()?;
+    let path = test_storage.path().join("output.bin");
+    let mut writer = FileWriter::new(
+        BufWriter::new(File::create(&path)?),
+        PayloadFraming::LengthPrefixed,
+        None,
+        false,
+    );
+    write_payloads(&mut writer, &[1, 1, 1])?;
+
+    // Each frame takes four bytes of the length in addition to the payload
+    let mut frontier = OffsetAntichain::new();
+    frontier.advance_offset(
+        OffsetKey::Empty,
+        OffsetValue::FilePosition {
+            total_entries_read: 1,
+            path: Arc::new(path.clone()),
+            bytes_offset: 4 + PAYLOADS[0].len() as u64,
+        },
+    );
+
+    let mut reader = reader_for(&path)?;
+    reader.seek(&frontier)?;
+    assert_eq!(
+        read_payloads(&mut reader)?,
+        expected_payloads(&PAYLOADS[1..])
+    );
+
+    Ok(())
+}
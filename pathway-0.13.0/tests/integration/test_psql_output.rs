@@ -1,6 +1,9 @@
 // Copyright © 2024 Pathway
 
+use std::thread::sleep;
+
 use pathway_engine::connectors::data_format::{Formatter, FormatterError, PsqlUpdatesFormatter};
+use pathway_engine::connectors::data_storage::CommitPolicy;
 use pathway_engine::engine::{DateTimeNaive, DateTimeUtc, Duration, Key, Timestamp, Value};
 
 #[test]
@@ -373,3 +376,38 @@ fn test_psql_format_duration() -> eyre::Result<()> {
 
     Ok(())
 }
+
+#[test]
+fn test_psql_commit_policy_batch_size_one() {
+    let policy = CommitPolicy::new(Some(1), None);
+    assert!(!policy.is_batch_full(0));
+    assert!(policy.is_batch_full(1));
+    // The buffer that somehow got past the limit must be committed as well
+    assert!(policy.is_batch_full(2));
+
+    // Without the commit frequency every flush commits
+    assert!(policy.is_commit_due(1, false));
+    assert!(!policy.is_commit_due(0, true));
+}
+
+#[test]
+fn test_psql_commit_policy_time_based() {
+    let mut policy = CommitPolicy::new(None, Some(std::time::Duration::from_millis(100)));
+    assert!(!policy.is_batch_full(1_000_000));
+    assert!(!policy.is_commit_due(5, false));
+    assert!(policy.is_commit_due(5, true));
+
+    sleep(std::time::Duration::from_millis(150));
+    assert!(policy.is_commit_due(5, false));
+
+    policy.on_commit();
+    assert!(!policy.is_commit_due(5, false));
+}
+
+#[test]
+fn test_psql_commit_policy_batch_boundary() {
+    let policy = CommitPolicy::new(Some(3), None);
+    assert!(!policy.is_batch_full(2));
+    assert!(policy.is_batch_full(3));
+    assert!(policy.is_batch_full(4));
+}
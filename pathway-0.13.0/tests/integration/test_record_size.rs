@@ -0,0 +1,120 @@
+// Copyright © 2024 Pathway
+
+use super::helpers::raw_bytes;
+
+use std::path::Path;
+
+use tempfile::tempdir;
+
+use pathway_engine::connectors::data_storage::{
+    ConnectorMode, FilesystemReader, FilesystemReaderSettings, ReadError, ReadMethod, ReadResult,
+    Reader,
+};
+
+fn reader_for(
+    path: &Path,
+    read_method: ReadMethod,
+    max_record_bytes: Option<usize>,
+) -> eyre::Result<FilesystemReader> {
+    Ok(FilesystemReader::new(
+        &[path.to_str().unwrap()],
+        ConnectorMode::Static,
+        None,
+        read_method,
+        FilesystemReaderSettings {
+            max_record_bytes,
+            ..Default::default()
+        },
+    )?)
+}
+
+/// Reads the entries until the end of the input or the first error, which is
+/// returned along with the entries read before it.
+fn read_entries(reader: &mut FilesystemReader) -> (Vec<Vec<u8>>, Option<ReadError>) {
+    let mut entries = Vec::new();
+    loop {
+        match reader.read() {
+            Ok(ReadResult::Data(context, _)) => entries.push(raw_bytes(context)),
+            Ok(ReadResult::Finished) => return (entries, None),
+            Ok(ReadResult::NewSource(_) | ReadResult::FinishedSource { .. }) => {}
+            Err(error) => return (entries, Some(error)),
+        }
+    }
+}
+
+#[test]
+fn test_long_line_rejected() -> eyre::Result<()> {
+    let test_storage = tempdir()?;
+    let path = test_storage.path().join("input.txt");
+    std::fs::write(&path, "ab\nabcd\nabcdefgh\nab\n")?;
+
+    // The limit includes the line terminator
+    let mut reader = reader_for(&path, ReadMethod::ByLine, Some(5))?;
+    let (entries, error) = read_entries(&mut reader);
+    assert_eq!(entries, vec![b"ab\n".to_vec(), b"abcd\n".to_vec()]);
+    assert!(
+        matches!(error, Some(ReadError::RecordTooLarge(5))),
+        "{error:?}"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_no_limit_by_default() -> eyre::Result<()> {
+    let test_storage = tempdir()?;
+    let path = test_storage.path().join("input.txt");
+    let long_line = "a".repeat(1 << 20);
+    std::fs::write(&path, &long_line)?;
+
+    let mut reader = reader_for(&path, ReadMethod::ByLineWithoutTerminator, None)?;
+    let (entries, error) = read_entries(&mut reader);
+    assert!(error.is_none(), "{error:?}");
+    assert_eq!(entries, vec![long_line.into_bytes()]);
+
+    Ok(())
+}
+
+#[test]
+fn test_full_read_limits_file_size() -> eyre::Result<()> {
+    let test_storage = tempdir()?;
+    let path = test_storage.path().join("input.txt");
+    std::fs::write(&path, "ab\ncd\nef\n")?;
+
+    let mut reader = reader_for(&path, ReadMethod::Full, Some(9))?;
+    let (entries, error) = read_entries(&mut reader);
+    assert!(error.is_none(), "{error:?}");
+    assert_eq!(entries, vec![b"ab\ncd\nef\n".to_vec()]);
+
+    let mut reader = reader_for(&path, ReadMethod::Full, Some(8))?;
+    let (entries, error) = read_entries(&mut reader);
+    assert!(entries.is_empty());
+    assert!(
+        matches!(error, Some(ReadError::RecordTooLarge(8))),
+        "{error:?}"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_oversized_frame_rejected_before_reading() -> eyre::Result<()> {
+    let test_storage = tempdir()?;
+    let path = test_storage.path().join("input.bin");
+    // A corrupted length announces a frame of almost 4 GiB
+    let mut contents = 2_u32.to_le_bytes().to_vec();
+    contents.extend_from_slice(b"ab");
+    contents.extend_from_slice(&u32::MAX.to_le_bytes());
+    contents.extend_from_slice(b"cd");
+    std::fs::write(&path, contents)?;
+
+    let mut reader = reader_for(&path, ReadMethod::LengthPrefixed, Some(1024))?;
+    let (entries, error) = read_entries(&mut reader);
+    assert_eq!(entries, vec![b"ab".to_vec()]);
+    assert!(
+        matches!(error, Some(ReadError::RecordTooLarge(1024))),
+        "{error:?}"
+    );
+
+    Ok(())
+}
@@ -0,0 +1,153 @@
+// Copyright © 2024 Pathway
+
+use std::io;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use assert_matches::assert_matches;
+
+use pathway_engine::connectors::data_format::FormatterContext;
+use pathway_engine::connectors::data_storage::{RetryingWriter, WriteError, Writer};
+use pathway_engine::engine::{Key, Timestamp, Value};
+
+/// Fails the first `failures` writes and flushes with errors produced by
+/// `make_error`, then succeeds. The successfully written payloads are recorded.
+struct FlakyWriter {
+    failures: usize,
+    make_error: fn() -> WriteError,
+    retriable: bool,
+    attempts: Arc<Mutex<usize>>,
+    written: Arc<Mutex<Vec<Vec<u8>>>>,
+}
+
+impl FlakyWriter {
+    fn try_once(&mut self) -> Result<(), WriteError> {
+        *self.attempts.lock().unwrap() += 1;
+        if self.failures > 0 {
+            self.failures -= 1;
+            return Err((self.make_error)());
+        }
+        Ok(())
+    }
+}
+
+impl Writer for FlakyWriter {
+    fn write(&mut self, data: FormatterContext) -> Result<(), WriteError> {
+        self.try_once()?;
+        self.written.lock().unwrap().extend(data.payloads);
+        Ok(())
+    }
+
+    fn flush(&mut self, _forced: bool) -> Result<(), WriteError> {
+        self.try_once()
+    }
+
+    fn retriable(&self) -> bool {
+        self.retriable
+    }
+}
+
+fn connection_reset() -> WriteError {
+    WriteError::Io(io::Error::from(io::ErrorKind::ConnectionReset))
+}
+
+fn unsupported_value() -> WriteError {
+    WriteError::SqliteUnsupportedValue(Value::None)
+}
+
+type Attempts = Arc<Mutex<usize>>;
+type Written = Arc<Mutex<Vec<Vec<u8>>>>;
+
+fn retrying_writer(
+    failures: usize,
+    make_error: fn() -> WriteError,
+    retriable: bool,
+) -> (RetryingWriter, Attempts, Written) {
+    let attempts = Arc::new(Mutex::new(0));
+    let written = Arc::new(Mutex::new(Vec::new()));
+    let inner = FlakyWriter {
+        failures,
+        make_error,
+        retriable,
+        attempts: attempts.clone(),
+        written: written.clone(),
+    };
+    let writer = RetryingWriter::new(Box::new(inner), 3, Duration::from_millis(1));
+    (writer, attempts, written)
+}
+
+fn context(payload: &[u8]) -> FormatterContext {
+    FormatterContext::new_single_payload(
+        payload.to_vec(),
+        Key::random(),
+        Vec::new(),
+        Timestamp(0),
+        1,
+    )
+}
+
+#[test]
+fn test_transient_errors_retried() -> eyre::Result<()> {
+    let (mut writer, attempts, written) = retrying_writer(2, connection_reset, true);
+    writer.write(context(b"a"))?;
+    assert_eq!(*attempts.lock().unwrap(), 3);
+    assert_eq!(*written.lock().unwrap(), vec![b"a".to_vec()]);
+    assert!(!writer.retriable());
+
+    Ok(())
+}
+
+#[test]
+fn test_flush_retried() -> eyre::Result<()> {
+    let (mut writer, attempts, _) = retrying_writer(1, connection_reset, true);
+    writer.flush(true)?;
+    assert_eq!(*attempts.lock().unwrap(), 2);
+
+    Ok(())
+}
+
+#[test]
+fn test_last_error_returned_after_all_attempts() {
+    let (mut writer, attempts, written) = retrying_writer(5, connection_reset, true);
+    assert_matches!(writer.write(context(b"a")), Err(WriteError::Io(_)));
+    assert_eq!(*attempts.lock().unwrap(), 3);
+    assert!(written.lock().unwrap().is_empty());
+}
+
+#[test]
+fn test_permanent_error_not_retried() {
+    let (mut writer, attempts, _) = retrying_writer(1, unsupported_value, true);
+    assert_matches!(
+        writer.write(context(b"a")),
+        Err(WriteError::SqliteUnsupportedValue(_))
+    );
+    assert_eq!(*attempts.lock().unwrap(), 1);
+}
+
+#[test]
+fn test_non_retriable_writer_not_retried() {
+    let (mut writer, attempts, _) = retrying_writer(1, connection_reset, false);
+    assert_matches!(writer.write(context(b"a")), Err(WriteError::Io(_)));
+    assert_matches!(writer.flush(true), Ok(()));
+    assert_eq!(*attempts.lock().unwrap(), 2);
+}
+
+#[test]
+fn test_finish_retries_final_flush() -> eyre::Result<()> {
+    let (writer, attempts, _) = retrying_writer(2, connection_reset, true);
+    Box::new(writer).finish()?;
+    // The retried flush is followed by the one finishing the inner writer
+    assert_eq!(*attempts.lock().unwrap(), 4);
+
+    Ok(())
+}
+
+#[test]
+fn test_finish_reports_error() {
+    let (writer, attempts, _) = retrying_writer(1, unsupported_value, true);
+    assert_matches!(
+        Box::new(writer).finish(),
+        Err(WriteError::SqliteUnsupportedValue(_))
+    );
+    assert_eq!(*attempts.lock().unwrap(), 1);
+}
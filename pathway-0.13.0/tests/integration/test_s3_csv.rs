@@ -0,0 +1,107 @@
+// Copyright © 2024 Pathway
+
+use std::collections::HashMap;
+
+use pathway_engine::connectors::data_format::InnerSchemaField;
+use pathway_engine::connectors::data_storage::{CsvObjectReader, S3CsvReader};
+use pathway_engine::engine::{Type, Value};
+
+fn schema() -> HashMap<String, InnerSchemaField> {
+    HashMap::from([
+        (
+            "id".to_string(),
+            InnerSchemaField::new(Type::Int, false, None),
+        ),
+        (
+            "score".to_string(),
+            InnerSchemaField::new(Type::Float, false, Some(Value::Float(0.0.into()))),
+        ),
+        (
+            "active".to_string(),
+            InnerSchemaField::new(Type::Bool, false, None),
+        ),
+    ])
+}
+
+fn tokens(values: &[&str]) -> Vec<String> {
+    values.iter().map(|value| (*value).to_string()).collect()
+}
+
+#[test]
+fn test_tokens_satisfying_schema() {
+    let header = tokens(&["id", "name", "score", "active"]);
+    assert!(
+        S3CsvReader::check_tokens(&schema(), &header, &tokens(&["1", "a", "2.5", "yes"])).is_ok()
+    );
+    // The empty token is replaced with the default, so it's accepted
+    assert!(S3CsvReader::check_tokens(&schema(), &header, &tokens(&["2", "b", "", "f"])).is_ok());
+}
+
+#[test]
+fn test_tokens_not_satisfying_schema() {
+    let header = tokens(&["id", "name", "score", "active"]);
+    assert!(
+        S3CsvReader::check_tokens(&schema(), &header, &tokens(&["x", "a", "2.5", "yes"])).is_err()
+    );
+    assert!(
+        S3CsvReader::check_tokens(&schema(), &header, &tokens(&["1", "a", "2.5", "maybe"]))
+            .is_err()
+    );
+}
+
+#[test]
+fn test_columns_outside_schema_not_checked() {
+    let header = tokens(&["name", "comment"]);
+    assert!(S3CsvReader::check_tokens(&schema(), &header, &tokens(&["a", "b"])).is_ok());
+}
+
+#[test]
+fn test_header_of_each_object_is_not_a_record() -> eyre::Result<()> {
+    let objects: [&[u8]; 2] = [b"id,name\n1,a\n2,b\n", b"id,name\n3,c\n"];
+    let mut records = Vec::new();
+    let mut offsets = Vec::new();
+    for object in objects {
+        let mut object_reader =
+            CsvObjectReader::new(csv::ReaderBuilder::new().has_headers(false), object)?;
+        assert_eq!(object_reader.header(), Some(&tokens(&["id", "name"])[..]));
+        while let Some(record) = object_reader.next_record()? {
+            records.push(record);
+            offsets.push(object_reader.position());
+        }
+    }
+    assert_eq!(
+        records,
+        vec![
+            tokens(&["1", "a"]),
+            tokens(&["2", "b"]),
+            tokens(&["3", "c"])
+        ]
+    );
+    // The byte positions are counted within each object, the header included
+    assert_eq!(offsets, vec![12, 16, 12]);
+
+    Ok(())
+}
+
+#[test]
+fn test_object_reader_skips_to_offset() -> eyre::Result<()> {
+    let object: &[u8] = b"id,name\n1,a\n2,b\n";
+    let mut object_reader =
+        CsvObjectReader::new(csv::ReaderBuilder::new().has_headers(false), object)?;
+    assert_eq!(object_reader.skip_to(12)?, 12);
+    assert_eq!(object_reader.next_record()?, Some(tokens(&["2", "b"])));
+    assert_eq!(object_reader.next_record()?, None);
+
+    Ok(())
+}
+
+#[test]
+fn test_empty_object_has_no_header() -> eyre::Result<()> {
+    let object: &[u8] = b"";
+    let mut object_reader =
+        CsvObjectReader::new(csv::ReaderBuilder::new().has_headers(false), object)?;
+    assert_eq!(object_reader.header(), None);
+    assert_eq!(object_reader.next_record()?, None);
+
+    Ok(())
+}
@@ -0,0 +1,431 @@
+// Copyright © 2024 Pathway
+
+use super::helpers::raw_bytes;
+
+use std::collections::HashSet;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::TcpListener;
+use std::path::PathBuf;
+use std::sync::{mpsc, Arc};
+use std::thread;
+use std::time::Duration;
+
+use s3::bucket::Bucket as S3Bucket;
+use s3::region::Region;
+use serde::Serialize;
+
+use pathway_engine::connectors::data_storage::{
+    ObjectOrdering, ProcessedS3Objects, ReadMethod, ReadResult, Reader, S3GenericReader, S3Scanner,
+};
+use pathway_engine::connectors::{OffsetKey, OffsetValue};
+use pathway_engine::deepcopy::DeepCopy;
+use pathway_engine::persistence::frontier::OffsetAntichain;
+
+const OBJECTS: [(&str, &str); 3] = [
+    ("prefix/one.csv", "2024-05-01T10:00:00.000Z"),
+    ("prefix/two.csv", "2024-05-01T10:00:01.000Z"),
+    ("prefix/three.csv", "2024-05-01T10:00:02.000Z"),
+];
+
+fn processed_objects(path: &str, last_modified: &str) -> HashSet<String> {
+    let last_modified = S3Scanner::parse_last_modified(last_modified).unwrap();
+    S3Scanner::objects_processed_before(OBJECTS, path, last_modified)
+}
+
+fn object_names(names: &[&str]) -> HashSet<String> {
+    names.iter().map(|name| (*name).to_string()).collect()
+}
+
+#[test]
+fn test_restart_in_the_middle_of_second_object() {
+    // The first object is fully read and the second is read partially, so the
+    // first one must not be streamed again after the restart
+    assert_eq!(
+        processed_objects("prefix/two.csv", "2024-05-01T10:00:01.000Z"),
+        object_names(&["prefix/one.csv", "prefix/two.csv"])
+    );
+}
+
+#[test]
+fn test_restart_after_overwritten_object() {
+    // The offset keeps the modification time observed while reading, so the later
+    // objects aren't skipped even if the current one was overwritten since then
+    let objects = [
+        ("prefix/one.csv", "2024-05-01T10:00:00.000Z"),
+        ("prefix/two.csv", "2024-05-01T10:00:05.000Z"),
+        ("prefix/three.csv", "2024-05-01T10:00:02.000Z"),
+    ];
+    let last_modified = S3Scanner::parse_last_modified("2024-05-01T10:00:01.000Z").unwrap();
+    assert_eq!(
+        S3Scanner::objects_processed_before(objects, "prefix/two.csv", last_modified),
+        object_names(&["prefix/one.csv", "prefix/two.csv"])
+    );
+}
+
+#[test]
+fn test_restart_after_deleted_object() {
+    assert_eq!(
+        processed_objects("prefix/deleted.csv", "2024-05-01T10:00:01.500Z"),
+        object_names(&["prefix/one.csv", "prefix/two.csv", "prefix/deleted.csv"])
+    );
+}
+
+#[test]
+fn test_same_modification_time_ordered_by_key() {
+    let objects = [
+        ("prefix/a.csv", "2024-05-01T10:00:00.000Z"),
+        ("prefix/b.csv", "2024-05-01T10:00:00.000Z"),
+        ("prefix/c.csv", "2024-05-01T10:00:00.000Z"),
+    ];
+    let last_modified = S3Scanner::parse_last_modified("2024-05-01T10:00:00.000Z").unwrap();
+    assert_eq!(
+        S3Scanner::objects_processed_before(objects, "prefix/b.csv", last_modified),
+        object_names(&["prefix/a.csv", "prefix/b.csv"])
+    );
+}
+
+#[test]
+fn test_object_pattern() {
+    let pattern = S3Scanner::compile_object_pattern("*.csv").unwrap();
+    assert!(pattern.matches("prefix/one.csv"));
+    assert!(pattern.matches("prefix/nested/two.csv"));
+    assert!(!pattern.matches("prefix/_SUCCESS"));
+    assert!(!pattern.matches("prefix/three.json"));
+}
+
+#[test]
+fn test_default_object_pattern_matches_everything() {
+    let pattern = S3Scanner::compile_object_pattern("*").unwrap();
+    for key in ["one.csv", "prefix/_SUCCESS", "prefix/nested/three.json"] {
+        assert!(pattern.matches(key));
+    }
+}
+
+#[test]
+fn test_processed_objects_eviction_moves_watermark() {
+    let mut processed_objects = ProcessedS3Objects::new(2);
+    processed_objects.insert("prefix/one.csv".to_string(), 1);
+    processed_objects.insert("prefix/two.csv".to_string(), 2);
+    processed_objects.insert("prefix/three.csv".to_string(), 3);
+
+    // The first object is evicted, but it's still processed thanks to the watermark
+    assert!(processed_objects.contains("prefix/one.csv", 1));
+    assert!(processed_objects.contains("prefix/two.csv", 2));
+    assert!(processed_objects.contains("prefix/three.csv", 3));
+    assert!(!processed_objects.contains("prefix/four.csv", 4));
+
+    // An evicted object that was overwritten since then has to be read again
+    assert!(!processed_objects.contains("prefix/one.csv", 5));
+}
+
+#[test]
+fn test_processed_objects_reset_to_watermark() {
+    let mut processed_objects = ProcessedS3Objects::new(ProcessedS3Objects::DEFAULT_CAPACITY);
+    processed_objects.insert("prefix/other.csv".to_string(), 10);
+    processed_objects.reset(Some((2, "prefix/two.csv".to_string())));
+
+    assert!(processed_objects.contains("prefix/one.csv", 1));
+    assert!(processed_objects.contains("prefix/a.csv", 2));
+    assert!(processed_objects.contains("prefix/two.csv", 2));
+    assert!(!processed_objects.contains("prefix/u.csv", 2));
+    assert!(!processed_objects.contains("prefix/other.csv", 10));
+}
+
+#[test]
+fn test_object_orderings() {
+    let earlier_by_time = (1, "prefix/b.csv");
+    let later_by_time = (2, "prefix/a.csv");
+    assert!(ObjectOrdering::default()
+        .compare(earlier_by_time, later_by_time)
+        .is_lt());
+    assert!(ObjectOrdering::KeyOnly
+        .compare(earlier_by_time, later_by_time)
+        .is_gt());
+    assert!(ObjectOrdering::KeyDescending
+        .compare(earlier_by_time, later_by_time)
+        .is_lt());
+}
+
+#[test]
+fn test_processed_objects_watermark_follows_ordering() {
+    // The modification times go against the key order, so only the keys matter
+    let mut processed_objects = ProcessedS3Objects::with_ordering(1, ObjectOrdering::KeyOnly);
+    processed_objects.insert("2024-05-01/one.csv".to_string(), 3);
+    processed_objects.insert("2024-05-02/one.csv".to_string(), 2);
+
+    assert!(processed_objects.contains("2024-05-01/one.csv", 3));
+    assert!(processed_objects.contains("2024-04-30/one.csv", 5));
+    assert!(!processed_objects.contains("2024-05-03/one.csv", 1));
+
+    let mut processed_objects = ProcessedS3Objects::with_ordering(1, ObjectOrdering::KeyDescending);
+    processed_objects.insert("2024-05-02/one.csv".to_string(), 1);
+    processed_objects.insert("2024-05-01/one.csv".to_string(), 2);
+
+    assert!(processed_objects.contains("2024-05-03/one.csv", 1));
+    assert!(!processed_objects.contains("2024-04-30/one.csv", 1));
+}
+
+/// Starts a server answering every request with `body`. The request line and the
+/// headers of each request are sent to the returned receiver.
+fn start_object_server(body: &'static str) -> eyre::Result<(String, mpsc::Receiver<Vec<String>>)> {
+    let listener = TcpListener::bind("127.0.0.1:0")?;
+    let address = listener.local_addr()?;
+    let (sender, receiver) = mpsc::channel();
+    thread::spawn(move || {
+        for stream in listener.incoming() {
+            let mut stream = stream.unwrap();
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+            let mut request = Vec::new();
+            loop {
+                let mut line = String::new();
+                reader.read_line(&mut line).unwrap();
+                if line.trim().is_empty() {
+                    break;
+                }
+                request.push(line.trim().to_string());
+            }
+            sender.send(request).unwrap();
+
+            let response = format!(
+                "HTTP/1.1 200 OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+                body.len()
+            );
+            stream.write_all(response.as_bytes()).unwrap();
+        }
+    });
+    Ok((format!("http://{address}"), receiver))
+}
+
+#[test]
+fn test_deep_copy_keeps_custom_endpoint() -> eyre::Result<()> {
+    let (endpoint, requests) = start_object_server("id,name\n1,a\n")?;
+    let region = Region::Custom {
+        region: "minio".to_string(),
+        endpoint: endpoint.clone(),
+    };
+    let mut bucket = S3Bucket::new_public("bucket", region.clone())?.with_path_style();
+    bucket.add_header("x-amz-server-side-encryption", "aws:kms");
+    bucket.set_request_timeout(Some(Duration::from_secs(5)));
+
+    let bucket_copy = bucket.deep_copy();
+    assert_eq!(bucket_copy.region, region);
+    assert!(bucket_copy.is_path_style());
+    assert_eq!(bucket_copy.extra_headers, bucket.extra_headers);
+    // The downloads of the copy are bounded by the same timeout
+    assert_eq!(bucket_copy.request_timeout, Some(Duration::from_secs(5)));
+
+    let (current_object, mut pipe_reader) =
+        S3Scanner::stream_object_from_path_and_bucket("prefix/one.csv", bucket_copy, 1);
+    let mut contents = String::new();
+    pipe_reader.read_to_string(&mut contents)?;
+    current_object.finalize()?;
+    assert_eq!(contents, "id,name\n1,a\n");
+
+    // The object is requested from the custom endpoint with the path-style address
+    // and the extra headers of the original bucket
+    let request = requests.recv()?;
+    assert!(
+        request[0].starts_with("GET /bucket/prefix/one.csv "),
+        "{request:?}"
+    );
+    assert!(request
+        .iter()
+        .any(|header| header.to_lowercase() == "x-amz-server-side-encryption: aws:kms"));
+
+    Ok(())
+}
+
+const BUCKET_OBJECTS: [(&str, &str, &str); 3] = [
+    ("prefix/one.txt", "2024-05-01T10:00:00.000Z", "a\nb\n"),
+    ("prefix/two.txt", "2024-05-01T10:00:01.000Z", "c\nd\n"),
+    ("prefix/three.txt", "2024-05-01T10:00:02.000Z", "e\nf\n"),
+];
+
+/// Starts a server of a bucket with `BUCKET_OBJECTS`, which answers the
+/// `ListObjectsV2` requests and the downloads of the objects.
+fn start_bucket_server() -> eyre::Result<String> {
+    let listener = TcpListener::bind("127.0.0.1:0")?;
+    let address = listener.local_addr()?;
+    thread::spawn(move || {
+        for stream in listener.incoming() {
+            let mut stream = stream.unwrap();
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+            let mut request_line = String::new();
+            reader.read_line(&mut request_line).unwrap();
+            loop {
+                let mut line = String::new();
+                reader.read_line(&mut line).unwrap();
+                if line.trim().is_empty() {
+                    break;
+                }
+            }
+
+            let target = request_line.split(' ').nth(1).unwrap();
+            let body = if let Some(query) = target.strip_prefix("/bucket/?") {
+                let start_after = query
+                    .split('&')
+                    .find_map(|param| param.strip_prefix("start-after="))
+                    .map(|key| key.replace("%2F", "/"));
+                let contents: String = BUCKET_OBJECTS
+                    .iter()
+                    .filter(|(key, _, _)| start_after.as_deref().map_or(true, |after| *key > after))
+                    .map(|(key, last_modified, contents)| {
+                        format!(
+                            "<Contents><Key>{key}</Key><LastModified>{last_modified}</LastModified><Size>{}</Size></Contents>",
+                            contents.len()
+                        )
+                    })
+                    .collect();
+                format!(
+                    "<?xml version=\"1.0\" encoding=\"UTF-8\"?><ListBucketResult><Name>bucket</Name><Prefix>prefix/</Prefix><IsTruncated>false</IsTruncated>{contents}</ListBucketResult>"
+                )
+            } else {
+                let key = target.strip_prefix("/bucket/").unwrap();
+                BUCKET_OBJECTS
+                    .iter()
+                    .find_map(|(object_key, _, contents)| (*object_key == key).then_some(*contents))
+                    .unwrap()
+                    .to_string()
+            };
+            let response = format!(
+                "HTTP/1.1 200 OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+                body.len()
+            );
+            stream.write_all(response.as_bytes()).unwrap();
+        }
+    });
+    Ok(format!("http://{address}"))
+}
+
+fn bucket_reader(endpoint: &str) -> eyre::Result<S3GenericReader> {
+    let region = Region::Custom {
+        region: "minio".to_string(),
+        endpoint: endpoint.to_string(),
+    };
+    let bucket = S3Bucket::new_public("bucket", region)?.with_path_style();
+    Ok(S3GenericReader::new(
+        bucket,
+        "prefix/",
+        "*",
+        ObjectOrdering::default(),
+        false,
+        None,
+        ReadMethod::ByLine,
+        None,
+        None,
+        0,
+        None,
+        None,
+        None,
+    )?)
+}
+
+/// Reads the lines until `limit` of them are read or the objects run out. Returns
+/// the lines and the offset of the last one.
+fn read_lines(
+    reader: &mut S3GenericReader,
+    limit: usize,
+) -> eyre::Result<(Vec<String>, Option<OffsetValue>)> {
+    let mut lines = Vec::new();
+    let mut last_offset = None;
+    while lines.len() < limit {
+        match reader.read()? {
+            ReadResult::Data(context, (_, offset)) => {
+                lines.push(String::from_utf8(raw_bytes(context))?);
+                last_offset = Some(offset);
+            }
+            ReadResult::NewSource(_) | ReadResult::FinishedSource { .. } => {}
+            ReadResult::Finished => break,
+        }
+    }
+    Ok((lines, last_offset))
+}
+
+fn resumed_lines(endpoint: &str, offset: OffsetValue) -> eyre::Result<Vec<String>> {
+    let mut frontier = OffsetAntichain::new();
+    frontier.advance_offset(OffsetKey::Empty, offset);
+    let mut reader = bucket_reader(endpoint)?;
+    reader.seek(&frontier)?;
+    Ok(read_lines(&mut reader, usize::MAX)?.0)
+}
+
+#[test]
+fn test_generic_reader_restart_in_the_middle_of_second_object() -> eyre::Result<()> {
+    let endpoint = start_bucket_server()?;
+
+    // One and a half of the objects are read before the restart
+    let mut reader = bucket_reader(&endpoint)?;
+    let (lines, offset) = read_lines(&mut reader, 3)?;
+    assert_eq!(lines, ["a\n", "b\n", "c\n"]);
+    let offset = offset.unwrap();
+    assert_eq!(
+        offset,
+        OffsetValue::S3ObjectPositionV2 {
+            total_entries_read: 3,
+            path: Arc::new("prefix/two.txt".to_string()),
+            bytes_offset: 2,
+            last_modified: S3Scanner::parse_last_modified("2024-05-01T10:00:01.000Z"),
+        }
+    );
+
+    assert_eq!(resumed_lines(&endpoint, offset)?, ["d\n", "e\n", "f\n"]);
+    Ok(())
+}
+
+#[test]
+fn test_generic_reader_restart_from_legacy_offset() -> eyre::Result<()> {
+    let endpoint = start_bucket_server()?;
+
+    // The offsets persisted before the modification times were tracked are still
+    // resumed from, with the current modification time of the object
+    let offset = OffsetValue::S3ObjectPosition {
+        total_entries_read: 3,
+        path: Arc::new("prefix/two.txt".to_string()),
+        bytes_offset: 2,
+    };
+    assert_eq!(resumed_lines(&endpoint, offset)?, ["d\n", "e\n", "f\n"]);
+    Ok(())
+}
+
+/// The layout of the offsets written before the modification times of the objects
+/// were tracked.
+#[allow(dead_code)]
+#[derive(Serialize)]
+enum LegacyOffsetValue {
+    KafkaOffset(i64),
+    FilePosition {
+        total_entries_read: u64,
+        path: Arc<PathBuf>,
+        bytes_offset: u64,
+    },
+    S3ObjectPosition {
+        total_entries_read: u64,
+        path: Arc<String>,
+        bytes_offset: u64,
+    },
+    PythonEntrySequentialId(u64),
+    Empty,
+}
+
+#[test]
+fn test_legacy_offsets_deserialized() -> eyre::Result<()> {
+    let legacy_offset = LegacyOffsetValue::S3ObjectPosition {
+        total_entries_read: 3,
+        path: Arc::new("prefix/two.txt".to_string()),
+        bytes_offset: 2,
+    };
+    let offset: OffsetValue = bincode::deserialize(&bincode::serialize(&legacy_offset)?)?;
+    assert_eq!(
+        offset,
+        OffsetValue::S3ObjectPosition {
+            total_entries_read: 3,
+            path: Arc::new("prefix/two.txt".to_string()),
+            bytes_offset: 2,
+        }
+    );
+
+    let offset: OffsetValue =
+        bincode::deserialize(&bincode::serialize(&LegacyOffsetValue::Empty)?)?;
+    assert_eq!(offset, OffsetValue::Empty);
+    Ok(())
+}
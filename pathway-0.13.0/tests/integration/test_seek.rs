@@ -1,9 +1,12 @@
 // Copyright © 2024 Pathway
 
-use super::helpers::{create_persistence_manager, full_cycle_read, FullReadResult};
+use super::helpers::{
+    capture_warnings, create_persistence_manager, full_cycle_read, raw_bytes, read_entries,
+    FullReadResult,
+};
 
 use std::collections::HashMap;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::sync::{Arc, Mutex};
 
 use tempfile::tempdir;
@@ -13,10 +16,13 @@ use pathway_engine::connectors::data_format::{
 };
 use pathway_engine::connectors::data_storage::ReaderBuilder;
 use pathway_engine::connectors::data_storage::{
-    ConnectorMode, CsvFilesystemReader, FilesystemReader, ReadMethod,
+    extract_seek_position, ConnectorMode, CsvFilesystemReader, FilesystemReader,
+    FilesystemReaderSettings, ReadMethod, ReadResult, Reader, ReaderContext, SeekOffsetKind,
+    SeekPosition,
 };
-use pathway_engine::connectors::SessionType;
+use pathway_engine::connectors::{OffsetKey, OffsetValue, SessionType};
 use pathway_engine::engine::Value;
+use pathway_engine::persistence::frontier::OffsetAntichain;
 use pathway_engine::persistence::tracker::WorkerPersistentStorage;
 
 enum TestedFormat {
@@ -27,8 +33,15 @@ enum TestedFormat {
 fn csv_reader_parser_pair(input_path: &str) -> (Box<dyn ReaderBuilder>, Box<dyn Parser>) {
     let mut builder = csv::ReaderBuilder::new();
     builder.has_headers(false);
-    let reader =
-        CsvFilesystemReader::new(input_path, builder, ConnectorMode::Static, Some(1), "*").unwrap();
+    let reader = CsvFilesystemReader::new(
+        &[input_path],
+        builder,
+        ConnectorMode::Static,
+        Some(1),
+        false,
+        FilesystemReaderSettings::default(),
+    )
+    .unwrap();
     let parser = DsvParser::new(
         DsvSettings::new(
             Some(vec!["key".to_string()]),
@@ -42,11 +55,11 @@ fn csv_reader_parser_pair(input_path: &str) -> (Box<dyn ReaderBuilder>, Box<dyn
 
 fn json_reader_parser_pair(input_path: &str) -> (Box<dyn ReaderBuilder>, Box<dyn Parser>) {
     let reader = FilesystemReader::new(
-        input_path,
+        &[input_path],
         ConnectorMode::Static,
         Some(1),
         ReadMethod::ByLine,
-        "*",
+        FilesystemReaderSettings::default(),
     )
     .unwrap();
     let parser = JsonLinesParser::new(
@@ -360,3 +373,132 @@ fn test_json_recovery_from_empty_folder() -> eyre::Result<()> {
 
     Ok(())
 }
+
+fn frontier_with(offset_value: OffsetValue) -> OffsetAntichain {
+    let mut frontier = OffsetAntichain::new();
+    frontier.advance_offset(OffsetKey::Empty, offset_value);
+    frontier
+}
+
+#[test]
+fn test_file_position_extracted() {
+    let offset_value = OffsetValue::FilePosition {
+        total_entries_read: 2,
+        path: Arc::new(PathBuf::from("input.txt")),
+        bytes_offset: 5,
+    };
+    let frontier = frontier_with(offset_value.clone());
+    assert_eq!(
+        extract_seek_position(&frontier, SeekOffsetKind::FilePosition),
+        Some(SeekPosition {
+            offset_value: &offset_value,
+            total_entries_read: 2,
+            path: Arc::new(PathBuf::from("input.txt")),
+            bytes_offset: 5,
+            last_modified: None,
+        })
+    );
+}
+
+#[test]
+fn test_s3_object_position_extracted() {
+    let offset_value = OffsetValue::S3ObjectPositionV2 {
+        total_entries_read: 3,
+        path: Arc::new("prefix/object.csv".to_string()),
+        bytes_offset: 7,
+        last_modified: Some(1_000),
+    };
+    let frontier = frontier_with(offset_value.clone());
+    assert_eq!(
+        extract_seek_position(&frontier, SeekOffsetKind::S3ObjectPosition),
+        Some(SeekPosition {
+            offset_value: &offset_value,
+            total_entries_read: 3,
+            path: Arc::new(PathBuf::from("prefix/object.csv")),
+            bytes_offset: 7,
+            last_modified: Some(1_000),
+        })
+    );
+}
+
+#[test]
+fn test_legacy_s3_object_position_extracted() {
+    let offset_value = OffsetValue::S3ObjectPosition {
+        total_entries_read: 3,
+        path: Arc::new("prefix/object.csv".to_string()),
+        bytes_offset: 7,
+    };
+    let frontier = frontier_with(offset_value.clone());
+    assert_eq!(
+        extract_seek_position(&frontier, SeekOffsetKind::S3ObjectPosition),
+        Some(SeekPosition {
+            offset_value: &offset_value,
+            total_entries_read: 3,
+            path: Arc::new(PathBuf::from("prefix/object.csv")),
+            bytes_offset: 7,
+            last_modified: None,
+        })
+    );
+}
+
+#[test]
+fn test_wrong_offset_kind_ignored() {
+    let frontier = frontier_with(OffsetValue::KafkaOffset(10));
+    assert_eq!(
+        extract_seek_position(&frontier, SeekOffsetKind::FilePosition),
+        None
+    );
+
+    let frontier = frontier_with(OffsetValue::FilePosition {
+        total_entries_read: 1,
+        path: Arc::new(PathBuf::from("input.txt")),
+        bytes_offset: 2,
+    });
+    assert_eq!(
+        extract_seek_position(&frontier, SeekOffsetKind::S3ObjectPosition),
+        None
+    );
+    assert_eq!(
+        extract_seek_position(&OffsetAntichain::new(), SeekOffsetKind::FilePosition),
+        None
+    );
+}
+
+#[test]
+fn test_seek_with_wrong_offset_kind_is_noop() -> eyre::Result<()> {
+    let test_storage = tempdir()?;
+    let path = test_storage.path().join("input.txt");
+    std::fs::write(&path, b"a\nbb\n")?;
+
+    let new_reader = || {
+        FilesystemReader::new(
+            &[path.to_str().unwrap()],
+            ConnectorMode::Static,
+            None,
+            ReadMethod::ByLine,
+            FilesystemReaderSettings::default(),
+        )
+    };
+    let expected_entries = read_entries(&mut new_reader()?)?;
+
+    let mut reader = new_reader()?;
+    let (seek_result, warnings) =
+        capture_warnings(|| reader.seek(&frontier_with(OffsetValue::KafkaOffset(1))));
+    seek_result?;
+    assert_eq!(warnings.len(), 1, "{warnings:?}");
+    assert!(
+        warnings[0].starts_with("Incorrect type of offset value in frontier"),
+        "{warnings:?}"
+    );
+
+    // The reader starts from the beginning, as if there was no seek
+    let entries = read_entries(&mut reader)?;
+    assert_eq!(entries, expected_entries);
+    let lines: Vec<_> = entries
+        .into_iter()
+        .map(|(context, _)| raw_bytes(context))
+        .collect();
+    assert_eq!(lines, vec![b"a\n".to_vec(), b"bb\n".to_vec()]);
+
+    Ok(())
+}
@@ -0,0 +1,78 @@
+// Copyright © 2024 Pathway
+
+use super::helpers::{raw_bytes, read_entries};
+
+use std::thread;
+use std::time::{Duration, Instant};
+
+use tempfile::tempdir;
+
+use pathway_engine::connectors::data_storage::{
+    ConnectorMode, FilesystemReader, FilesystemReaderSettings, ReadMethod, Reader, ShutdownSignal,
+};
+
+#[test]
+fn test_sleep_interrupted_by_shutdown() {
+    let signal = ShutdownSignal::default();
+    let requesting_signal = signal.clone();
+    let requesting_thread = thread::spawn(move || {
+        thread::sleep(Duration::from_millis(100));
+        requesting_signal.request();
+    });
+
+    let sleep_started_at = Instant::now();
+    signal.sleep(Duration::from_secs(60));
+    assert!(sleep_started_at.elapsed() < Duration::from_secs(10));
+    assert!(signal.is_requested());
+    requesting_thread.join().unwrap();
+}
+
+#[test]
+fn test_block_on_interrupted_by_shutdown() -> eyre::Result<()> {
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .enable_time()
+        .build()?;
+    let signal = ShutdownSignal::default();
+    assert_eq!(signal.block_on(&runtime, async { 42 }), Some(42));
+
+    let requesting_signal = signal.clone();
+    let requesting_thread = thread::spawn(move || {
+        thread::sleep(Duration::from_millis(100));
+        requesting_signal.request();
+    });
+
+    let wait_started_at = Instant::now();
+    let output = signal.block_on(&runtime, std::future::pending::<()>());
+    assert_eq!(output, None);
+    assert!(wait_started_at.elapsed() < Duration::from_secs(10));
+    requesting_thread.join().unwrap();
+
+    Ok(())
+}
+
+#[test]
+fn test_streaming_filesystem_reader_stops_on_shutdown() -> eyre::Result<()> {
+    let test_storage = tempdir()?;
+    std::fs::write(test_storage.path().join("input.txt"), b"a\nbb\n")?;
+
+    let mut reader = FilesystemReader::new(
+        &[test_storage.path().to_str().unwrap()],
+        ConnectorMode::Streaming,
+        None,
+        ReadMethod::ByLine,
+        FilesystemReaderSettings::default(),
+    )?;
+    let signal = ShutdownSignal::default();
+    reader.set_shutdown_signal(signal.clone());
+    signal.request();
+
+    // The data present is read, after which the reader finishes instead of
+    // waiting for new files
+    let lines: Vec<_> = read_entries(&mut reader)?
+        .into_iter()
+        .map(|(context, _)| raw_bytes(context))
+        .collect();
+    assert_eq!(lines, vec![b"a\n".to_vec(), b"bb\n".to_vec()]);
+
+    Ok(())
+}
@@ -1,22 +1,33 @@
 // Copyright © 2024 Pathway
 
 use std::collections::HashMap;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering as AtomicOrdering};
 use std::sync::Arc;
+use std::thread;
 
+use assert_matches::assert_matches;
+use pathway_engine::connectors::data_format::FormatterContext;
 use pathway_engine::connectors::data_format::InnerSchemaField;
 use pathway_engine::connectors::data_format::ParseError;
 use pathway_engine::connectors::data_format::TransparentParser;
 use pathway_engine::connectors::data_storage::DataEventType;
 use pathway_engine::connectors::data_storage::ReaderContext;
 use pathway_engine::connectors::SessionType;
-use pathway_engine::engine::Type;
+use pathway_engine::engine::{Key, Timestamp, Type};
 use rusqlite::Connection as SqliteConnection;
 use rusqlite::OpenFlags as SqliteOpenFlags;
+use tempfile::tempdir;
 
 use pathway_engine::connectors::data_format::{ParsedEvent, Parser};
-use pathway_engine::connectors::data_storage::{ReadResult, Reader, SqliteReader};
+use pathway_engine::connectors::data_storage::{
+    ReadError, ReadResult, Reader, SqliteReader, SqliteWriter, Writer,
+};
 use pathway_engine::connectors::offset::EMPTY_OFFSET;
+use pathway_engine::connectors::{Offset, OffsetKey, OffsetValue};
 use pathway_engine::engine::Value;
+use pathway_engine::persistence::frontier::OffsetAntichain;
+use pathway_engine::persistence::metadata_backends::{FilesystemKVStorage, MetadataBackend};
 
 use crate::helpers::assert_error_shown_for_reader_context;
 use crate::helpers::ErrorPlacement;
@@ -34,7 +45,15 @@ fn test_sqlite_read_table() -> eyre::Result<()> {
         "price".to_string(),
         "photo".to_string(),
     ];
-    let mut reader = SqliteReader::new(connection, "goods".to_string(), value_field_names);
+    let mut reader = SqliteReader::new(
+        connection,
+        "goods".to_string(),
+        value_field_names,
+        None,
+        None,
+        None,
+        None,
+    )?;
     let mut read_results = Vec::new();
     loop {
         let entry = reader.read()?;
@@ -114,7 +133,15 @@ fn test_sqlite_read_table_with_parser() -> eyre::Result<()> {
             InnerSchemaField::new(Type::Bytes, true, None),
         ),
     ]);
-    let mut reader = SqliteReader::new(connection, "goods".to_string(), value_field_names.clone());
+    let mut reader = SqliteReader::new(
+        connection,
+        "goods".to_string(),
+        value_field_names.clone(),
+        None,
+        None,
+        None,
+        None,
+    )?;
     let mut parser = TransparentParser::new(None, value_field_names, schema, SessionType::Native);
 
     let mut parsed_events: Vec<ParsedEvent> = Vec::new();
@@ -187,7 +214,15 @@ fn test_sqlite_read_table_nonparsable() -> eyre::Result<()> {
             InnerSchemaField::new(Type::Bytes, false, None),
         ),
     ]);
-    let mut reader = SqliteReader::new(connection, "goods".to_string(), value_field_names.clone());
+    let mut reader = SqliteReader::new(
+        connection,
+        "goods".to_string(),
+        value_field_names.clone(),
+        None,
+        None,
+        None,
+        None,
+    )?;
     let parser = TransparentParser::new(None, value_field_names, schema, SessionType::Native);
 
     reader.read()?;
@@ -210,3 +245,444 @@ fn test_sqlite_read_table_nonparsable() -> eyre::Result<()> {
     );
     Ok(())
 }
+
+fn read_goods_table(connection: &SqliteConnection) -> eyre::Result<Vec<(i64, String, f64)>> {
+    let mut statement = connection.prepare("SELECT id, name, price FROM goods ORDER BY id")?;
+    let rows = statement
+        .query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(rows)
+}
+
+fn goods_row(id: i64, name: &str, price: f64, diff: isize) -> FormatterContext {
+    goods_row_at(0, id, name, price, diff)
+}
+
+fn goods_row_at(time: u64, id: i64, name: &str, price: f64, diff: isize) -> FormatterContext {
+    FormatterContext::new(
+        Vec::new(),
+        Key::random(),
+        vec![
+            Value::Int(id),
+            Value::String(name.into()),
+            Value::Float(price.into()),
+        ],
+        Timestamp(time),
+        diff,
+    )
+}
+
+#[test]
+fn test_sqlite_write_upserts_by_key() -> eyre::Result<()> {
+    let test_storage = tempdir()?;
+    let db_path = test_storage.path().join("output.db");
+    let check_connection = SqliteConnection::open(&db_path)?;
+    check_connection.execute(
+        "CREATE TABLE goods (id INTEGER PRIMARY KEY, name TEXT, price REAL)",
+        [],
+    )?;
+
+    let column_names = vec!["id".to_string(), "name".to_string(), "price".to_string()];
+    let mut writer = SqliteWriter::new(
+        SqliteConnection::open(&db_path)?,
+        "goods",
+        column_names,
+        &["id".to_string()],
+    )?;
+
+    writer.write(goods_row(1, "Milk", 1.1, 1))?;
+    writer.write(goods_row(2, "Bread", 0.75, 1))?;
+    assert_eq!(read_goods_table(&check_connection)?, vec![]);
+    writer.flush(true)?;
+    assert_eq!(
+        read_goods_table(&check_connection)?,
+        vec![(1, "Milk".to_string(), 1.1), (2, "Bread".to_string(), 0.75)]
+    );
+
+    writer.write(goods_row(1, "Milk", 1.2, 1))?;
+    writer.write(goods_row(2, "Bread", 0.75, -1))?;
+    writer.flush(true)?;
+    assert_eq!(
+        read_goods_table(&check_connection)?,
+        vec![(1, "Milk".to_string(), 1.2)]
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_sqlite_write_update_within_batch() -> eyre::Result<()> {
+    let test_storage = tempdir()?;
+    let db_path = test_storage.path().join("output.db");
+    let check_connection = SqliteConnection::open(&db_path)?;
+    check_connection.execute(
+        "CREATE TABLE goods (id INTEGER PRIMARY KEY, name TEXT, price REAL)",
+        [],
+    )?;
+
+    let column_names = vec!["id".to_string(), "name".to_string(), "price".to_string()];
+    let mut writer = SqliteWriter::new(
+        SqliteConnection::open(&db_path)?,
+        "goods",
+        column_names,
+        &["id".to_string()],
+    )?;
+    writer.write(goods_row_at(2, 1, "Milk", 1.1, 1))?;
+    writer.write(goods_row_at(2, 2, "Bread", 0.75, 1))?;
+    // The deletion of the old row comes after the upsert of the new one
+    writer.write(goods_row_at(4, 1, "Milk", 1.2, 1))?;
+    writer.write(goods_row_at(4, 1, "Milk", 1.1, -1))?;
+    writer.write(goods_row_at(4, 2, "Bread", 0.8, 1))?;
+    writer.write(goods_row_at(4, 2, "Bread", 0.75, -1))?;
+    writer.flush(true)?;
+    assert_eq!(
+        read_goods_table(&check_connection)?,
+        vec![(1, "Milk".to_string(), 1.2), (2, "Bread".to_string(), 0.8)]
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_sqlite_write_quoted_identifiers() -> eyre::Result<()> {
+    let test_storage = tempdir()?;
+    let db_path = test_storage.path().join("output.db");
+    let check_connection = SqliteConnection::open(&db_path)?;
+    check_connection.execute(
+        "CREATE TABLE \"order items\" (\"group\" INTEGER PRIMARY KEY, \"a \"\"b\"\"\" TEXT)",
+        [],
+    )?;
+
+    let mut writer = SqliteWriter::new(
+        SqliteConnection::open(&db_path)?,
+        "order items",
+        vec!["group".to_string(), "a \"b\"".to_string()],
+        &["group".to_string()],
+    )?;
+    for (value, diff) in [("x", 1), ("x", -1), ("y", 1)] {
+        writer.write(FormatterContext::new(
+            Vec::new(),
+            Key::random(),
+            vec![Value::Int(1), Value::String(value.into())],
+            Timestamp(0),
+            diff,
+        ))?;
+        writer.flush(true)?;
+    }
+    let rows: Vec<(i64, String)> = check_connection
+        .prepare("SELECT * FROM \"order items\"")?
+        .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+        .collect::<Result<_, _>>()?;
+    assert_eq!(rows, vec![(1, "y".to_string())]);
+
+    Ok(())
+}
+
+#[test]
+fn test_sqlite_write_unsupported_value() -> eyre::Result<()> {
+    let test_storage = tempdir()?;
+    let db_path = test_storage.path().join("output.db");
+    let connection = SqliteConnection::open(&db_path)?;
+    connection.execute("CREATE TABLE t (value)", [])?;
+
+    let mut writer = SqliteWriter::new(connection, "t", vec!["value".to_string()], &[])?;
+    writer.write(FormatterContext::new(
+        Vec::new(),
+        Key::random(),
+        vec![Value::Error],
+        Timestamp(0),
+        1,
+    ))?;
+    assert!(writer.flush(true).is_err());
+
+    Ok(())
+}
+
+fn persisted_items_reader(db_path: &Path, state_path: &Path) -> eyre::Result<SqliteReader> {
+    let mut reader = SqliteReader::new(
+        SqliteConnection::open(db_path)?,
+        "items".to_string(),
+        vec!["name".to_string()],
+        None,
+        None,
+        None,
+        Some(1),
+    )?;
+    reader.set_state_storage(Box::new(FilesystemKVStorage::new(state_path)?));
+    Ok(reader)
+}
+
+fn read_batch(reader: &mut SqliteReader) -> eyre::Result<(Vec<(DataEventType, Value)>, Offset)> {
+    let mut changes = Vec::new();
+    let mut last_offset = EMPTY_OFFSET;
+    loop {
+        match reader.read()? {
+            ReadResult::Data(ReaderContext::Diff((event, _, values)), offset) => {
+                changes.push((event, values.get("name").unwrap().clone()));
+                last_offset = offset;
+            }
+            ReadResult::FinishedSource { .. } => break,
+            ReadResult::NewSource(_) => {}
+            other => panic!("unexpected read result: {other:?}"),
+        }
+    }
+    Ok((changes, last_offset))
+}
+
+#[test]
+fn test_sqlite_seek_restores_state() -> eyre::Result<()> {
+    let test_storage = tempdir()?;
+    let db_path = test_storage.path().join("input.db");
+    let writer_connection = SqliteConnection::open(&db_path)?;
+    writer_connection.execute("CREATE TABLE items (name TEXT)", [])?;
+    writer_connection.execute("INSERT INTO items VALUES ('a'), ('b')", [])?;
+    let state_path = test_storage.path().join("state");
+
+    let mut reader = persisted_items_reader(&db_path, &state_path)?;
+    let (changes, (offset_key, offset_value)) = read_batch(&mut reader)?;
+    assert_eq!(
+        changes,
+        vec![
+            (DataEventType::Insert, Value::String("a".into())),
+            (DataEventType::Insert, Value::String("b".into())),
+        ]
+    );
+    assert_eq!(offset_key, OffsetKey::Empty);
+    let mut frontier = OffsetAntichain::new();
+    frontier.advance_offset(offset_key, offset_value);
+
+    writer_connection.execute("DELETE FROM items WHERE name = 'a'", [])?;
+    writer_connection.execute("INSERT INTO items VALUES ('c')", [])?;
+
+    let mut reader = persisted_items_reader(&db_path, &state_path)?;
+    reader.seek(&frontier)?;
+    let (changes, _) = read_batch(&mut reader)?;
+    assert_eq!(
+        changes,
+        vec![
+            (DataEventType::Insert, Value::String("c".into())),
+            (DataEventType::Delete, Value::String("a".into())),
+        ]
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_sqlite_committed_state_kept() -> eyre::Result<()> {
+    let test_storage = tempdir()?;
+    let db_path = test_storage.path().join("input.db");
+    let writer_connection = SqliteConnection::open(&db_path)?;
+    writer_connection.execute("CREATE TABLE items (name TEXT)", [])?;
+    writer_connection.execute("INSERT INTO items VALUES ('a')", [])?;
+    let state_path = test_storage.path().join("state");
+    let stored_versions = || -> eyre::Result<Vec<String>> {
+        let mut keys = FilesystemKVStorage::new(&state_path)?.list_keys()?;
+        keys.sort();
+        Ok(keys)
+    };
+
+    let mut reader = persisted_items_reader(&db_path, &state_path)?;
+    let (_, (_, first_offset_value)) = read_batch(&mut reader)?;
+    writer_connection.execute("INSERT INTO items VALUES ('b')", [])?;
+    let (_, (offset_key, offset_value)) = read_batch(&mut reader)?;
+    // Only the number of the batch is kept in the offset
+    assert_eq!(
+        offset_value,
+        OffsetValue::SqliteSnapshot { snapshots_taken: 2 }
+    );
+    assert_eq!(stored_versions()?, vec!["1".to_string(), "2".to_string()]);
+
+    let mut first_frontier = OffsetAntichain::new();
+    first_frontier.advance_offset(OffsetKey::Empty, first_offset_value);
+    reader.on_commit(&first_frontier)?;
+    assert_eq!(stored_versions()?, vec!["1".to_string(), "2".to_string()]);
+
+    let mut frontier = OffsetAntichain::new();
+    frontier.advance_offset(offset_key, offset_value);
+    reader.on_commit(&frontier)?;
+    assert_eq!(stored_versions()?, vec!["2".to_string()]);
+
+    // A frontier without the saved state can't be restored
+    let mut reader = persisted_items_reader(&db_path, &test_storage.path().join("other_state"))?;
+    assert_matches!(reader.seek(&frontier), Err(ReadError::StateNotFound(2)));
+
+    Ok(())
+}
+
+#[test]
+fn test_sqlite_projection_and_filter() -> eyre::Result<()> {
+    let test_storage = tempdir()?;
+    let db_path = test_storage.path().join("input.db");
+    let writer_connection = SqliteConnection::open(&db_path)?;
+    writer_connection.execute("CREATE TABLE items (name TEXT, price REAL, photo BLOB)", [])?;
+    writer_connection.execute(
+        "INSERT INTO items VALUES ('a', 1.0, x'00'), ('b', 20.0, x'01')",
+        [],
+    )?;
+
+    let mut reader = SqliteReader::new(
+        SqliteConnection::open(&db_path)?,
+        "items".to_string(),
+        vec!["name".to_string(), "price".to_string(), "photo".to_string()],
+        Some(vec!["name".to_string()]),
+        Some("price < 10".to_string()),
+        None,
+        None,
+    )?;
+    let mut read_results = Vec::new();
+    loop {
+        match reader.read()? {
+            ReadResult::Data(ReaderContext::Diff((event, _, values)), _) => {
+                // Only the projected column is selected
+                assert_eq!(values.get("price"), None);
+                read_results.push((event, values.get("name").unwrap().clone()));
+            }
+            ReadResult::FinishedSource { .. } => break,
+            ReadResult::NewSource(_) => {}
+            other => panic!("unexpected read result: {other:?}"),
+        }
+    }
+    assert_eq!(
+        read_results,
+        vec![(DataEventType::Insert, Value::String("a".into()))]
+    );
+
+    // The row that doesn't satisfy the filter anymore is removed
+    writer_connection.execute("UPDATE items SET price = 30.0 WHERE name = 'a'", [])?;
+    let (changes, _) = read_batch(&mut reader)?;
+    assert_eq!(
+        changes,
+        vec![(DataEventType::Delete, Value::String("a".into()))]
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_sqlite_unknown_projected_column() -> eyre::Result<()> {
+    let test_storage = tempdir()?;
+    let db_path = test_storage.path().join("input.db");
+    let result = SqliteReader::new(
+        SqliteConnection::open(&db_path)?,
+        "items".to_string(),
+        vec!["name".to_string()],
+        Some(vec!["price".to_string()]),
+        None,
+        None,
+        None,
+    );
+    assert!(matches!(
+        result,
+        Err(ReadError::UnknownProjectedColumn(name)) if name == "price"
+    ));
+
+    Ok(())
+}
+
+#[test]
+fn test_sqlite_incremental_reads_by_tracking_column() -> eyre::Result<()> {
+    let test_storage = tempdir()?;
+    let db_path = test_storage.path().join("input.db");
+    let writer_connection = SqliteConnection::open(&db_path)?;
+    writer_connection.execute("CREATE TABLE items (name TEXT, version INTEGER)", [])?;
+    writer_connection.execute("INSERT INTO items VALUES ('a', 1), ('b', 2), ('c', 3)", [])?;
+
+    let mut reader = SqliteReader::new(
+        SqliteConnection::open(&db_path)?,
+        "items".to_string(),
+        vec!["name".to_string()],
+        None,
+        None,
+        Some("version".to_string()),
+        None,
+    )?;
+    let (changes, _) = read_batch(&mut reader)?;
+    assert_eq!(
+        changes,
+        vec![
+            (DataEventType::Insert, Value::String("a".into())),
+            (DataEventType::Insert, Value::String("b".into())),
+            (DataEventType::Insert, Value::String("c".into())),
+        ]
+    );
+
+    // An update that doesn't advance the tracking column isn't noticed,
+    // since only the rows with the newer versions are fetched
+    writer_connection.execute("UPDATE items SET name = 'aa' WHERE version = 1", [])?;
+    writer_connection.execute(
+        "UPDATE items SET name = 'bb', version = 4 WHERE version = 2",
+        [],
+    )?;
+    writer_connection.execute("DELETE FROM items WHERE version = 3", [])?;
+    let (changes, _) = read_batch(&mut reader)?;
+    assert_eq!(
+        changes,
+        vec![
+            (DataEventType::Delete, Value::String("b".into())),
+            (DataEventType::Insert, Value::String("bb".into())),
+            (DataEventType::Delete, Value::String("c".into())),
+        ]
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_sqlite_scan_sees_consistent_snapshot() -> eyre::Result<()> {
+    let test_storage = tempdir()?;
+    let db_path = test_storage.path().join("input.db");
+    let mut writer_connection = SqliteConnection::open(&db_path)?;
+    writer_connection.pragma_update(None, "journal_mode", "WAL")?;
+    writer_connection.execute(
+        "CREATE TABLE holders (account INTEGER PRIMARY KEY, name TEXT, version INTEGER)",
+        [],
+    )?;
+    writer_connection.execute("INSERT INTO holders VALUES (0, 'holder', 0)", [])?;
+
+    // Each transaction replaces the only row with a new one, so any consistent
+    // view of the table contains exactly one row. The incremental scan makes two
+    // queries, and a commit between them must not be visible to the second one.
+    let stop = Arc::new(AtomicBool::new(false));
+    let writer_thread = thread::spawn({
+        let stop = stop.clone();
+        move || -> rusqlite::Result<()> {
+            let mut version = 0;
+            while !stop.load(AtomicOrdering::Relaxed) {
+                version += 1;
+                let transaction = writer_connection.transaction()?;
+                transaction.execute("DELETE FROM holders", [])?;
+                transaction.execute("INSERT INTO holders VALUES (?1, 'holder', ?1)", [version])?;
+                transaction.commit()?;
+            }
+            Ok(())
+        }
+    });
+
+    let mut reader = SqliteReader::new(
+        SqliteConnection::open(&db_path)?,
+        "holders".to_string(),
+        vec!["name".to_string()],
+        None,
+        None,
+        Some("version".to_string()),
+        None,
+    )?;
+    let mut rows = 0;
+    for _ in 0..50 {
+        let (changes, _) = read_batch(&mut reader)?;
+        for (event, _) in changes {
+            match event {
+                DataEventType::Insert => rows += 1,
+                DataEventType::Delete => rows -= 1,
+                DataEventType::Upsert => panic!("unexpected upsert"),
+            }
+        }
+        assert_eq!(rows, 1);
+    }
+
+    stop.store(true, AtomicOrdering::Relaxed);
+    writer_thread.join().unwrap()?;
+
+    Ok(())
+}
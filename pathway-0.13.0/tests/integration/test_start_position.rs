@@ -0,0 +1,134 @@
+// Copyright © 2024 Pathway
+
+use super::helpers::{raw_bytes, read_entries, tokenized_entry};
+
+use std::path::Path;
+use std::sync::Arc;
+
+use tempfile::tempdir;
+
+use pathway_engine::connectors::data_storage::{
+    ConnectorMode, CsvFilesystemReader, FileStartPosition, FilesystemReader,
+    FilesystemReaderSettings, ReadMethod, Reader,
+};
+use pathway_engine::connectors::{OffsetKey, OffsetValue};
+use pathway_engine::persistence::frontier::OffsetAntichain;
+
+fn lines_reader(path: &Path) -> eyre::Result<FilesystemReader> {
+    Ok(FilesystemReader::new(
+        &[path.to_str().unwrap()],
+        ConnectorMode::Static,
+        None,
+        ReadMethod::ByLine,
+        FilesystemReaderSettings {
+            start_position: FileStartPosition::End,
+            ..Default::default()
+        },
+    )?)
+}
+
+fn raw_lines(reader: &mut dyn Reader) -> eyre::Result<Vec<Vec<u8>>> {
+    Ok(read_entries(reader)?
+        .into_iter()
+        .map(|(context, _)| raw_bytes(context))
+        .collect())
+}
+
+#[test]
+fn test_files_present_at_start_skipped() -> eyre::Result<()> {
+    let test_storage = tempdir()?;
+    std::fs::write(test_storage.path().join("old.txt"), b"a\nb\n")?;
+
+    let mut reader = lines_reader(test_storage.path())?;
+    // The file appears after the start, so all of its contents are new
+    std::fs::write(test_storage.path().join("new.txt"), b"c\nd\n")?;
+
+    assert_eq!(
+        raw_lines(&mut reader)?,
+        vec![b"c\n".to_vec(), b"d\n".to_vec()]
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_incomplete_last_line_read_from_file_present_at_start() -> eyre::Result<()> {
+    let test_storage = tempdir()?;
+    let path = test_storage.path().join("input.txt");
+    std::fs::write(&path, b"a\nb\nunfinished")?;
+
+    // The reading starts after the last line terminator, so the line that was still
+    // being written at the start isn't cut
+    let mut reader = lines_reader(&path)?;
+    let entries = read_entries(&mut reader)?;
+    assert_eq!(entries.len(), 1);
+    let (context, (_, offset_value)) = entries.into_iter().next().unwrap();
+    assert_eq!(raw_bytes(context), b"unfinished".to_vec());
+    assert!(matches!(
+        offset_value,
+        OffsetValue::FilePosition {
+            total_entries_read: 1,
+            bytes_offset: 14,
+            ..
+        }
+    ));
+
+    Ok(())
+}
+
+#[test]
+fn test_persisted_offset_wins_over_start_position() -> eyre::Result<()> {
+    let test_storage = tempdir()?;
+    let path = test_storage.path().join("input.txt");
+    std::fs::write(&path, b"a\nb\nc\n")?;
+
+    let mut frontier = OffsetAntichain::new();
+    frontier.advance_offset(
+        OffsetKey::Empty,
+        OffsetValue::FilePosition {
+            total_entries_read: 1,
+            path: Arc::new(path.clone()),
+            bytes_offset: 2,
+        },
+    );
+
+    let mut reader = lines_reader(&path)?;
+    reader.seek(&frontier)?;
+    assert_eq!(
+        raw_lines(&mut reader)?,
+        vec![b"b\n".to_vec(), b"c\n".to_vec()]
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_csv_header_read_from_file_present_at_start() -> eyre::Result<()> {
+    let test_storage = tempdir()?;
+    std::fs::write(
+        test_storage.path().join("input.csv"),
+        b"key,value\n1,a\n2,b\n",
+    )?;
+
+    let mut builder = csv::ReaderBuilder::new();
+    builder.has_headers(false);
+    let mut reader = CsvFilesystemReader::new(
+        &[test_storage.path().to_str().unwrap()],
+        builder,
+        ConnectorMode::Static,
+        None,
+        false,
+        FilesystemReaderSettings {
+            start_position: FileStartPosition::End,
+            ..Default::default()
+        },
+    )?;
+
+    let entries = read_entries(&mut reader)?;
+    assert_eq!(entries.len(), 1);
+    let (context, _) = entries.into_iter().next().unwrap();
+    let (_, header) = tokenized_entry(context);
+    assert_eq!(header, vec!["key".to_string(), "value".to_string()]);
+
+    Ok(())
+}
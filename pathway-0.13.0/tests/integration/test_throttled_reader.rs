@@ -0,0 +1,150 @@
+// Copyright © 2024 Pathway
+
+use super::helpers::read_entries;
+
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use pathway_engine::connectors::data_storage::{
+    DataEventType, ReadError, ReadResult, Reader, ReaderContext, ShutdownSignal, StorageType,
+    ThrottledReader,
+};
+use pathway_engine::connectors::{OffsetKey, OffsetValue};
+use pathway_engine::persistence::frontier::OffsetAntichain;
+use pathway_engine::persistence::PersistentId;
+
+/// Emits `entries_left` entries of `entry_len` bytes, each preceded by a new source.
+/// The frontiers it was asked to seek to are saved in `seeks`.
+struct CountingReader {
+    entries_left: usize,
+    entry_len: usize,
+    is_new_source_pending: bool,
+    persistent_id: Option<PersistentId>,
+    seeks: Arc<Mutex<Vec<OffsetAntichain>>>,
+}
+
+impl CountingReader {
+    fn new(entries_left: usize, entry_len: usize) -> Self {
+        Self {
+            entries_left,
+            entry_len,
+            is_new_source_pending: true,
+            persistent_id: None,
+            seeks: Arc::default(),
+        }
+    }
+}
+
+impl Reader for CountingReader {
+    fn read(&mut self) -> Result<ReadResult, ReadError> {
+        if self.entries_left == 0 {
+            return Ok(ReadResult::Finished);
+        }
+        if self.is_new_source_pending {
+            self.is_new_source_pending = false;
+            return Ok(ReadResult::NewSource(None));
+        }
+        self.is_new_source_pending = true;
+        self.entries_left -= 1;
+        Ok(ReadResult::Data(
+            ReaderContext::from_raw_bytes(DataEventType::Insert, vec![b'a'; self.entry_len]),
+            (
+                OffsetKey::Empty,
+                OffsetValue::PythonEntrySequentialId(self.entries_left.try_into().unwrap()),
+            ),
+        ))
+    }
+
+    fn seek(&mut self, frontier: &OffsetAntichain) -> Result<(), ReadError> {
+        self.seeks.lock().unwrap().push(frontier.clone());
+        Ok(())
+    }
+
+    fn update_persistent_id(&mut self, persistent_id: Option<PersistentId>) {
+        self.persistent_id = persistent_id;
+    }
+
+    fn persistent_id(&self) -> Option<PersistentId> {
+        self.persistent_id
+    }
+
+    fn storage_type(&self) -> StorageType {
+        StorageType::Python
+    }
+}
+
+/// Reads until the end, returning the number of the data entries.
+fn read_to_end(reader: &mut ThrottledReader) -> eyre::Result<usize> {
+    Ok(read_entries(reader)?.len())
+}
+
+#[test]
+fn test_records_per_second_limit() -> eyre::Result<()> {
+    let mut reader = ThrottledReader::new(Box::new(CountingReader::new(30, 1)), 20.0, None);
+
+    // The first 21 entries fit into the initial burst and the debt of the last one,
+    // while each of the other 9 waits for 50ms. The new sources aren't limited.
+    let started_at = Instant::now();
+    assert_eq!(read_to_end(&mut reader)?, 30);
+    assert!(started_at.elapsed() >= Duration::from_millis(400));
+
+    Ok(())
+}
+
+#[test]
+fn test_bytes_per_second_limit() -> eyre::Result<()> {
+    let mut reader =
+        ThrottledReader::new(Box::new(CountingReader::new(4, 50)), 1000.0, Some(100.0));
+
+    // The third entry puts the reader into a debt of 50 bytes, which takes half
+    // a second to pay off
+    let started_at = Instant::now();
+    assert_eq!(read_to_end(&mut reader)?, 4);
+    assert!(started_at.elapsed() >= Duration::from_millis(450));
+
+    Ok(())
+}
+
+#[test]
+fn test_shutdown_interrupts_throttling() -> eyre::Result<()> {
+    let mut reader = ThrottledReader::new(Box::new(CountingReader::new(3, 1)), 0.1, None);
+    let shutdown_signal = ShutdownSignal::default();
+    reader.set_shutdown_signal(shutdown_signal.clone());
+    shutdown_signal.request();
+
+    // Without the shutdown, the entries after the first one would be read about
+    // 10 seconds apart
+    let started_at = Instant::now();
+    assert_eq!(read_to_end(&mut reader)?, 3);
+    assert!(started_at.elapsed() < Duration::from_secs(5));
+
+    Ok(())
+}
+
+#[test]
+fn test_persistence_delegated_to_inner_reader() -> eyre::Result<()> {
+    let inner = CountingReader::new(1, 1);
+    let seeks = inner.seeks.clone();
+    let mut reader = ThrottledReader::new(Box::new(inner), 1.0, None);
+
+    reader.update_persistent_id(Some(42));
+    assert_eq!(reader.persistent_id(), Some(42));
+    assert!(matches!(reader.storage_type(), StorageType::Python));
+
+    let mut frontier = OffsetAntichain::new();
+    frontier.advance_offset(OffsetKey::Empty, OffsetValue::PythonEntrySequentialId(7));
+    reader.seek(&frontier)?;
+    assert_eq!(*seeks.lock().unwrap(), vec![frontier]);
+
+    // The offsets of the inner reader are returned as they are
+    assert_eq!(reader.read()?, ReadResult::NewSource(None));
+    assert_eq!(
+        reader.read()?,
+        ReadResult::Data(
+            ReaderContext::from_raw_bytes(DataEventType::Insert, b"a".to_vec()),
+            (OffsetKey::Empty, OffsetValue::PythonEntrySequentialId(0))
+        )
+    );
+
+    Ok(())
+}
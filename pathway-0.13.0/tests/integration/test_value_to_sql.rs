@@ -2,8 +2,9 @@
 
 use bytes::BytesMut;
 use postgres::types::{IsNull, ToSql, Type};
+use uuid::Uuid;
 
-use pathway_engine::engine::Value;
+use pathway_engine::engine::{Key, Value};
 
 fn assert_success<T: ToSql>(value: Value, postgres_type: &Type, expected: T) {
     let mut value_bytes = BytesMut::new();
@@ -131,3 +132,20 @@ fn test_float() {
 
     assert_failure(Value::Float(42.5.into()), &Type::TEXT);
 }
+
+#[test]
+fn test_pointer() {
+    let key = Key(0x0011_2233_4455_6677_8899_aabb_ccdd_eeff);
+    assert_success(Value::Pointer(key), &Type::TEXT, key.to_string());
+
+    // The bytes are in the same order as in the textual form
+    let expected_bytes: Vec<u8> = (0..16).rev().map(|i| i * 0x11).collect();
+    assert_success(
+        Value::Pointer(key),
+        &Type::UUID,
+        Uuid::parse_str("ffeeddcc-bbaa-9988-7766-554433221100").unwrap(),
+    );
+    assert_success(Value::Pointer(key), &Type::BYTEA, expected_bytes);
+
+    assert_failure(Value::Pointer(key), &Type::INT8);
+}
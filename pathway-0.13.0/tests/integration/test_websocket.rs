@@ -0,0 +1,44 @@
+// Copyright © 2024 Pathway
+
+use std::net::TcpListener;
+use std::thread;
+
+use tokio_tungstenite::tungstenite::{accept, Message};
+
+use pathway_engine::connectors::data_storage::{
+    ReadResult, Reader, ReaderContext, WebSocketReader,
+};
+
+#[test]
+fn test_websocket_frames_read() -> eyre::Result<()> {
+    let listener = TcpListener::bind("127.0.0.1:0")?;
+    let url = format!("ws://{}", listener.local_addr()?);
+    let server = thread::spawn(move || {
+        let (stream, _) = listener.accept().unwrap();
+        let mut socket = accept(stream).unwrap();
+        let subscription = socket.read().unwrap();
+        socket.send(Message::Text("first".to_string())).unwrap();
+        socket.send(Message::Binary(b"second".to_vec())).unwrap();
+        // Keep the connection open until the reader is done
+        let _ = socket.read();
+        subscription
+    });
+
+    let mut reader = WebSocketReader::new(url, Some("subscribe".to_string()), None, None)?;
+    let mut payloads = Vec::new();
+    while payloads.len() < 2 {
+        match reader.read()? {
+            ReadResult::Data(ReaderContext::RawBytes(_, payload), _) => payloads.push(payload),
+            other => panic!("unexpected read result: {other:?}"),
+        }
+    }
+    assert_eq!(payloads, vec![b"first".to_vec(), b"second".to_vec()]);
+    drop(reader);
+
+    assert_eq!(
+        server.join().unwrap(),
+        Message::Text("subscribe".to_string())
+    );
+
+    Ok(())
+}